@@ -0,0 +1,208 @@
+//! User-defined actions loaded from configuration
+//!
+//! Custom actions let users add phrase buttons ("Thank you", an address, ...)
+//! without recompiling. They are declared in JSON:
+//!
+//! ```json
+//! {
+//!   "variables": { "name": "Sam" },
+//!   "actions": [
+//!     { "name": "thanks", "label": "Thank you", "text": "Thank you! " },
+//!     { "name": "intro", "label": "Intro", "text": "My name is {name}. ", "callback_id": 7 }
+//!   ]
+//! }
+//! ```
+//!
+//! or the equivalent XML:
+//!
+//! ```xml
+//! <actions>
+//!   <variable name="name" value="Sam"/>
+//!   <action name="thanks" label="Thank you" text="Thank you! "/>
+//!   <action name="intro" label="Intro" text="My name is {name}. " callback="7"/>
+//! </actions>
+//! ```
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+
+use super::Action;
+use crate::{DasherError, Result};
+
+/// Shared slot holding the host callback invoked by custom actions
+pub type ActionCallbackSlot = Rc<RefCell<Option<Box<dyn Fn(u32)>>>>;
+
+/// Declaration of a single custom action
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomActionConfig {
+    /// Unique name of the action
+    pub name: String,
+    /// Display label
+    pub label: String,
+    /// Text (or template) appended to the output when the action runs
+    #[serde(default)]
+    pub text: Option<String>,
+    /// Identifier passed to the host action callback when the action runs
+    #[serde(default)]
+    pub callback_id: Option<u32>,
+}
+
+/// A set of custom action declarations
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CustomActionsConfig {
+    /// Values substituted for `{variable}` placeholders in action text
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+    /// The actions
+    #[serde(default)]
+    pub actions: Vec<CustomActionConfig>,
+}
+
+impl CustomActionsConfig {
+    /// Parse a JSON configuration
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| DasherError::SettingsError(format!("Invalid actions config: {}", e)))
+    }
+
+    /// Parse an XML configuration
+    pub fn from_xml(xml: &str) -> Result<Self> {
+        let mut reader = Reader::from_str(xml);
+        let mut config = Self::default();
+
+        loop {
+            match reader.read_event() {
+                Ok(Event::Start(e)) | Ok(Event::Empty(e)) => {
+                    let mut attributes = HashMap::new();
+                    for attr in e.attributes().flatten() {
+                        let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+                        let value = attr.unescape_value()
+                            .map_err(|e| DasherError::SettingsError(format!("Invalid actions config: {}", e)))?;
+                        attributes.insert(key, value.to_string());
+                    }
+
+                    match e.name().as_ref() {
+                        b"variable" => {
+                            if let (Some(name), Some(value)) = (attributes.remove("name"), attributes.remove("value")) {
+                                config.variables.insert(name, value);
+                            }
+                        }
+                        b"action" => {
+                            let name = attributes.remove("name")
+                                .ok_or_else(|| DasherError::SettingsError("Action is missing a name".to_string()))?;
+                            let callback_id = match attributes.remove("callback") {
+                                Some(id) => Some(id.parse().map_err(|_| {
+                                    DasherError::SettingsError(format!("Invalid callback id '{}' for action '{}'", id, name))
+                                })?),
+                                None => None,
+                            };
+                            config.actions.push(CustomActionConfig {
+                                label: attributes.remove("label").unwrap_or_else(|| name.clone()),
+                                text: attributes.remove("text"),
+                                callback_id,
+                                name,
+                            });
+                        }
+                        _ => {}
+                    }
+                }
+                Ok(Event::Eof) => break,
+                Err(e) => return Err(DasherError::SettingsError(format!("Invalid actions config: {}", e))),
+                _ => {}
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Load a configuration file, choosing the format from its extension
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .map_err(|e| DasherError::SettingsError(format!("Failed to read {}: {}", path.display(), e)))?;
+
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("xml") => Self::from_xml(&contents),
+            _ => Self::from_json(&contents),
+        }
+    }
+}
+
+/// Expand `{variable}` placeholders in a template
+///
+/// Unknown placeholders are left as they are.
+pub fn expand_template(template: &str, variables: &HashMap<String, String>) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('}') {
+            Some(end) => {
+                let key = &after[..end];
+                match variables.get(key) {
+                    Some(value) => result.push_str(value),
+                    None => {
+                        result.push('{');
+                        result.push_str(key);
+                        result.push('}');
+                    }
+                }
+                rest = &after[end + 1..];
+            }
+            None => {
+                result.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// An action declared in configuration
+pub struct CustomAction {
+    name: String,
+    label: String,
+    text: Option<String>,
+    callback_id: Option<u32>,
+    callback: ActionCallbackSlot,
+}
+
+impl CustomAction {
+    /// Create an action from its declaration, expanding its text template
+    pub fn new(config: &CustomActionConfig, variables: &HashMap<String, String>, callback: ActionCallbackSlot) -> Self {
+        Self {
+            name: config.name.clone(),
+            label: config.label.clone(),
+            text: config.text.as_deref().map(|text| expand_template(text, variables)),
+            callback_id: config.callback_id,
+            callback,
+        }
+    }
+}
+
+impl Action for CustomAction {
+    fn name(&self) -> &str { &self.name }
+    fn label(&self) -> &str { &self.label }
+    fn execute(&self, model: &mut crate::model::DasherModel) {
+        if let Some(text) = &self.text {
+            for c in text.chars() {
+                model.append_to_output(c);
+            }
+        }
+        if let Some(id) = self.callback_id {
+            if let Some(callback) = self.callback.borrow().as_ref() {
+                callback(id);
+            }
+        }
+    }
+}
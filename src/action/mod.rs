@@ -1,6 +1,13 @@
 //! Dasher Actions System - Rust scaffold
 
-use std::collections::HashMap;
+mod custom;
+
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::model::CursorMovement;
+
+pub use custom::{expand_template, ActionCallbackSlot, CustomAction, CustomActionConfig, CustomActionsConfig};
 
 /// Trait for actions that can be triggered in Dasher (e.g., backspace, space, accept)
 pub trait Action {
@@ -14,7 +21,10 @@ pub trait Action {
 
 /// Manages available actions and their registration
 pub struct ActionManager {
-    actions: HashMap<String, Box<dyn Action>>,
+    /// Actions in the order they were registered
+    actions: Vec<Box<dyn Action>>,
+    /// Host callback shared with custom actions
+    callback: ActionCallbackSlot,
 }
 
 impl Default for ActionManager {
@@ -25,16 +35,31 @@ impl Default for ActionManager {
 
 impl ActionManager {
     pub fn new() -> Self {
-        Self { actions: HashMap::new() }
+        Self { actions: Vec::new(), callback: ActionCallbackSlot::default() }
     }
+    /// Register an action, replacing any action of the same name in its place
     pub fn register_action(&mut self, action: Box<dyn Action>) {
-        self.actions.insert(action.name().to_string(), action);
+        match self.actions.iter_mut().find(|existing| existing.name() == action.name()) {
+            Some(existing) => *existing = action,
+            None => self.actions.push(action),
+        }
     }
     pub fn get_action(&self, name: &str) -> Option<&Box<dyn Action>> {
-        self.actions.get(name)
+        self.actions.iter().find(|action| action.name() == name)
     }
+    /// Get the actions, in the order they were registered
     pub fn all_actions(&self) -> Vec<&Box<dyn Action>> {
-        self.actions.values().collect()
+        self.actions.iter().collect()
+    }
+    /// Execute the named action, returning `false` if it is not registered
+    pub fn execute(&self, name: &str, model: &mut crate::model::DasherModel) -> bool {
+        match self.get_action(name) {
+            Some(action) => {
+                action.execute(model);
+                true
+            }
+            None => false,
+        }
     }
 }
 
@@ -44,16 +69,7 @@ impl Action for BackspaceAction {
     fn name(&self) -> &str { "backspace" }
     fn label(&self) -> &str { "⌫" }
     fn execute(&self, model: &mut crate::model::DasherModel) {
-        let text = model.output_text();
-        if !text.is_empty() {
-            let mut new_text = text.to_string();
-            if let Some(idx) = new_text.char_indices().next_back().map(|(i, _)| i) {
-                new_text.truncate(idx);
-            } else {
-                new_text.clear();
-            }
-            model.set_output_text(&new_text);
-        }
+        model.backspace();
     }
 }
 
@@ -70,8 +86,8 @@ pub struct AcceptAction;
 impl Action for AcceptAction {
     fn name(&self) -> &str { "accept" }
     fn label(&self) -> &str { "✔" }
-    fn execute(&self, _model: &mut crate::model::DasherModel) {
-        // Placeholder: mark input as accepted, trigger callback, etc.
+    fn execute(&self, model: &mut crate::model::DasherModel) {
+        model.commit_sentence();
     }
 }
 
@@ -80,22 +96,222 @@ impl Action for UndoAction {
     fn name(&self) -> &str { "undo" }
     fn label(&self) -> &str { "↶" }
     fn execute(&self, model: &mut crate::model::DasherModel) {
-        let text = model.output_text();
-        if !text.is_empty() {
-            let mut new_text = text.to_string();
-            if let Some(idx) = new_text.char_indices().next_back().map(|(i, _)| i) {
-                new_text.truncate(idx);
-            } else {
-                new_text.clear();
-            }
-            model.set_output_text(&new_text);
+        model.undo();
+    }
+}
+
+pub struct RedoAction;
+impl Action for RedoAction {
+    fn name(&self) -> &str { "redo" }
+    fn label(&self) -> &str { "↷" }
+    fn execute(&self, model: &mut crate::model::DasherModel) {
+        model.redo();
+    }
+}
+
+/// Moves the cursor by a character, a word, or to either end of the text
+pub struct MoveCursorAction {
+    movement: CursorMovement,
+}
+impl MoveCursorAction {
+    pub fn new(movement: CursorMovement) -> Self {
+        Self { movement }
+    }
+    /// Create an action for each movement of the cursor
+    pub fn all() -> Vec<Self> {
+        [
+            CursorMovement::CharLeft,
+            CursorMovement::CharRight,
+            CursorMovement::WordLeft,
+            CursorMovement::WordRight,
+            CursorMovement::Start,
+            CursorMovement::End,
+        ].into_iter().map(Self::new).collect()
+    }
+}
+impl Action for MoveCursorAction {
+    fn name(&self) -> &str {
+        match self.movement {
+            CursorMovement::CharLeft => "cursor-left",
+            CursorMovement::CharRight => "cursor-right",
+            CursorMovement::WordLeft => "word-left",
+            CursorMovement::WordRight => "word-right",
+            CursorMovement::Start => "cursor-start",
+            CursorMovement::End => "cursor-end",
+        }
+    }
+    fn label(&self) -> &str {
+        match self.movement {
+            CursorMovement::CharLeft => "←",
+            CursorMovement::CharRight => "→",
+            CursorMovement::WordLeft => "⇠",
+            CursorMovement::WordRight => "⇢",
+            CursorMovement::Start => "⇤",
+            CursorMovement::End => "⇥",
+        }
+    }
+    fn execute(&self, model: &mut crate::model::DasherModel) {
+        model.move_cursor(self.movement);
+    }
+}
+
+/// Host-provided speech synthesis (e.g. a platform TTS engine)
+pub trait SpeechProvider {
+    /// Speak the given text
+    fn speak(&self, text: &str);
+    /// Stop any speech in progress
+    fn stop(&self) {}
+}
+
+/// Characters that end a sentence
+pub(crate) const SENTENCE_TERMINATORS: &[char] = &['.', '!', '?', '\n'];
+
+/// Get the sentence currently being written, or the last complete one
+/// if the text ends with a sentence terminator.
+pub fn current_sentence(text: &str) -> &str {
+    let trimmed = text.trim_end();
+    let body = trimmed.trim_end_matches(SENTENCE_TERMINATORS);
+    let start = body
+        .rfind(SENTENCE_TERMINATORS)
+        .map(|i| i + body[i..].chars().next().map_or(1, char::len_utf8))
+        .unwrap_or(0);
+    trimmed[start..].trim()
+}
+
+/// Speaks the current sentence through a host-provided speech provider
+pub struct SpeakAction {
+    provider: Box<dyn SpeechProvider>,
+}
+impl SpeakAction {
+    pub fn new(provider: Box<dyn SpeechProvider>) -> Self {
+        Self { provider }
+    }
+}
+impl Action for SpeakAction {
+    fn name(&self) -> &str { "speak" }
+    fn label(&self) -> &str { "🔊" }
+    fn execute(&self, model: &mut crate::model::DasherModel) {
+        let sentence = current_sentence(model.output_text());
+        if !sentence.is_empty() {
+            self.provider.speak(sentence);
+        }
+    }
+}
+
+/// Host-provided clipboard access
+pub trait ClipboardProvider {
+    /// Place the given text on the clipboard
+    fn set_text(&self, text: &str);
+}
+
+/// Copies the output text to the clipboard
+pub struct CopyAction {
+    provider: Rc<dyn ClipboardProvider>,
+}
+impl CopyAction {
+    pub fn new(provider: Rc<dyn ClipboardProvider>) -> Self {
+        Self { provider }
+    }
+}
+impl Action for CopyAction {
+    fn name(&self) -> &str { "copy" }
+    fn label(&self) -> &str { "⎘" }
+    fn execute(&self, model: &mut crate::model::DasherModel) {
+        self.provider.set_text(model.output_text());
+    }
+}
+
+/// Copies the output text to the clipboard and clears it
+pub struct CutAction {
+    provider: Rc<dyn ClipboardProvider>,
+}
+impl CutAction {
+    pub fn new(provider: Rc<dyn ClipboardProvider>) -> Self {
+        Self { provider }
+    }
+}
+impl Action for CutAction {
+    fn name(&self) -> &str { "cut" }
+    fn label(&self) -> &str { "✂" }
+    fn execute(&self, model: &mut crate::model::DasherModel) {
+        self.provider.set_text(model.output_text());
+        model.set_output_text("");
+    }
+}
+
+/// Switches to a language profile, offered as a control node
+pub struct SwitchProfileAction {
+    name: String,
+    profile: String,
+}
+impl SwitchProfileAction {
+    pub fn new(profile: &str) -> Self {
+        Self {
+            name: crate::model::LanguageProfile::action_name(profile),
+            profile: profile.to_string(),
         }
     }
 }
+impl Action for SwitchProfileAction {
+    fn name(&self) -> &str { &self.name }
+    fn label(&self) -> &str { &self.profile }
+    fn execute(&self, model: &mut crate::model::DasherModel) {
+        model.switch_profile(&self.profile);
+    }
+}
+
+pub struct ClearAction;
+impl Action for ClearAction {
+    fn name(&self) -> &str { "clear" }
+    fn label(&self) -> &str { "⌧" }
+    fn execute(&self, model: &mut crate::model::DasherModel) {
+        model.set_output_text("");
+    }
+}
 
 impl ActionManager {
     pub fn unregister_action(&mut self, name: &str) {
-        self.actions.remove(name);
+        self.actions.retain(|action| action.name() != name);
+    }
+
+    /// Set the host callback invoked with the callback id of custom actions
+    pub fn set_action_callback<F: Fn(u32) + 'static>(&mut self, callback: F) {
+        *self.callback.borrow_mut() = Some(Box::new(callback));
+    }
+
+    /// Remove the host callback for custom actions
+    pub fn clear_action_callback(&mut self) {
+        *self.callback.borrow_mut() = None;
+    }
+
+    /// Register the actions declared in a configuration, returning how many were added
+    pub fn load_custom_actions(&mut self, config: &CustomActionsConfig) -> usize {
+        for action in &config.actions {
+            self.register_action(Box::new(CustomAction::new(action, &config.variables, self.callback.clone())));
+        }
+        config.actions.len()
+    }
+
+    /// Register the actions declared in a JSON or XML configuration file
+    pub fn load_custom_actions_file<P: AsRef<Path>>(&mut self, path: P) -> crate::Result<usize> {
+        let config = CustomActionsConfig::load(path)?;
+        Ok(self.load_custom_actions(&config))
+    }
+
+    /// Register the actions from `actions.json` or `actions.xml` in the user's
+    /// Dasher configuration directory, if either exists
+    pub fn load_user_actions(&mut self) -> crate::Result<usize> {
+        let Some(config_dir) = dirs::config_dir() else {
+            return Ok(0);
+        };
+        let dasher_dir = config_dir.join("dasher");
+        for file_name in ["actions.json", "actions.xml"] {
+            let path = dasher_dir.join(file_name);
+            if path.exists() {
+                return self.load_custom_actions_file(path);
+            }
+        }
+        Ok(0)
     }
 }
 
@@ -112,4 +328,133 @@ mod tests {
         action.execute(&mut model);
         assert_eq!(model.output_text(), "ab");
     }
+
+    #[test]
+    fn test_actions_edit_at_cursor() {
+        let mut model = DasherModel::new();
+        model.set_output_text("helo");
+        model.set_cursor_position(3);
+        assert_eq!(model.get_offset(), 3);
+
+        assert!(model.execute_action("space"));
+        assert_eq!(model.output_text(), "hel o");
+        assert!(model.execute_action("backspace"));
+        assert!(model.execute_action("backspace"));
+        assert_eq!(model.output_text(), "heo");
+        assert_eq!(model.get_offset(), 2);
+
+        assert!(model.undo());
+        assert_eq!(model.output_text(), "helo");
+        assert_eq!(model.get_offset(), 3);
+    }
+
+    #[test]
+    fn test_undo_redo_actions() {
+        let mut model = DasherModel::new();
+        model.set_output_text("");
+        assert!(model.execute_action("space"));
+        assert!(model.execute_action("space"));
+        assert!(model.execute_action("backspace"));
+        assert_eq!(model.output_text(), " ");
+
+        // Undo reverts whole operations, not single characters
+        assert!(model.execute_action("undo"));
+        assert_eq!(model.output_text(), "  ");
+        assert!(model.execute_action("undo"));
+        assert!(model.execute_action("undo"));
+        assert_eq!(model.output_text(), "");
+        assert!(!model.undo());
+
+        assert!(model.execute_action("redo"));
+        assert_eq!(model.output_text(), " ");
+        assert!(model.undo_stack().can_redo());
+    }
+
+    #[test]
+    fn test_custom_actions_from_config() {
+        use std::cell::Cell;
+
+        let json = r#"{
+            "variables": { "name": "Sam" },
+            "actions": [
+                { "name": "thanks", "label": "Thank you", "text": "Thank you! " },
+                { "name": "intro", "label": "Intro", "text": "I am {name}, {unknown}", "callback_id": 7 }
+            ]
+        }"#;
+        let xml = r#"<actions>
+            <variable name="name" value="Sam"/>
+            <action name="thanks" label="Thank you" text="Thank you! "/>
+            <action name="intro" label="Intro" text="I am {name}, {unknown}" callback="7"/>
+        </actions>"#;
+        let config = CustomActionsConfig::from_json(json).unwrap();
+        assert_eq!(CustomActionsConfig::from_xml(xml).unwrap(), config);
+
+        let mut model = DasherModel::new();
+        assert_eq!(model.action_manager.load_custom_actions(&config), 2);
+        assert_eq!(model.action_manager.get_action("thanks").unwrap().label(), "Thank you");
+
+        let called = Rc::new(Cell::new(0));
+        let callback_called = called.clone();
+        model.action_manager.set_action_callback(move |id| callback_called.set(id));
+
+        model.set_output_text("");
+        assert!(model.execute_action("thanks"));
+        assert!(model.execute_action("intro"));
+        assert_eq!(model.output_text(), "Thank you! I am Sam, {unknown}");
+        assert_eq!(called.get(), 7);
+    }
+
+    #[test]
+    fn test_clipboard_actions() {
+        use std::cell::RefCell;
+
+        struct RecordingClipboard(RefCell<String>);
+        impl ClipboardProvider for RecordingClipboard {
+            fn set_text(&self, text: &str) {
+                *self.0.borrow_mut() = text.to_string();
+            }
+        }
+
+        let clipboard = Rc::new(RecordingClipboard(RefCell::new(String::new())));
+        let mut model = DasherModel::new();
+        model.action_manager.register_action(Box::new(CopyAction::new(clipboard.clone())));
+        model.action_manager.register_action(Box::new(CutAction::new(clipboard.clone())));
+
+        model.set_output_text("hello");
+        assert!(model.execute_action("copy"));
+        assert_eq!(*clipboard.0.borrow(), "hello");
+        assert_eq!(model.output_text(), "hello");
+
+        model.set_output_text("world");
+        assert!(model.execute_action("cut"));
+        assert_eq!(*clipboard.0.borrow(), "world");
+        assert_eq!(model.output_text(), "");
+
+        model.set_output_text("gone");
+        assert!(model.execute_action("clear"));
+        assert_eq!(model.output_text(), "");
+    }
+
+    #[test]
+    fn test_speak_action_speaks_current_sentence() {
+        use std::cell::RefCell;
+
+        struct RecordingProvider(Rc<RefCell<Vec<String>>>);
+        impl SpeechProvider for RecordingProvider {
+            fn speak(&self, text: &str) {
+                self.0.borrow_mut().push(text.to_string());
+            }
+        }
+
+        assert_eq!(current_sentence("Hello there. How are you? "), "How are you?");
+        assert_eq!(current_sentence("Hello there. How are"), "How are");
+        assert_eq!(current_sentence(""), "");
+
+        let spoken = Rc::new(RefCell::new(Vec::new()));
+        let mut model = DasherModel::new();
+        model.action_manager.register_action(Box::new(SpeakAction::new(Box::new(RecordingProvider(spoken.clone())))));
+        model.set_output_text("First one. Second one");
+        assert!(model.execute_action("speak"));
+        assert_eq!(*spoken.borrow(), vec!["Second one".to_string()]);
+    }
 }
@@ -2,7 +2,21 @@ use quick_xml::Reader;
 use quick_xml::events::Event;
 use std::path::Path;
 use std::io::BufReader;
-use crate::alphabet::AlphabetXmlError;
+use crate::alphabet::{AlphabetXmlError, Color};
+use super::xml::read_group_appearance;
+
+/// Shape and colors for nodes of one group of symbols, such as punctuation
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupAppearance {
+    /// Name of the group: `letters`, `digits`, `punctuation` or `control`
+    pub name: String,
+    /// Name of the node shape, such as `circle`
+    pub shape: Option<String>,
+    /// Label color
+    pub foreground: Option<Color>,
+    /// Node color
+    pub background: Option<Color>,
+}
 
 /// Color scheme for alphabet visualization
 #[derive(Debug, Clone)]
@@ -13,6 +27,8 @@ pub struct ColorScheme {
     pub description: String,
     /// Colors in the scheme (RGBA)
     pub colors: Vec<(u8, u8, u8, u8)>,
+    /// Appearance of each group of symbols
+    pub groups: Vec<GroupAppearance>,
 }
 
 impl ColorScheme {
@@ -22,6 +38,7 @@ impl ColorScheme {
             name,
             description,
             colors: Vec::new(),
+            groups: Vec::new(),
         }
     }
 
@@ -104,9 +121,19 @@ pub fn load_color_schemes<P: AsRef<Path>>(path: P) -> Result<Vec<ColorScheme>, A
                             scheme.add_color(r, g, b, a);
                         }
                     }
+                    b"group" => {
+                        if let Some(ref mut scheme) = current_scheme {
+                            scheme.groups.push(read_group_appearance(e)?);
+                        }
+                    }
                     _ => {}
                 }
             }
+            Event::Empty(ref e) => {
+                if let (b"group", Some(scheme)) = (e.name().as_ref(), current_scheme.as_mut()) {
+                    scheme.groups.push(read_group_appearance(e)?);
+                }
+            }
             Event::End(ref e) => {
                 if e.name().as_ref() == b"colorscheme" {
                     if let Some(scheme) = current_scheme.take() {
@@ -1,6 +1,9 @@
 use std::collections::HashMap;
+use std::path::Path;
 use std::str::FromStr;
 
+use super::{load_palettes, AlphabetXmlError, Palette};
+
 /// RGB color representation
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Color {
@@ -125,6 +128,8 @@ impl ColorScheme {
 pub struct ColorManager {
     /// Available color schemes
     schemes: HashMap<String, ColorScheme>,
+    /// Available upstream palettes of numbered colors
+    palettes: HashMap<String, Palette>,
 }
 
 impl ColorManager {
@@ -150,6 +155,31 @@ impl ColorManager {
         self.schemes.keys().cloned().collect()
     }
 
+    /// Add a palette of numbered colors, replacing any of the same name
+    pub fn add_palette(&mut self, palette: Palette) {
+        self.palettes.insert(palette.name.clone(), palette);
+    }
+
+    /// Load the palettes of an upstream `colour.*.xml` file, returning how many were loaded
+    pub fn load_palettes<P: AsRef<Path>>(&mut self, path: P) -> Result<usize, AlphabetXmlError> {
+        let palettes = load_palettes(path)?;
+        let count = palettes.len();
+        for palette in palettes {
+            self.add_palette(palette);
+        }
+        Ok(count)
+    }
+
+    /// Get a palette by name
+    pub fn get_palette(&self, name: &str) -> Option<&Palette> {
+        self.palettes.get(name)
+    }
+
+    /// Get the color numbered `index` in the palette named `palette`
+    pub fn color_by_index(&self, palette: &str, index: usize) -> Option<Color> {
+        self.get_palette(palette).and_then(|palette| palette.color(index))
+    }
+
     /// Add default color schemes
     fn add_default_schemes(&mut self) {
         // Default color scheme
@@ -241,4 +271,37 @@ mod tests {
         assert!(manager.get_scheme("High Contrast").is_some());
         assert!(manager.get_scheme("NonExistent").is_none());
     }
+
+    #[test]
+    fn test_upstream_palettes_are_looked_up_by_index() {
+        let mut manager = ColorManager::new();
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/colours/colour.xml");
+        assert_eq!(manager.load_palettes(path).unwrap(), 1);
+
+        let palette = manager.get_palette("Default").unwrap();
+        assert_eq!(palette.colors.len(), 243);
+        assert_eq!(manager.color_by_index("Default", Palette::BACKGROUND), Some(Color::new(255, 255, 255)));
+        assert_eq!(manager.color_by_index("Default", Palette::MOUSE_LINE), Some(Color::new(255, 0, 0)));
+        assert_eq!(manager.color_by_index("Default", Palette::FIRST_LETTER), Some(Color::new(0, 255, 255)));
+        assert_eq!(manager.color_by_index("Default", 1000), None);
+        assert_eq!(manager.color_by_index("Missing", 0), None);
+
+        // Symbols take the colors their characters are numbered with
+        let info = crate::alphabet::parse_alphabet(r#"<alphabet name="Tiny">
+            <node label="a" colour="10"><textCharAction /></node>
+            <node label="_"><textCharAction unicode="32"/></node>
+        </alphabet>"#).unwrap();
+        assert_eq!(info.characters[0].color, Some(10));
+        let alphabet = crate::alphabet::Alphabet::from_info_with_palette(info, palette);
+        assert_eq!(alphabet.symbols()[0].background_color, Color::new(0, 255, 255));
+        assert_eq!(alphabet.symbols()[1].background_color, Color::new(255, 255, 255));
+
+        // Node colors alternate by offset, and uncolored symbols cycle through defaults
+        let [a, space] = [&alphabet.symbols()[0], &alphabet.symbols()[1]];
+        assert_eq!(alphabet.node_colors(a, 1).1, Color::new(0, 255, 255));
+        assert_eq!(alphabet.node_colors(a, 2).1, palette.color(10 + Palette::PHASE_OFFSET).unwrap());
+        assert_eq!(alphabet.node_colors(space, 1).1, palette.color(81).unwrap());
+        assert_eq!(alphabet.node_colors(space, 2).1, palette.color(64 + Palette::PHASE_OFFSET).unwrap());
+        assert_ne!(alphabet.node_colors(a, 1).1, alphabet.node_colors(a, 2).1);
+    }
 }
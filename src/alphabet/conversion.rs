@@ -1,6 +1,30 @@
 use std::collections::HashMap;
+use std::fmt;
 use super::info::{AlphabetInfo, AlphabetConversion};
 
+/// A candidate conversion of a composition string
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionCandidate {
+    /// Text the composition string converts to
+    pub text: String,
+    /// Share of the probability given to the candidate, from 0 to 1
+    pub probability: f64,
+}
+
+/// Source of ranked conversions of composition strings
+///
+/// A provider turns what the user spelled, such as kana or pinyin, into the
+/// text it may stand for. Platform input methods or external conversion
+/// libraries implement this to replace the alphabet's own conversion table.
+pub trait ConversionProvider {
+    /// Get the candidate conversions of `input`, most likely first, given
+    /// the `context` the conversion happens in
+    fn candidates(&self, input: &str, context: &str) -> Vec<ConversionCandidate>;
+
+    /// Learn that the user converted `input` to `output`
+    fn learn(&mut self, _input: &str, _output: &str) {}
+}
+
 /// A conversion rule for converting between symbol sets
 #[derive(Debug, Clone)]
 pub struct ConversionRule {
@@ -23,6 +47,8 @@ pub struct ConversionTable {
     input_map: HashMap<String, Vec<usize>>,
     /// Map from context to rule indices for fast lookup
     context_map: HashMap<String, Vec<usize>>,
+    /// Number of times each rule was chosen, indexed like the rules
+    uses: Vec<u32>,
 }
 
 impl From<ConversionRule> for ConversionTable {
@@ -51,6 +77,7 @@ impl ConversionTable {
             rules: Vec::new(),
             input_map: HashMap::new(),
             context_map: HashMap::new(),
+            uses: Vec::new(),
         }
     }
 
@@ -73,6 +100,7 @@ impl ConversionTable {
         }
         
         self.rules.push(rule);
+        self.uses.push(0);
     }
 
     /// Get all rules that match an input
@@ -92,13 +120,65 @@ impl ConversionTable {
     }
 }
 
+impl ConversionProvider for ConversionTable {
+    /// Rank the rules for `input`: those of the context first, then those
+    /// for any context, each by how often they were chosen
+    ///
+    /// With no rule for `input`, the only candidate is `input` itself.
+    fn candidates(&self, input: &str, context: &str) -> Vec<ConversionCandidate> {
+        let mut indices: Vec<usize> = self.input_map.get(input).cloned().unwrap_or_default();
+        indices.retain(|&i| self.rules[i].context.is_empty() || self.rules[i].context == context);
+        if indices.is_empty() {
+            return vec![ConversionCandidate { text: input.to_string(), probability: 1.0 }];
+        }
+        // Stable, so rules added first come first among equals
+        indices.sort_by_key(|&i| (self.rules[i].context.is_empty(), std::cmp::Reverse(self.uses[i])));
+
+        let mut candidates: Vec<ConversionCandidate> = Vec::new();
+        for i in indices {
+            let weight = (self.uses[i] + 1) as f64;
+            match candidates.iter_mut().find(|candidate| candidate.text == self.rules[i].output) {
+                Some(candidate) => candidate.probability += weight,
+                None => candidates.push(ConversionCandidate { text: self.rules[i].output.clone(), probability: weight }),
+            }
+        }
+        let total: f64 = candidates.iter().map(|candidate| candidate.probability).sum();
+        for candidate in &mut candidates {
+            candidate.probability /= total;
+        }
+        candidates
+    }
+
+    /// Count a use of the trainable rules converting `input` to `output`
+    fn learn(&mut self, input: &str, output: &str) {
+        if let Some(indices) = self.input_map.get(input) {
+            for &i in indices {
+                if self.rules[i].trainable && self.rules[i].output == output {
+                    self.uses[i] += 1;
+                }
+            }
+        }
+    }
+}
+
 /// Manager for alphabet conversions
-#[derive(Debug)]
 pub struct ConversionManager {
     /// Tables for each conversion type
     tables: HashMap<AlphabetConversion, ConversionTable>,
     /// Current context for context-sensitive conversions
     current_context: String,
+    /// Provider of candidates used instead of the tables, if any
+    provider: Option<Box<dyn ConversionProvider>>,
+}
+
+impl fmt::Debug for ConversionManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConversionManager")
+            .field("tables", &self.tables)
+            .field("current_context", &self.current_context)
+            .field("provider", &self.provider.is_some())
+            .finish()
+    }
 }
 
 impl Default for ConversionManager {
@@ -118,6 +198,42 @@ impl ConversionManager {
         Self {
             tables: HashMap::new(),
             current_context: String::new(),
+            provider: None,
+        }
+    }
+
+    /// Set the provider of candidates, such as a platform input method,
+    /// used instead of the conversion tables; `None` goes back to the tables
+    pub fn set_provider(&mut self, provider: Option<Box<dyn ConversionProvider>>) {
+        self.provider = provider;
+    }
+
+    /// Check whether a provider replaces the conversion tables
+    pub fn has_provider(&self) -> bool {
+        self.provider.is_some()
+    }
+
+    /// Get the candidate conversions of `input`, most likely first
+    ///
+    /// The provider is asked if there is one, and otherwise the table for
+    /// `conversion_type`. Without either, `input` is its only candidate.
+    pub fn candidates(&self, input: &str, conversion_type: AlphabetConversion) -> Vec<ConversionCandidate> {
+        if let Some(provider) = &self.provider {
+            return provider.candidates(input, &self.current_context);
+        }
+        match self.tables.get(&conversion_type) {
+            Some(table) => table.candidates(input, &self.current_context),
+            None => vec![ConversionCandidate { text: input.to_string(), probability: 1.0 }],
+        }
+    }
+
+    /// Learn that the user converted `input` to `output`, so that it ranks
+    /// higher next time
+    pub fn learn(&mut self, input: &str, output: &str, conversion_type: AlphabetConversion) {
+        if let Some(provider) = &mut self.provider {
+            provider.learn(input, output);
+        } else if let Some(table) = self.tables.get_mut(&conversion_type) {
+            table.learn(input, output);
         }
     }
 
@@ -361,4 +477,49 @@ mod tests {
         manager.set_context("greek".to_string());
         assert_eq!(manager.convert("th", AlphabetConversion::RoutingContextSensitive), "θ");
     }
+
+    /// A provider standing in for a platform input method
+    struct FixedProvider;
+
+    impl ConversionProvider for FixedProvider {
+        fn candidates(&self, input: &str, _context: &str) -> Vec<ConversionCandidate> {
+            vec![ConversionCandidate { text: input.to_uppercase(), probability: 1.0 }]
+        }
+    }
+
+    #[test]
+    fn test_candidates_are_ranked_and_learned() {
+        let mut table = ConversionTable::new();
+        for output in ["橋", "箸", "端"] {
+            table.add_rule(ConversionRule {
+                input: "はし".to_string(),
+                output: output.to_string(),
+                context: String::new(),
+                trainable: true,
+            });
+        }
+        let mut manager = ConversionManager::new();
+        manager.add_table(AlphabetConversion::RoutingContextInsensitive, table);
+
+        let texts = |manager: &ConversionManager| -> Vec<String> {
+            manager.candidates("はし", AlphabetConversion::RoutingContextInsensitive)
+                .into_iter()
+                .map(|candidate| candidate.text)
+                .collect()
+        };
+        assert_eq!(texts(&manager), ["橋", "箸", "端"]);
+
+        // Choosing a candidate moves it up
+        manager.learn("はし", "端", AlphabetConversion::RoutingContextInsensitive);
+        assert_eq!(texts(&manager), ["端", "橋", "箸"]);
+        let candidates = manager.candidates("はし", AlphabetConversion::RoutingContextInsensitive);
+        assert!((candidates[0].probability - 0.5).abs() < 1e-9);
+        assert_eq!(manager.candidates("やま", AlphabetConversion::RoutingContextInsensitive)[0].text, "やま");
+
+        // A provider replaces the tables
+        manager.set_provider(Some(Box::new(FixedProvider)));
+        assert_eq!(texts(&manager), ["はし".to_uppercase()]);
+        manager.set_provider(None);
+        assert_eq!(texts(&manager)[0], "端");
+    }
 }
@@ -157,6 +157,23 @@ impl AlphabetDiscovery {
         Ok(None)
     }
 
+    /// Find an alphabet's training file by name
+    ///
+    /// Training files are looked for beside the alphabet files and in a
+    /// `training` directory next to each alphabet directory, as in the
+    /// original Dasher data layout.
+    pub fn find_training_file(&self, name: &str) -> Option<PathBuf> {
+        if name.is_empty() {
+            return None;
+        }
+        self.search_paths.iter()
+            .flat_map(|path| {
+                let sibling = path.parent().map(|parent| parent.join("training").join(name));
+                std::iter::once(path.join(name)).chain(sibling)
+            })
+            .find(|path| path.is_file())
+    }
+
     /// Find color schemes in a specific file
     pub fn find_color_schemes<P: AsRef<Path>>(&self, path: P) -> Result<Vec<ColorScheme>, DiscoveryError> {
         Ok(load_color_schemes(path)?)
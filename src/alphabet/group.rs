@@ -26,6 +26,8 @@ pub struct GroupInfo {
     pub parent: Option<Box<GroupInfo>>,
     /// Child groups
     pub children: Vec<GroupInfo>,
+    /// Label of the toggle node the group is offered behind, if it is
+    pub toggle_label: Option<String>,
 }
 
 impl GroupInfo {
@@ -43,6 +45,7 @@ impl GroupInfo {
             visible: true,
             parent: None,
             children: Vec::new(),
+            toggle_label: None,
         }
     }
 
@@ -42,6 +42,8 @@ pub struct Character {
     pub fixed_probability: f32,
     /// Speed factor for the character (-1 if not modified)
     pub speed_factor: f32,
+    /// Number of the character's colour in the palette, if the alphabet gives one
+    pub color: Option<usize>,
 }
 
 impl Default for Character {
@@ -53,6 +55,7 @@ impl Default for Character {
             color_group_offset: -1,
             fixed_probability: -1.0,
             speed_factor: -1.0,
+            color: None,
         }
     }
 }
@@ -80,6 +83,8 @@ pub struct AlphabetInfo {
     pub conversion_train_stop: String,
     /// Characters in the alphabet
     pub characters: Vec<Character>,
+    /// Top-level groups of characters, holding nested groups as children
+    pub groups: Vec<GroupInfo>,
     /// Character actions (do)
     pub character_do_actions: Vec<Vec<String>>,
     /// Character actions (undo)
@@ -100,6 +105,7 @@ impl AlphabetInfo {
             conversion_train_start: String::from("<"),
             conversion_train_stop: String::from(">"),
             characters: Vec::new(),
+            groups: Vec::new(),
             character_do_actions: Vec::new(),
             character_undo_actions: Vec::new(),
         }
@@ -16,15 +16,20 @@ mod conversion;
 mod discovery;
 mod training;
 mod color_schemes;
+mod palette;
+mod validation;
 
 pub use info::{AlphabetInfo, ScreenOrientation, AlphabetConversion, Character};
 pub use group::GroupInfo;
 pub use map::AlphabetMap;
-pub use xml::{AlphabetXmlError, save_alphabet, load_alphabet, load_color_schemes};
+pub use xml::{AlphabetXmlError, save_alphabet, load_alphabet, parse_alphabet, load_color_schemes, load_palettes, parse_palettes};
+pub use palette::Palette;
+pub use validation::{AlphabetDiagnostic, DiagnosticSeverity};
+pub use color_schemes::GroupAppearance;
 pub use colors::{Color, ColorManager, ColorScheme};
-pub use conversion::{ConversionManager, ConversionTable, ConversionRule};
+pub use conversion::{ConversionCandidate, ConversionManager, ConversionProvider, ConversionTable, ConversionRule};
 pub use discovery::{AlphabetDiscovery, DiscoveryError, DiscoveryResult};
-pub use training::{TrainingManager, TrainingStats, TrainingError};
+pub use training::{TrainingManager, TrainingStats, TrainingError, TrainingText, TrainingTextParser};
 
 use std::collections::HashMap;
 
@@ -42,9 +47,18 @@ pub struct Symbol {
     
     /// The background color for this symbol
     pub background_color: Color,
+
+    /// Number of the symbol's color in the alphabet's palette, if it has one
+    pub color: Option<usize>,
 }
 
 impl Symbol {
+    /// Character entered by the paragraph symbol
+    pub const PARAGRAPH: char = '\n';
+
+    /// Glyph shown for the paragraph symbol
+    pub const PARAGRAPH_DISPLAY: &'static str = "¶";
+
     /// Create a new symbol
     pub fn new(character: char, display_text: &str, foreground_color: Color, background_color: Color) -> Self {
         Self {
@@ -52,6 +66,7 @@ impl Symbol {
             display_text: display_text.to_string(),
             foreground_color,
             background_color,
+            color: None,
         }
     }
     
@@ -64,6 +79,45 @@ impl Symbol {
             Color::new(255, 255, 255) // White background
         )
     }
+
+    /// Create the paragraph symbol, which starts a new line
+    pub fn paragraph() -> Self {
+        Self::with_default_colors(Self::PARAGRAPH, Self::PARAGRAPH_DISPLAY)
+    }
+
+    /// Check whether this is the paragraph symbol
+    pub fn is_paragraph(&self) -> bool {
+        self.character == Self::PARAGRAPH
+    }
+}
+
+/// A group of symbols offered behind a single toggle node
+///
+/// The toggle node takes the combined probability of the group's symbols,
+/// which appear as its children, keeping rarely used digits and symbols
+/// out of the way of the letters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToggleGroup {
+    /// Label of the toggle node
+    pub label: String,
+
+    /// Characters of the symbols in the group
+    pub characters: Vec<char>,
+}
+
+impl ToggleGroup {
+    /// Create a toggle group
+    pub fn new(label: &str, characters: Vec<char>) -> Self {
+        Self {
+            label: label.to_string(),
+            characters,
+        }
+    }
+
+    /// Check whether the group holds the symbol of `c`
+    pub fn contains(&self, c: char) -> bool {
+        self.characters.contains(&c)
+    }
 }
 
 /// An alphabet for Dasher
@@ -76,9 +130,27 @@ pub struct Alphabet {
     
     /// The name of this alphabet
     name: String,
+
+    /// Groups of symbols offered behind toggle nodes
+    toggle_groups: Vec<ToggleGroup>,
+
+    /// Smallest share of their parent's interval pinned for some symbols
+    fixed_probabilities: HashMap<char, f32>,
+
+    /// Palette the symbols' color numbers refer to, if any
+    palette: Option<Palette>,
 }
 
 impl Alphabet {
+    /// Label of the digit toggle node
+    pub const DIGITS_LABEL: &'static str = "123";
+
+    /// Label of the symbol toggle node
+    pub const SYMBOLS_LABEL: &'static str = "#+=";
+
+    /// Characters of the symbol toggle group
+    pub const SYMBOL_CHARACTERS: &'static str = "-:;()@#&/+=%*$";
+
     /// Create a conversion manager for this alphabet
     pub fn create_conversion_manager(&self, info: &AlphabetInfo) -> ConversionManager {
         ConversionManager::from_alphabet(info)
@@ -86,25 +158,71 @@ impl Alphabet {
     /// Create an alphabet from alphabet info
     pub fn from_info(info: AlphabetInfo) -> Self {
         let mut alphabet = Self::new(&info.id);
+        // Start of the toggle group the previous character was in, if any
+        let mut toggle_start = None;
         
         for character in &info.characters {
-            let symbol = Symbol::new(
+            let mut symbol = Symbol::new(
                 character.text.chars().next().unwrap_or(' '),
                 &character.display,
                 Color::new(0, 0, 0), // Default colors, should be from color group
                 Color::new(255, 255, 255)
             );
+            symbol.color = character.color;
+
+            let toggle = character.parent_group.as_ref()
+                .and_then(|group| group.toggle_label.as_ref().map(|label| (group.start, label)));
+            match toggle {
+                Some((start, _)) if toggle_start == Some(start) => {
+                    if let Some(group) = alphabet.toggle_groups.last_mut() {
+                        group.characters.push(symbol.character);
+                    }
+                }
+                Some((start, label)) => {
+                    alphabet.toggle_groups.push(ToggleGroup::new(label, vec![symbol.character]));
+                    toggle_start = Some(start);
+                }
+                None => toggle_start = None,
+            }
+            if (0.0..=1.0).contains(&character.fixed_probability) {
+                alphabet.set_fixed_probability(symbol.character, character.fixed_probability);
+            }
             alphabet.add_symbol(symbol);
         }
         
         alphabet
     }
+
+    /// Create an alphabet from alphabet info, coloring each symbol with the
+    /// palette color its character is numbered with
+    ///
+    /// Labels take the palette's text color; symbols without a color number,
+    /// or with one past the end of the palette, keep the default colors.
+    /// The palette is kept for [`Alphabet::node_colors`].
+    pub fn from_info_with_palette(info: AlphabetInfo, palette: &Palette) -> Self {
+        let mut alphabet = Self::from_info(info);
+        alphabet.set_palette(Some(palette.clone()));
+        let text = palette.color(Palette::TEXT);
+        for symbol in &mut alphabet.symbols {
+            if let Some(background) = symbol.color.and_then(|index| palette.color(index)) {
+                symbol.background_color = background;
+                if let Some(text) = text {
+                    symbol.foreground_color = text;
+                }
+            }
+        }
+        alphabet
+    }
+
     /// Create a new empty alphabet
     pub fn new(name: &str) -> Self {
         Self {
             symbols: Vec::new(),
             char_to_index: HashMap::new(),
             name: name.to_string(),
+            toggle_groups: Vec::new(),
+            fixed_probabilities: HashMap::new(),
+            palette: None,
         }
     }
     
@@ -127,6 +245,9 @@ impl Alphabet {
         alphabet.add_symbol(Symbol::with_default_colors('?', "?"));
         alphabet.add_symbol(Symbol::with_default_colors('\'', "'"));
         alphabet.add_symbol(Symbol::with_default_colors('\"', "\""));
+
+        // Add paragraph
+        alphabet.add_symbol(Symbol::paragraph());
         
         alphabet
     }
@@ -171,4 +292,87 @@ impl Alphabet {
     pub fn symbols(&self) -> &[Symbol] {
         &self.symbols
     }
+
+    /// Add `symbols` to the alphabet behind a toggle node labelled `label`
+    ///
+    /// Symbols whose character the alphabet already has are left out.
+    pub fn add_toggle_group(&mut self, label: &str, symbols: Vec<Symbol>) {
+        let mut characters = Vec::new();
+        for symbol in symbols {
+            if self.char_to_index.contains_key(&symbol.character) {
+                continue;
+            }
+            characters.push(symbol.character);
+            self.add_symbol(symbol);
+        }
+        if !characters.is_empty() {
+            self.toggle_groups.push(ToggleGroup::new(label, characters));
+        }
+    }
+
+    /// Add the digits behind a toggle node
+    pub fn add_digit_group(&mut self) {
+        let digits = ('0'..='9').map(|c| Symbol::with_default_colors(c, &c.to_string())).collect();
+        self.add_toggle_group(Self::DIGITS_LABEL, digits);
+    }
+
+    /// Add common symbols behind a toggle node
+    pub fn add_symbol_group(&mut self) {
+        let symbols = Self::SYMBOL_CHARACTERS.chars().map(|c| Symbol::with_default_colors(c, &c.to_string())).collect();
+        self.add_toggle_group(Self::SYMBOLS_LABEL, symbols);
+    }
+
+    /// Get the groups of symbols offered behind toggle nodes
+    pub fn toggle_groups(&self) -> &[ToggleGroup] {
+        &self.toggle_groups
+    }
+
+    /// Pin the symbol for `c` to at least `probability` of its parent's
+    /// interval, whatever the language model predicts
+    ///
+    /// A probability outside 0 to 1 unpins the symbol.
+    pub fn set_fixed_probability(&mut self, c: char, probability: f32) {
+        if (0.0..=1.0).contains(&probability) {
+            self.fixed_probabilities.insert(c, probability);
+        } else {
+            self.fixed_probabilities.remove(&c);
+        }
+    }
+
+    /// Get the probability the symbol for `c` is pinned to, if any
+    pub fn fixed_probability(&self, c: char) -> Option<f32> {
+        self.fixed_probabilities.get(&c).copied()
+    }
+
+    /// Get the palette the symbols' color numbers refer to, if any
+    pub fn palette(&self) -> Option<&Palette> {
+        self.palette.as_ref()
+    }
+
+    /// Set the palette the symbols' color numbers refer to
+    pub fn set_palette(&mut self, palette: Option<Palette>) {
+        self.palette = palette;
+    }
+
+    /// Get the foreground and background colors of a node at `offset`
+    /// writing `symbol`
+    ///
+    /// With a palette, colors alternate between two phases by offset as in
+    /// upstream Dasher, and symbols without a color number cycle through a
+    /// few defaults, so neighbouring nodes can be told apart without
+    /// outlines. Without one, the symbol's own colors are used.
+    pub fn node_colors(&self, symbol: &Symbol, offset: i32) -> (Color, Color) {
+        let own = (symbol.foreground_color, symbol.background_color);
+        let Some(palette) = &self.palette else {
+            return own;
+        };
+        let index = symbol.color.unwrap_or_else(|| {
+            Palette::default_symbol_color(self.get_index(symbol.character).unwrap_or(0), offset)
+        });
+        let background = palette.color(Palette::phase_color(index, offset)).or_else(|| palette.color(index));
+        match background {
+            Some(background) => (palette.color(Palette::TEXT).unwrap_or(own.0), background),
+            None => own,
+        }
+    }
 }
@@ -0,0 +1,78 @@
+//! Colour palettes
+//!
+//! Upstream Dasher's `colour.*.xml` files list numbered colours, and its
+//! alphabets refer to the colour of each symbol by number. The first ten
+//! colours have fixed roles, such as the background and the text.
+
+use super::Color;
+
+/// A numbered list of colours, as read from a `colour.*.xml` file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    /// Name of the palette
+    pub name: String,
+    /// Colours, by number
+    pub colors: Vec<Color>,
+}
+
+impl Palette {
+    /// Number of the background colour
+    pub const BACKGROUND: usize = 0;
+    /// Number of the mouse line colour
+    pub const MOUSE_LINE: usize = 1;
+    /// Number of the mouse position colour
+    pub const MOUSE_POSITION: usize = 2;
+    /// Number of the box outline colour
+    pub const OUTLINE: usize = 3;
+    /// Number of the text colour
+    pub const TEXT: usize = 4;
+    /// Number of the crosshair colour
+    pub const CROSSHAIR: usize = 5;
+    /// Number of the root node colour
+    pub const ROOT_NODE: usize = 7;
+    /// Number of the control node colour
+    pub const CONTROL_NODE: usize = 8;
+    /// Number of the space colour
+    pub const SPACE: usize = 9;
+    /// Number of the first letter colour
+    pub const FIRST_LETTER: usize = 10;
+    /// Distance from each colour below it to its colour in the second phase
+    pub const PHASE_OFFSET: usize = 130;
+    /// Colours cycled through by symbols the alphabet gives no colour, for
+    /// nodes at odd and even offsets
+    pub const DEFAULT_SYMBOL_COLORS: [[usize; 3]; 2] = [[66, 64, 62], [78, 81, 76]];
+
+    /// Create an empty palette
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            colors: Vec::new(),
+        }
+    }
+
+    /// Get the colour numbered `index`
+    pub fn color(&self, index: usize) -> Option<Color> {
+        self.colors.get(index).copied()
+    }
+
+    /// Get the number of the colour of a node at `offset` that would have
+    /// colour `index`
+    ///
+    /// Like upstream Dasher, nodes at even offsets take the second phase of
+    /// their colour, so a node and its children always differ.
+    pub fn phase_color(index: usize, offset: i32) -> usize {
+        if offset % 2 == 0 && index < Self::PHASE_OFFSET {
+            index + Self::PHASE_OFFSET
+        } else {
+            index
+        }
+    }
+
+    /// Get the colour of the symbol numbered `symbol` in its alphabet, when
+    /// the alphabet gives it none, for a node at `offset`
+    ///
+    /// Neighbouring symbols take different colours, so siblings differ too.
+    pub fn default_symbol_color(symbol: usize, offset: i32) -> usize {
+        Self::DEFAULT_SYMBOL_COLORS[(offset & 1) as usize][symbol % 3]
+    }
+}
@@ -1,6 +1,6 @@
 use std::path::{Path, PathBuf};
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Write};
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom, Write};
 use std::collections::HashMap;
 use std::time::SystemTime;
 
@@ -19,6 +19,8 @@ pub enum TrainingError {
     Io(io::Error),
     /// Invalid data
     InvalidData(String),
+    /// Training was cancelled by the progress callback
+    Cancelled,
 }
 
 impl From<io::Error> for TrainingError {
@@ -113,6 +115,82 @@ impl TrainingStats {
     }
 }
 
+/// A piece of training text, split at context escapes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrainingText<'a> {
+    /// Text to learn from
+    Learn(&'a str),
+    /// Context for the text that follows, which is not itself learned
+    Context(&'a str),
+}
+
+/// Splits training text at the alphabet's context escapes
+///
+/// As in the original Dasher training files, text between two escape
+/// characters (`§` by default) sets the context for the text after it
+/// instead of being learned, and a doubled escape character stands for
+/// itself. A context may run over several lines.
+#[derive(Debug, Clone)]
+pub struct TrainingTextParser {
+    /// Context escape character, if the alphabet has one
+    escape: Option<char>,
+    /// Context read so far, while inside one
+    context: Option<String>,
+    /// Whether the last character was an escape
+    escape_pending: bool,
+}
+
+impl TrainingTextParser {
+    /// Create a parser for the given escape (empty for none)
+    pub fn new(escape: &str) -> Self {
+        Self {
+            escape: escape.chars().next(),
+            context: None,
+            escape_pending: false,
+        }
+    }
+
+    /// Split the next piece of `text`, passing each part to `emit` in order
+    pub fn parse<F: FnMut(TrainingText<'_>)>(&mut self, text: &str, mut emit: F) {
+        let Some(escape) = self.escape else {
+            if !text.is_empty() {
+                emit(TrainingText::Learn(text));
+            }
+            return;
+        };
+
+        let mut start = 0;
+        for (i, c) in text.char_indices() {
+            if let Some(context) = &mut self.context {
+                if c == escape {
+                    emit(TrainingText::Context(context));
+                    self.context = None;
+                    start = i + c.len_utf8();
+                } else {
+                    context.push(c);
+                }
+            } else if self.escape_pending {
+                self.escape_pending = false;
+                if c == escape {
+                    // A doubled escape is learned as a single one
+                    start = i;
+                } else {
+                    self.context = Some(c.to_string());
+                }
+            } else if c == escape {
+                if start < i {
+                    emit(TrainingText::Learn(&text[start..i]));
+                }
+                self.escape_pending = true;
+            }
+        }
+
+        if self.context.is_none() && !self.escape_pending && start < text.len() {
+            emit(TrainingText::Learn(&text[start..]));
+        }
+    }
+}
+
 /// Training data manager
 #[derive(Debug)]
 pub struct TrainingManager {
@@ -284,6 +362,62 @@ impl TrainingManager {
         self.save_training_data()
     }
 
+    /// Train from a stream of text, reporting progress
+    ///
+    /// The text is read a line at a time, split at the alphabet's context
+    /// escapes by [`TrainingTextParser`], and passed to `learn`, which should
+    /// feed [`TrainingText::Learn`] text to the language model and switch to
+    /// each [`TrainingText::Context`] without learning it. `progress` is called with the
+    /// percentage of the stream read whenever it changes, ending with 100;
+    /// returning `false` cancels training, leaving what has been learned so
+    /// far. Returns the statistics of the text read, which are also added
+    /// to this manager's statistics.
+    pub fn train_stream<R, L, P>(&mut self, mut reader: R, mut learn: L, mut progress: P) -> Result<TrainingStats, TrainingError>
+    where
+        R: BufRead + Seek,
+        L: FnMut(TrainingText<'_>),
+        P: FnMut(u8) -> bool,
+    {
+        let start = reader.stream_position()?;
+        let total_bytes = reader.seek(SeekFrom::End(0))?.saturating_sub(start);
+        reader.seek(SeekFrom::Start(start))?;
+
+        let mut stats = TrainingStats::new();
+        let mut bytes_read = 0u64;
+        let mut last_percent = None;
+        let mut line = String::new();
+        let mut parser = TrainingTextParser::new(&self.alphabet.context_escape_char);
+        loop {
+            line.clear();
+            let read = reader.read_line(&mut line)?;
+            if read == 0 {
+                break;
+            }
+            bytes_read += read as u64;
+
+            parser.parse(&line, |text| {
+                if let TrainingText::Learn(learned) = text {
+                    stats.update(learned);
+                    self.stats.update(learned);
+                }
+                learn(text);
+            });
+
+            let percent = (bytes_read * 100 / total_bytes.max(1)).min(100) as u8;
+            if last_percent != Some(percent) {
+                last_percent = Some(percent);
+                if !progress(percent) {
+                    return Err(TrainingError::Cancelled);
+                }
+            }
+        }
+
+        if last_percent != Some(100) && !progress(100) {
+            return Err(TrainingError::Cancelled);
+        }
+        Ok(stats)
+    }
+
     /// Get training statistics
     pub fn stats(&self) -> &TrainingStats {
         &self.stats
@@ -295,49 +429,70 @@ impl TrainingManager {
     }
 }
 
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
-//     use tempfile::NamedTempFile;
-//
-//     #[test]
-//     fn test_training_stats() {
-//         let mut stats = TrainingStats::new();
-//         stats.update("hello world");
-//
-//         assert_eq!(stats.total_chars, 11);
-//         assert_eq!(*stats.char_frequency.get(&'l').unwrap(), 3);
-//         assert_eq!(*stats.word_frequency.get("hello").unwrap(), 1);
-//         assert_eq!(*stats.bigram_frequency.get(&('l', 'l')).unwrap(), 1);
-//     }
-//
-//     #[test]
-//     fn test_training_manager() -> Result<(), TrainingError> {
-//         let alphabet = AlphabetInfo::new("test".to_string());
-//         let mut manager = TrainingManager::new(alphabet);
-//
-//         // Create temporary training file
-//         let temp_file = NamedTempFile::new().unwrap();
-//         manager.set_training_file(temp_file.path());
-//
-//         // Train on some text
-//         manager.train("hello world")?;
-//
-//         // Check statistics
-//         let stats = manager.stats();
-//         assert_eq!(stats.total_chars, 11);
-//         assert_eq!(*stats.char_frequency.get(&'l').unwrap(), 3);
-//
-//         // Load training data
-//         let mut new_manager = TrainingManager::new(AlphabetInfo::new("test".to_string()));
-//         new_manager.set_training_file(temp_file.path());
-//         new_manager.load_training_data()?;
-//
-//         // Check loaded statistics
-//         let new_stats = new_manager.stats();
-//         assert_eq!(new_stats.total_chars, 11);
-//         assert_eq!(*new_stats.char_frequency.get(&'l').unwrap(), 3);
-//
-//         Ok(())
-//     }
-// }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn learn_text(learned: &mut String, text: TrainingText<'_>) {
+        if let TrainingText::Learn(text) = text {
+            learned.push_str(text);
+        }
+    }
+
+    fn parse_all(lines: &[&str]) -> Vec<String> {
+        let mut parser = TrainingTextParser::new("§");
+        let mut parts = Vec::new();
+        for line in lines {
+            parser.parse(line, |text| parts.push(format!("{:?}", text)));
+        }
+        parts
+    }
+
+    #[test]
+    fn test_training_text_escapes() {
+        assert_eq!(parse_all(&["plain text\n"]), vec![r#"Learn("plain text\n")"#]);
+        assert_eq!(parse_all(&["§Dear §Sir, costs 5§§.\n"]), vec![
+            r#"Context("Dear ")"#,
+            r#"Learn("Sir, costs 5")"#,
+            r#"Learn("§.\n")"#,
+        ]);
+        // Contexts may span lines
+        assert_eq!(parse_all(&["one§two\n", "three§four"]), vec![
+            r#"Learn("one")"#,
+            r#"Context("two\nthree")"#,
+            r#"Learn("four")"#,
+        ]);
+        // Without an escape character everything is learned
+        let mut parser = TrainingTextParser::new("");
+        let mut parts = Vec::new();
+        parser.parse("a§b§c", |text| parts.push(format!("{:?}", text)));
+        assert_eq!(parts, vec![r#"Learn("a§b§c")"#]);
+    }
+
+    #[test]
+    fn test_train_stream_reports_progress() {
+        let text = "hello world\n".repeat(50);
+        let mut manager = TrainingManager::new(AlphabetInfo::new("test".to_string()));
+
+        let mut learned = String::new();
+        let mut reports = Vec::new();
+        let stats = manager.train_stream(Cursor::new(text.as_bytes()), |text| learn_text(&mut learned, text), |percent| {
+            reports.push(percent);
+            true
+        }).unwrap();
+
+        assert_eq!(learned, text);
+        assert_eq!(stats.total_chars, text.chars().count());
+        assert_eq!(*stats.word_frequency.get("hello").unwrap(), 50);
+        assert_eq!(manager.stats().total_chars, stats.total_chars);
+        assert!(reports.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(reports.last(), Some(&100));
+
+        // Cancelling stops reading
+        let mut learned = String::new();
+        let result = manager.train_stream(Cursor::new(text.as_bytes()), |text| learn_text(&mut learned, text), |percent| percent < 50);
+        assert!(matches!(result, Err(TrainingError::Cancelled)));
+        assert!(learned.len() < text.len());
+    }
+}
@@ -0,0 +1,204 @@
+//! Alphabet validation
+//!
+//! Checks a loaded alphabet for mistakes a user editing a custom alphabet
+//! is likely to make, so that frontends can say what to fix rather than
+//! failing later or quietly writing the wrong text.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use super::{AlphabetInfo, GroupInfo};
+
+/// How serious a problem found in an alphabet is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DiagnosticSeverity {
+    /// The alphabet works, but probably not as intended
+    Warning,
+    /// Part of the alphabet cannot be used as written
+    Error,
+}
+
+/// A problem found in an alphabet
+///
+/// Character indices count from 0, in the order the characters are listed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlphabetDiagnostic {
+    /// Two characters write the same text, so only the first can be reached
+    DuplicateSymbol {
+        /// Text the characters write
+        text: String,
+        /// Index of the first character writing it
+        first: usize,
+        /// Index of the later character writing it
+        duplicate: usize,
+    },
+    /// A character has no display text, so its node has no label
+    EmptyDisplay {
+        /// Index of the character
+        index: usize,
+    },
+    /// The alphabet names no training file, so its language model starts untrained
+    MissingTrainingFile,
+    /// The fixed probabilities of the characters add up to more than one,
+    /// or all characters have one and they do not add up to one
+    UnnormalizedProbabilities {
+        /// Sum of the fixed probabilities
+        total: f32,
+    },
+    /// A character's fixed probability is neither unset (-1) nor between 0 and 1
+    InvalidProbability {
+        /// Index of the character
+        index: usize,
+        /// The fixed probability
+        probability: f32,
+    },
+    /// A group holds no characters, or lies outside the characters of the
+    /// alphabet or of its parent group, so its nodes cannot be reached
+    UnreachableGroup {
+        /// Name of the group
+        name: String,
+    },
+}
+
+impl AlphabetDiagnostic {
+    /// Largest difference from one tolerated in a sum of fixed probabilities
+    const PROBABILITY_TOLERANCE: f32 = 1e-3;
+
+    /// Get how serious the problem is
+    pub fn severity(&self) -> DiagnosticSeverity {
+        match self {
+            AlphabetDiagnostic::DuplicateSymbol { .. }
+            | AlphabetDiagnostic::UnnormalizedProbabilities { .. }
+            | AlphabetDiagnostic::InvalidProbability { .. } => DiagnosticSeverity::Error,
+            AlphabetDiagnostic::EmptyDisplay { .. }
+            | AlphabetDiagnostic::MissingTrainingFile
+            | AlphabetDiagnostic::UnreachableGroup { .. } => DiagnosticSeverity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for AlphabetDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlphabetDiagnostic::DuplicateSymbol { text, first, duplicate } => {
+                write!(f, "Character {} writes {:?} like character {}, so it cannot be reached", duplicate, text, first)
+            }
+            AlphabetDiagnostic::EmptyDisplay { index } => {
+                write!(f, "Character {} has no display text", index)
+            }
+            AlphabetDiagnostic::MissingTrainingFile => {
+                write!(f, "No training file is given, so predictions start untrained")
+            }
+            AlphabetDiagnostic::UnnormalizedProbabilities { total } => {
+                write!(f, "Fixed probabilities add up to {} rather than at most 1", total)
+            }
+            AlphabetDiagnostic::InvalidProbability { index, probability } => {
+                write!(f, "Character {} has fixed probability {}, outside 0 to 1", index, probability)
+            }
+            AlphabetDiagnostic::UnreachableGroup { name } => {
+                write!(f, "Group '{}' holds no reachable characters", name)
+            }
+        }
+    }
+}
+
+impl AlphabetInfo {
+    /// Check the alphabet for problems, most serious first
+    ///
+    /// An empty list means no problems were found.
+    pub fn validate(&self) -> Vec<AlphabetDiagnostic> {
+        let mut diagnostics = Vec::new();
+
+        let mut first_writer: HashMap<&str, usize> = HashMap::new();
+        for (index, character) in self.characters.iter().enumerate() {
+            match first_writer.get(character.text.as_str()) {
+                Some(&first) => diagnostics.push(AlphabetDiagnostic::DuplicateSymbol {
+                    text: character.text.clone(),
+                    first,
+                    duplicate: index,
+                }),
+                None => {
+                    first_writer.insert(&character.text, index);
+                }
+            }
+            if character.display.is_empty() {
+                diagnostics.push(AlphabetDiagnostic::EmptyDisplay { index });
+            }
+        }
+
+        // A fixed probability of -1 means the language model decides
+        let fixed: Vec<_> = self.characters.iter().map(|character| character.fixed_probability).collect();
+        for (index, &probability) in fixed.iter().enumerate() {
+            if probability != -1.0 && !(0.0..=1.0).contains(&probability) {
+                diagnostics.push(AlphabetDiagnostic::InvalidProbability { index, probability });
+            }
+        }
+        let set: Vec<_> = fixed.iter().copied().filter(|probability| (0.0..=1.0).contains(probability)).collect();
+        let total: f32 = set.iter().sum();
+        let all_fixed = !fixed.is_empty() && set.len() == fixed.len();
+        if total > 1.0 + AlphabetDiagnostic::PROBABILITY_TOLERANCE
+            || (all_fixed && (total - 1.0).abs() > AlphabetDiagnostic::PROBABILITY_TOLERANCE)
+        {
+            diagnostics.push(AlphabetDiagnostic::UnnormalizedProbabilities { total });
+        }
+
+        check_groups(&self.groups, 0..self.characters.len(), &mut diagnostics);
+
+        if self.training_file.trim().is_empty() {
+            diagnostics.push(AlphabetDiagnostic::MissingTrainingFile);
+        }
+
+        diagnostics.sort_by_key(|diagnostic| std::cmp::Reverse(diagnostic.severity()));
+        diagnostics
+    }
+
+    /// Check whether the alphabet has no problems of error severity
+    pub fn is_valid(&self) -> bool {
+        self.validate().iter().all(|diagnostic| diagnostic.severity() < DiagnosticSeverity::Error)
+    }
+}
+
+/// Report groups that hold no characters within `range`, and check their children
+fn check_groups(groups: &[GroupInfo], range: std::ops::Range<usize>, diagnostics: &mut Vec<AlphabetDiagnostic>) {
+    for group in groups {
+        if group.start >= group.end || group.start < range.start || group.end > range.end {
+            diagnostics.push(AlphabetDiagnostic::UnreachableGroup { name: group.name.clone() });
+            continue;
+        }
+        check_groups(&group.children, group.start..group.end, diagnostics);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabet::parse_alphabet;
+
+    #[test]
+    fn test_validate_reports_problems() {
+        let info = parse_alphabet(r#"<alphabet name="Broken" trainingFilename="training.txt">
+            <group name="letters">
+                <node label="a" fixedProbability="0.75"><textCharAction /></node>
+                <node label="" text="b" fixedProbability="0.5"><textCharAction /></node>
+                <group name="empty"></group>
+            </group>
+            <node label="A" text="a"><textCharAction /></node>
+        </alphabet>"#).unwrap();
+
+        let diagnostics = info.validate();
+        assert_eq!(diagnostics, [
+            AlphabetDiagnostic::DuplicateSymbol { text: "a".to_string(), first: 0, duplicate: 2 },
+            AlphabetDiagnostic::UnnormalizedProbabilities { total: 1.25 },
+            AlphabetDiagnostic::EmptyDisplay { index: 1 },
+            AlphabetDiagnostic::UnreachableGroup { name: "empty".to_string() },
+        ]);
+        assert_eq!(diagnostics[0].to_string(), "Character 2 writes \"a\" like character 0, so it cannot be reached");
+        assert!(!info.is_valid());
+
+        let info = parse_alphabet(r#"<alphabet name="Fine">
+            <node label="a"><textCharAction /></node>
+        </alphabet>"#).unwrap();
+        assert_eq!(info.validate(), [AlphabetDiagnostic::MissingTrainingFile]);
+        assert!(info.is_valid());
+    }
+}
@@ -1,11 +1,14 @@
 use quick_xml::{Reader, Writer, events::{Event, BytesStart, BytesEnd, BytesDecl, BytesText}};
-use std::io::{self, BufReader, BufWriter, Write};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
 use std::fs::File;
 use std::path::Path;
 
 use super::{
     AlphabetInfo,
-    color_schemes::ColorScheme,
+    Palette,
+    Symbol,
+    ScreenOrientation,
+    color_schemes::{ColorScheme, GroupAppearance},
     Character,
     GroupInfo,
     Color,
@@ -24,6 +27,19 @@ pub enum AlphabetXmlError {
     InvalidFormat,
 }
 
+impl std::fmt::Display for AlphabetXmlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlphabetXmlError::Io(err) => write!(f, "IO error: {}", err),
+            AlphabetXmlError::Xml(err) => write!(f, "XML error: {}", err),
+            AlphabetXmlError::InvalidData(message) => write!(f, "Invalid data: {}", message),
+            AlphabetXmlError::InvalidFormat => write!(f, "Invalid format"),
+        }
+    }
+}
+
+impl std::error::Error for AlphabetXmlError {}
+
 impl From<io::Error> for AlphabetXmlError {
     fn from(err: io::Error) -> Self {
         AlphabetXmlError::Io(err)
@@ -49,11 +65,133 @@ impl From<std::num::ParseIntError> for AlphabetXmlError {
 }
 
 /// Alphabet XML reader
-pub struct AlphabetXmlReader {
-    reader: Reader<BufReader<File>>,
+pub struct AlphabetXmlReader<R: BufRead = BufReader<File>> {
+    reader: Reader<R>,
+}
+
+impl AlphabetXmlReader<BufReader<File>> {
+    /// Create a new alphabet XML reader
+    pub fn new<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let reader = Reader::from_reader(BufReader::new(file));
+        Ok(Self { reader })
+    }
+}
+
+impl<'a> AlphabetXmlReader<&'a [u8]> {
+    /// Create an alphabet XML reader over an in-memory document
+    pub fn from_str(xml: &'a str) -> Self {
+        Self { reader: Reader::from_reader(xml.as_bytes()) }
+    }
+}
+
+/// Get the unescaped value of an attribute, if present
+fn attribute(e: &BytesStart, name: &[u8]) -> Result<Option<String>, AlphabetXmlError> {
+    for attr in e.attributes() {
+        let attr = attr?;
+        if attr.key.as_ref() == name {
+            return Ok(Some(attr.unescape_value()?.into_owned()));
+        }
+    }
+    Ok(None)
 }
 
-impl AlphabetXmlReader {
+/// Get the value of a color attribute of an element, written as a hex color
+fn color_attribute(e: &BytesStart, name: &[u8]) -> Result<Option<Color>, AlphabetXmlError> {
+    attribute(e, name)?
+        .map(|hex| Color::from_hex(&hex).ok_or_else(|| AlphabetXmlError::InvalidData(format!("Invalid group color: {}", hex))))
+        .transpose()
+}
+
+/// Read a `<group name="punctuation" shape="circle" foreground="#000000" background="#00C800"/>` element
+pub(super) fn read_group_appearance(e: &BytesStart) -> Result<GroupAppearance, AlphabetXmlError> {
+    Ok(GroupAppearance {
+        name: attribute(e, b"name")?.ok_or_else(|| AlphabetXmlError::InvalidData("Missing group name".into()))?,
+        shape: attribute(e, b"shape")?,
+        foreground: color_attribute(e, b"foreground")?,
+        background: color_attribute(e, b"background")?,
+    })
+}
+
+/// Get the palette colour number of an element, given as `colour` or, in
+/// old-style alphabets, `b`
+fn color_number(e: &BytesStart) -> Result<Option<usize>, AlphabetXmlError> {
+    let number = match attribute(e, b"colour")? {
+        Some(number) => Some(number),
+        None => attribute(e, b"b")?,
+    };
+    number.map(|number| number.trim().parse()
+        .map_err(|_| AlphabetXmlError::InvalidData(format!("Invalid colour number: {}", number))))
+        .transpose()
+}
+
+/// Get the value of a colour channel attribute of a `<colour>` element
+fn channel_attribute(e: &BytesStart, name: &[u8]) -> Result<u8, AlphabetXmlError> {
+    attribute(e, name)?
+        .map(|value| value.trim().parse()
+            .map_err(|_| AlphabetXmlError::InvalidData(format!("Invalid colour channel: {}", value))))
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+/// Get the code an orientation is written as
+fn orientation_code(orientation: ScreenOrientation) -> &'static str {
+    match orientation {
+        ScreenOrientation::LeftToRight => "LR",
+        ScreenOrientation::RightToLeft => "RL",
+        ScreenOrientation::TopToBottom => "TB",
+        ScreenOrientation::BottomToTop => "BT",
+    }
+}
+
+/// Parse an orientation attribute, accepting both the Dasher codes and the names we write
+fn parse_orientation(value: &str) -> Option<ScreenOrientation> {
+    match value {
+        "LR" | "LeftToRight" => Some(ScreenOrientation::LeftToRight),
+        "RL" | "RightToLeft" => Some(ScreenOrientation::RightToLeft),
+        "TB" | "TopToBottom" => Some(ScreenOrientation::TopToBottom),
+        "BT" | "BottomToTop" => Some(ScreenOrientation::BottomToTop),
+        _ => None,
+    }
+}
+
+impl<R: BufRead> AlphabetXmlReader<R> {
+    /// Read upstream Dasher colour palettes, `<palette>` elements of
+    /// numbered `<colour r="255" g="255" b="255"/>` elements
+    pub fn read_palettes(&mut self) -> Result<Vec<Palette>, AlphabetXmlError> {
+        let mut buf = Vec::new();
+        let mut palettes = Vec::new();
+        let mut current = None;
+
+        loop {
+            match self.reader.read_event_into(&mut buf)? {
+                Event::Start(ref e) | Event::Empty(ref e) => match e.name().as_ref() {
+                    b"palette" => {
+                        let name = attribute(e, b"name")?.unwrap_or_default();
+                        current = Some(Palette::new(&name));
+                    }
+                    b"colour" => {
+                        if let Some(palette) = current.as_mut() {
+                            let color = Color::new(channel_attribute(e, b"r")?, channel_attribute(e, b"g")?, channel_attribute(e, b"b")?);
+                            palette.colors.push(color);
+                        }
+                    }
+                    _ => {}
+                },
+                Event::End(ref e) if e.name().as_ref() == b"palette" => {
+                    if let Some(palette) = current.take() {
+                        palettes.push(palette);
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+
+        Ok(palettes)
+    }
+
     /// Read color schemes from XML
     pub fn read_color_schemes(&mut self) -> Result<Vec<ColorScheme>, AlphabetXmlError> {
         let mut buf = Vec::new();
@@ -79,9 +217,19 @@ impl AlphabetXmlReader {
                         name if name == quick_xml::name::QName(b"pair") => {
                             current_pair = Some(());
                         }
+                        name if name == quick_xml::name::QName(b"group") => {
+                            if let Some(scheme) = current_scheme.as_mut() {
+                                scheme.groups.push(read_group_appearance(e)?);
+                            }
+                        }
                         _ => {}
                     }
                 }
+                Event::Empty(ref e) => {
+                    if let (b"group", Some(scheme)) = (e.name().as_ref(), current_scheme.as_mut()) {
+                        scheme.groups.push(read_group_appearance(e)?);
+                    }
+                }
                 Event::Text(e) => {
                     if let Some(scheme) = current_scheme.as_mut() {
                         if current_pair.is_some() {
@@ -123,73 +271,148 @@ scheme.add_color(bg.r, bg.g, bg.b, bg.a);
 
         Ok(schemes)
     }
-    /// Create a new alphabet XML reader
-    pub fn new<P: AsRef<Path>>(path: P) -> io::Result<Self> {
-        let file = File::open(path)?;
-        let reader = Reader::from_reader(BufReader::new(file));
-        Ok(Self { reader })
-    }
-
     /// Read an alphabet from XML
+    ///
+    /// Understands the upstream Dasher format (`<group>`/`<node>` elements with
+    /// `textCharAction`s) as well as the flat `<character>` elements we write.
     pub fn read(&mut self) -> Result<AlphabetInfo, AlphabetXmlError> {
         let mut buf = Vec::new();
-        let mut alphabet = None;
-        let mut current_group = None;
-        let mut current_character = None;
+        let mut alphabet: Option<AlphabetInfo> = None;
+        let mut groups: Vec<GroupInfo> = Vec::new();
+        let mut current_character: Option<Character> = None;
 
         loop {
-            match self.reader.read_event_into(&mut buf)? {
-                Event::Start(ref e) => {
+            let event = self.reader.read_event_into(&mut buf)?;
+            let is_empty = matches!(event, Event::Empty(_));
+            match event {
+                Event::Start(ref e) | Event::Empty(ref e) => {
                     match e.name().as_ref() {
                         b"alphabet" => {
-                            let attrs: Vec<_> = e.attributes().collect::<Result<_, _>>()?;
-                            let id = attrs.iter()
-                                .find(|attr| attr.key == quick_xml::name::QName(b"name"))
-                                .ok_or_else(|| AlphabetXmlError::InvalidData("Missing alphabet name".into()))?
-                                .value
-                                .as_ref();
-                            let id = String::from_utf8_lossy(id).into_owned();
-                            alphabet = Some(AlphabetInfo::new(id));
+                            let id = attribute(e, b"name")?
+                                .ok_or_else(|| AlphabetXmlError::InvalidData("Missing alphabet name".into()))?;
+                            let mut info = AlphabetInfo::new(id);
+                            if let Some(orientation) = attribute(e, b"orientation")?.as_deref().and_then(parse_orientation) {
+                                info.orientation = orientation;
+                            }
+                            if let Some(training_file) = attribute(e, b"trainingFilename")? {
+                                info.training_file = training_file;
+                            }
+                            if let Some(colors) = attribute(e, b"colorsName")? {
+                                info.preferred_colors = colors;
+                            }
+                            alphabet = Some(info);
                         }
-                        b"group" => {
-                            let attrs: Vec<_> = e.attributes().collect::<Result<_, _>>()?;
-                            let name = attrs.iter()
-                                .find(|attr| attr.key == quick_xml::name::QName(b"name"))
-                                .ok_or_else(|| AlphabetXmlError::InvalidData("Missing group name".into()))?
-                                .value
-                                .as_ref();
-                            let name = String::from_utf8_lossy(name).into_owned();
-                            current_group = Some(GroupInfo::new(name));
+                        b"context" => {
+                            if let Some(info) = alphabet.as_mut() {
+                                if let Some(default) = attribute(e, b"default")? {
+                                    info.default_context = default;
+                                }
+                                if let Some(escape) = attribute(e, b"escape")? {
+                                    info.context_escape_char = escape;
+                                }
+                            }
+                        }
+                        b"group" if !is_empty => {
+                            let name = match attribute(e, b"name")? {
+                                Some(name) => name,
+                                None => attribute(e, b"label")?.unwrap_or_default(),
+                            };
+                            let mut group = GroupInfo::new(name);
+                            group.color_group = attribute(e, b"colorInfoName")?.unwrap_or_default();
+                            group.foreground_color = color_attribute(e, b"foreground")?;
+                            group.background_color = color_attribute(e, b"background")?;
+                            group.visible = attribute(e, b"visible")?.is_none_or(|visible| visible != "no");
+                            // A toggle group is offered behind a node with its label
+                            if attribute(e, b"toggle")?.is_some_and(|toggle| matches!(toggle.as_str(), "yes" | "true")) {
+                                group.toggle_label = Some(attribute(e, b"label")?.unwrap_or_else(|| group.name.clone()));
+                            }
+                            group.start = alphabet.as_ref().map_or(0, |a| a.characters.len());
+                            groups.push(group);
+                        }
+                        b"node" | b"character" => {
+                            let display = match attribute(e, b"label")? {
+                                Some(label) => label,
+                                None => attribute(e, b"display")?.unwrap_or_default(),
+                            };
+                            let text = attribute(e, b"text")?.unwrap_or_else(|| display.clone());
+                            let mut character = Character { display, text, ..Default::default() };
+                            let fixed_probability = match attribute(e, b"fixedProbability")? {
+                                Some(p) => Some(p),
+                                None => attribute(e, b"p")?,
+                            };
+                            if let Some(p) = fixed_probability {
+                                character.fixed_probability = p.trim().parse()
+                                    .map_err(|_| AlphabetXmlError::InvalidData(format!("Invalid fixed probability: {}", p)))?;
+                            }
+                            if let Some(speed) = attribute(e, b"speedFactor")? {
+                                character.speed_factor = speed.trim().parse()
+                                    .map_err(|_| AlphabetXmlError::InvalidData(format!("Invalid speed factor: {}", speed)))?;
+                            }
+                            character.color = color_number(e)?;
+
+                            if is_empty {
+                                if let Some(alphabet) = alphabet.as_mut() {
+                                    alphabet.characters.push(character);
+                                }
+                            } else {
+                                current_character = Some(character);
+                            }
+                        }
+                        b"paragraph" => {
+                            // Old-style alphabets declare the paragraph symbol on its own
+                            let display = match attribute(e, b"d")? {
+                                Some(display) => display,
+                                None => attribute(e, b"label")?.unwrap_or_else(|| Symbol::PARAGRAPH_DISPLAY.to_string()),
+                            };
+                            let color = color_number(e)?;
+                            let character = Character { display, text: Symbol::PARAGRAPH.to_string(), color, ..Default::default() };
+                            if let Some(alphabet) = alphabet.as_mut() {
+                                alphabet.characters.push(character);
+                            }
                         }
-                        name if name == b"character" => {
-                            current_character = Some(Character::default());
+                        b"textCharAction" => {
+                            if let (Some(character), Some(unicode)) = (current_character.as_mut(), attribute(e, b"unicode")?) {
+                                let ch = unicode.trim().parse::<u32>().ok().and_then(char::from_u32)
+                                    .ok_or_else(|| AlphabetXmlError::InvalidData(format!("Invalid unicode value: {}", unicode)))?;
+                                character.text = ch.to_string();
+                            } else if let Some(character) = current_character.as_mut() {
+                                // The paragraph node of upstream alphabets enters a new line, not its label
+                                if character.display == Symbol::PARAGRAPH_DISPLAY && character.text == character.display {
+                                    character.text = Symbol::PARAGRAPH.to_string();
+                                }
+                            }
                         }
                         _ => {}
                     }
                 }
                 Event::Text(e) => {
                     if let Some(character) = current_character.as_mut() {
-                        character.text = e.unescape()?.to_string();
+                        let text = e.unescape()?;
+                        if character.text.is_empty() && !text.trim().is_empty() {
+                            character.text = text.trim().to_string();
+                        }
                     }
                 }
-                Event::End(e) => {
-                    let _name = e.name().as_ref();
-                    match e.name() {
-                        name if name == quick_xml::name::QName(b"group") => {
-                            if let (Some(_group), Some(_alphabet)) = (current_group.take(), alphabet.as_mut()) {
-                                // Flatten all descendants that are Character, not GroupInfo
-for _child in _group.children.iter() {
-    // If GroupInfo has a method to extract Characters, use it. Otherwise, skip.
-    // This is a placeholder for correct logic:
-    // alphabet.characters.extend(child.extract_characters());
-}
-// If group.children is Vec<Character>, just extend directly:
-// alphabet.characters.extend(group.children.clone());
+                Event::End(ref e) => {
+                    match e.name().as_ref() {
+                        b"group" => {
+                            if let (Some(mut group), Some(alphabet)) = (groups.pop(), alphabet.as_mut()) {
+                                group.end = alphabet.characters.len();
+                                // Inner groups claim their characters first
+                                for character in &mut alphabet.characters[group.start..group.end] {
+                                    if character.parent_group.is_none() {
+                                        character.parent_group = Some(group.clone());
+                                    }
+                                }
+                                match groups.last_mut() {
+                                    Some(parent) => parent.children.push(group),
+                                    None => alphabet.groups.push(group),
+                                }
                             }
                         }
-                        name if name.as_ref() == b"character" => {
-                            if let (Some(_character), Some(_group)) = (current_character.take(), current_group.as_mut()) {
-                                // group.children.push(character); // This is invalid if group.children is Vec<GroupInfo>
+                        b"node" | b"character" => {
+                            if let (Some(character), Some(alphabet)) = (current_character.take(), alphabet.as_mut()) {
+                                alphabet.characters.push(character);
                             }
                         }
                         _ => {}
@@ -198,6 +421,7 @@ for _child in _group.children.iter() {
                 Event::Eof => break,
                 _ => {}
             }
+            buf.clear();
         }
 
         alphabet.ok_or_else(|| AlphabetXmlError::InvalidData("No alphabet found".into()))
@@ -250,6 +474,22 @@ impl<W: Write> AlphabetXmlWriter<W> {
                 self.writer.write_event(Event::End(BytesEnd::new("pair")))?;
             }
 
+            // Write the appearance of each group
+            for group in &scheme.groups {
+                let mut group_elem = BytesStart::new("group");
+                group_elem.push_attribute(("name", group.name.as_str()));
+                if let Some(shape) = &group.shape {
+                    group_elem.push_attribute(("shape", shape.as_str()));
+                }
+                if let Some(foreground) = group.foreground {
+                    group_elem.push_attribute(("foreground", foreground.to_hex().as_str()));
+                }
+                if let Some(background) = group.background {
+                    group_elem.push_attribute(("background", background.to_hex().as_str()));
+                }
+                self.writer.write_event(Event::Empty(group_elem))?;
+            }
+
             self.writer.write_event(Event::End(BytesEnd::new("scheme")))?;
         }
 
@@ -278,6 +518,9 @@ impl<W: Write> AlphabetXmlWriter<W> {
     }
 
     /// Write an alphabet to XML
+    ///
+    /// The alphabet is written in the upstream Dasher format, with its
+    /// groups nested as they were read, so that it reads back the same.
     pub fn write(&mut self, alphabet: &AlphabetInfo) -> Result<(), AlphabetXmlError> {
         // Write XML declaration
         let decl = BytesDecl::new("1.0", Some("UTF-8"), None);
@@ -286,30 +529,110 @@ impl<W: Write> AlphabetXmlWriter<W> {
         // Write alphabet element
         let mut alphabet_elem = BytesStart::new("alphabet");
         alphabet_elem.push_attribute(("name", alphabet.id.as_str()));
-        self.writer.write_event(Event::Start(alphabet_elem.clone()))?;
-
-        // Write settings
-        self.write_element("training", &alphabet.training_file)?;
-        self.write_element("orientation", format!("{:?}", alphabet.orientation))?;
-        self.write_element("encoding", "UTF-8")?;
-
-        // Write characters
-        for character in &alphabet.characters {
-            let mut char_elem = BytesStart::new("character");
-            char_elem.push_attribute(("text", character.text.as_str()));
-            char_elem.push_attribute(("display", character.display.as_str()));
-            if character.fixed_probability >= 0.0 {
-                char_elem.push_attribute(("p", character.fixed_probability.to_string().as_str()));
-            }
-            self.writer.write_event(Event::Start(char_elem.clone()))?;
-            self.writer.write_event(Event::End(BytesEnd::new("character")))?;
+        alphabet_elem.push_attribute(("orientation", orientation_code(alphabet.orientation)));
+        if !alphabet.training_file.is_empty() {
+            alphabet_elem.push_attribute(("trainingFilename", alphabet.training_file.as_str()));
         }
+        if !alphabet.preferred_colors.is_empty() {
+            alphabet_elem.push_attribute(("colorsName", alphabet.preferred_colors.as_str()));
+        }
+        self.writer.write_event(Event::Start(alphabet_elem))?;
+
+        let mut context_elem = BytesStart::new("context");
+        context_elem.push_attribute(("default", alphabet.default_context.as_str()));
+        context_elem.push_attribute(("escape", alphabet.context_escape_char.as_str()));
+        self.writer.write_event(Event::Empty(context_elem))?;
+
+        // Write characters, inside their groups
+        self.write_characters(&alphabet.characters, 0..alphabet.characters.len(), &alphabet.groups)?;
 
         // Close alphabet element
         self.writer.write_event(Event::End(BytesEnd::new("alphabet")))?;
         Ok(())
     }
 
+    /// Write the characters in `range`, with those in `groups` inside their groups
+    fn write_characters(&mut self, characters: &[Character], range: std::ops::Range<usize>, groups: &[GroupInfo]) -> Result<(), AlphabetXmlError> {
+        let mut groups: Vec<_> = groups.iter().collect();
+        groups.sort_by_key(|group| group.start);
+
+        let mut index = range.start;
+        for group in groups {
+            let start = group.start.clamp(index, range.end);
+            let end = group.end.clamp(start, range.end);
+            for character in &characters[index..start] {
+                self.write_character(character)?;
+            }
+            self.write_group(characters, group, start..end)?;
+            index = end;
+        }
+        for character in &characters[index..range.end] {
+            self.write_character(character)?;
+        }
+        Ok(())
+    }
+
+    /// Write a group holding the characters in `range`
+    fn write_group(&mut self, characters: &[Character], group: &GroupInfo, range: std::ops::Range<usize>) -> Result<(), AlphabetXmlError> {
+        let mut group_elem = BytesStart::new("group");
+        group_elem.push_attribute(("name", group.name.as_str()));
+        if !group.color_group.is_empty() {
+            group_elem.push_attribute(("colorInfoName", group.color_group.as_str()));
+        }
+        if let Some(foreground) = group.foreground_color {
+            group_elem.push_attribute(("foreground", foreground.to_hex().as_str()));
+        }
+        if let Some(background) = group.background_color {
+            group_elem.push_attribute(("background", background.to_hex().as_str()));
+        }
+        if !group.visible {
+            group_elem.push_attribute(("visible", "no"));
+        }
+        if let Some(label) = &group.toggle_label {
+            group_elem.push_attribute(("toggle", "yes"));
+            group_elem.push_attribute(("label", label.as_str()));
+        }
+        self.writer.write_event(Event::Start(group_elem))?;
+        self.write_characters(characters, range, &group.children)?;
+        self.writer.write_event(Event::End(BytesEnd::new("group")))?;
+        Ok(())
+    }
+
+    /// Write a character as a node with a text action
+    ///
+    /// A single character is written as its code, so that spaces and new
+    /// lines survive.
+    fn write_character(&mut self, character: &Character) -> Result<(), AlphabetXmlError> {
+        let mut node_elem = BytesStart::new("node");
+        node_elem.push_attribute(("label", character.display.as_str()));
+        let mut chars = character.text.chars();
+        let single = match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        };
+        if single.is_none() {
+            node_elem.push_attribute(("text", character.text.as_str()));
+        }
+        if character.fixed_probability >= 0.0 {
+            node_elem.push_attribute(("fixedProbability", character.fixed_probability.to_string().as_str()));
+        }
+        if character.speed_factor >= 0.0 {
+            node_elem.push_attribute(("speedFactor", character.speed_factor.to_string().as_str()));
+        }
+        if let Some(color) = character.color {
+            node_elem.push_attribute(("colour", color.to_string().as_str()));
+        }
+        self.writer.write_event(Event::Start(node_elem))?;
+
+        let mut action_elem = BytesStart::new("textCharAction");
+        if let Some(c) = single {
+            action_elem.push_attribute(("unicode", (c as u32).to_string().as_str()));
+        }
+        self.writer.write_event(Event::Empty(action_elem))?;
+        self.writer.write_event(Event::End(BytesEnd::new("node")))?;
+        Ok(())
+    }
+
     /// Write a setting element
     #[allow(dead_code)]
     fn write_setting(&mut self, name: &str, value: &str) -> Result<(), AlphabetXmlError> {
@@ -350,54 +673,151 @@ pub fn load_alphabet<P: AsRef<Path>>(path: P) -> Result<AlphabetInfo, AlphabetXm
     reader.read()
 }
 
+/// Load upstream Dasher colour palettes from a `colour.*.xml` file
+pub fn load_palettes<P: AsRef<Path>>(path: P) -> Result<Vec<Palette>, AlphabetXmlError> {
+    let mut reader = AlphabetXmlReader::new(path)?;
+    reader.read_palettes()
+}
+
+/// Parse upstream Dasher colour palettes from an XML document held in memory
+pub fn parse_palettes(xml: &str) -> Result<Vec<Palette>, AlphabetXmlError> {
+    AlphabetXmlReader::from_str(xml).read_palettes()
+}
+
+/// Parse an alphabet from an XML document held in memory
+pub fn parse_alphabet(xml: &str) -> Result<AlphabetInfo, AlphabetXmlError> {
+    AlphabetXmlReader::from_str(xml).read()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::alphabet::{Alphabet, ToggleGroup};
     use std::io::Cursor;
 
+    #[test]
+    fn test_read_write_group_appearances() {
+        let xml = r##"<colorschemes><scheme name="Groups">
+            <group name="punctuation" shape="circle" background="#00C800"/>
+            <group name="digits" foreground="#FFFFFF"></group>
+        </scheme></colorschemes>"##;
+        let schemes = AlphabetXmlReader::from_str(xml).read_color_schemes().unwrap();
+        assert_eq!(schemes[0].groups, vec![
+            GroupAppearance {
+                name: "punctuation".to_string(),
+                shape: Some("circle".to_string()),
+                foreground: None,
+                background: Some(Color::new(0, 200, 0)),
+            },
+            GroupAppearance {
+                name: "digits".to_string(),
+                shape: None,
+                foreground: Some(Color::new(255, 255, 255)),
+                background: None,
+            },
+        ]);
+
+        let mut buffer = Vec::new();
+        AlphabetXmlWriter::new(Cursor::new(&mut buffer)).write_color_schemes(&schemes).unwrap();
+        let written = String::from_utf8(buffer).unwrap();
+        let reread = AlphabetXmlReader::from_str(&written).read_color_schemes().unwrap();
+        assert_eq!(reread[0].groups, schemes[0].groups);
+
+        let bad = r#"<scheme name="Bad"><group name="letters" background="green"/></scheme>"#;
+        assert!(AlphabetXmlReader::from_str(bad).read_color_schemes().is_err());
+    }
+
     #[test]
     fn test_write_read_alphabet() {
-        // Create a test alphabet
+        // Letters in a group nested inside another, then space and paragraph
         let mut alphabet = AlphabetInfo::new("test".to_string());
         alphabet.training_file = "training.txt".to_string();
-        use crate::alphabet::ScreenOrientation;
-        alphabet.orientation = ScreenOrientation::LeftToRight;
-        alphabet.characters.push(Character {
-            display: "a".to_string(),
-            text: "a".to_string(),
-            fixed_probability: 0.1,
-            ..Default::default()
-        });
-
-        // Write to memory buffer
-        let mut buffer = Vec::new();
-        {
-            let mut writer = AlphabetXmlWriter::new(Cursor::new(&mut buffer));
-            writer.write(&alphabet).unwrap();
+        alphabet.preferred_colors = "Default".to_string();
+        alphabet.orientation = ScreenOrientation::TopToBottom;
+        alphabet.default_context = ". ".to_string();
+        for (display, text) in [("a", "a"), ("b", "b"), ("ch", "ch"), ("_", " "), ("¶", "\n")] {
+            alphabet.characters.push(Character { display: display.to_string(), text: text.to_string(), ..Default::default() });
         }
+        alphabet.characters[0].fixed_probability = 0.1;
+        alphabet.characters[1].speed_factor = 2.0;
+
+        let mut vowels = GroupInfo::new("vowels".to_string());
+        vowels.start = 0;
+        vowels.end = 1;
+        vowels.background_color = Some(Color::new(255, 0, 0));
+        vowels.visible = false;
+        let mut letters = GroupInfo::new("letters".to_string());
+        letters.color_group = "lowercase".to_string();
+        letters.start = 0;
+        letters.end = 3;
+        letters.children.push(vowels);
+        let mut spaces = GroupInfo::new("paragraphSpace".to_string());
+        spaces.start = 3;
+        spaces.end = 5;
+        spaces.toggle_label = Some("_¶".to_string());
+        alphabet.groups = vec![letters, spaces];
 
-        // Read back
-        let mut reader = Reader::from_reader(Cursor::new(&buffer));
-        let mut buf = Vec::new();
-        let mut found_alphabet = false;
-
-        loop {
-            match reader.read_event_into(&mut buf).unwrap() {
-                Event::Start(ref e) if e.name() == quick_xml::name::QName(b"alphabet") => {
-                    found_alphabet = true;
-                    let attrs: Vec<_> = e.attributes().collect::<Result<_, _>>().unwrap();
-                    let name = attrs.iter()
-                        .find(|attr| attr.key == quick_xml::name::QName(b"name"))
-                        .unwrap()
-                        .value
-                        .as_ref();
-                    assert_eq!(String::from_utf8_lossy(name), "test");
-                }
-                Event::Eof => break,
-                _ => {}
-            }
-        }
+        let mut buffer = Vec::new();
+        AlphabetXmlWriter::new(Cursor::new(&mut buffer)).write(&alphabet).unwrap();
+        let xml = String::from_utf8(buffer).unwrap();
+        let read = parse_alphabet(&xml).unwrap();
+
+        assert_eq!(read.id, "test");
+        assert_eq!(read.training_file, "training.txt");
+        assert_eq!(read.preferred_colors, "Default");
+        assert_eq!(read.orientation, ScreenOrientation::TopToBottom);
+        assert_eq!(read.default_context, ". ");
+        let characters: Vec<_> = read.characters.iter().map(|c| (c.display.as_str(), c.text.as_str())).collect();
+        assert_eq!(characters, [("a", "a"), ("b", "b"), ("ch", "ch"), ("_", " "), ("¶", "\n")]);
+        assert_eq!(read.characters[0].fixed_probability, 0.1);
+        assert_eq!(read.characters[1].speed_factor, 2.0);
+
+        assert_eq!(read.groups.len(), 2);
+        assert_eq!((read.groups[0].name.as_str(), read.groups[0].start, read.groups[0].end), ("letters", 0, 3));
+        assert_eq!(read.groups[0].color_group, "lowercase");
+        let vowels = &read.groups[0].children[0];
+        assert_eq!((vowels.name.as_str(), vowels.start, vowels.end), ("vowels", 0, 1));
+        assert_eq!(vowels.background_color, Some(Color::new(255, 0, 0)));
+        assert!(!vowels.visible);
+        assert_eq!(read.groups[1].toggle_label.as_deref(), Some("_¶"));
+        assert_eq!(read.characters[0].parent_group.as_ref().unwrap().name, "vowels");
+        assert_eq!(read.characters[2].parent_group.as_ref().unwrap().name, "letters");
+    }
 
-        assert!(found_alphabet);
+    #[test]
+    fn test_parse_dasher_alphabet() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<alphabet name="Tiny" orientation="RL" trainingFilename="training_tiny.txt" colorsName="Default">
+    <group name="Letters" colorInfoName="lowercase">
+        <node label="a"><textCharAction /></node>
+        <node label="b" fixedProbability="0.25"><textCharAction /></node>
+    </group>
+    <node label="_"><textCharAction unicode="32"/></node>
+    <group name="paragraphSpace" colorInfoName="paragraphSpace">
+        <node label="¶"><textCharAction /></node>
+    </group>
+    <group name="digits" toggle="yes" label="12">
+        <node label="1"><textCharAction /></node>
+        <node label="2"><textCharAction /></node>
+    </group>
+</alphabet>"#;
+
+        let info = parse_alphabet(xml).unwrap();
+        assert_eq!(info.id, "Tiny");
+        assert_eq!(info.orientation, ScreenOrientation::RightToLeft);
+        assert_eq!(info.training_file, "training_tiny.txt");
+        assert_eq!(info.characters.len(), 6);
+        assert_eq!(info.characters[1].fixed_probability, 0.25);
+        assert_eq!(info.characters[2].display, "_");
+        assert_eq!(info.characters[2].text, " ");
+        assert_eq!(info.characters[3].display, "¶");
+        assert_eq!(info.characters[3].text, "\n");
+
+        let alphabet = Alphabet::from_info(info.clone());
+        assert_eq!(alphabet.toggle_groups(), [ToggleGroup::new("12", vec!['1', '2'])]);
+        assert_eq!(alphabet.fixed_probability('b'), Some(0.25));
+        assert_eq!(alphabet.fixed_probability('a'), None);
+        assert_eq!(info.get_color_group(1), Some("lowercase"));
+        assert_eq!(info.get_color_group(3), None);
     }
 }
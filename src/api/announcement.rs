@@ -0,0 +1,106 @@
+//! Accessibility announcements
+//!
+//! Screen readers can't see what Dasher draws, so the interface describes
+//! each change to the text in words a screen-reader frontend can speak:
+//! "typed letter h", "word 'hello' completed", "deleted letter o".
+
+use std::fmt;
+
+/// Host callback receiving announcements
+pub type AnnouncementCallback = Box<dyn Fn(&Announcement)>;
+
+/// A change to the text, described for a screen reader
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Announcement {
+    /// A character was written
+    Typed(char),
+
+    /// A word was ended by the character written after it
+    WordCompleted(String),
+
+    /// Text was deleted
+    Deleted(String),
+}
+
+/// Get a spoken name for a character
+fn describe(c: char) -> String {
+    match c {
+        ' ' => "space".to_string(),
+        '\n' => "new line".to_string(),
+        '\t' => "tab".to_string(),
+        c if c.is_alphabetic() => format!("letter {}", c),
+        c if c.is_numeric() => format!("digit {}", c),
+        c => format!("symbol {}", c),
+    }
+}
+
+impl fmt::Display for Announcement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Announcement::Typed(c) => write!(f, "typed {}", describe(*c)),
+            Announcement::WordCompleted(word) => write!(f, "word '{}' completed", word),
+            Announcement::Deleted(text) => {
+                let mut chars = text.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => write!(f, "deleted {}", describe(c)),
+                    _ => write!(f, "deleted {} characters", text.chars().count()),
+                }
+            }
+        }
+    }
+}
+
+/// Describe how the text changed from `before` to `after`
+///
+/// The text both have in common at the start and end is left out, so an
+/// edit at the cursor is described the same wherever the cursor is.
+pub(crate) fn announcements(before: &str, after: &str) -> Vec<Announcement> {
+    let before: Vec<char> = before.chars().collect();
+    let after: Vec<char> = after.chars().collect();
+    let prefix = before.iter().zip(&after).take_while(|(a, b)| a == b).count();
+    let suffix = before[prefix..].iter().rev().zip(after[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+
+    let mut announcements = Vec::new();
+    let deleted = &before[prefix..before.len() - suffix];
+    if !deleted.is_empty() {
+        announcements.push(Announcement::Deleted(deleted.iter().collect()));
+    }
+
+    for index in prefix..after.len() - suffix {
+        let c = after[index];
+        announcements.push(Announcement::Typed(c));
+
+        // A character that can't be part of a word ends the word before it
+        if !c.is_alphanumeric() && c != '\'' {
+            let start = after[..index].iter().rposition(|c| !c.is_alphanumeric() && *c != '\'').map_or(0, |i| i + 1);
+            if start < index {
+                announcements.push(Announcement::WordCompleted(after[start..index].iter().collect()));
+            }
+        }
+    }
+    announcements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describes_text_changes() {
+        let messages = |before: &str, after: &str| -> Vec<String> {
+            announcements(before, after).iter().map(|a| a.to_string()).collect()
+        };
+
+        assert_eq!(messages("", "h"), ["typed letter h"]);
+        assert_eq!(messages("hello", "hello "), ["typed space", "word 'hello' completed"]);
+        assert_eq!(messages("it's", "it's."), ["typed symbol .", "word 'it's' completed"]);
+        assert_eq!(messages("a ", "a  "), ["typed space"]);
+        assert_eq!(messages("hello", "hell"), ["deleted letter o"]);
+        assert_eq!(messages("hello world", "hello "), ["deleted 5 characters"]);
+        assert!(messages("same", "same").is_empty());
+
+        // Edits in the middle of the text are found too
+        assert_eq!(messages("ab cd", "ab3 cd"), ["typed digit 3"]);
+        assert_eq!(messages("ab cd", "ax cd"), ["deleted letter b", "typed letter x"]);
+    }
+}
@@ -4,12 +4,111 @@
 
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::collections::HashMap;
 
-use crate::model::{DasherModel, node::DasherNode};
-use crate::view::{DasherScreen, DasherView, DasherViewSquare, Orientation, NodeShape};
-use crate::input::{DasherInput, InputFilter, InputManager, VirtualKey};
+use crate::model::{ArpaLanguageModel, CacheStats, CombinedLanguageModel, DasherModel, ExpansionPolicy, LanguageLayer, LanguageProfile, LearningMode, PPMOrder, UserCorpus, node::DasherNode};
+#[cfg(feature = "candle")]
+use crate::model::{CandleCharModel, NeuralLanguageModel, NeuralPredictor};
+use crate::view::{DasherScreen, DasherView, DasherViewSquare, DebugInfo, FontDescriptor, Orientation, NodeShape, Scene, VisibleNode};
+use crate::input::{ButtonDwell, ButtonRegion, Calibration, DasherInput, FrameRate, InputEvent, InputFilter, InputFilterRegistry, InputManager, InputRecorder, InputRecording, InputReplay, JoystickInput, OneDimensionalFilter, PressureCalibration, PressureInput, RecordedEvent, HeadPointerConfig, SmoothingMethod, TouchGesture, VirtualKey};
 use crate::settings::{Settings, Parameter};
-use crate::Result;
+use crate::alphabet::{Alphabet, AlphabetDiscovery, AlphabetInfo, ConversionProvider, TrainingError, TrainingStats};
+use crate::action::{ClipboardProvider, CopyAction, CutAction, SpeakAction, SpeechProvider};
+use crate::logging::dasher_log;
+use crate::stats::{SessionReport, StatsFormat, UserStats};
+use crate::{DasherError, Result};
+
+mod announcement;
+
+pub use announcement::{Announcement, AnnouncementCallback};
+
+/// Host callback receiving the cursor position (in characters) whenever Dasher moves it
+pub type CursorCallback = Box<dyn Fn(usize)>;
+
+/// An alphabet to switch to with [`DasherInterface::set_alphabet`]
+#[derive(Debug, Clone)]
+pub enum AlphabetSelection {
+    /// The id of an alphabet file to find in the alphabet search paths
+    Id(String),
+    /// An alphabet that has already been loaded
+    Info(Box<AlphabetInfo>),
+}
+
+impl From<&str> for AlphabetSelection {
+    fn from(id: &str) -> Self {
+        AlphabetSelection::Id(id.to_string())
+    }
+}
+
+impl From<String> for AlphabetSelection {
+    fn from(id: String) -> Self {
+        AlphabetSelection::Id(id)
+    }
+}
+
+impl From<AlphabetInfo> for AlphabetSelection {
+    fn from(info: AlphabetInfo) -> Self {
+        AlphabetSelection::Info(Box::new(info))
+    }
+}
+
+/// A node found under a point on the screen by [`DasherInterface::node_at_screen_point`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeHit {
+    /// Symbol the node writes, if any
+    pub symbol: Option<char>,
+    /// Text the node is labelled with
+    pub label: String,
+    /// Dasher coordinates (y1, y2) the node is drawn at
+    pub bounds: (i64, i64),
+    /// Screen rectangle (x1, y1, x2, y2) the node's box covers
+    pub screen_rect: (i32, i32, i32, i32),
+}
+
+/// Running totals behind the counters reported for each frame written in
+///
+/// Each frame emits a `tracing` event on the `dasher_core::frame` target with
+/// the frame's share of each total, as `monotonic_counter` fields that
+/// metrics layers add up:
+///
+/// - `monotonic_counter.nodes_expanded`: nodes given children
+/// - `monotonic_counter.nodes_collapsed`: nodes whose children were pruned
+/// - `monotonic_counter.probability_cache_hits`: probability lookups answered from the cache
+/// - `monotonic_counter.probability_cache_misses`: probability lookups that had to be computed
+/// - `monotonic_counter.draw_calls`: drawing calls made on the screen
+#[cfg(feature = "tracing")]
+struct FrameCounters {
+    nodes_expanded: u64,
+    nodes_collapsed: u64,
+    cache_hits: u64,
+    cache_misses: u64,
+    draw_calls: u64,
+}
+
+#[cfg(feature = "tracing")]
+impl FrameCounters {
+    /// Read the totals from `model`, with `draw_calls` made for the frame
+    fn read(model: &DasherModel, draw_calls: u64) -> Self {
+        let (nodes_expanded, nodes_collapsed) = model.node_counters();
+        let cache = model.language_model_cache_stats().unwrap_or_default();
+        Self { nodes_expanded, nodes_collapsed, cache_hits: cache.hits, cache_misses: cache.misses, draw_calls }
+    }
+
+    /// Emit the counts since `before`, read at the start of the frame
+    fn trace_since(&self, before: &Self) {
+        // Cache statistics can be reset mid-session, so the totals may go down
+        tracing::event!(
+            target: "dasher_core::frame",
+            tracing::Level::DEBUG,
+            monotonic_counter.nodes_expanded = self.nodes_expanded.saturating_sub(before.nodes_expanded),
+            monotonic_counter.nodes_collapsed = self.nodes_collapsed.saturating_sub(before.nodes_collapsed),
+            monotonic_counter.probability_cache_hits = self.cache_hits.saturating_sub(before.cache_hits),
+            monotonic_counter.probability_cache_misses = self.cache_misses.saturating_sub(before.cache_misses),
+            monotonic_counter.draw_calls = self.draw_calls,
+            "frame counters"
+        );
+    }
+}
 
 /// The main interface for the Dasher core.
 ///
@@ -25,6 +124,9 @@ pub struct DasherInterface {
     /// The input manager
     input_manager: InputManager,
 
+    /// Input filters that can be chosen by name
+    input_filters: InputFilterRegistry,
+
     /// The settings
     settings: Settings,
 
@@ -36,26 +138,95 @@ pub struct DasherInterface {
 
     /// The current frame time
     current_time: u64,
+
+    /// Time of the previous frame, if there has been one
+    last_frame_time: Option<u64>,
+
+    /// Whether a zoom asked for with [`Self::zoom_to`] is being taken
+    zooming: bool,
+
+    /// Statistics for the current session
+    stats: UserStats,
+
+    /// Recorder for input sessions
+    recorder: InputRecorder,
+
+    /// Limits on how many nodes are expanded and collapsed each frame
+    expansion_policy: ExpansionPolicy,
+
+    /// Measured rate at which the host calls `new_frame`
+    frame_rate: FrameRate,
+
+    /// Host callback receiving descriptions of changes to the text
+    announcement_callback: Option<AnnouncementCallback>,
+
+    /// Text as of the last announcement
+    announced_text: String,
+
+    /// Host callback receiving the cursor position whenever Dasher moves it
+    cursor_callback: Option<CursorCallback>,
+
+    /// Cursor position as last reported to the host
+    reported_cursor: usize,
+
+    /// Direction the current alphabet is written in, used unless the settings override it
+    alphabet_orientation: Orientation,
 }
 
 impl DasherInterface {
     /// Create a new Dasher interface
     pub fn new(settings: Settings) -> Self {
         let mut model = DasherModel::new();
+        let min_child_probability = settings.get_long(Parameter::MinChildProbability).unwrap_or(0);
+        model.set_min_child_probability(min_child_probability.clamp(0, u32::MAX as i64) as u32);
+        model.set_uniform(Self::uniform_setting(&settings));
+        model.set_control_mass(settings.get_long(Parameter::ControlNodeMass).unwrap_or(0).clamp(0, 1000) as u32);
+        model.set_language_model_max_nodes(Self::max_nodes(&settings));
+        model.set_next_word_prediction(settings.get_bool(Parameter::NextWordPrediction).unwrap_or(false));
+        model.set_auto_space(settings.get_bool(Parameter::AutoSpace).unwrap_or(false));
+        model.set_smart_punctuation(settings.get_bool(Parameter::SmartPunctuation).unwrap_or(false));
+        model.set_toggle_groups(settings.get_bool(Parameter::ToggleGroups).unwrap_or(true));
+        model.set_word_separators(Self::word_separators_setting(&settings));
+        model.set_learning_mode(Self::learning_mode_setting(&settings));
+        model.corpus_mut().set_path(UserCorpus::user_path());
+        model.corpus_mut().set_enabled(settings.get_bool(Parameter::RecordCorpus).unwrap_or(false));
 
         // Initialize the model
         if let Err(e) = model.initialize() {
-            eprintln!("Failed to initialize model: {:?}", e);
+            dasher_log!(Error, "Failed to initialize model: {:?}", e);
         }
 
+        let mut input_manager = InputManager::new();
+        input_manager.set_max_bit_rate(settings.get_long(Parameter::MaxBitRate).unwrap_or(100) as f64 / 100.0);
+        input_manager.set_boost_factor(settings.get_long(Parameter::BoostFactor).unwrap_or(175) as f64 / 100.0);
+        input_manager.set_auto_speed_control(settings.get_bool(Parameter::AutoSpeedControl).unwrap_or(true));
+        Self::apply_smoothing_settings(&settings, &mut input_manager);
+        Self::apply_head_pointer_settings(&settings, &mut input_manager);
+        input_manager.set_button_dwell_time(settings.get_long(Parameter::ButtonDwellTime).unwrap_or(0).max(0) as u64);
+        input_manager.set_two_box_start_enabled(settings.get_bool(Parameter::TwoBoxStart).unwrap_or(false));
+        let input_filters = InputFilterRegistry::new();
+        Self::apply_input_filter_setting(&settings, &input_filters, &mut input_manager);
+
         Self {
             model,
             view: None,
-            input_manager: InputManager::new(),
+            input_manager,
+            input_filters,
             settings,
             running: false,
             paused: false,
             current_time: 0,
+            last_frame_time: None,
+            zooming: false,
+            stats: UserStats::new(),
+            recorder: InputRecorder::new(),
+            expansion_policy: ExpansionPolicy::default(),
+            frame_rate: FrameRate::default(),
+            announcement_callback: None,
+            announced_text: String::new(),
+            cursor_callback: None,
+            reported_cursor: 0,
+            alphabet_orientation: Orientation::LeftToRight,
         }
     }
 
@@ -65,27 +236,198 @@ impl DasherInterface {
         let mut view = Box::new(DasherViewSquare::new(screen));
 
         // Set the orientation based on settings
-        let orientation = match self.settings.get_long(Parameter::Orientation).unwrap_or(0) {
-            0 => Orientation::LeftToRight,
-            1 => Orientation::RightToLeft,
-            2 => Orientation::TopToBottom,
-            3 => Orientation::BottomToTop,
-            _ => Orientation::LeftToRight,
-        };
-        view.set_orientation(orientation);
+        view.set_orientation(self.orientation());
 
         // Store the view
         self.view = Some(view);
+        self.apply_view_settings();
+
+        Ok(())
+    }
+
+    /// Read the language model's node limit from the settings (0 means no limit)
+    fn max_nodes(settings: &Settings) -> Option<usize> {
+        match settings.get_long(Parameter::LanguageModelMaxNodes).unwrap_or(0) {
+            max_nodes if max_nodes > 0 => Some(max_nodes as usize),
+            _ => None,
+        }
+    }
+
+    /// Apply the pointer smoothing settings to the input manager
+    fn apply_smoothing_settings(settings: &Settings, input_manager: &mut InputManager) {
+        let method = SmoothingMethod::from_setting(settings.get_long(Parameter::PointerSmoothing).unwrap_or(0));
+        let strength_x = settings.get_long(Parameter::PointerSmoothingX).unwrap_or(50) as f64 / 100.0;
+        let strength_y = settings.get_long(Parameter::PointerSmoothingY).unwrap_or(50) as f64 / 100.0;
+        input_manager.set_smoothing(method, strength_x, strength_y);
+    }
+
+    /// Apply the head pointer profile settings to the input manager
+    fn apply_head_pointer_settings(settings: &Settings, input_manager: &mut InputManager) {
+        let config = settings.get_bool(Parameter::HeadPointer).unwrap_or(false).then(|| {
+            let defaults = HeadPointerConfig::default();
+            HeadPointerConfig {
+                smoothing: settings.get_long(Parameter::HeadPointerSmoothing).unwrap_or(70) as f64 / 100.0,
+                vertical_gain: settings.get_long(Parameter::HeadPointerVerticalGain).unwrap_or(150) as f64 / 100.0,
+                dwell_ms: settings.get_long(Parameter::HeadPointerDwellTime).unwrap_or(1000).max(0) as u64,
+                ..defaults
+            }
+        });
+        input_manager.set_head_pointer(config);
+    }
+
+    /// Get the direction text is written in
+    ///
+    /// This is the orientation in the settings, or the current alphabet's if
+    /// the settings leave it to the alphabet.
+    pub fn orientation(&self) -> Orientation {
+        match self.settings.get_long(Parameter::Orientation) {
+            Some(Orientation::ALPHABET_DEFAULT) | None => self.alphabet_orientation,
+            Some(code) => Orientation::from_code(code).unwrap_or(Orientation::LeftToRight),
+        }
+    }
+
+    /// Set a view setting and apply it
+    fn set_view_setting(&mut self, parameter: Parameter, value: i64) {
+        self.settings.set_long(parameter, value);
+        self.handle_parameter_change(parameter);
+    }
+
+    /// Set the direction text is written in, keeping it in the settings
+    ///
+    /// This overrides the orientation of the alphabets loaded from now on.
+    pub fn set_orientation(&mut self, orientation: Orientation) {
+        self.set_view_setting(Parameter::Orientation, orientation.code());
+    }
+
+    /// Write in the direction each alphabet declares, keeping this in the settings
+    pub fn use_alphabet_orientation(&mut self) {
+        self.set_view_setting(Parameter::Orientation, Orientation::ALPHABET_DEFAULT);
+    }
+
+    /// Set the width of the margin (in Dasher units), keeping it in the settings
+    pub fn set_margin_width(&mut self, width: i64) -> Result<()> {
+        if width < 0 {
+            return Err(DasherError::InvalidParameter(format!("Invalid margin width: {}", width)));
+        }
+        self.set_view_setting(Parameter::MarginWidth, width);
+        Ok(())
+    }
+
+    /// Set how much node labels grow with the node, keeping it in the settings
+    ///
+    /// The settings keep the scaling in hundredths.
+    pub fn set_font_scaling(&mut self, scaling: f64) -> Result<()> {
+        if !scaling.is_finite() || scaling < 0.0 {
+            return Err(DasherError::InvalidParameter(format!("Invalid font scaling: {}", scaling)));
+        }
+        self.set_view_setting(Parameter::FontScaling, (scaling * 100.0).round() as i64);
+        Ok(())
+    }
+
+    /// Set the X nonlinearity factor, 0 for none, keeping it in the settings
+    ///
+    /// The settings keep the factor in tenths.
+    pub fn set_x_nonlinear_factor(&mut self, factor: f64) -> Result<()> {
+        if !factor.is_finite() || factor < 0.0 {
+            return Err(DasherError::InvalidParameter(format!("Invalid X nonlinearity: {}", factor)));
+        }
+        self.set_view_setting(Parameter::XNonlinearity, (factor * 10.0).round() as i64);
+        Ok(())
+    }
 
+    /// Set the Y nonlinearity factor, 1 or less for none, keeping it in the settings
+    pub fn set_y_nonlinear_factor(&mut self, factor: i64) -> Result<()> {
+        if factor < 0 {
+            return Err(DasherError::InvalidParameter(format!("Invalid Y nonlinearity: {}", factor)));
+        }
+        self.set_view_setting(Parameter::YNonlinearity, factor);
         Ok(())
     }
 
+    /// Apply the geometry settings to the view, if it is a Square View
+    fn apply_view_settings(&mut self) {
+        let Some(view) = self.view.as_mut().and_then(|view| view.as_any_mut().downcast_mut::<DasherViewSquare>()) else {
+            return;
+        };
+
+        let x_nonlinearity = self.settings.get_long(Parameter::XNonlinearity).unwrap_or(48);
+        view.set_x_nonlinear(x_nonlinearity > 0);
+        if x_nonlinearity > 0 {
+            view.set_x_nonlinear_factor(x_nonlinearity as f64 / 10.0);
+        }
+
+        let y_nonlinearity = self.settings.get_long(Parameter::YNonlinearity).unwrap_or(4);
+        view.set_y_nonlinear(y_nonlinearity > 1);
+        if y_nonlinearity > 1 {
+            view.set_y_nonlinear_factor(y_nonlinearity);
+        }
+
+        view.set_margin_width(self.settings.get_long(Parameter::MarginWidth).unwrap_or(DasherModel::MAX_X / 10).max(0));
+        view.set_font_size_scaling(self.settings.get_long(Parameter::FontScaling).unwrap_or(50) as f64 / 100.0);
+        view.set_draw_mouse_line(self.settings.get_bool(Parameter::DrawMouseLine).unwrap_or(false));
+
+        let family = self.settings.get_string(Parameter::FontFamily).unwrap_or("").trim();
+        let weight = self.settings.get_long(Parameter::FontWeight).unwrap_or(FontDescriptor::NORMAL_WEIGHT as i64);
+        view.set_font(FontDescriptor::new((!family.is_empty()).then_some(family), weight.clamp(100, 900) as u16));
+    }
+
     /// Set the input device
-    pub fn set_input(&mut self, input: Box<dyn DasherInput>) {
+    ///
+    /// The device is given the calibration stored for it in the settings, if any.
+    pub fn set_input(&mut self, mut input: Box<dyn DasherInput>) {
+        if let Some(calibration) = Self::stored_calibrations(&self.settings).get(input.get_name()) {
+            input.set_calibration(*calibration);
+        }
+
         // Set the input device in the input manager
         self.input_manager.set_input_device(input);
+        self.refresh_view_input();
+    }
+
+    /// Add an input device under `name`, alongside the other devices
+    ///
+    /// The device is given the calibration stored for it in the settings, if
+    /// any, and becomes the primary device if the `PrimaryInputDevice`
+    /// setting names it.
+    pub fn add_input(&mut self, name: &str, mut input: Box<dyn DasherInput>) {
+        if let Some(calibration) = Self::stored_calibrations(&self.settings).get(input.get_name()) {
+            input.set_calibration(*calibration);
+        }
+
+        self.input_manager.add_input_device(name, input);
+        if self.settings.get_string(Parameter::PrimaryInputDevice) == Some(name) {
+            let _ = self.input_manager.set_primary_device(name);
+        }
+        self.refresh_view_input();
+    }
+
+    /// Remove the input device added under `name`
+    pub fn remove_input(&mut self, name: &str) -> Result<()> {
+        self.input_manager.remove_input_device(name)
+            .ok_or_else(|| DasherError::InputError(format!("No input device named {}", name)))?;
+        self.refresh_view_input();
+        Ok(())
+    }
+
+    /// Move the pointer of the input device added under `name`, in screen coordinates
+    pub fn set_device_position(&mut self, name: &str, x: i32, y: i32) -> Result<()> {
+        let device = self.input_manager.input_device_mut(name)
+            .ok_or_else(|| DasherError::InputError(format!("No input device named {}", name)))?;
+        device.set_screen_position(x, y);
+        Ok(())
+    }
+
+    /// Take the pointer from the input device added under `name`, keeping
+    /// the choice in the settings
+    pub fn set_primary_input(&mut self, name: &str) -> Result<()> {
+        self.input_manager.set_primary_device(name)?;
+        self.settings.set_string(Parameter::PrimaryInputDevice, name.to_string());
+        self.handle_parameter_change(Parameter::PrimaryInputDevice);
+        Ok(())
+    }
 
-        // Also set it in the view if available
+    /// Give the view a copy of the current input device
+    fn refresh_view_input(&mut self) {
         if let Some(view) = &mut self.view {
             if let Some(input) = self.input_manager.get_input_device() {
                 view.set_input_device(input.box_clone());
@@ -93,47 +435,258 @@ impl DasherInterface {
         }
     }
 
+    /// Read the share of the probability spread evenly over the symbols from the settings
+    fn uniform_setting(settings: &Settings) -> u32 {
+        settings.get_long(Parameter::Uniform).unwrap_or(DasherModel::DEFAULT_UNIFORM as i64).clamp(0, 1000) as u32
+    }
+
+    /// Read when the language model learns from the settings
+    fn learning_mode_setting(settings: &Settings) -> LearningMode {
+        LearningMode::from_settings(
+            settings.get_bool(Parameter::LearnWhileWriting).unwrap_or(true),
+            settings.get_bool(Parameter::LearnAtSentenceEnd).unwrap_or(false),
+        )
+    }
+
+    /// Read the word separators from the settings
+    fn word_separators_setting(settings: &Settings) -> Vec<char> {
+        settings.get_string(Parameter::WordSeparators)
+            .map_or_else(|| CombinedLanguageModel::DEFAULT_WORD_SEPARATORS.to_vec(), |separators| separators.chars().collect())
+    }
+
+    /// Get the characters that end a word
+    pub fn word_separators(&self) -> Vec<char> {
+        Self::word_separators_setting(&self.settings)
+    }
+
+    /// Add a character that ends a word, keeping it in the settings
+    ///
+    /// Lets languages whose punctuation differs from English, such as
+    /// Spanish "¿" or the CJK full stop "。", end words correctly.
+    pub fn add_word_separator(&mut self, separator: char) {
+        let mut separators = Self::word_separators_setting(&self.settings);
+        if !separators.contains(&separator) {
+            separators.push(separator);
+            self.settings.set_string(Parameter::WordSeparators, separators.into_iter().collect());
+            self.handle_parameter_change(Parameter::WordSeparators);
+        }
+    }
+
+    /// Remove a character from those that end a word, keeping the change in the settings
+    pub fn remove_word_separator(&mut self, separator: char) {
+        let mut separators = Self::word_separators_setting(&self.settings);
+        if separators.contains(&separator) {
+            separators.retain(|&c| c != separator);
+            self.settings.set_string(Parameter::WordSeparators, separators.into_iter().collect());
+            self.handle_parameter_change(Parameter::WordSeparators);
+        }
+    }
+
+    /// Read the calibration of each input device from the settings
+    fn stored_calibrations(settings: &Settings) -> HashMap<String, Calibration> {
+        let json = settings.get_string(Parameter::InputCalibration).unwrap_or("{}");
+        serde_json::from_str(json).unwrap_or_else(|e| {
+            dasher_log!(Warn, "Ignoring invalid input calibration: {}", e);
+            HashMap::new()
+        })
+    }
+
+    /// Calibrate the current input device, keeping the calibration in the settings
+    pub fn set_input_calibration(&mut self, calibration: Calibration) -> Result<()> {
+        let name = self.input_manager.get_input_device()
+            .map(|input| input.get_name().to_string())
+            .ok_or_else(|| DasherError::InputError("No input device available".to_string()))?;
+
+        let mut calibrations = Self::stored_calibrations(&self.settings);
+        calibrations.insert(name, calibration);
+        let json = serde_json::to_string(&calibrations)
+            .map_err(|e| DasherError::SettingsError(e.to_string()))?;
+        self.settings.set_string(Parameter::InputCalibration, json);
+        self.handle_parameter_change(Parameter::InputCalibration);
+        Ok(())
+    }
+
+    /// Get the calibration of the current input device
+    pub fn input_calibration(&self) -> Option<Calibration> {
+        self.input_manager.get_input_device().map(|input| input.calibration())
+    }
+
     /// Set the input filter
     pub fn set_input_filter(&mut self, filter: Box<dyn InputFilter>) {
         self.input_manager.set_input_filter(filter);
     }
 
+    /// Use the input filter named in the settings, keeping the current
+    /// filter if no filter has that name
+    fn apply_input_filter_setting(settings: &Settings, registry: &InputFilterRegistry, input_manager: &mut InputManager) {
+        let name = settings.get_string(Parameter::InputFilterID).unwrap_or(InputFilterRegistry::DEFAULT);
+        match registry.create(name) {
+            Some(filter) => input_manager.set_input_filter(filter),
+            None => dasher_log!(Warn, "Keeping the input filter: no input filter named {}", name),
+        }
+    }
+
+    /// Register an input filter under `name`, so it can be chosen with the
+    /// `InputFilterID` setting
+    pub fn register_input_filter<F>(&mut self, name: &str, constructor: F)
+    where
+        F: Fn() -> Box<dyn InputFilter> + 'static,
+    {
+        self.input_filters.register(name, constructor);
+    }
+
+    /// Get the names of the input filters that can be chosen
+    pub fn input_filter_names(&self) -> Vec<&str> {
+        self.input_filters.names()
+    }
+
+    /// Switch to the input filter registered under `name`, keeping the
+    /// choice in the settings
+    pub fn select_input_filter(&mut self, name: &str) -> Result<()> {
+        if !self.input_filters.contains(name) {
+            return Err(DasherError::InvalidParameter(format!("No input filter named {}", name)));
+        }
+        self.settings.set_string(Parameter::InputFilterID, name.to_string());
+        self.handle_parameter_change(Parameter::InputFilterID);
+        Ok(())
+    }
+
     /// Process a new frame
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self), fields(running = self.running, paused = self.paused)))]
     pub fn new_frame(&mut self, time_ms: u64) -> bool {
         // Update the current time
         self.current_time = time_ms;
 
-        // If not running, do nothing
+        // Animate jumps of the display by the time since the last frame
+        let elapsed = self.last_frame_time.map_or(0, |last| time_ms.saturating_sub(last));
+        self.last_frame_time = Some(time_ms);
+        self.model.ease_display(elapsed);
+
+        // Let the view draw the pointer where it is now
+        self.refresh_view_input();
+
+        // Record the device position for this frame
+        if self.recorder.is_recording() {
+            if let (Some(view), Some(device)) = (&self.view, self.input_manager.get_input_device()) {
+                // Record the position as the device reports it, since replaying calibrates it again
+                let mut raw = device.box_clone();
+                raw.set_calibration(Calibration::default());
+                if let Some((x, y)) = raw.get_screen_coordinates(view.as_ref()) {
+                    self.recorder.record(time_ms, InputEvent::Coordinates { x, y });
+                }
+            }
+            self.recorder.record(time_ms, InputEvent::Frame);
+        }
+
+        // A finger held still starts or stops, even while stopped
+        let gesture = self.input_manager.touch_input_mut().and_then(|touch| touch.update(time_ms));
+        self.handle_touch_gesture(gesture);
+
+        // In the head pointer profile, dwelling while stopped starts, and in
+        // two-box start mode, holding in both boxes does
         if !self.running {
+            if let Some(view) = &self.view {
+                if self.input_manager.dwell_to_start(time_ms, view.as_ref())
+                    || self.input_manager.two_box_start(time_ms, view.as_ref()) {
+                    self.start();
+                }
+            }
+        }
+
+        // If not running, only show the start boxes
+        if !self.running {
+            let overlay = Self::debug_info(&self.settings, &self.model, &self.frame_rate);
+            if let (Some(view), true) = (&mut self.view, self.input_manager.is_two_box_start_enabled()) {
+                let input_manager = &mut self.input_manager;
+                return view.render_decorated(&mut self.model, &mut |view| {
+                    input_manager.decorate_stopped_view(view);
+                    if let Some(info) = &overlay {
+                        view.draw_overlay(info);
+                    }
+                }).is_ok();
+            }
             return false;
         }
 
         // If paused, just render
         if self.paused {
+            let overlay = Self::debug_info(&self.settings, &self.model, &self.frame_rate);
             if let Some(view) = &mut self.view {
-                return view.render(&mut self.model).is_ok();
+                let input_manager = &mut self.input_manager;
+                return view.render_decorated(&mut self.model, &mut |view| {
+                    input_manager.decorate_view(view);
+                    if let Some(info) = &overlay {
+                        view.draw_overlay(info);
+                    }
+                }).is_ok();
             }
             return false;
         }
 
+        // Measure the frame rate while writing
+        self.frame_rate.record_frame_ms(time_ms);
+
         // Process input
         if let Some(view) = &mut self.view {
-            self.input_manager.process_frame(time_ms, &mut self.model, view.as_mut());
+            #[cfg(feature = "tracing")]
+            let counters_before = FrameCounters::read(&self.model, 0);
+            let nats_before = self.model.total_nats();
+            let symbols_before = self.model.output_text().chars().count();
+
+            // A zoom asked for by the host takes over from the input filter until it is done
+            self.zooming &= self.model.scheduled_step_count() > 0;
+            if !self.zooming {
+                self.input_manager.process_frame(time_ms, &mut self.model, view.as_mut());
+            }
 
             // Process the next scheduled step in the model
             self.model.next_scheduled_step();
 
-            // Render the view
-            return view.render(&mut self.model).is_ok();
+            // Grow the tree where the user is heading and prune it elsewhere
+            self.model.expand_visible_nodes(&self.expansion_policy);
+
+            // Update the statistics
+            let symbols_after = self.model.output_text().chars().count();
+            self.stats.record_frame(time_ms);
+            self.stats.record_speed(self.input_manager.max_bit_rate());
+            self.stats.record_nats(self.model.total_nats() - nats_before);
+            if symbols_after > symbols_before {
+                self.stats.record_entered(symbols_after - symbols_before);
+            } else {
+                self.stats.record_deleted(symbols_before - symbols_after);
+            }
+
+            // Render the view, with the input decorations and debug overlay over it
+            let overlay = Self::debug_info(&self.settings, &self.model, &self.frame_rate);
+            let input_manager = &mut self.input_manager;
+            let rendered = view.render_decorated(&mut self.model, &mut |view| {
+                input_manager.decorate_view(view);
+                if let Some(info) = &overlay {
+                    view.draw_overlay(info);
+                }
+            }).is_ok();
+
+            #[cfg(feature = "tracing")]
+            FrameCounters::read(&self.model, view.draw_calls()).trace_since(&counters_before);
+
+            self.report_edits();
+            return rendered;
         }
 
         false
     }
 
+    /// Gather the figures for the debug overlay, if the settings turn it on
+    fn debug_info(settings: &Settings, model: &DasherModel, frame_rate: &FrameRate) -> Option<DebugInfo> {
+        settings.get_bool(Parameter::DebugOverlay).unwrap_or(false)
+            .then(|| DebugInfo::from_model(model, frame_rate.frames_per_second()))
+    }
+
     /// Handle a key down event
     pub fn key_down(&mut self, time_ms: u64, key: VirtualKey) {
         // Update the current time
         self.current_time = time_ms;
+        self.recorder.record(time_ms, InputEvent::KeyDown(key));
 
         // If not running, check for start key
         if !self.running && key == VirtualKey::StartStopKey {
@@ -153,6 +706,7 @@ impl DasherInterface {
     pub fn key_up(&mut self, time_ms: u64, key: VirtualKey) {
         // Update the current time
         self.current_time = time_ms;
+        self.recorder.record(time_ms, InputEvent::KeyUp(key));
 
         // If running, process the key
         if self.running {
@@ -162,6 +716,110 @@ impl DasherInterface {
         }
     }
 
+    /// Handle a finger touching the screen, if the input device is a touch screen
+    pub fn touch_down(&mut self, time_ms: u64, id: u64, x: i32, y: i32) -> Result<()> {
+        self.current_time = time_ms;
+        let gesture = self.touch_input()?.touch_down(time_ms, id, x, y);
+        self.handle_touch_gesture(gesture);
+        Ok(())
+    }
+
+    /// Handle a finger moving on the screen, if the input device is a touch screen
+    pub fn touch_move(&mut self, time_ms: u64, id: u64, x: i32, y: i32) -> Result<()> {
+        self.current_time = time_ms;
+        self.touch_input()?.touch_move(time_ms, id, x, y);
+        Ok(())
+    }
+
+    /// Handle a finger leaving the screen, if the input device is a touch screen
+    pub fn touch_up(&mut self, time_ms: u64, id: u64) -> Result<()> {
+        self.current_time = time_ms;
+        self.touch_input()?.touch_up(time_ms, id);
+        Ok(())
+    }
+
+    /// Get the input device as a touch screen
+    fn touch_input(&mut self) -> Result<&mut crate::input::TouchInput> {
+        self.input_manager.touch_input_mut()
+            .ok_or_else(|| DasherError::InputError("The input device is not a touch screen".to_string()))
+    }
+
+    /// Get the input device as a joystick, if it is one
+    pub fn joystick_input_mut(&mut self) -> Option<&mut JoystickInput> {
+        self.input_manager.joystick_input_mut()
+    }
+
+    /// Set the deflection of the joystick, from -1 to 1 on each axis (right and down are positive)
+    pub fn joystick_axes(&mut self, x: f64, y: f64) -> Result<()> {
+        self.joystick_input_mut()
+            .ok_or_else(|| DasherError::InputError("The input device is not a joystick".to_string()))?
+            .set_axes(x, y);
+        Ok(())
+    }
+
+    /// Handle a joystick button, sending the virtual key it is mapped to
+    ///
+    /// Unmapped buttons are ignored.
+    pub fn joystick_button(&mut self, time_ms: u64, button: u32, pressed: bool) -> Result<()> {
+        let key = self.joystick_input_mut()
+            .ok_or_else(|| DasherError::InputError("The input device is not a joystick".to_string()))?
+            .button_key(button);
+        match key {
+            Some(key) if pressed => self.key_down(time_ms, key),
+            Some(key) => self.key_up(time_ms, key),
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// Set the regions of the screen that act as buttons, pressed by dwelling
+    /// in them for the `ButtonDwellTime` setting
+    pub fn set_button_regions(&mut self, regions: Vec<ButtonRegion>) {
+        self.input_manager.set_button_regions(regions);
+    }
+
+    /// Get the regions of the screen that act as buttons
+    pub fn button_regions(&self) -> &[ButtonRegion] {
+        self.input_manager.button_regions()
+    }
+
+    /// Get the progress of the dwell on a button region, for drawing a countdown
+    pub fn button_dwell(&self) -> Option<ButtonDwell> {
+        self.input_manager.button_dwell()
+    }
+
+    /// Use a breath or pressure sensor, steering with the one-dimensional filter
+    pub fn use_pressure_input(&mut self, calibration: PressureCalibration) {
+        let mut pressure = PressureInput::new(calibration);
+        pressure.activate();
+        self.set_input(Box::new(pressure));
+        self.set_input_filter(Box::new(OneDimensionalFilter::new()));
+    }
+
+    /// Get the input device as a breath or pressure sensor, if it is one
+    pub fn pressure_input_mut(&mut self) -> Option<&mut PressureInput> {
+        self.input_manager.pressure_input_mut()
+    }
+
+    /// Set the latest reading of the breath or pressure sensor
+    pub fn set_pressure(&mut self, pressure: f64) -> Result<()> {
+        self.pressure_input_mut()
+            .ok_or_else(|| DasherError::InputError("The input device is not a pressure sensor".to_string()))?
+            .set_pressure(pressure);
+        Ok(())
+    }
+
+    /// Start, stop, pause or resume as a touch gesture asks
+    fn handle_touch_gesture(&mut self, gesture: Option<TouchGesture>) {
+        match gesture {
+            Some(TouchGesture::StartStop) if self.running => self.stop(),
+            Some(TouchGesture::StartStop) => self.start(),
+            Some(TouchGesture::Pause) if self.paused => self.resume(),
+            Some(TouchGesture::Pause) => self.pause(),
+            None => {}
+        }
+    }
+
     /// Start Dasher
     pub fn start(&mut self) {
         self.running = true;
@@ -169,10 +827,14 @@ impl DasherInterface {
 
         // Create a root node if needed
         if self.model.get_node_under_crosshair().is_none() {
-            let root = Rc::new(RefCell::new(DasherNode::new(0, Some("Root".to_string()))));
+            // The root stands for the character just before the cursor
+            let root = Rc::new(RefCell::new(DasherNode::new(self.model.get_offset() - 1, Some("Root".to_string()))));
             self.model.set_node(root);
         }
 
+        // Don't count the time spent stopped as a frame
+        self.frame_rate.reset_ms(self.current_time);
+
         // Resume input processing
         self.input_manager.resume();
     }
@@ -181,6 +843,7 @@ impl DasherInterface {
     pub fn stop(&mut self) {
         self.running = false;
         self.paused = false;
+        self.stats.suspend();
 
         // Pause input processing
         self.input_manager.pause();
@@ -190,6 +853,7 @@ impl DasherInterface {
     pub fn pause(&mut self) {
         if self.running {
             self.paused = true;
+            self.stats.suspend();
 
             // Pause input processing
             self.input_manager.pause();
@@ -200,6 +864,7 @@ impl DasherInterface {
     pub fn resume(&mut self) {
         if self.running && self.paused {
             self.paused = false;
+            self.frame_rate.reset_ms(self.current_time);
 
             // Resume input processing
             self.input_manager.resume();
@@ -226,6 +891,98 @@ impl DasherInterface {
         &mut self.settings
     }
 
+    /// Get the policy limiting node expansion each frame
+    pub fn expansion_policy(&self) -> &ExpansionPolicy {
+        &self.expansion_policy
+    }
+
+    /// Set the policy limiting node expansion each frame
+    pub fn set_expansion_policy(&mut self, policy: ExpansionPolicy) {
+        self.expansion_policy = policy;
+    }
+
+    /// Get the measured rate at which frames are rendered
+    pub fn frame_rate(&self) -> &FrameRate {
+        &self.frame_rate
+    }
+
+    /// Get the statistics for the current session
+    pub fn stats(&self) -> &UserStats {
+        &self.stats
+    }
+
+    /// Reset the session statistics
+    pub fn reset_stats(&mut self) {
+        self.stats.reset();
+    }
+
+    /// Export the session statistics, minute by minute, as JSON or CSV
+    pub fn export_session_stats(&self, format: StatsFormat) -> Result<String> {
+        SessionReport::from_stats(&self.stats).export(format)
+    }
+
+    /// Start recording input events, discarding any recording in progress
+    pub fn start_recording(&mut self) {
+        self.recorder.start();
+    }
+
+    /// Stop recording input events and return the recording
+    pub fn stop_recording(&mut self) -> Option<InputRecording> {
+        self.recorder.stop()
+    }
+
+    /// Check if input events are being recorded
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_recording()
+    }
+
+    /// Replay the recorded events due at or before `time_ms`
+    ///
+    /// Returns the number of events replayed.
+    pub fn replay_until(&mut self, replay: &mut InputReplay, time_ms: u64) -> usize {
+        let events = replay.due_events(time_ms);
+        let count = events.len();
+        for event in events {
+            self.apply_recorded_event(event);
+        }
+        count
+    }
+
+    /// Replay every remaining recorded event
+    ///
+    /// Returns the number of events replayed.
+    pub fn replay_all(&mut self, replay: &mut InputReplay) -> usize {
+        let events = replay.remaining_events();
+        let count = events.len();
+        for event in events {
+            self.apply_recorded_event(event);
+        }
+        count
+    }
+
+    /// Feed a recorded event back through the interface
+    fn apply_recorded_event(&mut self, recorded: RecordedEvent) {
+        let time_ms = recorded.time_ms;
+        match recorded.event {
+            InputEvent::Coordinates { x, y } => {
+                self.current_time = time_ms;
+                if let Err(e) = self.set_mouse_position(x, y) {
+                    dasher_log!(Warn, "Failed to replay coordinates: {}", e);
+                }
+            }
+            InputEvent::KeyDown(key) => self.key_down(time_ms, key),
+            InputEvent::KeyUp(key) => self.key_up(time_ms, key),
+            InputEvent::ParameterChange { parameter, value } => {
+                self.current_time = time_ms;
+                self.settings.set(parameter, value);
+                self.handle_parameter_change(parameter);
+            }
+            InputEvent::Frame => {
+                self.new_frame(time_ms);
+            }
+        }
+    }
+
     /// Get a reference to the model
     pub fn model(&self) -> &DasherModel {
         &self.model
@@ -236,11 +993,71 @@ impl DasherInterface {
         &mut self.model
     }
 
+    /// Get a reference to the input manager
+    pub fn input_manager(&self) -> &InputManager {
+        &self.input_manager
+    }
+
     /// Get a reference to the view
     pub fn view(&self) -> Option<&dyn DasherView> {
         self.view.as_deref()
     }
 
+    /// Find the node drawn under the screen point (`x`, `y`), for tap-to-zoom or tooltips
+    ///
+    /// The point is mapped through the view's transforms, so the node is the
+    /// deepest one whose box covers it as drawn. Returns `None` without a view,
+    /// or if the point is outside the tree.
+    pub fn node_at_screen_point(&self, x: i32, y: i32) -> Option<NodeHit> {
+        let view = self.view.as_ref()?;
+        let (dasher_x, dasher_y) = view.screen_to_dasher(x, y);
+        let (node, (y1, y2)) = self.model.node_at(dasher_x, dasher_y)?;
+        let node = node.borrow();
+
+        // The box is as deep as it is high
+        let (sx1, sy1) = view.dasher_to_screen(0, y1);
+        let (sx2, sy2) = view.dasher_to_screen(y2 - y1, y2);
+        Some(NodeHit {
+            symbol: node.symbol(),
+            label: node.label().cloned().unwrap_or_default(),
+            bounds: (y1, y2),
+            screen_rect: (sx1.min(sx2), sy1.min(sy2), sx1.max(sx2), sy1.max(sy2)),
+        })
+    }
+
+    /// Call `visit` with each node the view would draw, parents before their children,
+    /// for frontends that draw the scene themselves
+    pub fn visit_visible_nodes(&self, visit: &mut dyn FnMut(&VisibleNode)) -> Result<()> {
+        self.square_view()?.visit_visible_nodes(&self.model, visit);
+        Ok(())
+    }
+
+    /// Describe the current frame (its nodes, crosshair and cursor), for
+    /// hosts that draw it natively rather than through a screen
+    pub fn scene(&self) -> Result<Scene> {
+        Ok(self.square_view()?.scene(&self.model))
+    }
+
+    /// Describe the current frame as JSON, for web and remote user interfaces
+    pub fn scene_json(&self) -> Result<String> {
+        self.scene()?.to_json()
+    }
+
+    /// Get the view, if it is a square view
+    fn square_view(&self) -> Result<&DasherViewSquare> {
+        let view = self.view.as_ref()
+            .ok_or_else(|| DasherError::RenderingError("No view available".to_string()))?;
+        view.as_any().downcast_ref::<DasherViewSquare>()
+            .ok_or_else(|| DasherError::RenderingError("View is not a Square View".to_string()))
+    }
+
+    /// Get the nodes the view would draw, parents before their children
+    pub fn visible_nodes(&self) -> Result<Vec<VisibleNode>> {
+        let mut nodes = Vec::new();
+        self.visit_visible_nodes(&mut |node| nodes.push(node.clone()))?;
+        Ok(nodes)
+    }
+
     /// Get a mutable reference to the view
     pub fn view_mut(&mut self) -> Option<&mut dyn DasherView> {
         if let Some(view) = &mut self.view {
@@ -253,6 +1070,7 @@ impl DasherInterface {
     /// Set the view
     pub fn set_view(&mut self, view: Box<dyn DasherView>) -> Result<()> {
         self.view = Some(view);
+        self.apply_view_settings();
         Ok(())
     }
 
@@ -261,10 +1079,456 @@ impl DasherInterface {
         self.model.get_offset()
     }
 
+    /// Get the cursor position in the output text (in characters)
+    pub fn cursor_position(&self) -> usize {
+        self.model.cursor_position()
+    }
+
+    /// Move the cursor in the output text
+    ///
+    /// The host made this change itself, so it isn't reported.
+    pub fn set_cursor_position(&mut self, position: usize) {
+        self.model.set_cursor_position(position);
+        self.reported_cursor = self.model.cursor_position();
+    }
+
     /// Edit the output text
+    ///
+    /// The host made this change itself, so it isn't announced.
     pub fn edit_output(&mut self, text: &str) {
         // Set the output text in the model
         self.model.set_output_text(text);
+        self.announced_text = text.to_string();
+        self.reported_cursor = self.model.cursor_position();
+    }
+
+    /// Set the host callback that receives a description of each change to the text
+    ///
+    /// The text as it is now is taken as already announced.
+    pub fn set_announcement_callback<F: Fn(&Announcement) + 'static>(&mut self, callback: F) {
+        self.announcement_callback = Some(Box::new(callback));
+        self.announced_text = self.model.output_text().to_string();
+    }
+
+    /// Remove the host callback for announcements
+    pub fn clear_announcement_callback(&mut self) {
+        self.announcement_callback = None;
+    }
+
+    /// Set the host callback that receives the cursor position whenever Dasher
+    /// moves it, by writing or deleting text or by a cursor movement action
+    ///
+    /// Moves made by the host itself are not reported.
+    pub fn set_cursor_callback<F: Fn(usize) + 'static>(&mut self, callback: F) {
+        self.cursor_callback = Some(Box::new(callback));
+        self.reported_cursor = self.model.cursor_position();
+    }
+
+    /// Remove the host callback for cursor moves
+    pub fn clear_cursor_callback(&mut self) {
+        self.cursor_callback = None;
+    }
+
+    /// Tell the host how the text and cursor have changed since they were last reported
+    fn report_edits(&mut self) {
+        let text = self.model.output_text();
+        if text != self.announced_text {
+            if let Some(callback) = &self.announcement_callback {
+                for announcement in announcement::announcements(&self.announced_text, text) {
+                    callback(&announcement);
+                }
+            }
+            self.announced_text = text.to_string();
+        }
+
+        let cursor = self.model.cursor_position();
+        if cursor != self.reported_cursor {
+            if let Some(callback) = &self.cursor_callback {
+                callback(cursor);
+            }
+            self.reported_cursor = cursor;
+        }
+    }
+
+    /// Load an alphabet, replacing the current one and rebuilding the node tree
+    pub fn load_alphabet(&mut self, info: AlphabetInfo) -> Result<()> {
+        self.set_alphabet(info)
+    }
+
+    /// Switch to another alphabet while Dasher is running
+    ///
+    /// The alphabet is given either by id, looked up in the alphabet search
+    /// paths, or as an already loaded [`AlphabetInfo`]. The output text is
+    /// kept, the language model is primed with the text before the cursor,
+    /// and the node tree is rebuilt from the new alphabet's symbols. On
+    /// error the current alphabet stays in use.
+    ///
+    /// Each alphabet has its own language model. The first time an alphabet
+    /// is used its model is trained from the alphabet's training file, if it
+    /// can be found; switching back to an alphabet reuses its trained model.
+    pub fn set_alphabet<A: Into<AlphabetSelection>>(&mut self, alphabet: A) -> Result<()> {
+        let (id, info) = match alphabet.into() {
+            AlphabetSelection::Id(id) => {
+                let info = Self::find_alphabet(&id)?;
+                (id, info)
+            }
+            AlphabetSelection::Info(info) => (info.id.clone(), *info),
+        };
+        if info.characters.is_empty() {
+            return Err(DasherError::InvalidParameter(format!("Alphabet '{}' has no characters", info.id)));
+        }
+
+        dasher_log!(Info, "Loading alphabet '{}' with {} characters", info.id, info.characters.len());
+        for diagnostic in info.validate() {
+            dasher_log!(Warn, "Alphabet '{}': {}", info.id, diagnostic);
+        }
+        let training_file = info.training_file.clone();
+        self.alphabet_orientation = info.orientation.into();
+        let orientation = self.orientation();
+        if let Some(view) = &mut self.view {
+            view.set_orientation(orientation);
+        }
+        if self.model.switch_alphabet(Alphabet::from_info(info)) {
+            self.model.set_language_model_max_nodes(Self::max_nodes(&self.settings));
+            self.train_from_file(&training_file);
+        }
+        self.model.rebuild_tree();
+        self.settings.set_string(Parameter::AlphabetID, id);
+        Ok(())
+    }
+
+    /// Add a language profile with its own alphabet and language model
+    ///
+    /// The profile's language model is trained from the alphabet's training
+    /// file, if it can be found, and keeps what it learns while other
+    /// profiles are in use. The profile in use does not change; switch with
+    /// [`Self::switch_profile`] or the profile's control node.
+    pub fn add_profile<A: Into<AlphabetSelection>>(&mut self, name: &str, alphabet: A) -> Result<()> {
+        let info = match alphabet.into() {
+            AlphabetSelection::Id(id) => Self::find_alphabet(&id)?,
+            AlphabetSelection::Info(info) => *info,
+        };
+        if info.characters.is_empty() {
+            return Err(DasherError::InvalidParameter(format!("Alphabet '{}' has no characters", info.id)));
+        }
+        if name == self.model.profile_name() {
+            return Err(DasherError::InvalidParameter(format!("Profile '{}' is in use", name)));
+        }
+
+        dasher_log!(Info, "Adding profile '{}' with alphabet '{}'", name, info.id);
+        let training_file = info.training_file.clone();
+        let language_model = Box::new(CombinedLanguageModel::new(PPMOrder::Three));
+        self.model.add_profile(name, LanguageProfile::new(Alphabet::from_info(info), language_model));
+
+        // Train the new profile's model while it is in use, then switch back
+        let current = self.model.profile_name().to_string();
+        self.model.switch_profile(name);
+        self.model.set_language_model_max_nodes(Self::max_nodes(&self.settings));
+        self.train_from_file(&training_file);
+        self.model.switch_profile(&current);
+        Ok(())
+    }
+
+    /// Switch to the language profile called `name`, keeping the output text
+    pub fn switch_profile(&mut self, name: &str) -> Result<()> {
+        if self.model.switch_profile(name) {
+            Ok(())
+        } else {
+            Err(DasherError::InvalidParameter(format!("Unknown profile '{}'", name)))
+        }
+    }
+
+    /// Get the statistics of the language model's probability cache, if it has one
+    pub fn language_model_cache_stats(&self) -> Option<CacheStats> {
+        self.model.language_model_cache_stats()
+    }
+
+    /// Get the names of all language profiles, in alphabetical order
+    pub fn profile_names(&self) -> Vec<&str> {
+        self.model.profile_names()
+    }
+
+    /// Get the name of the language profile in use
+    pub fn profile_name(&self) -> &str {
+        self.model.profile_name()
+    }
+
+    /// Create an alphabet discoverer searching the default paths and the bundled data
+    fn alphabet_discovery() -> Result<AlphabetDiscovery> {
+        let mut discovery = AlphabetDiscovery::new()
+            .map_err(|e| DasherError::Other(format!("Failed to search for alphabets: {:?}", e)))?;
+        discovery.add_search_path("data/alphabets");
+        Ok(discovery)
+    }
+
+    /// Train the language model from an alphabet's training file, if it can be found
+    fn train_from_file(&mut self, training_file: &str) {
+        let Some(path) = Self::alphabet_discovery().ok()
+            .and_then(|discovery| discovery.find_training_file(training_file)) else {
+            if !training_file.is_empty() {
+                dasher_log!(Info, "Training file '{}' not found", training_file);
+            }
+            return;
+        };
+
+        let result = std::fs::File::open(&path)
+            .map_err(TrainingError::Io)
+            .and_then(|file| self.model.train_language_model(std::io::BufReader::new(file), |_| true));
+        match result {
+            Ok(stats) => dasher_log!(Info, "Trained language model with {} characters from {}", stats.total_chars, path.display()),
+            Err(e) => dasher_log!(Warn, "Failed to train from {}: {:?}", path.display(), e),
+        }
+    }
+
+    /// Find an alphabet by id, either from its file name or the id in its file
+    fn find_alphabet(id: &str) -> Result<AlphabetInfo> {
+        let discovery = Self::alphabet_discovery()?;
+
+        if let Ok(Some(info)) = discovery.find_alphabet(id) {
+            return Ok(info);
+        }
+        discovery.discover()
+            .map_err(|e| DasherError::Other(format!("Failed to search for alphabets: {:?}", e)))?
+            .alphabets
+            .remove(id)
+            .ok_or_else(|| DasherError::InvalidParameter(format!("Unknown alphabet '{}'", id)))
+    }
+
+    /// Train the language model from a stream of text, reporting progress
+    ///
+    /// `progress` is called with the percentage of the stream read whenever
+    /// it changes; returning `false` cancels training. The node tree is
+    /// rebuilt afterwards, even if training was cancelled, so it reflects
+    /// what was learned.
+    pub fn train<R, P>(&mut self, reader: R, progress: P) -> Result<TrainingStats>
+    where
+        R: std::io::BufRead + std::io::Seek,
+        P: FnMut(u8) -> bool,
+    {
+        let result = self.model.train_language_model(reader, progress);
+        self.model.rebuild_tree();
+        result.map_err(|e| match e {
+            TrainingError::Cancelled => DasherError::Other("Training cancelled".to_string()),
+            TrainingError::Io(e) => DasherError::Other(format!("Failed to read training text: {}", e)),
+            TrainingError::InvalidData(message) => DasherError::InvalidParameter(message),
+        })
+    }
+
+    /// Replace the language model of the profile in use with an n-gram
+    /// model loaded from an ARPA file, which may be gzip-compressed
+    ///
+    /// The model's probabilities are fixed, so nothing is learned from what
+    /// is written, and words are no longer predicted.
+    pub fn load_arpa_model<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<()> {
+        let language_model = ArpaLanguageModel::load(&path)?;
+        dasher_log!(Info, "Loaded ARPA model from {} ({}-grams, {} in all)",
+            path.as_ref().display(), language_model.order(), language_model.ngram_count());
+        self.model.set_language_model(Box::new(language_model));
+        self.model.rebuild_tree();
+        Ok(())
+    }
+
+    /// Predict with a neural character model loaded from a safetensors file
+    ///
+    /// See [`CandleCharModel`] for what the file holds. The current language
+    /// model predicts instead for a while whenever the network takes longer
+    /// than `latency_budget`, and goes on learning from what is written.
+    /// Loading another network replaces the previous one.
+    #[cfg(feature = "candle")]
+    pub fn load_neural_model<P: AsRef<std::path::Path>>(&mut self, path: P, latency_budget: std::time::Duration) -> Result<()> {
+        let predictor = Box::new(CandleCharModel::load(&path)?);
+        dasher_log!(Info, "Loaded neural model from {} (window of {} characters)",
+            path.as_ref().display(), predictor.context_length());
+
+        let neural = self.model.language_model_mut()
+            .and_then(|language_model| language_model.as_any().downcast_mut::<NeuralLanguageModel>());
+        if let Some(neural) = neural {
+            neural.set_predictor(predictor);
+            neural.set_latency_budget(latency_budget);
+            self.model.sync_language_model_context();
+        } else {
+            let fallback = self.model.take_language_model()
+                .unwrap_or_else(|| Box::new(CombinedLanguageModel::new(PPMOrder::Three)));
+            let mut language_model = NeuralLanguageModel::new(predictor, fallback);
+            language_model.set_latency_budget(latency_budget);
+            self.model.set_language_model(Box::new(language_model));
+        }
+        self.model.rebuild_tree();
+        Ok(())
+    }
+
+    /// Set the conversion provider, such as a platform input method, whose
+    /// candidates for the composition spelled so far are offered as nodes
+    pub fn set_conversion_provider(&mut self, provider: Box<dyn ConversionProvider>) {
+        self.model.set_conversion_provider(Some(provider));
+        self.model.rebuild_tree();
+    }
+
+    /// Remove the conversion provider, no longer offering conversions
+    pub fn clear_conversion_provider(&mut self) {
+        self.model.set_conversion_provider(None);
+        self.model.rebuild_tree();
+    }
+
+    /// Set the speech provider used by the speak action
+    pub fn set_speech_provider(&mut self, provider: Box<dyn SpeechProvider>) {
+        self.model.action_manager.register_action(Box::new(SpeakAction::new(provider)));
+    }
+
+    /// Remove the speech provider, disabling the speak action
+    pub fn clear_speech_provider(&mut self) {
+        self.model.action_manager.unregister_action("speak");
+    }
+
+    /// Speak the current sentence
+    ///
+    /// Returns `false` if no speech provider has been set.
+    pub fn speak(&mut self) -> bool {
+        self.model.execute_action("speak")
+    }
+
+    /// Set the clipboard provider used by the copy and cut actions
+    pub fn set_clipboard_provider(&mut self, provider: Box<dyn ClipboardProvider>) {
+        let provider: Rc<dyn ClipboardProvider> = Rc::from(provider);
+        self.model.action_manager.register_action(Box::new(CopyAction::new(provider.clone())));
+        self.model.action_manager.register_action(Box::new(CutAction::new(provider)));
+    }
+
+    /// Remove the clipboard provider, disabling the copy and cut actions
+    pub fn clear_clipboard_provider(&mut self) {
+        self.model.action_manager.unregister_action("copy");
+        self.model.action_manager.unregister_action("cut");
+    }
+
+    /// Register the custom actions declared in a JSON or XML configuration file
+    ///
+    /// Returns the number of actions registered.
+    pub fn load_custom_actions<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<usize> {
+        self.model.action_manager.load_custom_actions_file(path)
+    }
+
+    /// Register the custom actions from `actions.json` or `actions.xml` in the
+    /// user's Dasher configuration directory, if either exists
+    ///
+    /// Returns the number of actions registered.
+    pub fn load_user_actions(&mut self) -> Result<usize> {
+        let count = self.model.action_manager.load_user_actions()?;
+        if count > 0 {
+            dasher_log!(Info, "Loaded {} custom actions", count);
+        }
+        Ok(count)
+    }
+
+    /// Load the words the user has typed in earlier sessions
+    ///
+    /// The lexicon is read from `lexicon.txt` in the user's Dasher configuration
+    /// directory and saved back there by [`Self::save_lexicon`]. Until it is
+    /// loaded, typed words are learned but not saved.
+    pub fn load_user_lexicon(&mut self) -> Result<()> {
+        self.model.load_user_lexicon()
+            .map_err(|e| DasherError::Other(format!("Failed to load personal lexicon: {}", e)))
+    }
+
+    /// Save the words the user has typed to their lexicon file
+    pub fn save_lexicon(&mut self) -> Result<()> {
+        match self.model.personal_lexicon_mut() {
+            Some(lexicon) => lexicon.save()
+                .map_err(|e| DasherError::Other(format!("Failed to save personal lexicon: {}", e))),
+            None => Ok(()),
+        }
+    }
+
+    /// Set the file committed sentences are recorded to
+    ///
+    /// With no file, sentences are kept until taken with
+    /// [`Self::take_corpus_text`]. Sentences are only recorded while the
+    /// [`Parameter::RecordCorpus`] setting is on.
+    pub fn set_corpus_path(&mut self, path: Option<std::path::PathBuf>) {
+        self.model.corpus_mut().set_path(path);
+    }
+
+    /// Record a sentence the host has committed to the corpus
+    ///
+    /// Sentences ended with a terminator or accepted in Dasher are recorded
+    /// without this. Does nothing while recording is off.
+    pub fn record_sentence(&mut self, sentence: &str) -> Result<()> {
+        self.model.corpus_mut().record(sentence)
+            .map_err(|e| DasherError::Other(format!("Failed to record sentence to corpus: {}", e)))
+    }
+
+    /// Take the sentences recorded while the corpus has no file
+    pub fn take_corpus_text(&mut self) -> String {
+        self.model.corpus_mut().take_text()
+    }
+
+    /// Forget what the language model has learned from the user
+    ///
+    /// The words typed and the user's adaptation of the predictions are
+    /// cleared; what was trained from corpora is kept, so no retraining is
+    /// needed. The lexicon file is emptied on the next [`Self::save_lexicon`].
+    pub fn reset_personalization(&mut self) {
+        self.model.reset_personalization();
+        self.model.rebuild_tree();
+    }
+
+    /// Export a layer of the language model as JSON
+    pub fn export_language_layer(&mut self, layer: LanguageLayer) -> Result<String> {
+        self.model.language_layer_to_json(layer)
+            .unwrap_or_else(|| Err(DasherError::Other("Language model has no layers".to_string())))
+    }
+
+    /// Replace a layer of the language model with one exported by
+    /// [`Self::export_language_layer`]
+    pub fn import_language_layer(&mut self, layer: LanguageLayer, json: &str) -> Result<()> {
+        self.model.load_language_layer_json(layer, json)
+            .unwrap_or_else(|| Err(DasherError::Other("Language model has no layers".to_string())))?;
+        self.model.rebuild_tree();
+        Ok(())
+    }
+
+    /// Get the distribution of the next symbol, most probable first
+    ///
+    /// Only the `top_k` most probable symbols are returned, or all of them
+    /// if `top_k` is 0. Empty if there is no language model.
+    pub fn probabilities(&mut self, top_k: usize) -> Vec<(char, f64)> {
+        self.model.ranked_probabilities(top_k, 0.0, None).unwrap_or_default()
+    }
+
+    /// Get up to `max` words predicted from the text before the cursor
+    pub fn word_predictions(&mut self, max: usize) -> Vec<String> {
+        let mut predictions = self.model.get_word_predictions();
+        predictions.truncate(max);
+        predictions
+    }
+
+    /// Remove a word from the user's lexicon, returning whether it was there
+    pub fn remove_lexicon_word(&mut self, word: &str) -> bool {
+        let removed = self.model.personal_lexicon_mut()
+            .is_some_and(|lexicon| lexicon.remove_word(word));
+        if removed {
+            self.model.rebuild_tree();
+        }
+        removed
+    }
+
+    /// Set the host callback invoked with the callback id of custom actions
+    pub fn set_action_callback<F: Fn(u32) + 'static>(&mut self, callback: F) {
+        self.model.action_manager.set_action_callback(callback);
+    }
+
+    /// Remove the host callback for custom actions
+    pub fn clear_action_callback(&mut self) {
+        self.model.action_manager.clear_action_callback();
+    }
+
+    /// Execute a registered action by name
+    pub fn execute_action(&mut self, name: &str) -> Result<()> {
+        if self.model.execute_action(name) {
+            self.report_edits();
+            Ok(())
+        } else {
+            Err(DasherError::InvalidParameter(format!("Unknown action '{}'", name)))
+        }
     }
 
     /// Get the current output text
@@ -274,15 +1538,145 @@ impl DasherInterface {
 
     /// Handle a parameter change
     pub fn handle_parameter_change(&mut self, parameter: Parameter) {
+        if let Some(value) = self.settings.get(parameter) {
+            let event = InputEvent::ParameterChange { parameter, value: value.clone() };
+            self.recorder.record(self.current_time, event);
+        }
+
+        if parameter == Parameter::MaxBitRate {
+            let bit_rate = self.settings.get_long(Parameter::MaxBitRate).unwrap_or(100);
+            self.input_manager.set_max_bit_rate(bit_rate as f64 / 100.0);
+        }
+
+        if parameter == Parameter::BoostFactor {
+            let boost = self.settings.get_long(Parameter::BoostFactor).unwrap_or(175);
+            self.input_manager.set_boost_factor(boost as f64 / 100.0);
+        }
+
+        if parameter == Parameter::AutoSpeedControl {
+            let enabled = self.settings.get_bool(Parameter::AutoSpeedControl).unwrap_or(true);
+            self.input_manager.set_auto_speed_control(enabled);
+        }
+
+        if matches!(parameter, Parameter::HeadPointer | Parameter::HeadPointerSmoothing
+            | Parameter::HeadPointerVerticalGain | Parameter::HeadPointerDwellTime) {
+            Self::apply_head_pointer_settings(&self.settings, &mut self.input_manager);
+        }
+
+        if parameter == Parameter::TwoBoxStart {
+            let enabled = self.settings.get_bool(Parameter::TwoBoxStart).unwrap_or(false);
+            self.input_manager.set_two_box_start_enabled(enabled);
+        }
+
+        if parameter == Parameter::ButtonDwellTime {
+            let dwell_ms = self.settings.get_long(Parameter::ButtonDwellTime).unwrap_or(0).max(0) as u64;
+            self.input_manager.set_button_dwell_time(dwell_ms);
+        }
+
+        if parameter == Parameter::InputCalibration {
+            let calibrations = Self::stored_calibrations(&self.settings);
+            for input in self.input_manager.input_devices_mut() {
+                input.set_calibration(calibrations.get(input.get_name()).copied().unwrap_or_default());
+            }
+            self.refresh_view_input();
+        }
+
+        if parameter == Parameter::InputFilterID {
+            Self::apply_input_filter_setting(&self.settings, &self.input_filters, &mut self.input_manager);
+        }
+
+        if parameter == Parameter::PrimaryInputDevice {
+            let name = self.settings.get_string(Parameter::PrimaryInputDevice).unwrap_or("");
+            if !name.is_empty() {
+                if let Err(e) = self.input_manager.set_primary_device(name) {
+                    dasher_log!(Warn, "Keeping the primary input device: {}", e);
+                }
+                self.refresh_view_input();
+            }
+        }
+
+        if matches!(parameter, Parameter::PointerSmoothing
+            | Parameter::PointerSmoothingX | Parameter::PointerSmoothingY) {
+            Self::apply_smoothing_settings(&self.settings, &mut self.input_manager);
+        }
+
+        if parameter == Parameter::MinChildProbability {
+            let probability = self.settings.get_long(Parameter::MinChildProbability).unwrap_or(0);
+            self.model.set_min_child_probability(probability.clamp(0, u32::MAX as i64) as u32);
+            self.model.rebuild_tree();
+        }
+
+        if parameter == Parameter::LearnWhileWriting || parameter == Parameter::LearnAtSentenceEnd {
+            self.model.set_learning_mode(Self::learning_mode_setting(&self.settings));
+        }
+
+        if parameter == Parameter::RecordCorpus {
+            let enabled = self.settings.get_bool(Parameter::RecordCorpus).unwrap_or(false);
+            self.model.corpus_mut().set_enabled(enabled);
+        }
+
+        if parameter == Parameter::Uniform {
+            self.model.set_uniform(Self::uniform_setting(&self.settings));
+            self.model.rebuild_tree();
+        }
+
+        if parameter == Parameter::ControlNodeMass {
+            let control_mass = self.settings.get_long(Parameter::ControlNodeMass).unwrap_or(0).clamp(0, 1000) as u32;
+            self.model.set_control_mass(control_mass);
+            self.model.rebuild_tree();
+        }
+
+        if parameter == Parameter::NextWordPrediction {
+            let enabled = self.settings.get_bool(Parameter::NextWordPrediction).unwrap_or(false);
+            self.model.set_next_word_prediction(enabled);
+            self.model.rebuild_tree();
+        }
+
+        if parameter == Parameter::AutoSpace {
+            let enabled = self.settings.get_bool(Parameter::AutoSpace).unwrap_or(false);
+            self.model.set_auto_space(enabled);
+            self.model.rebuild_tree();
+        }
+
+        if parameter == Parameter::SmartPunctuation {
+            let enabled = self.settings.get_bool(Parameter::SmartPunctuation).unwrap_or(false);
+            self.model.set_smart_punctuation(enabled);
+        }
+
+        if parameter == Parameter::ToggleGroups {
+            let enabled = self.settings.get_bool(Parameter::ToggleGroups).unwrap_or(true);
+            self.model.set_toggle_groups(enabled);
+            self.model.rebuild_tree();
+        }
+
+        if parameter == Parameter::WordSeparators {
+            self.model.set_word_separators(Self::word_separators_setting(&self.settings));
+            self.model.rebuild_tree();
+        }
+
+        if parameter == Parameter::AlphabetID {
+            let id = self.settings.get_string(Parameter::AlphabetID).unwrap_or("Default").to_string();
+            let current = self.model.alphabet().map(|alphabet| alphabet.name());
+            if current != Some(id.as_str()) {
+                if let Err(e) = self.set_alphabet(id) {
+                    dasher_log!(Warn, "Failed to switch alphabet: {}", e);
+                }
+            }
+        }
+
+        if parameter == Parameter::LanguageModelMaxNodes {
+            self.model.set_language_model_max_nodes(Self::max_nodes(&self.settings));
+        }
+
+        if matches!(parameter, Parameter::XNonlinearity | Parameter::YNonlinearity
+            | Parameter::MarginWidth | Parameter::FontScaling | Parameter::DrawMouseLine
+            | Parameter::FontFamily | Parameter::FontWeight) {
+            self.apply_view_settings();
+        }
+
         if parameter == Parameter::Orientation {
+            let orientation = self.orientation();
             if let Some(view) = &mut self.view {
-                let orientation = match self.settings.get_long(Parameter::Orientation).unwrap_or(0) {
-                    0 => Orientation::LeftToRight,
-                    1 => Orientation::RightToLeft,
-                    2 => Orientation::TopToBottom,
-                    3 => Orientation::BottomToTop,
-                    _ => Orientation::LeftToRight,
-                };
                 view.set_orientation(orientation);
             }
         }
@@ -290,6 +1684,7 @@ impl DasherInterface {
 
     /// Set the mouse position
     pub fn set_mouse_position(&mut self, x: i32, y: i32) -> Result<()> {
+        self.recorder.record(self.current_time, InputEvent::Coordinates { x, y });
         // Add a method to InputManager to set the mouse position directly
         self.input_manager.set_mouse_position(x, y)
     }
@@ -456,24 +1851,62 @@ impl DasherInterface {
 
     /// Reset the Dasher interface
     pub fn reset(&mut self) -> Result<()> {
-        // Reset the model
+        // Reset the model, without announcing the text it clears
         self.model.reset();
+        self.announced_text = self.model.output_text().to_string();
+        self.reported_cursor = self.model.cursor_position();
 
         // Reset the input manager
         self.input_manager.reset();
+        self.zooming = false;
+
+        Ok(())
+    }
 
+    /// Zoom so that the range `y - x` to `y + x` fills the screen over `steps` frames
+    ///
+    /// `x` and `y` are in Dasher coordinates, like the pointer (see
+    /// [`DasherModel::ORIGIN_X`]), so zooming to a node's top and bottom
+    /// brings it to the crosshair. While Dasher is running, the zoom is
+    /// taken one step a frame in place of the input filter's steering, for
+    /// tap-to-zoom or buttons that zoom to a prediction.
+    pub fn zoom_to(&mut self, x: i64, y: i64, steps: i32) -> Result<()> {
+        if x <= 0 || steps < 1 {
+            return Err(crate::DasherError::InvalidParameter(format!(
+                "Cannot zoom to x = {} over {} steps", x, steps
+            )));
+        }
+        self.model.schedule_zoom(y - x, y + x, steps);
+        self.zooming = true;
         Ok(())
     }
 
     /// Handle backspace
     pub fn backspace(&mut self) -> Result<()> {
         // Remove the last character from the output text
-        let text = self.model.output_text();
-        if !text.is_empty() {
-            let new_text = text.chars().take(text.chars().count() - 1).collect::<String>();
-            self.model.set_output_text(&new_text);
+        if !self.model.output_text().is_empty() {
+            self.execute_action("backspace")?;
+            self.stats.record_deleted(1);
         }
 
         Ok(())
     }
+
+    /// Undo the most recent text operation
+    ///
+    /// Returns `false` if there was nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        let undone = self.model.undo();
+        self.report_edits();
+        undone
+    }
+
+    /// Redo the most recently undone text operation
+    ///
+    /// Returns `false` if there was nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        let redone = self.model.redo();
+        self.report_edits();
+        redone
+    }
 }
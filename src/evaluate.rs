@@ -0,0 +1,110 @@
+//! # Evaluate Module
+//!
+//! This module measures how well a model's language model predicts a test
+//! corpus, as the average information needed per character. Fewer bits per
+//! character means less zooming to write the same text, so the figures can
+//! be used to compare training corpora and model settings.
+
+use crate::model::DasherModel;
+use crate::{DasherError, Result};
+
+/// The outcome of evaluating a language model over a text
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Evaluation {
+    /// Number of characters predicted
+    pub characters: usize,
+    /// Number of characters skipped because the alphabet cannot write them
+    pub skipped: usize,
+    /// Number of characters the model gave no probability, which are
+    /// charged as the least probable symbol the model can represent
+    pub unpredicted: usize,
+    /// Total information needed to write the predicted characters (in bits)
+    pub bits: f64,
+}
+
+impl Evaluation {
+    /// Get the average information per character (0 if there were none)
+    pub fn bits_per_character(&self) -> f64 {
+        if self.characters == 0 {
+            0.0
+        } else {
+            self.bits / self.characters as f64
+        }
+    }
+
+    /// Get the perplexity, the number of equally likely symbols the model is
+    /// as uncertain as on average
+    pub fn perplexity(&self) -> f64 {
+        self.bits_per_character().exp2()
+    }
+}
+
+/// Evaluate the language model of `model` over `text`
+///
+/// Each character is predicted from the text before it, as when writing
+/// `text` from the start, but the language model learns nothing. Characters
+/// outside the alphabet are skipped.
+pub fn bits_per_character(model: &mut DasherModel, text: &str) -> Result<Evaluation> {
+    let symbols: Vec<char> = model.alphabet()
+        .ok_or_else(|| DasherError::Other("Model has no alphabet".to_string()))?
+        .symbols().iter()
+        .map(|symbol| symbol.character)
+        .collect();
+    let language_model = model.language_model_mut()
+        .ok_or_else(|| DasherError::Other("Model has no language model".to_string()))?;
+
+    let norm = DasherModel::NORMALIZATION;
+    let mut evaluation = Evaluation::default();
+    let context = language_model.create_empty_context();
+    let mut probs = Vec::with_capacity(symbols.len());
+
+    for c in text.chars() {
+        let Some(index) = symbols.iter().position(|&symbol| symbol == c) else {
+            evaluation.skipped += 1;
+            continue;
+        };
+
+        language_model.get_probs(context, norm, &mut probs);
+
+        // A model with no prediction leaves every symbol equally likely
+        let total: u64 = probs.iter().map(|&count| count as u64).sum();
+        let prob = if total == 0 {
+            1.0 / symbols.len() as f64
+        } else if probs[index] == 0 {
+            evaluation.unpredicted += 1;
+            1.0 / total as f64
+        } else {
+            probs[index] as f64 / total as f64
+        };
+        evaluation.bits -= prob.log2();
+        evaluation.characters += 1;
+        language_model.enter_symbol(context, index);
+    }
+
+    language_model.release_context(context);
+    Ok(evaluation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bits_per_character() {
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        let uniform = (model.alphabet().unwrap().symbols().len() as f64).log2();
+
+        let evaluation = bits_per_character(&mut model, "the lazy dog\u{1F600}").unwrap();
+        assert_eq!(evaluation.characters, 12);
+        assert_eq!(evaluation.skipped, 1);
+        assert!(evaluation.bits_per_character() > 0.0);
+        assert!(evaluation.bits_per_character() < uniform);
+        assert!((evaluation.perplexity() - evaluation.bits_per_character().exp2()).abs() < 1e-9);
+
+        // Nothing is learned, so evaluating again gives the same figures
+        assert_eq!(bits_per_character(&mut model, "the lazy dog\u{1F600}").unwrap(), evaluation);
+
+        assert_eq!(bits_per_character(&mut model, "").unwrap().bits_per_character(), 0.0);
+    }
+}
@@ -4,8 +4,9 @@
 //! through the FFI layer.
 
 use crate::view::square::{SquareViewConfig, NodeShape};
-use crate::view::DasherViewSquare;
+use crate::view::{DasherViewSquare, FontDescriptor};
 use crate::api::DasherInterface;
+use super::ColorFFI;
 
 /// Node shape types for FFI
 #[repr(C)]
@@ -83,6 +84,9 @@ pub struct SquareViewConfigFFI {
     /// Font size scaling factor
     pub font_size_scaling: f64,
 
+    /// Font weight of node labels, from 100 to 900 as in CSS
+    pub font_weight: u16,
+
     /// Margin width in abstract screen coordinates
     pub margin_width: i64,
 
@@ -92,9 +96,24 @@ pub struct SquareViewConfigFFI {
     /// Whether to draw the cursor
     pub draw_cursor: bool,
 
+    /// Whether to draw a line from the crosshair to the pointer
+    pub draw_mouse_line: bool,
+
+    /// Colour of the line to the pointer
+    pub mouse_line_color: ColorFFI,
+
+    /// Width of the line to the pointer, in pixels
+    pub mouse_line_width: i32,
+
     /// Whether to draw node outlines
     pub draw_outlines: bool,
 
+    /// Nodes smaller than this (in pixels, in either direction) are not labelled
+    pub min_label_pixels: i32,
+
+    /// Nodes less high than this (in pixels) are not drawn, nor are their descendants
+    pub min_node_pixels: i32,
+
     /// Whether to use the flowing interface (right to left movement)
     pub flowing_interface: bool,
 
@@ -119,10 +138,19 @@ impl From<SquareViewConfigFFI> for SquareViewConfig {
             text_3d_depth: config.text_3d_depth,
             base_font_size: config.base_font_size,
             font_size_scaling: config.font_size_scaling,
+            // The font family is chosen with the FontFamily setting
+            font: FontDescriptor::new(None, config.font_weight),
             margin_width: config.margin_width,
             draw_crosshair: config.draw_crosshair,
             draw_cursor: config.draw_cursor,
+            draw_mouse_line: config.draw_mouse_line,
+            mouse_line_color: config.mouse_line_color.into(),
+            mouse_line_width: config.mouse_line_width,
             draw_outlines: config.draw_outlines,
+            min_label_pixels: config.min_label_pixels,
+            min_node_pixels: config.min_node_pixels,
+            // Group styles come from colour scheme files, not the C configuration
+            group_styles: Default::default(),
             flowing_interface: config.flowing_interface,
             flowing_speed: config.flowing_speed,
             use_ppm: config.use_ppm,
@@ -144,10 +172,16 @@ impl From<SquareViewConfig> for SquareViewConfigFFI {
             text_3d_depth: config.text_3d_depth,
             base_font_size: config.base_font_size,
             font_size_scaling: config.font_size_scaling,
+            font_weight: config.font.weight,
             margin_width: config.margin_width,
             draw_crosshair: config.draw_crosshair,
             draw_cursor: config.draw_cursor,
+            draw_mouse_line: config.draw_mouse_line,
+            mouse_line_color: config.mouse_line_color.into(),
+            mouse_line_width: config.mouse_line_width,
             draw_outlines: config.draw_outlines,
+            min_label_pixels: config.min_label_pixels,
+            min_node_pixels: config.min_node_pixels,
             flowing_interface: config.flowing_interface,
             flowing_speed: config.flowing_speed,
             use_ppm: config.use_ppm,
@@ -194,10 +228,17 @@ pub unsafe fn ffi_to_rust_config(config: &SquareViewConfigFFI) -> SquareViewConf
         text_3d_depth: config.text_3d_depth,
         base_font_size: config.base_font_size,
         font_size_scaling: config.font_size_scaling,
+        font: FontDescriptor::new(None, config.font_weight),
         margin_width: config.margin_width,
         draw_crosshair: config.draw_crosshair,
         draw_cursor: config.draw_cursor,
+        draw_mouse_line: config.draw_mouse_line,
+        mouse_line_color: config.mouse_line_color.into(),
+        mouse_line_width: config.mouse_line_width,
         draw_outlines: config.draw_outlines,
+        min_label_pixels: config.min_label_pixels,
+            min_node_pixels: config.min_node_pixels,
+        group_styles: Default::default(),
         flowing_interface: config.flowing_interface,
         flowing_speed: config.flowing_speed,
         use_ppm: config.use_ppm,
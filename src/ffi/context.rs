@@ -3,12 +3,12 @@
 //! This module provides context information for the FFI layer.
 
 use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
-use std::sync::Mutex;
-use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use std::cell::RefCell;
 use std::rc::Rc;
-use crate::model::node::DasherNode;
+use crate::logging::{self, LogLevel};
 
 /// Global context for FFI operations
 pub struct FFIContext {
@@ -21,25 +21,28 @@ pub struct FFIContext {
     /// Current screen height
     pub screen_height: AtomicI32,
 
-    /// Error messages
-    pub error_messages: Mutex<Vec<String>>,
+    /// Error messages, the most recent [`FFIContext::MAX_MESSAGES`] of them
+    pub error_messages: Mutex<VecDeque<String>>,
 
-    /// Debug messages
-    pub debug_messages: Mutex<Vec<String>>,
+    /// Debug messages, the most recent [`FFIContext::MAX_MESSAGES`] of them
+    pub debug_messages: Mutex<VecDeque<String>>,
 
     /// Node information for debugging
     pub node_info: Mutex<HashMap<String, String>>,
 }
 
 impl FFIContext {
+    /// Most error or debug messages kept; older ones are dropped as new ones arrive
+    pub const MAX_MESSAGES: usize = 256;
+
     /// Create a new FFI context
     pub fn new() -> Self {
         Self {
             debug_mode: AtomicBool::new(false),
             screen_width: AtomicI32::new(0),
             screen_height: AtomicI32::new(0),
-            error_messages: Mutex::new(Vec::new()),
-            debug_messages: Mutex::new(Vec::new()),
+            error_messages: Mutex::new(VecDeque::new()),
+            debug_messages: Mutex::new(VecDeque::new()),
             node_info: Mutex::new(HashMap::new()),
         }
     }
@@ -70,26 +73,36 @@ impl FFIContext {
 
     /// Add an error message
     pub fn add_error(&self, message: &str) {
+        logging::log_message(module_path!(), LogLevel::Error, message);
         if let Ok(mut messages) = self.error_messages.lock() {
-            messages.push(message.to_string());
+            push_message(&mut messages, message);
         }
     }
 
     /// Add a debug message
     pub fn add_debug(&self, message: &str) {
         if self.get_debug_mode() {
+            logging::log_message(module_path!(), LogLevel::Debug, message);
             if let Ok(mut messages) = self.debug_messages.lock() {
-                messages.push(message.to_string());
+                push_message(&mut messages, message);
             }
         }
     }
 
+    /// Get all error messages, keeping them
+    pub fn error_messages(&self) -> Vec<String> {
+        self.error_messages.lock().map(|messages| messages.iter().cloned().collect()).unwrap_or_default()
+    }
+
+    /// Get all debug messages, keeping them
+    pub fn debug_messages(&self) -> Vec<String> {
+        self.debug_messages.lock().map(|messages| messages.iter().cloned().collect()).unwrap_or_default()
+    }
+
     /// Get all error messages and clear the list
     pub fn get_errors(&self) -> Vec<String> {
         if let Ok(mut messages) = self.error_messages.lock() {
-            let result = messages.clone();
-            messages.clear();
-            result
+            messages.drain(..).collect()
         } else {
             Vec::new()
         }
@@ -98,9 +111,7 @@ impl FFIContext {
     /// Get all debug messages and clear the list
     pub fn get_debug_messages(&self) -> Vec<String> {
         if let Ok(mut messages) = self.debug_messages.lock() {
-            let result = messages.clone();
-            messages.clear();
-            result
+            messages.drain(..).collect()
         } else {
             Vec::new()
         }
@@ -132,27 +143,25 @@ impl FFIContext {
     }
 }
 
-/// Global FFI context
-static mut GLOBAL_CONTEXT: Option<Arc<FFIContext>> = None;
+/// Append a message to a history, dropping the oldest once it is full
+fn push_message(messages: &mut VecDeque<String>, message: &str) {
+    if messages.len() >= FFIContext::MAX_MESSAGES {
+        messages.pop_front();
+    }
+    messages.push_back(message.to_string());
+}
+
+/// Global FFI context, shared by every thread
+static GLOBAL_CONTEXT: OnceLock<Arc<FFIContext>> = OnceLock::new();
 
-/// Initialize the global FFI context
+/// Initialize the global FFI context, if no thread has yet
 pub fn init_global_context() -> Arc<FFIContext> {
-    let context = Arc::new(FFIContext::new());
-    unsafe {
-        GLOBAL_CONTEXT = Some(context.clone());
-    }
-    context
+    GLOBAL_CONTEXT.get_or_init(|| Arc::new(FFIContext::new())).clone()
 }
 
 /// Get the global FFI context
 pub fn get_global_context() -> Arc<FFIContext> {
-    unsafe {
-        if let Some(context) = &GLOBAL_CONTEXT {
-            context.clone()
-        } else {
-            init_global_context()
-        }
-    }
+    init_global_context()
 }
 
 /// Enhanced callback context for drawing operations
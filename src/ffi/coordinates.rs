@@ -41,7 +41,7 @@ pub fn dasher_to_screen(
             // In LTR orientation:
             // - x=0 in Dasher is the right edge of the screen
             // - x increases to the left in Dasher, but to the right on screen
-            // - y=0 in Dasher is the vertical center of the screen
+            // - y=ORIGIN_Y in Dasher is the vertical center of the screen
             // - y increases upward in Dasher, but downward on screen
             
             // Scale factors
@@ -58,7 +58,7 @@ pub fn dasher_to_screen(
             // In RTL orientation:
             // - x=0 in Dasher is the left edge of the screen
             // - x increases to the right in Dasher, and to the left on screen
-            // - y=0 in Dasher is the vertical center of the screen
+            // - y=ORIGIN_Y in Dasher is the vertical center of the screen
             // - y increases upward in Dasher, but downward on screen
             
             // Scale factors
@@ -75,7 +75,7 @@ pub fn dasher_to_screen(
             // In TTB orientation:
             // - y=0 in Dasher is the bottom edge of the screen
             // - y increases upward in Dasher, but downward on screen
-            // - x=0 in Dasher is the horizontal center of the screen
+            // - y=ORIGIN_Y in Dasher is the horizontal center of the screen
             // - x increases to the right in Dasher, and to the right on screen
             
             // Scale factors
@@ -92,7 +92,7 @@ pub fn dasher_to_screen(
             // In BTT orientation:
             // - y=0 in Dasher is the top edge of the screen
             // - y increases downward in Dasher, and upward on screen
-            // - x=0 in Dasher is the horizontal center of the screen
+            // - y=ORIGIN_Y in Dasher is the horizontal center of the screen
             // - x increases to the right in Dasher, and to the right on screen
             
             // Scale factors
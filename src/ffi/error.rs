@@ -0,0 +1,151 @@
+//! # Error Module for FFI
+//!
+//! This module keeps panics from unwinding across the FFI boundary and
+//! records the last error so hosts can retrieve a description of it.
+//! Panics can only be caught if the library is built with `panic = "unwind"`.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::ffi::{c_char, CString};
+use std::panic::{self, AssertUnwindSafe};
+
+use super::context;
+use crate::DasherError;
+
+/// Error codes returned by the FFI functions
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DasherErrorCode {
+    Success = 0,
+    InvalidParameter = 1,
+    RenderingError = 2,
+    InputError = 3,
+    SettingsError = 4,
+    Other = 5,
+    /// A panic was caught before it could cross the FFI boundary
+    Panic = 6,
+}
+
+impl From<&DasherError> for DasherErrorCode {
+    fn from(error: &DasherError) -> Self {
+        match error {
+            DasherError::InvalidParameter(_) => DasherErrorCode::InvalidParameter,
+            DasherError::RenderingError(_) => DasherErrorCode::RenderingError,
+            DasherError::InputError(_) => DasherErrorCode::InputError,
+            DasherError::SettingsError(_) => DasherErrorCode::SettingsError,
+            DasherError::Other(_) => DasherErrorCode::Other,
+        }
+    }
+}
+
+thread_local! {
+    /// Description of the last error raised by an FFI call on this thread
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Record an error message for the current thread
+pub(crate) fn set_last_error(message: &str) {
+    context::get_global_context().add_error(message);
+    let c_message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(c_message));
+}
+
+/// Forget the last error recorded for the current thread
+pub(crate) fn clear_last_error() {
+    LAST_ERROR.with(|e| *e.borrow_mut() = None);
+}
+
+/// Get a pointer to the last error message, or null if there is none
+pub(crate) fn last_error_ptr() -> *const c_char {
+    LAST_ERROR.with(|e| {
+        e.borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Extract a readable message from a panic payload
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        (*message).to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// Run the body of an FFI function, catching panics and recording errors.
+///
+/// Returns the body's value on success or the error code describing the failure.
+fn run<T, F>(f: F) -> Result<T, DasherErrorCode>
+where
+    F: FnOnce() -> crate::Result<T>,
+{
+    clear_last_error();
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(error)) => {
+            set_last_error(&error.to_string());
+            Err((&error).into())
+        }
+        Err(payload) => {
+            set_last_error(&format!("Panic: {}", panic_message(payload.as_ref())));
+            Err(DasherErrorCode::Panic)
+        }
+    }
+}
+
+/// Run the body of an FFI function that reports its outcome as an error code
+pub(crate) fn guard<F>(f: F) -> DasherErrorCode
+where
+    F: FnOnce() -> crate::Result<()>,
+{
+    run(f).err().unwrap_or(DasherErrorCode::Success)
+}
+
+/// Run the body of an FFI function that returns a value, using `default` on failure
+pub(crate) fn guard_or<T, F>(default: T, f: F) -> T
+where
+    F: FnOnce() -> crate::Result<T>,
+{
+    run(f).unwrap_or(default)
+}
+
+/// Convert a raw pointer into a mutable reference, failing if it is null
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a valid, live `T` that is not aliased for `'a`.
+pub(crate) unsafe fn non_null<'a, T>(ptr: *mut T, name: &str) -> crate::Result<&'a mut T> {
+    ptr.as_mut()
+        .ok_or_else(|| DasherError::InvalidParameter(format!("{} pointer is null", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    #[test]
+    fn test_guard_maps_errors_and_panics() {
+        assert_eq!(guard(|| Ok(())), DasherErrorCode::Success);
+        assert!(last_error_ptr().is_null());
+
+        let code = guard(|| Err(DasherError::SettingsError("bad value".to_string())));
+        assert_eq!(code, DasherErrorCode::SettingsError);
+        let message = unsafe { CStr::from_ptr(last_error_ptr()) };
+        assert_eq!(message.to_str().unwrap(), "Settings error: bad value");
+
+        let value = guard_or(-1, || -> crate::Result<i32> { panic!("boom") });
+        assert_eq!(value, -1);
+        let message = unsafe { CStr::from_ptr(last_error_ptr()) };
+        assert_eq!(message.to_str().unwrap(), "Panic: boom");
+    }
+
+    #[test]
+    fn test_non_null_rejects_null() {
+        let result = unsafe { non_null::<i32>(std::ptr::null_mut(), "interface") };
+        assert!(matches!(result, Err(DasherError::InvalidParameter(_))));
+    }
+}
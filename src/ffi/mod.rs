@@ -6,22 +6,31 @@
 mod coordinates;
 mod config;
 pub mod context;
+mod error;
+mod settings;
 
 pub use coordinates::*;
 pub use config::*;
 pub use context::*;
+pub use error::DasherErrorCode;
+pub use settings::ParameterFFI;
 
+use crate::action::{ClipboardProvider, SpeechProvider};
+use crate::alphabet::{ConversionCandidate, ConversionProvider};
 use crate::api::DasherInterface;
-use crate::input::{DasherInput, MouseInput, VirtualKey};
-use crate::settings::Settings;
-use crate::view::{DasherScreen, Color, Label};
+use crate::input::{ButtonRegion, Calibration, DasherInput, DemoDynamicFilter, DemoDynamicFilterConfig, DynamicFilterConfig, JoystickConfig, JoystickInput, KeyboardSteeringFilter, MouseInput, PressureCalibration, TouchInput, VirtualKey};
+use crate::settings::{ParameterType, Settings};
+use crate::view::{DasherScreen, Color, FontDescriptor, Label, Orientation, VisibleNode};
 use crate::view::square::{DasherViewSquare, SquareViewConfig, NodeShape};
-use std::ffi::{c_char, CStr};
+use crate::logging::{self, dasher_log, LogLevel};
+use crate::DasherError;
+use std::ffi::{c_char, CStr, CString};
 
 // Simple implementation of Label for FFI
 struct SimpleLabel {
     text: String,
     wrap_size: u32,
+    font: Option<FontDescriptor>,
 }
 
 impl SimpleLabel {
@@ -29,6 +38,7 @@ impl SimpleLabel {
         Self {
             text: text.to_string(),
             wrap_size,
+            font: None,
         }
     }
 }
@@ -41,6 +51,10 @@ impl Label for SimpleLabel {
     fn get_wrap_size(&self) -> u32 {
         self.wrap_size
     }
+
+    fn font(&self) -> Option<&FontDescriptor> {
+        self.font.as_ref()
+    }
 }
 
 // Simple implementation of DasherScreen for FFI
@@ -65,6 +79,11 @@ struct SimpleDasherScreen {
     make_label_fn: Option<extern "C" fn(text: *const c_char, size: i32) -> *mut std::ffi::c_void>,
     destroy_label_fn: Option<extern "C" fn(label: *mut std::ffi::c_void)>,
     get_text_size_fn: Option<extern "C" fn(label: *mut std::ffi::c_void, size: i32, width: *mut i32, height: *mut i32)>,
+    draw_polygon_fn: Option<extern "C" fn(points: *const PointFFI, num_points: i32,
+                                        fill_r: u8, fill_g: u8, fill_b: u8, fill_a: u8,
+                                        outline_r: u8, outline_g: u8, outline_b: u8, outline_a: u8,
+                                        line_width: i32)>,
+    set_font_fn: Option<extern "C" fn(family: *const c_char, weight: i32)>,
 }
 
 impl SimpleDasherScreen {
@@ -79,6 +98,8 @@ impl SimpleDasherScreen {
             make_label_fn: None,
             destroy_label_fn: None,
             get_text_size_fn: None,
+            draw_polygon_fn: None,
+            set_font_fn: None,
         }
     }
 
@@ -118,6 +139,26 @@ impl SimpleDasherScreen {
     fn set_get_text_size_fn(&mut self, f: extern "C" fn(label: *mut std::ffi::c_void, size: i32, width: *mut i32, height: *mut i32)) {
         self.get_text_size_fn = Some(f);
     }
+
+    fn set_draw_polygon_fn(&mut self, f: extern "C" fn(points: *const PointFFI, num_points: i32,
+                                                     fill_r: u8, fill_g: u8, fill_b: u8, fill_a: u8,
+                                                     outline_r: u8, outline_g: u8, outline_b: u8, outline_a: u8,
+                                                     line_width: i32)) {
+        self.draw_polygon_fn = Some(f);
+    }
+
+    fn set_set_font_fn(&mut self, f: extern "C" fn(family: *const c_char, weight: i32)) {
+        self.set_font_fn = Some(f);
+    }
+
+    /// Tell the host which font the next label is measured or drawn in
+    fn select_font(&self, label: &dyn Label) {
+        let (Some(f), Some(font)) = (self.set_font_fn, label.font()) else {
+            return;
+        };
+        let family = font.family.as_deref().and_then(|family| CString::new(family).ok());
+        f(family.as_ref().map_or(std::ptr::null(), |family| family.as_ptr()), font.weight as i32);
+    }
 }
 
 impl DasherScreen for SimpleDasherScreen {
@@ -166,6 +207,15 @@ impl DasherScreen for SimpleDasherScreen {
         Box::new(SimpleLabel::new(text, wrap_size))
     }
 
+    fn make_font_label(&self, text: &str, wrap_size: u32, font: &FontDescriptor) -> Box<dyn Label> {
+        let label = self.make_label(text, wrap_size);
+        Box::new(SimpleLabel {
+            text: label.get_text().to_string(),
+            wrap_size: label.get_wrap_size(),
+            font: Some(font.clone()),
+        })
+    }
+
     fn text_size(&self, label: &dyn Label, font_size: u32) -> (i32, i32) {
         // Get the global context
         let context = context::get_global_context();
@@ -176,6 +226,8 @@ impl DasherScreen for SimpleDasherScreen {
         }
 
         if let Some(f) = self.get_text_size_fn {
+            self.select_font(label);
+
             // Create a new SimpleLabel that wraps the C label
             let c_text = std::ffi::CString::new(label.get_text()).unwrap_or_default();
             let label_ptr = if let Some(make_label) = self.make_label_fn {
@@ -240,6 +292,7 @@ impl DasherScreen for SimpleDasherScreen {
             let c_text = std::ffi::CString::new(label.get_text()).unwrap_or_default();
 
             // Call the C function
+            self.select_font(label);
             f(c_text.as_ptr(), x, y, font_size as i32, color.r, color.g, color.b, color.a);
         } else {
             // Fallback to a simple implementation
@@ -345,6 +398,49 @@ impl DasherScreen for SimpleDasherScreen {
         }
     }
 
+    fn draw_polygon(&mut self, points: &[(i32, i32)], fill_color: Color, outline_color: Color, line_width: i32) {
+        // Get the global context
+        let context = context::get_global_context();
+
+        // Get the current drawing context
+        let drawing_context = context::get_current_drawing_context();
+
+        // Log debug information
+        if context.get_debug_mode() {
+            context.add_debug(&format!(
+                "draw_polygon: points={}, fill=({},{},{},{}), outline=({},{},{},{}), width={}, node={}",
+                points.len(),
+                fill_color.r, fill_color.g, fill_color.b, fill_color.a,
+                outline_color.r, outline_color.g, outline_color.b, outline_color.a,
+                line_width,
+                drawing_context.node_id
+            ));
+        }
+
+        if points.len() < 3 {
+            return;
+        }
+
+        if let Some(f) = self.draw_polygon_fn {
+            let ffi_points: Vec<PointFFI> = points.iter().map(|&(x, y)| PointFFI { x, y }).collect();
+            f(ffi_points.as_ptr(), ffi_points.len() as i32,
+              fill_color.r, fill_color.g, fill_color.b, fill_color.a,
+              outline_color.r, outline_color.g, outline_color.b, outline_color.a,
+              line_width);
+        } else {
+            // Fallback to drawing the outline as line segments
+            if context.get_debug_mode() {
+                context.add_debug("draw_polygon: No C function registered, drawing outline");
+            }
+
+            for i in 0..points.len() {
+                let (x1, y1) = points[i];
+                let (x2, y2) = points[(i + 1) % points.len()];
+                self.draw_line(x1, y1, x2, y2, outline_color, line_width);
+            }
+        }
+    }
+
     fn display(&mut self) {
         // Get the global context
         let context = context::get_global_context();
@@ -373,23 +469,18 @@ pub struct DasherInterfaceFFI {
     interface: DasherInterface,
 }
 
+/// Initial settings for `dasher_interface_create`
+///
+/// Individual parameters are read and changed afterwards through the
+/// `dasher_settings_*` functions.
 #[repr(C)]
 pub struct DasherSettingsFFI {
     // TODO: Define FFI-compatible settings structure
 }
 
-#[repr(C)]
-pub enum DasherErrorCode {
-    Success = 0,
-    InvalidParameter = 1,
-    RenderingError = 2,
-    InputError = 3,
-    SettingsError = 4,
-    Other = 5,
-}
-
 /// Color representation for FFI
 #[repr(C)]
+#[derive(Clone, Copy)]
 pub struct ColorFFI {
     pub r: u8,
     pub g: u8,
@@ -414,6 +505,31 @@ impl From<Color> for ColorFFI {
     }
 }
 
+/// Point in screen coordinates, passed to the polygon callback
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PointFFI {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// A node the view would draw, passed to the visible node callback
+///
+/// `label` is null for a node too small to be labelled, and is only valid
+/// during the callback.
+#[repr(C)]
+pub struct VisibleNodeFFI {
+    pub x1: i32,
+    pub y1: i32,
+    pub x2: i32,
+    pub y2: i32,
+    pub label: *const c_char,
+    pub foreground: ColorFFI,
+    pub background: ColorFFI,
+    pub shape: NodeShapeFFI,
+    pub depth: i32,
+}
+
 /// Opaque handle to a DasherInput
 #[repr(C)]
 pub struct DasherInputFFI {
@@ -426,35 +542,58 @@ pub struct DasherScreenFFI {
     screen: SimpleDasherScreen,
 }
 
+/// Create a Dasher interface with the built-in English alphabet
+///
+/// No files are read; hosts load their alphabet and training text with
+/// `dasher_interface_load_alphabet_file` and `dasher_interface_train_file`,
+/// or opt in to `dasher_interface_load_default_data`.
 #[no_mangle]
 pub extern "C" fn dasher_interface_create(
     settings: *const DasherSettingsFFI
 ) -> *mut DasherInterfaceFFI {
-    println!("FFI: Creating DasherInterface");
+    error::guard_or(std::ptr::null_mut(), || {
+        dasher_log!(Debug, "Creating DasherInterface");
 
-    // TODO: Implement proper settings conversion
-    let settings = if settings.is_null() {
-        println!("FFI: Using default settings");
-        Settings::new()
-    } else {
-        println!("FFI: Converting FFI settings to Rust settings");
-        // Convert FFI settings to Rust settings
-        Settings::new()
-    };
+        // TODO: Implement proper settings conversion
+        let settings = if settings.is_null() {
+            dasher_log!(Debug, "Using default settings");
+            Settings::new()
+        } else {
+            dasher_log!(Debug, "Converting FFI settings to Rust settings");
+            // Convert FFI settings to Rust settings
+            Settings::new()
+        };
+
+        dasher_log!(Debug, "Creating DasherInterface with settings");
+        let mut interface = DasherInterface::new(settings);
+
+        // Initialize the model
+        dasher_log!(Debug, "Initializing model");
+        if let Err(e) = interface.model_mut().initialize() {
+            dasher_log!(Error, "Failed to initialize model: {:?}", e);
+            return Err(e);
+        }
+        dasher_log!(Debug, "Model initialized successfully");
 
-    println!("FFI: Creating DasherInterface with settings");
-    let mut interface = DasherInterface::new(settings);
+        // Create a default view if none exists
+        if interface.view().is_none() {
+            dasher_log!(Debug, "Creating default view");
+            // We'll create a view when the screen is set
+        }
 
-    // Initialize the model
-    println!("FFI: Initializing model");
-    if let Err(e) = interface.model_mut().initialize() {
-        println!("FFI: Failed to initialize model: {:?}", e);
-        return std::ptr::null_mut();
-    }
-    println!("FFI: Model initialized successfully");
+        dasher_log!(Info, "DasherInterface created successfully");
+        Ok(Box::into_raw(Box::new(DasherInterfaceFFI { interface })))
+    })
+}
 
+/// Load the English alphabet and training text from the first of the data
+/// directories DasherUI uses, relative to the working directory, that has them
+///
+/// An alphabet loaded from file is trained from its own training file. What
+/// cannot be found or loaded is logged and skipped.
+fn load_default_data(interface: &mut DasherInterface) {
     // Try different paths for the English alphabet
-    println!("FFI: Loading alphabet");
+    dasher_log!(Debug, "Loading alphabet");
     let alphabet_paths = [
         "data/alphabets/alphabet.english.xml",
         "DasherUI/Data/alphabet.english.with.limited.punctuation.xml",
@@ -465,80 +604,87 @@ pub extern "C" fn dasher_interface_create(
     let mut alphabet_loaded = false;
     for &path in &alphabet_paths {
         let alphabet_path = std::path::Path::new(path);
-        println!("FFI: Trying alphabet path: {}", path);
+        dasher_log!(Debug, "Trying alphabet path: {}", path);
         if alphabet_path.exists() {
-            println!("FFI: Alphabet path exists: {}", path);
-            match crate::alphabet::load_alphabet(alphabet_path) {
-                Ok(alphabet_info) => {
-                    println!("FFI: Loaded alphabet from {}", path);
-                    let alphabet = crate::alphabet::Alphabet::from_info(alphabet_info);
-                    interface.model_mut().set_alphabet(alphabet);
+            dasher_log!(Debug, "Alphabet path exists: {}", path);
+            let result = crate::alphabet::load_alphabet(alphabet_path)
+                .map_err(|e| DasherError::InvalidParameter(e.to_string()))
+                .and_then(|alphabet_info| interface.load_alphabet(alphabet_info));
+            match result {
+                Ok(()) => {
+                    dasher_log!(Info, "Loaded alphabet from {}", path);
                     alphabet_loaded = true;
                     break;
                 }
                 Err(e) => {
-                    println!("FFI: Failed to load alphabet from {}: {:?}", path, e);
+                    dasher_log!(Warn, "Failed to load alphabet from {}: {:?}", path, e);
                 }
             }
         } else {
-            println!("FFI: Alphabet path does not exist: {}", path);
+            dasher_log!(Debug, "Alphabet path does not exist: {}", path);
         }
     }
 
-    // If no alphabet was loaded, create a default English alphabet
+    // An alphabet loaded from file is trained from its own training file;
+    // otherwise try different paths for the English training data
     if !alphabet_loaded {
-        println!("FFI: Using default English alphabet");
-        let alphabet = crate::alphabet::Alphabet::english();
-        interface.model_mut().set_alphabet(alphabet);
-    }
-
-    // Try different paths for the training data
-    println!("FFI: Loading training data");
-    let training_paths = [
-        "data/training/training_english_GB.txt",
-        "DasherUI/Data/training_english_GB.txt",
-        "DasherUI-main/build/DasherUI/Data/training_english_GB.txt",
-        "./DasherUI/Data/training_english_GB.txt"
-    ];
-
-    let mut training_loaded = false;
-    for &path in &training_paths {
-        let training_path = std::path::Path::new(path);
-        println!("FFI: Trying training path: {}", path);
-        if training_path.exists() {
-            println!("FFI: Training path exists: {}", path);
-            match std::fs::read_to_string(training_path) {
-                Ok(training_text) => {
-                    println!("FFI: Loaded training data from {}", path);
-                    println!("FFI: Training language model with {} characters", training_text.len());
-                    // Train the language model with the text
-                    for c in training_text.chars() {
-                        interface.model_mut().update_language_model(c);
+        dasher_log!(Debug, "Loading training data");
+        let training_paths = [
+            "data/training/training_english_GB.txt",
+            "DasherUI/Data/training_english_GB.txt",
+            "DasherUI-main/build/DasherUI/Data/training_english_GB.txt",
+            "./DasherUI/Data/training_english_GB.txt"
+        ];
+
+        let mut training_loaded = false;
+        for &path in &training_paths {
+            let training_path = std::path::Path::new(path);
+            dasher_log!(Debug, "Trying training path: {}", path);
+            if training_path.exists() {
+                dasher_log!(Debug, "Training path exists: {}", path);
+                let result = std::fs::File::open(training_path)
+                    .map_err(|e| DasherError::Other(e.to_string()))
+                    .and_then(|file| interface.train(std::io::BufReader::new(file), |_| true));
+                match result {
+                    Ok(stats) => {
+                        dasher_log!(Info, "Trained language model with {} characters from {}", stats.total_chars, path);
+                        training_loaded = true;
+                        break;
+                    }
+                    Err(e) => {
+                        dasher_log!(Warn, "Failed to load training data from {}: {:?}", path, e);
                     }
-                    training_loaded = true;
-                    break;
-                }
-                Err(e) => {
-                    println!("FFI: Failed to load training data from {}: {:?}", path, e);
                 }
+            } else {
+                dasher_log!(Debug, "Training path does not exist: {}", path);
             }
-        } else {
-            println!("FFI: Training path does not exist: {}", path);
         }
-    }
-
-    if !training_loaded {
-        println!("FFI: No training data loaded");
-    }
 
-    // Create a default view if none exists
-    if interface.view().is_none() {
-        println!("FFI: Creating default view");
-        // We'll create a view when the screen is set
+        if !training_loaded {
+            dasher_log!(Info, "No training data loaded");
+        }
     }
+}
 
-    println!("FFI: DasherInterface created successfully");
-    Box::into_raw(Box::new(DasherInterfaceFFI { interface }))
+/// Load the English alphabet and training text from the data directories
+/// DasherUI uses (`data/alphabets`, `data/training` and `DasherUI/Data`,
+/// relative to the working directory)
+///
+/// `dasher_interface_create` reads no files itself. Files that cannot be
+/// found or loaded are skipped, leaving the alphabet and training as they were.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_load_default_data(
+    interface: *mut DasherInterfaceFFI
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        load_default_data(&mut interface.interface);
+        Ok(())
+    })
 }
 
 /// # Safety
@@ -548,13 +694,18 @@ pub extern "C" fn dasher_interface_create(
 /// the pointer is no longer valid and should not be used.
 #[no_mangle]
 pub unsafe extern "C" fn dasher_interface_destroy(interface: *mut DasherInterfaceFFI) {
-    if !interface.is_null() {
-        let _ = Box::from_raw(interface);
-    }
+    error::guard(|| {
+        if !interface.is_null() {
+            let _ = Box::from_raw(interface);
+        }
+        Ok(())
+    });
 }
 
 /// Process a new frame in the Dasher interface
 ///
+/// Returns true if the frame was rendered.
+///
 /// # Safety
 ///
 /// The `interface` pointer must be a valid pointer to a `DasherInterfaceFFI` object
@@ -564,18 +715,49 @@ pub unsafe extern "C" fn dasher_interface_new_frame(
     interface: *mut DasherInterfaceFFI,
     time_ms: u64
 ) -> bool {
-    if interface.is_null() {
-        return false;
-    }
-
-    (*interface).interface.new_frame(time_ms)
+    error::guard_or(false, || {
+        let interface = error::non_null(interface, "interface")?;
+        Ok(interface.interface.new_frame(time_ms))
+    })
 }
 
 /// Create a mouse input device
 #[no_mangle]
 pub extern "C" fn dasher_create_mouse_input() -> *mut DasherInputFFI {
-    let input = Box::new(MouseInput::new()) as Box<dyn DasherInput>;
-    Box::into_raw(Box::new(DasherInputFFI { input }))
+    error::guard_or(std::ptr::null_mut(), || {
+        let input = Box::new(MouseInput::new()) as Box<dyn DasherInput>;
+        Ok(Box::into_raw(Box::new(DasherInputFFI { input })))
+    })
+}
+
+/// Create a touch screen input device
+///
+/// Holding a finger still starts or stops Dasher and touching with a second
+/// finger pauses or resumes; pass the touch events with
+/// `dasher_interface_touch_down`, `_move` and `_up`.
+#[no_mangle]
+pub extern "C" fn dasher_create_touch_input() -> *mut DasherInputFFI {
+    error::guard_or(std::ptr::null_mut(), || {
+        let mut touch = TouchInput::default();
+        touch.activate();
+        let input = Box::new(touch) as Box<dyn DasherInput>;
+        Ok(Box::into_raw(Box::new(DasherInputFFI { input })))
+    })
+}
+
+/// Create a gamepad or joystick input device
+///
+/// Deflection below `dead_zone` (from 0 to 1) is ignored, and the rest is
+/// raised to the power `exponent`. Buttons 0, 1 and 2 start and stop, and
+/// send the primary and secondary input, until mapped otherwise.
+#[no_mangle]
+pub extern "C" fn dasher_create_joystick_input(dead_zone: f64, exponent: f64) -> *mut DasherInputFFI {
+    error::guard_or(std::ptr::null_mut(), || {
+        let mut joystick = JoystickInput::new(JoystickConfig { dead_zone, exponent, ..JoystickConfig::default() });
+        joystick.activate();
+        let input = Box::new(joystick) as Box<dyn DasherInput>;
+        Ok(Box::into_raw(Box::new(DasherInputFFI { input })))
+    })
 }
 
 /// Destroy an input device
@@ -587,934 +769,3843 @@ pub extern "C" fn dasher_create_mouse_input() -> *mut DasherInputFFI {
 /// the pointer is no longer valid and should not be used.
 #[no_mangle]
 pub unsafe extern "C" fn dasher_destroy_input(input: *mut DasherInputFFI) {
-    if !input.is_null() {
-        let _ = Box::from_raw(input);
-    }
+    error::guard(|| {
+        if !input.is_null() {
+            let _ = Box::from_raw(input);
+        }
+        Ok(())
+    });
 }
 
 /// Set the input device for a DasherInterface
+///
+/// # Safety
+///
+/// `interface` and `input` must be valid pointers created by this library.
 #[no_mangle]
 pub unsafe extern "C" fn dasher_interface_set_input(
     interface: *mut DasherInterfaceFFI,
     input: *mut DasherInputFFI
-) -> bool {
-    if interface.is_null() || input.is_null() {
-        return false;
-    }
-
-    let input_box = Box::from_raw(input);
-    (*interface).interface.set_input(input_box.input.box_clone());
-    // Don't drop the input, just forget the box
-    std::mem::forget(input_box);
-    true
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let input = error::non_null(input, "input")?;
+        interface.interface.set_input(input.input.box_clone());
+        Ok(())
+    })
 }
 
 /// Set mouse coordinates for a mouse input device
+///
+/// The interface keeps its own copy of a device given to it with
+/// `dasher_interface_set_input`, so this only moves the pointer of devices
+/// not yet given to an interface. Use `dasher_interface_set_mouse_position`
+/// to move the pointer of the interface's device.
+///
+/// # Safety
+///
+/// `input` must be a valid pointer created by `dasher_create_mouse_input`.
 #[no_mangle]
 pub unsafe extern "C" fn dasher_set_mouse_coordinates(
     input: *mut DasherInputFFI,
     x: i32,
     y: i32
-) -> bool {
-    if input.is_null() {
-        return false;
-    }
-
-    // For now, we'll assume it's a MouseInput since that's all we're using
-    // In a more complete implementation, we'd need a way to check the type
-    let input_ref = &mut (*input).input;
-
-    // Check if the name contains "Mouse" as a simple heuristic
-    if input_ref.get_name().contains("Mouse") {
-        // Create a new MouseInput with the updated coordinates
-        let mut new_mouse = MouseInput::new();
-        new_mouse.set_coordinates(x, y);
-
-        // Replace the input with the new one
-        *input_ref = Box::new(new_mouse);
-        true
-    } else {
-        false
-    }
+) -> DasherErrorCode {
+    error::guard(|| {
+        let input = &mut error::non_null(input, "input")?.input;
+        let name = input.get_name().to_string();
+        let mouse = input.as_any_mut()
+            .and_then(|input| input.downcast_mut::<MouseInput>())
+            .ok_or_else(|| DasherError::InputError(format!("{} is not a mouse input", name)))?;
+        mouse.set_coordinates(x, y);
+        Ok(())
+    })
 }
 
-/// Handle a key down event
+/// Move the pointer of the interface's input device, in screen coordinates
+///
+/// The position is recorded along with the other input events while recording.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
 #[no_mangle]
-pub unsafe extern "C" fn dasher_interface_key_down(
+pub unsafe extern "C" fn dasher_interface_set_mouse_position(
     interface: *mut DasherInterfaceFFI,
-    time_ms: u64,
-    key: i32
-) {
-    if interface.is_null() {
-        return;
-    }
-
-    let virtual_key = match key {
-        0 => VirtualKey::PrimaryInput,
-        1 => VirtualKey::SecondaryInput,
-        2 => VirtualKey::TertiaryInput,
-        3 => VirtualKey::StartStopKey,
-        4 => VirtualKey::Button1,
-        5 => VirtualKey::Button2,
-        6 => VirtualKey::Button3,
-        7 => VirtualKey::Button4,
-        8 => VirtualKey::Button5,
-        9 => VirtualKey::Left,
-        10 => VirtualKey::Right,
-        11 => VirtualKey::Up,
-        12 => VirtualKey::Down,
-        13 => VirtualKey::Delete,
-        14 => VirtualKey::Backspace,
-        15 => VirtualKey::Tab,
-        16 => VirtualKey::Return,
-        17 => VirtualKey::Escape,
-        18 => VirtualKey::Space,
-        _ => VirtualKey::Other(' '),
-    };
-    (*interface).interface.key_down(time_ms, virtual_key);
+    x: i32,
+    y: i32
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        interface.interface.set_mouse_position(x, y)
+    })
 }
 
-/// Handle a key up event
+/// Add a copy of an input device to a DasherInterface under `name`, alongside its other devices
+///
+/// Key events reach every device, while the pointer is taken from the device
+/// named by the `PrimaryInputDevice` setting (the first device added by default).
+///
+/// # Safety
+///
+/// `interface` and `input` must be valid pointers created by this library,
+/// and `name` a valid null-terminated UTF-8 string.
 #[no_mangle]
-pub unsafe extern "C" fn dasher_interface_key_up(
+pub unsafe extern "C" fn dasher_interface_add_input(
     interface: *mut DasherInterfaceFFI,
-    time_ms: u64,
-    key: i32
-) {
-    if interface.is_null() {
-        return;
-    }
-
-    let virtual_key = match key {
-        0 => VirtualKey::PrimaryInput,
-        1 => VirtualKey::SecondaryInput,
-        2 => VirtualKey::TertiaryInput,
-        3 => VirtualKey::StartStopKey,
-        4 => VirtualKey::Button1,
-        5 => VirtualKey::Button2,
-        6 => VirtualKey::Button3,
-        7 => VirtualKey::Button4,
-        8 => VirtualKey::Button5,
-        9 => VirtualKey::Left,
-        10 => VirtualKey::Right,
-        11 => VirtualKey::Up,
-        12 => VirtualKey::Down,
-        13 => VirtualKey::Delete,
-        14 => VirtualKey::Backspace,
-        15 => VirtualKey::Tab,
-        16 => VirtualKey::Return,
-        17 => VirtualKey::Escape,
-        18 => VirtualKey::Space,
-        _ => VirtualKey::Other(' '),
-    };
-    (*interface).interface.key_up(time_ms, virtual_key);
+    name: *const c_char,
+    input: *mut DasherInputFFI
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let name = c_str_arg(name, "name")?;
+        let input = error::non_null(input, "input")?;
+        interface.interface.add_input(name, input.input.box_clone());
+        Ok(())
+    })
 }
 
-/// Start Dasher
+/// Remove the input device added to a DasherInterface under `name`
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`,
+/// and `name` a valid null-terminated UTF-8 string.
 #[no_mangle]
-pub unsafe extern "C" fn dasher_interface_start(interface: *mut DasherInterfaceFFI) {
-    if interface.is_null() {
-        return;
-    }
-
-    (*interface).interface.start();
+pub unsafe extern "C" fn dasher_interface_remove_input(
+    interface: *mut DasherInterfaceFFI,
+    name: *const c_char
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let name = c_str_arg(name, "name")?;
+        interface.interface.remove_input(name)
+    })
 }
 
-/// Stop Dasher
+/// Move the pointer of the input device added to a DasherInterface under `name`
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`,
+/// and `name` a valid null-terminated UTF-8 string.
 #[no_mangle]
-pub unsafe extern "C" fn dasher_interface_stop(interface: *mut DasherInterfaceFFI) {
-    if interface.is_null() {
-        return;
-    }
-
-    (*interface).interface.stop();
+pub unsafe extern "C" fn dasher_interface_set_device_position(
+    interface: *mut DasherInterfaceFFI,
+    name: *const c_char,
+    x: i32,
+    y: i32
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let name = c_str_arg(name, "name")?;
+        interface.interface.set_device_position(name, x, y)
+    })
 }
 
-/// Pause Dasher
+/// Set the direction text is written in
+///
+/// `orientation` is 0 for left to right, 1 for right to left, 2 for top to
+/// bottom, 3 for bottom to top or -1 for the direction the alphabet
+/// declares. Like the other view setters, the value is kept in the settings
+/// and applied to the current and any later view.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
 #[no_mangle]
-pub unsafe extern "C" fn dasher_interface_pause(interface: *mut DasherInterfaceFFI) {
-    if interface.is_null() {
-        return;
-    }
-
-    (*interface).interface.pause();
+pub unsafe extern "C" fn dasher_interface_set_orientation(
+    interface: *mut DasherInterfaceFFI,
+    orientation: i32
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        if orientation as i64 == Orientation::ALPHABET_DEFAULT {
+            interface.interface.use_alphabet_orientation();
+            return Ok(());
+        }
+        let orientation = Orientation::from_code(orientation as i64)
+            .ok_or_else(|| DasherError::InvalidParameter(format!("Invalid orientation: {}", orientation)))?;
+        interface.interface.set_orientation(orientation);
+        Ok(())
+    })
 }
 
-/// Resume Dasher
+/// Set the width of the margin, in Dasher units
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
 #[no_mangle]
-pub unsafe extern "C" fn dasher_interface_resume(interface: *mut DasherInterfaceFFI) {
-    if interface.is_null() {
-        return;
-    }
-
-    (*interface).interface.resume();
+pub unsafe extern "C" fn dasher_interface_set_margin_width(
+    interface: *mut DasherInterfaceFFI,
+    width: i64
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        interface.interface.set_margin_width(width)
+    })
 }
 
-/// Check if Dasher is running
+/// Set how much node labels grow with the node, 0 for a fixed size
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
 #[no_mangle]
-pub unsafe extern "C" fn dasher_interface_is_running(interface: *mut DasherInterfaceFFI) -> bool {
-    if interface.is_null() {
-        return false;
-    }
-
-    (*interface).interface.is_running()
+pub unsafe extern "C" fn dasher_interface_set_font_scaling(
+    interface: *mut DasherInterfaceFFI,
+    scaling: f64
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        interface.interface.set_font_scaling(scaling)
+    })
 }
 
-/// Check if Dasher is paused
+/// Set the X nonlinearity factor, 0 to turn the X nonlinearity off
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
 #[no_mangle]
-pub unsafe extern "C" fn dasher_interface_is_paused(interface: *mut DasherInterfaceFFI) -> bool {
-    if interface.is_null() {
-        return false;
-    }
-
-    (*interface).interface.is_paused()
+pub unsafe extern "C" fn dasher_interface_set_x_nonlinear_factor(
+    interface: *mut DasherInterfaceFFI,
+    factor: f64
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        interface.interface.set_x_nonlinear_factor(factor)
+    })
 }
 
-/// Get the current offset in the text buffer
+/// Set the Y nonlinearity factor, 1 or less to turn the Y nonlinearity off
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
 #[no_mangle]
-pub unsafe extern "C" fn dasher_interface_get_offset(interface: *mut DasherInterfaceFFI) -> i32 {
-    if interface.is_null() {
-        return 0;
-    }
-
-    (*interface).interface.get_offset()
+pub unsafe extern "C" fn dasher_interface_set_y_nonlinear_factor(
+    interface: *mut DasherInterfaceFFI,
+    factor: i64
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        interface.interface.set_y_nonlinear_factor(factor)
+    })
 }
 
-/// Edit the output text
+/// Calibrate the interface's input device
+///
+/// The calibration is kept in the `InputCalibration` setting for the device,
+/// and applied again whenever a device of the same name is set.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
 #[no_mangle]
-pub unsafe extern "C" fn dasher_interface_edit_output(
+pub unsafe extern "C" fn dasher_interface_set_input_calibration(
     interface: *mut DasherInterfaceFFI,
-    text: *const c_char
-) {
-    if interface.is_null() || text.is_null() {
-        return;
-    }
-
-    let c_str = CStr::from_ptr(text);
-    if let Ok(text) = c_str.to_str() {
-        (*interface).interface.edit_output(text);
-    }
+    x_offset: i32,
+    y_offset: i32,
+    x_gain: f64,
+    y_gain: f64
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        interface.interface.set_input_calibration(Calibration { x_offset, y_offset, x_gain, y_gain })
+    })
 }
 
-/// Create a new screen for rendering
+/// Handle a finger touching the screen
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`,
+/// whose input device was created by `dasher_create_touch_input`.
 #[no_mangle]
-pub extern "C" fn dasher_create_screen(
-    width: i32,
-    height: i32,
-) -> *mut DasherScreenFFI {
-    let screen = SimpleDasherScreen::new(width, height);
-    Box::into_raw(Box::new(DasherScreenFFI { screen }))
+pub unsafe extern "C" fn dasher_interface_touch_down(
+    interface: *mut DasherInterfaceFFI,
+    time_ms: u64,
+    id: u64,
+    x: i32,
+    y: i32
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        interface.interface.touch_down(time_ms, id, x, y)
+    })
 }
 
-/// Destroy a screen
+/// Handle a finger moving on the screen
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`,
+/// whose input device was created by `dasher_create_touch_input`.
 #[no_mangle]
-pub unsafe extern "C" fn dasher_destroy_screen(screen: *mut DasherScreenFFI) {
-    if !screen.is_null() {
-        let _ = Box::from_raw(screen);
-    }
+pub unsafe extern "C" fn dasher_interface_touch_move(
+    interface: *mut DasherInterfaceFFI,
+    time_ms: u64,
+    id: u64,
+    x: i32,
+    y: i32
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        interface.interface.touch_move(time_ms, id, x, y)
+    })
+}
+
+/// Handle a finger leaving the screen
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`,
+/// whose input device was created by `dasher_create_touch_input`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_touch_up(
+    interface: *mut DasherInterfaceFFI,
+    time_ms: u64,
+    id: u64
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        interface.interface.touch_up(time_ms, id)
+    })
+}
+
+/// Set the deflection of the joystick, from -1 to 1 on each axis
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`,
+/// whose input device was created by `dasher_create_joystick_input`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_joystick_axes(
+    interface: *mut DasherInterfaceFFI,
+    x: f64,
+    y: f64
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        interface.interface.joystick_axes(x, y)
+    })
+}
+
+/// Handle a joystick button being pressed or released
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`,
+/// whose input device was created by `dasher_create_joystick_input`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_joystick_button(
+    interface: *mut DasherInterfaceFFI,
+    time_ms: u64,
+    button: u32,
+    pressed: bool
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        interface.interface.joystick_button(time_ms, button, pressed)
+    })
+}
+
+/// Map a joystick button to a key code (as for `dasher_interface_key_down`)
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`,
+/// whose input device was created by `dasher_create_joystick_input`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_map_joystick_button(
+    interface: *mut DasherInterfaceFFI,
+    button: u32,
+    key: i32
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let joystick = interface.interface.joystick_input_mut()
+            .ok_or_else(|| DasherError::InputError("The input device is not a joystick".to_string()))?;
+        joystick.map_button(button, virtual_key_from_code(key));
+        Ok(())
+    })
+}
+
+/// Use a breath or pressure sensor as the interface's input device
+///
+/// The pressure moves the pointer from the bottom at `min` (the hardest sip)
+/// to the top at `max` (the hardest puff), and Dasher steers with the
+/// one-dimensional filter. Pass readings with `dasher_interface_set_pressure`.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_use_pressure_input(
+    interface: *mut DasherInterfaceFFI,
+    neutral: f64,
+    min: f64,
+    max: f64
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        interface.interface.use_pressure_input(PressureCalibration { neutral, min, max });
+        Ok(())
+    })
+}
+
+/// Set the latest reading of the breath or pressure sensor
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`,
+/// which is using a pressure sensor (see `dasher_interface_use_pressure_input`).
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_set_pressure(
+    interface: *mut DasherInterfaceFFI,
+    pressure: f64
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        interface.interface.set_pressure(pressure)
+    })
+}
+
+/// Steer with the arrow keys (or W, A, S and D) instead of a pointing device
+///
+/// While a key is held the target point moves by `rate` of the canvas height
+/// per second. Pass the keys with `dasher_interface_key_down` and `_key_up`.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_use_keyboard_steering(
+    interface: *mut DasherInterfaceFFI,
+    rate: f64
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        interface.interface.set_input_filter(Box::new(KeyboardSteeringFilter::new(rate)));
+        Ok(())
+    })
+}
+
+/// Type `text` automatically, for demos and end-to-end tests
+///
+/// `text` is the whole output wanted, including any already written. It is
+/// typed at `bit_rate` bits per second, with the steering jittered by up to
+/// `noise` of the canvas height, once Dasher is started.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`,
+/// and `text` a null-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_use_demo_filter(
+    interface: *mut DasherInterfaceFFI,
+    text: *const c_char,
+    bit_rate: f64,
+    noise: f64
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let text = c_str_arg(text, "text")?;
+        let config = DemoDynamicFilterConfig {
+            base: DynamicFilterConfig { bit_rate, ..DynamicFilterConfig::default() },
+            text: Some(text.to_string()),
+            noise,
+            ..DemoDynamicFilterConfig::default()
+        };
+        interface.interface.set_input_filter(Box::new(DemoDynamicFilter::new(config)));
+        Ok(())
+    })
+}
+
+/// Zoom so that the range `y - x` to `y + x` fills the screen over `steps` frames
+///
+/// `x` and `y` are Dasher coordinates (see `dasher_screen_to_dasher`). The
+/// zoom is taken one step a frame while Dasher is running, in place of the
+/// input filter's steering.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_zoom_to(
+    interface: *mut DasherInterfaceFFI,
+    x: i64,
+    y: i64,
+    steps: i32
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        interface.interface.zoom_to(x, y, steps)?;
+        Ok(())
+    })
+}
+
+/// Add a region of the screen that acts as a button
+///
+/// Hovering the pointer in the region for the `ButtonDwellTime` setting
+/// sends `key` (a key code as for `dasher_interface_key_down`).
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_add_button_region(
+    interface: *mut DasherInterfaceFFI,
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    key: i32
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let mut regions = interface.interface.button_regions().to_vec();
+        regions.push(ButtonRegion { x, y, width, height, key: virtual_key_from_code(key) });
+        interface.interface.set_button_regions(regions);
+        Ok(())
+    })
+}
+
+/// Remove every button region
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_clear_button_regions(interface: *mut DasherInterfaceFFI) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        interface.interface.set_button_regions(Vec::new());
+        Ok(())
+    })
+}
+
+/// Get the progress of the dwell on a button region, for drawing a countdown
+///
+/// `region` is set to the index of the region being dwelt on, in the order
+/// added, or -1 if there is none.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`,
+/// and `region` and `remaining_ms` must point to a writable `int32_t` and `uint64_t`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_button_dwell(
+    interface: *mut DasherInterfaceFFI,
+    region: *mut i32,
+    remaining_ms: *mut u64
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let region = error::non_null(region, "region")?;
+        let remaining_ms = error::non_null(remaining_ms, "remaining_ms")?;
+
+        match interface.interface.button_dwell() {
+            Some(dwell) => {
+                *region = dwell.region as i32;
+                *remaining_ms = dwell.remaining_ms;
+            }
+            None => {
+                *region = -1;
+                *remaining_ms = 0;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Find the node drawn under a point on the screen, for tap-to-zoom or tooltips
+///
+/// `found` is set to whether there is a node there. If there is, `symbol` is
+/// set to the Unicode code point it writes (0 for none) and `y1` and `y2` to
+/// the Dasher coordinates it is drawn at, so `dasher_interface_zoom_to` with
+/// `x = (y2 - y1) / 2` and `y = (y1 + y2) / 2` zooms to it.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`,
+/// `found` must point to a writable `bool`, `symbol` to a writable `uint32_t`,
+/// and `y1` and `y2` to writable `int64_t`s.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_node_at_screen_point(
+    interface: *mut DasherInterfaceFFI,
+    x: i32,
+    y: i32,
+    found: *mut bool,
+    symbol: *mut u32,
+    y1: *mut i64,
+    y2: *mut i64
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let found = error::non_null(found, "found")?;
+        let symbol = error::non_null(symbol, "symbol")?;
+        let y1 = error::non_null(y1, "y1")?;
+        let y2 = error::non_null(y2, "y2")?;
+
+        let hit = interface.interface.node_at_screen_point(x, y);
+        *found = hit.is_some();
+        if let Some(hit) = hit {
+            *symbol = hit.symbol.map_or(0, u32::from);
+            (*y1, *y2) = hit.bounds;
+        }
+        Ok(())
+    })
+}
+
+/// Call `callback` with each node the view would draw, parents before their children
+///
+/// For frontends that draw the scene themselves rather than through the
+/// screen's drawing callbacks.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_visit_visible_nodes(
+    interface: *mut DasherInterfaceFFI,
+    callback: extern "C" fn(node: *const VisibleNodeFFI)
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        interface.interface.visit_visible_nodes(&mut |node: &VisibleNode| {
+            let label = node.label.as_deref().map(|label| CString::new(label.replace('\0', "")).unwrap_or_default());
+            let (x1, y1, x2, y2) = node.rect;
+            callback(&VisibleNodeFFI {
+                x1,
+                y1,
+                x2,
+                y2,
+                label: label.as_ref().map_or(std::ptr::null(), |label| label.as_ptr()),
+                foreground: node.foreground.into(),
+                background: node.background.into(),
+                shape: node.shape.into(),
+                depth: node.depth as i32,
+            });
+        })?;
+        Ok(())
+    })
+}
+
+/// Get a description of the current frame as JSON: the screen size, the
+/// nodes the view would draw, and where the crosshair and cursor are
+///
+/// `required_size`, if not null, is set to the buffer size needed for the
+/// whole description, including the terminator. Pass a null buffer to only query it.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`,
+/// `buffer` must be null or point to a writable buffer of at least
+/// `buffer_size` bytes, and `required_size` must be null or point to a writable `size_t`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_get_scene_json(
+    interface: *mut DasherInterfaceFFI,
+    buffer: *mut c_char,
+    buffer_size: usize,
+    required_size: *mut usize,
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let json = interface.interface.scene_json()?;
+        copy_string_result(&json, buffer, buffer_size, required_size)
+    })
+}
+
+/// Convert a key code passed across the FFI to a virtual key
+fn virtual_key_from_code(key: i32) -> VirtualKey {
+    match key {
+        0 => VirtualKey::PrimaryInput,
+        1 => VirtualKey::SecondaryInput,
+        2 => VirtualKey::TertiaryInput,
+        3 => VirtualKey::StartStopKey,
+        4 => VirtualKey::Button1,
+        5 => VirtualKey::Button2,
+        6 => VirtualKey::Button3,
+        7 => VirtualKey::Button4,
+        8 => VirtualKey::Button5,
+        9 => VirtualKey::Left,
+        10 => VirtualKey::Right,
+        11 => VirtualKey::Up,
+        12 => VirtualKey::Down,
+        13 => VirtualKey::Delete,
+        14 => VirtualKey::Backspace,
+        15 => VirtualKey::Tab,
+        16 => VirtualKey::Return,
+        17 => VirtualKey::Escape,
+        18 => VirtualKey::Space,
+        _ => VirtualKey::Other(' '),
+    }
+}
+
+/// Handle a key down event
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_key_down(
+    interface: *mut DasherInterfaceFFI,
+    time_ms: u64,
+    key: i32
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        interface.interface.key_down(time_ms, virtual_key_from_code(key));
+        Ok(())
+    })
+}
+
+/// Handle a key up event
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_key_up(
+    interface: *mut DasherInterfaceFFI,
+    time_ms: u64,
+    key: i32
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        interface.interface.key_up(time_ms, virtual_key_from_code(key));
+        Ok(())
+    })
+}
+
+/// Start Dasher
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_start(interface: *mut DasherInterfaceFFI) -> DasherErrorCode {
+    error::guard(|| {
+        error::non_null(interface, "interface")?.interface.start();
+        Ok(())
+    })
+}
+
+/// Stop Dasher
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_stop(interface: *mut DasherInterfaceFFI) -> DasherErrorCode {
+    error::guard(|| {
+        error::non_null(interface, "interface")?.interface.stop();
+        Ok(())
+    })
+}
+
+/// Pause Dasher
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_pause(interface: *mut DasherInterfaceFFI) -> DasherErrorCode {
+    error::guard(|| {
+        error::non_null(interface, "interface")?.interface.pause();
+        Ok(())
+    })
+}
+
+/// Resume Dasher
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_resume(interface: *mut DasherInterfaceFFI) -> DasherErrorCode {
+    error::guard(|| {
+        error::non_null(interface, "interface")?.interface.resume();
+        Ok(())
+    })
+}
+
+/// Check if Dasher is running
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_is_running(interface: *mut DasherInterfaceFFI) -> bool {
+    error::guard_or(false, || {
+        Ok(error::non_null(interface, "interface")?.interface.is_running())
+    })
+}
+
+/// Check if Dasher is paused
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_is_paused(interface: *mut DasherInterfaceFFI) -> bool {
+    error::guard_or(false, || {
+        Ok(error::non_null(interface, "interface")?.interface.is_paused())
+    })
+}
+
+/// Get the current offset in the text buffer
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_get_offset(interface: *mut DasherInterfaceFFI) -> i32 {
+    error::guard_or(0, || {
+        Ok(error::non_null(interface, "interface")?.interface.get_offset())
+    })
+}
+
+/// Edit the output text
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create` and
+/// `text` must point to a null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_edit_output(
+    interface: *mut DasherInterfaceFFI,
+    text: *const c_char
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let text = c_str_arg(text, "text")?;
+        interface.interface.edit_output(text);
+        Ok(())
+    })
+}
+
+/// Load an alphabet from an XML file, replacing the current alphabet
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create` and
+/// `path` must point to a null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_load_alphabet_file(
+    interface: *mut DasherInterfaceFFI,
+    path: *const c_char
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let path = c_str_arg(path, "path")?;
+
+        dasher_log!(Debug, "Loading alphabet from {}", path);
+        let info = crate::alphabet::load_alphabet(path)
+            .map_err(|e| DasherError::InvalidParameter(format!("Failed to load alphabet from {}: {}", path, e)))?;
+        interface.interface.load_alphabet(info)
+    })
+}
+
+/// Load an alphabet from an XML document, replacing the current alphabet
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create` and
+/// `xml` must point to a null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_load_alphabet_xml(
+    interface: *mut DasherInterfaceFFI,
+    xml: *const c_char
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let xml = c_str_arg(xml, "xml")?;
+
+        let info = crate::alphabet::parse_alphabet(xml)
+            .map_err(|e| DasherError::InvalidParameter(format!("Failed to parse alphabet: {}", e)))?;
+        interface.interface.load_alphabet(info)
+    })
+}
+
+/// Switch to the alphabet with the given id while keeping the output text
+///
+/// The id is either the part of an `alphabet.<id>.xml` file name or the name
+/// given in the file, searched for in the alphabet search paths.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create` and
+/// `id` must point to a null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_set_alphabet(
+    interface: *mut DasherInterfaceFFI,
+    id: *const c_char
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let id = c_str_arg(id, "id")?;
+
+        dasher_log!(Debug, "Switching to alphabet {}", id);
+        interface.interface.set_alphabet(id)
+    })
+}
+
+/// Add a language profile using the alphabet with the given id
+///
+/// The profile gets its own language model, trained from the alphabet's
+/// training file, and can be switched to with `dasher_interface_switch_profile`
+/// or its control node.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`,
+/// and `name` and `alphabet_id` must point to null-terminated C strings.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_add_profile(
+    interface: *mut DasherInterfaceFFI,
+    name: *const c_char,
+    alphabet_id: *const c_char
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let name = c_str_arg(name, "name")?;
+        let alphabet_id = c_str_arg(alphabet_id, "alphabet_id")?;
+
+        interface.interface.add_profile(name, alphabet_id)
+    })
+}
+
+/// Switch to the language profile with the given name, keeping the output text
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create` and
+/// `name` must point to a null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_switch_profile(
+    interface: *mut DasherInterfaceFFI,
+    name: *const c_char
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let name = c_str_arg(name, "name")?;
+
+        interface.interface.switch_profile(name)
+    })
+}
+
+/// Borrow a C string argument as UTF-8
+///
+/// # Safety
+///
+/// `text` must be null or point to a null-terminated C string that outlives `'a`.
+unsafe fn c_str_arg<'a>(text: *const c_char, name: &str) -> crate::Result<&'a str> {
+    if text.is_null() {
+        return Err(DasherError::InvalidParameter(format!("{} pointer is null", name)));
+    }
+    CStr::from_ptr(text)
+        .to_str()
+        .map_err(|_| DasherError::InvalidParameter(format!("{} is not valid UTF-8", name)))
+}
+
+/// Speech provider that forwards text to a host callback
+struct CallbackSpeechProvider {
+    speak_fn: extern "C" fn(text: *const c_char),
+}
+
+impl SpeechProvider for CallbackSpeechProvider {
+    fn speak(&self, text: &str) {
+        let c_text = CString::new(text.replace('\0', "")).unwrap_or_default();
+        (self.speak_fn)(c_text.as_ptr());
+    }
+}
+
+/// Set the callback used to speak text when the speak action is selected
+///
+/// Passing a null callback disables the speak action.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_set_speech_callback(
+    interface: *mut DasherInterfaceFFI,
+    callback: Option<extern "C" fn(text: *const c_char)>
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        match callback {
+            Some(speak_fn) => interface.interface.set_speech_provider(Box::new(CallbackSpeechProvider { speak_fn })),
+            None => interface.interface.clear_speech_provider(),
+        }
+        Ok(())
+    })
+}
+
+/// Speak the current sentence through the speech callback
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_speak(interface: *mut DasherInterfaceFFI) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        if interface.interface.speak() {
+            Ok(())
+        } else {
+            Err(DasherError::Other("No speech callback has been set".to_string()))
+        }
+    })
+}
+
+/// Clipboard provider that forwards text to a host callback
+struct CallbackClipboardProvider {
+    set_text_fn: extern "C" fn(text: *const c_char),
+}
+
+impl ClipboardProvider for CallbackClipboardProvider {
+    fn set_text(&self, text: &str) {
+        let c_text = CString::new(text.replace('\0', "")).unwrap_or_default();
+        (self.set_text_fn)(c_text.as_ptr());
+    }
+}
+
+/// Set the callback that receives text from the copy and cut actions
+///
+/// Passing a null callback disables the copy and cut actions.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_set_clipboard_callback(
+    interface: *mut DasherInterfaceFFI,
+    callback: Option<extern "C" fn(text: *const c_char)>
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        match callback {
+            Some(set_text_fn) => interface.interface.set_clipboard_provider(Box::new(CallbackClipboardProvider { set_text_fn })),
+            None => interface.interface.clear_clipboard_provider(),
+        }
+        Ok(())
+    })
+}
+
+/// Conversion provider that asks a host callback for candidates
+struct CallbackConversionProvider {
+    candidates_fn: extern "C" fn(input: *const c_char, context: *const c_char, buffer: *mut c_char, buffer_size: usize) -> usize,
+}
+
+impl CallbackConversionProvider {
+    /// Size of the buffer first offered to the callback
+    const BUFFER_SIZE: usize = 256;
+
+    /// Read candidates written one per line, each optionally followed by a
+    /// tab and its probability; candidates without one share equally
+    fn parse(text: &str) -> Vec<ConversionCandidate> {
+        let mut candidates: Vec<ConversionCandidate> = text.lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| match line.split_once('\t') {
+                Some((text, probability)) => ConversionCandidate {
+                    text: text.to_string(),
+                    probability: probability.trim().parse().unwrap_or(1.0),
+                },
+                None => ConversionCandidate { text: line.to_string(), probability: 1.0 },
+            })
+            .collect();
+        let total: f64 = candidates.iter().map(|candidate| candidate.probability.max(0.0)).sum();
+        for candidate in &mut candidates {
+            candidate.probability = if total > 0.0 { candidate.probability.max(0.0) / total } else { 0.0 };
+        }
+        candidates
+    }
+}
+
+impl ConversionProvider for CallbackConversionProvider {
+    fn candidates(&self, input: &str, context: &str) -> Vec<ConversionCandidate> {
+        let c_input = CString::new(input.replace('\0', "")).unwrap_or_default();
+        let c_context = CString::new(context.replace('\0', "")).unwrap_or_default();
+
+        // Ask again with a larger buffer if the candidates did not fit
+        let mut buffer = vec![0 as c_char; Self::BUFFER_SIZE];
+        let required = (self.candidates_fn)(c_input.as_ptr(), c_context.as_ptr(), buffer.as_mut_ptr(), buffer.len());
+        if required > buffer.len() {
+            buffer = vec![0 as c_char; required];
+            (self.candidates_fn)(c_input.as_ptr(), c_context.as_ptr(), buffer.as_mut_ptr(), buffer.len());
+        }
+        if let Some(last) = buffer.last_mut() {
+            *last = 0;
+        }
+
+        // SAFETY: the buffer ends with a terminator
+        let text = unsafe { CStr::from_ptr(buffer.as_ptr()) }.to_string_lossy();
+        Self::parse(&text)
+    }
+}
+
+/// Set the callback that converts what the user spelled, such as kana or
+/// pinyin, into candidates offered as nodes
+///
+/// The callback receives the composition spelled since the last word
+/// separator and the text before it, and writes its candidates into
+/// `buffer`, most likely first: one per line, each optionally followed by a
+/// tab and its probability. It returns the buffer size needed, including
+/// the terminator, and is called again with a larger buffer if that is more
+/// than `buffer_size`. Passing a null callback stops offering conversions.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_set_conversion_callback(
+    interface: *mut DasherInterfaceFFI,
+    callback: Option<extern "C" fn(input: *const c_char, context: *const c_char, buffer: *mut c_char, buffer_size: usize) -> usize>
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        match callback {
+            Some(candidates_fn) => interface.interface.set_conversion_provider(Box::new(CallbackConversionProvider { candidates_fn })),
+            None => interface.interface.clear_conversion_provider(),
+        }
+        Ok(())
+    })
+}
+
+/// Register the custom actions declared in a JSON or XML configuration file
+///
+/// Returns the number of actions registered, or -1 on error.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create` and
+/// `path` must point to a null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_load_actions_file(
+    interface: *mut DasherInterfaceFFI,
+    path: *const c_char
+) -> i32 {
+    error::guard_or(-1, || {
+        let interface = error::non_null(interface, "interface")?;
+        let path = c_str_arg(path, "path")?;
+        let count = interface.interface.load_custom_actions(path)?;
+        Ok(count as i32)
+    })
+}
+
+/// Register the custom actions from `actions.json` or `actions.xml` in the
+/// user's Dasher configuration directory, if either exists
+///
+/// Returns the number of actions registered, or -1 on error.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_load_user_actions(
+    interface: *mut DasherInterfaceFFI
+) -> i32 {
+    error::guard_or(-1, || {
+        let interface = error::non_null(interface, "interface")?;
+        let count = interface.interface.load_user_actions()?;
+        Ok(count as i32)
+    })
+}
+
+/// Replace the language model with an n-gram model from an ARPA file
+///
+/// The file may be gzip-compressed.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create` and
+/// `path` must point to a null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_load_arpa_model(
+    interface: *mut DasherInterfaceFFI,
+    path: *const c_char
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let path = c_str_arg(path, "path")?;
+        interface.interface.load_arpa_model(path)
+    })
+}
+
+/// Predict with a neural character model loaded from a safetensors file
+///
+/// The current language model predicts instead for a while whenever the
+/// network takes longer than `latency_budget_ms`. Only available with the
+/// `candle` feature.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create` and
+/// `path` must point to a null-terminated C string.
+#[cfg(feature = "candle")]
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_load_neural_model(
+    interface: *mut DasherInterfaceFFI,
+    path: *const c_char,
+    latency_budget_ms: u32
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let path = c_str_arg(path, "path")?;
+        let budget = std::time::Duration::from_millis(latency_budget_ms as u64);
+        interface.interface.load_neural_model(path, budget)
+    })
+}
+
+/// Train the language model from a text file, reporting progress
+///
+/// `progress`, if not null, is called with the percentage of the file read
+/// whenever it changes; returning false cancels training. Returns the number
+/// of characters trained on, or -1 on error or cancellation.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create` and
+/// `path` must point to a null-terminated C string. The interface is in use
+/// for the whole of training, so `progress` must not call any function on it.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_train_file(
+    interface: *mut DasherInterfaceFFI,
+    path: *const c_char,
+    progress: Option<extern "C" fn(percent: u32) -> bool>
+) -> i64 {
+    error::guard_or(-1, || {
+        let interface = error::non_null(interface, "interface")?;
+        let path = c_str_arg(path, "path")?;
+        let file = std::fs::File::open(path)
+            .map_err(|e| DasherError::InvalidParameter(format!("Failed to open training file {}: {}", path, e)))?;
+
+        dasher_log!(Debug, "Training language model from {}", path);
+        let stats = interface.interface.train(std::io::BufReader::new(file), |percent| {
+            progress.is_none_or(|callback| callback(percent as u32))
+        })?;
+        Ok(stats.total_chars as i64)
+    })
+}
+
+/// Load the words the user has typed in earlier sessions from `lexicon.txt`
+/// in the user's Dasher configuration directory
+///
+/// Until it is loaded, typed words are learned but not saved.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_load_user_lexicon(
+    interface: *mut DasherInterfaceFFI
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        interface.interface.load_user_lexicon()
+    })
+}
+
+/// Save the words the user has typed to their lexicon file
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_save_lexicon(
+    interface: *mut DasherInterfaceFFI
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        interface.interface.save_lexicon()
+    })
+}
+
+/// Remove a word from the user's lexicon
+///
+/// Returns 1 if the word was removed, 0 if it was not in the lexicon, or -1 on error.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create` and
+/// `word` must point to a null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_remove_lexicon_word(
+    interface: *mut DasherInterfaceFFI,
+    word: *const c_char
+) -> i32 {
+    error::guard_or(-1, || {
+        let interface = error::non_null(interface, "interface")?;
+        let word = c_str_arg(word, "word")?;
+        Ok(interface.interface.remove_lexicon_word(word) as i32)
+    })
+}
+
+/// Get the distribution of the next symbol, most probable first
+///
+/// The symbols' Unicode code points are written to `symbols_out` and their
+/// probabilities to `probs_out`. At most `max` entries are written, and only
+/// the `top_k` most probable symbols (all of them if `top_k` is 0). Returns
+/// the number of entries written, or -1 on error.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`,
+/// and `symbols_out` and `probs_out` must point to writable arrays of at
+/// least `max` elements.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_get_probabilities(
+    interface: *mut DasherInterfaceFFI,
+    symbols_out: *mut u32,
+    probs_out: *mut f64,
+    max: usize,
+    top_k: usize
+) -> i32 {
+    error::guard_or(-1, || {
+        let interface = error::non_null(interface, "interface")?;
+        if symbols_out.is_null() || probs_out.is_null() {
+            return Err(DasherError::InvalidParameter("output pointer is null".to_string()));
+        }
+
+        let probabilities = interface.interface.probabilities(top_k);
+        let count = probabilities.len().min(max);
+        for (i, (symbol, probability)) in probabilities.into_iter().take(count).enumerate() {
+            *symbols_out.add(i) = symbol as u32;
+            *probs_out.add(i) = probability;
+        }
+        Ok(count as i32)
+    })
+}
+
+/// Get the words predicted from the text before the cursor
+///
+/// Up to `max` words are written to `buffer`, most likely first, each ending
+/// with a newline. Words that do not fit in the buffer are left out.
+/// `required_size`, if not null, is set to the buffer size needed for all
+/// the words, including the terminator; pass a null buffer to only query it.
+/// Returns the number of words written, or -1 on error.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`,
+/// `buffer` must be null or point to a writable buffer of at least
+/// `buffer_size` bytes, and `required_size` must be null or point to a writable `size_t`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_get_predictions(
+    interface: *mut DasherInterfaceFFI,
+    buffer: *mut c_char,
+    buffer_size: usize,
+    max: usize,
+    required_size: *mut usize
+) -> i32 {
+    error::guard_or(-1, || {
+        let interface = error::non_null(interface, "interface")?;
+        if buffer.is_null() && required_size.is_null() {
+            return Err(DasherError::InvalidParameter("buffer and required_size pointers are both null".to_string()));
+        }
+        let words = interface.interface.word_predictions(max);
+        let all: String = words.iter().map(|word| format!("{}\n", word)).collect();
+
+        // Only whole words are written, leaving room for the terminator
+        let mut fitting = 0;
+        let mut count = 0;
+        for word in &words {
+            if fitting + word.len() + 1 >= buffer_size {
+                break;
+            }
+            fitting += word.len() + 1;
+            count += 1;
+        }
+        copy_to_buffer(&all[..fitting], buffer, buffer_size);
+        if !required_size.is_null() {
+            *required_size = all.len() + 1;
+        }
+        Ok(if buffer.is_null() { 0 } else { count })
+    })
+}
+
+/// Set the file committed sentences are recorded to
+///
+/// Sentences are only recorded while the `RecordCorpus` setting is on.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create` and
+/// `path` must point to a null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_set_corpus_path(
+    interface: *mut DasherInterfaceFFI,
+    path: *const c_char
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let path = c_str_arg(path, "path")?;
+        interface.interface.set_corpus_path(Some(path.into()));
+        Ok(())
+    })
+}
+
+/// Record a sentence the host has committed to the corpus
+///
+/// Does nothing while the `RecordCorpus` setting is off.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create` and
+/// `sentence` must point to a null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_record_sentence(
+    interface: *mut DasherInterfaceFFI,
+    sentence: *const c_char
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let sentence = c_str_arg(sentence, "sentence")?;
+        interface.interface.record_sentence(sentence)
+    })
+}
+
+/// Set the callback invoked with the callback id of custom actions
+///
+/// Passing a null callback removes any registered callback.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_set_action_callback(
+    interface: *mut DasherInterfaceFFI,
+    callback: Option<extern "C" fn(callback_id: u32)>
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        match callback {
+            Some(f) => interface.interface.set_action_callback(move |id| f(id)),
+            None => interface.interface.clear_action_callback(),
+        }
+        Ok(())
+    })
+}
+
+/// Set the callback receiving a description of each change to the text, for screen readers
+///
+/// The message (such as "typed letter h") is only valid during the call.
+/// Passing a null callback removes any registered callback.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_set_announcement_callback(
+    interface: *mut DasherInterfaceFFI,
+    callback: Option<extern "C" fn(message: *const c_char)>
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        match callback {
+            Some(f) => interface.interface.set_announcement_callback(move |announcement| {
+                let message = CString::new(announcement.to_string().replace('\0', "")).unwrap_or_default();
+                f(message.as_ptr());
+            }),
+            None => interface.interface.clear_announcement_callback(),
+        }
+        Ok(())
+    })
+}
+
+/// Set the callback receiving the cursor position (in characters) whenever
+/// Dasher moves it, by writing or deleting text or by a cursor movement action
+///
+/// Passing a null callback removes any registered callback.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_set_cursor_callback(
+    interface: *mut DasherInterfaceFFI,
+    callback: Option<extern "C" fn(position: usize)>
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        match callback {
+            Some(f) => interface.interface.set_cursor_callback(move |position| f(position)),
+            None => interface.interface.clear_cursor_callback(),
+        }
+        Ok(())
+    })
+}
+
+/// Execute a registered action (e.g. "copy", "cut", "clear") by name
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create` and
+/// `name` must point to a null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_execute_action(
+    interface: *mut DasherInterfaceFFI,
+    name: *const c_char
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let name = c_str_arg(name, "name")?;
+        interface.interface.execute_action(name)
+    })
+}
+
+/// Create a new screen for rendering
+#[no_mangle]
+pub extern "C" fn dasher_create_screen(
+    width: i32,
+    height: i32,
+) -> *mut DasherScreenFFI {
+    error::guard_or(std::ptr::null_mut(), || {
+        let screen = SimpleDasherScreen::new(width, height);
+        Ok(Box::into_raw(Box::new(DasherScreenFFI { screen })))
+    })
+}
+
+/// Destroy a screen
+///
+/// # Safety
+///
+/// `screen` must be null or a pointer created by `dasher_create_screen`. After this
+/// function is called, the pointer is no longer valid and should not be used.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_destroy_screen(screen: *mut DasherScreenFFI) {
+    error::guard(|| {
+        if !screen.is_null() {
+            let _ = Box::from_raw(screen);
+        }
+        Ok(())
+    });
+}
+
+/// Set the draw rectangle callback for a screen
+///
+/// # Safety
+///
+/// `screen` must be a valid pointer created by `dasher_create_screen`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_screen_set_draw_rectangle_callback(
+    screen: *mut DasherScreenFFI,
+    callback: extern "C" fn(x1: i32, y1: i32, x2: i32, y2: i32,
+                          fill_r: u8, fill_g: u8, fill_b: u8, fill_a: u8,
+                          outline_r: u8, outline_g: u8, outline_b: u8, outline_a: u8,
+                          line_width: i32),
+) -> DasherErrorCode {
+    error::guard(|| {
+        error::non_null(screen, "screen")?.screen.set_draw_rectangle_fn(callback);
+        Ok(())
+    })
+}
+
+/// Set the draw circle callback for a screen
+///
+/// # Safety
+///
+/// `screen` must be a valid pointer created by `dasher_create_screen`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_screen_set_draw_circle_callback(
+    screen: *mut DasherScreenFFI,
+    callback: extern "C" fn(cx: i32, cy: i32, r: i32,
+                          fill_r: u8, fill_g: u8, fill_b: u8, fill_a: u8,
+                          line_r: u8, line_g: u8, line_b: u8, line_a: u8,
+                          line_width: i32),
+) -> DasherErrorCode {
+    error::guard(|| {
+        error::non_null(screen, "screen")?.screen.set_draw_circle_fn(callback);
+        Ok(())
+    })
+}
+
+/// Set the draw line callback for a screen
+///
+/// # Safety
+///
+/// `screen` must be a valid pointer created by `dasher_create_screen`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_screen_set_draw_line_callback(
+    screen: *mut DasherScreenFFI,
+    callback: extern "C" fn(x1: i32, y1: i32, x2: i32, y2: i32,
+                          r: u8, g: u8, b: u8, a: u8,
+                          line_width: i32),
+) -> DasherErrorCode {
+    error::guard(|| {
+        error::non_null(screen, "screen")?.screen.set_draw_line_fn(callback);
+        Ok(())
+    })
+}
+
+/// Set the draw string callback for a screen
+///
+/// # Safety
+///
+/// `screen` must be a valid pointer created by `dasher_create_screen`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_screen_set_draw_string_callback(
+    screen: *mut DasherScreenFFI,
+    callback: extern "C" fn(text: *const c_char, x: i32, y: i32, size: i32,
+                          r: u8, g: u8, b: u8, a: u8),
+) -> DasherErrorCode {
+    error::guard(|| {
+        error::non_null(screen, "screen")?.screen.set_draw_string_fn(callback);
+        Ok(())
+    })
+}
+
+/// Set the make label callback for a screen
+///
+/// # Safety
+///
+/// `screen` must be a valid pointer created by `dasher_create_screen`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_screen_set_make_label_callback(
+    screen: *mut DasherScreenFFI,
+    callback: extern "C" fn(text: *const c_char, size: i32) -> *mut std::ffi::c_void,
+) -> DasherErrorCode {
+    error::guard(|| {
+        error::non_null(screen, "screen")?.screen.set_make_label_fn(callback);
+        Ok(())
+    })
+}
+
+/// Set the destroy label callback for a screen
+///
+/// # Safety
+///
+/// `screen` must be a valid pointer created by `dasher_create_screen`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_screen_set_destroy_label_callback(
+    screen: *mut DasherScreenFFI,
+    callback: extern "C" fn(label: *mut std::ffi::c_void),
+) -> DasherErrorCode {
+    error::guard(|| {
+        error::non_null(screen, "screen")?.screen.set_destroy_label_fn(callback);
+        Ok(())
+    })
+}
+
+/// Set the get text size callback for a screen
+///
+/// # Safety
+///
+/// `screen` must be a valid pointer created by `dasher_create_screen`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_screen_set_get_text_size_callback(
+    screen: *mut DasherScreenFFI,
+    callback: extern "C" fn(label: *mut std::ffi::c_void, size: i32, width: *mut i32, height: *mut i32),
+) -> DasherErrorCode {
+    error::guard(|| {
+        error::non_null(screen, "screen")?.screen.set_get_text_size_fn(callback);
+        Ok(())
+    })
+}
+
+/// Set the draw polygon callback for a screen
+///
+/// The callback receives `num_points` vertices in screen coordinates and should
+/// fill the polygon with the fill colour before stroking its outline. Without it,
+/// polygons are drawn as unfilled outlines using the line callback.
+///
+/// # Safety
+///
+/// `screen` must be a valid pointer created by `dasher_create_screen`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_screen_set_draw_polygon_callback(
+    screen: *mut DasherScreenFFI,
+    callback: extern "C" fn(points: *const PointFFI, num_points: i32,
+                          fill_r: u8, fill_g: u8, fill_b: u8, fill_a: u8,
+                          outline_r: u8, outline_g: u8, outline_b: u8, outline_a: u8,
+                          line_width: i32),
+) -> DasherErrorCode {
+    error::guard(|| {
+        error::non_null(screen, "screen")?.screen.set_draw_polygon_fn(callback);
+        Ok(())
+    })
+}
+
+/// Set the callback choosing the font of the labels measured and drawn next
+///
+/// The callback is given the font family (null for the host's own font)
+/// and weight, from 100 to 900 as in CSS, before a label in a chosen font
+/// is measured or drawn.
+///
+/// # Safety
+///
+/// `screen` must be a valid pointer created by `dasher_create_screen`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_screen_set_font_callback(
+    screen: *mut DasherScreenFFI,
+    callback: extern "C" fn(family: *const c_char, weight: i32),
+) -> DasherErrorCode {
+    error::guard(|| {
+        error::non_null(screen, "screen")?.screen.set_set_font_fn(callback);
+        Ok(())
+    })
+}
+
+/// Set the screen for a DasherInterface
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create` and
+/// `screen` a valid pointer created by `dasher_create_screen`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_set_screen(
+    interface: *mut DasherInterfaceFFI,
+    screen: *mut DasherScreenFFI,
+) -> DasherErrorCode {
+    error::guard(|| {
+        dasher_log!(Debug, "Setting screen for interface");
+
+        let interface = error::non_null(interface, "interface")?;
+        let screen_ref = error::non_null(screen, "screen")?;
+
+        dasher_log!(Debug, "Screen dimensions: {}x{}", screen_ref.screen.get_width(), screen_ref.screen.get_height());
+
+        // Clone the screen
+        let screen_clone = screen_ref.screen.clone();
+
+        // Create a square view with the screen
+        dasher_log!(Debug, "Creating square view with screen");
+        let mut view = Box::new(DasherViewSquare::new(Box::new(screen_clone)));
+
+        // Configure the view with default settings for flowing interface
+        dasher_log!(Debug, "Configuring square view");
+
+        // Enable flowing interface
+        view.set_flowing_interface(true);
+        dasher_log!(Debug, "Enabled flowing interface");
+
+        // Set flowing speed
+        view.set_flowing_speed(2.0);
+        dasher_log!(Debug, "Set flowing speed to 2.0");
+
+        // Set node shape to Rectangle
+        view.set_node_shape(NodeShape::Rectangle);
+        dasher_log!(Debug, "Set node shape to Rectangle");
+
+        // Enable crosshair, cursor, and outlines
+        view.config_mut().draw_crosshair = true;
+        view.config_mut().draw_cursor = true;
+        view.config_mut().draw_outlines = true;
+        dasher_log!(Debug, "Enabled crosshair, cursor, and outlines");
+
+        // Set the view
+        dasher_log!(Debug, "Setting view for interface");
+        let result = interface.interface.set_view(view);
+
+        match &result {
+            Ok(()) => dasher_log!(Debug, "View set successfully"),
+            Err(e) => dasher_log!(Error, "Failed to set view: {:?}", e),
+        }
+        result
+    })
+}
+
+/// Node shape types for FFI
+#[repr(C)]
+pub enum NodeShapeFFI {
+    /// Rectangle shape
+    Rectangle = 0,
+    /// Triangle shape
+    Triangle = 1,
+    /// Truncated triangle shape
+    TruncatedTriangle = 2,
+    /// Circle shape
+    Circle = 3,
+    /// Quadric shape (curved)
+    Quadric = 4,
+}
+
+impl From<NodeShapeFFI> for crate::view::NodeShape {
+    fn from(shape: NodeShapeFFI) -> Self {
+        match shape {
+            NodeShapeFFI::Rectangle => crate::view::NodeShape::Rectangle,
+            NodeShapeFFI::Triangle => crate::view::NodeShape::Triangle,
+            NodeShapeFFI::TruncatedTriangle => crate::view::NodeShape::TruncatedTriangle,
+            NodeShapeFFI::Circle => crate::view::NodeShape::Circle,
+            NodeShapeFFI::Quadric => crate::view::NodeShape::Quadric,
+        }
+    }
+}
+
+impl From<crate::view::NodeShape> for NodeShapeFFI {
+    fn from(shape: crate::view::NodeShape) -> Self {
+        match shape {
+            crate::view::NodeShape::Rectangle => NodeShapeFFI::Rectangle,
+            crate::view::NodeShape::Triangle => NodeShapeFFI::Triangle,
+            crate::view::NodeShape::TruncatedTriangle => NodeShapeFFI::TruncatedTriangle,
+            crate::view::NodeShape::Circle => NodeShapeFFI::Circle,
+            crate::view::NodeShape::Quadric => NodeShapeFFI::Quadric,
+        }
+    }
+}
+
+/// Set the node shape for the Square View
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_set_node_shape(
+    interface: *mut DasherInterfaceFFI,
+    shape: NodeShapeFFI,
+) -> DasherErrorCode {
+    error::guard(|| {
+        error::non_null(interface, "interface")?.interface.set_node_shape(shape.into())
+    })
+}
+
+/// Enable or disable X nonlinearity
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_set_x_nonlinear(
+    interface: *mut DasherInterfaceFFI,
+    enable: bool,
+) -> DasherErrorCode {
+    error::guard(|| {
+        error::non_null(interface, "interface")?.interface.set_x_nonlinear(enable)
+    })
+}
+
+/// Enable or disable Y nonlinearity
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_set_y_nonlinear(
+    interface: *mut DasherInterfaceFFI,
+    enable: bool,
+) -> DasherErrorCode {
+    error::guard(|| {
+        error::non_null(interface, "interface")?.interface.set_y_nonlinear(enable)
+    })
+}
+
+/// Enable or disable 3D text
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_set_text_3d(
+    interface: *mut DasherInterfaceFFI,
+    enable: bool,
+) -> DasherErrorCode {
+    error::guard(|| {
+        error::non_null(interface, "interface")?.interface.set_text_3d(enable)
+    })
+}
+
+/// Enable or disable the flowing interface
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_set_flowing_interface(
+    interface: *mut DasherInterfaceFFI,
+    enable: bool,
+) -> DasherErrorCode {
+    error::guard(|| {
+        error::non_null(interface, "interface")?.interface.set_flowing_interface(enable)
+    })
+}
+
+/// Set the flowing interface speed
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_set_flowing_speed(
+    interface: *mut DasherInterfaceFFI,
+    speed: f64,
+) -> DasherErrorCode {
+    error::guard(|| {
+        error::non_null(interface, "interface")?.interface.set_flowing_speed(speed)
+    })
+}
+
+/// Enable or disable PPM (Prediction by Partial Match)
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_set_ppm(
+    interface: *mut DasherInterfaceFFI,
+    enable: bool,
+) -> DasherErrorCode {
+    error::guard(|| {
+        error::non_null(interface, "interface")?.interface.set_ppm(enable)
+    })
+}
+
+/// Enable or disable drawing the crosshair
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_set_draw_crosshair(
+    interface: *mut DasherInterfaceFFI,
+    enable: bool,
+) -> DasherErrorCode {
+    error::guard(|| {
+        error::non_null(interface, "interface")?.interface.set_draw_crosshair(enable)
+    })
+}
+
+/// Enable or disable drawing the cursor
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_set_draw_cursor(
+    interface: *mut DasherInterfaceFFI,
+    enable: bool,
+) -> DasherErrorCode {
+    error::guard(|| {
+        error::non_null(interface, "interface")?.interface.set_draw_cursor(enable)
+    })
+}
+
+/// Enable or disable drawing node outlines
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_set_draw_outlines(
+    interface: *mut DasherInterfaceFFI,
+    enable: bool,
+) -> DasherErrorCode {
+    error::guard(|| {
+        error::non_null(interface, "interface")?.interface.set_draw_outlines(enable)
+    })
+}
+
+/// Set a boolean parameter and apply it to the running interface
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_settings_set_bool(
+    interface: *mut DasherInterfaceFFI,
+    parameter: i32,
+    value: bool,
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let parameter = settings::parameter_arg(parameter, ParameterType::Bool)?;
+
+        interface.interface.settings_mut().set_bool(parameter, value);
+        interface.interface.handle_parameter_change(parameter);
+        Ok(())
+    })
+}
+
+/// Get a boolean parameter
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create` and
+/// `value` must point to a writable `bool`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_settings_get_bool(
+    interface: *mut DasherInterfaceFFI,
+    parameter: i32,
+    value: *mut bool,
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let value = error::non_null(value, "value")?;
+        let parameter = settings::parameter_arg(parameter, ParameterType::Bool)?;
+
+        *value = interface.interface.settings().get_bool(parameter)
+            .ok_or_else(|| DasherError::SettingsError(format!("{:?} is not set", parameter)))?;
+        Ok(())
+    })
+}
+
+/// Set an integer parameter and apply it to the running interface
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_settings_set_long(
+    interface: *mut DasherInterfaceFFI,
+    parameter: i32,
+    value: i64,
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let parameter = settings::parameter_arg(parameter, ParameterType::Long)?;
+
+        interface.interface.settings_mut().set_long(parameter, value);
+        interface.interface.handle_parameter_change(parameter);
+        Ok(())
+    })
+}
+
+/// Get an integer parameter
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create` and
+/// `value` must point to a writable `int64_t`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_settings_get_long(
+    interface: *mut DasherInterfaceFFI,
+    parameter: i32,
+    value: *mut i64,
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let value = error::non_null(value, "value")?;
+        let parameter = settings::parameter_arg(parameter, ParameterType::Long)?;
+
+        *value = interface.interface.settings().get_long(parameter)
+            .ok_or_else(|| DasherError::SettingsError(format!("{:?} is not set", parameter)))?;
+        Ok(())
+    })
+}
+
+/// Set a string parameter and apply it to the running interface
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create` and
+/// `value` must point to a null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_settings_set_string(
+    interface: *mut DasherInterfaceFFI,
+    parameter: i32,
+    value: *const c_char,
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let value = c_str_arg(value, "value")?;
+        let parameter = settings::parameter_arg(parameter, ParameterType::String)?;
+
+        interface.interface.settings_mut().set_string(parameter, value.to_string());
+        interface.interface.handle_parameter_change(parameter);
+        Ok(())
+    })
+}
+
+/// Get a string parameter, copying it into a caller-provided buffer
+///
+/// `required_size`, if not null, is set to the buffer size needed for the
+/// whole value, including the terminator. Pass a null buffer to only query it.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`,
+/// `buffer` must be null or point to a writable buffer of at least
+/// `buffer_size` bytes, and `required_size` must be null or point to a writable `size_t`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_settings_get_string(
+    interface: *mut DasherInterfaceFFI,
+    parameter: i32,
+    buffer: *mut c_char,
+    buffer_size: usize,
+    required_size: *mut usize,
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let parameter = settings::parameter_arg(parameter, ParameterType::String)?;
+
+        let value = interface.interface.settings().get_string(parameter)
+            .ok_or_else(|| DasherError::SettingsError(format!("{:?} is not set", parameter)))?;
+        copy_string_result(value, buffer, buffer_size, required_size)
+    })
+}
+
+/// Get the names of the input filters that can be chosen with the
+/// `InputFilterID` setting, each ending with a newline
+///
+/// `required_size`, if not null, is set to the buffer size needed for all
+/// the names, including the terminator. Pass a null buffer to only query it.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`,
+/// `buffer` must be null or point to a writable buffer of at least
+/// `buffer_size` bytes, and `required_size` must be null or point to a writable `size_t`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_get_input_filter_names(
+    interface: *mut DasherInterfaceFFI,
+    buffer: *mut c_char,
+    buffer_size: usize,
+    required_size: *mut usize,
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let names: String = interface.interface.input_filter_names().iter().map(|name| format!("{}\n", name)).collect();
+        copy_string_result(&names, buffer, buffer_size, required_size)
+    })
+}
+
+/// Copy a string into a caller-provided buffer, null-terminating it
+///
+/// Strings are returned across the FFI in two calls: one with a null buffer
+/// (or a size of zero) to get the size needed, and one with a buffer of that
+/// size. A string that does not fit is truncated at a character boundary, so
+/// the buffer always holds valid UTF-8.
+///
+/// Returns the buffer size needed for the whole string, including the terminator.
+///
+/// # Safety
+///
+/// `buffer` must be null or point to a writable buffer of at least `buffer_size` bytes.
+unsafe fn copy_to_buffer(text: &str, buffer: *mut c_char, buffer_size: usize) -> usize {
+    if !buffer.is_null() && buffer_size > 0 {
+        let mut copy_len = text.len().min(buffer_size - 1);
+        while !text.is_char_boundary(copy_len) {
+            copy_len -= 1;
+        }
+        std::ptr::copy_nonoverlapping(text.as_ptr(), buffer as *mut u8, copy_len);
+
+        // Null-terminate the string
+        *buffer.add(copy_len) = 0;
+    }
+
+    text.len() + 1
+}
+
+/// Copy a string into a caller-provided buffer, reporting the size needed through `required_size`
+///
+/// At least one of `buffer` and `required_size` must be given.
+///
+/// # Safety
+///
+/// `buffer` must be null or point to a writable buffer of at least
+/// `buffer_size` bytes, and `required_size` must be null or point to a writable `size_t`.
+unsafe fn copy_string_result(text: &str, buffer: *mut c_char, buffer_size: usize, required_size: *mut usize) -> crate::Result<()> {
+    if buffer.is_null() && required_size.is_null() {
+        return Err(DasherError::InvalidParameter("buffer and required_size pointers are both null".to_string()));
+    }
+    let required = copy_to_buffer(text, buffer, buffer_size);
+    if !required_size.is_null() {
+        *required_size = required;
+    }
+    Ok(())
+}
+
+/// Get the output text
+///
+/// `required_size`, if not null, is set to the buffer size needed for the
+/// whole text, including the terminator. Pass a null buffer to only query it.
+///
+/// # Safety
+///
+/// `interface` must be a valid pointer created by `dasher_interface_create`,
+/// `buffer` must be null or point to a writable buffer of at least `buffer_size` bytes,
+/// and `required_size` must be null or point to a writable `size_t`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_get_output(
+    interface: *mut DasherInterfaceFFI,
+    buffer: *mut c_char,
+    buffer_size: usize,
+    required_size: *mut usize
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        copy_string_result(interface.interface.get_output_text(), buffer, buffer_size, required_size)
+    })
+}
+
+/// Transform Dasher coordinates to screen coordinates
+///
+/// # Safety
+///
+/// This function is unsafe because it dereferences raw pointers.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_transform_coordinates(
+    dasher_x: i64,
+    dasher_y: i64,
+    screen_width: i32,
+    screen_height: i32,
+    orientation: i32,
+    screen_x: *mut i32,
+    screen_y: *mut i32
+) -> DasherErrorCode {
+    error::guard(|| {
+        let screen_x = error::non_null(screen_x, "screen_x")?;
+        let screen_y = error::non_null(screen_y, "screen_y")?;
+
+        let (x, y) = coordinates::dasher_to_screen(
+            dasher_x,
+            dasher_y,
+            screen_width,
+            screen_height,
+            orientation
+        );
+
+        *screen_x = x;
+        *screen_y = y;
+
+        Ok(())
+    })
+}
+
+/// Transform screen coordinates to Dasher coordinates
+///
+/// # Safety
+///
+/// This function is unsafe because it dereferences raw pointers.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_screen_to_dasher(
+    screen_x: i32,
+    screen_y: i32,
+    screen_width: i32,
+    screen_height: i32,
+    orientation: i32,
+    dasher_x: *mut i64,
+    dasher_y: *mut i64
+) -> DasherErrorCode {
+    error::guard(|| {
+        let dasher_x = error::non_null(dasher_x, "dasher_x")?;
+        let dasher_y = error::non_null(dasher_y, "dasher_y")?;
+
+        let (x, y) = coordinates::screen_to_dasher(
+            screen_x,
+            screen_y,
+            screen_width,
+            screen_height,
+            orientation
+        );
+
+        *dasher_x = x;
+        *dasher_y = y;
+
+        Ok(())
+    })
+}
+
+/// Transform a rectangle from Dasher coordinates to screen coordinates
+///
+/// # Safety
+///
+/// This function is unsafe because it dereferences raw pointers.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_transform_rectangle(
+    dasher_x1: i64,
+    dasher_y1: i64,
+    dasher_x2: i64,
+    dasher_y2: i64,
+    screen_width: i32,
+    screen_height: i32,
+    orientation: i32,
+    screen_x1: *mut i32,
+    screen_y1: *mut i32,
+    screen_x2: *mut i32,
+    screen_y2: *mut i32
+) -> DasherErrorCode {
+    error::guard(|| {
+        let screen_x1 = error::non_null(screen_x1, "screen_x1")?;
+        let screen_y1 = error::non_null(screen_y1, "screen_y1")?;
+        let screen_x2 = error::non_null(screen_x2, "screen_x2")?;
+        let screen_y2 = error::non_null(screen_y2, "screen_y2")?;
+
+        let (x1, y1, x2, y2) = coordinates::transform_rectangle(
+            dasher_x1,
+            dasher_y1,
+            dasher_x2,
+            dasher_y2,
+            screen_width,
+            screen_height,
+            orientation
+        );
+
+        *screen_x1 = x1;
+        *screen_y1 = y1;
+        *screen_x2 = x2;
+        *screen_y2 = y2;
+
+        Ok(())
+    })
+}
+
+/// Create a default square view configuration
+#[no_mangle]
+pub extern "C" fn dasher_create_square_view_config() -> *mut SquareViewConfigFFI {
+    error::guard_or(std::ptr::null_mut(), || {
+        let config = SquareViewConfigFFI::default();
+        Ok(Box::into_raw(Box::new(config)))
+    })
+}
+
+/// Destroy a square view configuration
+///
+/// # Safety
+///
+/// This function is unsafe because it deallocates memory from a raw pointer.
+/// The pointer must have been created by `dasher_create_square_view_config`.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_destroy_square_view_config(config: *mut SquareViewConfigFFI) {
+    error::guard(|| {
+        if !config.is_null() {
+            let _ = Box::from_raw(config);
+        }
+        Ok(())
+    });
+}
+
+/// Set the square view configuration for a Dasher interface
+///
+/// # Safety
+///
+/// This function is unsafe because it dereferences raw pointers.
+/// Both `interface` and `config` must be valid pointers to their respective types.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_interface_set_square_view_config(
+    interface: *mut DasherInterfaceFFI,
+    config: *const SquareViewConfigFFI
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let config = error::non_null(config as *mut SquareViewConfigFFI, "config")?;
+
+        // Convert FFI config to Rust config
+        let rust_config: SquareViewConfig = config::ffi_to_rust_config(config);
+
+        // Get the square view from the interface
+        let square_view = config::get_square_view(&mut interface.interface)
+            .ok_or_else(|| DasherError::RenderingError("No square view is set".to_string()))?;
+
+        // Update the configuration
+        *square_view.config_mut() = rust_config;
+        Ok(())
+    })
 }
 
-/// Set the draw rectangle callback for a screen
+/// Get the square view configuration from a Dasher interface
+///
+/// # Safety
+///
+/// This function is unsafe because it dereferences raw pointers.
+/// Both `interface` and `config` must be valid pointers to their respective types.
 #[no_mangle]
-pub unsafe extern "C" fn dasher_screen_set_draw_rectangle_callback(
-    screen: *mut DasherScreenFFI,
-    callback: extern "C" fn(x1: i32, y1: i32, x2: i32, y2: i32,
-                          fill_r: u8, fill_g: u8, fill_b: u8, fill_a: u8,
-                          outline_r: u8, outline_g: u8, outline_b: u8, outline_a: u8,
-                          line_width: i32),
-) {
-    if screen.is_null() {
-        return;
-    }
+pub unsafe extern "C" fn dasher_interface_get_square_view_config(
+    interface: *mut DasherInterfaceFFI,
+    config: *mut SquareViewConfigFFI
+) -> DasherErrorCode {
+    error::guard(|| {
+        let interface = error::non_null(interface, "interface")?;
+        let config = error::non_null(config, "config")?;
 
-    (*screen).screen.set_draw_rectangle_fn(callback);
+        // Get the square view from the interface
+        let square_view = config::get_square_view(&mut interface.interface)
+            .ok_or_else(|| DasherError::RenderingError("No square view is set".to_string()))?;
+
+        // Convert Rust config to FFI config
+        *config = square_view.config().clone().into();
+        Ok(())
+    })
 }
 
-/// Set the draw circle callback for a screen
+// Functions moved to avoid duplication
+
+/// Get the version of the library, such as "0.1.0"
+///
+/// The returned string is static.
 #[no_mangle]
-pub unsafe extern "C" fn dasher_screen_set_draw_circle_callback(
-    screen: *mut DasherScreenFFI,
-    callback: extern "C" fn(cx: i32, cy: i32, r: i32,
-                          fill_r: u8, fill_g: u8, fill_b: u8, fill_a: u8,
-                          line_r: u8, line_g: u8, line_b: u8, line_a: u8,
-                          line_width: i32),
-) {
-    if screen.is_null() {
-        return;
-    }
+pub extern "C" fn dasher_core_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
+}
 
-    (*screen).screen.set_draw_circle_fn(callback);
+/// Check whether the library was built with an optional feature
+///
+/// Known features are "ppm", "arpa", "conversion", "wasm", "svg", "raster",
+/// "tracing" and "candle". Returns false for unknown features and on error.
+///
+/// # Safety
+///
+/// `name` must point to a null-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn dasher_core_has_feature(name: *const c_char) -> bool {
+    error::guard_or(false, || {
+        let name = c_str_arg(name, "name")?;
+        Ok(crate::has_feature(name))
+    })
 }
 
-/// Set the draw line callback for a screen
+/// Get a description of the last error raised by an FFI call on this thread
+///
+/// Returns null if the most recent call succeeded. The returned string is owned
+/// by the library and remains valid until the next FFI call on the same thread.
 #[no_mangle]
-pub unsafe extern "C" fn dasher_screen_set_draw_line_callback(
-    screen: *mut DasherScreenFFI,
-    callback: extern "C" fn(x1: i32, y1: i32, x2: i32, y2: i32,
-                          r: u8, g: u8, b: u8, a: u8,
-                          line_width: i32),
-) {
-    if screen.is_null() {
-        return;
-    }
+pub extern "C" fn dasher_last_error_message() -> *const c_char {
+    error::last_error_ptr()
+}
 
-    (*screen).screen.set_draw_line_fn(callback);
+/// Register a callback receiving diagnostic messages from the core
+///
+/// Messages at `level` or more severe are delivered (0 = error, 1 = warning,
+/// 2 = info, 3 = debug). Passing a null callback removes any registered callback,
+/// after which diagnostics are no longer delivered to the host.
+#[no_mangle]
+pub extern "C" fn dasher_set_log_callback(
+    level: i32,
+    callback: Option<extern "C" fn(level: i32, message: *const c_char)>,
+) -> DasherErrorCode {
+    error::guard(|| {
+        let max_level = LogLevel::from_i32(level)
+            .ok_or_else(|| DasherError::InvalidParameter(format!("Invalid log level: {}", level)))?;
+
+        match callback {
+            Some(f) => logging::set_log_sink(max_level, move |level, target, message| {
+                let c_message = CString::new(format!("{}: {}", target, message).replace('\0', "")).unwrap_or_default();
+                f(level as i32, c_message.as_ptr());
+            }),
+            None => logging::clear_log_sink(),
+        }
+        Ok(())
+    })
 }
 
-/// Set the draw string callback for a screen
+/// Set the most verbose level of diagnostics delivered from a target
+///
+/// Targets are module paths such as `dasher_core::model`, and cover the
+/// modules within them. A negative level removes the target's own level, so
+/// it follows the level given to `dasher_set_log_callback` again.
+///
+/// # Safety
+///
+/// `target` must point to a null-terminated C string.
 #[no_mangle]
-pub unsafe extern "C" fn dasher_screen_set_draw_string_callback(
-    screen: *mut DasherScreenFFI,
-    callback: extern "C" fn(text: *const c_char, x: i32, y: i32, size: i32,
-                          r: u8, g: u8, b: u8, a: u8),
-) {
-    if screen.is_null() {
-        return;
-    }
+pub unsafe extern "C" fn dasher_set_log_target_level(target: *const c_char, level: i32) -> DasherErrorCode {
+    error::guard(|| {
+        let target = c_str_arg(target, "target")?;
+        let level = if level < 0 {
+            None
+        } else {
+            Some(LogLevel::from_i32(level)
+                .ok_or_else(|| DasherError::InvalidParameter(format!("Invalid log level: {}", level)))?)
+        };
+        logging::set_target_level(target, level);
+        Ok(())
+    })
+}
 
-    (*screen).screen.set_draw_string_fn(callback);
+/// Enable or disable debug mode
+#[no_mangle]
+pub extern "C" fn dasher_set_debug_mode(enable: bool) -> DasherErrorCode {
+    error::guard(|| {
+        context::get_global_context().set_debug_mode(enable);
+        Ok(())
+    })
 }
 
-/// Set the make label callback for a screen
+/// Get debug mode
 #[no_mangle]
-pub unsafe extern "C" fn dasher_screen_set_make_label_callback(
-    screen: *mut DasherScreenFFI,
-    callback: extern "C" fn(text: *const c_char, size: i32) -> *mut std::ffi::c_void,
-) {
-    if screen.is_null() {
-        return;
-    }
+pub extern "C" fn dasher_get_debug_mode() -> bool {
+    error::guard_or(false, || {
+        Ok(context::get_global_context().get_debug_mode())
+    })
+}
 
-    (*screen).screen.set_make_label_fn(callback);
+/// Get the number of error messages
+#[no_mangle]
+pub extern "C" fn dasher_get_error_count() -> i32 {
+    error::guard_or(0, || {
+        Ok(context::get_global_context().error_messages().len() as i32)
+    })
 }
 
-/// Set the destroy label callback for a screen
+/// Get an error message
+///
+/// `required_size`, if not null, is set to the buffer size needed for the
+/// whole message, including the terminator. Pass a null buffer to only query it.
+///
+/// # Safety
+///
+/// `buffer` must be null or point to a writable buffer of at least
+/// `buffer_size` bytes, and `required_size` must be null or point to a writable `size_t`.
 #[no_mangle]
-pub unsafe extern "C" fn dasher_screen_set_destroy_label_callback(
-    screen: *mut DasherScreenFFI,
-    callback: extern "C" fn(label: *mut std::ffi::c_void),
-) {
-    if screen.is_null() {
-        return;
-    }
+pub unsafe extern "C" fn dasher_get_error_message(
+    index: i32,
+    buffer: *mut c_char,
+    buffer_size: usize,
+    required_size: *mut usize
+) -> DasherErrorCode {
+    error::guard(|| {
+        let errors = context::get_global_context().error_messages();
+        let message = usize::try_from(index).ok()
+            .and_then(|index| errors.get(index))
+            .ok_or_else(|| DasherError::InvalidParameter(format!("No error message at index {}", index)))?;
+
+        copy_string_result(message, buffer, buffer_size, required_size)
+    })
+}
 
-    (*screen).screen.set_destroy_label_fn(callback);
+/// Get the number of debug messages
+#[no_mangle]
+pub extern "C" fn dasher_get_debug_message_count() -> i32 {
+    error::guard_or(0, || {
+        Ok(context::get_global_context().debug_messages().len() as i32)
+    })
 }
 
-/// Set the get text size callback for a screen
+/// Get a debug message
+///
+/// `required_size`, if not null, is set to the buffer size needed for the
+/// whole message, including the terminator. Pass a null buffer to only query it.
+///
+/// # Safety
+///
+/// `buffer` must be null or point to a writable buffer of at least
+/// `buffer_size` bytes, and `required_size` must be null or point to a writable `size_t`.
 #[no_mangle]
-pub unsafe extern "C" fn dasher_screen_set_get_text_size_callback(
-    screen: *mut DasherScreenFFI,
-    callback: extern "C" fn(label: *mut std::ffi::c_void, size: i32, width: *mut i32, height: *mut i32),
-) {
-    if screen.is_null() {
-        return;
-    }
+pub unsafe extern "C" fn dasher_get_debug_message(
+    index: i32,
+    buffer: *mut c_char,
+    buffer_size: usize,
+    required_size: *mut usize
+) -> DasherErrorCode {
+    error::guard(|| {
+        let messages = context::get_global_context().debug_messages();
+        let message = usize::try_from(index).ok()
+            .and_then(|index| messages.get(index))
+            .ok_or_else(|| DasherError::InvalidParameter(format!("No debug message at index {}", index)))?;
+
+        copy_string_result(message, buffer, buffer_size, required_size)
+    })
+}
 
-    (*screen).screen.set_get_text_size_fn(callback);
+/// Clear the error and debug messages, once the host has read them
+#[no_mangle]
+pub extern "C" fn dasher_clear_messages() {
+    error::guard_or((), || {
+        let context = context::get_global_context();
+        context.get_errors();
+        context.get_debug_messages();
+        Ok(())
+    })
 }
 
-/// Set the screen for a DasherInterface
+/// Add a debug message
+///
+/// # Safety
+///
+/// This function is unsafe because it dereferences raw pointers.
+/// The `message` pointer must be valid and point to a null-terminated C string.
 #[no_mangle]
-pub unsafe extern "C" fn dasher_interface_set_screen(
-    interface: *mut DasherInterfaceFFI,
-    screen: *mut DasherScreenFFI,
-) -> bool {
-    println!("FFI: Setting screen for interface");
+pub unsafe extern "C" fn dasher_add_debug_message(
+    message: *const c_char
+) -> DasherErrorCode {
+    error::guard(|| {
+        let message = c_str_arg(message, "message")?;
+        context::get_global_context().add_debug(message);
+        Ok(())
+    })
+}
+
+// Functions moved to avoid duplication
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicI32, Ordering};
+    use crate::model::{LanguageLayer, LearningMode};
+    use crate::settings::Parameter;
+
+    static POLYGON_POINTS: AtomicI32 = AtomicI32::new(0);
+    static SPOKEN_LENGTH: AtomicI32 = AtomicI32::new(-1);
+    static CLIPBOARD_LENGTH: AtomicI32 = AtomicI32::new(-1);
+    static TRAINING_PERCENT: AtomicI32 = AtomicI32::new(-1);
+    static VISIBLE_NODES: AtomicI32 = AtomicI32::new(0);
+    static LABELLED_NODES: AtomicI32 = AtomicI32::new(0);
+
+    extern "C" fn record_polygon(_points: *const PointFFI, num_points: i32,
+                                 _fill_r: u8, _fill_g: u8, _fill_b: u8, _fill_a: u8,
+                                 _outline_r: u8, _outline_g: u8, _outline_b: u8, _outline_a: u8,
+                                 _line_width: i32) {
+        POLYGON_POINTS.store(num_points, Ordering::SeqCst);
+    }
+
+    extern "C" fn record_visible_node(node: *const VisibleNodeFFI) {
+        let node = unsafe { &*node };
+        VISIBLE_NODES.fetch_add(1, Ordering::SeqCst);
+        if !node.label.is_null() {
+            LABELLED_NODES.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    extern "C" fn record_speech(text: *const c_char) {
+        let text = unsafe { CStr::from_ptr(text) };
+        SPOKEN_LENGTH.store(text.to_bytes().len() as i32, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn test_load_alphabet_xml() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let xml = CString::new(r#"<alphabet name="Tiny" orientation="LR">
+            <group name="Letters"><node label="x"/><node label="y"/></group>
+        </alphabet>"#).unwrap();
+        let bad_xml = CString::new("<alphabet name=\"Empty\"></alphabet>").unwrap();
+
+        unsafe {
+            assert_eq!(dasher_interface_load_alphabet_xml(interface, xml.as_ptr()), DasherErrorCode::Success);
+            let alphabet = (*interface).interface.model().alphabet().unwrap();
+            assert_eq!(alphabet.name(), "Tiny");
+            assert_eq!(alphabet.size(), 2);
+
+            assert_eq!(dasher_interface_load_alphabet_xml(interface, bad_xml.as_ptr()), DasherErrorCode::InvalidParameter);
+            assert!(!dasher_last_error_message().is_null());
+            assert_eq!((*interface).interface.model().alphabet().unwrap().name(), "Tiny");
+
+            dasher_interface_destroy(interface);
+        }
+    }
+
+    #[test]
+    fn test_error_history_keeps_the_most_recent_messages() {
+        let context = context::FFIContext::new();
+        for index in 0..context::FFIContext::MAX_MESSAGES + 10 {
+            context.add_error(&format!("error {}", index));
+        }
 
-    if interface.is_null() {
-        println!("FFI: Interface pointer is null");
-        return false;
+        let errors = context.error_messages();
+        assert_eq!(errors.len(), context::FFIContext::MAX_MESSAGES);
+        assert_eq!(errors[0], "error 10");
+        assert_eq!(context.get_errors().len(), context::FFIContext::MAX_MESSAGES);
+        assert!(context.error_messages().is_empty());
     }
 
-    if screen.is_null() {
-        println!("FFI: Screen pointer is null");
-        return false;
+    #[test]
+    fn test_set_alphabet_keeps_output() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let xml = CString::new(r#"<alphabet name="Tiny" orientation="LR">
+            <group name="Letters"><node label="x"/><node label="y"/></group>
+        </alphabet>"#).unwrap();
+        let file_id = CString::new("english.lower.case").unwrap();
+        let name_id = CString::new("English, lower case").unwrap();
+        let unknown_id = CString::new("no.such.alphabet").unwrap();
+
+        unsafe {
+            assert_eq!(dasher_interface_load_alphabet_xml(interface, xml.as_ptr()), DasherErrorCode::Success);
+            (*interface).interface.edit_output("xy");
+
+            assert_eq!(dasher_interface_set_alphabet(interface, unknown_id.as_ptr()), DasherErrorCode::InvalidParameter);
+            assert_eq!((*interface).interface.model().alphabet().unwrap().name(), "Tiny");
+
+            assert_eq!(dasher_interface_set_alphabet(interface, file_id.as_ptr()), DasherErrorCode::Success);
+            let dasher = &(*interface).interface;
+            assert_eq!(dasher.model().alphabet().unwrap().name(), "English, lower case");
+            assert_eq!(dasher.get_output_text(), "xy");
+            assert_eq!(dasher.settings().get_string(Parameter::AlphabetID), Some("english.lower.case"));
+            assert!(dasher.model().get_root_node().unwrap().borrow().children().len() > 2);
+
+            assert_eq!(dasher_interface_load_alphabet_xml(interface, xml.as_ptr()), DasherErrorCode::Success);
+            assert_eq!(dasher_interface_set_alphabet(interface, name_id.as_ptr()), DasherErrorCode::Success);
+            assert_eq!((*interface).interface.model().alphabet().unwrap().name(), "English, lower case");
+
+            dasher_interface_destroy(interface);
+        }
     }
 
-    let interface = &mut *interface;
-    let screen_ref = &mut *screen;
+    #[test]
+    fn test_alphabet_trains_from_its_training_file() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let xml = CString::new(r#"<alphabet name="Tiny" orientation="LR">
+            <group name="Letters"><node label="x"/><node label="y"/></group>
+        </alphabet>"#).unwrap();
+        let id = CString::new("english.without.punctuation").unwrap();
+
+        unsafe {
+            // The tiny alphabet has no training file, so starts untrained
+            assert_eq!(dasher_interface_load_alphabet_xml(interface, xml.as_ptr()), DasherErrorCode::Success);
+            (*interface).interface.edit_output("th");
+            let probs = (*interface).interface.model().get_probabilities().unwrap();
+            assert!(probs.is_empty());
+
+            assert_eq!(dasher_interface_set_alphabet(interface, id.as_ptr()), DasherErrorCode::Success);
+            let probs = (*interface).interface.model().get_probabilities().unwrap();
+            assert!(probs.iter().any(|&(c, _)| c == 'e'));
+
+            dasher_interface_destroy(interface);
+        }
+    }
 
-    println!("FFI: Screen dimensions: {}x{}", screen_ref.screen.get_width(), screen_ref.screen.get_height());
+    #[test]
+    fn test_profiles_keep_their_models() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let english = CString::new("English").unwrap();
+        let tiny = CString::new("Tiny").unwrap();
+        let english_lc = CString::new("english.lower.case").unwrap();
+        let unknown = CString::new("Unknown").unwrap();
+
+        unsafe {
+            let dasher = &mut (*interface).interface;
+            let xml = r#"<alphabet name="Tiny" orientation="LR">
+                <group name="Letters"><node label="x"/><node label="y"/></group>
+            </alphabet>"#;
+            dasher.add_profile("Tiny", crate::alphabet::parse_alphabet(xml).unwrap()).unwrap();
+            assert_eq!(dasher_interface_add_profile(interface, english.as_ptr(), english_lc.as_ptr()), DasherErrorCode::InvalidParameter);
+            assert_eq!((*interface).interface.profile_names(), vec!["English", "Tiny"]);
+
+            (*interface).interface.edit_output("th");
+            assert_eq!(dasher_interface_switch_profile(interface, tiny.as_ptr()), DasherErrorCode::Success);
+            let dasher = &(*interface).interface;
+            assert_eq!(dasher.profile_name(), "Tiny");
+            assert_eq!(dasher.model().alphabet().unwrap().name(), "Tiny");
+            assert_eq!(dasher.get_output_text(), "th");
+
+            assert_eq!(dasher_interface_switch_profile(interface, unknown.as_ptr()), DasherErrorCode::InvalidParameter);
+            assert_eq!(dasher_interface_switch_profile(interface, english.as_ptr()), DasherErrorCode::Success);
+            let probs = (*interface).interface.model().get_probabilities().unwrap();
+            assert!(probs.iter().any(|&(c, _)| c == 'e'));
+
+            dasher_interface_destroy(interface);
+        }
+    }
 
-    // Clone the screen
-    let screen_clone = screen_ref.screen.clone();
+    #[test]
+    fn test_word_separators_setting() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let separators = CString::new(" 。").unwrap();
 
-    // Create a square view with the screen
-    println!("FFI: Creating square view with screen");
-    let mut view = Box::new(DasherViewSquare::new(Box::new(screen_clone)));
+        unsafe {
+            let api = &mut (*interface).interface;
+            api.add_word_separator('¿');
+            api.remove_word_separator('?');
+            assert_eq!(api.settings().get_string(Parameter::WordSeparators), Some(" \t\n.,!¿"));
+            assert!(api.model().word_separators().contains(&'¿'));
+            assert!(!api.model().word_separators().contains(&'?'));
 
-    // Configure the view with default settings for flowing interface
-    println!("FFI: Configuring square view");
+            assert_eq!(dasher_settings_set_string(interface, ParameterFFI::WordSeparators as i32, separators.as_ptr()), DasherErrorCode::Success);
+            assert_eq!((*interface).interface.word_separators(), [' ', '。']);
+            assert_eq!((*interface).interface.model().word_separators().len(), 2);
 
-    // Enable flowing interface
-    view.set_flowing_interface(true);
-    println!("FFI: Enabled flowing interface");
+            dasher_interface_destroy(interface);
+        }
+    }
 
-    // Set flowing speed
-    view.set_flowing_speed(2.0);
-    println!("FFI: Set flowing speed to 2.0");
+    #[test]
+    fn test_default_data_is_loaded_on_request() {
+        let interface = dasher_interface_create(std::ptr::null());
 
-    // Enable X nonlinearity
-    view.set_x_nonlinear(true);
-    println!("FFI: Enabled X nonlinearity");
+        unsafe {
+            // Only the model's built-in sentence is trained until the data is asked for
+            let trained = |interface: *mut DasherInterfaceFFI| {
+                (*interface).interface.export_language_layer(LanguageLayer::Base).unwrap().len()
+            };
+            let built_in = trained(interface);
+            assert_eq!(dasher_interface_load_default_data(interface), DasherErrorCode::Success);
+            assert!(trained(interface) > built_in);
+            assert_eq!(dasher_interface_load_default_data(std::ptr::null_mut()), DasherErrorCode::InvalidParameter);
 
-    // Enable Y nonlinearity
-    view.set_y_nonlinear(true);
-    println!("FFI: Enabled Y nonlinearity");
+            dasher_interface_destroy(interface);
+        }
+    }
 
-    // Set node shape to Rectangle
-    view.set_node_shape(NodeShape::Rectangle);
-    println!("FFI: Set node shape to Rectangle");
+    #[test]
+    fn test_user_lexicon_is_loaded_on_request() {
+        let interface = dasher_interface_create(std::ptr::null());
 
-    // Enable crosshair, cursor, and outlines
-    view.config_mut().draw_crosshair = true;
-    view.config_mut().draw_cursor = true;
-    view.config_mut().draw_outlines = true;
-    println!("FFI: Enabled crosshair, cursor, and outlines");
+        unsafe {
+            let lexicon_path = |interface: *mut DasherInterfaceFFI| {
+                (*interface).interface.model_mut().personal_lexicon_mut().unwrap().path().map(|path| path.to_path_buf())
+            };
+            assert_eq!(lexicon_path(interface), None);
 
-    // Set the view
-    println!("FFI: Setting view for interface");
-    let result = interface.interface.set_view(view);
+            assert_eq!(dasher_interface_load_user_lexicon(interface), DasherErrorCode::Success);
+            assert_eq!(lexicon_path(interface), crate::model::PersonalLexicon::user_path());
+            assert_eq!(dasher_interface_load_user_lexicon(std::ptr::null_mut()), DasherErrorCode::InvalidParameter);
 
-    if result.is_ok() {
-        println!("FFI: View set successfully");
-        true
-    } else {
-        println!("FFI: Failed to set view: {:?}", result);
-        false
+            dasher_interface_destroy(interface);
+        }
     }
-}
 
-/// Node shape types for FFI
-#[repr(C)]
-pub enum NodeShapeFFI {
-    /// Rectangle shape
-    Rectangle = 0,
-    /// Triangle shape
-    Triangle = 1,
-    /// Truncated triangle shape
-    TruncatedTriangle = 2,
-    /// Circle shape
-    Circle = 3,
-    /// Quadric shape (curved)
-    Quadric = 4,
-}
+    #[test]
+    fn test_user_actions_are_loaded_on_request() {
+        let interface = dasher_interface_create(std::ptr::null());
 
-impl From<NodeShapeFFI> for crate::view::NodeShape {
-    fn from(shape: NodeShapeFFI) -> Self {
-        match shape {
-            NodeShapeFFI::Rectangle => crate::view::NodeShape::Rectangle,
-            NodeShapeFFI::Triangle => crate::view::NodeShape::Triangle,
-            NodeShapeFFI::TruncatedTriangle => crate::view::NodeShape::TruncatedTriangle,
-            NodeShapeFFI::Circle => crate::view::NodeShape::Circle,
-            NodeShapeFFI::Quadric => crate::view::NodeShape::Quadric,
+        unsafe {
+            assert!(dasher_interface_load_user_actions(interface) >= 0);
+            assert_eq!(dasher_interface_load_user_actions(std::ptr::null_mut()), -1);
+
+            dasher_interface_destroy(interface);
         }
     }
-}
 
-/// Set the node shape for the Square View
-#[no_mangle]
-pub unsafe extern "C" fn dasher_interface_set_node_shape(
-    interface: *mut DasherInterfaceFFI,
-    shape: NodeShapeFFI,
-) -> bool {
-    if interface.is_null() {
-        return false;
+    #[test]
+    fn test_reset_personalization_keeps_training() {
+        let interface = dasher_interface_create(std::ptr::null());
+
+        unsafe {
+            let api = &mut (*interface).interface;
+            let base = api.export_language_layer(LanguageLayer::Base).unwrap();
+            let untrained = api.export_language_layer(LanguageLayer::User).unwrap();
+
+            // Writing adapts the user layer only
+            "zork ".chars().for_each(|c| api.model_mut().append_to_output(c));
+            let user = api.export_language_layer(LanguageLayer::User).unwrap();
+            assert_ne!(user, untrained);
+            assert_eq!(api.export_language_layer(LanguageLayer::Base).unwrap(), base);
+
+            api.reset_personalization();
+            assert_eq!(api.export_language_layer(LanguageLayer::User).unwrap(), untrained);
+            assert_eq!(api.export_language_layer(LanguageLayer::Base).unwrap(), base);
+            assert_eq!(api.model_mut().personal_lexicon_mut().unwrap().count("zork"), 0);
+
+            api.import_language_layer(LanguageLayer::User, &user).unwrap();
+            assert_eq!(api.export_language_layer(LanguageLayer::User).unwrap(), user);
+            assert!(api.import_language_layer(LanguageLayer::User, "{").is_err());
+
+            dasher_interface_destroy(interface);
+        }
     }
 
-    let interface = &mut *interface;
-    let result = interface.interface.set_node_shape(shape.into());
-    result.is_ok()
-}
+    #[test]
+    fn test_corpus_records_committed_sentences() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let path = std::env::temp_dir().join(format!("dasher_ffi_corpus_{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let c_path = CString::new(path.to_str().unwrap()).unwrap();
+        let host_sentence = CString::new("From the host.").unwrap();
+
+        unsafe {
+            assert_eq!(dasher_interface_set_corpus_path(interface, c_path.as_ptr()), DasherErrorCode::Success);
+            "Not recorded. ".chars().for_each(|c| (*interface).interface.model_mut().append_to_output(c));
+            assert!(!path.exists());
+
+            assert_eq!(dasher_settings_set_bool(interface, ParameterFFI::RecordCorpus as i32, true), DasherErrorCode::Success);
+            "Hi there. Draft".chars().for_each(|c| (*interface).interface.model_mut().append_to_output(c));
+            (*interface).interface.execute_action("accept").unwrap();
+            assert_eq!(dasher_interface_record_sentence(interface, host_sentence.as_ptr()), DasherErrorCode::Success);
+
+            let corpus = std::fs::read_to_string(&path).unwrap();
+            let sentences: Vec<_> = corpus.lines().map(|line| line.rsplit('§').next().unwrap()).collect();
+            assert_eq!(sentences, ["Hi there.", "Draft", "From the host."]);
+            assert!(corpus.starts_with('§'));
+
+            // Without a file the sentences are kept for the host
+            (*interface).interface.set_corpus_path(None);
+            "\nKept.".chars().for_each(|c| (*interface).interface.model_mut().append_to_output(c));
+            assert!((*interface).interface.take_corpus_text().ends_with("§Kept.\n"));
+
+            dasher_interface_destroy(interface);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
 
-/// Enable or disable X nonlinearity
-#[no_mangle]
-pub unsafe extern "C" fn dasher_interface_set_x_nonlinear(
-    interface: *mut DasherInterfaceFFI,
-    enable: bool,
-) -> bool {
-    if interface.is_null() {
-        return false;
+    #[test]
+    fn test_learning_settings() {
+        let interface = dasher_interface_create(std::ptr::null());
+
+        unsafe {
+            assert_eq!((*interface).interface.model().learning_mode(), LearningMode::WhileWriting);
+            assert_eq!(dasher_settings_set_bool(interface, ParameterFFI::LearnAtSentenceEnd as i32, true), DasherErrorCode::Success);
+            assert_eq!((*interface).interface.model().learning_mode(), LearningMode::AtSentenceEnd);
+            assert_eq!(dasher_settings_set_bool(interface, ParameterFFI::LearnWhileWriting as i32, false), DasherErrorCode::Success);
+            assert_eq!((*interface).interface.model().learning_mode(), LearningMode::Off);
+
+            dasher_interface_destroy(interface);
+        }
     }
 
-    let interface = &mut *interface;
-    let result = interface.interface.set_x_nonlinear(enable);
-    result.is_ok()
-}
+    #[test]
+    fn test_pointer_smoothing_settings() {
+        let interface = dasher_interface_create(std::ptr::null());
 
-/// Enable or disable Y nonlinearity
-#[no_mangle]
-pub unsafe extern "C" fn dasher_interface_set_y_nonlinear(
-    interface: *mut DasherInterfaceFFI,
-    enable: bool,
-) -> bool {
-    if interface.is_null() {
-        return false;
+        unsafe {
+            assert!(!(*interface).interface.input_manager().smoother().is_active());
+            assert_eq!(dasher_settings_set_long(interface, ParameterFFI::PointerSmoothing as i32, 3), DasherErrorCode::Success);
+            assert_eq!(dasher_settings_set_long(interface, ParameterFFI::PointerSmoothingY as i32, 80), DasherErrorCode::Success);
+
+            let smoother = (*interface).interface.input_manager().smoother();
+            assert_eq!(smoother.method(), crate::input::SmoothingMethod::Kalman);
+            assert_eq!(smoother.strength(), (0.5, 0.8));
+
+            dasher_interface_destroy(interface);
+        }
     }
 
-    let interface = &mut *interface;
-    let result = interface.interface.set_y_nonlinear(enable);
-    result.is_ok()
-}
+    #[test]
+    fn test_input_calibration_is_kept_per_device() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let screen = dasher_create_screen(800, 600);
+        let mouse = dasher_create_mouse_input();
+        let mut buffer = [0 as c_char; 128];
+
+        unsafe {
+            assert_eq!(dasher_interface_set_input_calibration(interface, 10, -5, 1.0, 1.0), DasherErrorCode::InputError);
+
+            assert_eq!(dasher_interface_set_screen(interface, screen), DasherErrorCode::Success);
+            assert_eq!(dasher_interface_set_input(interface, mouse), DasherErrorCode::Success);
+            assert_eq!(dasher_interface_set_input_calibration(interface, 10, -5, 2.0, 1.0), DasherErrorCode::Success);
+
+            // Gain scales about the centre of the screen, then the offset moves the pointer
+            let mut device = (*interface).interface.input_manager().get_input_device().unwrap().box_clone();
+            device.activate();
+            device.set_screen_position(500, 300);
+            let view = (*interface).interface.view().unwrap();
+            assert_eq!(device.get_screen_coordinates(view), Some((610, 295)));
+
+            // The calibration is stored in the settings and given to a new device of the same kind
+            assert_eq!(dasher_settings_get_string(interface, ParameterFFI::InputCalibration as i32, buffer.as_mut_ptr(), buffer.len(), std::ptr::null_mut()), DasherErrorCode::Success);
+            assert!(CStr::from_ptr(buffer.as_ptr()).to_str().unwrap().contains("\"Mouse\""));
+            (*interface).interface.set_input(Box::new(MouseInput::new()));
+            assert_eq!((*interface).interface.input_calibration().map(|c| (c.x_offset, c.y_offset, c.x_gain)), Some((10, -5, 2.0)));
+
+            dasher_destroy_input(mouse);
+            dasher_interface_destroy(interface);
+        }
+    }
 
-/// Enable or disable 3D text
-#[no_mangle]
-pub unsafe extern "C" fn dasher_interface_set_text_3d(
-    interface: *mut DasherInterfaceFFI,
-    enable: bool,
-) -> bool {
-    if interface.is_null() {
-        return false;
+    #[test]
+    fn test_mouse_coordinates_update_in_place() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let screen = dasher_create_screen(800, 600);
+        let mouse = dasher_create_mouse_input();
+        let touch = dasher_create_touch_input();
+
+        unsafe {
+            assert_eq!(dasher_interface_set_mouse_position(interface, 10, 10), DasherErrorCode::InputError);
+            assert_eq!(dasher_interface_set_screen(interface, screen), DasherErrorCode::Success);
+            let view = (*interface).interface.view().unwrap();
+
+            // The device keeps its calibration and state
+            (*mouse).input.set_calibration(Calibration { x_offset: 10, ..Calibration::default() });
+            (*mouse).input.activate();
+            assert_eq!(dasher_set_mouse_coordinates(mouse, 100, 200), DasherErrorCode::Success);
+            assert_eq!((*mouse).input.get_screen_coordinates(view), Some((110, 200)));
+            assert_eq!(dasher_set_mouse_coordinates(touch, 100, 200), DasherErrorCode::InputError);
+
+            // The interface moves its own copy of the device
+            assert_eq!(dasher_interface_set_input(interface, mouse), DasherErrorCode::Success);
+            assert_eq!(dasher_interface_set_mouse_position(interface, 300, 400), DasherErrorCode::Success);
+            let device = (*interface).interface.input_manager().get_input_device().unwrap();
+            let view = (*interface).interface.view().unwrap();
+            assert_eq!(device.get_screen_coordinates(view), Some((310, 400)));
+
+            dasher_destroy_input(touch);
+            dasher_destroy_input(mouse);
+            dasher_interface_destroy(interface);
+        }
+    }
+
+    #[test]
+    fn test_multiple_input_devices() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let screen = dasher_create_screen(800, 600);
+        let mouse = dasher_create_mouse_input();
+        let mouse_name = CString::new("mouse").unwrap();
+        let tracker_name = CString::new("tracker").unwrap();
+        let missing_name = CString::new("missing").unwrap();
+
+        unsafe {
+            assert_eq!(dasher_interface_set_screen(interface, screen), DasherErrorCode::Success);
+            (*mouse).input.activate();
+            assert_eq!(dasher_interface_add_input(interface, mouse_name.as_ptr(), mouse), DasherErrorCode::Success);
+            assert_eq!(dasher_interface_add_input(interface, tracker_name.as_ptr(), mouse), DasherErrorCode::Success);
+            let manager = (*interface).interface.input_manager();
+            assert_eq!(manager.input_device_names(), ["mouse", "tracker"]);
+            assert_eq!(manager.primary_device_name(), Some("mouse"));
+
+            // Each device keeps its own position, and the pointer follows the primary device
+            assert_eq!(dasher_interface_set_device_position(interface, tracker_name.as_ptr(), 5, 6), DasherErrorCode::Success);
+            assert_eq!(dasher_interface_set_device_position(interface, missing_name.as_ptr(), 5, 6), DasherErrorCode::InputError);
+            assert_eq!(dasher_interface_set_mouse_position(interface, 1, 2), DasherErrorCode::Success);
+            let view = (*interface).interface.view().unwrap();
+            let position = |name: &str| (*interface).interface.input_manager().input_device(name).unwrap().get_screen_coordinates(view);
+            assert_eq!(position("mouse"), Some((1, 2)));
+            assert_eq!(position("tracker"), Some((5, 6)));
+
+            // The primary device is switched with a setting
+            assert_eq!(dasher_settings_set_string(interface, ParameterFFI::PrimaryInputDevice as i32, tracker_name.as_ptr()), DasherErrorCode::Success);
+            assert_eq!((*interface).interface.input_manager().primary_device_name(), Some("tracker"));
+            dasher_settings_set_string(interface, ParameterFFI::PrimaryInputDevice as i32, missing_name.as_ptr());
+            assert_eq!((*interface).interface.input_manager().primary_device_name(), Some("tracker"));
+
+            // Removing the primary device hands the pointer to the first remaining device
+            assert_eq!(dasher_interface_remove_input(interface, tracker_name.as_ptr()), DasherErrorCode::Success);
+            assert_eq!(dasher_interface_remove_input(interface, tracker_name.as_ptr()), DasherErrorCode::InputError);
+            assert_eq!((*interface).interface.input_manager().primary_device_name(), Some("mouse"));
+
+            dasher_destroy_input(mouse);
+            dasher_interface_destroy(interface);
+        }
+    }
+
+    #[test]
+    fn test_input_filter_switched_by_name() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let scanning = CString::new("scanning").unwrap();
+        let missing = CString::new("missing").unwrap();
+        let mut buffer = [0 as c_char; 128];
+
+        unsafe {
+            assert_eq!(dasher_interface_get_input_filter_names(interface, buffer.as_mut_ptr(), buffer.len(), std::ptr::null_mut()), DasherErrorCode::Success);
+            assert_eq!(CStr::from_ptr(buffer.as_ptr()).to_str().unwrap(), "default\none-button-dynamic\ntwo-button\nclick\nscanning\n");
+
+            assert_eq!(dasher_settings_set_string(interface, ParameterFFI::InputFilterID as i32, scanning.as_ptr()), DasherErrorCode::Success);
+            assert_eq!(dasher_settings_set_string(interface, ParameterFFI::InputFilterID as i32, missing.as_ptr()), DasherErrorCode::Success);
+            assert!((*interface).interface.select_input_filter("missing").is_err());
+            assert!((*interface).interface.select_input_filter("click").is_ok());
+            assert_eq!((*interface).interface.settings().get_string(Parameter::InputFilterID), Some("click"));
+
+            dasher_interface_destroy(interface);
+        }
+    }
+
+    #[test]
+    fn test_touch_gestures_start_and_pause() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let touch = dasher_create_touch_input();
+
+        unsafe {
+            assert_eq!(dasher_interface_touch_down(interface, 0, 1, 10, 10), DasherErrorCode::InputError);
+            assert_eq!(dasher_interface_set_input(interface, touch), DasherErrorCode::Success);
+
+            // Holding a finger still starts Dasher
+            assert_eq!(dasher_interface_touch_down(interface, 1000, 1, 100, 100), DasherErrorCode::Success);
+            dasher_interface_new_frame(interface, 1200);
+            assert!(!dasher_interface_is_running(interface));
+            dasher_interface_new_frame(interface, 1700);
+            assert!(dasher_interface_is_running(interface));
+
+            // A second finger pauses, and again resumes
+            assert_eq!(dasher_interface_touch_move(interface, 1800, 1, 300, 120), DasherErrorCode::Success);
+            assert_eq!(dasher_interface_touch_down(interface, 1900, 2, 400, 120), DasherErrorCode::Success);
+            assert!(dasher_interface_is_paused(interface));
+            assert_eq!(dasher_interface_touch_up(interface, 2000, 2), DasherErrorCode::Success);
+            assert_eq!(dasher_interface_touch_up(interface, 2000, 1), DasherErrorCode::Success);
+            dasher_interface_touch_down(interface, 2100, 3, 100, 100);
+            dasher_interface_touch_down(interface, 2150, 4, 200, 100);
+            assert!(!dasher_interface_is_paused(interface));
+            assert!(dasher_interface_is_running(interface));
+
+            dasher_destroy_input(touch);
+            dasher_interface_destroy(interface);
+        }
+    }
+
+    #[test]
+    fn test_joystick_buttons_and_axes() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let joystick = dasher_create_joystick_input(0.2, 1.0);
+
+        unsafe {
+            assert_eq!(dasher_interface_joystick_axes(interface, 1.0, 0.0), DasherErrorCode::InputError);
+            assert_eq!(dasher_interface_set_input(interface, joystick), DasherErrorCode::Success);
+
+            // Button 0 starts and stops by default
+            assert_eq!(dasher_interface_joystick_button(interface, 0, 0, true), DasherErrorCode::Success);
+            assert_eq!(dasher_interface_joystick_button(interface, 10, 0, false), DasherErrorCode::Success);
+            assert!(dasher_interface_is_running(interface));
+
+            // Unmapped buttons do nothing until mapped
+            assert_eq!(dasher_interface_joystick_button(interface, 20, 9, true), DasherErrorCode::Success);
+            assert_eq!(dasher_interface_map_joystick_button(interface, 9, 14), DasherErrorCode::Success);
+            assert_eq!((*interface).interface.joystick_input_mut().unwrap().button_key(9), Some(VirtualKey::Backspace));
+
+            assert_eq!(dasher_interface_joystick_axes(interface, 0.6, 0.0), DasherErrorCode::Success);
+            let response = (*interface).interface.joystick_input_mut().unwrap().response();
+            assert!((response.0 - 0.5).abs() < 1e-9);
+
+            dasher_destroy_input(joystick);
+            dasher_interface_destroy(interface);
+        }
+    }
+
+    #[test]
+    fn test_head_pointer_dwells_to_start() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let screen = dasher_create_screen(800, 600);
+
+        unsafe {
+            assert_eq!(dasher_interface_set_screen(interface, screen), DasherErrorCode::Success);
+            let mut mouse = MouseInput::new();
+            mouse.activate();
+            (*interface).interface.set_input(Box::new(mouse));
+            (*interface).interface.set_mouse_position(400, 300).unwrap();
+
+            // Dwelling does nothing until the profile is chosen
+            dasher_interface_new_frame(interface, 0);
+            dasher_interface_new_frame(interface, 2000);
+            assert!(!dasher_interface_is_running(interface));
+
+            assert_eq!(dasher_settings_set_bool(interface, ParameterFFI::HeadPointer as i32, true), DasherErrorCode::Success);
+            assert_eq!(dasher_settings_set_long(interface, ParameterFFI::HeadPointerDwellTime as i32, 500), DasherErrorCode::Success);
+            let config = *(*interface).interface.input_manager().head_pointer().unwrap();
+            assert_eq!((config.dwell_ms, config.smoothing, config.vertical_gain), (500, 0.7, 1.5));
+
+            dasher_interface_new_frame(interface, 3000);
+            dasher_interface_new_frame(interface, 3400);
+            assert!(!dasher_interface_is_running(interface));
+            dasher_interface_new_frame(interface, 3500);
+            assert!(dasher_interface_is_running(interface));
+
+            assert_eq!(dasher_settings_set_bool(interface, ParameterFFI::HeadPointer as i32, false), DasherErrorCode::Success);
+            assert!((*interface).interface.input_manager().head_pointer().is_none());
+
+            dasher_interface_destroy(interface);
+        }
+    }
+
+    #[test]
+    fn test_pressure_input() {
+        unsafe {
+            let interface = dasher_interface_create(std::ptr::null());
+            let screen = dasher_create_screen(800, 600);
+            dasher_interface_set_screen(interface, screen);
+
+            assert_eq!(dasher_interface_set_pressure(interface, 0.5), DasherErrorCode::InputError);
+            assert_eq!(dasher_interface_use_pressure_input(interface, 0.0, -10.0, 20.0), DasherErrorCode::Success);
+            assert_eq!(dasher_interface_set_pressure(interface, 10.0), DasherErrorCode::Success);
+
+            let pressure = (*interface).interface.pressure_input_mut().unwrap();
+            assert_eq!(pressure.pressure(), 10.0);
+            assert_eq!(pressure.pressure_calibration().max, 20.0);
+
+            dasher_interface_start(interface);
+            for frame in 0..20 {
+                dasher_interface_new_frame(interface, frame * 20);
+            }
+            assert!(dasher_interface_is_running(interface));
+
+            dasher_interface_destroy(interface);
+        }
+    }
+
+    #[test]
+    fn test_keyboard_steering_without_pointer() {
+        unsafe {
+            let interface = dasher_interface_create(std::ptr::null());
+            let screen = dasher_create_screen(800, 600);
+            dasher_interface_set_screen(interface, screen);
+            assert_eq!(dasher_interface_use_keyboard_steering(interface, 0.5), DasherErrorCode::Success);
+
+            // Hold the right arrow to zoom in
+            dasher_interface_start(interface);
+            dasher_interface_key_down(interface, 0, 10);
+            for frame in 0..100 {
+                dasher_interface_new_frame(interface, frame * 20);
+            }
+            dasher_interface_key_up(interface, 2000, 10);
+            assert!((*interface).interface.model().total_nats() > 0.0);
+
+            dasher_interface_destroy(interface);
+        }
     }
 
-    let interface = &mut *interface;
-    let result = interface.interface.set_text_3d(enable);
-    result.is_ok()
-}
+    #[test]
+    fn test_zoom_to_takes_over_from_steering() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let screen = dasher_create_screen(800, 600);
+        let mouse = dasher_create_mouse_input();
+
+        unsafe {
+            dasher_interface_set_screen(interface, screen);
+            (*mouse).input.activate();
+            dasher_interface_set_input(interface, mouse);
+            // Pointing at the left edge would zoom out
+            dasher_interface_set_mouse_position(interface, 0, 300);
+            dasher_interface_start(interface);
+
+            // Zoom so that the top half of the screen fills it
+            let quarter = crate::model::DasherModel::MAX_Y / 4;
+            assert_eq!(dasher_interface_zoom_to(interface, quarter, quarter, 5), DasherErrorCode::Success);
+            for frame in 0..5 {
+                dasher_interface_new_frame(interface, frame * 20);
+            }
+            let model = (*interface).interface.model();
+            assert!((model.total_nats() - 2f64.ln()).abs() < 0.01);
+            assert_eq!(model.scheduled_step_count(), 0);
 
-/// Enable or disable the flowing interface
-#[no_mangle]
-pub unsafe extern "C" fn dasher_interface_set_flowing_interface(
-    interface: *mut DasherInterfaceFFI,
-    enable: bool,
-) -> bool {
-    if interface.is_null() {
-        return false;
-    }
+            // Then the pointer steers again
+            dasher_interface_new_frame(interface, 100);
+            assert!((*interface).interface.model().total_nats() < 2f64.ln());
 
-    let interface = &mut *interface;
-    let result = interface.interface.set_flowing_interface(enable);
-    result.is_ok()
-}
+            assert_eq!(dasher_interface_zoom_to(interface, 0, quarter, 5), DasherErrorCode::InvalidParameter);
+            assert_eq!(dasher_interface_zoom_to(interface, quarter, quarter, 0), DasherErrorCode::InvalidParameter);
 
-/// Set the flowing interface speed
-#[no_mangle]
-pub unsafe extern "C" fn dasher_interface_set_flowing_speed(
-    interface: *mut DasherInterfaceFFI,
-    speed: f64,
-) -> bool {
-    if interface.is_null() {
-        return false;
+            dasher_destroy_input(mouse);
+            dasher_interface_destroy(interface);
+        }
     }
 
-    let interface = &mut *interface;
-    let result = interface.interface.set_flowing_speed(speed);
-    result.is_ok()
-}
+    #[test]
+    fn test_node_at_screen_point() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let screen = dasher_create_screen(800, 600);
+
+        unsafe {
+            let (mut found, mut symbol, mut y1, mut y2) = (false, 0u32, 0i64, 0i64);
+            assert_eq!(dasher_interface_node_at_screen_point(interface, 720, 300, &mut found, &mut symbol, &mut y1, &mut y2), DasherErrorCode::Success);
+            assert!(!found);
+
+            dasher_interface_set_screen(interface, screen);
+            let api = &(*interface).interface;
+            let hit = api.node_at_screen_point(720, 300).unwrap();
+            let (x1, top, x2, bottom) = hit.screen_rect;
+            assert!(x1 <= 720 && 720 <= x2 && top <= 300 && 300 <= bottom);
+            assert!(hit.symbol.is_some() && !hit.label.is_empty());
+            let (_, dasher_y) = api.view().unwrap().screen_to_dasher(720, 300);
+            assert!(hit.bounds.0 <= dasher_y && dasher_y < hit.bounds.1);
+
+            // Nothing is drawn in the margin, and nearer the left edge only the root's box is deep enough
+            assert_eq!(api.node_at_screen_point(790, 300), None);
+            let root = api.node_at_screen_point(200, 300).unwrap();
+            assert_eq!(root.bounds, api.model().display_bounds());
+
+            assert_eq!(dasher_interface_node_at_screen_point(interface, 720, 300, &mut found, &mut symbol, &mut y1, &mut y2), DasherErrorCode::Success);
+            assert!(found);
+            assert_eq!(char::from_u32(symbol), hit.symbol);
+            assert_eq!((y1, y2), hit.bounds);
+            assert_eq!(dasher_interface_node_at_screen_point(interface, 720, 300, std::ptr::null_mut(), &mut symbol, &mut y1, &mut y2), DasherErrorCode::InvalidParameter);
+
+            dasher_interface_destroy(interface);
+        }
+    }
 
-/// Enable or disable PPM (Prediction by Partial Match)
-#[no_mangle]
-pub unsafe extern "C" fn dasher_interface_set_ppm(
-    interface: *mut DasherInterfaceFFI,
-    enable: bool,
-) -> bool {
-    if interface.is_null() {
-        return false;
+    #[test]
+    fn test_visit_visible_nodes() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let screen = dasher_create_screen(800, 600);
+
+        unsafe {
+            assert_eq!(dasher_interface_visit_visible_nodes(interface, record_visible_node), DasherErrorCode::RenderingError);
+            dasher_interface_set_screen(interface, screen);
+            assert_eq!(dasher_interface_visit_visible_nodes(interface, record_visible_node), DasherErrorCode::Success);
+
+            let nodes = (*interface).interface.visible_nodes().unwrap();
+            assert_eq!(VISIBLE_NODES.load(Ordering::SeqCst), nodes.len() as i32);
+            assert_eq!(LABELLED_NODES.load(Ordering::SeqCst), nodes.iter().filter(|node| node.label.is_some()).count() as i32);
+
+            // The scene holds the same nodes
+            let mut required = 0;
+            assert_eq!(dasher_interface_get_scene_json(interface, std::ptr::null_mut(), 0, &mut required), DasherErrorCode::Success);
+            let mut buffer = vec![0 as c_char; required];
+            assert_eq!(dasher_interface_get_scene_json(interface, buffer.as_mut_ptr(), required, std::ptr::null_mut()), DasherErrorCode::Success);
+            let json = CStr::from_ptr(buffer.as_ptr()).to_str().unwrap();
+            assert_eq!(crate::view::Scene::from_json(json).unwrap().nodes, nodes);
+
+            dasher_interface_destroy(interface);
+        }
     }
 
-    let interface = &mut *interface;
-    let result = interface.interface.set_ppm(enable);
-    result.is_ok()
-}
+    #[test]
+    fn test_reset_starts_writing_again() {
+        unsafe {
+            let interface = dasher_interface_create(std::ptr::null());
+            let screen = dasher_create_screen(800, 600);
+            dasher_interface_set_screen(interface, screen);
+            dasher_interface_use_keyboard_steering(interface, 0.5);
+            let steer = |start: u64| {
+                dasher_interface_start(interface);
+                dasher_interface_key_down(interface, start, 10);
+                for frame in 0..100 {
+                    dasher_interface_new_frame(interface, start + frame * 20);
+                }
+                dasher_interface_key_up(interface, start + 2000, 10);
+            };
+            steer(0);
+            (*interface).interface.model_mut().append_to_output('a');
+            assert!((*interface).interface.model().total_nats() > 0.0);
 
-/// Enable or disable drawing the crosshair
-#[no_mangle]
-pub unsafe extern "C" fn dasher_interface_set_draw_crosshair(
-    interface: *mut DasherInterfaceFFI,
-    enable: bool,
-) -> bool {
-    if interface.is_null() {
-        return false;
-    }
+            (*interface).interface.reset().unwrap();
+            assert_eq!((*interface).interface.model().output_text(), "");
+            assert_eq!((*interface).interface.model().total_nats(), 0.0);
 
-    let interface = &mut *interface;
-    let result = interface.interface.set_draw_crosshair(enable);
-    result.is_ok()
-}
+            steer(5000);
+            assert!((*interface).interface.model().total_nats() > 0.0);
 
-/// Enable or disable drawing the cursor
-#[no_mangle]
-pub unsafe extern "C" fn dasher_interface_set_draw_cursor(
-    interface: *mut DasherInterfaceFFI,
-    enable: bool,
-) -> bool {
-    if interface.is_null() {
-        return false;
+            dasher_interface_destroy(interface);
+        }
     }
 
-    let interface = &mut *interface;
-    let result = interface.interface.set_draw_cursor(enable);
-    result.is_ok()
-}
+    #[test]
+    fn test_demo_filter_types_text() {
+        unsafe {
+            let interface = dasher_interface_create(std::ptr::null());
+            let screen = dasher_create_screen(800, 600);
+            dasher_interface_set_screen(interface, screen);
+            let text = CString::new("the ").unwrap();
+            assert_eq!(dasher_interface_use_demo_filter(interface, text.as_ptr(), 10.0, 0.0), DasherErrorCode::Success);
+
+            dasher_interface_start(interface);
+            let mut frame = 0;
+            while (*interface).interface.model().output_text() != "the " && frame < 5000 {
+                dasher_interface_new_frame(interface, frame * 16);
+                frame += 1;
+            }
+            assert_eq!((*interface).interface.model().output_text(), "the ");
 
-/// Enable or disable drawing node outlines
-#[no_mangle]
-pub unsafe extern "C" fn dasher_interface_set_draw_outlines(
-    interface: *mut DasherInterfaceFFI,
-    enable: bool,
-) -> bool {
-    if interface.is_null() {
-        return false;
+            assert_eq!(dasher_interface_use_demo_filter(interface, std::ptr::null(), 10.0, 0.0), DasherErrorCode::InvalidParameter);
+            dasher_interface_destroy(interface);
+        }
     }
 
-    let interface = &mut *interface;
-    let result = interface.interface.set_draw_outlines(enable);
-    result.is_ok()
-}
+    #[test]
+    fn test_version_and_features() {
+        let version = unsafe { CStr::from_ptr(dasher_core_version()) };
+        assert_eq!(version.to_str().unwrap(), crate::VERSION);
+
+        let ppm = CString::new("ppm").unwrap();
+        let wasm = CString::new("wasm").unwrap();
+        unsafe {
+            assert!(dasher_core_has_feature(ppm.as_ptr()));
+            assert_eq!(dasher_core_has_feature(wasm.as_ptr()), cfg!(feature = "wasm"));
+            assert!(!dasher_core_has_feature(std::ptr::null()));
+        }
+    }
 
-/// Get the output text
-#[no_mangle]
-pub unsafe extern "C" fn dasher_interface_get_output(
-    interface: *mut DasherInterfaceFFI,
-    buffer: *mut c_char,
-    buffer_size: usize
-) -> usize {
-    if interface.is_null() || buffer.is_null() {
-        return 0;
+    #[test]
+    fn test_string_results() {
+        unsafe {
+            let interface = dasher_interface_create(std::ptr::null());
+            (*interface).interface.model_mut().set_output_text("café");
+
+            // Query the size, then fetch the text
+            let mut required = 0;
+            assert_eq!(dasher_interface_get_output(interface, std::ptr::null_mut(), 0, &mut required), DasherErrorCode::Success);
+            assert_eq!(required, "café".len() + 1);
+            let mut buffer = vec![0 as c_char; required];
+            assert_eq!(dasher_interface_get_output(interface, buffer.as_mut_ptr(), buffer.len(), std::ptr::null_mut()), DasherErrorCode::Success);
+            assert_eq!(CStr::from_ptr(buffer.as_ptr()).to_str().unwrap(), "café");
+
+            // Truncation keeps whole characters
+            let mut small = [0x7f as c_char; 5];
+            let mut size = 0;
+            assert_eq!(dasher_interface_get_output(interface, small.as_mut_ptr(), small.len(), &mut size), DasherErrorCode::Success);
+            assert_eq!(size, required);
+            assert_eq!(CStr::from_ptr(small.as_ptr()).to_str().unwrap(), "caf");
+            assert_eq!(dasher_interface_get_output(std::ptr::null_mut(), buffer.as_mut_ptr(), buffer.len(), &mut size), DasherErrorCode::InvalidParameter);
+
+            let mut size = 0;
+            assert_eq!(dasher_settings_get_string(interface, ParameterFFI::AlphabetID as i32, std::ptr::null_mut(), 0, &mut size), DasherErrorCode::Success);
+            let mut buffer = vec![0 as c_char; size];
+            assert_eq!(dasher_settings_get_string(interface, ParameterFFI::AlphabetID as i32, buffer.as_mut_ptr(), buffer.len(), std::ptr::null_mut()), DasherErrorCode::Success);
+            assert_eq!(CStr::from_ptr(buffer.as_ptr()).to_bytes().len() + 1, size);
+            assert_eq!(dasher_settings_get_string(interface, ParameterFFI::AlphabetID as i32, std::ptr::null_mut(), 0, std::ptr::null_mut()), DasherErrorCode::InvalidParameter);
+
+            dasher_interface_destroy(interface);
+        }
     }
 
-    let output = (*interface).interface.get_output_text();
-    let output_len = output.len();
+    #[test]
+    fn test_get_probabilities() {
+        unsafe {
+            let interface = dasher_interface_create(std::ptr::null());
+            dasher_interface_load_default_data(interface);
+            (*interface).interface.model_mut().set_output_text("the quick brown fox jumps over th");
+            let all = (*interface).interface.probabilities(0);
+            assert!(all.len() > 3);
+            assert!((all.iter().map(|(_, p)| p).sum::<f64>() - 1.0).abs() < 0.01);
+
+            let mut symbols = [0u32; 8];
+            let mut probs = [0.0f64; 8];
+            assert_eq!(dasher_interface_get_probabilities(interface, symbols.as_mut_ptr(), probs.as_mut_ptr(), 8, 3), 3);
+            assert_eq!(char::from_u32(symbols[0]), Some(all[0].0));
+            assert!(probs[0] >= probs[1] && probs[1] >= probs[2]);
+            assert_eq!(probs[0], all[0].1);
+
+            // No more than fit in the arrays
+            assert_eq!(dasher_interface_get_probabilities(interface, symbols.as_mut_ptr(), probs.as_mut_ptr(), 2, 0), 2);
+            assert_eq!(dasher_interface_get_probabilities(interface, std::ptr::null_mut(), probs.as_mut_ptr(), 2, 0), -1);
+            dasher_interface_destroy(interface);
+        }
+    }
 
-    if output_len == 0 {
-        return 0;
+    #[test]
+    fn test_load_arpa_model() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.arpa");
+        std::fs::write(&path, "\\data\\\nngram 1=4\nngram 2=1\n\n\\1-grams:\n-1.0 <s> 0\n-0.5 t -0.5\n-0.5 h\n-0.5 e\n\n\\2-grams:\n-0.01 t h\n\n\\end\\\n").unwrap();
+        unsafe {
+            let interface = dasher_interface_create(std::ptr::null());
+            let path = CString::new(path.to_str().unwrap()).unwrap();
+            assert_eq!(dasher_interface_load_arpa_model(interface, path.as_ptr()), DasherErrorCode::Success);
+            (*interface).interface.model_mut().set_output_text("t");
+            assert_eq!((*interface).interface.probabilities(1)[0].0, 'h');
+
+            let missing = CString::new(dir.path().join("missing.arpa").to_str().unwrap()).unwrap();
+            assert_eq!(dasher_interface_load_arpa_model(interface, missing.as_ptr()), DasherErrorCode::InvalidParameter);
+            dasher_interface_destroy(interface);
+        }
     }
 
-    // Copy the output to the buffer
-    let copy_len = std::cmp::min(output_len, buffer_size - 1);
-    let output_bytes = output.as_bytes();
+    #[test]
+    fn test_get_predictions() {
+        use crate::model::word_generator::WordGenerator;
 
-    std::ptr::copy_nonoverlapping(
-        output_bytes.as_ptr(),
-        buffer as *mut u8,
-        copy_len
-    );
+        struct Completions;
+        impl WordGenerator for Completions {
+            fn next_word(&mut self) -> Option<String> {
+                None
+            }
+            fn get_symbols(&self, _word: &str) -> Vec<u32> {
+                Vec::new()
+            }
+            fn generate_words(&mut self, context: &str) -> Vec<String> {
+                ["hello", "help", "helmet"].iter().filter(|word| word.starts_with(context)).map(|word| word.to_string()).collect()
+            }
+        }
 
-    // Null-terminate the string
-    *buffer.add(copy_len) = 0;
+        unsafe {
+            let interface = dasher_interface_create(std::ptr::null());
+            let mut buffer = [0 as c_char; 64];
+            let mut required = 0;
+            assert_eq!(dasher_interface_get_predictions(interface, buffer.as_mut_ptr(), buffer.len(), 3, &mut required), 0);
+            assert_eq!(CStr::from_ptr(buffer.as_ptr()).to_str().unwrap(), "");
+            assert_eq!(required, 1);
+
+            let model = (*interface).interface.model_mut();
+            model.word_prediction_mut().unwrap().add_generator(Box::new(Completions));
+            model.set_output_text("hel");
+            assert_eq!(dasher_interface_get_predictions(interface, buffer.as_mut_ptr(), buffer.len(), 2, std::ptr::null_mut()), 2);
+            assert_eq!(CStr::from_ptr(buffer.as_ptr()).to_str().unwrap(), "hello\nhelp\n");
+
+            // Only whole words are written
+            assert_eq!(dasher_interface_get_predictions(interface, buffer.as_mut_ptr(), 8, 3, &mut required), 1);
+            assert_eq!(CStr::from_ptr(buffer.as_ptr()).to_str().unwrap(), "hello\n");
+            assert_eq!(required, "hello\nhelp\nhelmet\n".len() + 1);
+
+            // A null buffer only queries the size
+            assert_eq!(dasher_interface_get_predictions(interface, std::ptr::null_mut(), 0, 2, &mut required), 0);
+            assert_eq!(required, "hello\nhelp\n".len() + 1);
+            assert_eq!(dasher_interface_get_predictions(interface, std::ptr::null_mut(), 8, 3, std::ptr::null_mut()), -1);
+            dasher_interface_destroy(interface);
+        }
+    }
 
-    copy_len
-}
+    #[test]
+    fn test_session_stats_export() {
+        use crate::stats::{SessionReport, StatsFormat};
+
+        unsafe {
+            let interface = dasher_interface_create(std::ptr::null());
+            dasher_interface_load_default_data(interface);
+            let screen = dasher_create_screen(800, 600);
+            dasher_interface_set_screen(interface, screen);
+            let text = CString::new("hi").unwrap();
+            dasher_interface_use_demo_filter(interface, text.as_ptr(), 10.0, 0.0);
+            dasher_interface_start(interface);
+            let mut frame = 0;
+            while (*interface).interface.model().output_text() != "hi" && frame < 5000 {
+                dasher_interface_new_frame(interface, frame * 16);
+                frame += 1;
+            }
 
-/// Transform Dasher coordinates to screen coordinates
-///
-/// # Safety
-///
-/// This function is unsafe because it dereferences raw pointers.
-#[no_mangle]
-pub unsafe extern "C" fn dasher_transform_coordinates(
-    dasher_x: i64,
-    dasher_y: i64,
-    screen_width: i32,
-    screen_height: i32,
-    orientation: i32,
-    screen_x: *mut i32,
-    screen_y: *mut i32
-) -> bool {
-    if screen_x.is_null() || screen_y.is_null() {
-        return false;
+            let dasher = &(*interface).interface;
+            let json = dasher.export_session_stats(StatsFormat::Json).unwrap();
+            let report: SessionReport = serde_json::from_str(&json).unwrap();
+            assert_eq!(report.symbols_entered, 2);
+            assert!(report.bits_per_symbol > 0.0);
+            assert_eq!(report.speed_history.len(), 1);
+            assert_eq!(report.minutes[0].max_bit_rate, Some(dasher.input_manager().max_bit_rate()));
+
+            let csv = dasher.export_session_stats(StatsFormat::Csv).unwrap();
+            assert!(csv.starts_with(SessionReport::CSV_HEADER));
+            assert_eq!(csv.lines().count(), report.minutes.len() + 1);
+            dasher_interface_destroy(interface);
+        }
     }
 
-    let (x, y) = coordinates::dasher_to_screen(
-        dasher_x,
-        dasher_y,
-        screen_width,
-        screen_height,
-        orientation
-    );
+    thread_local! {
+        static ANNOUNCEMENTS: std::cell::RefCell<Vec<String>> = const { std::cell::RefCell::new(Vec::new()) };
+    }
 
-    *screen_x = x;
-    *screen_y = y;
+    extern "C" fn record_announcement(message: *const c_char) {
+        let message = unsafe { CStr::from_ptr(message) }.to_string_lossy().into_owned();
+        ANNOUNCEMENTS.with(|announcements| announcements.borrow_mut().push(message));
+    }
 
-    true
-}
+    #[test]
+    fn test_announcement_callback() {
+        unsafe {
+            let interface = dasher_interface_create(std::ptr::null());
+            dasher_interface_load_default_data(interface);
+            let screen = dasher_create_screen(800, 600);
+            dasher_interface_set_screen(interface, screen);
+            assert_eq!(dasher_interface_set_announcement_callback(interface, Some(record_announcement)), DasherErrorCode::Success);
+            let text = CString::new("hi ").unwrap();
+            dasher_interface_use_demo_filter(interface, text.as_ptr(), 10.0, 0.0);
+
+            dasher_interface_start(interface);
+            let mut frame = 0;
+            while (*interface).interface.model().output_text() != "hi " && frame < 5000 {
+                dasher_interface_new_frame(interface, frame * 16);
+                frame += 1;
+            }
+            let backspace = CString::new("backspace").unwrap();
+            assert_eq!(dasher_interface_execute_action(interface, backspace.as_ptr()), DasherErrorCode::Success);
+
+            // Steering may pass in and out of other letters on the way
+            ANNOUNCEMENTS.with(|announcements| {
+                let announcements = announcements.borrow();
+                assert_eq!(announcements.first().map(String::as_str), Some("typed letter h"));
+                assert!(announcements.ends_with(&[
+                    "typed letter i".to_string(), "typed space".to_string(), "word 'hi' completed".to_string(), "deleted space".to_string(),
+                ]));
+            });
+
+            assert_eq!(dasher_interface_set_announcement_callback(interface, None), DasherErrorCode::Success);
+            dasher_interface_destroy(interface);
+        }
+    }
 
-/// Transform screen coordinates to Dasher coordinates
-///
-/// # Safety
-///
-/// This function is unsafe because it dereferences raw pointers.
-#[no_mangle]
-pub unsafe extern "C" fn dasher_screen_to_dasher(
-    screen_x: i32,
-    screen_y: i32,
-    screen_width: i32,
-    screen_height: i32,
-    orientation: i32,
-    dasher_x: *mut i64,
-    dasher_y: *mut i64
-) -> bool {
-    if dasher_x.is_null() || dasher_y.is_null() {
-        return false;
+    thread_local! {
+        static CURSOR_POSITIONS: std::cell::RefCell<Vec<usize>> = const { std::cell::RefCell::new(Vec::new()) };
     }
 
-    let (x, y) = coordinates::screen_to_dasher(
-        screen_x,
-        screen_y,
-        screen_width,
-        screen_height,
-        orientation
-    );
+    extern "C" fn record_cursor(position: usize) {
+        CURSOR_POSITIONS.with(|positions| positions.borrow_mut().push(position));
+    }
 
-    *dasher_x = x;
-    *dasher_y = y;
+    #[test]
+    fn test_cursor_movement_actions() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let text = CString::new("one two three").unwrap();
+
+        unsafe {
+            dasher_interface_edit_output(interface, text.as_ptr());
+            assert_eq!(dasher_interface_set_cursor_callback(interface, Some(record_cursor)), DasherErrorCode::Success);
+            for name in ["word-left", "cursor-left", "word-left", "cursor-start", "cursor-left", "word-right", "cursor-right", "cursor-end"] {
+                let name = CString::new(name).unwrap();
+                assert_eq!(dasher_interface_execute_action(interface, name.as_ptr()), DasherErrorCode::Success);
+            }
+            assert_eq!((*interface).interface.model().output_text(), "one two three");
 
-    true
-}
+            // Moves the host makes itself are not reported
+            (*interface).interface.set_cursor_position(4);
+            CURSOR_POSITIONS.with(|positions| assert_eq!(*positions.borrow(), [8, 7, 4, 0, 3, 4, 13]));
 
-/// Transform a rectangle from Dasher coordinates to screen coordinates
-///
-/// # Safety
-///
-/// This function is unsafe because it dereferences raw pointers.
-#[no_mangle]
-pub unsafe extern "C" fn dasher_transform_rectangle(
-    dasher_x1: i64,
-    dasher_y1: i64,
-    dasher_x2: i64,
-    dasher_y2: i64,
-    screen_width: i32,
-    screen_height: i32,
-    orientation: i32,
-    screen_x1: *mut i32,
-    screen_y1: *mut i32,
-    screen_x2: *mut i32,
-    screen_y2: *mut i32
-) -> bool {
-    if screen_x1.is_null() || screen_y1.is_null() || screen_x2.is_null() || screen_y2.is_null() {
-        return false;
+            assert_eq!(dasher_interface_set_cursor_callback(interface, None), DasherErrorCode::Success);
+            dasher_interface_destroy(interface);
+        }
     }
 
-    let (x1, y1, x2, y2) = coordinates::transform_rectangle(
-        dasher_x1,
-        dasher_y1,
-        dasher_x2,
-        dasher_y2,
-        screen_width,
-        screen_height,
-        orientation
-    );
+    #[test]
+    fn test_button_region_dwell() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let screen = dasher_create_screen(800, 600);
+
+        unsafe {
+            assert_eq!(dasher_interface_set_screen(interface, screen), DasherErrorCode::Success);
+            let mut mouse = MouseInput::new();
+            mouse.activate();
+            (*interface).interface.set_input(Box::new(mouse));
+            (*interface).interface.set_mouse_position(50, 50).unwrap();
+
+            assert_eq!(dasher_interface_add_button_region(interface, 0, 0, 100, 100, 14), DasherErrorCode::Success);
+            assert_eq!(dasher_settings_set_long(interface, ParameterFFI::ButtonDwellTime as i32, 500), DasherErrorCode::Success);
+            assert_eq!((*interface).interface.button_regions()[0].key, VirtualKey::Backspace);
+
+            dasher_interface_start(interface);
+            let (mut region, mut remaining_ms) = (0, 0);
+            dasher_interface_new_frame(interface, 0);
+            dasher_interface_new_frame(interface, 200);
+            assert_eq!(dasher_interface_button_dwell(interface, &mut region, &mut remaining_ms), DasherErrorCode::Success);
+            assert_eq!((region, remaining_ms), (0, 300));
+
+            // The press happens once, when the dwell completes
+            dasher_interface_new_frame(interface, 500);
+            dasher_interface_button_dwell(interface, &mut region, &mut remaining_ms);
+            assert_eq!(region, -1);
+
+            assert_eq!(dasher_interface_clear_button_regions(interface), DasherErrorCode::Success);
+            assert!((*interface).interface.button_regions().is_empty());
+            assert_eq!(dasher_interface_button_dwell(interface, std::ptr::null_mut(), &mut remaining_ms), DasherErrorCode::InvalidParameter);
+
+            dasher_interface_destroy(interface);
+        }
+    }
 
-    *screen_x1 = x1;
-    *screen_y1 = y1;
-    *screen_x2 = x2;
-    *screen_y2 = y2;
+    #[test]
+    fn test_two_box_start() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let screen = dasher_create_screen(800, 600);
+
+        unsafe {
+            assert_eq!(dasher_interface_set_screen(interface, screen), DasherErrorCode::Success);
+            let mut mouse = MouseInput::new();
+            mouse.activate();
+            (*interface).interface.set_input(Box::new(mouse));
+            assert_eq!(dasher_settings_set_bool(interface, ParameterFFI::TwoBoxStart as i32, true), DasherErrorCode::Success);
+
+            // Hold in the top box, then the bottom one
+            (*interface).interface.set_mouse_position(400, 150).unwrap();
+            assert!(dasher_interface_new_frame(interface, 0));
+            dasher_interface_new_frame(interface, 500);
+            (*interface).interface.set_mouse_position(400, 450).unwrap();
+            dasher_interface_new_frame(interface, 700);
+            assert!(!dasher_interface_is_running(interface));
+            dasher_interface_new_frame(interface, 1200);
+            assert!(dasher_interface_is_running(interface));
+
+            dasher_interface_destroy(interface);
+        }
+    }
 
-    true
-}
+    #[test]
+    fn test_settings_round_trip() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let alphabet_id = CString::new("English").unwrap();
+        let mut buffer = [0 as c_char; 32];
+        let mut long_value = 0;
+        let mut bool_value = true;
 
-/// Create a default square view configuration
-#[no_mangle]
-pub extern "C" fn dasher_create_square_view_config() -> *mut SquareViewConfigFFI {
-    let config = SquareViewConfigFFI::default();
-    Box::into_raw(Box::new(config))
-}
+        unsafe {
+            assert_eq!(dasher_settings_set_long(interface, ParameterFFI::MaxBitRate as i32, 250), DasherErrorCode::Success);
+            assert_eq!(dasher_settings_get_long(interface, ParameterFFI::MaxBitRate as i32, &mut long_value), DasherErrorCode::Success);
+            assert_eq!(long_value, 250);
 
-/// Destroy a square view configuration
-///
-/// # Safety
-///
-/// This function is unsafe because it deallocates memory from a raw pointer.
-/// The pointer must have been created by `dasher_create_square_view_config`.
-#[no_mangle]
-pub unsafe extern "C" fn dasher_destroy_square_view_config(config: *mut SquareViewConfigFFI) {
-    if !config.is_null() {
-        let _ = Box::from_raw(config);
-    }
-}
+            assert_eq!(dasher_settings_set_bool(interface, ParameterFFI::ButtonMode as i32, false), DasherErrorCode::Success);
+            assert_eq!(dasher_settings_get_bool(interface, ParameterFFI::ButtonMode as i32, &mut bool_value), DasherErrorCode::Success);
+            assert!(!bool_value);
 
-/// Set the square view configuration for a Dasher interface
-///
-/// # Safety
-///
-/// This function is unsafe because it dereferences raw pointers.
-/// Both `interface` and `config` must be valid pointers to their respective types.
-#[no_mangle]
-pub unsafe extern "C" fn dasher_interface_set_square_view_config(
-    interface: *mut DasherInterfaceFFI,
-    config: *const SquareViewConfigFFI
-) -> bool {
-    if interface.is_null() || config.is_null() {
-        return false;
-    }
+            assert_eq!(dasher_settings_set_string(interface, ParameterFFI::AlphabetID as i32, alphabet_id.as_ptr()), DasherErrorCode::Success);
+            assert_eq!(dasher_settings_get_string(interface, ParameterFFI::AlphabetID as i32, buffer.as_mut_ptr(), buffer.len(), std::ptr::null_mut()), DasherErrorCode::Success);
+            assert_eq!(CStr::from_ptr(buffer.as_ptr()).to_str().unwrap(), "English");
 
-    let interface = &mut *interface;
-    let config = &*config;
+            // Type mismatches are rejected rather than silently changing the stored type
+            assert_eq!(dasher_settings_set_bool(interface, ParameterFFI::MaxBitRate as i32, true), DasherErrorCode::SettingsError);
 
-    // Convert FFI config to Rust config
-    let rust_config: SquareViewConfig = config::ffi_to_rust_config(config);
+            // Numbers that are no parameter are rejected
+            assert_eq!(dasher_settings_set_long(interface, 99, 1), DasherErrorCode::InvalidParameter);
+            assert_eq!(dasher_settings_get_bool(interface, -1, &mut bool_value), DasherErrorCode::InvalidParameter);
+            assert_eq!(ParameterFFI::try_from(ParameterFFI::FontFamily as i32).unwrap(), ParameterFFI::FontFamily);
 
-    // Get the square view from the interface
-    if let Some(square_view) = config::get_square_view(&mut interface.interface) {
-        // Update the configuration
-        *square_view.config_mut() = rust_config;
-        true
-    } else {
-        false
+            dasher_interface_destroy(interface);
+        }
     }
-}
 
-/// Get the square view configuration from a Dasher interface
-///
-/// # Safety
-///
-/// This function is unsafe because it dereferences raw pointers.
-/// Both `interface` and `config` must be valid pointers to their respective types.
-#[no_mangle]
-pub unsafe extern "C" fn dasher_interface_get_square_view_config(
-    interface: *mut DasherInterfaceFFI,
-    config: *mut SquareViewConfigFFI
-) -> bool {
-    if interface.is_null() || config.is_null() {
-        return false;
+    #[test]
+    fn test_view_geometry_settings() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let screen = dasher_create_screen(800, 600);
+
+        unsafe {
+            assert_eq!(dasher_settings_set_long(interface, ParameterFFI::MarginWidth as i32, 1000), DasherErrorCode::Success);
+            assert_eq!(dasher_interface_set_screen(interface, screen), DasherErrorCode::Success);
+            assert_eq!(dasher_settings_set_long(interface, ParameterFFI::XNonlinearity as i32, 30), DasherErrorCode::Success);
+            assert_eq!(dasher_settings_set_long(interface, ParameterFFI::YNonlinearity as i32, 0), DasherErrorCode::Success);
+            assert_eq!(dasher_settings_set_long(interface, ParameterFFI::FontScaling as i32, 25), DasherErrorCode::Success);
+
+            let view = (*interface).interface.view().unwrap();
+            let config = view.as_any().downcast_ref::<DasherViewSquare>().unwrap().config();
+            assert_eq!(config.margin_width, 1000);
+            assert!(config.x_nonlinear);
+            assert_eq!(config.x_nonlinear_factor, 3.0);
+            assert!(!config.y_nonlinear);
+            assert_eq!(config.font_size_scaling, 0.25);
+
+            dasher_destroy_screen(screen);
+            dasher_interface_destroy(interface);
+        }
     }
 
-    let interface = &mut *interface;
+    #[test]
+    fn test_view_setters() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let screen = dasher_create_screen(800, 600);
+
+        unsafe {
+            assert_eq!(dasher_interface_set_screen(interface, screen), DasherErrorCode::Success);
+            assert_eq!(dasher_interface_set_orientation(interface, 2), DasherErrorCode::Success);
+            assert_eq!(dasher_interface_set_orientation(interface, 4), DasherErrorCode::InvalidParameter);
+            assert_eq!(dasher_interface_set_margin_width(interface, 2000), DasherErrorCode::Success);
+            assert_eq!(dasher_interface_set_margin_width(interface, -1), DasherErrorCode::InvalidParameter);
+            assert_eq!(dasher_interface_set_font_scaling(interface, 0.75), DasherErrorCode::Success);
+            assert_eq!(dasher_interface_set_x_nonlinear_factor(interface, 2.5), DasherErrorCode::Success);
+            assert_eq!(dasher_interface_set_y_nonlinear_factor(interface, 1), DasherErrorCode::Success);
+
+            let view = (*interface).interface.view().unwrap();
+            assert_eq!(view.get_orientation(), Orientation::TopToBottom);
+            let config = view.as_any().downcast_ref::<DasherViewSquare>().unwrap().config();
+            assert_eq!(config.margin_width, 2000);
+            assert_eq!(config.font_size_scaling, 0.75);
+            assert_eq!(config.x_nonlinear_factor, 2.5);
+            assert!(!config.y_nonlinear);
+
+            // The settings keep the values for later views
+            let mut value = 0;
+            dasher_settings_get_long(interface, ParameterFFI::Orientation as i32, &mut value);
+            assert_eq!(value, 2);
+            dasher_settings_get_long(interface, ParameterFFI::FontScaling as i32, &mut value);
+            assert_eq!(value, 75);
+
+            dasher_destroy_screen(screen);
+            dasher_interface_destroy(interface);
+        }
+    }
 
-    // Get the square view from the interface
-    if let Some(square_view) = config::get_square_view(&mut interface.interface) {
-        // Get the configuration
-        let rust_config = square_view.config();
+    #[test]
+    fn test_alphabet_orientation_applied_unless_overridden() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let screen = dasher_create_screen(800, 600);
+        let xml = r#"<alphabet name="Backwards" orientation="RL">
+            <node label="a"><textCharAction /></node>
+            <node label="b"><textCharAction /></node>
+        </alphabet>"#;
+        let info = crate::alphabet::parse_alphabet(xml).unwrap();
+
+        unsafe {
+            assert_eq!(dasher_interface_set_screen(interface, screen), DasherErrorCode::Success);
+            let dasher = &mut (*interface).interface;
+            dasher.set_alphabet(info.clone()).unwrap();
+            assert_eq!(dasher.orientation(), Orientation::RightToLeft);
+            assert_eq!(dasher.view().unwrap().get_orientation(), Orientation::RightToLeft);
+
+            // An orientation the user chose wins over the alphabet's
+            assert_eq!(dasher_interface_set_orientation(interface, 2), DasherErrorCode::Success);
+            let dasher = &mut (*interface).interface;
+            dasher.set_alphabet(info).unwrap();
+            assert_eq!(dasher.view().unwrap().get_orientation(), Orientation::TopToBottom);
+
+            assert_eq!(dasher_interface_set_orientation(interface, -1), DasherErrorCode::Success);
+            let dasher = &mut (*interface).interface;
+            assert_eq!(dasher.view().unwrap().get_orientation(), Orientation::RightToLeft);
+            assert_eq!(dasher.settings().get_long(Parameter::Orientation), Some(Orientation::ALPHABET_DEFAULT));
+
+            dasher_destroy_screen(screen);
+            dasher_interface_destroy(interface);
+        }
+    }
 
-        // Convert Rust config to FFI config
-        *config = rust_config.clone().into();
+    extern "C" fn record_training_progress(percent: u32) -> bool {
+        TRAINING_PERCENT.store(percent as i32, Ordering::SeqCst);
         true
-    } else {
-        false
     }
-}
 
-// Functions moved to avoid duplication
+    extern "C" fn cancel_training(_percent: u32) -> bool {
+        false
+    }
 
-/// Enable or disable debug mode
-#[no_mangle]
-pub extern "C" fn dasher_set_debug_mode(enable: bool) {
-    let context = context::get_global_context();
-    context.set_debug_mode(enable);
-}
+    #[test]
+    fn test_train_file_reports_progress() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, "hello world\n".repeat(100).as_bytes()).unwrap();
+        let path = CString::new(file.path().to_str().unwrap()).unwrap();
+        let interface = dasher_interface_create(std::ptr::null());
 
-/// Get debug mode
-#[no_mangle]
-pub extern "C" fn dasher_get_debug_mode() -> bool {
-    let context = context::get_global_context();
-    context.get_debug_mode()
-}
+        unsafe {
+            let trained = dasher_interface_train_file(interface, path.as_ptr(), Some(record_training_progress));
+            assert_eq!(trained, 1200);
+            assert_eq!(TRAINING_PERCENT.load(Ordering::SeqCst), 100);
 
-/// Get the number of error messages
-#[no_mangle]
-pub extern "C" fn dasher_get_error_count() -> i32 {
-    let context = context::get_global_context();
-    let errors = context.get_errors();
-    errors.len() as i32
-}
+            assert_eq!(dasher_interface_train_file(interface, path.as_ptr(), Some(cancel_training)), -1);
+            assert_eq!(dasher_interface_train_file(interface, path.as_ptr(), None), 1200);
 
-/// Get an error message
-///
-/// # Safety
-///
-/// This function is unsafe because it dereferences raw pointers.
-/// The `buffer` pointer must be valid and point to a buffer of at least `buffer_size` bytes.
-#[no_mangle]
-pub unsafe extern "C" fn dasher_get_error_message(
-    index: i32,
-    buffer: *mut c_char,
-    buffer_size: usize
-) -> bool {
-    if buffer.is_null() {
-        return false;
+            dasher_interface_destroy(interface);
+        }
     }
 
-    let context = context::get_global_context();
-    let errors = context.get_errors();
-
-    if index < 0 || index >= errors.len() as i32 {
-        return false;
+    extern "C" fn record_clipboard(text: *const c_char) {
+        let text = unsafe { CStr::from_ptr(text) };
+        CLIPBOARD_LENGTH.store(text.to_bytes().len() as i32, Ordering::SeqCst);
     }
 
-    let message = &errors[index as usize];
-    let copy_len = std::cmp::min(message.len(), buffer_size - 1);
-
-    std::ptr::copy_nonoverlapping(
-        message.as_ptr(),
-        buffer as *mut u8,
-        copy_len
-    );
-
-    // Null-terminate the string
-    *buffer.add(copy_len) = 0;
+    extern "C" fn convert_kana(input: *const c_char, _context: *const c_char, buffer: *mut c_char, buffer_size: usize) -> usize {
+        let input = unsafe { CStr::from_ptr(input) };
+        let candidates = if input.to_bytes() == b"ka" { "\u{304b}\t3\n\u{30ab}\t1" } else { "" };
+        unsafe { copy_to_buffer(candidates, buffer, buffer_size) }
+    }
 
-    true
-}
+    #[test]
+    fn test_conversion_callback_offers_candidates() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let text = CString::new("ka").unwrap();
+        let conversions = |interface: *mut DasherInterfaceFFI| unsafe {
+            let root = (*interface).interface.model().get_root_node().unwrap();
+            let conversions: Vec<(String, u32)> = root.borrow().children().iter()
+                .filter_map(|child| {
+                    let child = child.borrow();
+                    child.conversion().map(|(_, output)| (output.to_string(), child.upper_bound() - child.lower_bound()))
+                })
+                .collect();
+            conversions
+        };
+
+        unsafe {
+            assert_eq!(dasher_interface_set_conversion_callback(std::ptr::null_mut(), Some(convert_kana)), DasherErrorCode::InvalidParameter);
+            dasher_interface_edit_output(interface, text.as_ptr());
+            assert_eq!(dasher_interface_set_conversion_callback(interface, Some(convert_kana)), DasherErrorCode::Success);
+
+            // The more probable candidate gets the larger node
+            let offered = conversions(interface);
+            assert_eq!(offered.iter().map(|(text, _)| text.as_str()).collect::<Vec<_>>(), ["\u{304b}", "\u{30ab}"]);
+            assert!(offered[0].1 > offered[1].1);
+
+            assert_eq!(dasher_interface_set_conversion_callback(interface, None), DasherErrorCode::Success);
+            assert!(conversions(interface).is_empty());
+
+            dasher_interface_destroy(interface);
+        }
+    }
 
-/// Get the number of debug messages
-#[no_mangle]
-pub extern "C" fn dasher_get_debug_message_count() -> i32 {
-    let context = context::get_global_context();
-    let messages = context.get_debug_messages();
-    messages.len() as i32
-}
+    #[test]
+    fn test_clipboard_callback_receives_cut_text() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let text = CString::new("copied text").unwrap();
+        let cut = CString::new("cut").unwrap();
+        let unknown = CString::new("no such action").unwrap();
 
-/// Get a debug message
-///
-/// # Safety
-///
-/// This function is unsafe because it dereferences raw pointers.
-/// The `buffer` pointer must be valid and point to a buffer of at least `buffer_size` bytes.
-#[no_mangle]
-pub unsafe extern "C" fn dasher_get_debug_message(
-    index: i32,
-    buffer: *mut c_char,
-    buffer_size: usize
-) -> bool {
-    if buffer.is_null() {
-        return false;
-    }
+        unsafe {
+            assert_eq!(dasher_interface_execute_action(interface, cut.as_ptr()), DasherErrorCode::InvalidParameter);
+            assert_eq!(dasher_interface_execute_action(interface, unknown.as_ptr()), DasherErrorCode::InvalidParameter);
 
-    let context = context::get_global_context();
-    let messages = context.get_debug_messages();
+            assert_eq!(dasher_interface_set_clipboard_callback(interface, Some(record_clipboard)), DasherErrorCode::Success);
+            dasher_interface_edit_output(interface, text.as_ptr());
+            assert_eq!(dasher_interface_execute_action(interface, cut.as_ptr()), DasherErrorCode::Success);
+            assert_eq!(CLIPBOARD_LENGTH.load(Ordering::SeqCst), "copied text".len() as i32);
+            assert_eq!((*interface).interface.get_output_text(), "");
 
-    if index < 0 || index >= messages.len() as i32 {
-        return false;
+            dasher_interface_destroy(interface);
+        }
     }
 
-    let message = &messages[index as usize];
-    let copy_len = std::cmp::min(message.len(), buffer_size - 1);
+    #[test]
+    fn test_speech_callback_speaks_current_sentence() {
+        let interface = dasher_interface_create(std::ptr::null());
+        let text = CString::new("Hi. Speak me").unwrap();
 
-    std::ptr::copy_nonoverlapping(
-        message.as_ptr(),
-        buffer as *mut u8,
-        copy_len
-    );
+        unsafe {
+            assert_eq!(dasher_interface_speak(interface), DasherErrorCode::Other);
 
-    // Null-terminate the string
-    *buffer.add(copy_len) = 0;
+            assert_eq!(dasher_interface_set_speech_callback(interface, Some(record_speech)), DasherErrorCode::Success);
+            dasher_interface_edit_output(interface, text.as_ptr());
+            assert_eq!(dasher_interface_speak(interface), DasherErrorCode::Success);
+            assert_eq!(SPOKEN_LENGTH.load(Ordering::SeqCst), "Speak me".len() as i32);
 
-    true
-}
+            assert_eq!(dasher_interface_set_speech_callback(interface, None), DasherErrorCode::Success);
+            assert_eq!(dasher_interface_speak(interface), DasherErrorCode::Other);
 
-/// Add a debug message
-///
-/// # Safety
-///
-/// This function is unsafe because it dereferences raw pointers.
-/// The `message` pointer must be valid and point to a null-terminated C string.
-#[no_mangle]
-pub unsafe extern "C" fn dasher_add_debug_message(
-    message: *const c_char
-) -> bool {
-    if message.is_null() {
-        return false;
+            dasher_interface_destroy(interface);
+        }
     }
 
-    let c_str = CStr::from_ptr(message);
-    if let Ok(message_str) = c_str.to_str() {
-        let context = context::get_global_context();
-        context.add_debug(message_str);
-        true
-    } else {
-        false
+    #[test]
+    fn test_polygon_callback_receives_all_points() {
+        let screen = dasher_create_screen(100, 100);
+        unsafe {
+            assert_eq!(dasher_screen_set_draw_polygon_callback(screen, record_polygon), DasherErrorCode::Success);
+            (*screen).screen.draw_polygon(&[(0, 0), (10, 0), (5, 10)], Color::rgb(255, 0, 0), Color::rgb(0, 0, 0), 1);
+            dasher_destroy_screen(screen);
+        }
+        assert_eq!(POLYGON_POINTS.load(Ordering::SeqCst), 3);
     }
 }
-
-// Functions moved to avoid duplication
@@ -0,0 +1,271 @@
+//! # Settings Module for FFI
+//!
+//! This module provides a stable, C-compatible numbering of the Dasher
+//! parameters so native frontends can read and change settings.
+
+use crate::settings::{Parameter, ParameterType};
+use crate::DasherError;
+
+/// Parameter identifiers for FFI
+///
+/// The numeric values are part of the C API and must not change.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterFFI {
+    /// Use button mode (bool)
+    ButtonMode = 0,
+    /// Draw the mouse position (bool)
+    DrawMouse = 1,
+    /// Draw a line from the origin to the mouse (bool)
+    DrawMouseLine = 2,
+    /// Adapt the speed to how smoothly the user steers (bool)
+    AutoSpeedControl = 3,
+    /// Offer likely next words after a word separator (bool)
+    NextWordPrediction = 4,
+    /// Write a space after a predicted word (bool)
+    AutoSpace = 5,
+    /// Move punctuation written after a space to before it (bool)
+    SmartPunctuation = 6,
+    /// Use the head pointer profile (bool)
+    HeadPointer = 7,
+    /// Start by holding the pointer in two boxes in turn (bool)
+    TwoBoxStart = 8,
+    /// Let written symbols teach the language model (bool)
+    LearnWhileWriting = 9,
+    /// Learn only once each sentence is committed (bool)
+    LearnAtSentenceEnd = 10,
+    /// Record committed sentences to the user's corpus (bool)
+    RecordCorpus = 11,
+    /// Draw debugging figures over each frame (bool)
+    DebugOverlay = 12,
+    /// Offer digit and symbol groups behind toggle nodes (bool)
+    ToggleGroups = 13,
+    /// Maximum bit rate (long)
+    MaxBitRate = 100,
+    /// View identifier (long)
+    ViewID = 101,
+    /// Language identifier (long)
+    Language = 102,
+    /// Screen orientation (long, 0-3, or -1 for the alphabet's)
+    Orientation = 103,
+    /// Minimum probability of a separate child node, in 1/65536ths (long)
+    MinChildProbability = 104,
+    /// X nonlinearity of the view, in tenths (long)
+    XNonlinearity = 105,
+    /// Y nonlinearity of the view (long)
+    YNonlinearity = 106,
+    /// Width of the view's margin, in Dasher X units (long)
+    MarginWidth = 107,
+    /// Font scaling of the view, in hundredths (long)
+    FontScaling = 108,
+    /// Maximum number of language model nodes, 0 for no limit (long)
+    LanguageModelMaxNodes = 109,
+    /// Pointer smoothing method, 0 none, 1 exponential, 2 moving average, 3 Kalman (long)
+    PointerSmoothing = 110,
+    /// Strength of smoothing of the pointer's X axis, in hundredths (long)
+    PointerSmoothingX = 111,
+    /// Strength of smoothing of the pointer's Y axis, in hundredths (long)
+    PointerSmoothingY = 112,
+    /// Strength of the head pointer's low-pass filter, in hundredths (long)
+    HeadPointerSmoothing = 113,
+    /// Gain of the head pointer's vertical movement, in hundredths (long)
+    HeadPointerVerticalGain = 114,
+    /// How long the head pointer must dwell to start, in milliseconds (long)
+    HeadPointerDwellTime = 115,
+    /// How long the pointer must hover in a button region to press it, in milliseconds (long)
+    ButtonDwellTime = 116,
+    /// Speed multiplier while the boost key is held, in percent (long)
+    BoostFactor = 117,
+    /// Share of the probability spread evenly over the symbols, in thousandths (long)
+    Uniform = 118,
+    /// Share of each node given to control nodes, in thousandths (long)
+    ControlNodeMass = 119,
+    /// Weight of node labels, from 100 to 900 (long)
+    FontWeight = 120,
+    /// Alphabet identifier (string)
+    AlphabetID = 200,
+    /// Colour scheme identifier (string)
+    ColourID = 201,
+    /// Calibration of each input device, as JSON (string)
+    InputCalibration = 202,
+    /// Characters that end a word (string)
+    WordSeparators = 203,
+    /// Name of the primary input device (string)
+    PrimaryInputDevice = 204,
+    /// Name of the input filter (string)
+    InputFilterID = 205,
+    /// Font family of node labels, or empty for the screen's own (string)
+    FontFamily = 206,
+}
+
+impl TryFrom<i32> for ParameterFFI {
+    type Error = DasherError;
+
+    /// Convert a raw value passed across the FFI, which may be any integer
+    fn try_from(value: i32) -> crate::Result<Self> {
+        match value {
+            0 => Ok(ParameterFFI::ButtonMode),
+            1 => Ok(ParameterFFI::DrawMouse),
+            2 => Ok(ParameterFFI::DrawMouseLine),
+            3 => Ok(ParameterFFI::AutoSpeedControl),
+            4 => Ok(ParameterFFI::NextWordPrediction),
+            5 => Ok(ParameterFFI::AutoSpace),
+            6 => Ok(ParameterFFI::SmartPunctuation),
+            7 => Ok(ParameterFFI::HeadPointer),
+            8 => Ok(ParameterFFI::TwoBoxStart),
+            9 => Ok(ParameterFFI::LearnWhileWriting),
+            10 => Ok(ParameterFFI::LearnAtSentenceEnd),
+            11 => Ok(ParameterFFI::RecordCorpus),
+            12 => Ok(ParameterFFI::DebugOverlay),
+            13 => Ok(ParameterFFI::ToggleGroups),
+            100 => Ok(ParameterFFI::MaxBitRate),
+            101 => Ok(ParameterFFI::ViewID),
+            102 => Ok(ParameterFFI::Language),
+            103 => Ok(ParameterFFI::Orientation),
+            104 => Ok(ParameterFFI::MinChildProbability),
+            105 => Ok(ParameterFFI::XNonlinearity),
+            106 => Ok(ParameterFFI::YNonlinearity),
+            107 => Ok(ParameterFFI::MarginWidth),
+            108 => Ok(ParameterFFI::FontScaling),
+            109 => Ok(ParameterFFI::LanguageModelMaxNodes),
+            110 => Ok(ParameterFFI::PointerSmoothing),
+            111 => Ok(ParameterFFI::PointerSmoothingX),
+            112 => Ok(ParameterFFI::PointerSmoothingY),
+            113 => Ok(ParameterFFI::HeadPointerSmoothing),
+            114 => Ok(ParameterFFI::HeadPointerVerticalGain),
+            115 => Ok(ParameterFFI::HeadPointerDwellTime),
+            116 => Ok(ParameterFFI::ButtonDwellTime),
+            117 => Ok(ParameterFFI::BoostFactor),
+            118 => Ok(ParameterFFI::Uniform),
+            119 => Ok(ParameterFFI::ControlNodeMass),
+            120 => Ok(ParameterFFI::FontWeight),
+            200 => Ok(ParameterFFI::AlphabetID),
+            201 => Ok(ParameterFFI::ColourID),
+            202 => Ok(ParameterFFI::InputCalibration),
+            203 => Ok(ParameterFFI::WordSeparators),
+            204 => Ok(ParameterFFI::PrimaryInputDevice),
+            205 => Ok(ParameterFFI::InputFilterID),
+            206 => Ok(ParameterFFI::FontFamily),
+            _ => Err(DasherError::InvalidParameter(format!("Unknown parameter {}", value))),
+        }
+    }
+}
+
+impl From<ParameterFFI> for Parameter {
+    fn from(parameter: ParameterFFI) -> Self {
+        match parameter {
+            ParameterFFI::ButtonMode => Parameter::ButtonMode,
+            ParameterFFI::DrawMouse => Parameter::DrawMouse,
+            ParameterFFI::DrawMouseLine => Parameter::DrawMouseLine,
+            ParameterFFI::AutoSpeedControl => Parameter::AutoSpeedControl,
+            ParameterFFI::NextWordPrediction => Parameter::NextWordPrediction,
+            ParameterFFI::AutoSpace => Parameter::AutoSpace,
+            ParameterFFI::SmartPunctuation => Parameter::SmartPunctuation,
+            ParameterFFI::HeadPointer => Parameter::HeadPointer,
+            ParameterFFI::TwoBoxStart => Parameter::TwoBoxStart,
+            ParameterFFI::LearnWhileWriting => Parameter::LearnWhileWriting,
+            ParameterFFI::LearnAtSentenceEnd => Parameter::LearnAtSentenceEnd,
+            ParameterFFI::RecordCorpus => Parameter::RecordCorpus,
+            ParameterFFI::DebugOverlay => Parameter::DebugOverlay,
+            ParameterFFI::ToggleGroups => Parameter::ToggleGroups,
+            ParameterFFI::MaxBitRate => Parameter::MaxBitRate,
+            ParameterFFI::ViewID => Parameter::ViewID,
+            ParameterFFI::Language => Parameter::Language,
+            ParameterFFI::Orientation => Parameter::Orientation,
+            ParameterFFI::MinChildProbability => Parameter::MinChildProbability,
+            ParameterFFI::XNonlinearity => Parameter::XNonlinearity,
+            ParameterFFI::YNonlinearity => Parameter::YNonlinearity,
+            ParameterFFI::MarginWidth => Parameter::MarginWidth,
+            ParameterFFI::FontScaling => Parameter::FontScaling,
+            ParameterFFI::LanguageModelMaxNodes => Parameter::LanguageModelMaxNodes,
+            ParameterFFI::PointerSmoothing => Parameter::PointerSmoothing,
+            ParameterFFI::PointerSmoothingX => Parameter::PointerSmoothingX,
+            ParameterFFI::PointerSmoothingY => Parameter::PointerSmoothingY,
+            ParameterFFI::HeadPointerSmoothing => Parameter::HeadPointerSmoothing,
+            ParameterFFI::HeadPointerVerticalGain => Parameter::HeadPointerVerticalGain,
+            ParameterFFI::HeadPointerDwellTime => Parameter::HeadPointerDwellTime,
+            ParameterFFI::ButtonDwellTime => Parameter::ButtonDwellTime,
+            ParameterFFI::BoostFactor => Parameter::BoostFactor,
+            ParameterFFI::Uniform => Parameter::Uniform,
+            ParameterFFI::ControlNodeMass => Parameter::ControlNodeMass,
+            ParameterFFI::FontWeight => Parameter::FontWeight,
+            ParameterFFI::AlphabetID => Parameter::AlphabetID,
+            ParameterFFI::ColourID => Parameter::ColourID,
+            ParameterFFI::InputCalibration => Parameter::InputCalibration,
+            ParameterFFI::WordSeparators => Parameter::WordSeparators,
+            ParameterFFI::PrimaryInputDevice => Parameter::PrimaryInputDevice,
+            ParameterFFI::InputFilterID => Parameter::InputFilterID,
+            ParameterFFI::FontFamily => Parameter::FontFamily,
+        }
+    }
+}
+
+impl From<Parameter> for ParameterFFI {
+    fn from(parameter: Parameter) -> Self {
+        match parameter {
+            Parameter::ButtonMode => ParameterFFI::ButtonMode,
+            Parameter::DrawMouse => ParameterFFI::DrawMouse,
+            Parameter::DrawMouseLine => ParameterFFI::DrawMouseLine,
+            Parameter::AutoSpeedControl => ParameterFFI::AutoSpeedControl,
+            Parameter::NextWordPrediction => ParameterFFI::NextWordPrediction,
+            Parameter::AutoSpace => ParameterFFI::AutoSpace,
+            Parameter::SmartPunctuation => ParameterFFI::SmartPunctuation,
+            Parameter::HeadPointer => ParameterFFI::HeadPointer,
+            Parameter::TwoBoxStart => ParameterFFI::TwoBoxStart,
+            Parameter::LearnWhileWriting => ParameterFFI::LearnWhileWriting,
+            Parameter::LearnAtSentenceEnd => ParameterFFI::LearnAtSentenceEnd,
+            Parameter::RecordCorpus => ParameterFFI::RecordCorpus,
+            Parameter::DebugOverlay => ParameterFFI::DebugOverlay,
+            Parameter::ToggleGroups => ParameterFFI::ToggleGroups,
+            Parameter::MaxBitRate => ParameterFFI::MaxBitRate,
+            Parameter::ViewID => ParameterFFI::ViewID,
+            Parameter::Language => ParameterFFI::Language,
+            Parameter::Orientation => ParameterFFI::Orientation,
+            Parameter::MinChildProbability => ParameterFFI::MinChildProbability,
+            Parameter::XNonlinearity => ParameterFFI::XNonlinearity,
+            Parameter::YNonlinearity => ParameterFFI::YNonlinearity,
+            Parameter::MarginWidth => ParameterFFI::MarginWidth,
+            Parameter::FontScaling => ParameterFFI::FontScaling,
+            Parameter::LanguageModelMaxNodes => ParameterFFI::LanguageModelMaxNodes,
+            Parameter::PointerSmoothing => ParameterFFI::PointerSmoothing,
+            Parameter::PointerSmoothingX => ParameterFFI::PointerSmoothingX,
+            Parameter::PointerSmoothingY => ParameterFFI::PointerSmoothingY,
+            Parameter::HeadPointerSmoothing => ParameterFFI::HeadPointerSmoothing,
+            Parameter::HeadPointerVerticalGain => ParameterFFI::HeadPointerVerticalGain,
+            Parameter::HeadPointerDwellTime => ParameterFFI::HeadPointerDwellTime,
+            Parameter::ButtonDwellTime => ParameterFFI::ButtonDwellTime,
+            Parameter::BoostFactor => ParameterFFI::BoostFactor,
+            Parameter::Uniform => ParameterFFI::Uniform,
+            Parameter::ControlNodeMass => ParameterFFI::ControlNodeMass,
+            Parameter::FontWeight => ParameterFFI::FontWeight,
+            Parameter::AlphabetID => ParameterFFI::AlphabetID,
+            Parameter::ColourID => ParameterFFI::ColourID,
+            Parameter::InputCalibration => ParameterFFI::InputCalibration,
+            Parameter::WordSeparators => ParameterFFI::WordSeparators,
+            Parameter::PrimaryInputDevice => ParameterFFI::PrimaryInputDevice,
+            Parameter::InputFilterID => ParameterFFI::InputFilterID,
+            Parameter::FontFamily => ParameterFFI::FontFamily,
+        }
+    }
+}
+
+/// Get the parameter numbered `raw` that holds values of the expected type
+pub(crate) fn parameter_arg(raw: i32, expected: ParameterType) -> crate::Result<Parameter> {
+    let parameter = Parameter::from(ParameterFFI::try_from(raw)?);
+    check_parameter_type(parameter, expected)?;
+    Ok(parameter)
+}
+
+/// Check that a parameter holds values of the expected type
+fn check_parameter_type(parameter: Parameter, expected: ParameterType) -> crate::Result<()> {
+    if parameter.parameter_type() == expected {
+        Ok(())
+    } else {
+        Err(DasherError::SettingsError(format!(
+            "{:?} holds {:?} values, not {:?}",
+            parameter,
+            parameter.parameter_type(),
+            expected
+        )))
+    }
+}
@@ -0,0 +1,137 @@
+//! Automatic speed control
+//!
+//! This module adapts the speed of continuous steering to the user, in the
+//! spirit of AutoSpeedControl in upstream Dasher. The angle of the pointer
+//! from the crosshair is sampled every frame: when it varies little the user
+//! is steering smoothly and the speed is raised, and when it varies a lot, or
+//! the user keeps reversing, the speed is lowered.
+
+use std::collections::VecDeque;
+
+use crate::model::DasherModel;
+
+/// Adapts a speed multiplier to how smoothly the user steers
+#[derive(Debug, Clone)]
+pub struct AutoSpeedControl {
+    /// Angles of the pointer from the crosshair (in radians) since the last adjustment
+    samples: VecDeque<f64>,
+
+    /// Number of those samples in which the pointer was left of the crosshair
+    reversals: usize,
+
+    /// Multiplier applied to the maximum bit rate
+    multiplier: f64,
+}
+
+impl Default for AutoSpeedControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AutoSpeedControl {
+    /// Number of frames sampled between adjustments
+    pub const WINDOW: usize = 60;
+
+    /// Angle variance below which steering counts as smooth
+    pub const SMOOTH_VARIANCE: f64 = 0.02;
+
+    /// Angle variance above which steering counts as erratic
+    pub const ERRATIC_VARIANCE: f64 = 0.2;
+
+    /// Fraction of samples spent reversing above which steering counts as erratic
+    pub const MAX_REVERSAL_FRACTION: f64 = 0.2;
+
+    /// Relative change in speed per adjustment
+    pub const ADJUSTMENT: f64 = 0.05;
+
+    /// Smallest multiplier of the maximum bit rate
+    pub const MIN_MULTIPLIER: f64 = 0.25;
+
+    /// Largest multiplier of the maximum bit rate
+    pub const MAX_MULTIPLIER: f64 = 4.0;
+
+    /// Create a new controller, starting at the user's chosen speed
+    pub fn new() -> Self {
+        Self {
+            samples: VecDeque::with_capacity(Self::WINDOW),
+            reversals: 0,
+            multiplier: 1.0,
+        }
+    }
+
+    /// Get the multiplier to apply to the maximum bit rate
+    pub fn multiplier(&self) -> f64 {
+        self.multiplier
+    }
+
+    /// Forget the samples and return to the user's chosen speed
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Sample the pointer position (in Dasher coordinates) for one frame
+    ///
+    /// Returns the (possibly adjusted) multiplier.
+    pub fn update(&mut self, x: i64, y: i64) -> f64 {
+        let dx = DasherModel::ORIGIN_X - x;
+        let dy = y - DasherModel::ORIGIN_Y;
+        if dx <= 0 {
+            self.reversals += 1;
+        }
+        self.samples.push_back((dy as f64).atan2(dx.max(1) as f64));
+
+        if self.samples.len() >= Self::WINDOW {
+            self.adjust();
+        }
+        self.multiplier
+    }
+
+    /// Adjust the multiplier from the samples collected, then start a new window
+    fn adjust(&mut self) {
+        let count = self.samples.len() as f64;
+        let mean = self.samples.iter().sum::<f64>() / count;
+        let variance = self.samples.iter().map(|angle| (angle - mean).powi(2)).sum::<f64>() / count;
+        let reversal_fraction = self.reversals as f64 / count;
+
+        if variance > Self::ERRATIC_VARIANCE || reversal_fraction > Self::MAX_REVERSAL_FRACTION {
+            self.multiplier *= 1.0 - Self::ADJUSTMENT;
+        } else if variance < Self::SMOOTH_VARIANCE {
+            self.multiplier *= 1.0 + Self::ADJUSTMENT;
+        }
+        self.multiplier = self.multiplier.clamp(Self::MIN_MULTIPLIER, Self::MAX_MULTIPLIER);
+
+        self.samples.clear();
+        self.reversals = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_speed_adapts_to_steering() {
+        let mut smooth = AutoSpeedControl::new();
+        for _ in 0..AutoSpeedControl::WINDOW * 3 {
+            smooth.update(DasherModel::ORIGIN_X / 2, DasherModel::ORIGIN_Y + 1000);
+        }
+        assert!(smooth.multiplier() > 1.0);
+
+        let mut erratic = AutoSpeedControl::new();
+        for frame in 0..AutoSpeedControl::WINDOW * 3 {
+            let y = if frame % 2 == 0 { 0 } else { DasherModel::MAX_Y };
+            erratic.update(DasherModel::ORIGIN_X / 2, y);
+        }
+        assert!(erratic.multiplier() < 1.0);
+
+        let mut reversing = AutoSpeedControl::new();
+        for _ in 0..AutoSpeedControl::WINDOW {
+            reversing.update(DasherModel::MAX_X, DasherModel::ORIGIN_Y);
+        }
+        assert!(reversing.multiplier() < 1.0);
+
+        reversing.reset();
+        assert_eq!(reversing.multiplier(), 1.0);
+    }
+}
@@ -0,0 +1,121 @@
+//! Dwell selection of button regions
+//!
+//! Users who cannot press a switch can press an on-screen button by
+//! hovering the pointer inside it. Each region sends a virtual key once the
+//! pointer has stayed inside it for the dwell time; the pointer must leave
+//! the region before it can be pressed again.
+
+use crate::input::VirtualKey;
+
+/// A region of the screen that acts as a button
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ButtonRegion {
+    /// Left edge in screen coordinates
+    pub x: i32,
+    /// Top edge in screen coordinates
+    pub y: i32,
+    /// Width in pixels
+    pub width: i32,
+    /// Height in pixels
+    pub height: i32,
+    /// Key sent when the region is pressed
+    pub key: VirtualKey,
+}
+
+impl ButtonRegion {
+    /// Check whether a screen position is inside the region
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        (self.x..self.x + self.width).contains(&x) && (self.y..self.y + self.height).contains(&y)
+    }
+}
+
+/// Progress of a dwell on a button region, for drawing a countdown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ButtonDwell {
+    /// Index of the region being dwelt on
+    pub region: usize,
+    /// Time left until the region is pressed (in milliseconds)
+    pub remaining_ms: u64,
+    /// Whole dwell time (in milliseconds)
+    pub dwell_ms: u64,
+}
+
+/// Tracks the pointer dwelling in button regions
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RegionDwell {
+    /// Region the pointer is in and when it entered
+    current: Option<(usize, u64)>,
+    /// Whether the current region has already been pressed
+    pressed: bool,
+    /// Time of the last update (in milliseconds)
+    last_time: u64,
+}
+
+impl RegionDwell {
+    /// Follow the pointer, returning the region pressed by a completed dwell
+    pub(crate) fn update(&mut self, time_ms: u64, position: Option<(i32, i32)>, regions: &[ButtonRegion], dwell_ms: u64) -> Option<usize> {
+        self.last_time = time_ms;
+        let region = position.and_then(|(x, y)| regions.iter().position(|region| region.contains(x, y)));
+
+        match (region, self.current) {
+            (Some(region), Some((current, start_ms))) if region == current => {
+                if !self.pressed && time_ms.saturating_sub(start_ms) >= dwell_ms {
+                    self.pressed = true;
+                    return Some(region);
+                }
+                None
+            }
+            (region, _) => {
+                self.current = region.map(|region| (region, time_ms));
+                self.pressed = false;
+                None
+            }
+        }
+    }
+
+    /// Get the progress of the dwell in progress, as of the last update
+    pub(crate) fn progress(&self, dwell_ms: u64) -> Option<ButtonDwell> {
+        let (region, start_ms) = self.current?;
+        if self.pressed {
+            return None;
+        }
+        Some(ButtonDwell {
+            region,
+            remaining_ms: dwell_ms.saturating_sub(self.last_time.saturating_sub(start_ms)),
+            dwell_ms,
+        })
+    }
+
+    /// Forget any dwell in progress
+    pub(crate) fn reset(&mut self) {
+        self.current = None;
+        self.pressed = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dwell_presses_region_once() {
+        let regions = [
+            ButtonRegion { x: 0, y: 0, width: 100, height: 50, key: VirtualKey::StartStopKey },
+            ButtonRegion { x: 0, y: 50, width: 100, height: 50, key: VirtualKey::Backspace },
+        ];
+        let mut dwell = RegionDwell::default();
+
+        assert_eq!(dwell.update(0, Some((10, 10)), &regions, 800), None);
+        assert_eq!(dwell.update(300, Some((90, 40)), &regions, 800), None);
+        assert_eq!(dwell.progress(800), Some(ButtonDwell { region: 0, remaining_ms: 500, dwell_ms: 800 }));
+        assert_eq!(dwell.update(800, Some((50, 20)), &regions, 800), Some(0));
+        assert_eq!(dwell.update(2000, Some((50, 20)), &regions, 800), None);
+        assert_eq!(dwell.progress(800), None);
+
+        // Moving to the other region starts its dwell afresh
+        assert_eq!(dwell.update(2100, Some((50, 70)), &regions, 800), None);
+        assert_eq!(dwell.update(2500, None, &regions, 800), None);
+        assert_eq!(dwell.update(2600, Some((50, 70)), &regions, 800), None);
+        assert_eq!(dwell.update(3400, Some((50, 70)), &regions, 800), Some(1));
+    }
+}
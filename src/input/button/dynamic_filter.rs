@@ -93,7 +93,7 @@ impl OneButtonDynamicFilter {
         self.current_coords = Coordinates { x: x as f64, y: y as f64 };
 
         // Apply coordinates to model
-        model.apply_input_coordinates((x as i64, y as i64));
+        model.apply_input_coordinates((x as i64, y as i64), 1);
     }
 
     /// Handle button click
@@ -238,7 +238,7 @@ impl InputFilter for OneButtonDynamicFilter {
         self._update_coordinates_internal(now, dt);
 
         // Apply coordinates to model
-        model.apply_input_coordinates((self.current_coords.x as i64, self.current_coords.y as i64));
+        model.apply_input_coordinates((self.current_coords.x as i64, self.current_coords.y as i64), 1);
     }
 
     fn key_down(&mut self, _time: u64, _key: VirtualKey, _model: &mut DasherModel, _view: &mut dyn DasherView) {}
@@ -272,8 +272,15 @@ impl InputFilter for OneButtonDynamicFilter {
         self.reset();
     }
 
-    fn decorate_view(&mut self, _view: &mut dyn DasherView) -> bool {
-        false
+    fn decorate_view(&mut self, view: &mut dyn DasherView) -> bool {
+        // Draw a guide line from the crosshair the way Dasher is moving
+        let (end_x, color) = match self.state {
+            DynamicFilterState::Forward { .. } => (DasherModel::X_LIMIT, (0, 160, 0, 255)),
+            DynamicFilterState::Backward { .. } => (DasherModel::MAX_X, (200, 0, 0, 255)),
+            DynamicFilterState::Waiting | DynamicFilterState::Paused { .. } => return false,
+        };
+        view.draw_line(DasherModel::ORIGIN_X, DasherModel::ORIGIN_Y, end_x, DasherModel::ORIGIN_Y, color, 2);
+        true
     }
 }
 
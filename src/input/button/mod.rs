@@ -1,12 +1,16 @@
 use crate::input::filter::DasherInputExt;
 
 mod dynamic_filter;
+mod dwell;
 mod multi_press;
 pub mod one_button_dynamic_filter;
 pub mod two_button_dynamic_filter;
 
 pub use dynamic_filter::{OneButtonDynamicFilter, DynamicFilterConfig};
 pub use multi_press::{MultiPressMode, MultiPressConfig};
+pub use dwell::{ButtonDwell, ButtonRegion};
+
+use dwell::RegionDwell;
 
 use crate::{DasherInput, input::{InputFilter, Coordinates, VirtualKey}};
 use crate::model::DasherModel;
@@ -32,6 +36,11 @@ pub struct ButtonConfig {
     pub dynamic_config: DynamicFilterConfig,
     /// Multi-press configuration
     pub multi_press_config: MultiPressConfig,
+    /// Regions of the screen that act as buttons
+    pub regions: Vec<ButtonRegion>,
+    /// How long the pointer must hover in a region to press it (in
+    /// milliseconds); 0 disables dwell selection
+    pub dwell_ms: u64,
 }
 
 impl Default for ButtonConfig {
@@ -40,6 +49,8 @@ impl Default for ButtonConfig {
             mode: ButtonMode::Dynamic,
             dynamic_config: DynamicFilterConfig::default(),
             multi_press_config: MultiPressConfig::default(),
+            regions: Vec::new(),
+            dwell_ms: 0,
         }
     }
 }
@@ -55,6 +66,8 @@ pub struct ButtonHandler {
     multi_press: Option<MultiPressMode>,
     /// Current coordinates
     current_coords: Coordinates,
+    /// Dwell on the button regions
+    dwell: RegionDwell,
 }
 
 impl ButtonHandler {
@@ -77,6 +90,7 @@ impl ButtonHandler {
             dynamic_filter,
             multi_press,
             current_coords: Coordinates::default(),
+            dwell: RegionDwell::default(),
         }
     }
 
@@ -109,6 +123,46 @@ impl ButtonHandler {
         self.config.mode
     }
 
+    /// Get the regions of the screen that act as buttons
+    pub fn regions(&self) -> &[ButtonRegion] {
+        &self.config.regions
+    }
+
+    /// Set the regions of the screen that act as buttons
+    pub fn set_regions(&mut self, regions: Vec<ButtonRegion>) {
+        self.config.regions = regions;
+        self.dwell.reset();
+    }
+
+    /// Get how long the pointer must hover in a region to press it (0 if disabled)
+    pub fn dwell_time(&self) -> u64 {
+        self.config.dwell_ms
+    }
+
+    /// Set how long the pointer must hover in a region to press it (0 disables dwell selection)
+    pub fn set_dwell_time(&mut self, dwell_ms: u64) {
+        self.config.dwell_ms = dwell_ms;
+        self.dwell.reset();
+    }
+
+    /// Follow the pointer over the button regions, returning the key of a
+    /// region pressed by dwelling in it
+    pub fn update_dwell(&mut self, time_ms: u64, position: Option<(i32, i32)>) -> Option<VirtualKey> {
+        if self.config.dwell_ms == 0 {
+            return None;
+        }
+        let region = self.dwell.update(time_ms, position, &self.config.regions, self.config.dwell_ms)?;
+        Some(self.config.regions[region].key)
+    }
+
+    /// Get the progress of the dwell in progress, for drawing a countdown
+    pub fn dwell(&self) -> Option<ButtonDwell> {
+        if self.config.dwell_ms == 0 {
+            return None;
+        }
+        self.dwell.progress(self.config.dwell_ms)
+    }
+
     /// Update the button state based on screen coordinates
     pub fn update_state(&mut self, x: i32, y: i32, model: &mut DasherModel) {
         // Store the current coordinates
@@ -138,6 +192,8 @@ impl ButtonHandler {
 
 impl InputFilter for ButtonHandler {
     fn reset(&mut self) {
+        self.dwell.reset();
+
         // Reset the button handler state
         match self.config.mode {
             ButtonMode::Direct => {
@@ -297,11 +353,28 @@ impl InputFilter for ButtonHandler {
     }
 
     fn decorate_view(&mut self, view: &mut dyn DasherView) -> bool {
-        match self.config.mode {
+        let decorated = match self.config.mode {
             ButtonMode::Dynamic => self.dynamic_filter.as_mut().is_some_and(|f| f.decorate_view(view)),
             ButtonMode::MultiPress => self.multi_press.as_mut().is_some_and(|f| f.decorate_view(view)),
             ButtonMode::Direct => false,
+        };
+
+        // Draw the button boxes, filling the one being dwelt on as a countdown
+        let dwell = self.dwell();
+        for (index, region) in self.config.regions.iter().enumerate() {
+            let (x1, y1) = view.screen_to_dasher(region.x, region.y);
+            let (x2, y2) = view.screen_to_dasher(region.x + region.width, region.y + region.height);
+            view.draw_rectangle(x1, y1, x2, y2, (0, 0, 0, 0), (0, 0, 160, 255), 2);
+
+            if let Some(dwell) = dwell.filter(|dwell| dwell.region == index && dwell.dwell_ms > 0) {
+                let elapsed = (dwell.dwell_ms - dwell.remaining_ms) as f64 / dwell.dwell_ms as f64;
+                let width = (region.width as f64 * elapsed).round() as i32;
+                let (fill_x, _) = view.screen_to_dasher(region.x + width, region.y);
+                view.draw_rectangle(x1, y1, fill_x, y2, (0, 0, 160, 96), (0, 0, 0, 0), 0);
+            }
         }
+
+        decorated || !self.config.regions.is_empty()
     }
 }
 
@@ -101,7 +101,7 @@ impl MultiPressMode {
         self.current_coords = Coordinates { x: x as f64, y: y as f64 };
 
         // Apply coordinates to model
-        model.apply_input_coordinates((x as i64, y as i64));
+        model.apply_input_coordinates((x as i64, y as i64), 1);
     }
 
     /// Handle button press
@@ -201,7 +201,7 @@ impl InputFilter for MultiPressMode {
         self._update_coordinates_internal(dt);
 
         // Apply coordinates to model
-        model.apply_input_coordinates((self.current_coords.x as i64, self.current_coords.y as i64));
+        model.apply_input_coordinates((self.current_coords.x as i64, self.current_coords.y as i64), 1);
     }
 
     fn key_down(&mut self, _time: u64, _key: VirtualKey, _model: &mut DasherModel, _view: &mut dyn DasherView) {}
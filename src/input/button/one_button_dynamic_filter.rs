@@ -171,12 +171,17 @@ impl DynamicFilter for OneButtonDynamicFilter {
     fn set_start_time(&mut self, time: Instant) {
         self.base.set_start_time(time);
     }
+
+    fn speed_key_mul(&self) -> f64 {
+        self.base.speed_key_mul()
+    }
 }
 
 impl InputFilter for OneButtonDynamicFilter {
     fn reset(&mut self) {
         // Reset to default state
         self.target = 0;
+        self.base.release_speed_keys();
         self.decoration_changed = true;
     }
 
@@ -218,6 +223,11 @@ impl InputFilter for OneButtonDynamicFilter {
     }
 
     fn key_down(&mut self, _time: u64, key: VirtualKey, model: &mut DasherModel, _view: &mut dyn DasherView) {
+        // Speed keys change the speed while held
+        if self.base.speed_key(key, true) {
+            return;
+        }
+
         // Handle primary input (mouse click)
         if key == VirtualKey::PrimaryInput && !self.config.backoff_button {
             // Simulate press of button 2
@@ -228,8 +238,8 @@ impl InputFilter for OneButtonDynamicFilter {
         }
     }
 
-    fn key_up(&mut self, _time: u64, _key: VirtualKey, _model: &mut DasherModel, _view: &mut dyn DasherView) {
-        // No action on key up
+    fn key_up(&mut self, _time: u64, key: VirtualKey, _model: &mut DasherModel, _view: &mut dyn DasherView) {
+        self.base.speed_key(key, false);
     }
 
     fn supports_pause(&self) -> bool {
@@ -256,6 +266,14 @@ impl InputFilter for OneButtonDynamicFilter {
         self.reset();
     }
 
+    fn set_max_bit_rate(&mut self, bits_per_second: f64) {
+        self.set_bit_rate(bits_per_second);
+    }
+
+    fn set_boost_factor(&mut self, factor: f64) {
+        self.config_mut().boost_factor = factor;
+    }
+
     fn decorate_view(&mut self, view: &mut dyn DasherView) -> bool {
         // If no decoration change, return false
         if !self.decoration_changed {
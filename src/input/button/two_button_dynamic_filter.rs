@@ -148,6 +148,10 @@ impl DynamicFilter for TwoButtonDynamicFilter {
     fn set_start_time(&mut self, time: Instant) {
         self.base.set_start_time(time);
     }
+
+    fn speed_key_mul(&self) -> f64 {
+        self.base.speed_key_mul()
+    }
 }
 
 impl InputFilter for TwoButtonDynamicFilter {
@@ -156,6 +160,7 @@ impl InputFilter for TwoButtonDynamicFilter {
         self.state = TwoButtonDynamicFilterState::Idle;
         self.button1_pressed = false;
         self.button2_pressed = false;
+        self.base.release_speed_keys();
         self.decoration_changed = true;
     }
 
@@ -209,6 +214,11 @@ impl InputFilter for TwoButtonDynamicFilter {
     }
 
     fn key_down(&mut self, _time: u64, key: VirtualKey, _model: &mut DasherModel, _view: &mut dyn DasherView) {
+        // Speed keys change the speed while held
+        if self.base.speed_key(key, true) {
+            return;
+        }
+
         // Handle key down
         match key {
             VirtualKey::Button1 => {
@@ -226,6 +236,10 @@ impl InputFilter for TwoButtonDynamicFilter {
     }
 
     fn key_up(&mut self, _time: u64, key: VirtualKey, _model: &mut DasherModel, _view: &mut dyn DasherView) {
+        if self.base.speed_key(key, false) {
+            return;
+        }
+
         // Handle key up
         match key {
             VirtualKey::Button1 => {
@@ -266,6 +280,14 @@ impl InputFilter for TwoButtonDynamicFilter {
         self.reset();
     }
 
+    fn set_max_bit_rate(&mut self, bits_per_second: f64) {
+        self.set_bit_rate(bits_per_second);
+    }
+
+    fn set_boost_factor(&mut self, factor: f64) {
+        self.config_mut().boost_factor = factor;
+    }
+
     fn decorate_view(&mut self, view: &mut dyn DasherView) -> bool {
         // If no decoration change, return false
         if !self.decoration_changed {
@@ -420,4 +442,30 @@ mod tests {
         filter.state = TwoButtonDynamicFilterState::Down;
         assert_eq!(filter.current_target_y(), filter.target_y[2]);
     }
+
+    #[test]
+    fn test_two_button_dynamic_filter_speed_settings() {
+        let mut filter = TwoButtonDynamicFilter::new(TwoButtonDynamicFilterConfig::default());
+        filter.set_max_bit_rate(3.5);
+        assert_eq!(filter.config().bit_rate, 3.5);
+        assert_eq!(filter.frame_rate().target_bit_rate(), 3.5);
+
+        // Holding the boost key multiplies the speed by the boost factor
+        let model = DasherModel::new();
+        let now = Instant::now();
+        filter.set_boost_factor(2.0);
+        assert_eq!(filter.frame_speed_mul(&model, now), 1.0);
+        assert!(filter.base.speed_key(VirtualKey::Button5, true));
+        assert_eq!(filter.frame_speed_mul(&model, now), 2.0);
+
+        // The slow key halves it, on top of any boost
+        filter.config_mut().slow_key = Some(VirtualKey::Button4);
+        assert!(filter.base.speed_key(VirtualKey::Button4, true));
+        assert_eq!(filter.frame_speed_mul(&model, now), 1.0);
+        assert!(filter.base.speed_key(VirtualKey::Button5, false));
+        assert_eq!(filter.frame_speed_mul(&model, now), 0.5);
+        assert!(!filter.base.speed_key(VirtualKey::Button1, true));
+        filter.reset();
+        assert_eq!(filter.speed_key_mul(), 1.0);
+    }
 }
@@ -86,11 +86,19 @@ impl CircleStartHandler {
         }
     }
 
-    /// Set center coordinates
+    /// Set center coordinates (in screen space)
     pub fn set_center(&mut self, x: f64, y: f64) {
         self.center = Coordinates { x, y };
     }
 
+    /// Put the circle on the crosshair if no center has been set
+    fn ensure_center(&mut self, view: &dyn DasherView) {
+        if self.center.x == 0.0 && self.center.y == 0.0 {
+            let (x, y) = view.dasher_to_screen(DasherModel::ORIGIN_X, DasherModel::ORIGIN_Y);
+            self.set_center(x as f64, y as f64);
+        }
+    }
+
     /// Calculate angle between two points
     fn calculate_angle(&self, p1: &Coordinates, p2: &Coordinates) -> f64 {
         let dx = p2.x - p1.x;
@@ -240,17 +248,16 @@ impl InputFilter for CircleStartHandler {
     }
 
     fn decorate_view(&mut self, view: &mut dyn DasherView) -> bool {
-        // Get the screen dimensions
-        let (width, height) = view.get_dimensions();
-
-        // Set the center coordinates if not already set
-        if self.center.x == 0.0 && self.center.y == 0.0 {
-            // Default to center of screen
-            self.set_center(width as f64 / 2.0, height as f64 / 2.0);
-        }
+        self.ensure_center(view);
 
-        // Convert center to screen coordinates
-        let (center_x, center_y) = view.dasher_to_screen(self.center.x as i64, self.center.y as i64);
+        // The view draws in Dasher coordinates, so convert the center and the
+        // radius (measured across the flow of the view)
+        let (screen_x, screen_y) = (self.center.x as i32, self.center.y as i32);
+        let radius = self.config.radius as i32;
+        let (center_x, center_y) = view.screen_to_dasher(screen_x, screen_y);
+        let (_, across_x) = view.screen_to_dasher(screen_x + radius, screen_y);
+        let (_, across_y) = view.screen_to_dasher(screen_x, screen_y + radius);
+        let dasher_radius = (across_x - center_y).abs().max((across_y - center_y).abs());
 
         // Draw the circle with different colors based on state
         let (fill_color, outline_color, line_width) = match self.state {
@@ -261,9 +268,9 @@ impl InputFilter for CircleStartHandler {
 
         // Draw the circle
         view.draw_circle(
-            center_x as i64,
-            center_y as i64,
-            self.config.radius as i64,
+            center_x,
+            center_y,
+            dasher_radius,
             fill_color,
             outline_color,
             line_width
@@ -274,7 +281,8 @@ impl InputFilter for CircleStartHandler {
 
     fn process(&mut self, input: &mut dyn DasherInput, _time: u64, model: &mut DasherModel, view: &mut dyn DasherView) {
         let now = Instant::now();
-        if let Some((x, y)) = input.get_dasher_coordinates(view) {
+        self.ensure_center(view);
+        if let Some((x, y)) = input.get_screen_coordinates(view) {
             let coords = Coordinates { x: x as f64, y: y as f64 };
             match self.state {
                 CircleState::Outside => {
@@ -1,12 +1,16 @@
 //! Demo dynamic filter implementation
 //!
 //! This module provides a dynamic filter that automatically demonstrates Dasher.
+//! Given a target text it types it, steering as an ideal user would, which
+//! suits kiosk demos, screenshots and end-to-end tests of the dynamics;
+//! otherwise it wanders between targets above and below the crosshair.
 
 use std::time::Instant;
 use crate::input::{DasherInput, InputFilter, VirtualKey};
 use crate::input::dynamic_filter::{DynamicFilter, DynamicFilterBase, DynamicFilterConfig};
 use crate::input::frame_rate::FrameRate;
 use crate::model::DasherModel;
+use crate::simulator::Simulator;
 use crate::view::DasherView;
 
 /// Demo dynamic filter configuration
@@ -23,6 +27,15 @@ pub struct DemoDynamicFilterConfig {
 
     /// Whether to use random targets
     pub random_targets: bool,
+
+    /// Text to type, or `None` to wander between targets
+    ///
+    /// This is the whole output wanted, including any text already written.
+    /// It is typed at the base configuration's bit rate.
+    pub text: Option<String>,
+
+    /// Random jitter added to the steering while typing, as a fraction of the canvas height
+    pub noise: f64,
 }
 
 impl Default for DemoDynamicFilterConfig {
@@ -32,6 +45,8 @@ impl Default for DemoDynamicFilterConfig {
             target_offset: 2048,
             target_change_interval: 5000,
             random_targets: true,
+            text: None,
+            noise: 0.0,
         }
     }
 }
@@ -54,6 +69,9 @@ pub struct DemoDynamicFilter {
 
     /// Whether the view decoration has changed
     decoration_changed: bool,
+
+    /// Whether the target text has been typed
+    finished: bool,
 }
 
 impl DemoDynamicFilter {
@@ -70,7 +88,99 @@ impl DemoDynamicFilter {
             target_y: 2048,
             last_target_change: None,
             decoration_changed: true,
+            finished: false,
+        }
+    }
+
+    /// Get the text being typed, if any
+    pub fn text(&self) -> Option<&str> {
+        self.config.text.as_deref()
+    }
+
+    /// Set the text to type, or `None` to wander between targets
+    pub fn set_text(&mut self, text: Option<String>) {
+        self.config.text = text;
+        self.finished = false;
+    }
+
+    /// Check whether the target text has been typed
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Steer one frame towards the next symbol of the target text
+    fn type_text(&mut self, time: u64, model: &mut DasherModel) {
+        let target: Vec<char> = self.config.text.as_deref().unwrap_or_default().chars().collect();
+        let Some((x, y)) = Simulator::steer_to_text(model, &target) else {
+            self.finished = true;
+            return;
+        };
+        self.finished = false;
+
+        let jitter = (rand::random::<f64>() * 2.0 - 1.0) * self.config.noise * DasherModel::MAX_Y as f64;
+        self.target_x = x;
+        self.target_y = (y + jitter as i64).clamp(0, DasherModel::MAX_Y);
+        self.decoration_changed = true;
+
+        // As for the default filter, the frame rate gives the steps per bit
+        self.frame_rate_mut().record_frame_ms(time);
+        let bits_at_limit = (DasherModel::ORIGIN_X as f64 / DasherModel::X_LIMIT as f64).log2();
+        let steps = ((self.frame_rate().steps() as f64 * bits_at_limit).round() as i32).max(1);
+        model.apply_input_coordinates((self.target_x, self.target_y), steps);
+    }
+
+    /// Move the model on by one frame
+    fn step(&mut self, time: u64, model: &mut DasherModel) {
+        // If paused, do nothing
+        if self.is_paused() {
+            return;
+        }
+
+        if self.config.text.is_some() {
+            self.type_text(time, model);
+            return;
+        }
+
+        // Convert time to Instant
+        let now = Instant::now();
+
+        // Check if it's time to change the target
+        if let Some(last_change) = self.last_target_change {
+            let elapsed = now.duration_since(last_change).as_millis() as u64;
+            if elapsed >= self.config.target_change_interval {
+                self.change_target(now);
+            }
+        } else {
+            // First run, set the initial target
+            self.change_target(now);
+        }
+
+        // Move towards the current target
+        let speed_mul = self.frame_speed_mul(model, now);
+
+        // If speed multiplier is zero or negative, we're not moving
+        if speed_mul <= 0.0 {
+            return;
         }
+
+        // Record the frame
+        self.frame_rate_mut().record_frame(now);
+
+        // Calculate the number of steps based on the frame rate and speed multiplier
+        let steps = (self.frame_rate().steps() as f64 / speed_mul).round() as i32;
+        assert!(steps > 0);
+
+        // Limit X to prevent overflow
+        let x = self.target_x.clamp(1, (1 << 29) / 100);
+
+        // Schedule the step
+        model.schedule_one_step(
+            self.target_y - x, // y1
+            self.target_y + x, // y2
+            steps,
+            self.config.base.x_limit_speed as i32,
+            self.config.base.exact_dynamics
+        );
     }
 
     /// Change the target
@@ -130,57 +240,19 @@ impl DynamicFilter for DemoDynamicFilter {
 impl InputFilter for DemoDynamicFilter {
     fn reset(&mut self) {
         // Reset to default state
+        self.target_x = 100;
         self.target_y = 2048;
         self.last_target_change = None;
         self.decoration_changed = true;
+        self.finished = false;
     }
 
-    fn process(&mut self, _input: &mut dyn DasherInput, _time: u64, model: &mut DasherModel, _view: &mut dyn DasherView) {
-        // If paused, do nothing
-        if self.is_paused() {
-            return;
-        }
-
-        // Convert time to Instant
-        let now = Instant::now();
-
-        // Check if it's time to change the target
-        if let Some(last_change) = self.last_target_change {
-            let elapsed = now.duration_since(last_change).as_millis() as u64;
-            if elapsed >= self.config.target_change_interval {
-                self.change_target(now);
-            }
-        } else {
-            // First run, set the initial target
-            self.change_target(now);
-        }
-
-        // Move towards the current target
-        let speed_mul = self.frame_speed_mul(model, now);
-
-        // If speed multiplier is zero or negative, we're not moving
-        if speed_mul <= 0.0 {
-            return;
-        }
-
-        // Record the frame
-        self.frame_rate_mut().record_frame(now);
-
-        // Calculate the number of steps based on the frame rate and speed multiplier
-        let steps = (self.frame_rate().steps() as f64 / speed_mul).round() as i32;
-        assert!(steps > 0);
-
-        // Limit X to prevent overflow
-        let x = self.target_x.max(1).min((1 << 29) / 100);
+    fn process(&mut self, _input: &mut dyn DasherInput, time: u64, model: &mut DasherModel, _view: &mut dyn DasherView) {
+        self.step(time, model);
+    }
 
-        // Schedule the step
-        model.schedule_one_step(
-            self.target_y - x, // y1
-            self.target_y + x, // y2
-            steps,
-            self.config.base.x_limit_speed as i32,
-            self.config.base.exact_dynamics
-        );
+    fn process_without_input(&mut self, time: u64, model: &mut DasherModel, _view: &mut dyn DasherView) {
+        self.step(time, model);
     }
 
     fn key_down(&mut self, _time: u64, _key: VirtualKey, _model: &mut DasherModel, _view: &mut dyn DasherView) {
@@ -216,6 +288,16 @@ impl InputFilter for DemoDynamicFilter {
     }
 
     fn decorate_view(&mut self, view: &mut dyn DasherView) -> bool {
+        // While typing, draw the line being steered along
+        if self.config.text.is_some() {
+            self.decoration_changed = false;
+            if self.finished {
+                return false;
+            }
+            view.draw_line(DasherModel::ORIGIN_X, DasherModel::ORIGIN_Y, self.target_x, self.target_y, (255, 0, 0, 255), 3);
+            return true;
+        }
+
         // If no decoration change, return false
         if !self.decoration_changed {
             return false;
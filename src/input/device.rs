@@ -2,9 +2,53 @@
 //!
 //! This module contains the implementation of input devices for Dasher.
 
+use serde::{Deserialize, Serialize};
+
 use super::VirtualKey;
 use crate::view::DasherView;
 
+/// Correction applied to a device's screen coordinates
+///
+/// Users of head pointers and eye trackers can compensate for systematic
+/// drift: the position is scaled about the centre of the screen by the gain,
+/// then moved by the offset.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Calibration {
+    /// Horizontal offset (in pixels)
+    pub x_offset: i32,
+    /// Vertical offset (in pixels)
+    pub y_offset: i32,
+    /// Horizontal gain about the centre of the screen
+    pub x_gain: f64,
+    /// Vertical gain about the centre of the screen
+    pub y_gain: f64,
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self {
+            x_offset: 0,
+            y_offset: 0,
+            x_gain: 1.0,
+            y_gain: 1.0,
+        }
+    }
+}
+
+impl Calibration {
+    /// Calibrate a position on a screen of the given size
+    pub fn apply(&self, (x, y): (i32, i32), (width, height): (i32, i32)) -> (i32, i32) {
+        let calibrate = |value: i32, size: i32, gain: f64, offset: i32| {
+            let centre = size as f64 / 2.0;
+            (centre + (value as f64 - centre) * gain).round() as i32 + offset
+        };
+        (
+            calibrate(x, width, self.x_gain, self.x_offset),
+            calibrate(y, height, self.y_gain, self.y_offset),
+        )
+    }
+}
+
 /// Interface for input devices
 pub trait DasherInput {
     /// Get the coordinates from the input device in Dasher coordinates
@@ -36,6 +80,21 @@ pub trait DasherInput {
 
     /// Clone the input device into a Box
     fn box_clone(&self) -> Box<dyn DasherInput>;
+
+    /// Get the calibration applied to the device's coordinates
+    fn calibration(&self) -> Calibration {
+        Calibration::default()
+    }
+
+    /// Set the calibration applied to the device's coordinates
+    ///
+    /// Devices that cannot be calibrated ignore this.
+    fn set_calibration(&mut self, _calibration: Calibration) {}
+
+    /// Get the device as `Any`, for devices with their own events (such as touch)
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        None
+    }
 }
 
 /// Mouse input implementation
@@ -52,6 +111,9 @@ pub struct MouseInput {
 
     /// Whether the device is active
     active: bool,
+
+    /// Correction applied to the coordinates
+    calibration: Calibration,
 }
 
 impl Default for MouseInput {
@@ -68,6 +130,7 @@ impl MouseInput {
             x: 0,
             y: 0,
             active: false,
+            calibration: Calibration::default(),
         }
     }
 
@@ -80,20 +143,16 @@ impl MouseInput {
 
 impl DasherInput for MouseInput {
     fn get_dasher_coordinates(&mut self, view: &dyn DasherView) -> Option<(i64, i64)> {
-        if !self.active {
-            return None;
-        }
-
-        let (x, y) = view.screen_to_dasher(self.x, self.y);
-        Some((x, y))
+        let (x, y) = self.get_screen_coordinates(view)?;
+        Some(view.screen_to_dasher(x, y))
     }
 
-    fn get_screen_coordinates(&self, _view: &dyn DasherView) -> Option<(i32, i32)> {
+    fn get_screen_coordinates(&self, view: &dyn DasherView) -> Option<(i32, i32)> {
         if !self.active {
             return None;
         }
 
-        Some((self.x, self.y))
+        Some(self.calibration.apply((self.x, self.y), view.get_dimensions()))
     }
 
     fn set_screen_position(&mut self, x: i32, y: i32) {
@@ -151,6 +210,18 @@ impl DasherInput for MouseInput {
     fn box_clone(&self) -> Box<dyn DasherInput> {
         Box::new(self.clone())
     }
+
+    fn calibration(&self) -> Calibration {
+        self.calibration
+    }
+
+    fn set_calibration(&mut self, calibration: Calibration) {
+        self.calibration = calibration;
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        Some(self)
+    }
 }
 
 /// One-dimensional input implementation
@@ -164,6 +235,9 @@ pub struct OneDimensionalInput {
 
     /// Whether the device is active
     active: bool,
+
+    /// Correction applied to the coordinates (only the vertical part is used)
+    calibration: Calibration,
 }
 
 impl OneDimensionalInput {
@@ -174,6 +248,7 @@ impl OneDimensionalInput {
             name: "One-Dimensional".to_string(),
             y: 0,
             active: false,
+            calibration: Calibration::default(),
         }
     }
 
@@ -191,7 +266,8 @@ impl DasherInput for OneDimensionalInput {
         }
 
         // In one-dimensional mode, the X coordinate is always 0
-        let (_, y) = view.screen_to_dasher(0, self.y);
+        let (_, y) = self.calibration.apply((0, self.y), view.get_dimensions());
+        let (_, y) = view.screen_to_dasher(0, y);
         Some((0, y))
     }
 
@@ -201,8 +277,9 @@ impl DasherInput for OneDimensionalInput {
         }
 
         // In one-dimensional mode, the X coordinate depends on the orientation
-        let (width, _) = view.get_dimensions();
-        Some((width / 2, self.y))
+        let (width, height) = view.get_dimensions();
+        let (_, y) = self.calibration.apply((0, self.y), (width, height));
+        Some((width / 2, y))
     }
 
     fn set_screen_position(&mut self, _x: i32, y: i32) {
@@ -237,6 +314,14 @@ impl DasherInput for OneDimensionalInput {
     fn box_clone(&self) -> Box<dyn DasherInput> {
         Box::new(self.clone())
     }
+
+    fn calibration(&self) -> Calibration {
+        self.calibration
+    }
+
+    fn set_calibration(&mut self, calibration: Calibration) {
+        self.calibration = calibration;
+    }
 }
 
 /// Eye tracker input implementation
@@ -265,6 +350,9 @@ pub struct EyeTrackerInput {
     /// Previous Y coordinate
     #[allow(dead_code)]
     prev_y: i32,
+
+    /// Correction applied to the coordinates
+    calibration: Calibration,
 }
 
 impl EyeTrackerInput {
@@ -279,6 +367,7 @@ impl EyeTrackerInput {
             smoothing_factor: 0.8,
             prev_x: 0,
             prev_y: 0,
+            calibration: Calibration::default(),
         }
     }
 
@@ -302,20 +391,16 @@ impl EyeTrackerInput {
 
 impl DasherInput for EyeTrackerInput {
     fn get_dasher_coordinates(&mut self, view: &dyn DasherView) -> Option<(i64, i64)> {
-        if !self.active {
-            return None;
-        }
-
-        let (x, y) = view.screen_to_dasher(self.x, self.y);
-        Some((x, y))
+        let (x, y) = self.get_screen_coordinates(view)?;
+        Some(view.screen_to_dasher(x, y))
     }
 
-    fn get_screen_coordinates(&self, _view: &dyn DasherView) -> Option<(i32, i32)> {
+    fn get_screen_coordinates(&self, view: &dyn DasherView) -> Option<(i32, i32)> {
         if !self.active {
             return None;
         }
 
-        Some((self.x, self.y))
+        Some(self.calibration.apply((self.x, self.y), view.get_dimensions()))
     }
 
     fn set_screen_position(&mut self, x: i32, y: i32) {
@@ -350,4 +435,12 @@ impl DasherInput for EyeTrackerInput {
     fn box_clone(&self) -> Box<dyn DasherInput> {
         Box::new(self.clone())
     }
+
+    fn calibration(&self) -> Calibration {
+        self.calibration
+    }
+
+    fn set_calibration(&mut self, calibration: Calibration) {
+        self.calibration = calibration;
+    }
 }
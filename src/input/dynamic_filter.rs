@@ -5,6 +5,7 @@
 
 use std::time::Instant;
 use crate::input::filter::InputFilter;
+use crate::input::VirtualKey;
 use crate::input::frame_rate::FrameRate;
 use crate::model::DasherModel;
 use crate::view::DasherView;
@@ -27,6 +28,15 @@ pub struct DynamicFilterConfig {
 
     /// X limit speed (maximum X coordinate)
     pub x_limit_speed: i64,
+
+    /// Speed multiplier while the boost key is held
+    pub boost_factor: f64,
+
+    /// Key that multiplies the speed by the boost factor while held
+    pub boost_key: Option<VirtualKey>,
+
+    /// Key that halves the speed while held
+    pub slow_key: Option<VirtualKey>,
 }
 
 impl Default for DynamicFilterConfig {
@@ -37,6 +47,9 @@ impl Default for DynamicFilterConfig {
             slow_start_time: 1000,
             exact_dynamics: false,
             x_limit_speed: 100,
+            boost_factor: 1.75,
+            boost_key: Some(VirtualKey::Button5),
+            slow_key: None,
         }
     }
 }
@@ -63,6 +76,17 @@ pub trait DynamicFilter: InputFilter {
     /// Set the start time
     fn set_start_time(&mut self, time: Instant);
 
+    /// Get the speed multiplier from the speed keys held
+    fn speed_key_mul(&self) -> f64 {
+        1.0
+    }
+
+    /// Set the target bit rate, for both the configuration and the frame rate
+    fn set_bit_rate(&mut self, bits_per_second: f64) {
+        self.config_mut().bit_rate = bits_per_second;
+        self.frame_rate_mut().set_target_bit_rate(bits_per_second);
+    }
+
     /// Calculate the speed multiplier for the current frame
     ///
     /// # Arguments
@@ -85,6 +109,8 @@ pub trait DynamicFilter: InputFilter {
         // Apply slow start if enabled
         let mut speed_mul = node_speed_mul;
 
+        speed_mul *= self.speed_key_mul();
+
         if self.config().slow_start {
             if let Some(start_time) = self.start_time() {
                 let elapsed = time.duration_since(start_time).as_millis() as u64;
@@ -173,6 +199,12 @@ pub struct DynamicFilterBase {
 
     /// Whether the filter is paused
     paused: bool,
+
+    /// Whether the boost key is held
+    boosted: bool,
+
+    /// Whether the slow key is held
+    slowed: bool,
 }
 
 impl DynamicFilterBase {
@@ -187,6 +219,8 @@ impl DynamicFilterBase {
             config,
             start_time: None,
             paused: true,
+            boosted: false,
+            slowed: false,
         }
     }
 
@@ -234,6 +268,32 @@ impl DynamicFilterBase {
     pub fn unpause(&mut self) {
         self.paused = false;
     }
+
+    /// Follow the boost and slow keys, returning whether `key` is one of them
+    pub fn speed_key(&mut self, key: VirtualKey, pressed: bool) -> bool {
+        if self.config.boost_key == Some(key) {
+            self.boosted = pressed;
+            true
+        } else if self.config.slow_key == Some(key) {
+            self.slowed = pressed;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get the speed multiplier from the speed keys held
+    pub fn speed_key_mul(&self) -> f64 {
+        let boost = if self.boosted { self.config.boost_factor } else { 1.0 };
+        let slow = if self.slowed { 0.5 } else { 1.0 };
+        boost * slow
+    }
+
+    /// Release the speed keys
+    pub fn release_speed_keys(&mut self) {
+        self.boosted = false;
+        self.slowed = false;
+    }
 }
 
 // We don't implement DynamicFilter for DynamicFilterBase because it doesn't implement InputFilter
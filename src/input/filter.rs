@@ -6,6 +6,7 @@ use super::VirtualKey;
 use crate::model::DasherModel;
 use crate::view::DasherView;
 use crate::input::DasherInput;
+use crate::input::{AutoSpeedControl, FrameRate};
 
 /// Input filter interface
 pub trait DasherInputExt {
@@ -50,6 +51,18 @@ pub trait InputFilter {
 
     /// Decorate the view with filter-specific elements
     fn decorate_view(&mut self, view: &mut dyn DasherView) -> bool;
+
+    /// Set the maximum speed (in bits per second), for filters that steer continuously
+    fn set_max_bit_rate(&mut self, _bits_per_second: f64) {}
+
+    /// Set the speed multiplier while boosted, for filters that steer continuously
+    fn set_boost_factor(&mut self, _factor: f64) {}
+
+    /// Enable or disable automatic speed control, for filters that steer continuously
+    fn set_auto_speed_control(&mut self, _enabled: bool) {}
+
+    /// Process a frame when there is no input device, for filters that steer without one
+    fn process_without_input(&mut self, _time: u64, _model: &mut DasherModel, _view: &mut dyn DasherView) {}
 }
 
 /// Default input filter implementation
@@ -60,12 +73,21 @@ pub struct DefaultFilter {
     /// Whether the filter is in turbo mode
     turbo: bool,
 
+    /// Speed multiplier in turbo mode
+    boost_factor: f64,
+
     /// Last known Dasher coordinates
     last_x: i64,
     last_y: i64,
 
     /// Whether we have valid mouse coordinates
     got_mouse_coords: bool,
+
+    /// Frame rate manager, so movement speed does not depend on the host's frame rate
+    frame_rate: FrameRate,
+
+    /// Automatic speed control, if enabled
+    auto_speed: Option<AutoSpeedControl>,
 }
 
 impl Default for DefaultFilter {
@@ -80,12 +102,30 @@ impl DefaultFilter {
         Self {
             paused: false,
             turbo: false,
+            boost_factor: 1.75,
             last_x: 0,
             last_y: 0,
             got_mouse_coords: false,
+            frame_rate: FrameRate::default(),
+            auto_speed: None,
         }
     }
 
+    /// Get the frame rate manager
+    pub fn frame_rate(&self) -> &FrameRate {
+        &self.frame_rate
+    }
+
+    /// Get a mutable reference to the frame rate manager
+    pub fn frame_rate_mut(&mut self) -> &mut FrameRate {
+        &mut self.frame_rate
+    }
+
+    /// Get the automatic speed control, if enabled
+    pub fn auto_speed_control(&self) -> Option<&AutoSpeedControl> {
+        self.auto_speed.as_ref()
+    }
+
     /// Apply a transform to the coordinates
     fn apply_transform(&mut self, _x: &mut i64, _y: &mut i64, _view: &dyn DasherView) {
         // Default implementation does nothing
@@ -104,16 +144,15 @@ impl DefaultFilter {
         self.apply_transform(&mut x, &mut y, view);
 
         if !self.is_paused() {
-            // Check if we're outside the visible region
+            // Check if we're outside the screen
             let (min_x, min_y, max_x, max_y) = view.get_visible_region();
-
-            if x > max_x || x < min_x || y > max_y || y < min_y {
+            if !(min_x..=max_x).contains(&x) || !(min_y..=max_y).contains(&y) {
                 self.pause();
                 return;
             }
 
             // Calculate the speed multiplier
-            let speed_mul = if self.turbo { 1.75 } else { 1.0 };
+            let speed_mul = if self.turbo { self.boost_factor } else { 1.0 };
 
             // Schedule a step towards the target
             self.one_step_towards(model, x, y, time, speed_mul);
@@ -121,13 +160,24 @@ impl DefaultFilter {
     }
 
     /// Schedule a step towards the target
-    fn one_step_towards(&mut self, model: &mut DasherModel, _x: i64, y: i64, _time: u64, _speed_mul: f64) {
-        // Calculate the target range
-        let y1 = y - 1800;
-        let y2 = y + 1800;
+    fn one_step_towards(&mut self, model: &mut DasherModel, x: i64, y: i64, time: u64, speed_mul: f64) {
+        // Record the frame
+        self.frame_rate.record_frame_ms(time);
 
-        // Schedule a step
-        model.schedule_one_step(y1, y2, 1, 100, false);
+        // The frame rate gives the steps per bit; at full speed (the pointer at the
+        // X limit) each zoom gains this many bits
+        let bits_at_limit = (DasherModel::ORIGIN_X as f64 / DasherModel::X_LIMIT as f64).log2();
+
+        // Speed up for smooth steering and slow down for erratic steering
+        let speed_mul = match &mut self.auto_speed {
+            Some(auto_speed) => speed_mul * auto_speed.update(x, y),
+            None => speed_mul,
+        };
+
+        // Spread the movement over more frames on faster hosts, and fewer in turbo mode
+        let steps = ((self.frame_rate.steps() as f64 * bits_at_limit / speed_mul).round() as i32).max(1);
+
+        model.apply_input_coordinates((x, y), steps);
     }
 
     /// Stop the filter
@@ -136,7 +186,8 @@ impl DefaultFilter {
     }
 
     /// Run the filter
-    fn run(&mut self, _time: u64) {
+    fn run(&mut self, time: u64) {
+        self.frame_rate.reset_ms(time);
         self.unpause();
     }
 }
@@ -218,36 +269,68 @@ impl InputFilter for DefaultFilter {
 
         true
     }
+
+    fn set_max_bit_rate(&mut self, bits_per_second: f64) {
+        self.frame_rate.set_target_bit_rate(bits_per_second);
+    }
+
+    fn set_boost_factor(&mut self, factor: f64) {
+        self.boost_factor = factor;
+    }
+
+    fn set_auto_speed_control(&mut self, enabled: bool) {
+        if enabled != self.auto_speed.is_some() {
+            self.auto_speed = enabled.then(AutoSpeedControl::new);
+        }
+    }
 }
 
 /// One-dimensional filter implementation
+///
+/// Steers with the Y coordinate alone, for inputs such as breath or pressure
+/// that give a single value. The Y range is wrapped around a circle centred
+/// on the crosshair: the middle steers straight ahead, a quarter of the way
+/// up or down steers straight up or down, and either end reverses.
 pub struct OneDimensionalFilter {
     /// Base filter
     base: DefaultFilter,
 
-    /// Maximum forward value
-    forward_max: i64,
+    /// Radius of the circle steered around
+    radius: i64,
+}
+
+impl Default for OneDimensionalFilter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl OneDimensionalFilter {
     /// Create a new one-dimensional filter
-    #[allow(dead_code)]
     pub fn new() -> Self {
         Self {
             base: DefaultFilter::new(),
-            forward_max: 100,
+            radius: DasherModel::ORIGIN_X * 3 / 4,
         }
     }
 
     /// Apply a transform to the coordinates
     fn apply_transform(&mut self, x: &mut i64, y: &mut i64, _view: &dyn DasherView) {
-        // In one-dimensional mode, we only use the Y coordinate
-        // and calculate X based on a function of Y
-        let y_abs = y.abs() as f64;
-        let x_max = (y_abs * y_abs).exp() - 1.0;
-        let x_max = x_max.min(self.forward_max as f64);
+        (*x, *y) = self.steering_position(*y);
+    }
+
+    /// Get the position steered to for a Y coordinate
+    fn steering_position(&self, y: i64) -> (i64, i64) {
+        // In one-dimensional mode, we only use the Y coordinate, as the
+        // angle around the crosshair from straight ahead
+        let offset = ((DasherModel::ORIGIN_Y - y) as f64 / DasherModel::ORIGIN_Y as f64).clamp(-1.0, 1.0);
+        let angle = offset * std::f64::consts::PI;
+        let radius = self.radius as f64;
 
-        *x = x_max as i64;
+        (
+            DasherModel::ORIGIN_X - (radius * angle.cos()).round() as i64,
+            DasherModel::ORIGIN_Y - (radius * angle.sin()).round() as i64,
+        )
     }
 }
 
@@ -263,8 +346,8 @@ impl InputFilter for OneDimensionalFilter {
         if let Some((_, y)) = input.get_dasher_coordinates(view) {
             // In one-dimensional mode, we calculate X based on Y
             let mut x = 0;
-            let mut y_copy = y;
-            self.apply_transform(&mut x, &mut y_copy, view);
+            let mut y = y;
+            self.apply_transform(&mut x, &mut y, view);
 
             // Execute the movement
             self.base.execute_movement(time, model, view, x, y);
@@ -309,6 +392,198 @@ impl InputFilter for OneDimensionalFilter {
     fn decorate_view(&mut self, view: &mut dyn DasherView) -> bool {
         self.base.decorate_view(view)
     }
+
+    fn set_max_bit_rate(&mut self, bits_per_second: f64) {
+        self.base.set_max_bit_rate(bits_per_second);
+    }
+
+    fn set_boost_factor(&mut self, factor: f64) {
+        self.base.set_boost_factor(factor);
+    }
+
+    fn set_auto_speed_control(&mut self, enabled: bool) {
+        self.base.set_auto_speed_control(enabled);
+    }
+}
+
+/// Keyboard steering filter implementation
+///
+/// Steers without any pointing device: while an arrow key (or W, A, S or D)
+/// is held, the target point moves that way at a steady rate. Right zooms
+/// in, left reverses, and up and down move towards the symbols above or
+/// below. The target stays where it was left when the keys are released.
+pub struct KeyboardSteeringFilter {
+    /// Base filter
+    base: DefaultFilter,
+
+    /// How fast the target moves, as a fraction of the height of the canvas per second
+    rate: f64,
+
+    /// Target point in Dasher coordinates
+    target_x: i64,
+    target_y: i64,
+
+    /// Which of up, down, left and right are held
+    held: [bool; 4],
+
+    /// Time of the last frame, once there has been one
+    last_time: Option<u64>,
+}
+
+impl Default for KeyboardSteeringFilter {
+    fn default() -> Self {
+        Self::new(0.5)
+    }
+}
+
+impl KeyboardSteeringFilter {
+    const UP: usize = 0;
+    const DOWN: usize = 1;
+    const LEFT: usize = 2;
+    const RIGHT: usize = 3;
+
+    /// Create a keyboard steering filter moving the target by `rate` of the canvas height per second
+    pub fn new(rate: f64) -> Self {
+        Self {
+            base: DefaultFilter::new(),
+            rate: rate.max(0.0),
+            target_x: DasherModel::ORIGIN_X,
+            target_y: DasherModel::ORIGIN_Y,
+            held: [false; 4],
+            last_time: None,
+        }
+    }
+
+    /// Get how fast the target moves, as a fraction of the canvas height per second
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Set how fast the target moves, as a fraction of the canvas height per second
+    pub fn set_rate(&mut self, rate: f64) {
+        self.rate = rate.max(0.0);
+    }
+
+    /// Get the target point in Dasher coordinates
+    pub fn target(&self) -> (i64, i64) {
+        (self.target_x, self.target_y)
+    }
+
+    /// Get the direction a key steers in, if it steers
+    fn direction(key: VirtualKey) -> Option<usize> {
+        match key {
+            VirtualKey::Up => Some(Self::UP),
+            VirtualKey::Down => Some(Self::DOWN),
+            VirtualKey::Left => Some(Self::LEFT),
+            VirtualKey::Right => Some(Self::RIGHT),
+            VirtualKey::Other(c) => match c.to_ascii_lowercase() {
+                'w' => Some(Self::UP),
+                's' => Some(Self::DOWN),
+                'a' => Some(Self::LEFT),
+                'd' => Some(Self::RIGHT),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Move the target for the keys held since the last frame
+    fn move_target(&mut self, time: u64) {
+        let elapsed_ms = self.last_time.map_or(0, |last| time.saturating_sub(last));
+        self.last_time = Some(time);
+        if self.base.is_paused() {
+            return;
+        }
+
+        let distance = (self.rate * DasherModel::MAX_Y as f64 * elapsed_ms as f64 / 1000.0) as i64;
+        let axis = |negative: bool, positive: bool| (positive as i64 - negative as i64) * distance;
+
+        // Dasher X grows towards the left of the canvas
+        self.target_x = (self.target_x + axis(self.held[Self::RIGHT], self.held[Self::LEFT]))
+            .clamp(DasherModel::X_LIMIT, DasherModel::MAX_X);
+        self.target_y = (self.target_y + axis(self.held[Self::UP], self.held[Self::DOWN]))
+            .clamp(0, DasherModel::MAX_Y);
+    }
+
+    /// Steer towards the target for this frame
+    fn steer(&mut self, time: u64, model: &mut DasherModel, view: &mut dyn DasherView) {
+        self.move_target(time);
+        self.base.execute_movement(time, model, view, self.target_x, self.target_y);
+    }
+}
+
+impl InputFilter for KeyboardSteeringFilter {
+    fn reset(&mut self) {
+        self.base.reset();
+        self.target_x = DasherModel::ORIGIN_X;
+        self.target_y = DasherModel::ORIGIN_Y;
+        self.held = [false; 4];
+        self.last_time = None;
+    }
+
+    fn process(&mut self, _input: &mut dyn DasherInput, time: u64, model: &mut DasherModel, view: &mut dyn DasherView) {
+        // The keys steer, whatever the pointer is doing
+        self.steer(time, model, view);
+    }
+
+    fn process_without_input(&mut self, time: u64, model: &mut DasherModel, view: &mut dyn DasherView) {
+        self.steer(time, model, view);
+    }
+
+    fn key_down(&mut self, time: u64, key: VirtualKey, model: &mut DasherModel, view: &mut dyn DasherView) {
+        match Self::direction(key) {
+            Some(direction) => self.held[direction] = true,
+            None => self.base.key_down(time, key, model, view),
+        }
+    }
+
+    fn key_up(&mut self, time: u64, key: VirtualKey, model: &mut DasherModel, view: &mut dyn DasherView) {
+        match Self::direction(key) {
+            Some(direction) => self.held[direction] = false,
+            None => self.base.key_up(time, key, model, view),
+        }
+    }
+
+    fn supports_pause(&self) -> bool {
+        self.base.supports_pause()
+    }
+
+    fn pause(&mut self) {
+        self.base.pause();
+    }
+
+    fn unpause(&mut self) {
+        self.base.unpause();
+    }
+
+    fn is_paused(&self) -> bool {
+        self.base.is_paused()
+    }
+
+    fn activate(&mut self) {
+        self.base.activate();
+    }
+
+    fn deactivate(&mut self) {
+        self.base.deactivate();
+        self.held = [false; 4];
+    }
+
+    fn decorate_view(&mut self, view: &mut dyn DasherView) -> bool {
+        self.base.decorate_view(view)
+    }
+
+    fn set_max_bit_rate(&mut self, bits_per_second: f64) {
+        self.base.set_max_bit_rate(bits_per_second);
+    }
+
+    fn set_boost_factor(&mut self, factor: f64) {
+        self.base.set_boost_factor(factor);
+    }
+
+    fn set_auto_speed_control(&mut self, enabled: bool) {
+        self.base.set_auto_speed_control(enabled);
+    }
 }
 
 /// Button input filter implementation
@@ -422,8 +697,14 @@ pub struct ClickFilter {
     clicked: bool,
 }
 
+impl Default for ClickFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ClickFilter {
-    #[allow(dead_code)]
+    /// Create a new click filter
     pub fn new() -> Self {
         Self {
             last_x: 0,
@@ -443,9 +724,15 @@ impl InputFilter for ClickFilter {
         self.last_y = 0;
         self.paused = false;
     }
-    fn process(&mut self, _input: &mut dyn DasherInput, _time: u64, model: &mut DasherModel, _view: &mut dyn DasherView) {
+    fn process(&mut self, input: &mut dyn DasherInput, _time: u64, model: &mut DasherModel, view: &mut dyn DasherView) {
+        // Follow the pointer, so a click can target where it is
+        if let Some((x, y)) = input.get_dasher_coordinates(view) {
+            self.last_x = x;
+            self.last_y = y;
+        }
+
         // In click mode, we only move when a click is registered
-        if self.clicked {
+        if self.clicked && !self.paused {
             // Schedule a step towards the target
             let y1 = self.target_y - 1800;
             let y2 = self.target_y + 1800;
@@ -457,20 +744,13 @@ impl InputFilter for ClickFilter {
         }
     }
 
-    fn key_down(&mut self, _time: u64, key: VirtualKey, _model: &mut DasherModel, view: &mut dyn DasherView) {
+    fn key_down(&mut self, _time: u64, key: VirtualKey, _model: &mut DasherModel, _view: &mut dyn DasherView) {
         match key {
             VirtualKey::PrimaryInput => {
-                // Get the current mouse coordinates
-                if let Some(_input) = view.get_input_device() {
-                    // We need a mutable reference to get coordinates
-                    // This is a design issue - we should refactor to avoid this
-                    // For now, we'll just ignore this case
-                    // if let Some((x, y)) = input.get_dasher_coordinates(view) {
-                    //     self.target_x = x;
-                    //     self.target_y = y;
-                    //     self.clicked = true;
-                    // }
-                }
+                // Target where the pointer was last seen
+                self.target_x = self.last_x;
+                self.target_y = self.last_y;
+                self.clicked = true;
             }
             VirtualKey::StartStopKey => {
                 if self.is_paused() {
@@ -522,3 +802,51 @@ impl InputFilter for ClickFilter {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_dimensional_steering() {
+        let filter = OneDimensionalFilter::new();
+        let radius = filter.radius;
+
+        // The middle zooms straight in and either end reverses
+        assert_eq!(filter.steering_position(DasherModel::ORIGIN_Y), (DasherModel::ORIGIN_X - radius, DasherModel::ORIGIN_Y));
+        assert_eq!(filter.steering_position(0), (DasherModel::ORIGIN_X + radius, DasherModel::ORIGIN_Y));
+        assert_eq!(filter.steering_position(DasherModel::MAX_Y), (DasherModel::ORIGIN_X + radius, DasherModel::ORIGIN_Y));
+
+        // A quarter of the way up steers straight up
+        assert_eq!(filter.steering_position(DasherModel::ORIGIN_Y / 2), (DasherModel::ORIGIN_X, DasherModel::ORIGIN_Y - radius));
+        assert_eq!(filter.steering_position(DasherModel::ORIGIN_Y * 3 / 2), (DasherModel::ORIGIN_X, DasherModel::ORIGIN_Y + radius));
+    }
+
+    #[test]
+    fn test_keyboard_steering_moves_target_while_held() {
+        let mut filter = KeyboardSteeringFilter::new(0.25);
+        let quarter = DasherModel::MAX_Y / 4;
+        assert_eq!(KeyboardSteeringFilter::direction(VirtualKey::Other('W')), Some(KeyboardSteeringFilter::UP));
+        assert_eq!(KeyboardSteeringFilter::direction(VirtualKey::Space), None);
+
+        filter.move_target(0);
+        filter.held[KeyboardSteeringFilter::RIGHT] = true;
+        filter.held[KeyboardSteeringFilter::DOWN] = true;
+        filter.move_target(1000);
+        assert_eq!(filter.target(), (DasherModel::ORIGIN_X - quarter, DasherModel::ORIGIN_Y + quarter));
+
+        // Released keys leave the target where it is, and it stays on the canvas
+        filter.held = [false; 4];
+        filter.move_target(2000);
+        assert_eq!(filter.target(), (DasherModel::ORIGIN_X - quarter, DasherModel::ORIGIN_Y + quarter));
+        filter.held[KeyboardSteeringFilter::RIGHT] = true;
+        filter.move_target(10000);
+        assert_eq!(filter.target().0, DasherModel::X_LIMIT);
+
+        // Nothing moves while paused
+        filter.pause();
+        filter.held[KeyboardSteeringFilter::LEFT] = true;
+        filter.move_target(11000);
+        assert_eq!(filter.target().0, DasherModel::X_LIMIT);
+    }
+}
@@ -0,0 +1,97 @@
+//! # Filter Registry Module
+//!
+//! This module maps input filter names to constructors, so frontends can
+//! switch input modes by name through the settings, like upstream Dasher's
+//! module manager.
+
+use super::{
+    ClickFilter, DefaultFilter, InputFilter, OneButtonDynamicFilter, OneButtonDynamicFilterConfig,
+    ScanningConfig, ScanningFilter, TwoButtonDynamicFilter, TwoButtonDynamicFilterConfig,
+};
+
+/// Constructor of an input filter
+pub type FilterConstructor = Box<dyn Fn() -> Box<dyn InputFilter>>;
+
+/// Input filters by name
+pub struct InputFilterRegistry {
+    /// Names and constructors, in the order they were registered
+    filters: Vec<(String, FilterConstructor)>,
+}
+
+impl Default for InputFilterRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputFilterRegistry {
+    /// Name of the default filter, steering towards the pointer
+    pub const DEFAULT: &'static str = "default";
+
+    /// Create a registry of the built-in filters
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        registry.register(Self::DEFAULT, || Box::new(DefaultFilter::new()));
+        registry.register("one-button-dynamic", || {
+            Box::new(OneButtonDynamicFilter::new(OneButtonDynamicFilterConfig::default()))
+        });
+        registry.register("two-button", || {
+            Box::new(TwoButtonDynamicFilter::new(TwoButtonDynamicFilterConfig::default()))
+        });
+        registry.register("click", || Box::new(ClickFilter::new()));
+        registry.register("scanning", || Box::new(ScanningFilter::new(ScanningConfig::default())));
+        registry
+    }
+
+    /// Create a registry with no filters
+    pub fn empty() -> Self {
+        Self { filters: Vec::new() }
+    }
+
+    /// Register a filter under `name`, replacing any filter of that name
+    pub fn register<F>(&mut self, name: &str, constructor: F)
+    where
+        F: Fn() -> Box<dyn InputFilter> + 'static,
+    {
+        let constructor: FilterConstructor = Box::new(constructor);
+        match self.filters.iter_mut().find(|(filter_name, _)| filter_name == name) {
+            Some((_, existing)) => *existing = constructor,
+            None => self.filters.push((name.to_string(), constructor)),
+        }
+    }
+
+    /// Create the filter registered under `name`
+    pub fn create(&self, name: &str) -> Option<Box<dyn InputFilter>> {
+        self.filters.iter()
+            .find(|(filter_name, _)| filter_name == name)
+            .map(|(_, constructor)| constructor())
+    }
+
+    /// Check whether a filter is registered under `name`
+    pub fn contains(&self, name: &str) -> bool {
+        self.filters.iter().any(|(filter_name, _)| filter_name == name)
+    }
+
+    /// Get the names of the filters, in the order they were registered
+    pub fn names(&self) -> Vec<&str> {
+        self.filters.iter().map(|(name, _)| name.as_str()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_creates_filters_by_name() {
+        let mut registry = InputFilterRegistry::new();
+        assert_eq!(registry.names(), ["default", "one-button-dynamic", "two-button", "click", "scanning"]);
+        assert!(registry.create("scanning").is_some());
+        assert!(registry.create("missing").is_none());
+
+        registry.register("click", || Box::new(DefaultFilter::new()));
+        registry.register("custom", || Box::new(ClickFilter::new()));
+        assert_eq!(registry.names().len(), 6);
+        assert!(registry.contains("custom"));
+    }
+}
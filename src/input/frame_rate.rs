@@ -2,6 +2,10 @@
 //!
 //! This module provides a frame rate manager that tracks frame times and calculates
 //! the number of steps needed to maintain a consistent bit rate.
+//!
+//! A step is one frame's worth of movement: the faster the host renders, the
+//! more steps each zoom is spread over, so the speed in bits per second is the
+//! same at 30Hz and at 144Hz.
 
 use std::time::Instant;
 
@@ -11,6 +15,9 @@ pub struct FrameRate {
     /// Last frame time
     last_frame_time: Option<Instant>,
 
+    /// Last frame time, for hosts that supply timestamps (in milliseconds)
+    last_frame_ms: Option<u64>,
+
     /// Average time between frames (in seconds)
     average_frame_time: f64,
 
@@ -37,6 +44,7 @@ impl FrameRate {
     pub fn new(target_bit_rate: f64, min_steps: i32) -> Self {
         Self {
             last_frame_time: None,
+            last_frame_ms: None,
             average_frame_time: 0.016, // Default to 60 FPS
             target_bit_rate,
             min_steps,
@@ -53,6 +61,16 @@ impl FrameRate {
         self.average_frame_time = 0.016; // Default to 60 FPS
     }
 
+    /// Reset the frame rate manager, using a host timestamp
+    ///
+    /// # Arguments
+    ///
+    /// * `time_ms` - Current time in milliseconds
+    pub fn reset_ms(&mut self, time_ms: u64) {
+        self.last_frame_ms = Some(time_ms);
+        self.average_frame_time = 0.016; // Default to 60 FPS
+    }
+
     /// Record a frame and update the average frame time
     ///
     /// # Arguments
@@ -60,31 +78,62 @@ impl FrameRate {
     /// * `time` - Current time
     pub fn record_frame(&mut self, time: Instant) {
         if let Some(last_time) = self.last_frame_time {
-            let frame_time = time.duration_since(last_time).as_secs_f64();
-
-            // Update the average frame time with a simple exponential moving average
-            // This gives more weight to recent frames while still smoothing out variations
-            const ALPHA: f64 = 0.1; // Smoothing factor
-            self.average_frame_time = (1.0 - ALPHA) * self.average_frame_time + ALPHA * frame_time;
+            self.record_frame_time(time.duration_since(last_time).as_secs_f64());
         }
 
         self.last_frame_time = Some(time);
     }
 
+    /// Record a frame at a host timestamp and update the average frame time
+    ///
+    /// # Arguments
+    ///
+    /// * `time_ms` - Current time in milliseconds
+    pub fn record_frame_ms(&mut self, time_ms: u64) {
+        if let Some(last_ms) = self.last_frame_ms {
+            if time_ms > last_ms {
+                self.record_frame_time((time_ms - last_ms) as f64 / 1000.0);
+            }
+        }
+
+        self.last_frame_ms = Some(time_ms);
+    }
+
+    /// Fold the time taken by one frame (in seconds) into the average
+    fn record_frame_time(&mut self, frame_time: f64) {
+        // Ignore stalls (e.g. the host was suspended) so they don't skew the average
+        const MAX_FRAME_TIME: f64 = 0.25;
+        let frame_time = frame_time.min(MAX_FRAME_TIME);
+
+        // Update the average frame time with a simple exponential moving average
+        // This gives more weight to recent frames while still smoothing out variations
+        const ALPHA: f64 = 0.1; // Smoothing factor
+        self.average_frame_time = (1.0 - ALPHA) * self.average_frame_time + ALPHA * frame_time;
+    }
+
     /// Calculate the number of steps needed to maintain the target bit rate
     ///
     /// # Returns
     ///
     /// The number of steps
     pub fn steps(&self) -> i32 {
-        // Calculate the number of steps based on the target bit rate and average frame time
-        // steps = bit_rate * frame_time
-        let steps = (self.target_bit_rate * self.average_frame_time).round() as i32;
+        // Spread one bit of zoom over the frames rendered while it should take:
+        // steps = frames_per_second / bit_rate
+        let steps = (1.0 / (self.target_bit_rate * self.average_frame_time)).round() as i32;
 
         // Ensure we have at least the minimum number of steps
         steps.max(self.min_steps)
     }
 
+    /// Get the measured frame rate
+    ///
+    /// # Returns
+    ///
+    /// The frame rate in frames per second
+    pub fn frames_per_second(&self) -> f64 {
+        1.0 / self.average_frame_time
+    }
+
     /// Get the average frame time
     ///
     /// # Returns
@@ -123,8 +172,8 @@ mod tests {
     fn test_frame_rate_basic() {
         let mut frame_rate = FrameRate::new(10.0, 1);
 
-        // Test initial state
-        assert_eq!(frame_rate.steps(), 1);
+        // Test initial state (60 frames per second at 10 bits per second)
+        assert_eq!(frame_rate.steps(), 6);
 
         // Test reset
         let now = Instant::now();
@@ -145,19 +194,36 @@ mod tests {
         let mut frame_rate = FrameRate::new(10.0, 1);
 
         // Test with different frame times
-        frame_rate.average_frame_time = 0.1; // 100ms per frame
-        assert_eq!(frame_rate.steps(), 1); // 10 * 0.1 = 1
+        frame_rate.average_frame_time = 0.01; // 100 frames per second
+        assert_eq!(frame_rate.steps(), 10); // 100 / 10 = 10
 
-        frame_rate.average_frame_time = 0.5; // 500ms per frame
-        assert_eq!(frame_rate.steps(), 5); // 10 * 0.5 = 5
+        frame_rate.average_frame_time = 0.02; // 50 frames per second
+        assert_eq!(frame_rate.steps(), 5); // 50 / 10 = 5
 
         // Test with different bit rates
-        frame_rate.set_target_bit_rate(20.0);
-        assert_eq!(frame_rate.steps(), 10); // 20 * 0.5 = 10
+        frame_rate.set_target_bit_rate(5.0);
+        assert_eq!(frame_rate.steps(), 10); // 50 / 5 = 10
 
         // Test with minimum steps
         let mut frame_rate = FrameRate::new(10.0, 5);
-        frame_rate.average_frame_time = 0.1; // 100ms per frame
-        assert_eq!(frame_rate.steps(), 5); // 10 * 0.1 = 1, but min is 5
+        frame_rate.average_frame_time = 0.1; // 10 frames per second
+        assert_eq!(frame_rate.steps(), 5); // 10 / 10 = 1, but min is 5
+    }
+
+    #[test]
+    fn test_speed_independent_of_frame_rate() {
+        // Steps per second (and so the zoom speed) should not depend on the host's frame rate
+        let mut slow = FrameRate::new(2.0, 1);
+        let mut fast = FrameRate::new(2.0, 1);
+        for frame in 0..200u64 {
+            slow.record_frame_ms(frame * 1000 / 30);
+            fast.record_frame_ms(frame * 1000 / 144);
+        }
+
+        assert!((slow.frames_per_second() - 30.0).abs() < 1.0);
+        assert!((fast.frames_per_second() - 144.0).abs() < 3.0);
+        let slow_seconds = slow.steps() as f64 * slow.average_frame_time();
+        let fast_seconds = fast.steps() as f64 * fast.average_frame_time();
+        assert!((slow_seconds - fast_seconds).abs() < 0.05);
     }
 }
@@ -0,0 +1,107 @@
+//! Head pointer profile
+//!
+//! Head trackers jitter with the user's tremor and posture, and most people
+//! can turn their head further from side to side than they can nod. The
+//! head pointer profile low-pass filters the pointer, scales its vertical
+//! movement, and starts Dasher when the pointer dwells in one place, since
+//! a head pointer user may have no button to press.
+
+/// Settings of the head pointer profile
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeadPointerConfig {
+    /// Strength of the low-pass filter, from 0 (none) to 1
+    pub smoothing: f64,
+    /// Gain of vertical movement about the centre of the screen
+    pub vertical_gain: f64,
+    /// How long the pointer must dwell to start Dasher (in milliseconds)
+    pub dwell_ms: u64,
+    /// How far the pointer may wander while dwelling (in pixels)
+    pub dwell_radius: i32,
+}
+
+impl Default for HeadPointerConfig {
+    fn default() -> Self {
+        Self {
+            smoothing: 0.7,
+            vertical_gain: 1.5,
+            dwell_ms: 1000,
+            dwell_radius: 30,
+        }
+    }
+}
+
+impl HeadPointerConfig {
+    /// Scale a position's vertical movement about the centre of a screen `height` high
+    pub fn apply_vertical_gain(&self, y: f64, height: i32) -> f64 {
+        let centre = height as f64 / 2.0;
+        centre + (y - centre) * self.vertical_gain
+    }
+}
+
+/// Detects the pointer dwelling in one place
+#[derive(Debug, Clone, Default)]
+pub struct DwellDetector {
+    /// Where and when the current dwell began
+    anchor: Option<(i32, i32, u64)>,
+}
+
+impl DwellDetector {
+    /// Create a detector with no dwell in progress
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Follow the pointer, returning whether it has now dwelt for `dwell_ms`
+    ///
+    /// A completed dwell is reported once; the pointer must then move away
+    /// and dwell again.
+    pub fn update(&mut self, time_ms: u64, position: Option<(i32, i32)>, dwell_ms: u64, radius: i32) -> bool {
+        let Some((x, y)) = position else {
+            self.anchor = None;
+            return false;
+        };
+
+        match self.anchor {
+            Some((anchor_x, anchor_y, start_ms)) if (x - anchor_x).abs() <= radius && (y - anchor_y).abs() <= radius => {
+                if start_ms != u64::MAX && time_ms.saturating_sub(start_ms) >= dwell_ms {
+                    // Don't report again until the pointer moves
+                    self.anchor = Some((anchor_x, anchor_y, u64::MAX));
+                    return true;
+                }
+                false
+            }
+            _ => {
+                self.anchor = Some((x, y, time_ms));
+                false
+            }
+        }
+    }
+
+    /// Forget any dwell in progress
+    pub fn reset(&mut self) {
+        self.anchor = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dwell_detector() {
+        let mut dwell = DwellDetector::new();
+        assert!(!dwell.update(0, Some((100, 100)), 1000, 30));
+        assert!(!dwell.update(500, Some((120, 90)), 1000, 30));
+        assert!(dwell.update(1000, Some((110, 110)), 1000, 30));
+        assert!(!dwell.update(3000, Some((110, 110)), 1000, 30));
+
+        // Moving away starts a new dwell
+        assert!(!dwell.update(3100, Some((300, 100)), 1000, 30));
+        assert!(!dwell.update(3500, None, 1000, 30));
+        assert!(!dwell.update(4000, Some((300, 100)), 1000, 30));
+        assert!(dwell.update(5000, Some((300, 100)), 1000, 30));
+
+        let config = HeadPointerConfig::default();
+        assert_eq!(config.apply_vertical_gain(200.0, 600), 150.0);
+    }
+}
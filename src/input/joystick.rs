@@ -0,0 +1,226 @@
+//! # Joystick Input Module
+//!
+//! This module contains a gamepad or joystick input device, for console
+//! style and wheelchair joystick access. The stick steers relative to the
+//! crosshair: pushing right zooms in, pushing left reverses and pushing up
+//! or down moves towards the symbols above or below. Buttons are mapped to
+//! virtual keys.
+
+use std::collections::HashMap;
+
+use super::{DasherInput, VirtualKey};
+use crate::model::DasherModel;
+use crate::view::DasherView;
+
+/// Settings for turning stick deflection into steering
+#[derive(Debug, Clone, PartialEq)]
+pub struct JoystickConfig {
+    /// Deflection (from 0 to 1) below which the stick counts as centred
+    pub dead_zone: f64,
+    /// Exponent of the response curve; 1 is linear and larger values give
+    /// finer control near the centre
+    pub exponent: f64,
+    /// Virtual key sent by each button
+    pub buttons: HashMap<u32, VirtualKey>,
+}
+
+impl Default for JoystickConfig {
+    fn default() -> Self {
+        Self {
+            dead_zone: 0.15,
+            exponent: 2.0,
+            buttons: HashMap::from([
+                (0, VirtualKey::StartStopKey),
+                (1, VirtualKey::PrimaryInput),
+                (2, VirtualKey::SecondaryInput),
+            ]),
+        }
+    }
+}
+
+/// Gamepad or joystick input device
+#[derive(Debug, Clone)]
+pub struct JoystickInput {
+    /// Name of the input device
+    name: String,
+
+    /// Steering and button settings
+    config: JoystickConfig,
+
+    /// Horizontal deflection, from -1 (left) to 1 (right)
+    x: f64,
+
+    /// Vertical deflection, from -1 (up) to 1 (down)
+    y: f64,
+
+    /// Whether the device is active
+    active: bool,
+}
+
+impl Default for JoystickInput {
+    fn default() -> Self {
+        Self::new(JoystickConfig::default())
+    }
+}
+
+impl JoystickInput {
+    /// Create a new joystick input
+    pub fn new(config: JoystickConfig) -> Self {
+        Self {
+            name: "Joystick".to_string(),
+            config,
+            x: 0.0,
+            y: 0.0,
+            active: false,
+        }
+    }
+
+    /// Get the steering and button settings
+    pub fn config(&self) -> &JoystickConfig {
+        &self.config
+    }
+
+    /// Set the steering and button settings
+    pub fn set_config(&mut self, config: JoystickConfig) {
+        self.config = config;
+    }
+
+    /// Send `key` when `button` is pressed
+    pub fn map_button(&mut self, button: u32, key: VirtualKey) {
+        self.config.buttons.insert(button, key);
+    }
+
+    /// Get the virtual key sent by `button`, if it is mapped
+    pub fn button_key(&self, button: u32) -> Option<VirtualKey> {
+        self.config.buttons.get(&button).copied()
+    }
+
+    /// Set the stick's deflection on each axis, from -1 to 1 (right and down are positive)
+    pub fn set_axes(&mut self, x: f64, y: f64) {
+        self.x = x.clamp(-1.0, 1.0);
+        self.y = y.clamp(-1.0, 1.0);
+    }
+
+    /// Get the deflection after the dead zone and response curve
+    ///
+    /// Both apply to the distance from the centre, so the direction of the
+    /// stick is kept.
+    pub fn response(&self) -> (f64, f64) {
+        let magnitude = self.x.hypot(self.y);
+        let dead_zone = self.config.dead_zone.clamp(0.0, 0.99);
+        if magnitude <= dead_zone {
+            return (0.0, 0.0);
+        }
+
+        let scaled = ((magnitude - dead_zone) / (1.0 - dead_zone)).min(1.0);
+        let curved = scaled.powf(self.config.exponent.max(0.1));
+        (self.x * curved / magnitude, self.y * curved / magnitude)
+    }
+
+    /// Get the position in Dasher coordinates that the stick steers to
+    fn steering_position(&self) -> (i64, i64) {
+        let (x, y) = self.response();
+        let x = if x >= 0.0 {
+            // Forwards, up to the fastest zoom
+            DasherModel::ORIGIN_X - (x * (DasherModel::ORIGIN_X - DasherModel::X_LIMIT) as f64) as i64
+        } else {
+            // Backwards, up to the edge of the screen
+            DasherModel::ORIGIN_X - (x * (DasherModel::MAX_X - DasherModel::ORIGIN_X) as f64) as i64
+        };
+        let y = DasherModel::ORIGIN_Y + (y * DasherModel::ORIGIN_Y as f64) as i64;
+        (x, y)
+    }
+}
+
+impl DasherInput for JoystickInput {
+    fn get_dasher_coordinates(&mut self, _view: &dyn DasherView) -> Option<(i64, i64)> {
+        if !self.active {
+            return None;
+        }
+
+        Some(self.steering_position())
+    }
+
+    fn get_screen_coordinates(&self, view: &dyn DasherView) -> Option<(i32, i32)> {
+        if !self.active {
+            return None;
+        }
+
+        let (x, y) = self.steering_position();
+        Some(view.dasher_to_screen(x, y))
+    }
+
+    fn set_screen_position(&mut self, _x: i32, _y: i32) {
+        // The position comes from the stick
+    }
+
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn supports_pause(&self) -> bool {
+        true
+    }
+
+    fn activate(&mut self) {
+        self.active = true;
+    }
+
+    fn deactivate(&mut self) {
+        self.active = false;
+        self.x = 0.0;
+        self.y = 0.0;
+    }
+
+    fn key_down(&mut self, _time: u64, _key: VirtualKey) {
+        // Nothing to do
+    }
+
+    fn key_up(&mut self, _time: u64, _key: VirtualKey) {
+        // Nothing to do
+    }
+
+    fn box_clone(&self) -> Box<dyn DasherInput> {
+        Box::new(self.clone())
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dead_zone_and_response_curve() {
+        let mut joystick = JoystickInput::default();
+        joystick.activate();
+
+        joystick.set_axes(0.1, -0.05);
+        assert_eq!(joystick.response(), (0.0, 0.0));
+        assert_eq!(joystick.steering_position(), (DasherModel::ORIGIN_X, DasherModel::ORIGIN_Y));
+
+        // Halfway past the dead zone gives a quarter of full speed
+        joystick.set_axes(0.575, 0.0);
+        let (x, y) = joystick.response();
+        assert!((x - 0.25).abs() < 1e-9 && y == 0.0);
+
+        joystick.set_axes(1.0, 0.0);
+        assert_eq!(joystick.steering_position(), (DasherModel::X_LIMIT, DasherModel::ORIGIN_Y));
+        joystick.set_axes(-1.0, 0.0);
+        assert_eq!(joystick.steering_position(), (DasherModel::MAX_X, DasherModel::ORIGIN_Y));
+        joystick.set_axes(0.0, 1.0);
+        assert_eq!(joystick.steering_position(), (DasherModel::ORIGIN_X, DasherModel::MAX_Y));
+    }
+
+    #[test]
+    fn test_button_mapping() {
+        let mut joystick = JoystickInput::default();
+        assert_eq!(joystick.button_key(0), Some(VirtualKey::StartStopKey));
+        assert_eq!(joystick.button_key(7), None);
+        joystick.map_button(7, VirtualKey::Backspace);
+        assert_eq!(joystick.button_key(7), Some(VirtualKey::Backspace));
+    }
+}
@@ -6,49 +6,70 @@ mod filter;
 mod device;
 mod button;
 mod circle_start;
+mod two_box_start;
 mod frame_rate;
+mod auto_speed;
 mod dynamic_filter;
 mod demo_filter;
+mod recorder;
+mod smoothing;
+mod touch;
+mod joystick;
+mod head_pointer;
+mod pressure;
+mod scanning;
+mod filter_registry;
 
+use serde::{Deserialize, Serialize};
 
 use crate::model::DasherModel;
 use crate::view::DasherView;
 
-pub use filter::{InputFilter, DefaultFilter};
-pub use device::{DasherInput, MouseInput};
-pub use button::{ButtonHandler, ButtonConfig, ButtonMode};
+pub use filter::{InputFilter, ClickFilter, DefaultFilter, KeyboardSteeringFilter, OneDimensionalFilter};
+pub use device::{Calibration, DasherInput, MouseInput};
+pub use button::{ButtonDwell, ButtonHandler, ButtonConfig, ButtonMode, ButtonRegion};
 pub use circle_start::{CircleStartHandler, CircleStartConfig};
+pub use two_box_start::{TwoBoxStartConfig, TwoBoxStartHandler};
 pub use frame_rate::FrameRate;
+pub use auto_speed::AutoSpeedControl;
 pub use dynamic_filter::{DynamicFilter, DynamicFilterBase, DynamicFilterConfig};
 pub use demo_filter::{DemoDynamicFilter, DemoDynamicFilterConfig};
+pub use recorder::{InputEvent, InputRecorder, InputRecording, InputReplay, RecordedEvent};
+pub use smoothing::{PointerSmoother, SmoothingMethod};
+pub use touch::{TouchGesture, TouchInput, TouchInputConfig};
+pub use joystick::{JoystickConfig, JoystickInput};
+pub use head_pointer::{DwellDetector, HeadPointerConfig};
+pub use pressure::{PressureCalibration, PressureInput};
+pub use scanning::{ScanningConfig, ScanningFilter};
+pub use filter_registry::{FilterConstructor, InputFilterRegistry};
 pub use button::one_button_dynamic_filter::{OneButtonDynamicFilter, OneButtonDynamicFilterConfig};
 pub use button::two_button_dynamic_filter::{TwoButtonDynamicFilter, TwoButtonDynamicFilterConfig};
 
 // --- ADDED: Stub update traits for input handlers ---
 trait UpdatableInputHandler {
-    fn update(&mut self, device: &dyn DasherInput, model: &mut DasherModel, view: &mut dyn DasherView);
+    fn update(&mut self, device: &dyn DasherInput, time: u64, model: &mut DasherModel, view: &mut dyn DasherView);
 }
 
 impl UpdatableInputHandler for Box<dyn InputFilter> {
-    fn update(&mut self, device: &dyn DasherInput, model: &mut DasherModel, view: &mut dyn DasherView) {
+    fn update(&mut self, device: &dyn DasherInput, time: u64, model: &mut DasherModel, view: &mut dyn DasherView) {
         // Clone the device to avoid borrowing issues
         let mut device_clone = device.box_clone();
         // Call the process method to handle the input filter logic
-        self.as_mut().process(&mut *device_clone, 0, model, view);
+        self.as_mut().process(&mut *device_clone, time, model, view);
     }
 }
 
 impl UpdatableInputHandler for crate::input::CircleStartHandler {
-    fn update(&mut self, device: &dyn DasherInput, model: &mut DasherModel, view: &mut dyn DasherView) {
+    fn update(&mut self, device: &dyn DasherInput, time: u64, model: &mut DasherModel, view: &mut dyn DasherView) {
         // Clone the device to avoid borrowing issues
         let mut device_clone = device.box_clone();
         // Call the process method to handle the circle start logic
-        self.process(&mut *device_clone, 0, model, view);
+        self.process(&mut *device_clone, time, model, view);
     }
 }
 
 impl UpdatableInputHandler for crate::input::button::ButtonHandler {
-    fn update(&mut self, device: &dyn DasherInput, model: &mut DasherModel, view: &mut dyn DasherView) {
+    fn update(&mut self, device: &dyn DasherInput, _time: u64, model: &mut DasherModel, view: &mut dyn DasherView) {
         // Process button input
         if let Some(coords) = device.get_screen_coordinates(view) {
             // Update the button state based on the coordinates
@@ -59,7 +80,7 @@ impl UpdatableInputHandler for crate::input::button::ButtonHandler {
 // --- END ADDED ---
 
 /// Virtual key codes for keyboard input
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum VirtualKey {
     /// Primary input key (usually left mouse button)
     PrimaryInput,
@@ -139,10 +160,27 @@ pub trait InputDevice {
     fn is_button_pressed(&self, button: u32) -> bool;
 }
 
+/// An input device with the name it was added under
+struct NamedInput {
+    /// Name the device was added under
+    name: String,
+
+    /// The device
+    device: Box<dyn DasherInput>,
+}
+
 /// Input manager that handles input devices and filters
+///
+/// Several devices can be in use at once, such as a mouse, a switch and an
+/// eye tracker. Key events reach every device, while the pointer is taken
+/// from the primary device, or from the first other device with a position
+/// while the primary device has none.
 pub struct InputManager {
-    /// Current input device
-    input_device: Option<Box<dyn DasherInput>>,
+    /// Input devices, in the order they were added
+    input_devices: Vec<NamedInput>,
+
+    /// Name of the primary pointer device
+    primary_device: Option<String>,
 
     /// Current input filter
     input_filter: Option<Box<dyn InputFilter>>,
@@ -153,8 +191,32 @@ pub struct InputManager {
     /// Circle start handler
     circle_start: Option<CircleStartHandler>,
 
+    /// Two-box start handler, if starting by moving through two boxes
+    two_box_start: Option<TwoBoxStartHandler>,
+
     /// Whether the input is paused
     paused: bool,
+
+    /// Maximum speed of continuous filters (in bits per second)
+    max_bit_rate: f64,
+
+    /// Speed multiplier of continuous filters while boosted
+    boost_factor: f64,
+
+    /// Whether continuous filters adapt their speed automatically
+    auto_speed_control: bool,
+
+    /// Smoothing applied to the pointer position each frame
+    smoother: PointerSmoother,
+
+    /// Head pointer profile, which replaces the smoothing while in use
+    head_pointer: Option<HeadPointerConfig>,
+
+    /// Low-pass filter of the head pointer profile
+    head_smoother: PointerSmoother,
+
+    /// Detects the head pointer dwelling to start
+    dwell: DwellDetector,
 }
 
 impl Default for InputManager {
@@ -167,11 +229,20 @@ impl InputManager {
     /// Create a new input manager
     pub fn new() -> Self {
         Self {
-            input_device: None,
+            input_devices: Vec::new(),
+            primary_device: None,
             input_filter: Some(Box::new(DefaultFilter::new())),
             button_handler: Some(ButtonHandler::new(ButtonConfig::default())),
             circle_start: Some(CircleStartHandler::new(CircleStartConfig::default())),
+            two_box_start: None,
             paused: false,
+            max_bit_rate: FrameRate::default().target_bit_rate(),
+            boost_factor: 1.75,
+            auto_speed_control: false,
+            smoother: PointerSmoother::default(),
+            head_pointer: None,
+            head_smoother: PointerSmoother::default(),
+            dwell: DwellDetector::new(),
         }
     }
 
@@ -196,67 +267,380 @@ impl InputManager {
         }
     }
 
+    /// Set the regions of the screen that act as buttons
+    pub fn set_button_regions(&mut self, regions: Vec<ButtonRegion>) {
+        if let Some(handler) = &mut self.button_handler {
+            handler.set_regions(regions);
+        }
+    }
+
+    /// Get the regions of the screen that act as buttons
+    pub fn button_regions(&self) -> &[ButtonRegion] {
+        self.button_handler.as_ref().map_or(&[], |handler| handler.regions())
+    }
+
+    /// Set how long the pointer must hover in a button region to press it (0 disables dwell selection)
+    pub fn set_button_dwell_time(&mut self, dwell_ms: u64) {
+        if let Some(handler) = &mut self.button_handler {
+            handler.set_dwell_time(dwell_ms);
+        }
+    }
+
+    /// Get the progress of the dwell on a button region, for drawing a countdown
+    pub fn button_dwell(&self) -> Option<ButtonDwell> {
+        self.button_handler.as_ref()?.dwell()
+    }
+
     /// Check if circle start is enabled
     pub fn is_circle_start_enabled(&self) -> bool {
         self.circle_start.is_some()
     }
 
-    /// Set the input device
+    /// Enable/disable two-box start
+    pub fn set_two_box_start_enabled(&mut self, enabled: bool) {
+        if enabled && self.two_box_start.is_none() {
+            self.two_box_start = Some(TwoBoxStartHandler::new(TwoBoxStartConfig::default()));
+        } else if !enabled {
+            self.two_box_start = None;
+        }
+    }
+
+    /// Check if two-box start is enabled
+    pub fn is_two_box_start_enabled(&self) -> bool {
+        self.two_box_start.is_some()
+    }
+
+    /// Check whether the pointer has now held in both start boxes, in two-box start mode
+    ///
+    /// Call this each frame while stopped.
+    pub fn two_box_start(&mut self, time_ms: u64, view: &dyn DasherView) -> bool {
+        let position = self.pointer_position(view);
+        let Some(handler) = &mut self.two_box_start else {
+            return false;
+        };
+        handler.update(time_ms, position, view.get_dimensions().1)
+    }
+
+    /// Draw what is shown while stopped, such as the two-box start boxes,
+    /// returning whether anything was drawn
+    pub fn decorate_stopped_view(&mut self, view: &mut dyn DasherView) -> bool {
+        self.two_box_start.as_ref().is_some_and(|handler| handler.decorate_view(view))
+    }
+
+    /// Replace the primary device, or add the device as the primary device
+    /// if there is none
+    ///
+    /// The device is added under its own name.
     pub fn set_input_device(&mut self, device: Box<dyn DasherInput>) {
-        self.input_device = Some(device);
+        let name = device.get_name().to_string();
+        let index = self.primary_index();
+        if let Some(index) = index {
+            self.input_devices.remove(index);
+        }
+        self.input_devices.retain(|input| input.name != name);
+        let index = index.unwrap_or(self.input_devices.len()).min(self.input_devices.len());
+        self.input_devices.insert(index, NamedInput { name: name.clone(), device });
+        self.primary_device = Some(name);
+    }
+
+    /// Add a device under `name`, replacing any device of that name
+    ///
+    /// The first device added becomes the primary device.
+    pub fn add_input_device(&mut self, name: &str, device: Box<dyn DasherInput>) {
+        match self.input_devices.iter_mut().find(|input| input.name == name) {
+            Some(input) => input.device = device,
+            None => self.input_devices.push(NamedInput { name: name.to_string(), device }),
+        }
+        if self.primary_device.is_none() {
+            self.primary_device = Some(name.to_string());
+        }
+    }
+
+    /// Remove the device added under `name`
+    ///
+    /// If it was the primary device, the first remaining device becomes primary.
+    pub fn remove_input_device(&mut self, name: &str) -> Option<Box<dyn DasherInput>> {
+        let index = self.input_devices.iter().position(|input| input.name == name)?;
+        let removed = self.input_devices.remove(index);
+        if self.primary_device.as_deref() == Some(name) {
+            self.primary_device = self.input_devices.first().map(|input| input.name.clone());
+        }
+        Some(removed.device)
+    }
+
+    /// Get the names of the devices, in the order they were added
+    pub fn input_device_names(&self) -> Vec<&str> {
+        self.input_devices.iter().map(|input| input.name.as_str()).collect()
+    }
+
+    /// Make the device added under `name` the primary pointer device
+    pub fn set_primary_device(&mut self, name: &str) -> Result<(), crate::DasherError> {
+        if !self.input_devices.iter().any(|input| input.name == name) {
+            return Err(crate::DasherError::InputError(format!("No input device named {}", name)));
+        }
+        self.primary_device = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Get the name of the primary pointer device
+    pub fn primary_device_name(&self) -> Option<&str> {
+        self.primary_device.as_deref()
     }
 
-    /// Get a reference to the input device
+    /// Get the position of the primary device in the list of devices
+    fn primary_index(&self) -> Option<usize> {
+        let name = self.primary_device.as_deref()?;
+        self.input_devices.iter().position(|input| input.name == name)
+    }
+
+    /// Get a reference to the primary device
     pub fn get_input_device(&self) -> Option<&dyn DasherInput> {
-        self.input_device.as_deref()
+        self.primary_index().map(|index| &*self.input_devices[index].device)
+    }
+
+    /// Get the device added under `name`
+    pub fn input_device(&self, name: &str) -> Option<&dyn DasherInput> {
+        self.input_devices.iter().find(|input| input.name == name).map(|input| &*input.device)
+    }
+
+    /// Get the device added under `name`, to modify it
+    pub fn input_device_mut(&mut self, name: &str) -> Option<&mut Box<dyn DasherInput>> {
+        self.input_devices.iter_mut().find(|input| input.name == name).map(|input| &mut input.device)
+    }
+
+    /// Iterate over the devices, to modify them
+    pub fn input_devices_mut(&mut self) -> impl Iterator<Item = &mut Box<dyn DasherInput>> {
+        self.input_devices.iter_mut().map(|input| &mut input.device)
+    }
+
+    /// Get the position in the list of the device the pointer is taken from:
+    /// the primary device, or the first other device with a position while
+    /// the primary device has none
+    fn pointer_index(&self, view: &dyn DasherView) -> Option<usize> {
+        let primary = self.primary_index();
+        let has_position = |index: &usize| self.input_devices[*index].device.get_screen_coordinates(view).is_some();
+        primary.filter(has_position)
+            .or_else(|| (0..self.input_devices.len()).find(has_position))
+            .or(primary)
+    }
+
+    /// Get the position of the pointer in screen coordinates
+    fn pointer_position(&self, view: &dyn DasherView) -> Option<(i32, i32)> {
+        let index = self.pointer_index(view)?;
+        self.input_devices[index].device.get_screen_coordinates(view)
+    }
+
+    /// Get the first device of type `T`
+    fn input_of_type_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.input_devices.iter_mut()
+            .find_map(|input| input.device.as_any_mut()?.downcast_mut::<T>())
+    }
+
+    /// Get the first touch screen device
+    pub fn touch_input_mut(&mut self) -> Option<&mut TouchInput> {
+        self.input_of_type_mut::<TouchInput>()
+    }
+
+    /// Get the first joystick device
+    pub fn joystick_input_mut(&mut self) -> Option<&mut JoystickInput> {
+        self.input_of_type_mut::<JoystickInput>()
     }
 
-    /// Set the mouse position for the input device
+    /// Get the first breath or pressure sensor device
+    pub fn pressure_input_mut(&mut self) -> Option<&mut PressureInput> {
+        self.input_of_type_mut::<PressureInput>()
+    }
+
+    /// Get the primary device, to modify it
+    fn primary_device_mut(&mut self) -> Option<&mut Box<dyn DasherInput>> {
+        let index = self.primary_index()?;
+        Some(&mut self.input_devices[index].device)
+    }
+
+    /// Set the mouse position for the primary device
     pub fn set_mouse_position(&mut self, x: i32, y: i32) -> Result<(), crate::DasherError> {
-        if let Some(input) = &mut self.input_device {
-            // Use a match to avoid borrowing issues
-            match input.as_mut() {
-                input => {
-                    input.set_screen_position(x, y);
-                    Ok(())
-                }
+        match self.primary_device_mut() {
+            Some(input) => {
+                input.set_screen_position(x, y);
+                Ok(())
             }
-        } else {
-            Err(crate::DasherError::InputError("No input device available".to_string()))
+            None => Err(crate::DasherError::InputError("No input device available".to_string())),
+        }
+    }
+
+    /// Set the calibration of the primary device
+    pub fn set_input_calibration(&mut self, calibration: Calibration) -> Result<(), crate::DasherError> {
+        match self.primary_device_mut() {
+            Some(input) => {
+                input.set_calibration(calibration);
+                Ok(())
+            }
+            None => Err(crate::DasherError::InputError("No input device available".to_string())),
         }
     }
 
     /// Set the input filter
-    pub fn set_input_filter(&mut self, filter: Box<dyn InputFilter>) {
+    pub fn set_input_filter(&mut self, mut filter: Box<dyn InputFilter>) {
+        filter.set_max_bit_rate(self.max_bit_rate);
+        filter.set_boost_factor(self.boost_factor);
+        filter.set_auto_speed_control(self.auto_speed_control);
         self.input_filter = Some(filter);
     }
 
+    /// Set the maximum speed of continuous filters (in bits per second)
+    pub fn set_max_bit_rate(&mut self, bits_per_second: f64) {
+        self.max_bit_rate = bits_per_second;
+        if let Some(filter) = &mut self.input_filter {
+            filter.set_max_bit_rate(bits_per_second);
+        }
+    }
+
+    /// Get the maximum speed of continuous filters (in bits per second)
+    pub fn max_bit_rate(&self) -> f64 {
+        self.max_bit_rate
+    }
+
+    /// Set the speed multiplier of continuous filters while boosted
+    pub fn set_boost_factor(&mut self, factor: f64) {
+        self.boost_factor = factor;
+        if let Some(filter) = &mut self.input_filter {
+            filter.set_boost_factor(factor);
+        }
+    }
+
+    /// Get the speed multiplier of continuous filters while boosted
+    pub fn boost_factor(&self) -> f64 {
+        self.boost_factor
+    }
+
+    /// Enable or disable automatic speed control of continuous filters
+    pub fn set_auto_speed_control(&mut self, enabled: bool) {
+        self.auto_speed_control = enabled;
+        if let Some(filter) = &mut self.input_filter {
+            filter.set_auto_speed_control(enabled);
+        }
+    }
+
+    /// Check if automatic speed control is enabled
+    pub fn auto_speed_control(&self) -> bool {
+        self.auto_speed_control
+    }
+
+    /// Set how the pointer position is smoothed, with strengths from 0 (none) to 1 for each axis
+    pub fn set_smoothing(&mut self, method: SmoothingMethod, strength_x: f64, strength_y: f64) {
+        if method != self.smoother.method() {
+            self.smoother.set_method(method);
+        }
+        self.smoother.set_strength(strength_x, strength_y);
+    }
+
+    /// Get the smoothing applied to the pointer position
+    pub fn smoother(&self) -> &PointerSmoother {
+        &self.smoother
+    }
+
+    /// Use the head pointer profile, or stop using it with `None`
+    pub fn set_head_pointer(&mut self, config: Option<HeadPointerConfig>) {
+        if let Some(config) = &config {
+            if self.head_pointer.is_none() {
+                self.head_smoother.set_method(SmoothingMethod::Exponential);
+            }
+            self.head_smoother.set_strength(config.smoothing, config.smoothing);
+        } else {
+            self.dwell.reset();
+        }
+        self.head_pointer = config;
+    }
+
+    /// Get the head pointer profile, if it is in use
+    pub fn head_pointer(&self) -> Option<&HeadPointerConfig> {
+        self.head_pointer.as_ref()
+    }
+
+    /// Check whether the pointer has dwelt long enough to start, in the head pointer profile
+    ///
+    /// Call this each frame while stopped.
+    pub fn dwell_to_start(&mut self, time_ms: u64, view: &dyn DasherView) -> bool {
+        let Some(config) = self.head_pointer else {
+            return false;
+        };
+        let position = self.pointer_position(view);
+        self.dwell.update(time_ms, position, config.dwell_ms, config.dwell_radius)
+    }
+
     /// Process input for a frame
-    pub fn process_frame(&mut self, _time: u64, model: &mut DasherModel, view: &mut dyn DasherView) {
+    pub fn process_frame(&mut self, time: u64, model: &mut DasherModel, view: &mut dyn DasherView) {
         if self.paused {
             return;
         }
 
+        // Smooth the pointer before anything reacts to it
+        let adjust = self.head_pointer.is_some() || self.smoother.is_active();
+        let pointer = self.pointer_index(view).map(|index| &*self.input_devices[index].device);
+        let smoothed = match pointer {
+            Some(device) if adjust => device.get_screen_coordinates(view).map(|(x, y)| {
+                let (x, y) = match &self.head_pointer {
+                    Some(config) => {
+                        let (x, y) = self.head_smoother.smooth(x as f64, y as f64);
+                        (x, config.apply_vertical_gain(y, view.get_dimensions().1))
+                    }
+                    None => self.smoother.smooth(x as f64, y as f64),
+                };
+                // The position is already calibrated
+                let mut smoothed = device.box_clone();
+                smoothed.set_calibration(Calibration::default());
+                smoothed.set_screen_position(x.round() as i32, y.round() as i32);
+                smoothed
+            }),
+            _ => None,
+        };
+        let device = smoothed.as_deref().or(pointer);
+
         // Process circle start first if enabled
         if let Some(circle) = &mut self.circle_start {
-            if let Some(device) = &self.input_device {
-                circle.update(device.as_ref(), model, view);
+            if let Some(device) = device {
+                circle.update(device, time, model, view);
             }
         }
 
-        // Process button handler
+        // Process button handler, including dwelling in its regions
+        let mut dwell_key = None;
         if let Some(handler) = &mut self.button_handler {
-            if let Some(device) = &self.input_device {
-                handler.update(device.as_ref(), model, view);
+            if let Some(device) = device {
+                handler.update(device, time, model, view);
             }
+            dwell_key = handler.update_dwell(time, device.and_then(|device| device.get_screen_coordinates(view)));
         }
 
-        // Process main input filter
+        // Process main input filter, which may steer without a device
         if let Some(filter) = &mut self.input_filter {
-            if let Some(device) = &self.input_device {
-                filter.update(device.as_ref(), model, view);
+            match device {
+                Some(device) => filter.update(device, time, model, view),
+                None => filter.process_without_input(time, model, view),
             }
         }
+
+        // A completed dwell presses its region's key
+        if let Some(key) = dwell_key {
+            self.key_down(time, key, model, view);
+            self.key_up(time, key, model, view);
+        }
+    }
+
+    /// Draw the decorations of the circle start, button handler and input
+    /// filter over the view, returning whether anything was drawn
+    pub fn decorate_view(&mut self, view: &mut dyn DasherView) -> bool {
+        let mut decorated = false;
+        if let Some(circle) = &mut self.circle_start {
+            decorated |= circle.decorate_view(view);
+        }
+        if let Some(handler) = &mut self.button_handler {
+            decorated |= handler.decorate_view(view);
+        }
+        if let Some(filter) = &mut self.input_filter {
+            decorated |= filter.decorate_view(view);
+        }
+        decorated
     }
 
     /// Handle a key down event
@@ -269,8 +653,8 @@ impl InputManager {
             filter.key_down(time, key, model, view);
         }
 
-        if let Some(input) = &mut self.input_device {
-            input.key_down(time, key);
+        for input in &mut self.input_devices {
+            input.device.key_down(time, key);
         }
     }
 
@@ -284,14 +668,16 @@ impl InputManager {
             filter.key_up(time, key, model, view);
         }
 
-        if let Some(input) = &mut self.input_device {
-            input.key_up(time, key);
+        for input in &mut self.input_devices {
+            input.device.key_up(time, key);
         }
     }
 
     /// Pause input processing
     pub fn pause(&mut self) {
         self.paused = true;
+        self.smoother.reset();
+        self.head_smoother.reset();
 
         if let Some(filter) = &mut self.input_filter {
             filter.pause();
@@ -314,6 +700,10 @@ impl InputManager {
 
     /// Reset the input manager
     pub fn reset(&mut self) {
+        self.smoother.reset();
+        self.head_smoother.reset();
+        self.dwell.reset();
+
         // Reset the input filter
         if let Some(filter) = &mut self.input_filter {
             filter.reset();
@@ -0,0 +1,204 @@
+//! # Pressure Input Module
+//!
+//! This module contains a single-axis breath or pressure input device, for
+//! sip-and-puff users. The pressure moves the pointer up and down: puffing
+//! moves it up and sipping moves it down, and resting at the neutral
+//! pressure keeps it level with the crosshair. It is meant to be used with
+//! the [`OneDimensionalFilter`](super::OneDimensionalFilter), which turns the
+//! vertical position alone into steering.
+
+use super::{DasherInput, VirtualKey};
+use crate::model::DasherModel;
+use crate::view::DasherView;
+
+/// The pressures a user's breath ranges over, in the sensor's own units
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PressureCalibration {
+    /// Pressure at rest
+    pub neutral: f64,
+    /// Hardest sip, which moves the pointer to the bottom
+    pub min: f64,
+    /// Hardest puff, which moves the pointer to the top
+    pub max: f64,
+}
+
+impl Default for PressureCalibration {
+    fn default() -> Self {
+        Self {
+            neutral: 0.0,
+            min: -1.0,
+            max: 1.0,
+        }
+    }
+}
+
+impl PressureCalibration {
+    /// Get the position of `pressure` from -1 (hardest sip) to 1 (hardest puff)
+    ///
+    /// Either side of neutral is scaled separately, since users rarely sip
+    /// as hard as they puff.
+    pub fn normalise(&self, pressure: f64) -> f64 {
+        let (offset, range) = if pressure >= self.neutral {
+            (pressure - self.neutral, self.max - self.neutral)
+        } else {
+            (pressure - self.neutral, self.neutral - self.min)
+        };
+        if range <= 0.0 {
+            return 0.0;
+        }
+        (offset / range).clamp(-1.0, 1.0)
+    }
+}
+
+/// Breath or pressure input device
+#[derive(Debug, Clone)]
+pub struct PressureInput {
+    /// Name of the input device
+    name: String,
+
+    /// Range of the user's breath
+    calibration: PressureCalibration,
+
+    /// Latest pressure reading
+    pressure: f64,
+
+    /// Whether the device is active
+    active: bool,
+}
+
+impl Default for PressureInput {
+    fn default() -> Self {
+        Self::new(PressureCalibration::default())
+    }
+}
+
+impl PressureInput {
+    /// Create a new pressure input
+    pub fn new(calibration: PressureCalibration) -> Self {
+        Self {
+            name: "Pressure".to_string(),
+            pressure: calibration.neutral,
+            calibration,
+            active: false,
+        }
+    }
+
+    /// Get the range of the user's breath
+    pub fn pressure_calibration(&self) -> PressureCalibration {
+        self.calibration
+    }
+
+    /// Set the range of the user's breath
+    pub fn set_pressure_calibration(&mut self, calibration: PressureCalibration) {
+        self.calibration = calibration;
+    }
+
+    /// Take the latest reading as the pressure at rest
+    pub fn calibrate_neutral(&mut self) {
+        self.calibration.neutral = self.pressure;
+    }
+
+    /// Get the latest pressure reading
+    pub fn pressure(&self) -> f64 {
+        self.pressure
+    }
+
+    /// Set the latest pressure reading
+    pub fn set_pressure(&mut self, pressure: f64) {
+        if pressure.is_finite() {
+            self.pressure = pressure;
+        }
+    }
+
+    /// Get the position in Dasher coordinates, on the crosshair's vertical line
+    fn position(&self) -> (i64, i64) {
+        let offset = self.calibration.normalise(self.pressure);
+        let y = DasherModel::ORIGIN_Y - (offset * DasherModel::ORIGIN_Y as f64) as i64;
+        (DasherModel::ORIGIN_X, y)
+    }
+}
+
+impl DasherInput for PressureInput {
+    fn get_dasher_coordinates(&mut self, _view: &dyn DasherView) -> Option<(i64, i64)> {
+        if !self.active {
+            return None;
+        }
+
+        Some(self.position())
+    }
+
+    fn get_screen_coordinates(&self, view: &dyn DasherView) -> Option<(i32, i32)> {
+        if !self.active {
+            return None;
+        }
+
+        let (x, y) = self.position();
+        Some(view.dasher_to_screen(x, y))
+    }
+
+    fn set_screen_position(&mut self, _x: i32, _y: i32) {
+        // The position comes from the pressure
+    }
+
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn supports_pause(&self) -> bool {
+        true
+    }
+
+    fn activate(&mut self) {
+        self.active = true;
+    }
+
+    fn deactivate(&mut self) {
+        self.active = false;
+        self.pressure = self.calibration.neutral;
+    }
+
+    fn key_down(&mut self, _time: u64, _key: VirtualKey) {
+        // Nothing to do
+    }
+
+    fn key_up(&mut self, _time: u64, _key: VirtualKey) {
+        // Nothing to do
+    }
+
+    fn box_clone(&self) -> Box<dyn DasherInput> {
+        Box::new(self.clone())
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pressure_maps_onto_y() {
+        let mut input = PressureInput::new(PressureCalibration { neutral: 10.0, min: 0.0, max: 50.0 });
+        input.activate();
+        assert_eq!(input.position(), (DasherModel::ORIGIN_X, DasherModel::ORIGIN_Y));
+
+        // Each side of neutral has its own range
+        input.set_pressure(30.0);
+        assert_eq!(input.position().1, DasherModel::ORIGIN_Y / 2);
+        input.set_pressure(5.0);
+        assert_eq!(input.position().1, DasherModel::ORIGIN_Y + DasherModel::ORIGIN_Y / 2);
+        input.set_pressure(-20.0);
+        assert_eq!(input.position().1, DasherModel::MAX_Y);
+        input.set_pressure(f64::NAN);
+        assert_eq!(input.pressure(), -20.0);
+
+        input.set_pressure(12.0);
+        input.calibrate_neutral();
+        assert_eq!(input.position().1, DasherModel::ORIGIN_Y);
+
+        let degenerate = PressureCalibration { neutral: 1.0, min: 1.0, max: 1.0 };
+        assert_eq!(degenerate.normalise(5.0), 0.0);
+    }
+}
@@ -0,0 +1,231 @@
+//! Input session recording and replay
+//!
+//! This module captures timestamped input events so that a session can be
+//! replayed deterministically, either to reproduce a bug or to re-run a user
+//! experiment offline.
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::VirtualKey;
+use crate::settings::{Parameter, ParameterValue};
+use crate::{DasherError, Result};
+
+/// An input event that can be recorded and replayed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum InputEvent {
+    /// The input device moved to the given screen coordinates
+    Coordinates { x: i32, y: i32 },
+
+    /// A key was pressed
+    KeyDown(VirtualKey),
+
+    /// A key was released
+    KeyUp(VirtualKey),
+
+    /// A parameter was changed
+    ParameterChange {
+        parameter: Parameter,
+        value: ParameterValue,
+    },
+
+    /// A frame was processed
+    Frame,
+}
+
+/// An input event with the time at which it occurred
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    /// Time of the event (in milliseconds)
+    pub time_ms: u64,
+
+    /// The event
+    pub event: InputEvent,
+}
+
+/// A recorded input session
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputRecording {
+    /// Events in the order they occurred
+    pub events: Vec<RecordedEvent>,
+}
+
+impl InputRecording {
+    /// Create an empty recording
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serialize the recording to JSON
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(self).map_err(|e| DasherError::Other(format!("Failed to serialize recording: {}", e)))
+    }
+
+    /// Deserialize a recording from JSON
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| DasherError::InvalidParameter(format!("Invalid recording: {}", e)))
+    }
+
+    /// Save the recording to a file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        fs::write(path, self.to_json()?).map_err(|e| DasherError::Other(format!("Failed to save recording: {}", e)))
+    }
+
+    /// Load a recording from a file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let json = fs::read_to_string(path).map_err(|e| DasherError::Other(format!("Failed to load recording: {}", e)))?;
+        Self::from_json(&json)
+    }
+}
+
+/// Records input events while active
+#[derive(Debug, Default)]
+pub struct InputRecorder {
+    /// The recording in progress, if any
+    recording: Option<InputRecording>,
+
+    /// Last coordinates recorded, so unchanged positions are not repeated
+    last_coordinates: Option<(i32, i32)>,
+}
+
+impl InputRecorder {
+    /// Create a new, inactive recorder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a new recording, discarding any recording in progress
+    pub fn start(&mut self) {
+        self.recording = Some(InputRecording::new());
+        self.last_coordinates = None;
+    }
+
+    /// Stop recording and return the captured events
+    pub fn stop(&mut self) -> Option<InputRecording> {
+        self.last_coordinates = None;
+        self.recording.take()
+    }
+
+    /// Check if a recording is in progress
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Record an event if a recording is in progress
+    pub fn record(&mut self, time_ms: u64, event: InputEvent) {
+        if let Some(recording) = &mut self.recording {
+            if let InputEvent::Coordinates { x, y } = event {
+                if self.last_coordinates == Some((x, y)) {
+                    return;
+                }
+                self.last_coordinates = Some((x, y));
+            }
+            recording.events.push(RecordedEvent { time_ms, event });
+        }
+    }
+}
+
+/// Plays back a recording in time order
+#[derive(Debug, Clone)]
+pub struct InputReplay {
+    /// The recording being replayed
+    recording: InputRecording,
+
+    /// Index of the next event to replay
+    position: usize,
+}
+
+impl InputReplay {
+    /// Create a replay of the given recording
+    pub fn new(recording: InputRecording) -> Self {
+        Self { recording, position: 0 }
+    }
+
+    /// Take the events due at or before `time_ms` that have not been replayed yet
+    pub fn due_events(&mut self, time_ms: u64) -> Vec<RecordedEvent> {
+        let start = self.position;
+        while self.position < self.recording.events.len()
+            && self.recording.events[self.position].time_ms <= time_ms
+        {
+            self.position += 1;
+        }
+        self.recording.events[start..self.position].to_vec()
+    }
+
+    /// Take all remaining events
+    pub fn remaining_events(&mut self) -> Vec<RecordedEvent> {
+        let start = self.position;
+        self.position = self.recording.events.len();
+        self.recording.events[start..].to_vec()
+    }
+
+    /// Check if every event has been replayed
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.recording.events.len()
+    }
+
+    /// Restart the replay from the beginning
+    pub fn rewind(&mut self) {
+        self.position = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_replay_in_order() {
+        let mut recorder = InputRecorder::new();
+        recorder.record(0, InputEvent::Frame);
+        assert!(!recorder.is_recording());
+
+        recorder.start();
+        recorder.record(10, InputEvent::Coordinates { x: 5, y: 6 });
+        recorder.record(20, InputEvent::Coordinates { x: 5, y: 6 });
+        recorder.record(20, InputEvent::KeyDown(VirtualKey::Button1));
+        recorder.record(30, InputEvent::ParameterChange {
+            parameter: Parameter::MaxBitRate,
+            value: ParameterValue::Long(200),
+        });
+        let recording = recorder.stop().unwrap();
+
+        // Repeated coordinates are dropped
+        assert_eq!(recording.events.len(), 3);
+
+        let json = recording.to_json().unwrap();
+        let mut replay = InputReplay::new(InputRecording::from_json(&json).unwrap());
+        assert_eq!(replay.due_events(20).len(), 2);
+        assert_eq!(replay.due_events(20).len(), 0);
+        assert_eq!(replay.remaining_events()[0].time_ms, 30);
+        assert!(replay.is_finished());
+    }
+
+    #[test]
+    fn test_replay_through_interface_is_deterministic() {
+        use crate::api::DasherInterface;
+        use crate::settings::Settings;
+
+        let mut original = DasherInterface::new(Settings::new());
+        original.start_recording();
+        original.new_frame(0);
+        original.key_down(10, VirtualKey::StartStopKey);
+        original.settings_mut().set_long(Parameter::Orientation, 2);
+        original.handle_parameter_change(Parameter::Orientation);
+        original.new_frame(20);
+        let recording = original.stop_recording().unwrap();
+
+        let mut replayed = DasherInterface::new(Settings::new());
+        replayed.start_recording();
+        let mut replay = InputReplay::new(recording.clone());
+        // The parameter change is stamped with the time of the preceding key event
+        assert_eq!(replayed.replay_until(&mut replay, 10), 3);
+        assert!(replayed.is_running());
+        assert_eq!(replayed.replay_all(&mut replay), 1);
+
+        assert_eq!(replayed.settings().get_long(Parameter::Orientation), Some(2));
+        assert_eq!(replayed.stop_recording().unwrap(), recording);
+    }
+}
@@ -0,0 +1,201 @@
+//! # Scanning Module
+//!
+//! This module contains the scanning filter, for users with a single switch.
+//! The height of the screen is split into equal boxes, highlighted one after
+//! the other, followed by a box for zooming out. Pressing the switch zooms
+//! into the highlighted box, as in upstream Dasher's menu mode.
+
+use super::{DasherInput, InputFilter, VirtualKey};
+use crate::model::DasherModel;
+use crate::view::DasherView;
+
+/// Scanning filter configuration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScanningConfig {
+    /// Number of boxes the screen is split into, not counting the zoom-out box
+    pub box_count: usize,
+    /// How long each box stays highlighted (in milliseconds)
+    pub scan_ms: u64,
+    /// Number of frames taken to zoom into a box
+    pub zoom_steps: i32,
+}
+
+impl Default for ScanningConfig {
+    fn default() -> Self {
+        Self {
+            box_count: 5,
+            scan_ms: 1000,
+            zoom_steps: 20,
+        }
+    }
+}
+
+/// Scanning filter
+#[derive(Debug, Clone)]
+pub struct ScanningFilter {
+    /// Configuration
+    config: ScanningConfig,
+    /// Index of the highlighted box
+    highlighted: usize,
+    /// When the highlighted box was highlighted, if scanning has begun
+    highlighted_at: Option<u64>,
+    /// Box selected with the switch, zoomed into on the next frame
+    selected: Option<usize>,
+    /// Whether the filter is paused
+    paused: bool,
+}
+
+impl ScanningFilter {
+    /// Create a new scanning filter
+    pub fn new(config: ScanningConfig) -> Self {
+        Self {
+            config,
+            highlighted: 0,
+            highlighted_at: None,
+            selected: None,
+            paused: false,
+        }
+    }
+
+    /// Get the configuration
+    pub fn config(&self) -> &ScanningConfig {
+        &self.config
+    }
+
+    /// Get the index of the highlighted box; the last index is the zoom-out box
+    pub fn highlighted_box(&self) -> usize {
+        self.highlighted
+    }
+
+    /// Get the top and bottom of each box in Dasher coordinates, the
+    /// zoom-out box last
+    pub fn boxes(&self) -> Vec<(i64, i64)> {
+        let count = self.config.box_count.max(1) as i64;
+        let mut boxes: Vec<_> = (0..count)
+            .map(|index| (index * DasherModel::MAX_Y / count, (index + 1) * DasherModel::MAX_Y / count))
+            .collect();
+        boxes.push((-DasherModel::MAX_Y / 2, DasherModel::MAX_Y * 3 / 2));
+        boxes
+    }
+
+    /// Move the highlight on, and zoom into the selected box
+    fn step(&mut self, time: u64, model: &mut DasherModel) {
+        if self.paused {
+            return;
+        }
+
+        if let Some(index) = self.selected.take() {
+            let (y1, y2) = self.boxes()[index];
+            model.schedule_zoom(y1, y2, self.config.zoom_steps.max(1));
+            self.highlighted = 0;
+            self.highlighted_at = Some(time);
+            return;
+        }
+
+        match self.highlighted_at {
+            Some(since) if time.saturating_sub(since) >= self.config.scan_ms => {
+                self.highlighted = (self.highlighted + 1) % (self.config.box_count.max(1) + 1);
+                self.highlighted_at = Some(time);
+            }
+            Some(_) => {}
+            None => self.highlighted_at = Some(time),
+        }
+    }
+}
+
+impl InputFilter for ScanningFilter {
+    fn process(&mut self, _input: &mut dyn DasherInput, time: u64, model: &mut DasherModel, _view: &mut dyn DasherView) {
+        self.step(time, model);
+    }
+
+    fn process_without_input(&mut self, time: u64, model: &mut DasherModel, _view: &mut dyn DasherView) {
+        self.step(time, model);
+    }
+
+    fn key_down(&mut self, _time: u64, key: VirtualKey, _model: &mut DasherModel, _view: &mut dyn DasherView) {
+        match key {
+            VirtualKey::PrimaryInput | VirtualKey::Button1 | VirtualKey::Space if !self.paused => {
+                self.selected = Some(self.highlighted);
+            }
+            VirtualKey::StartStopKey => self.paused = !self.paused,
+            _ => {}
+        }
+    }
+
+    fn key_up(&mut self, _time: u64, _key: VirtualKey, _model: &mut DasherModel, _view: &mut dyn DasherView) {}
+
+    fn supports_pause(&self) -> bool {
+        true
+    }
+
+    fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    fn unpause(&mut self) {
+        self.paused = false;
+    }
+
+    fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    fn reset(&mut self) {
+        self.highlighted = 0;
+        self.highlighted_at = None;
+        self.selected = None;
+    }
+
+    fn activate(&mut self) {}
+
+    fn deactivate(&mut self) {
+        self.reset();
+    }
+
+    fn decorate_view(&mut self, view: &mut dyn DasherView) -> bool {
+        let boxes = self.boxes();
+        let zoom_out = boxes.len() - 1;
+        let (y1, y2) = if self.highlighted == zoom_out {
+            // The zoom-out box is larger than the screen, so outline the screen
+            (0, DasherModel::MAX_Y)
+        } else {
+            boxes[self.highlighted]
+        };
+        view.draw_rectangle(0, y1, y2 - y1, y2, (255, 200, 0, 64), (255, 160, 0, 255), 3);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_cycles_through_the_boxes() {
+        let mut filter = ScanningFilter::new(ScanningConfig { box_count: 2, ..ScanningConfig::default() });
+        let mut model = DasherModel::new();
+        assert_eq!(filter.boxes(), [
+            (0, DasherModel::MAX_Y / 2),
+            (DasherModel::MAX_Y / 2, DasherModel::MAX_Y),
+            (-DasherModel::MAX_Y / 2, DasherModel::MAX_Y * 3 / 2),
+        ]);
+
+        // The highlight moves on once per scan time, wrapping after the zoom-out box
+        for (time, highlighted) in [(0, 0), (999, 0), (1000, 1), (2000, 2), (3000, 0)] {
+            filter.step(time, &mut model);
+            assert_eq!(filter.highlighted_box(), highlighted);
+        }
+
+        // Selecting a box zooms into it and starts scanning again
+        filter.step(4000, &mut model);
+        filter.selected = Some(filter.highlighted_box());
+        filter.step(4010, &mut model);
+        assert!(model.scheduled_step_count() > 0);
+        assert_eq!(filter.highlighted_box(), 0);
+
+        // Nothing moves while paused
+        filter.pause();
+        filter.step(9000, &mut model);
+        assert_eq!(filter.highlighted_box(), 0);
+    }
+}
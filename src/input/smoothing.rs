@@ -0,0 +1,210 @@
+//! Pointer smoothing
+//!
+//! This module smooths the pointer position before it drives the dynamics,
+//! to help users with tremor or noisy head trackers. The position is
+//! filtered once per frame, each axis separately and with its own strength,
+//! using an exponential average, a moving average or a Kalman filter.
+
+use std::collections::VecDeque;
+
+/// How the pointer position is smoothed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SmoothingMethod {
+    /// Use the position as it is
+    #[default]
+    None,
+    /// Move a fraction of the way to each new position
+    Exponential,
+    /// Average the positions of the last few frames
+    MovingAverage,
+    /// Estimate the position with a Kalman filter, trusting each new
+    /// position less the stronger the smoothing
+    Kalman,
+}
+
+impl SmoothingMethod {
+    /// Get the method for a setting value (0 none, 1 exponential, 2 moving average, 3 Kalman)
+    pub fn from_setting(value: i64) -> Self {
+        match value {
+            1 => SmoothingMethod::Exponential,
+            2 => SmoothingMethod::MovingAverage,
+            3 => SmoothingMethod::Kalman,
+            _ => SmoothingMethod::None,
+        }
+    }
+}
+
+/// Smoothing state for one axis
+#[derive(Debug, Clone, Default)]
+struct AxisSmoother {
+    /// Strength from 0 (none) to 1 (the position never moves)
+    strength: f64,
+    /// Smoothed position, once there has been one
+    estimate: Option<f64>,
+    /// Recent positions, for the moving average
+    window: VecDeque<f64>,
+    /// Variance of the estimate, for the Kalman filter
+    variance: f64,
+}
+
+impl AxisSmoother {
+    /// Smooth the next position on this axis
+    fn smooth(&mut self, method: SmoothingMethod, value: f64) -> f64 {
+        let smoothed = match (method, self.estimate) {
+            (SmoothingMethod::None, _) | (_, None) => {
+                self.variance = PointerSmoother::PROCESS_NOISE;
+                value
+            }
+            (SmoothingMethod::Exponential, Some(estimate)) => {
+                estimate + (1.0 - self.strength) * (value - estimate)
+            }
+            (SmoothingMethod::MovingAverage, Some(_)) => {
+                let length = 1 + (self.strength * PointerSmoother::MAX_WINDOW as f64).round() as usize;
+                while self.window.len() >= length {
+                    self.window.pop_front();
+                }
+                self.window.push_back(value);
+                return self.record(self.window.iter().sum::<f64>() / self.window.len() as f64);
+            }
+            (SmoothingMethod::Kalman, Some(estimate)) => {
+                // The pointer is expected to stay put, drifting by the process noise
+                let strength = self.strength.min(PointerSmoother::MAX_KALMAN_STRENGTH);
+                let ratio = strength / (1.0 - strength);
+                let measurement_noise = PointerSmoother::PROCESS_NOISE * ratio * ratio;
+                let predicted = self.variance + PointerSmoother::PROCESS_NOISE;
+                let gain = predicted / (predicted + measurement_noise);
+                self.variance = (1.0 - gain) * predicted;
+                estimate + gain * (value - estimate)
+            }
+        };
+
+        self.window.clear();
+        self.window.push_back(smoothed);
+        self.record(smoothed)
+    }
+
+    /// Remember a smoothed position
+    fn record(&mut self, smoothed: f64) -> f64 {
+        self.estimate = Some(smoothed);
+        smoothed
+    }
+
+    /// Forget the positions seen so far
+    fn reset(&mut self) {
+        self.estimate = None;
+        self.window.clear();
+        self.variance = 0.0;
+    }
+}
+
+/// Smooths pointer positions frame by frame
+#[derive(Debug, Clone, Default)]
+pub struct PointerSmoother {
+    /// How positions are smoothed
+    method: SmoothingMethod,
+    /// Smoothing of the X axis
+    x: AxisSmoother,
+    /// Smoothing of the Y axis
+    y: AxisSmoother,
+}
+
+impl PointerSmoother {
+    /// Number of earlier frames averaged at full strength by the moving average
+    pub const MAX_WINDOW: usize = 15;
+
+    /// Variance the Kalman filter expects the pointer to move by each frame
+    const PROCESS_NOISE: f64 = 1.0;
+
+    /// Strength above which the Kalman filter is no stronger, so it still moves
+    const MAX_KALMAN_STRENGTH: f64 = 0.99;
+
+    /// Create a smoother using `method`, with strengths from 0 (none) to 1 for each axis
+    pub fn new(method: SmoothingMethod, strength_x: f64, strength_y: f64) -> Self {
+        let mut smoother = Self::default();
+        smoother.set_method(method);
+        smoother.set_strength(strength_x, strength_y);
+        smoother
+    }
+
+    /// Get how positions are smoothed
+    pub fn method(&self) -> SmoothingMethod {
+        self.method
+    }
+
+    /// Set how positions are smoothed, starting afresh from the next position
+    pub fn set_method(&mut self, method: SmoothingMethod) {
+        self.method = method;
+        self.reset();
+    }
+
+    /// Get the strength of smoothing of each axis
+    pub fn strength(&self) -> (f64, f64) {
+        (self.x.strength, self.y.strength)
+    }
+
+    /// Set the strength of smoothing of each axis, from 0 (none) to 1
+    pub fn set_strength(&mut self, strength_x: f64, strength_y: f64) {
+        self.x.strength = strength_x.clamp(0.0, 1.0);
+        self.y.strength = strength_y.clamp(0.0, 1.0);
+    }
+
+    /// Check whether positions are changed at all
+    pub fn is_active(&self) -> bool {
+        self.method != SmoothingMethod::None && (self.x.strength > 0.0 || self.y.strength > 0.0)
+    }
+
+    /// Smooth the position for the next frame
+    pub fn smooth(&mut self, x: f64, y: f64) -> (f64, f64) {
+        (self.x.smooth(self.method, x), self.y.smooth(self.method, y))
+    }
+
+    /// Forget the positions seen so far, so the next is used as it is
+    pub fn reset(&mut self) {
+        self.x.reset();
+        self.y.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Smooth a step from a steady 0 to 100 on both axes, returning the positions after it
+    fn step_response(method: SmoothingMethod, strength_x: f64, strength_y: f64) -> Vec<(f64, f64)> {
+        let mut smoother = PointerSmoother::new(method, strength_x, strength_y);
+        for _ in 0..PointerSmoother::MAX_WINDOW {
+            smoother.smooth(0.0, 0.0);
+        }
+        (0..40).map(|_| smoother.smooth(100.0, 100.0)).collect()
+    }
+
+    #[test]
+    fn test_smoothing_lags_each_axis_by_its_strength() {
+        for method in [SmoothingMethod::Exponential, SmoothingMethod::MovingAverage, SmoothingMethod::Kalman] {
+            let response = step_response(method, 0.0, 0.5);
+            let (x, y) = response[0];
+            assert_eq!(x, 100.0, "{:?} smoothed an axis with no strength", method);
+            assert!(y > 0.0 && y < 100.0, "{:?} gave {}", method, y);
+
+            // A steady pointer is reached in the end
+            let (_, y) = response[response.len() - 1];
+            assert!((y - 100.0).abs() < 1.0, "{:?} settled at {}", method, y);
+
+            // Stronger smoothing lags further
+            let (_, stronger) = step_response(method, 0.0, 0.9)[0];
+            assert!(stronger < response[0].1, "{:?} was not slower when stronger", method);
+        }
+
+        assert_eq!(step_response(SmoothingMethod::None, 0.9, 0.9)[0], (100.0, 100.0));
+    }
+
+    #[test]
+    fn test_smoothing_restarts_after_reset() {
+        let mut smoother = PointerSmoother::new(SmoothingMethod::Exponential, 0.8, 0.8);
+        smoother.smooth(0.0, 0.0);
+        smoother.reset();
+        assert_eq!(smoother.smooth(50.0, 60.0), (50.0, 60.0));
+        assert_eq!(SmoothingMethod::from_setting(3), SmoothingMethod::Kalman);
+        assert_eq!(SmoothingMethod::from_setting(9), SmoothingMethod::None);
+    }
+}
@@ -0,0 +1,298 @@
+//! # Touch Input Module
+//!
+//! This module contains a touch screen input device for tablet and phone
+//! frontends. The first finger down steers; holding it still starts or
+//! stops Dasher, and touching with a second finger pauses or resumes.
+
+use super::device::Calibration;
+use super::{DasherInput, VirtualKey};
+use crate::view::DasherView;
+
+/// A gesture recognised by a [`TouchInput`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TouchGesture {
+    /// A finger was held still: start Dasher, or stop it if running
+    StartStop,
+    /// A second finger touched: pause Dasher, or resume it if paused
+    Pause,
+}
+
+/// Settings for recognising touch gestures
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TouchInputConfig {
+    /// How long a finger must be held still to start or stop (in milliseconds)
+    pub hold_ms: u64,
+    /// How far a held finger may wander and still count as still (in pixels)
+    pub hold_tolerance: i32,
+}
+
+impl Default for TouchInputConfig {
+    fn default() -> Self {
+        Self {
+            hold_ms: 600,
+            hold_tolerance: 20,
+        }
+    }
+}
+
+/// A finger on the screen
+#[derive(Debug, Clone, Copy)]
+struct Touch {
+    /// Identifier of the finger, as given by the platform
+    id: u64,
+    /// X coordinate in screen space
+    x: i32,
+    /// Y coordinate in screen space
+    y: i32,
+}
+
+/// A finger being held to start or stop
+#[derive(Debug, Clone, Copy)]
+struct Hold {
+    /// When the finger touched (in milliseconds)
+    start_ms: u64,
+    /// Where the finger touched
+    x: i32,
+    y: i32,
+}
+
+/// Touch screen input device
+#[derive(Debug, Clone)]
+pub struct TouchInput {
+    /// Name of the input device
+    name: String,
+
+    /// Gesture settings
+    config: TouchInputConfig,
+
+    /// Fingers on the screen, in the order they touched
+    touches: Vec<Touch>,
+
+    /// The first finger, while it may still become a hold
+    hold: Option<Hold>,
+
+    /// Whether more than one finger has touched since the screen was last clear
+    multi_touch: bool,
+
+    /// Whether the device is active
+    active: bool,
+
+    /// Correction applied to the coordinates
+    calibration: Calibration,
+}
+
+impl Default for TouchInput {
+    fn default() -> Self {
+        Self::new(TouchInputConfig::default())
+    }
+}
+
+impl TouchInput {
+    /// Create a new touch input
+    pub fn new(config: TouchInputConfig) -> Self {
+        Self {
+            name: "Touch".to_string(),
+            config,
+            touches: Vec::new(),
+            hold: None,
+            multi_touch: false,
+            active: false,
+            calibration: Calibration::default(),
+        }
+    }
+
+    /// Get the gesture settings
+    pub fn config(&self) -> &TouchInputConfig {
+        &self.config
+    }
+
+    /// Set the gesture settings
+    pub fn set_config(&mut self, config: TouchInputConfig) {
+        self.config = config;
+    }
+
+    /// Check whether any finger is on the screen
+    pub fn is_touching(&self) -> bool {
+        !self.touches.is_empty()
+    }
+
+    /// Handle a finger touching the screen
+    pub fn touch_down(&mut self, time_ms: u64, id: u64, x: i32, y: i32) -> Option<TouchGesture> {
+        self.touches.retain(|touch| touch.id != id);
+        self.touches.push(Touch { id, x, y });
+
+        match self.touches.len() {
+            1 if !self.multi_touch => {
+                self.hold = Some(Hold { start_ms: time_ms, x, y });
+                None
+            }
+            2 if !self.multi_touch => {
+                // Steering stops until every finger is lifted
+                self.multi_touch = true;
+                self.hold = None;
+                Some(TouchGesture::Pause)
+            }
+            _ => None,
+        }
+    }
+
+    /// Handle a finger moving on the screen
+    pub fn touch_move(&mut self, _time_ms: u64, id: u64, x: i32, y: i32) {
+        let Some(touch) = self.touches.iter_mut().find(|touch| touch.id == id) else {
+            return;
+        };
+        touch.x = x;
+        touch.y = y;
+
+        // Moving the first finger away means it is steering, not holding
+        if let Some(hold) = self.hold {
+            let tolerance = self.config.hold_tolerance;
+            if self.touches[0].id == id && ((x - hold.x).abs() > tolerance || (y - hold.y).abs() > tolerance) {
+                self.hold = None;
+            }
+        }
+    }
+
+    /// Handle a finger leaving the screen
+    pub fn touch_up(&mut self, _time_ms: u64, id: u64) {
+        let Some(index) = self.touches.iter().position(|touch| touch.id == id) else {
+            return;
+        };
+        self.touches.remove(index);
+        if index == 0 {
+            self.hold = None;
+        }
+        if self.touches.is_empty() {
+            self.multi_touch = false;
+        }
+    }
+
+    /// Check for a finger held long enough to start or stop
+    ///
+    /// Call this every frame; a hold is reported once, when it is reached.
+    pub fn update(&mut self, time_ms: u64) -> Option<TouchGesture> {
+        let hold = self.hold?;
+        if time_ms.saturating_sub(hold.start_ms) < self.config.hold_ms {
+            return None;
+        }
+        self.hold = None;
+        Some(TouchGesture::StartStop)
+    }
+
+    /// Get the position of the steering finger, if one is steering
+    fn steering_position(&self) -> Option<(i32, i32)> {
+        if !self.active || self.multi_touch {
+            return None;
+        }
+        self.touches.first().map(|touch| (touch.x, touch.y))
+    }
+}
+
+impl DasherInput for TouchInput {
+    fn get_dasher_coordinates(&mut self, view: &dyn DasherView) -> Option<(i64, i64)> {
+        let (x, y) = self.get_screen_coordinates(view)?;
+        Some(view.screen_to_dasher(x, y))
+    }
+
+    fn get_screen_coordinates(&self, view: &dyn DasherView) -> Option<(i32, i32)> {
+        let position = self.steering_position()?;
+        Some(self.calibration.apply(position, view.get_dimensions()))
+    }
+
+    fn set_screen_position(&mut self, x: i32, y: i32) {
+        // Move the steering finger, or put one down if there is none
+        match self.touches.first_mut() {
+            Some(touch) => {
+                touch.x = x;
+                touch.y = y;
+            }
+            None => self.touches.push(Touch { id: 0, x, y }),
+        }
+    }
+
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn supports_pause(&self) -> bool {
+        true
+    }
+
+    fn activate(&mut self) {
+        self.active = true;
+    }
+
+    fn deactivate(&mut self) {
+        self.active = false;
+        self.touches.clear();
+        self.hold = None;
+        self.multi_touch = false;
+    }
+
+    fn key_down(&mut self, _time: u64, _key: VirtualKey) {
+        // Nothing to do
+    }
+
+    fn key_up(&mut self, _time: u64, _key: VirtualKey) {
+        // Nothing to do
+    }
+
+    fn box_clone(&self) -> Box<dyn DasherInput> {
+        Box::new(self.clone())
+    }
+
+    fn calibration(&self) -> Calibration {
+        self.calibration
+    }
+
+    fn set_calibration(&mut self, calibration: Calibration) {
+        self.calibration = calibration;
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_holding_still_starts_and_stops() {
+        let mut touch = TouchInput::default();
+        assert_eq!(touch.touch_down(1000, 7, 100, 100), None);
+        touch.touch_move(1100, 7, 110, 95);
+        assert_eq!(touch.update(1500), None);
+        assert_eq!(touch.update(1600), Some(TouchGesture::StartStop));
+
+        // The hold is reported once
+        assert_eq!(touch.update(2000), None);
+        touch.touch_up(2100, 7);
+
+        // Dragging steers rather than holding
+        touch.touch_down(3000, 8, 100, 100);
+        touch.touch_move(3100, 8, 200, 100);
+        assert_eq!(touch.update(4000), None);
+    }
+
+    #[test]
+    fn test_second_finger_pauses_and_stops_steering() {
+        let mut touch = TouchInput::default();
+        touch.activate();
+        touch.touch_down(0, 1, 100, 200);
+        assert_eq!(touch.steering_position(), Some((100, 200)));
+
+        assert_eq!(touch.touch_down(50, 2, 300, 200), Some(TouchGesture::Pause));
+        assert_eq!(touch.steering_position(), None);
+        assert_eq!(touch.update(1000), None);
+
+        // Nothing steers until both fingers are lifted
+        touch.touch_up(100, 2);
+        assert_eq!(touch.steering_position(), None);
+        touch.touch_up(150, 1);
+        assert!(!touch.is_touching());
+        touch.touch_down(200, 3, 50, 60);
+        assert_eq!(touch.steering_position(), Some((50, 60)));
+    }
+}
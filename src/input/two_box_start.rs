@@ -0,0 +1,189 @@
+//! # Two-Box Start Module
+//!
+//! This module contains the two-box start mode, for users who cannot press
+//! a button to start Dasher. While Dasher is stopped two boxes are shown,
+//! one above and one below the centre of the screen; holding the pointer in
+//! the first box and then in the second starts Dasher.
+
+use crate::view::DasherView;
+
+/// Two-box start configuration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TwoBoxStartConfig {
+    /// Distance of each box from the centre of the screen, as a fraction of its height
+    pub distance: f64,
+    /// Height of each box (in pixels)
+    pub box_height: i32,
+    /// How long the pointer must stay in each box (in milliseconds)
+    pub hold_ms: u64,
+    /// How long the user has to reach the second box after the first (in milliseconds)
+    pub timeout_ms: u64,
+}
+
+impl Default for TwoBoxStartConfig {
+    fn default() -> Self {
+        Self {
+            distance: 0.25,
+            box_height: 40,
+            hold_ms: 500,
+            timeout_ms: 3000,
+        }
+    }
+}
+
+/// Progress through the two boxes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TwoBoxState {
+    /// Waiting for the pointer to hold in the first box
+    First {
+        /// When the pointer entered the box
+        entered: Option<u64>,
+    },
+    /// Waiting for the pointer to hold in the second box
+    Second {
+        /// When the first box was completed
+        since: u64,
+        /// When the pointer entered the box
+        entered: Option<u64>,
+    },
+}
+
+/// Two-box start handler
+#[derive(Debug, Clone)]
+pub struct TwoBoxStartHandler {
+    /// Configuration
+    config: TwoBoxStartConfig,
+    /// Current state
+    state: TwoBoxState,
+}
+
+impl TwoBoxStartHandler {
+    /// Create a new two-box start handler
+    pub fn new(config: TwoBoxStartConfig) -> Self {
+        Self {
+            config,
+            state: TwoBoxState::First { entered: None },
+        }
+    }
+
+    /// Get the configuration
+    pub fn config(&self) -> &TwoBoxStartConfig {
+        &self.config
+    }
+
+    /// Get the index (0 or 1) of the box the pointer should go to next
+    pub fn target_box(&self) -> usize {
+        match self.state {
+            TwoBoxState::First { .. } => 0,
+            TwoBoxState::Second { .. } => 1,
+        }
+    }
+
+    /// Get the top and bottom of each box on a screen `height` pixels high
+    pub fn boxes(&self, height: i32) -> [(i32, i32); 2] {
+        let centre = height / 2;
+        let distance = (height as f64 * self.config.distance).round() as i32;
+        let half = self.config.box_height / 2;
+        [
+            (centre - distance - half, centre - distance + half),
+            (centre + distance - half, centre + distance + half),
+        ]
+    }
+
+    /// Follow the pointer, returning whether it has now held in both boxes
+    ///
+    /// Call this each frame while stopped.
+    pub fn update(&mut self, time_ms: u64, position: Option<(i32, i32)>, height: i32) -> bool {
+        let boxes = self.boxes(height);
+        let in_box = |index: usize| position.is_some_and(|(_, y)| (boxes[index].0..=boxes[index].1).contains(&y));
+
+        match self.state {
+            TwoBoxState::First { entered } => {
+                if !in_box(0) {
+                    self.state = TwoBoxState::First { entered: None };
+                } else if let Some(entered) = entered {
+                    if time_ms.saturating_sub(entered) >= self.config.hold_ms {
+                        self.state = TwoBoxState::Second { since: time_ms, entered: None };
+                    }
+                } else {
+                    self.state = TwoBoxState::First { entered: Some(time_ms) };
+                }
+            }
+            TwoBoxState::Second { since, entered } => {
+                if !in_box(1) {
+                    self.state = if time_ms.saturating_sub(since) > self.config.timeout_ms {
+                        TwoBoxState::First { entered: None }
+                    } else {
+                        TwoBoxState::Second { since, entered: None }
+                    };
+                } else if let Some(entered) = entered {
+                    if time_ms.saturating_sub(entered) >= self.config.hold_ms {
+                        self.reset();
+                        return true;
+                    }
+                } else {
+                    self.state = TwoBoxState::Second { since, entered: Some(time_ms) };
+                }
+            }
+        }
+        false
+    }
+
+    /// Start again from the first box
+    pub fn reset(&mut self) {
+        self.state = TwoBoxState::First { entered: None };
+    }
+
+    /// Draw the boxes, highlighting the one to go to next
+    pub fn decorate_view(&self, view: &mut dyn DasherView) -> bool {
+        let (width, height) = view.get_dimensions();
+        for (index, (top, bottom)) in self.boxes(height).into_iter().enumerate() {
+            let (x1, y1) = view.screen_to_dasher(0, top);
+            let (x2, y2) = view.screen_to_dasher(width, bottom);
+            let (fill_color, line_width) = if index == self.target_box() {
+                ((0, 160, 0, 96), 3)
+            } else {
+                ((0, 0, 0, 0), 1)
+            };
+            view.draw_rectangle(x1, y1, x2, y2, fill_color, (0, 120, 0, 255), line_width);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_holding_in_both_boxes_starts() {
+        let mut handler = TwoBoxStartHandler::new(TwoBoxStartConfig::default());
+        assert_eq!(handler.boxes(600), [(130, 170), (430, 470)]);
+
+        // The second box does nothing first
+        assert!(!handler.update(0, Some((400, 450)), 600));
+        assert!(!handler.update(1000, Some((400, 450)), 600));
+        assert_eq!(handler.target_box(), 0);
+
+        assert!(!handler.update(1100, Some((400, 150)), 600));
+        assert!(!handler.update(1600, Some((300, 160)), 600));
+        assert_eq!(handler.target_box(), 1);
+        assert!(!handler.update(2000, Some((300, 460)), 600));
+        assert!(handler.update(2500, Some((300, 460)), 600));
+        assert_eq!(handler.target_box(), 0);
+    }
+
+    #[test]
+    fn test_second_box_times_out() {
+        let mut handler = TwoBoxStartHandler::new(TwoBoxStartConfig::default());
+        handler.update(0, Some((0, 150)), 600);
+        handler.update(500, Some((0, 150)), 600);
+        assert_eq!(handler.target_box(), 1);
+
+        // Not reaching the second box in time starts again
+        assert!(!handler.update(2000, Some((0, 300)), 600));
+        assert_eq!(handler.target_box(), 1);
+        assert!(!handler.update(3600, None, 600));
+        assert_eq!(handler.target_box(), 0);
+    }
+}
@@ -13,11 +13,12 @@ pub use self::view::DasherScreen;
 pub use self::input::DasherInput;
 pub use self::settings::{Parameter, Settings};
 pub use self::alphabet::{Alphabet, Symbol};
-pub use self::logging::{Logger, FileLogger};
+pub use self::logging::{Logger, FileLogger, LogLevel, LogSink, set_log_sink, clear_log_sink, set_target_level};
+pub use self::stats::UserStats;
 
 // Re-export WASM bindings
 #[cfg(feature = "wasm")]
-pub use self::wasm_api_simple::{init_dasher, set_canvas, new_frame, start, stop, pause, resume, reset, backspace, get_output_text, set_node_shape, set_x_nonlinear, set_y_nonlinear, set_text_3d, set_flowing_interface, set_flowing_speed, set_ppm, set_draw_crosshair, set_draw_cursor, set_draw_outlines};
+pub use self::wasm_api_simple::{init_dasher, set_canvas, new_frame, start, stop, pause, resume, reset, backspace, get_output_text, set_node_shape, set_x_nonlinear, set_y_nonlinear, set_text_3d, set_flowing_interface, set_flowing_speed, set_ppm, set_draw_crosshair, set_draw_cursor, set_draw_outlines, set_speech_callback, speak, set_conversion_callback, set_clipboard_callback, set_announcement_callback, execute_action, touch_down, touch_move, touch_up, set_record_corpus, record_sentence, take_corpus_text, set_console_log_level};
 
 // Define modules
 pub mod api;
@@ -28,6 +29,9 @@ pub mod settings;
 pub mod alphabet;
 pub mod wordgen;
 pub mod action;
+pub mod stats;
+pub mod simulator;
+pub mod evaluate;
 mod logging;
 
 // FFI and WebAssembly support
@@ -59,32 +63,43 @@ pub mod wasm_bindings {
     use super::*;
     use wasm_bindgen::prelude::*;
     use wasm_bindgen::JsValue;
-    use web_sys::console;
+    use crate::logging::dasher_log;
     use serde_wasm_bindgen;
     use view::NodeShape;
 
+    /// Get the options for the next symbol, most probable first
+    ///
+    /// Only the `top_k` most probable options are returned, or all of them if
+    /// it is missing or 0, and options less probable than `min_probability`
+    /// are left out. The options follow `context` if it is given, and the
+    /// output text otherwise.
     #[wasm_bindgen]
-    pub fn dasher_get_options() -> JsValue {
+    pub fn dasher_get_options(top_k: Option<u32>, min_probability: Option<f64>, context: Option<String>) -> JsValue {
         MODEL.with(|model| {
-            let model = model.borrow();
+            let mut model = model.borrow_mut();
             let has_language_model = model.language_model().is_some();
-            let context = model.output_text();
+            let probabilities = model.ranked_probabilities(
+                top_k.unwrap_or(0) as usize,
+                min_probability.unwrap_or(0.0),
+                context.as_deref(),
+            );
+            let context = context.as_deref().unwrap_or(model.output_text());
             let alphabet = model.alphabet();
             if let Some(alphabet) = alphabet {
-                console::log_1(&JsValue::from_str(&format!("[WASM] dasher_get_options: alphabet loaded, {} symbols", alphabet.size())));
+                dasher_log!(Debug, "dasher_get_options: alphabet loaded, {} symbols", alphabet.size());
             } else {
-                console::log_1(&JsValue::from_str("[WASM] dasher_get_options: alphabet is None"));
+                dasher_log!(Debug, "dasher_get_options: alphabet is None");
             }
-            console::log_1(&JsValue::from_str(&format!("[WASM] dasher_get_options: language_model present? {} | context: '{}'", has_language_model, context)));
-            if let Some(prob_vec) = model.get_probabilities() {
-                console::log_1(&JsValue::from_str(&format!("[WASM] dasher_get_options: got {} probabilities", prob_vec.len())));
+            dasher_log!(Debug, "dasher_get_options: language_model present? {} | context: '{}'", has_language_model, context);
+            if let Some(prob_vec) = probabilities {
+                dasher_log!(Debug, "dasher_get_options: got {} probabilities", prob_vec.len());
                 let options: Vec<OptionBox> = prob_vec.iter().map(|(c, p)| OptionBox {
                     symbol: c.to_string(),
                     prob: *p as f32,
                 }).collect();
                 serde_wasm_bindgen::to_value(&options).unwrap()
             } else {
-                console::log_1(&JsValue::from_str("[WASM] dasher_get_options: language_model is None"));
+                dasher_log!(Debug, "dasher_get_options: language_model is None");
                 JsValue::NULL
             }
         })
@@ -96,7 +111,6 @@ pub mod wasm_bindings {
             let mut model = model.borrow_mut();
             if let Some(ch) = symbol.chars().next() {
                 model.append_to_output(ch);
-                model.update_language_model(ch);
             }
         });
     }
@@ -120,16 +134,41 @@ pub mod wasm_bindings {
 
     #[wasm_bindgen]
     pub fn dasher_train(text: &str) -> bool {
-        console::log_1(&JsValue::from_str(&format!("[WASM] dasher_train: training with text of length {}", text.len())));
+        dasher_log!(Debug, "dasher_train: training with text of length {}", text.len());
+
+        MODEL.with(|model| {
+            let mut model = model.borrow_mut();
+            // Train the language model with the text
+            let trained = model.train_language_model(std::io::Cursor::new(text), |_| true).is_ok();
+            dasher_log!(Debug, "dasher_train: training complete");
+            trained
+        })
+    }
 
+    /// Train the language model, calling `progress` with the percentage done
+    ///
+    /// Returning `false` from `progress` cancels training. Returns the
+    /// number of characters trained on, or -1 on error or cancellation.
+    ///
+    /// The model is borrowed for the whole of training, so `progress` must
+    /// not call back into these bindings; schedule such work for later
+    /// (with `setTimeout`, say) instead.
+    #[wasm_bindgen]
+    pub fn dasher_train_with_progress(text: &str, progress: &js_sys::Function) -> f64 {
         MODEL.with(|model| {
             let mut model = model.borrow_mut();
-            // Train the language model with each character in the text
-            for ch in text.chars() {
-                model.update_language_model(ch);
+            let result = model.train_language_model(std::io::Cursor::new(text), |percent| {
+                progress.call1(&JsValue::NULL, &JsValue::from(percent))
+                    .map(|value| value.as_bool() != Some(false))
+                    .unwrap_or(false)
+            });
+            match result {
+                Ok(stats) => stats.total_chars as f64,
+                Err(e) => {
+                    dasher_log!(Warn, "dasher_train_with_progress: {:?}", e);
+                    -1.0
+                }
             }
-            console::log_1(&JsValue::from_str("[WASM] dasher_train: training complete"));
-            true
         })
     }
 }
@@ -137,6 +176,40 @@ pub mod wasm_bindings {
 
 pub mod ffi;
 
+/// Version of the library
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Get the optional features and capabilities of this build
+///
+/// `ppm` (the PPM language model), `arpa` (n-gram models loaded from ARPA
+/// files) and `conversion` (alphabets converting what is shown into what is
+/// written) are always present; the rest depend on the Cargo features the
+/// library was built with.
+pub fn features() -> Vec<&'static str> {
+    let mut features = vec!["ppm", "arpa", "conversion"];
+    if cfg!(feature = "wasm") {
+        features.push("wasm");
+    }
+    if cfg!(feature = "svg") {
+        features.push("svg");
+    }
+    if cfg!(feature = "raster") {
+        features.push("raster");
+    }
+    if cfg!(feature = "tracing") {
+        features.push("tracing");
+    }
+    if cfg!(feature = "candle") {
+        features.push("candle");
+    }
+    features
+}
+
+/// Check whether this build has an optional feature or capability
+pub fn has_feature(name: &str) -> bool {
+    features().contains(&name)
+}
+
 // Error handling
 use thiserror::Error;
 
@@ -171,6 +244,14 @@ pub type Result<T> = std::result::Result<T, DasherError>;
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_features() {
+        assert!(!VERSION.is_empty());
+        assert!(has_feature("ppm"));
+        assert_eq!(has_feature("svg"), cfg!(feature = "svg"));
+        assert!(!has_feature("teleportation"));
+    }
+
     #[test]
     fn it_works() {
         // Basic test to ensure the library compiles
@@ -1,11 +1,155 @@
 //! # Logging Module
 //!
-//! Provides a trait and implementation for user event logging and statistics.
+//! Provides a trait and implementation for user event logging and statistics,
+//! as well as the sink through which core diagnostics are reported.
 
 use std::fs::{OpenOptions};
 use std::io::Write;
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Severity of a diagnostic message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    /// Unrecoverable or unexpected failures
+    Error = 0,
+    /// Recoverable problems
+    Warn = 1,
+    /// High-level progress information
+    Info = 2,
+    /// Detailed diagnostic information
+    Debug = 3,
+}
+
+impl LogLevel {
+    /// Convert a raw integer (as passed across the FFI) to a log level
+    pub fn from_i32(level: i32) -> Option<Self> {
+        match level {
+            0 => Some(LogLevel::Error),
+            1 => Some(LogLevel::Warn),
+            2 => Some(LogLevel::Info),
+            3 => Some(LogLevel::Debug),
+            _ => None,
+        }
+    }
+}
+
+impl From<LogLevel> for log::Level {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Error => log::Level::Error,
+            LogLevel::Warn => log::Level::Warn,
+            LogLevel::Info => log::Level::Info,
+            LogLevel::Debug => log::Level::Debug,
+        }
+    }
+}
+
+/// Function receiving diagnostic messages, with the target they come from
+pub type LogSink = Arc<dyn Fn(LogLevel, &str, &str) + Send + Sync>;
+
+/// The registered sink together with the most verbose level it accepts
+static LOG_SINK: Mutex<Option<(LogLevel, LogSink)>> = Mutex::new(None);
+
+/// Most verbose level accepted from each target given its own level
+static TARGET_LEVELS: Mutex<Vec<(String, LogLevel)>> = Mutex::new(Vec::new());
+
+/// Lock the registered sink
+///
+/// Sinks are only ever called with the lock released, so it cannot be
+/// poisoned by them; a poisoned lock is used as it is all the same.
+fn lock_sink() -> MutexGuard<'static, Option<(LogLevel, LogSink)>> {
+    LOG_SINK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Report a diagnostic message from the current module
+///
+/// The module path is the message's target, for example
+/// `dasher_core::model`. The message is only formatted if it would be
+/// delivered.
+macro_rules! dasher_log {
+    ($level:ident, $($arg:tt)*) => {
+        if $crate::logging::log_enabled(module_path!(), $crate::logging::LogLevel::$level) {
+            $crate::logging::log_message(module_path!(), $crate::logging::LogLevel::$level, &format!($($arg)*));
+        }
+    };
+}
+
+pub(crate) use dasher_log;
+
+/// Register a sink for diagnostic messages.
+///
+/// Messages at `max_level` or more severe are passed to `sink` with their
+/// target; anything more verbose is discarded, unless the target has its own
+/// level set with [`set_target_level`]. Replaces any previously registered sink.
+pub fn set_log_sink<F>(max_level: LogLevel, sink: F)
+where
+    F: Fn(LogLevel, &str, &str) + Send + Sync + 'static,
+{
+    *lock_sink() = Some((max_level, Arc::new(sink)));
+}
+
+/// Remove the registered sink, falling back to the `log` crate facade
+pub fn clear_log_sink() {
+    *lock_sink() = None;
+}
+
+/// Set the most verbose level accepted from `target` and the targets within it
+///
+/// Targets are module paths, so `dasher_core::model` also covers
+/// `dasher_core::model::language`. The most specific target set wins.
+/// Passing `None` removes the target's own level.
+pub fn set_target_level(target: &str, level: Option<LogLevel>) {
+    if let Ok(mut levels) = TARGET_LEVELS.lock() {
+        levels.retain(|(t, _)| t != target);
+        if let Some(level) = level {
+            levels.push((target.to_string(), level));
+        }
+    }
+}
+
+/// Get the level given to `target` or the most specific target containing it
+fn target_level(target: &str) -> Option<LogLevel> {
+    let levels = TARGET_LEVELS.lock().ok()?;
+    levels
+        .iter()
+        .filter(|(t, _)| target == t || target.strip_prefix(t.as_str()).is_some_and(|rest| rest.starts_with("::")))
+        .max_by_key(|(t, _)| t.len())
+        .map(|(_, level)| *level)
+}
+
+/// Check whether a message from `target` at the given level would be delivered anywhere
+pub fn log_enabled(target: &str, level: LogLevel) -> bool {
+    let own_level = target_level(target);
+    if own_level.is_some_and(|own_level| level > own_level) {
+        return false;
+    }
+    match lock_sink().as_ref() {
+        Some((max_level, _)) => level <= own_level.unwrap_or(*max_level),
+        None => log::log_enabled!(target: target, level.into()),
+    }
+}
+
+/// Report a diagnostic message from `target`.
+///
+/// If a sink has been registered with [`set_log_sink`] the message goes there,
+/// otherwise it is forwarded to the `log` crate so Rust hosts can pick it up
+/// with their logger of choice. Within the crate, use the `dasher_log!`
+/// macro, which takes the target from the module path.
+///
+/// The sink is called without holding any lock, so it may itself log or
+/// call back into the library.
+pub fn log_message(target: &str, level: LogLevel, message: &str) {
+    if !log_enabled(target, level) {
+        return;
+    }
+    let sink = lock_sink().as_ref().map(|(_, sink)| sink.clone());
+    match sink {
+        Some(sink) => sink(level, target, message),
+        None => log::log!(target: target, level.into(), "{}", message),
+    }
+}
+
 /// Logger trait for logging user events and statistics
 pub trait Logger {
     /// Log a generic event string
@@ -58,6 +202,9 @@ mod tests {
     use super::*;
     use std::fs;
 
+    /// Held by tests that register a sink, as there is only one
+    static SINK_TESTS: Mutex<()> = Mutex::new(());
+
     #[test]
     fn test_file_logger_writes_log() {
         let path = "test_dasher.log";
@@ -71,4 +218,71 @@ mod tests {
         assert!(contents.contains("STAT: chars_entered = 42"));
         let _ = fs::remove_file(path);
     }
+
+    #[test]
+    fn test_log_sink_filters_by_level() {
+        let _sink_test = SINK_TESTS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink_received = received.clone();
+        set_log_sink(LogLevel::Info, move |level, target, message| {
+            sink_received.lock().unwrap().push((level, target.to_string(), message.to_string()));
+        });
+        set_target_level("test_target::chatty", Some(LogLevel::Debug));
+        set_target_level("test_target::quiet", Some(LogLevel::Error));
+
+        assert!(log_enabled("test_target", LogLevel::Warn));
+        assert!(!log_enabled("test_target", LogLevel::Debug));
+        assert!(log_enabled("test_target::chatty::inner", LogLevel::Debug));
+        assert!(!log_enabled("test_target::chattyish", LogLevel::Debug));
+        assert!(!log_enabled("test_target::quiet", LogLevel::Warn));
+        log_message("test_target", LogLevel::Error, "broken");
+        log_message("test_target", LogLevel::Debug, "too chatty");
+        log_message("test_target::chatty", LogLevel::Debug, "wanted detail");
+        log_message("test_target::quiet", LogLevel::Warn, "silenced");
+        set_target_level("test_target::chatty", None);
+        set_target_level("test_target::quiet", None);
+        clear_log_sink();
+        log_message("test_target", LogLevel::Error, "after clear");
+
+        // Other tests may log concurrently, so only look for our own messages
+        let received = received.lock().unwrap();
+        let messages: Vec<_> = received
+            .iter()
+            .filter(|(_, target, _)| target.starts_with("test_target"))
+            .map(|(level, target, message)| (*level, target.as_str(), message.as_str()))
+            .collect();
+        assert_eq!(messages, vec![
+            (LogLevel::Error, "test_target", "broken"),
+            (LogLevel::Debug, "test_target::chatty", "wanted detail"),
+        ]);
+    }
+
+    #[test]
+    fn test_log_sink_may_log_from_inside_itself() {
+        let _sink_test = SINK_TESTS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink_received = received.clone();
+        set_log_sink(LogLevel::Info, move |_, target, message| {
+            if target != "reentrant_target" {
+                return;
+            }
+            sink_received.lock().unwrap().push(message.to_string());
+            if message == "outer" {
+                log_message(target, LogLevel::Info, "inner");
+            }
+        });
+        log_message("reentrant_target", LogLevel::Info, "outer");
+
+        // A panicking sink leaves logging working
+        set_log_sink(LogLevel::Info, |_, target, _| {
+            if target == "reentrant_target" {
+                panic!("sink failed");
+            }
+        });
+        assert!(std::panic::catch_unwind(|| log_message("reentrant_target", LogLevel::Info, "boom")).is_err());
+        assert!(log_enabled("reentrant_target", LogLevel::Info));
+        clear_log_sink();
+
+        assert_eq!(*received.lock().unwrap(), ["outer", "inner"]);
+    }
 }
@@ -0,0 +1,135 @@
+//! # Corpus Module
+//!
+//! This module records the sentences the user writes as a training corpus,
+//! so that they can later train the language model on another device.
+//! Recording is off until the user opts in.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Sentences the user has written, kept as training text
+///
+/// Each sentence is written on its own line, after its timestamp (in
+/// seconds since the Unix epoch) as a context escape, for example
+/// `§1760000000 §Hello there.`. Training on the corpus thus learns the
+/// sentences but not the timestamps.
+///
+/// Sentences are appended to the corpus file if it has one, and otherwise
+/// kept until taken with [`Self::take_text`], for hosts without a file system.
+#[derive(Debug, Clone, Default)]
+pub struct UserCorpus {
+    /// Whether sentences are recorded
+    enabled: bool,
+    /// File sentences are appended to
+    path: Option<PathBuf>,
+    /// Sentences recorded without a file, not yet taken
+    text: String,
+}
+
+impl UserCorpus {
+    /// Name of the corpus file in the user's Dasher configuration directory
+    pub const FILE_NAME: &'static str = "corpus.txt";
+
+    /// Context escape character of training text
+    pub const CONTEXT_ESCAPE: char = '§';
+
+    /// Create a corpus that records nothing until enabled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the path of the corpus in the user's Dasher configuration directory
+    pub fn user_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|config_dir| config_dir.join("dasher").join(Self::FILE_NAME))
+    }
+
+    /// Check whether sentences are recorded
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Set whether sentences are recorded
+    ///
+    /// Sentences recorded without a file and not yet taken are dropped
+    /// when recording is turned off.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.text.clear();
+        }
+    }
+
+    /// Get the file sentences are appended to
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Set the file sentences are appended to
+    pub fn set_path(&mut self, path: Option<PathBuf>) {
+        self.path = path;
+    }
+
+    /// Record `sentence` as written now, if recording is enabled
+    pub fn record(&mut self, sentence: &str) -> io::Result<()> {
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        self.record_at(sentence, time)
+    }
+
+    /// Record `sentence` as written at `time`, in seconds since the Unix
+    /// epoch, if recording is enabled
+    pub fn record_at(&mut self, sentence: &str, time: u64) -> io::Result<()> {
+        let sentence = sentence.trim();
+        if !self.enabled || sentence.is_empty() {
+            return Ok(());
+        }
+
+        let escape = Self::CONTEXT_ESCAPE;
+        let escaped = sentence.replace(escape, &format!("{escape}{escape}"));
+        let entry = format!("{escape}{time} {escape}{escaped}\n");
+        match &self.path {
+            Some(path) => {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                OpenOptions::new().create(true).append(true).open(path)?.write_all(entry.as_bytes())
+            }
+            None => {
+                self.text.push_str(&entry);
+                Ok(())
+            }
+        }
+    }
+
+    /// Take the sentences recorded without a file
+    pub fn take_text(&mut self) -> String {
+        std::mem::take(&mut self.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_corpus_records_only_when_enabled() {
+        let mut corpus = UserCorpus::new();
+        corpus.record_at("Not recorded.", 1).unwrap();
+        assert_eq!(corpus.take_text(), "");
+
+        corpus.set_enabled(true);
+        corpus.record_at(" Costs 5§. ", 1760000000).unwrap();
+        corpus.record_at("", 1760000001).unwrap();
+        assert_eq!(corpus.take_text(), "§1760000000 §Costs 5§§.\n");
+        assert_eq!(corpus.take_text(), "");
+
+        let path = std::env::temp_dir().join(format!("dasher_corpus_{}.txt", std::process::id()));
+        let _ = fs::remove_file(&path);
+        corpus.set_path(Some(path.clone()));
+        corpus.record_at("One.", 1).unwrap();
+        corpus.record_at("Two.", 2).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "§1 §One.\n§2 §Two.\n");
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,129 @@
+//! # Diacritics Module
+//!
+//! This module composes accents with base letters into precomposed
+//! characters, so that an alphabet can offer a few accents rather than
+//! every accented letter. An accent is written either as a combining mark
+//! after its letter (e, U+0301 gives é) or as a dead key before it
+//! (´, e gives é). Each composition can be undone, so erasing the
+//! second symbol leaves the first.
+
+/// Accents, as their combining mark, their dead key, the letters they
+/// compose with and the composed letters, in the same order
+const ACCENTS: &[(char, char, &str, &str)] = &[
+    ('\u{0300}', '\u{02CB}', "AEIOUaeiou", "ÀÈÌÒÙàèìòù"),
+    ('\u{0301}', '\u{00B4}', "AEIOUYaeiouyCcNnSsZzLlRr", "ÁÉÍÓÚÝáéíóúýĆćŃńŚśŹźĹĺŔŕ"),
+    ('\u{0302}', '\u{02C6}', "AEIOUaeiouCcGgHhJjSsWwYy", "ÂÊÎÔÛâêîôûĈĉĜĝĤĥĴĵŜŝŴŵŶŷ"),
+    ('\u{0303}', '\u{02DC}', "ANOanoIiUu", "ÃÑÕãñõĨĩŨũ"),
+    ('\u{0304}', '\u{00AF}', "AEIOUaeiou", "ĀĒĪŌŪāēīōū"),
+    ('\u{0306}', '\u{02D8}', "AEGIOUaegiou", "ĂĔĞĬŎŬăĕğĭŏŭ"),
+    ('\u{0307}', '\u{02D9}', "CEGIZcegz", "ĊĖĠİŻċėġż"),
+    ('\u{0308}', '\u{00A8}', "AEIOUYaeiouy", "ÄËÏÖÜŸäëïöüÿ"),
+    ('\u{030A}', '\u{02DA}', "AUau", "ÅŮåů"),
+    ('\u{030B}', '\u{02DD}', "OUou", "ŐŰőű"),
+    ('\u{030C}', '\u{02C7}', "CDENRSTZcdenrstz", "ČĎĚŇŘŠŤŽčďěňřšťž"),
+    ('\u{0327}', '\u{00B8}', "CKLNRSTcklnrst", "ÇĶĻŅŖŞŢçķļņŗşţ"),
+    ('\u{0328}', '\u{02DB}', "AEIUaeiu", "ĄĘĮŲąęįų"),
+];
+
+/// Find the accent with combining mark or dead key `c`
+fn accent(c: char) -> Option<&'static (char, char, &'static str, &'static str)> {
+    ACCENTS.iter().find(|(mark, dead_key, _, _)| *mark == c || *dead_key == c)
+}
+
+/// Check whether `c` is a combining mark this module composes
+pub fn is_combining_mark(c: char) -> bool {
+    ACCENTS.iter().any(|(mark, _, _, _)| *mark == c)
+}
+
+/// Check whether `c` is a dead key this module composes
+pub fn is_dead_key(c: char) -> bool {
+    ACCENTS.iter().any(|(_, dead_key, _, _)| *dead_key == c)
+}
+
+/// Compose `letter` with the accent whose combining mark or dead key is `accent_char`
+fn compose_letter(letter: char, accent_char: char) -> Option<char> {
+    let (_, _, letters, composed) = accent(accent_char)?;
+    let index = letters.chars().position(|c| c == letter)?;
+    composed.chars().nth(index)
+}
+
+/// Combine `previous` with `next` written after it, if they compose
+///
+/// A letter composes with a combining mark after it, and a dead key with
+/// a letter after it.
+pub fn compose(previous: char, next: char) -> Option<char> {
+    if is_combining_mark(next) {
+        compose_letter(previous, next)
+    } else if is_dead_key(previous) {
+        compose_letter(next, previous)
+    } else {
+        None
+    }
+}
+
+/// Get the letter and combining mark a composed letter is made of, if `c` is one
+pub fn decompose(c: char) -> Option<(char, char)> {
+    ACCENTS.iter().find_map(|(mark, _, letters, composed)| {
+        let index = composed.chars().position(|composed| composed == c)?;
+        letters.chars().nth(index).map(|letter| (letter, *mark))
+    })
+}
+
+/// Get the two symbols the composed letter `c` is written as, in order
+///
+/// Its letter and combining mark are preferred if `symbols` holds the
+/// mark, and otherwise its dead key and letter if `symbols` holds the key.
+pub fn spelling(c: char, symbols: &[char]) -> Option<(char, char)> {
+    let (letter, mark) = decompose(c)?;
+    let (_, dead_key, _, _) = accent(mark)?;
+    if symbols.contains(&mark) {
+        Some((letter, mark))
+    } else if symbols.contains(dead_key) {
+        Some((*dead_key, letter))
+    } else {
+        None
+    }
+}
+
+/// Take `last` back off the composed letter `c`, the reverse of [`compose`]
+///
+/// Returns what was written before `last`, or `None` if `c` cannot have
+/// been written ending with it.
+pub fn split_off(c: char, last: char) -> Option<char> {
+    let (letter, mark) = decompose(c)?;
+    if last == mark {
+        Some(letter)
+    } else if last == letter {
+        accent(mark).map(|(_, dead_key, _, _)| *dead_key)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accents_compose_and_split() {
+        for (mark, _, letters, composed) in ACCENTS {
+            assert_eq!(letters.chars().count(), composed.chars().count(), "accent {:?}", mark);
+        }
+
+        // A combining mark follows its letter, a dead key comes before it
+        assert_eq!(compose('e', '\u{0301}'), Some('é'));
+        assert_eq!(compose('\u{00B4}', 'e'), Some('é'));
+        assert_eq!(compose('\u{00B8}', 'c'), Some('ç'));
+        assert_eq!(compose('x', '\u{0301}'), None);
+        assert_eq!(compose('e', 'e'), None);
+
+        assert_eq!(decompose('ž'), Some(('z', '\u{030C}')));
+        assert_eq!(decompose('e'), None);
+        assert_eq!(spelling('é', &['e', '\u{0301}']), Some(('e', '\u{0301}')));
+        assert_eq!(spelling('é', &['e', '\u{00B4}']), Some(('\u{00B4}', 'e')));
+        assert_eq!(spelling('é', &['e']), None);
+        assert_eq!(split_off('é', '\u{0301}'), Some('e'));
+        assert_eq!(split_off('é', 'e'), Some('\u{00B4}'));
+        assert_eq!(split_off('é', 'a'), None);
+    }
+}
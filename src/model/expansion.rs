@@ -0,0 +1,144 @@
+//! # Expansion Module
+//!
+//! This module decides which nodes of the tree are expanded (given children)
+//! and which are collapsed each frame, in the spirit of the BudgettingPolicy
+//! in upstream Dasher. Nodes are only expanded once they are large enough on
+//! screen to matter, the largest (and hence most probable) first, and the
+//! number of expansions and collapses per frame is capped so that deep trees
+//! cannot blow up memory or frame time.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::node::{DasherNode, NodeFlags};
+use super::DasherModel;
+
+/// Limits on how the tree grows and shrinks each frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpansionPolicy {
+    /// Maximum number of nodes expanded per frame
+    pub max_expansions: usize,
+
+    /// Maximum number of nodes collapsed per frame
+    pub max_collapses: usize,
+
+    /// Minimum height (in Dasher coordinates) of a node worth expanding
+    pub min_node_size: i64,
+}
+
+impl Default for ExpansionPolicy {
+    fn default() -> Self {
+        Self {
+            max_expansions: Self::DEFAULT_MAX_EXPANSIONS,
+            max_collapses: Self::DEFAULT_MAX_COLLAPSES,
+            min_node_size: Self::DEFAULT_MIN_NODE_SIZE,
+        }
+    }
+}
+
+/// Nodes chosen by an [`ExpansionPolicy`] for a single frame
+#[derive(Default)]
+pub struct ExpansionPlan {
+    /// Leaves to expand, largest first
+    pub expand: Vec<Rc<RefCell<DasherNode>>>,
+
+    /// Nodes whose children should be deleted, smallest first
+    pub collapse: Vec<Rc<RefCell<DasherNode>>>,
+}
+
+impl ExpansionPolicy {
+    /// Default maximum number of nodes expanded per frame
+    pub const DEFAULT_MAX_EXPANSIONS: usize = 4;
+
+    /// Default maximum number of nodes collapsed per frame
+    pub const DEFAULT_MAX_COLLAPSES: usize = 32;
+
+    /// Default minimum node height (1/64 of the screen)
+    pub const DEFAULT_MIN_NODE_SIZE: i64 = DasherModel::MAX_Y / 64;
+
+    /// Create a policy with the given limits
+    pub fn new(max_expansions: usize, max_collapses: usize, min_node_size: i64) -> Self {
+        Self { max_expansions, max_collapses, min_node_size }
+    }
+
+    /// Choose the nodes to expand and collapse this frame.
+    ///
+    /// `root_min` and `root_max` are the coordinates of the root, and
+    /// `visible_min` and `visible_max` the range of coordinates on screen.
+    pub fn plan(
+        &self,
+        root: &Rc<RefCell<DasherNode>>,
+        (root_min, root_max): (i64, i64),
+        (visible_min, visible_max): (i64, i64),
+    ) -> ExpansionPlan {
+        let mut expand = Vec::new();
+        let mut collapse = Vec::new();
+        let mut stack = vec![(root.clone(), root_min, root_max)];
+
+        while let Some((node, y1, y2)) = stack.pop() {
+            let node_ref = node.borrow();
+            let size = y2 - y1;
+            let visible = y2 >= visible_min && y1 <= visible_max;
+
+            if !visible || size < self.min_node_size {
+                // Nodes that have been output must stay, as they hold the text state
+                if !Rc::ptr_eq(&node, root) && !node_ref.is_leaf() && !node_ref.get_flag(NodeFlags::SEEN) {
+                    collapse.push((size, node.clone()));
+                }
+                continue;
+            }
+
+            if node_ref.is_leaf() {
+                if !node_ref.get_flag(NodeFlags::ALL_CHILDREN) {
+                    expand.push((size, node.clone()));
+                }
+                continue;
+            }
+
+            for child in node_ref.children() {
+                let child_ref = child.borrow();
+                let child_min = y1 + (child_ref.lower_bound() as i64 * size) / DasherModel::NORMALIZATION as i64;
+                let child_max = y1 + (child_ref.upper_bound() as i64 * size) / DasherModel::NORMALIZATION as i64;
+                stack.push((child.clone(), child_min, child_max));
+            }
+        }
+
+        // Expand the most probable (largest) nodes first and collapse the least
+        expand.sort_by_key(|(size, _)| std::cmp::Reverse(*size));
+        collapse.sort_by_key(|(size, _)| *size);
+
+        ExpansionPlan {
+            expand: expand.into_iter().take(self.max_expansions).map(|(_, node)| node).collect(),
+            collapse: collapse.into_iter().take(self.max_collapses).map(|(_, node)| node).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expansion_is_budgeted_and_largest_first() {
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+
+        let root = model.get_root_node().unwrap();
+        let children = root.borrow().children().len();
+        assert!(children > 2);
+
+        let policy = ExpansionPolicy::new(2, 0, 0);
+        let plan = policy.plan(&root, (0, DasherModel::MAX_Y), (0, DasherModel::MAX_Y));
+        assert_eq!(plan.expand.len(), 2);
+        assert!(plan.expand[0].borrow().range() >= plan.expand[1].borrow().range());
+
+        assert_eq!(model.expand_visible_nodes(&policy), 2);
+        let expanded = root.borrow().children().iter().filter(|child| !child.borrow().is_leaf()).count();
+        assert_eq!(expanded, 2);
+
+        // Once they are too small to matter, the expanded nodes are collapsed again
+        let policy = ExpansionPolicy::new(0, 1, DasherModel::MAX_Y);
+        let plan = policy.plan(&root, (0, DasherModel::MAX_Y), (0, DasherModel::MAX_Y));
+        assert_eq!(plan.collapse.len(), 1);
+    }
+}
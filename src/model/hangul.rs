@@ -0,0 +1,94 @@
+//! # Hangul Module
+//!
+//! This module composes conjoining Hangul jamo into precomposed syllables,
+//! following the Unicode algorithm, so that a Korean alphabet can write
+//! one jamo at a time: a leading consonant followed by a vowel becomes a
+//! syllable, and a trailing consonant joins a syllable without one. Each
+//! step can be undone, so erasing a jamo takes it back off its syllable.
+
+/// First precomposed syllable (가)
+const SYLLABLE_BASE: u32 = 0xAC00;
+/// First leading consonant (ᄀ)
+const LEADING_BASE: u32 = 0x1100;
+/// First vowel (ᅡ)
+const VOWEL_BASE: u32 = 0x1161;
+/// Code before the first trailing consonant, standing for no trailing consonant
+const TRAILING_BASE: u32 = 0x11A7;
+/// Number of leading consonants
+const LEADING_COUNT: u32 = 19;
+/// Number of vowels
+const VOWEL_COUNT: u32 = 21;
+/// Number of trailing consonants, counting none
+const TRAILING_COUNT: u32 = 28;
+/// Number of syllables with each leading consonant
+const SYLLABLES_PER_LEADING: u32 = VOWEL_COUNT * TRAILING_COUNT;
+/// Number of precomposed syllables
+const SYLLABLE_COUNT: u32 = LEADING_COUNT * SYLLABLES_PER_LEADING;
+
+/// Get the index of a precomposed syllable, if `c` is one
+fn syllable_index(c: char) -> Option<u32> {
+    (c as u32).checked_sub(SYLLABLE_BASE).filter(|&index| index < SYLLABLE_COUNT)
+}
+
+/// Combine `previous` with the jamo `next` written after it, if they compose
+///
+/// A leading consonant and a vowel compose to a syllable, and a syllable
+/// without a trailing consonant composes with one.
+pub fn compose(previous: char, next: char) -> Option<char> {
+    let next = next as u32;
+    let leading = (previous as u32).checked_sub(LEADING_BASE).filter(|&index| index < LEADING_COUNT);
+    if let Some(leading) = leading {
+        let vowel = next.checked_sub(VOWEL_BASE).filter(|&index| index < VOWEL_COUNT)?;
+        return char::from_u32(SYLLABLE_BASE + leading * SYLLABLES_PER_LEADING + vowel * TRAILING_COUNT);
+    }
+
+    let syllable = syllable_index(previous)?;
+    let trailing = next.checked_sub(TRAILING_BASE).filter(|&index| index > 0 && index < TRAILING_COUNT)?;
+    if syllable % TRAILING_COUNT != 0 {
+        return None;
+    }
+    char::from_u32(previous as u32 + trailing)
+}
+
+/// Split the last jamo off a precomposed syllable, the reverse of [`compose`]
+///
+/// Returns what is left (a shorter syllable or a leading consonant) and the
+/// jamo taken off, or `None` if `c` is not a syllable.
+pub fn split_last(c: char) -> Option<(char, char)> {
+    let index = syllable_index(c)?;
+    let trailing = index % TRAILING_COUNT;
+    if trailing != 0 {
+        return Some((char::from_u32(c as u32 - trailing)?, char::from_u32(TRAILING_BASE + trailing)?));
+    }
+    let leading = index / SYLLABLES_PER_LEADING;
+    let vowel = (index % SYLLABLES_PER_LEADING) / TRAILING_COUNT;
+    Some((char::from_u32(LEADING_BASE + leading)?, char::from_u32(VOWEL_BASE + vowel)?))
+}
+
+/// Get the jamo a precomposed syllable is made of, if `c` is one
+pub fn decompose(c: char) -> Option<Vec<char>> {
+    let (rest, last) = split_last(c)?;
+    let mut jamo = decompose(rest).unwrap_or_else(|| vec![rest]);
+    jamo.push(last);
+    Some(jamo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jamo_compose_and_split() {
+        // ᄒ + ᅡ = 하, 하 + ᆫ = 한
+        assert_eq!(compose('\u{1112}', '\u{1161}'), Some('하'));
+        assert_eq!(compose('하', '\u{11AB}'), Some('한'));
+        assert_eq!(compose('한', '\u{11AB}'), None);
+        assert_eq!(compose('한', '\u{1100}'), None);
+        assert_eq!(compose('a', '\u{1161}'), None);
+
+        assert_eq!(split_last('한'), Some(('하', '\u{11AB}')));
+        assert_eq!(split_last('하'), Some(('\u{1112}', '\u{1161}')));
+        assert_eq!(split_last('a'), None);
+        assert_eq!(decompose('한'), Some(vec!['\u{1112}', '\u{1161}', '\u{11AB}']));
+    }
+}
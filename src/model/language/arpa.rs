@@ -0,0 +1,332 @@
+//! ARPA n-gram language models
+//!
+//! ARPA is the text format n-gram toolkits such as KenLM and SRILM write
+//! their models in. A model built over characters, with each character a
+//! token, can predict Dasher's symbols directly, so a model trained once on
+//! a large corpus can be reused rather than training PPM from the raw text.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use flate2::bufread::GzDecoder;
+
+use super::{normalize_counts, Context, ContextTable, LanguageModel};
+use crate::{DasherError, Result};
+
+/// Log probability and backoff weight of an n-gram, in base 10
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct NGram {
+    /// Log probability of the last token following the others
+    log_prob: f32,
+    /// Log weight given to shorter contexts when an n-gram starting with
+    /// this one is missing
+    backoff: f32,
+}
+
+/// Language model reading its probabilities from an ARPA n-gram file
+///
+/// Tokens of a single character predict the symbol with that character,
+/// and `<sp>` or `<space>` predict a space. Symbols without a token take
+/// the probability of `<unk>`, if the model has it. The context starts with
+/// `<s>`. The probabilities are fixed, so learning a symbol only enters it.
+#[derive(Debug, Clone)]
+pub struct ArpaLanguageModel {
+    /// Identifier of each token
+    tokens: HashMap<String, u32>,
+    /// N-grams, as the identifiers of their tokens, of every order
+    ngrams: HashMap<Vec<u32>, NGram>,
+    /// Length of the longest n-grams
+    order: usize,
+    /// Identifier of the token of each symbol, in symbol index order
+    symbol_tokens: Vec<Option<u32>>,
+    /// Tokens before the symbol predicted, at most `order - 1` of them
+    contexts: ContextTable<Vec<u32>>,
+}
+
+impl ArpaLanguageModel {
+    /// Token starting a sentence
+    const START: &'static str = "<s>";
+    /// Token standing for tokens the model does not know
+    const UNKNOWN: &'static str = "<unk>";
+    /// Tokens standing for a space
+    const SPACES: [&'static str; 2] = ["<sp>", "<space>"];
+    /// First bytes of a gzip stream
+    const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+    /// Load a model from an ARPA file, which may be gzip-compressed
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .map_err(|e| DasherError::InvalidParameter(format!("Failed to open {}: {}", path.display(), e)))?;
+        let mut reader = BufReader::new(file);
+        let compressed = reader.fill_buf()
+            .map_err(|e| DasherError::Other(format!("Failed to read {}: {}", path.display(), e)))?
+            .starts_with(&Self::GZIP_MAGIC);
+        if compressed {
+            Self::from_reader(BufReader::new(GzDecoder::new(reader)))
+        } else {
+            Self::from_reader(reader)
+        }
+    }
+
+    /// Read a model from ARPA text
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self> {
+        let invalid = |line: usize, message: &str| {
+            DasherError::InvalidParameter(format!("Invalid ARPA model at line {}: {}", line, message))
+        };
+
+        let mut tokens = HashMap::new();
+        let mut ngrams = HashMap::new();
+        // Number of n-grams of each order, as declared and as read
+        let mut declared: Vec<usize> = Vec::new();
+        let mut read: Vec<usize> = Vec::new();
+        let mut in_data = false;
+        let mut section = None;
+        let mut ended = false;
+
+        for (index, line) in reader.lines().enumerate() {
+            let number = index + 1;
+            let line = line.map_err(|e| DasherError::Other(format!("Failed to read ARPA model: {}", e)))?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if line == "\\data\\" {
+                in_data = true;
+            } else if line == "\\end\\" {
+                ended = true;
+                break;
+            } else if let Some(order) = line.strip_prefix('\\').and_then(|rest| rest.strip_suffix("-grams:")) {
+                let order: usize = order.parse().map_err(|_| invalid(number, "bad section header"))?;
+                if order == 0 || order > declared.len() {
+                    return Err(invalid(number, "section for an order not declared in \\data\\"));
+                }
+                section = Some(order);
+            } else if let Some(order) = section {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                if fields.len() != order + 1 && fields.len() != order + 2 {
+                    return Err(invalid(number, &format!("expected {} tokens", order)));
+                }
+                let log_prob: f32 = fields[0].parse().map_err(|_| invalid(number, "bad log probability"))?;
+                let backoff: f32 = match fields.get(order + 1) {
+                    Some(backoff) => backoff.parse().map_err(|_| invalid(number, "bad backoff weight"))?,
+                    None => 0.0,
+                };
+                let key = fields[1..=order].iter().map(|&token| {
+                    let next = tokens.len() as u32;
+                    *tokens.entry(token.to_string()).or_insert(next)
+                }).collect();
+                ngrams.insert(key, NGram { log_prob, backoff });
+                read[order - 1] += 1;
+            } else if in_data {
+                let (order, count) = line.strip_prefix("ngram ")
+                    .and_then(|counts| counts.split_once('='))
+                    .and_then(|(order, count)| Some((order.trim().parse::<usize>().ok()?, count.trim().parse::<usize>().ok()?)))
+                    .ok_or_else(|| invalid(number, "bad n-gram count"))?;
+                if order != declared.len() + 1 {
+                    return Err(invalid(number, "n-gram counts out of order"));
+                }
+                declared.push(count);
+                read.push(0);
+            }
+        }
+
+        if declared.is_empty() {
+            return Err(invalid(0, "no \\data\\ section"));
+        }
+        if !ended {
+            return Err(invalid(0, "missing \\end\\"));
+        }
+        if let Some(order) = (0..declared.len()).find(|&i| declared[i] != read[i]) {
+            return Err(invalid(0, &format!(
+                "{} {}-grams declared but {} found", declared[order], order + 1, read[order]
+            )));
+        }
+
+        Ok(Self {
+            tokens,
+            ngrams,
+            order: declared.len(),
+            symbol_tokens: Vec::new(),
+            contexts: ContextTable::new(),
+        })
+    }
+
+    /// Get the length of the longest n-grams
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// Get the number of n-grams of all orders
+    pub fn ngram_count(&self) -> usize {
+        self.ngrams.len()
+    }
+
+    /// Get the identifier of the token predicting `c`
+    fn token_for(&self, c: char) -> Option<u32> {
+        let mut buffer = [0; 4];
+        let own = self.tokens.get(&*c.encode_utf8(&mut buffer));
+        let space = || if c == ' ' {
+            Self::SPACES.iter().find_map(|&space| self.tokens.get(space))
+        } else {
+            None
+        };
+        own.or_else(space).or_else(|| self.tokens.get(Self::UNKNOWN)).copied()
+    }
+
+    /// Get the log probability of `token` following `history`, backing off
+    /// to shorter histories where the n-gram is missing
+    fn log_prob(&self, history: &[u32], token: u32) -> f32 {
+        let mut backoff = 0.0;
+        let mut key = Vec::with_capacity(history.len() + 1);
+        for start in 0..=history.len() {
+            let context = &history[start..];
+            key.clear();
+            key.extend_from_slice(context);
+            key.push(token);
+            if let Some(ngram) = self.ngrams.get(&key) {
+                return backoff + ngram.log_prob;
+            }
+            if let Some(ngram) = self.ngrams.get(context) {
+                backoff += ngram.backoff;
+            }
+        }
+        f32::NEG_INFINITY
+    }
+
+    /// Add `symbol` to the tokens before the symbol predicted
+    fn push_symbol(&mut self, context: Context, symbol: usize) {
+        let token = self.symbol_tokens.get(symbol).copied().flatten();
+        let keep = self.order.saturating_sub(1);
+        if let Some(history) = self.contexts.get_mut(context) {
+            match token {
+                Some(token) => history.push(token),
+                // No n-gram holds a token the model does not know
+                None => history.clear(),
+            }
+            if history.len() > keep {
+                history.drain(..history.len() - keep);
+            }
+        }
+    }
+}
+
+impl LanguageModel for ArpaLanguageModel {
+    fn as_any(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn set_symbols(&mut self, symbols: &[char]) {
+        self.symbol_tokens = symbols.iter().map(|&c| self.token_for(c)).collect();
+    }
+
+    fn create_empty_context(&mut self) -> Context {
+        let history = self.tokens.get(Self::START).copied().into_iter().collect();
+        self.contexts.insert(history)
+    }
+
+    fn clone_context(&mut self, context: Context) -> Context {
+        let history = self.contexts.get(context).cloned().unwrap_or_default();
+        self.contexts.insert(history)
+    }
+
+    fn release_context(&mut self, context: Context) {
+        self.contexts.remove(context);
+    }
+
+    fn get_probs(&self, context: Context, norm: u32, probs: &mut Vec<u32>) {
+        probs.clear();
+        probs.resize(self.symbol_tokens.len(), 0);
+        let Some(history) = self.contexts.get(context) else {
+            return;
+        };
+
+        for (prob, token) in probs.iter_mut().zip(&self.symbol_tokens) {
+            if let Some(token) = *token {
+                let p = 10f64.powf(self.log_prob(history, token) as f64);
+                *prob = (p * norm as f64) as u32;
+            }
+        }
+        normalize_counts(probs, norm);
+    }
+
+    fn enter_symbol(&mut self, context: Context, symbol: usize) {
+        self.push_symbol(context, symbol);
+    }
+
+    fn learn_symbol(&mut self, context: Context, symbol: usize) {
+        self.push_symbol(context, symbol);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Bigram model over "a", "b" and spaces
+    const MODEL: &str = "
+\\data\\
+ngram 1=5
+ngram 2=3
+
+\\1-grams:
+-1.0\t<s>\t-0.3
+-0.3\ta\t-0.2
+-0.6\tb\t-0.1
+-0.9\t<sp>
+-1.2\t</s>
+
+\\2-grams:
+-0.1\t<s> a
+-0.05\ta b
+-0.2\tb <sp>
+
+\\end\\
+";
+
+    #[test]
+    fn test_arpa_predicts_with_backoff() {
+        let mut model = ArpaLanguageModel::from_reader(MODEL.as_bytes()).unwrap();
+        assert_eq!(model.order(), 2);
+        assert_eq!(model.ngram_count(), 8);
+        model.set_symbols(&['a', 'b', ' ', 'c']);
+
+        let mut probs = Vec::new();
+        let context = model.create_empty_context();
+        model.get_probs(context, 1000, &mut probs);
+        assert_eq!(probs.iter().sum::<u32>(), 1000);
+        assert!(probs[0] > probs[1] && probs[0] > probs[2]);
+        assert_eq!(probs[3], 0);
+
+        // "a b" is listed, "a <sp>" backs off to the unigram
+        model.enter_symbol(context, 0);
+        model.get_probs(context, 1000, &mut probs);
+        assert!(probs[1] > probs[0]);
+        let after_a = probs.clone();
+        let clone = model.clone_context(context);
+        model.get_probs(clone, 1000, &mut probs);
+        assert_eq!(probs, after_a);
+
+        assert!((model.log_prob(&[model.tokens["a"]], model.tokens["<sp>"]) - (-0.2 - 0.9)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_arpa_loads_gzip_and_rejects_bad_files() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(MODEL.as_bytes()).unwrap();
+        file.write_all(&encoder.finish().unwrap()).unwrap();
+        assert_eq!(ArpaLanguageModel::load(file.path()).unwrap().ngram_count(), 8);
+
+        let truncated = MODEL.replace("\\end\\", "");
+        assert!(ArpaLanguageModel::from_reader(truncated.as_bytes()).is_err());
+        let miscounted = MODEL.replace("ngram 2=3", "ngram 2=4");
+        let error = ArpaLanguageModel::from_reader(miscounted.as_bytes()).unwrap_err();
+        assert!(error.to_string().contains("4 2-grams declared but 3 found"), "{}", error);
+        assert!(ArpaLanguageModel::from_reader("just text".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+
+/// Word bigram model, predicting the next word from the previous one
+#[derive(Debug, Clone, Default)]
+pub struct WordBigramModel {
+    /// Number of times each word followed each previous word
+    bigrams: HashMap<String, HashMap<String, u32>>,
+    /// Number of times each word was seen, used when the previous word is unknown
+    unigrams: HashMap<String, u32>,
+}
+
+impl WordBigramModel {
+    /// Create an empty model
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Learn that `word` followed `previous` (empty at the start of the text)
+    pub fn learn(&mut self, previous: &str, word: &str) {
+        if word.is_empty() {
+            return;
+        }
+        let count = self.bigrams
+            .entry(previous.to_string())
+            .or_default()
+            .entry(word.to_string())
+            .or_insert(0);
+        *count = count.saturating_add(1);
+        let count = self.unigrams.entry(word.to_string()).or_insert(0);
+        *count = count.saturating_add(1);
+    }
+
+    /// Predict up to `max` words following `previous`, most probable first
+    ///
+    /// Each word comes with its probability of being next. If `previous` has
+    /// never been seen, the words are predicted from their overall frequency.
+    pub fn predict(&self, previous: &str, max: usize) -> Vec<(String, f64)> {
+        let counts = self.bigrams.get(previous).unwrap_or(&self.unigrams);
+        let total: u64 = counts.values().map(|&count| count as u64).sum();
+        if total == 0 {
+            return Vec::new();
+        }
+
+        let mut words: Vec<_> = counts.iter().collect();
+        words.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        words.into_iter()
+            .take(max)
+            .map(|(word, &count)| (word.clone(), count as f64 / total as f64))
+            .collect()
+    }
+
+    /// Forget everything learned
+    pub fn clear(&mut self) {
+        self.bigrams.clear();
+        self.unigrams.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bigram_predictions() {
+        let mut model = WordBigramModel::new();
+        model.learn("", "the");
+        model.learn("the", "cat");
+        model.learn("cat", "sat");
+        model.learn("sat", "on");
+        model.learn("on", "the");
+        model.learn("the", "mat");
+        model.learn("the", "cat");
+        model.learn("mat", "the");
+
+        let predictions = model.predict("the", 5);
+        assert_eq!(predictions.len(), 2);
+        assert_eq!(predictions[0].0, "cat");
+        assert!((predictions[0].1 - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(model.predict("the", 1).len(), 1);
+
+        // Unknown words fall back to overall frequency
+        let predictions = model.predict("dog", 1);
+        assert_eq!(predictions[0].0, "the");
+    }
+}
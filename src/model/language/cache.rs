@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+
+/// Hit and miss counts of a [`ProbabilityCache`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Number of lookups answered from the cache
+    pub hits: u64,
+    /// Number of lookups that had to be computed
+    pub misses: u64,
+    /// Number of entries dropped to make room for new ones
+    pub evictions: u64,
+    /// Number of entries in the cache
+    pub entries: usize,
+}
+
+impl CacheStats {
+    /// Get the fraction of lookups answered from the cache (0 if there were none)
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+/// A cached distribution and when it was last used
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    probs: Vec<u32>,
+    last_used: u64,
+}
+
+/// Least recently used cache of probability distributions, keyed by context
+///
+/// All entries are for the same symbols and normalisation; looking up with
+/// different ones empties the cache.
+#[derive(Debug, Clone)]
+pub struct ProbabilityCache {
+    /// Cached distributions by context
+    entries: HashMap<String, CacheEntry>,
+    /// Maximum number of entries (0 disables the cache)
+    capacity: usize,
+    /// Symbols the cached distributions are for
+    symbols: Vec<char>,
+    /// Normalisation the cached distributions sum to
+    norm: u32,
+    /// Counter used to order entries by last use
+    clock: u64,
+    /// Lookup statistics
+    stats: CacheStats,
+}
+
+impl ProbabilityCache {
+    /// Default maximum number of entries
+    pub const DEFAULT_CAPACITY: usize = 256;
+
+    /// Create an empty cache holding up to `capacity` distributions
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity,
+            symbols: Vec::new(),
+            norm: 0,
+            clock: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Get the maximum number of entries
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Set the maximum number of entries, dropping the least recently used as needed
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.entries.len() > capacity {
+            self.evict();
+        }
+    }
+
+    /// Get the lookup statistics
+    pub fn stats(&self) -> CacheStats {
+        CacheStats { entries: self.entries.len(), ..self.stats }
+    }
+
+    /// Reset the lookup statistics, keeping the entries
+    pub fn reset_stats(&mut self) {
+        self.stats = CacheStats::default();
+    }
+
+    /// Drop every entry
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Copy the distribution for `context` into `probs`, returning whether it was cached
+    pub fn get(&mut self, context: &str, symbols: &[char], norm: u32, probs: &mut Vec<u32>) -> bool {
+        if self.capacity == 0 {
+            return false;
+        }
+        self.select(symbols, norm);
+
+        self.clock += 1;
+        match self.entries.get_mut(context) {
+            Some(entry) => {
+                entry.last_used = self.clock;
+                probs.clear();
+                probs.extend_from_slice(&entry.probs);
+                self.stats.hits += 1;
+                true
+            }
+            None => {
+                self.stats.misses += 1;
+                false
+            }
+        }
+    }
+
+    /// Hold distributions for `symbols` summing to `norm`, emptying the
+    /// cache if they differ from those held
+    pub fn select(&mut self, symbols: &[char], norm: u32) {
+        if self.norm != norm || self.symbols != symbols {
+            self.entries.clear();
+            self.symbols = symbols.to_vec();
+            self.norm = norm;
+        }
+    }
+
+    /// Check whether the distribution for `context` is cached, without
+    /// counting a lookup
+    pub fn contains(&self, context: &str, symbols: &[char], norm: u32) -> bool {
+        self.norm == norm && self.symbols == symbols && self.entries.contains_key(context)
+    }
+
+    /// Cache the distribution for `context`, for the symbols of the last
+    /// lookup or [`Self::select`]
+    pub fn insert(&mut self, context: &str, probs: &[u32]) {
+        if self.capacity == 0 {
+            return;
+        }
+        if !self.entries.contains_key(context) && self.entries.len() >= self.capacity {
+            self.evict();
+        }
+        self.entries.insert(context.to_string(), CacheEntry {
+            probs: probs.to_vec(),
+            last_used: self.clock,
+        });
+    }
+
+    /// Drop the least recently used entry
+    fn evict(&mut self) {
+        let oldest = self.entries.iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(context, _)| context.clone());
+        if let Some(context) = oldest {
+            self.entries.remove(&context);
+            self.stats.evictions += 1;
+        }
+    }
+}
+
+impl Default for ProbabilityCache {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let symbols = ['a', 'b'];
+        let mut cache = ProbabilityCache::new(2);
+        let mut probs = Vec::new();
+
+        assert!(!cache.get("a", &symbols, 10, &mut probs));
+        cache.insert("a", &[4, 6]);
+        assert!(!cache.get("b", &symbols, 10, &mut probs));
+        cache.insert("b", &[5, 5]);
+        assert!(cache.get("a", &symbols, 10, &mut probs));
+        assert_eq!(probs, vec![4, 6]);
+
+        // "b" is now the least recently used
+        assert!(!cache.get("c", &symbols, 10, &mut probs));
+        cache.insert("c", &[1, 9]);
+        assert!(!cache.get("b", &symbols, 10, &mut probs));
+        assert!(cache.get("a", &symbols, 10, &mut probs));
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.evictions, stats.entries), (2, 4, 1, 2));
+        assert!((stats.hit_rate() - 1.0 / 3.0).abs() < 1e-9);
+
+        // Other symbols invalidate the cache
+        assert!(!cache.get("a", &['a'], 10, &mut probs));
+    }
+}
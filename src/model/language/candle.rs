@@ -0,0 +1,175 @@
+//! Character language models run with candle
+//!
+//! A [`CandleCharModel`] is a small feed-forward network over a fixed window
+//! of characters: each character of the window is embedded, the embeddings
+//! go through one hidden layer, and a softmax over the vocabulary gives the
+//! next character. Its weights are read from a safetensors file, which can
+//! be exported from a model trained with any framework.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use candle_core::{DType, Device, Tensor};
+
+use super::NeuralPredictor;
+use crate::{DasherError, Result};
+
+/// Character language model, for a [`super::NeuralLanguageModel`]
+///
+/// The safetensors file holds these tensors:
+///
+/// - `vocabulary`: the Unicode code point of each token, as `u32`; token 0
+///   stands for unknown characters and pads histories shorter than the window
+/// - `embedding`: `[tokens, dimensions]`
+/// - `hidden.weight`: `[hidden, window * dimensions]` and `hidden.bias`: `[hidden]`
+/// - `output.weight`: `[tokens, hidden]` and `output.bias`: `[tokens]`
+///
+/// The window is the width of `hidden.weight` over the embedding dimensions.
+pub struct CandleCharModel {
+    /// Token of each character of the vocabulary
+    tokens: HashMap<char, u32>,
+    /// Embedding of each token
+    embedding: Tensor,
+    /// Weights of the hidden layer
+    hidden_weight: Tensor,
+    /// Biases of the hidden layer
+    hidden_bias: Tensor,
+    /// Weights of the output layer
+    output_weight: Tensor,
+    /// Biases of the output layer
+    output_bias: Tensor,
+    /// Number of characters before the symbol looked at
+    window: usize,
+    /// Device the network runs on
+    device: Device,
+}
+
+/// Turn an error of candle into one of ours
+fn candle_error(e: candle_core::Error) -> DasherError {
+    DasherError::Other(format!("Neural language model failed: {}", e))
+}
+
+impl CandleCharModel {
+    /// Load a model from a safetensors file, to run on the CPU
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let tensors = candle_core::safetensors::load(path, &Device::Cpu)
+            .map_err(|e| DasherError::InvalidParameter(format!("Failed to load {}: {}", path.display(), e)))?;
+        Self::from_tensors(tensors, Device::Cpu)
+    }
+
+    /// Create a model from its tensors, by name
+    pub fn from_tensors(mut tensors: HashMap<String, Tensor>, device: Device) -> Result<Self> {
+        let mut take = |name: &str, dtype: DType| {
+            tensors.remove(name)
+                .ok_or_else(|| DasherError::InvalidParameter(format!("Neural language model has no '{}' tensor", name)))?
+                .to_dtype(dtype)
+                .and_then(|tensor| tensor.to_device(&device))
+                .map_err(candle_error)
+        };
+        let vocabulary = take("vocabulary", DType::U32)?;
+        let embedding = take("embedding", DType::F32)?;
+        let hidden_weight = take("hidden.weight", DType::F32)?;
+        let hidden_bias = take("hidden.bias", DType::F32)?;
+        let output_weight = take("output.weight", DType::F32)?;
+        let output_bias = take("output.bias", DType::F32)?;
+
+        let code_points = vocabulary.to_vec1::<u32>().map_err(candle_error)?;
+        let mut tokens = HashMap::new();
+        for (token, &code_point) in code_points.iter().enumerate() {
+            let c = char::from_u32(code_point)
+                .ok_or_else(|| DasherError::InvalidParameter(format!("Invalid character {:#x} in vocabulary", code_point)))?;
+            tokens.entry(c).or_insert(token as u32);
+        }
+
+        let shape_error = || DasherError::InvalidParameter("Neural language model tensors do not fit together".to_string());
+        let (token_count, dimensions) = embedding.dims2().map_err(|_| shape_error())?;
+        let (hidden, input) = hidden_weight.dims2().map_err(|_| shape_error())?;
+        if token_count != code_points.len()
+            || dimensions == 0
+            || input % dimensions != 0
+            || hidden_bias.dims() != [hidden]
+            || output_weight.dims() != [token_count, hidden]
+            || output_bias.dims() != [token_count]
+        {
+            return Err(shape_error());
+        }
+
+        Ok(Self {
+            tokens,
+            embedding,
+            hidden_weight,
+            hidden_bias,
+            output_weight,
+            output_bias,
+            window: input / dimensions,
+            device,
+        })
+    }
+
+    /// Get the tokens of the window of characters ending `history`
+    fn window_tokens(&self, history: &str) -> Vec<u32> {
+        let chars: Vec<char> = history.chars().collect();
+        let start = chars.len().saturating_sub(self.window);
+        let mut tokens = vec![0; self.window - (chars.len() - start)];
+        tokens.extend(chars[start..].iter().map(|c| self.tokens.get(c).copied().unwrap_or(0)));
+        tokens
+    }
+
+    /// Get the probability of each token following each history
+    fn forward(&self, histories: &[&str]) -> candle_core::Result<Vec<Vec<f32>>> {
+        let ids: Vec<u32> = histories.iter().flat_map(|history| self.window_tokens(history)).collect();
+        let ids = Tensor::from_vec(ids, histories.len() * self.window, &self.device)?;
+        let embedded = self.embedding.embedding(&ids)?.reshape((histories.len(), ()))?;
+        let hidden = embedded.matmul(&self.hidden_weight.t()?)?.broadcast_add(&self.hidden_bias)?.tanh()?;
+        let logits = hidden.matmul(&self.output_weight.t()?)?.broadcast_add(&self.output_bias)?;
+        let exp = logits.broadcast_sub(&logits.max_keepdim(1)?)?.exp()?;
+        exp.broadcast_div(&exp.sum_keepdim(1)?)?.to_vec2::<f32>()
+    }
+}
+
+impl NeuralPredictor for CandleCharModel {
+    fn context_length(&self) -> usize {
+        self.window
+    }
+
+    fn predict(&self, histories: &[&str], symbols: &[char]) -> Result<Vec<Vec<f32>>> {
+        let probabilities = self.forward(histories).map_err(candle_error)?;
+        Ok(probabilities.iter().map(|row| {
+            symbols.iter().map(|c| self.tokens.get(c).map_or(0.0, |&token| row[token as usize])).collect()
+        }).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candle_model_predicts_from_its_window() {
+        // After 'a' the hidden unit is high and 'b' follows, after 'b' it is low and 'a' follows
+        let cpu = Device::Cpu;
+        let tensors = HashMap::from([
+            ("vocabulary".to_string(), Tensor::new(&[0u32, 'a' as u32, 'b' as u32], &cpu).unwrap()),
+            ("embedding".to_string(), Tensor::new(&[[0f32], [1.0], [-1.0]], &cpu).unwrap()),
+            ("hidden.weight".to_string(), Tensor::new(&[[10f32]], &cpu).unwrap()),
+            ("hidden.bias".to_string(), Tensor::new(&[0f32], &cpu).unwrap()),
+            ("output.weight".to_string(), Tensor::new(&[[0f32], [-10.0], [10.0]], &cpu).unwrap()),
+            ("output.bias".to_string(), Tensor::new(&[0f32, 0.0, 0.0], &cpu).unwrap()),
+        ]);
+        let file = tempfile::NamedTempFile::new().unwrap();
+        candle_core::safetensors::save(&tensors, file.path()).unwrap();
+
+        let model = CandleCharModel::load(file.path()).unwrap();
+        assert_eq!(model.context_length(), 1);
+        let predictions = model.predict(&["xa", "ab", ""], &['a', 'b', 'z']).unwrap();
+        assert!(predictions[0][1] > 0.9);
+        assert!(predictions[1][0] > 0.9);
+        assert!((predictions[2][0] - predictions[2][1]).abs() < 1e-6);
+        assert_eq!(predictions[0][2], 0.0);
+
+        let mut broken = tensors.clone();
+        broken.remove("output.bias");
+        assert!(CandleCharModel::from_tensors(broken, Device::Cpu).is_err());
+    }
+}
@@ -0,0 +1,226 @@
+//! Language model contexts
+//!
+//! A language model predicts the next symbol from a context: an opaque
+//! handle to whatever the model keeps about the symbols before it. Contexts
+//! are created empty, extended one symbol at a time, cloned to explore
+//! different continuations, and released when no longer needed.
+
+use super::LanguageModel;
+
+/// Opaque handle to a context of a language model
+///
+/// A handle is only meaningful to the model that created it, and only
+/// until the model releases it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Context(usize);
+
+impl Context {
+    /// Create a handle from a model's own identifier for a context
+    pub fn new(id: usize) -> Self {
+        Self(id)
+    }
+
+    /// Get the model's identifier for the context
+    pub fn id(&self) -> usize {
+        self.0
+    }
+}
+
+/// Store of contexts by handle, for implementing [`LanguageModel`]
+///
+/// Released handles are reused, so the store stays as large as the most
+/// contexts alive at once.
+#[derive(Debug, Clone)]
+pub struct ContextTable<T> {
+    /// Contexts by identifier, `None` where released
+    slots: Vec<Option<T>>,
+    /// Identifiers of released contexts, to be reused
+    free: Vec<usize>,
+}
+
+impl<T> Default for ContextTable<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> ContextTable<T> {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    /// Add a context, returning its handle
+    pub fn insert(&mut self, value: T) -> Context {
+        match self.free.pop() {
+            Some(id) => {
+                self.slots[id] = Some(value);
+                Context(id)
+            }
+            None => {
+                self.slots.push(Some(value));
+                Context(self.slots.len() - 1)
+            }
+        }
+    }
+
+    /// Get a context
+    pub fn get(&self, context: Context) -> Option<&T> {
+        self.slots.get(context.0)?.as_ref()
+    }
+
+    /// Get a context to modify it
+    pub fn get_mut(&mut self, context: Context) -> Option<&mut T> {
+        self.slots.get_mut(context.0)?.as_mut()
+    }
+
+    /// Remove a context, so its handle can be reused
+    pub fn remove(&mut self, context: Context) -> Option<T> {
+        let value = self.slots.get_mut(context.0)?.take();
+        if value.is_some() {
+            self.free.push(context.0);
+        }
+        value
+    }
+
+    /// Iterate over the contexts alive, to modify them
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.slots.iter_mut().flatten()
+    }
+
+    /// Get the number of contexts alive
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    /// Check whether no contexts are alive
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Contexts kept as the text they end with, for models that predict from text
+///
+/// Only the last `max_chars` characters of each context are kept.
+#[derive(Debug, Clone)]
+pub struct TextContexts {
+    /// Text of each context
+    contexts: ContextTable<String>,
+    /// Character of each symbol, in symbol index order
+    symbols: Vec<char>,
+    /// Number of characters kept of each context
+    max_chars: usize,
+}
+
+impl TextContexts {
+    /// Create an empty store keeping up to `max_chars` characters of each context
+    pub fn new(max_chars: usize) -> Self {
+        Self {
+            contexts: ContextTable::new(),
+            symbols: Vec::new(),
+            max_chars,
+        }
+    }
+
+    /// Get the character of each symbol, in symbol index order
+    pub fn symbols(&self) -> &[char] {
+        &self.symbols
+    }
+
+    /// Set the character of each symbol, in symbol index order
+    pub fn set_symbols(&mut self, symbols: &[char]) {
+        self.symbols = symbols.to_vec();
+    }
+
+    /// Get the character of a symbol
+    pub fn symbol(&self, symbol: usize) -> Option<char> {
+        self.symbols.get(symbol).copied()
+    }
+
+    /// Create a context with no text
+    pub fn create_empty(&mut self) -> Context {
+        self.contexts.insert(String::new())
+    }
+
+    /// Create a copy of a context
+    pub fn clone_context(&mut self, context: Context) -> Context {
+        let text = self.text(context).to_string();
+        self.contexts.insert(text)
+    }
+
+    /// Release a context
+    pub fn release(&mut self, context: Context) {
+        self.contexts.remove(context);
+    }
+
+    /// Get the text of a context (empty for an unknown context)
+    pub fn text(&self, context: Context) -> &str {
+        self.contexts.get(context).map_or("", String::as_str)
+    }
+
+    /// Append a symbol to a context, returning its character
+    pub fn enter(&mut self, context: Context, symbol: usize) -> Option<char> {
+        let c = self.symbol(symbol)?;
+        let text = self.contexts.get_mut(context)?;
+        text.push(c);
+        if text.len() > 2 * self.max_chars {
+            let start = text.char_indices().rev().nth(self.max_chars.max(1) - 1).map_or(0, |(i, _)| i);
+            text.drain(..start);
+        }
+        Some(c)
+    }
+}
+
+/// Extend `context` with the characters of `text`, without learning from them
+///
+/// `symbols` lists the character of each symbol, in symbol index order.
+/// Characters that are not symbols are skipped.
+pub fn enter_text<M: LanguageModel + ?Sized>(model: &mut M, context: Context, symbols: &[char], text: &str) {
+    for c in text.chars() {
+        if let Some(index) = symbols.iter().position(|&symbol| symbol == c) {
+            model.enter_symbol(context, index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_handles_are_reused() {
+        let mut table = ContextTable::new();
+        let a = table.insert("a");
+        let b = table.insert("b");
+        assert_ne!(a, b);
+        assert_eq!(table.remove(a), Some("a"));
+        assert_eq!(table.remove(a), None);
+        assert_eq!(table.get(a), None);
+        assert_eq!(table.insert("c"), a);
+        assert_eq!(table.get(a), Some(&"c"));
+        assert_eq!(table.len(), 2);
+    }
+
+    #[test]
+    fn test_text_contexts_keep_the_end_of_the_text() {
+        let mut contexts = TextContexts::new(3);
+        contexts.set_symbols(&['a', 'b', 'c']);
+        let context = contexts.create_empty();
+        for symbol in [0, 1, 2, 0, 1, 2, 0] {
+            contexts.enter(context, symbol);
+        }
+        assert!(contexts.text(context).ends_with("bca"));
+        assert!(contexts.text(context).chars().count() <= 6);
+        assert_eq!(contexts.enter(context, 3), None);
+
+        let copy = contexts.clone_context(context);
+        contexts.enter(copy, 1);
+        assert!(contexts.text(copy).ends_with("cab"));
+        assert!(contexts.text(context).ends_with("bca"));
+        contexts.release(context);
+        assert_eq!(contexts.text(context), "");
+    }
+}
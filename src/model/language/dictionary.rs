@@ -42,31 +42,66 @@ impl Dictionary {
     }
 
     /// Load dictionary from file
+    ///
+    /// See [`load_from_reader`](Self::load_from_reader) for the formats accepted.
     pub fn load<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
         let file = File::open(path)?;
-        let reader = BufReader::new(file);
+        self.load_from_reader(BufReader::new(file))
+    }
+
+    /// Load a word list, one word per line
+    ///
+    /// Each line is either a plain word (counted once), `word<TAB>count`, or
+    /// `count<TAB>word` as in Dasher's frequency lists; spaces may be used
+    /// instead of the tab. Blank lines and lines starting with `#` are
+    /// skipped. Counts of repeated words are added together, and the counts
+    /// are then normalised into probabilities which replace the frequencies
+    /// of any words already in the dictionary.
+    pub fn load_from_reader<R: BufRead>(&mut self, reader: R) -> io::Result<()> {
+        let mut counts: HashMap<String, f64> = HashMap::new();
 
         for line in reader.lines() {
             let line = line?;
+            let line = line.trim();
             if line.is_empty() || line.starts_with('#') {
                 continue;
             }
 
-            // Parse line: word\tfrequency
-            let parts: Vec<&str> = line.split('\t').collect();
-            if parts.len() != 2 {
-                continue;
+            if let Some((word, count)) = Self::parse_line(line) {
+                *counts.entry(word.to_string()).or_insert(0.0) += count;
             }
+        }
 
-            let word = parts[0].trim();
-            if let Ok(frequency) = parts[1].trim().parse::<f64>() {
-                self.add_word(word, frequency, false);
+        let total: f64 = counts.values().sum();
+        if total > 0.0 {
+            for (word, count) in counts {
+                self.add_word(&word, count / total, false);
             }
         }
 
         Ok(())
     }
 
+    /// Split a word list line into its word and count
+    fn parse_line(line: &str) -> Option<(&str, f64)> {
+        let parse_count = |field: &str| field.parse::<f64>().ok().filter(|count| count.is_finite() && *count >= 0.0);
+
+        let mut fields = line.split(['\t', ' ']).filter(|field| !field.is_empty());
+        let first = fields.next()?;
+        let Some(second) = fields.next() else {
+            return Some((first, 1.0));
+        };
+        if fields.next().is_some() {
+            return None;
+        }
+
+        match (parse_count(first), parse_count(second)) {
+            (_, Some(count)) => Some((first, count)),
+            (Some(count), None) => Some((second, count)),
+            (None, None) => None,
+        }
+    }
+
     /// Add word to dictionary
     pub fn add_word(&mut self, word: &str, frequency: f64, user_added: bool) {
         let entry = DictionaryEntry {
@@ -77,8 +112,7 @@ impl Dictionary {
         self.entries.insert(word.to_string(), entry);
 
         // Update prefix cache
-        for i in 1..=word.len().min(self.max_prefix_length) {
-            let prefix = &word[..i];
+        for prefix in Self::prefixes(word, self.max_prefix_length) {
             self.prefix_cache
                 .entry(prefix.to_string())
                 .or_default()
@@ -90,8 +124,7 @@ impl Dictionary {
     pub fn remove_word(&mut self, word: &str) {
         if self.entries.remove(word).is_some() {
             // Update prefix cache
-            for i in 1..=word.len().min(self.max_prefix_length) {
-                let prefix = &word[..i];
+            for prefix in Self::prefixes(word, self.max_prefix_length) {
                 if let Some(words) = self.prefix_cache.get_mut(prefix) {
                     words.remove(word);
                     if words.is_empty() {
@@ -102,6 +135,16 @@ impl Dictionary {
         }
     }
 
+    /// Iterate over the prefixes of `word` up to `max_length` characters long
+    fn prefixes(word: &str, max_length: usize) -> impl Iterator<Item = &str> {
+        word.char_indices()
+            .skip(1)
+            .map(|(i, _)| i)
+            .chain(std::iter::once(word.len()))
+            .take(max_length)
+            .map(move |end| &word[..end])
+    }
+
     /// Get word entry
     pub fn get_word(&self, word: &str) -> Option<&DictionaryEntry> {
         self.entries.get(word)
@@ -112,7 +155,7 @@ impl Dictionary {
         let mut results = Vec::new();
 
         // Check prefix cache first
-        if prefix.len() <= self.max_prefix_length {
+        if prefix.chars().count() <= self.max_prefix_length {
             if let Some(cached) = self.prefix_cache.get(prefix) {
                 results.extend(cached.iter().filter_map(|word| self.entries.get(word)));
                 return results;
@@ -143,70 +186,47 @@ impl Dictionary {
     }
 }
 
-// #[cfg(test)]
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
-//     use std::io::Write;
-//     // use tempfile::NamedTempFile;
-//
-//     #[test]
-//     fn test_dictionary_basic() {
-//         let mut dict = Dictionary::new();
-//
-//         // Add words
-//         dict.add_word("hello", 0.5, false);
-//         dict.add_word("help", 0.3, false);
-//         dict.add_word("world", 0.4, false);
-//
-//         // Test word lookup
-//         assert!(dict.get_word("hello").is_some());
-//         assert_eq!(dict.get_word("hello").unwrap().frequency, 0.5);
-//
-//         // Test prefix search
-//         let results = dict.find_words_with_prefix("hel");
-//         assert_eq!(results.len(), 2);
-//         assert!(results.iter().any(|e| e.text == "hello"));
-//         assert!(results.iter().any(|e| e.text == "help"));
-//     }
-//
-//     // #[test]
-//     // fn test_dictionary_file() -> io::Result<()> {
-//     //     // Create temporary dictionary file
-//     //     let mut temp_file = NamedTempFile::new()?;
-//     //     writeln!(temp_file, "hello\t0.5")?;
-//     //     writeln!(temp_file, "help\t0.3")?;
-//     //     writeln!(temp_file, "world\t0.4")?;
-//     //
-//     //     // Load dictionary
-//     //     let mut dict = Dictionary::new();
-//     //     dict.load(temp_file.path())?;
-//     //
-//     //     assert_eq!(dict.word_count(), 3);
-//     //     assert!(dict.get_word("hello").is_some());
-//     //     assert!(dict.get_word("help").is_some());
-//     //     assert!(dict.get_word("world").is_some());
-//     //
-//     //     Ok(())
-//     // }
-//
-//     // #[test]
-//     // fn test_dictionary_remove() {
-//     //     let mut dict = Dictionary::new();
-//     //
-//     //     // Add and remove words
-//     //     dict.add_word("hello", 0.5, false);
-//     //     dict.add_word("help", 0.3, false);
-//     //     assert_eq!(dict.word_count(), 2);
-//     //
-//     //     dict.remove_word("hello");
-//     //     assert_eq!(dict.word_count(), 1);
-//     //     assert!(dict.get_word("hello").is_none());
-//     //     assert!(dict.get_word("help").is_some());
-//     //
-//     //     // Test prefix cache update
-//     //     let results = dict.find_words_with_prefix("hel");
-//     //     assert_eq!(results.len(), 1);
-//     //     assert_eq!(results[0].text, "help");
-//     // }
-// }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dictionary_basic() {
+        let mut dict = Dictionary::new();
+
+        // Add words
+        dict.add_word("hello", 0.5, false);
+        dict.add_word("help", 0.3, false);
+        dict.add_word("world", 0.4, false);
+
+        // Test word lookup
+        assert!(dict.get_word("hello").is_some());
+        assert_eq!(dict.get_word("hello").unwrap().frequency, 0.5);
+
+        // Test prefix search
+        let results = dict.find_words_with_prefix("hel");
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().any(|e| e.text == "hello"));
+        assert!(results.iter().any(|e| e.text == "help"));
+
+        // Prefixes are measured in characters, not bytes
+        dict.add_word("café", 0.1, false);
+        assert_eq!(dict.find_words_with_prefix("caf").len(), 1);
+        dict.remove_word("café");
+        assert!(dict.find_words_with_prefix("caf").is_empty());
+    }
+
+    #[test]
+    fn test_dictionary_load_frequencies() {
+        let list = "# comment\nthe\t60\n20\tof\nand 10\nzebra\n\nthe\t9\nbad line here\n";
+        let mut dict = Dictionary::new();
+        dict.load_from_reader(list.as_bytes()).unwrap();
+
+        assert_eq!(dict.word_count(), 4);
+        let frequency = |word| dict.get_word(word).unwrap().frequency;
+        assert!((frequency("the") - 0.69).abs() < 1e-9);
+        assert!((frequency("of") - 0.2).abs() < 1e-9);
+        assert!((frequency("and") - 0.1).abs() < 1e-9);
+        assert!((frequency("zebra") - 0.01).abs() < 1e-9);
+    }
+}
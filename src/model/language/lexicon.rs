@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// Words the user has typed, with the number of times each was used
+///
+/// The lexicon is kept apart from the base dictionary so that it can be
+/// saved, inspected and edited on its own. It is stored as a plain
+/// `word<TAB>count` list.
+#[derive(Debug, Clone, Default)]
+pub struct PersonalLexicon {
+    /// Number of times each word has been typed
+    counts: HashMap<String, u32>,
+    /// File the lexicon is saved to
+    path: Option<PathBuf>,
+    /// Whether there are changes not yet saved
+    modified: bool,
+}
+
+impl PersonalLexicon {
+    /// Name of the lexicon file in the user's Dasher configuration directory
+    pub const FILE_NAME: &'static str = "lexicon.txt";
+
+    /// Create an empty lexicon that is not saved anywhere
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the path of the lexicon in the user's Dasher configuration directory
+    pub fn user_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|config_dir| config_dir.join("dasher").join(Self::FILE_NAME))
+    }
+
+    /// Get the file the lexicon is saved to
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Set the file the lexicon is saved to
+    pub fn set_path(&mut self, path: Option<PathBuf>) {
+        self.path = path;
+    }
+
+    /// Load the lexicon from `path`, which becomes the file it is saved to
+    ///
+    /// The words loaded replace those in the lexicon. A missing file gives an
+    /// empty lexicon.
+    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        self.counts.clear();
+        self.path = Some(path.to_path_buf());
+        self.modified = false;
+
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            let mut fields = line.trim().split('\t');
+            let Some(word) = fields.next().filter(|word| !word.is_empty()) else {
+                continue;
+            };
+            let count = fields.next().and_then(|count| count.trim().parse().ok()).unwrap_or(1);
+            *self.counts.entry(word.to_string()).or_insert(0) += count;
+        }
+
+        Ok(())
+    }
+
+    /// Save the lexicon to its file, if it has one and has changed
+    pub fn save(&mut self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if !self.modified {
+            return Ok(());
+        }
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut words: Vec<_> = self.counts.iter().collect();
+        words.sort();
+        let mut file = File::create(path)?;
+        for (word, count) in words {
+            writeln!(file, "{}\t{}", word, count)?;
+        }
+
+        self.modified = false;
+        Ok(())
+    }
+
+    /// Record one use of `word`, adding it if it is new
+    pub fn record_word(&mut self, word: &str) {
+        if word.is_empty() {
+            return;
+        }
+        let count = self.counts.entry(word.to_string()).or_insert(0);
+        *count = count.saturating_add(1);
+        self.modified = true;
+    }
+
+    /// Remove `word` from the lexicon, returning whether it was there
+    pub fn remove_word(&mut self, word: &str) -> bool {
+        let removed = self.counts.remove(word).is_some();
+        self.modified |= removed;
+        removed
+    }
+
+    /// Get the number of times `word` has been typed
+    pub fn count(&self, word: &str) -> u32 {
+        self.counts.get(word).copied().unwrap_or(0)
+    }
+
+    /// Get the probability-like weight given to `word` when predicting
+    ///
+    /// A word used once already gets half the weight of a certain prediction,
+    /// so that names and jargon are predictable straight after first use.
+    pub fn frequency(&self, word: &str) -> f64 {
+        let count = self.count(word) as f64;
+        count / (count + 1.0)
+    }
+
+    /// Iterate over the words starting with `prefix`
+    pub fn words_with_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.counts.keys().filter(move |word| word.starts_with(prefix)).map(String::as_str)
+    }
+
+    /// Get the number of words in the lexicon
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Check whether the lexicon is empty
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Remove every word from the lexicon
+    pub fn clear(&mut self) {
+        self.modified |= !self.counts.is_empty();
+        self.counts.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_lexicon_persistence() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("dasher").join(PersonalLexicon::FILE_NAME);
+
+        let mut lexicon = PersonalLexicon::new();
+        lexicon.load(&path).unwrap();
+        assert!(lexicon.is_empty());
+
+        lexicon.record_word("Zoë");
+        lexicon.record_word("Zoë");
+        lexicon.record_word("zeugma");
+        assert_eq!(lexicon.count("Zoë"), 2);
+        assert!(lexicon.frequency("Zoë") > lexicon.frequency("zeugma"));
+        lexicon.save().unwrap();
+
+        let mut loaded = PersonalLexicon::new();
+        loaded.load(&path).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.count("Zoë"), 2);
+        assert_eq!(loaded.words_with_prefix("ze").collect::<Vec<_>>(), vec!["zeugma"]);
+
+        assert!(loaded.remove_word("zeugma"));
+        assert!(!loaded.remove_word("zeugma"));
+        loaded.save().unwrap();
+        loaded.load(&path).unwrap();
+        assert_eq!(loaded.len(), 1);
+    }
+}
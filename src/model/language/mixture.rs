@@ -0,0 +1,289 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use super::{normalize_counts, Context, ContextTable, LanguageModel};
+
+/// A context of the mixture
+#[derive(Debug, Clone)]
+struct MixtureContext {
+    /// Context of each component model
+    components: Vec<Context>,
+    /// Last symbol entered, which selects the weights
+    last: Option<char>,
+}
+
+/// Language model mixing the predictions of several component models
+///
+/// Each component has a weight, learned separately for each context (the
+/// symbol before the one being predicted). After every symbol learned, the
+/// weights move towards the components that gave it the highest probability,
+/// so a blend of, say, a word model, a character model and a domain model
+/// follows whichever predicts best in each context.
+pub struct MixtureLanguageModel {
+    /// Component models
+    models: Vec<Box<dyn LanguageModel>>,
+    /// Initial weight of each component, used for contexts not yet seen
+    prior: Vec<f64>,
+    /// Learned weights of each component, by the symbol before the prediction
+    weights: HashMap<Option<char>, Vec<f64>>,
+    /// Character of each symbol, in symbol index order
+    symbols: Vec<char>,
+    /// Contexts of the mixture
+    contexts: ContextTable<MixtureContext>,
+    /// Reusable buffer for each component's counts
+    scratch: RefCell<Vec<u32>>,
+}
+
+impl Default for MixtureLanguageModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MixtureLanguageModel {
+    /// Total of the counts used when learning weights
+    const NORMALIZATION: u32 = 1 << 16;
+
+    /// Fraction of the weight shared equally after each update, so that no
+    /// component is ever ruled out entirely
+    pub const SHARE: f64 = 0.02;
+
+    /// Create an empty mixture
+    pub fn new() -> Self {
+        Self {
+            models: Vec::new(),
+            prior: Vec::new(),
+            weights: HashMap::new(),
+            symbols: Vec::new(),
+            contexts: ContextTable::new(),
+            scratch: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Add a component model with an initial weight
+    ///
+    /// The model is given an empty context for each context of the mixture.
+    pub fn add_model(&mut self, mut model: Box<dyn LanguageModel>, weight: f64) {
+        let weight = weight.max(0.0);
+        model.set_symbols(&self.symbols);
+        for context in self.contexts.values_mut() {
+            context.components.push(model.create_empty_context());
+        }
+        self.models.push(model);
+        self.prior.push(weight);
+        for weights in self.weights.values_mut() {
+            weights.push(weight);
+        }
+    }
+
+    /// Get the number of component models
+    pub fn len(&self) -> usize {
+        self.models.len()
+    }
+
+    /// Check whether the mixture has no component models
+    pub fn is_empty(&self) -> bool {
+        self.models.is_empty()
+    }
+
+    /// Get a component model
+    pub fn model_mut(&mut self, index: usize) -> Option<&mut (dyn LanguageModel + 'static)> {
+        self.models.get_mut(index).map(|model| model.as_mut())
+    }
+
+    /// Get the normalised weight of each component when predicting after `context`
+    pub fn weights(&self, context: Context) -> Vec<f64> {
+        let last = self.contexts.get(context).and_then(|context| context.last);
+        let weights = self.weights_for(last);
+        let total: f64 = weights.iter().sum();
+        if total > 0.0 {
+            weights.iter().map(|weight| weight / total).collect()
+        } else {
+            vec![1.0 / weights.len().max(1) as f64; weights.len()]
+        }
+    }
+
+    /// Get the raw weights for the context ending in `last`
+    fn weights_for(&self, last: Option<char>) -> &[f64] {
+        self.weights.get(&last).unwrap_or(&self.prior)
+    }
+
+    /// Move the weights for `context` towards the components that best
+    /// predicted `symbol`
+    fn learn_weights(&mut self, context: &MixtureContext, symbol: usize) {
+        let mut scratch = self.scratch.borrow_mut();
+        let uniform = 1.0 / self.symbols.len().max(1) as f64;
+        let mut weights = self.weights_for(context.last).to_vec();
+
+        for ((model, weight), &component) in self.models.iter().zip(weights.iter_mut()).zip(&context.components) {
+            model.get_probs(component, Self::NORMALIZATION, &mut scratch);
+            let prob = if scratch.iter().all(|&count| count == 0) {
+                uniform
+            } else {
+                scratch.get(symbol).map_or(0.0, |&count| count as f64 / Self::NORMALIZATION as f64)
+            };
+            *weight *= prob;
+        }
+
+        let total: f64 = weights.iter().sum();
+        let count = weights.len() as f64;
+        for weight in &mut weights {
+            let share = if total > 0.0 { *weight / total } else { 1.0 / count };
+            *weight = (1.0 - Self::SHARE) * share + Self::SHARE / count;
+        }
+        self.weights.insert(context.last, weights);
+    }
+}
+
+impl LanguageModel for MixtureLanguageModel {
+    fn as_any(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn set_symbols(&mut self, symbols: &[char]) {
+        self.symbols = symbols.to_vec();
+        for model in &mut self.models {
+            model.set_symbols(symbols);
+        }
+    }
+
+    fn create_empty_context(&mut self) -> Context {
+        let components = self.models.iter_mut().map(|model| model.create_empty_context()).collect();
+        self.contexts.insert(MixtureContext { components, last: None })
+    }
+
+    fn clone_context(&mut self, context: Context) -> Context {
+        let Some(original) = self.contexts.get(context).cloned() else {
+            return self.create_empty_context();
+        };
+        let components = self.models.iter_mut().zip(&original.components)
+            .map(|(model, &component)| model.clone_context(component))
+            .collect();
+        self.contexts.insert(MixtureContext { components, last: original.last })
+    }
+
+    fn release_context(&mut self, context: Context) {
+        if let Some(context) = self.contexts.remove(context) {
+            for (model, component) in self.models.iter_mut().zip(context.components) {
+                model.release_context(component);
+            }
+        }
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all, fields(models = self.models.len())))]
+    fn get_probs(&self, context: Context, norm: u32, probs: &mut Vec<u32>) {
+        probs.clear();
+        probs.resize(self.symbols.len(), 0);
+        let Some(mixture_context) = self.contexts.get(context) else {
+            return;
+        };
+
+        let mut scratch = self.scratch.borrow_mut();
+        for ((model, weight), &component) in self.models.iter().zip(self.weights(context)).zip(&mixture_context.components) {
+            model.get_probs(component, norm, &mut scratch);
+            for (prob, &count) in probs.iter_mut().zip(scratch.iter()) {
+                *prob = prob.saturating_add((count as f64 * weight) as u32);
+            }
+        }
+
+        normalize_counts(probs, norm);
+    }
+
+    fn enter_symbol(&mut self, context: Context, symbol: usize) {
+        let Some(mixture_context) = self.contexts.get_mut(context) else {
+            return;
+        };
+        mixture_context.last = self.symbols.get(symbol).copied();
+        for (model, &component) in self.models.iter_mut().zip(&mixture_context.components) {
+            model.enter_symbol(component, symbol);
+        }
+    }
+
+    fn learn_symbol(&mut self, context: Context, symbol: usize) {
+        let Some(mixture_context) = self.contexts.get(context).cloned() else {
+            return;
+        };
+        if symbol >= self.symbols.len() {
+            return;
+        }
+        self.learn_weights(&mixture_context, symbol);
+
+        for (model, &component) in self.models.iter_mut().zip(&mixture_context.components) {
+            model.learn_symbol(component, symbol);
+        }
+        if let Some(mixture_context) = self.contexts.get_mut(context) {
+            mixture_context.last = Some(self.symbols[symbol]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Model that always predicts the same symbol
+    struct FixedModel(char, Vec<char>);
+
+    impl LanguageModel for FixedModel {
+        fn set_symbols(&mut self, symbols: &[char]) {
+            self.1 = symbols.to_vec();
+        }
+        fn create_empty_context(&mut self) -> Context { Context::new(0) }
+        fn clone_context(&mut self, context: Context) -> Context { context }
+        fn release_context(&mut self, _context: Context) {}
+        fn get_probs(&self, _context: Context, norm: u32, probs: &mut Vec<u32>) {
+            probs.clear();
+            probs.extend(self.1.iter().map(|&c| if c == self.0 { norm } else { 0 }));
+        }
+        fn enter_symbol(&mut self, _context: Context, _symbol: usize) {}
+        fn learn_symbol(&mut self, _context: Context, _symbol: usize) {}
+        fn as_any(&mut self) -> &mut dyn std::any::Any { self }
+    }
+
+    #[test]
+    fn test_mixture_learns_weights_per_context() {
+        let mut mixture = MixtureLanguageModel::new();
+        let symbols = ['a', 'b', 'c', 'x', 'y', 'z'];
+        mixture.set_symbols(&symbols);
+        mixture.add_model(Box::new(FixedModel('a', Vec::new())), 1.0);
+        mixture.add_model(Box::new(FixedModel('b', Vec::new())), 1.0);
+
+        let context = mixture.create_empty_context();
+        let mut probs = Vec::new();
+        mixture.get_probs(context, 1000, &mut probs);
+        assert_eq!(probs, vec![500, 500, 0, 0, 0, 0]);
+
+        // After 'x' the text always continues with 'a', after 'y' with 'b'
+        let index = |c| symbols.iter().position(|&symbol| symbol == c).unwrap();
+        for _ in 0..10 {
+            let training = mixture.create_empty_context();
+            for c in "xaybx".chars() {
+                mixture.learn_symbol(training, index(c));
+            }
+            mixture.release_context(training);
+        }
+
+        let after = |mixture: &mut MixtureLanguageModel, c| {
+            let context = mixture.create_empty_context();
+            mixture.enter_symbol(context, index(c));
+            context
+        };
+        let x = after(&mut mixture, 'x');
+        let y = after(&mut mixture, 'y');
+        let z = after(&mut mixture, 'z');
+        assert!(mixture.weights(x)[0] > 0.9);
+        assert!(mixture.weights(y)[1] > 0.9);
+        mixture.get_probs(x, 1000, &mut probs);
+        assert!(probs[0] > 900);
+        assert_eq!(probs.iter().sum::<u32>(), 1000);
+
+        // Unseen contexts keep the initial weights
+        assert_eq!(mixture.weights(z), vec![0.5, 0.5]);
+
+        // Clones follow their own symbols
+        let clone = mixture.clone_context(x);
+        mixture.enter_symbol(clone, index('y'));
+        assert!(mixture.weights(clone)[1] > 0.9);
+        assert!(mixture.weights(x)[0] > 0.9);
+    }
+}
@@ -1,62 +1,179 @@
 mod ppm;
 mod dictionary;
+mod mixture;
+mod lexicon;
+mod bigram;
+mod cache;
+mod context;
+mod arpa;
+mod neural;
+#[cfg(feature = "candle")]
+mod candle;
 
 pub use ppm::{PPMLanguageModel, PPMOrder, PPMNode};
 pub use dictionary::Dictionary;
-use std::collections::{HashMap, HashSet};
+pub use mixture::MixtureLanguageModel;
+pub use lexicon::PersonalLexicon;
+pub use bigram::WordBigramModel;
+pub use cache::{CacheStats, ProbabilityCache};
+pub use context::{enter_text, Context, ContextTable, TextContexts};
+pub use arpa::ArpaLanguageModel;
+pub use neural::{NeuralLanguageModel, NeuralPredictor, NeuralStats};
+#[cfg(feature = "candle")]
+pub use candle::CandleCharModel;
+use std::cell::RefCell;
+use std::collections::HashSet;
 
 /// Language model trait
+///
+/// A model predicts the next symbol of the alphabet from a [`Context`], an
+/// opaque handle to what the model keeps about the symbols before it.
+/// Symbols are identified by their index in the alphabet, as given to
+/// [`Self::set_symbols`]. A context is created empty, extended symbol by
+/// symbol, either just following the text or learning from it, and must
+/// be released once it is no longer needed.
 pub trait LanguageModel {
-    /// Create an empty context (stub)
-    fn create_empty_context(&mut self) -> String { String::new() }
-    /// Get probability distribution for next symbol
-    fn get_probs(&self, context: &str) -> HashMap<char, f64>;
+    /// Set the symbols predicted, as the character of each in symbol index order
+    ///
+    /// Contexts created before the symbols change keep the symbols already
+    /// entered into them.
+    fn set_symbols(&mut self, symbols: &[char]);
 
-    /// Enter symbol into model
-    fn enter_symbol(&mut self, symbol: char);
+    /// Create a context with nothing before it
+    fn create_empty_context(&mut self) -> Context;
 
-    /// Reset model state
-    fn reset(&mut self);
+    /// Create a copy of `context`, to be extended separately
+    fn clone_context(&mut self, context: Context) -> Context;
+
+    /// Release `context`; its handle must not be used again
+    fn release_context(&mut self, context: Context);
+
+    /// Get the probability distribution for the symbol following `context`
+    ///
+    /// On return `probs` holds one count per symbol, summing to `norm`, or
+    /// all zeros if the model makes no prediction. The vector is reused
+    /// between calls so that no allocation is needed once it has grown to
+    /// the size of the alphabet.
+    fn get_probs(&self, context: Context, norm: u32, probs: &mut Vec<u32>);
+
+    /// Extend `context` with `symbol` without learning from it
+    fn enter_symbol(&mut self, context: Context, symbol: usize);
+
+    /// Extend `context` with `symbol`, learning that it follows the context
+    fn learn_symbol(&mut self, context: Context, symbol: usize);
+
+    /// Get the statistics of the model's probability cache, if it has one
+    fn cache_stats(&self) -> Option<CacheStats> {
+        None
+    }
+
+    /// Check whether the model predicts faster in batches, and so wants
+    /// [`Self::prefetch`] called
+    fn predicts_in_batches(&self) -> bool {
+        false
+    }
+
+    /// Predict the symbol following each of `contexts` at once, ready for
+    /// [`Self::get_probs`]
+    ///
+    /// Called with the contexts of the nodes about to be expanded in a frame.
+    fn prefetch(&mut self, _contexts: &[Context]) {}
 
     /// For downcasting
     fn as_any(&mut self) -> &mut dyn std::any::Any;
 }
 
+/// Scale `counts` so that they sum to `norm`
+///
+/// Any rounding error goes to the largest count. Counts summing to zero are
+/// left alone.
+pub(crate) fn normalize_counts(counts: &mut [u32], norm: u32) {
+    let total: u64 = counts.iter().map(|&count| count as u64).sum();
+    if total == 0 {
+        return;
+    }
+
+    let mut assigned = 0u64;
+    let mut largest = 0;
+    for i in 0..counts.len() {
+        counts[i] = (counts[i] as u64 * norm as u64 / total) as u32;
+        assigned += counts[i] as u64;
+        if counts[i] > counts[largest] {
+            largest = i;
+        }
+    }
+    counts[largest] += (norm as u64 - assigned) as u32;
+}
+
+/// One of the two PPM models of a [`CombinedLanguageModel`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LanguageLayer {
+    /// Statistics trained from corpora
+    Base,
+    /// Statistics adapted to what the user writes
+    User,
+}
+
 /// Combined language model using PPM and dictionary
+///
+/// Corpus training and the user's own writing are learned by two separate
+/// PPM models, blended when predicting, so the user's adaptation can be
+/// cleared or exported without touching the trained statistics.
 pub struct CombinedLanguageModel {
-    /// PPM model
-    ppm: PPMLanguageModel,
+    /// PPM model trained from corpora
+    base: PPMLanguageModel,
+    /// PPM model adapted to what the user writes
+    user: PPMLanguageModel,
+    /// Layer that learning goes into
+    learning_layer: LanguageLayer,
+    /// Weight of the user layer where it makes a prediction (0-1)
+    user_weight: f64,
     /// Dictionary
     dictionary: Dictionary,
+    /// Words the user has typed
+    lexicon: PersonalLexicon,
+    /// Whether typed words are added to the lexicon
+    learn_words: bool,
+    /// Word bigrams, for predicting the next word
+    bigrams: WordBigramModel,
     /// Weight for PPM model (0-1)
     ppm_weight: f64,
-    /// Current word buffer
-    current_word: String,
-    /// Rolling context buffer for PPM
-    context_buffer: String,
+    /// Text of each context
+    contexts: TextContexts,
     /// Word separator characters
     word_separators: HashSet<char>,
+    /// Distributions already computed, cleared whenever the predictions may change
+    cache: RefCell<ProbabilityCache>,
+    /// Distribution of the user layer, kept to avoid allocating
+    user_probs: RefCell<Vec<u32>>,
 }
 
 impl CombinedLanguageModel {
+    /// Number of characters kept of each context
+    const CONTEXT_CHARS: usize = 64;
+
+    /// Characters that end a word unless configured otherwise
+    pub const DEFAULT_WORD_SEPARATORS: &'static [char] = &[' ', '\t', '\n', '.', ',', '!', '?'];
+
+    /// Weight of the user layer unless set otherwise
+    pub const DEFAULT_USER_WEIGHT: f64 = 0.4;
+
     /// Create a new combined language model
     pub fn new(max_order: PPMOrder) -> Self {
-        let mut word_separators = HashSet::new();
-        word_separators.insert(' ');
-        word_separators.insert('\t');
-        word_separators.insert('\n');
-        word_separators.insert('.');
-        word_separators.insert(',');
-        word_separators.insert('!');
-        word_separators.insert('?');
-
         Self {
-            ppm: PPMLanguageModel::new(max_order),
+            base: PPMLanguageModel::new(max_order),
+            user: PPMLanguageModel::new(max_order),
+            learning_layer: LanguageLayer::User,
+            user_weight: Self::DEFAULT_USER_WEIGHT,
             dictionary: Dictionary::new(),
+            lexicon: PersonalLexicon::new(),
+            learn_words: true,
+            bigrams: WordBigramModel::new(),
             ppm_weight: 0.7,
-            current_word: String::new(),
-            context_buffer: String::new(),
-            word_separators,
+            contexts: TextContexts::new(Self::CONTEXT_CHARS),
+            word_separators: Self::DEFAULT_WORD_SEPARATORS.iter().copied().collect(),
+            cache: RefCell::new(ProbabilityCache::default()),
+            user_probs: RefCell::new(Vec::new()),
         }
     }
 
@@ -64,12 +181,45 @@ impl CombinedLanguageModel {
     #[allow(dead_code)]
     pub fn set_ppm_weight(&mut self, weight: f64) {
         self.ppm_weight = weight.clamp(0.0, 1.0);
+        self.cache.get_mut().clear();
     }
 
     /// Add word separator
-    #[allow(dead_code)]
     pub fn add_word_separator(&mut self, separator: char) {
         self.word_separators.insert(separator);
+        self.cache.get_mut().clear();
+    }
+
+    /// Remove word separator
+    pub fn remove_word_separator(&mut self, separator: char) {
+        self.word_separators.remove(&separator);
+        self.cache.get_mut().clear();
+    }
+
+    /// Get the characters that end a word
+    pub fn word_separators(&self) -> &HashSet<char> {
+        &self.word_separators
+    }
+
+    /// Replace the characters that end a word
+    pub fn set_word_separators(&mut self, separators: &HashSet<char>) {
+        self.word_separators = separators.clone();
+        self.cache.get_mut().clear();
+    }
+
+    /// Get the maximum number of distributions cached (0 if caching is off)
+    pub fn cache_capacity(&self) -> usize {
+        self.cache.borrow().capacity()
+    }
+
+    /// Set the maximum number of distributions cached, 0 to turn caching off
+    pub fn set_cache_capacity(&mut self, capacity: usize) {
+        self.cache.get_mut().set_capacity(capacity);
+    }
+
+    /// Reset the cache's hit and miss counts
+    pub fn reset_cache_stats(&mut self) {
+        self.cache.get_mut().reset_stats();
     }
 
     /// Get dictionary reference
@@ -80,19 +230,124 @@ impl CombinedLanguageModel {
 
     /// Get dictionary mutable reference
     pub fn dictionary_mut(&mut self) -> &mut Dictionary {
+        self.cache.get_mut().clear();
         &mut self.dictionary
     }
 
-    /// Get PPM model reference
-    #[allow(dead_code)]
-    pub fn ppm(&self) -> &PPMLanguageModel {
-        &self.ppm
+    /// Get the personal lexicon
+    pub fn lexicon(&self) -> &PersonalLexicon {
+        &self.lexicon
     }
 
-    /// Get PPM model mutable reference
-    #[allow(dead_code)]
-    pub fn ppm_mut(&mut self) -> &mut PPMLanguageModel {
-        &mut self.ppm
+    /// Get the personal lexicon mutably
+    pub fn lexicon_mut(&mut self) -> &mut PersonalLexicon {
+        self.cache.get_mut().clear();
+        &mut self.lexicon
+    }
+
+    /// Check whether typed words are added to the lexicon
+    pub fn learn_words(&self) -> bool {
+        self.learn_words
+    }
+
+    /// Set whether typed words are added to the lexicon
+    pub fn set_learn_words(&mut self, learn_words: bool) {
+        self.learn_words = learn_words;
+    }
+
+    /// Predict up to `max` words following `context`, most probable first
+    ///
+    /// Words are only predicted when `context` is empty or ends with a word
+    /// separator, from the last complete word of `context`. Each word comes
+    /// with its probability of being next.
+    pub fn predict_next_words(&self, context: &str, max: usize) -> Vec<(String, f64)> {
+        if context.chars().last().is_some_and(|c| !self.word_separators.contains(&c)) {
+            return Vec::new();
+        }
+        let previous = context
+            .split(|c| self.word_separators.contains(&c))
+            .rfind(|word| !word.is_empty())
+            .unwrap_or("");
+        self.bigrams.predict(previous, max)
+    }
+
+    /// Get where the word being written starts in `text`
+    fn word_start(&self, text: &str) -> usize {
+        text.char_indices()
+            .rfind(|(_, c)| self.word_separators.contains(c))
+            .map_or(0, |(i, c)| i + c.len_utf8())
+    }
+
+    /// Get the last complete word of `text`
+    fn last_word<'a>(&self, text: &'a str) -> &'a str {
+        text.rsplit(|c| self.word_separators.contains(&c))
+            .find(|word| !word.is_empty())
+            .unwrap_or("")
+    }
+
+    /// Get the PPM model of a layer
+    pub fn layer(&self, layer: LanguageLayer) -> &PPMLanguageModel {
+        match layer {
+            LanguageLayer::Base => &self.base,
+            LanguageLayer::User => &self.user,
+        }
+    }
+
+    /// Get the PPM model of a layer mutably
+    pub fn layer_mut(&mut self, layer: LanguageLayer) -> &mut PPMLanguageModel {
+        self.cache.get_mut().clear();
+        match layer {
+            LanguageLayer::Base => &mut self.base,
+            LanguageLayer::User => &mut self.user,
+        }
+    }
+
+    /// Get the layer that learning goes into
+    pub fn learning_layer(&self) -> LanguageLayer {
+        self.learning_layer
+    }
+
+    /// Set the layer that learning goes into
+    ///
+    /// Learning goes into the user layer unless set otherwise; corpus
+    /// training switches to the base layer while it runs.
+    pub fn set_learning_layer(&mut self, layer: LanguageLayer) {
+        self.learning_layer = layer;
+    }
+
+    /// Get the weight of the user layer where it makes a prediction
+    pub fn user_weight(&self) -> f64 {
+        self.user_weight
+    }
+
+    /// Set the weight of the user layer where it makes a prediction (0-1)
+    pub fn set_user_weight(&mut self, weight: f64) {
+        self.user_weight = weight.clamp(0.0, 1.0);
+        self.cache.get_mut().clear();
+    }
+
+    /// Forget everything learned from the user: the user layer and the lexicon
+    pub fn reset_personalization(&mut self) {
+        self.user.clear();
+        self.lexicon.clear();
+        self.cache.get_mut().clear();
+    }
+
+    /// Blend the user layer's distribution `user` into the base layer's `probs`
+    ///
+    /// Where only one layer makes a prediction it is used as it is.
+    fn blend_user_probs(&self, probs: &mut [u32], user: &[u32], norm: u32) {
+        if user.iter().all(|&count| count == 0) {
+            return;
+        }
+        if probs.iter().all(|&count| count == 0) {
+            probs.copy_from_slice(user);
+            return;
+        }
+        for (prob, &user) in probs.iter_mut().zip(user) {
+            *prob = (*prob as f64 * (1.0 - self.user_weight) + user as f64 * self.user_weight) as u32;
+        }
+        normalize_counts(probs, norm);
     }
 }
 
@@ -101,56 +356,115 @@ impl LanguageModel for CombinedLanguageModel {
         self
     }
 
-    fn get_probs(&self, context: &str) -> HashMap<char, f64> {
-        // Get PPM probabilities
-        let mut probs = self.ppm.get_probs(context);
+    fn set_symbols(&mut self, symbols: &[char]) {
+        self.contexts.set_symbols(symbols);
+    }
+
+    fn create_empty_context(&mut self) -> Context {
+        self.contexts.create_empty()
+    }
+
+    fn clone_context(&mut self, context: Context) -> Context {
+        self.contexts.clone_context(context)
+    }
 
-        // Get dictionary predictions if we're building a word
-        if !self.current_word.is_empty() {
-            let dict_words = self.dictionary.find_words_with_prefix(&self.current_word);
+    fn release_context(&mut self, context: Context) {
+        self.contexts.release(context);
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all, fields(cached)))]
+    fn get_probs(&self, context: Context, norm: u32, probs: &mut Vec<u32>) {
+        let text = self.contexts.text(context);
+        let symbols = self.contexts.symbols();
+        let mut cache = self.cache.borrow_mut();
+        let cached = cache.get(text, symbols, norm, probs);
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("cached", cached);
+        if cached {
+            return;
+        }
+
+        // Get PPM probabilities, blending in the user's own
+        self.base.fill_probs(text, symbols, norm, probs);
+        let mut user_probs = self.user_probs.borrow_mut();
+        self.user.fill_probs(text, symbols, norm, &mut user_probs);
+        self.blend_user_probs(probs, &user_probs, norm);
+
+        // Add dictionary predictions if we're building a word
+        let current_word = &text[self.word_start(text)..];
+        if !current_word.is_empty() {
             let dict_weight = 1.0 - self.ppm_weight;
+            let prefix_len = current_word.chars().count();
 
-            for entry in dict_words {
-                if self.current_word.len() < entry.text.len() {
-                    let next_char = entry.text.chars().nth(self.current_word.len()).unwrap();
-                    let prob = entry.frequency * dict_weight;
-                    *probs.entry(next_char).or_insert(0.0) += prob;
+            for entry in self.dictionary.find_words_with_prefix(current_word) {
+                let Some(next_char) = entry.text.chars().nth(prefix_len) else {
+                    continue;
+                };
+                if let Some(index) = symbols.iter().position(|&c| c == next_char) {
+                    let count = (entry.frequency * dict_weight * norm as f64) as u32;
+                    probs[index] = probs[index].saturating_add(count);
                 }
             }
-        }
 
-        // Normalize probabilities
-        let total: f64 = probs.values().sum();
-        if total > 0.0 {
-            for prob in probs.values_mut() {
-                *prob /= total;
+            // Words the user has typed before
+            for word in self.lexicon.words_with_prefix(current_word) {
+                let Some(next_char) = word.chars().nth(prefix_len) else {
+                    continue;
+                };
+                if let Some(index) = symbols.iter().position(|&c| c == next_char) {
+                    let count = (self.lexicon.frequency(word) * dict_weight * norm as f64) as u32;
+                    probs[index] = probs[index].saturating_add(count);
+                }
             }
+
+            normalize_counts(probs, norm);
         }
 
-        probs
+        cache.insert(text, probs);
     }
 
-    fn enter_symbol(&mut self, symbol: char) {
-        // Update PPM model with context buffer
-        self.ppm.enter_symbol(&self.context_buffer, symbol);
-        // Update context buffer
-        let max_order = self.ppm.max_order().value() as usize;
-        self.context_buffer.push(symbol);
-        if self.context_buffer.len() > max_order {
-            self.context_buffer.remove(0);
-        }
-        // Update word buffer
-        if self.word_separators.contains(&symbol) {
-            self.current_word.clear();
-            self.context_buffer.clear(); // Reset context at word boundary
-        } else {
-            self.current_word.push(symbol);
-        }
+    fn cache_stats(&self) -> Option<CacheStats> {
+        Some(self.cache.borrow().stats())
     }
 
-    fn reset(&mut self) {
-        self.current_word.clear();
-        self.context_buffer.clear();
+    fn enter_symbol(&mut self, context: Context, symbol: usize) {
+        self.contexts.enter(context, symbol);
+    }
+
+    fn learn_symbol(&mut self, context: Context, symbol: usize) {
+        let Some(c) = self.contexts.symbol(symbol) else {
+            return;
+        };
+        // Learning changes the predictions
+        self.cache.get_mut().clear();
+
+        // The PPM model learns from the end of the word being written
+        let text = self.contexts.text(context);
+        let word_start = self.word_start(text);
+        let current_word = &text[word_start..];
+        let ppm = match self.learning_layer {
+            LanguageLayer::Base => &mut self.base,
+            LanguageLayer::User => &mut self.user,
+        };
+        let ppm_context = match ppm.max_order().value().max(0) as usize {
+            0 => "",
+            max_order => {
+                let start = current_word.char_indices().rev().nth(max_order - 1).map_or(0, |(i, _)| i);
+                &current_word[start..]
+            }
+        };
+        ppm.enter_symbol(ppm_context, c);
+
+        // A separator completes the word before it
+        if self.word_separators.contains(&c) && !current_word.is_empty() {
+            if self.learn_words {
+                self.lexicon.record_word(current_word);
+            }
+            let previous_word = self.last_word(&text[..word_start]);
+            self.bigrams.learn(previous_word, current_word);
+        }
+
+        self.contexts.enter(context, symbol);
     }
 }
 
@@ -158,27 +472,132 @@ impl LanguageModel for CombinedLanguageModel {
 mod tests {
     use super::*;
 
+    /// Learn each character of `text` in `context`
+    fn learn(model: &mut CombinedLanguageModel, context: Context, symbols: &[char], text: &str) {
+        for c in text.chars() {
+            let index = symbols.iter().position(|&symbol| symbol == c).unwrap();
+            model.learn_symbol(context, index);
+        }
+    }
+
     #[test]
     fn test_combined_model() {
         let mut model = CombinedLanguageModel::new(PPMOrder::Two);
+        let symbols: Vec<char> = "helowrdp ".chars().collect();
+        model.set_symbols(&symbols);
 
         // Add dictionary words
         model.dictionary_mut().add_word("hello", 0.5, false);
         model.dictionary_mut().add_word("help", 0.3, false);
 
         // Train PPM
-        for c in "hello world".chars() {
-            model.enter_symbol(c);
-        }
+        let training = model.create_empty_context();
+        learn(&mut model, training, &symbols, "hello world");
+        model.release_context(training);
 
         // Test predictions
-        model.current_word = "hel".to_string();
-        let probs = model.get_probs("hel");
-        assert!(probs.contains_key(&'l'));
-        assert!(probs.contains_key(&'p'));
-
-        // Test word separation
-        model.enter_symbol(' ');
-        assert_eq!(model.current_word, "");
+        let context = model.create_empty_context();
+        enter_text(&mut model, context, &symbols, "hel");
+        let index = |c| symbols.iter().position(|&s| s == c).unwrap();
+        let mut probs = Vec::new();
+        model.get_probs(context, 65536, &mut probs);
+        assert_eq!(probs.len(), symbols.len());
+        assert_eq!(probs.iter().sum::<u32>(), 65536);
+        assert!(probs[index('l')] > 0);
+        assert!(probs[index('p')] > 0);
+
+        // A clone is extended separately; after a separator no word is being written
+        let clone = model.clone_context(context);
+        model.enter_symbol(clone, index(' '));
+        assert_eq!(model.contexts.text(clone), "hel ");
+        assert_eq!(model.word_start(model.contexts.text(clone)), 4);
+        assert_eq!(model.last_word(model.contexts.text(clone)), "hel");
+        assert_eq!(model.word_start(model.contexts.text(context)), 0);
+    }
+
+    #[test]
+    fn test_probabilities_are_cached_until_learning() {
+        let mut model = CombinedLanguageModel::new(PPMOrder::Two);
+        let symbols: Vec<char> = "abc".chars().collect();
+        model.set_symbols(&symbols);
+        let context = model.create_empty_context();
+        learn(&mut model, context, &symbols, "abab");
+
+        let mut probs = Vec::new();
+        let mut cached = Vec::new();
+        model.get_probs(context, 65536, &mut probs);
+        model.get_probs(context, 65536, &mut cached);
+        assert_eq!(probs, cached);
+        let stats = model.cache_stats().unwrap();
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+
+        // Another context with the same text shares the cache, learning clears it
+        let same = model.create_empty_context();
+        enter_text(&mut model, same, &symbols, "abab");
+        model.get_probs(same, 65536, &mut cached);
+        assert_eq!(model.cache_stats().unwrap().hits, 2);
+        model.learn_symbol(same, 2);
+        model.get_probs(context, 65536, &mut cached);
+        assert_eq!(model.cache_stats().unwrap().misses, 2);
+
+        model.set_cache_capacity(0);
+        model.reset_cache_stats();
+        model.get_probs(context, 65536, &mut cached);
+        model.get_probs(context, 65536, &mut cached);
+        assert_eq!(model.cache_stats().unwrap().hits, 0);
+    }
+
+    #[test]
+    fn test_user_layer_is_kept_apart_from_base() {
+        let mut model = CombinedLanguageModel::new(PPMOrder::One);
+        let symbols: Vec<char> = "abc".chars().collect();
+        model.set_symbols(&symbols);
+        let context = model.create_empty_context();
+        model.set_learning_layer(LanguageLayer::Base);
+        learn(&mut model, context, &symbols, "abab");
+        model.set_learning_layer(LanguageLayer::User);
+        learn(&mut model, context, &symbols, "acac");
+        assert!(model.layer(LanguageLayer::Base).get_probs("a").contains_key(&'b'));
+        assert!(!model.layer(LanguageLayer::Base).get_probs("a").contains_key(&'c'));
+
+        // Both layers predict, the user's own writing with less weight
+        let after_a = model.create_empty_context();
+        enter_text(&mut model, after_a, &symbols, "a");
+        let mut probs = Vec::new();
+        model.get_probs(after_a, 65536, &mut probs);
+        assert!(probs[1] > probs[2] && probs[2] > 0);
+
+        let user = model.layer(LanguageLayer::User).to_json().unwrap();
+        model.reset_personalization();
+        assert_eq!(model.layer(LanguageLayer::User).node_count(), 0);
+        model.get_probs(after_a, 65536, &mut probs);
+        assert_eq!(probs, vec![0, 65536, 0]);
+
+        // An exported layer can be brought back
+        model.layer_mut(LanguageLayer::User).load_json(&user).unwrap();
+        model.get_probs(after_a, 65536, &mut probs);
+        assert!(probs[2] > 0);
+    }
+
+    #[test]
+    fn test_lexicon_learns_typed_words() {
+        let mut model = CombinedLanguageModel::new(PPMOrder::Two);
+        let symbols: Vec<char> = "lovxyzeta ".chars().collect();
+        model.set_symbols(&symbols);
+        let context = model.create_empty_context();
+        learn(&mut model, context, &symbols, "xylo xylo ");
+        assert_eq!(model.lexicon().count("xylo"), 2);
+
+        // The typed word now predicts its own continuation
+        let mut probs = Vec::new();
+        let word = model.create_empty_context();
+        enter_text(&mut model, word, &symbols, "xyl");
+        model.get_probs(word, 65536, &mut probs);
+        let o = symbols.iter().position(|&c| c == 'o').unwrap();
+        assert!(probs[o] > 65536 / 2);
+
+        model.set_learn_words(false);
+        learn(&mut model, context, &symbols, "zeta ");
+        assert_eq!(model.lexicon().count("zeta"), 0);
     }
 }
@@ -0,0 +1,405 @@
+//! Neural language models
+//!
+//! A small neural network can predict the next symbol better than PPM, but
+//! takes far longer, and how much longer depends on the device. A
+//! [`NeuralLanguageModel`] runs a [`NeuralPredictor`] on all the nodes
+//! expanded in a frame at once, and whenever inference takes longer than
+//! its latency budget it predicts with a PPM model for a while instead, so
+//! that zooming stays smooth on slow devices.
+
+use std::cell::{Cell, RefCell};
+use std::time::{Duration, Instant};
+
+use super::{normalize_counts, CacheStats, Context, ContextTable, LanguageModel, ProbabilityCache};
+use crate::logging::dasher_log;
+use crate::Result;
+
+/// Inference engine of a [`NeuralLanguageModel`]
+pub trait NeuralPredictor {
+    /// Get the number of characters before the symbol that predictions look at
+    fn context_length(&self) -> usize;
+
+    /// Predict the symbol following each of `histories`
+    ///
+    /// Returns, for each history in order, the probability of each of
+    /// `symbols` in order. The probabilities need not sum to one.
+    fn predict(&self, histories: &[&str], symbols: &[char]) -> Result<Vec<Vec<f32>>>;
+}
+
+/// Counts of the work done by a [`NeuralLanguageModel`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NeuralStats {
+    /// Number of times the predictor was run
+    pub batches: u64,
+    /// Number of contexts the predictor predicted for
+    pub predictions: u64,
+    /// Number of runs that failed or took longer than the latency budget
+    pub slow_batches: u64,
+    /// Number of distributions given by the fallback model
+    pub fallbacks: u64,
+}
+
+/// A context of the neural model
+#[derive(Debug, Clone)]
+struct NeuralContext {
+    /// Characters before the symbol predicted, as many as the predictor looks at
+    history: String,
+    /// Context of the fallback model
+    fallback: Context,
+}
+
+/// Language model predicting with a neural network, within a latency budget
+///
+/// Predictions are cached by the characters before the symbol. When a run
+/// of the network fails or takes longer than the budget, the fallback
+/// model predicts for [`Self::RETRY_AFTER`] before the network is tried
+/// again. The network's weights are fixed, so only the fallback learns.
+pub struct NeuralLanguageModel {
+    /// Network making the predictions
+    predictor: Box<dyn NeuralPredictor>,
+    /// Model predicting when the network is too slow
+    fallback: Box<dyn LanguageModel>,
+    /// Character of each symbol, in symbol index order
+    symbols: Vec<char>,
+    /// Contexts of the model
+    contexts: ContextTable<NeuralContext>,
+    /// Predictions of the network by history, as counts summing to [`Self::NORMALIZATION`]
+    cache: RefCell<ProbabilityCache>,
+    /// Longest a run of the network may take
+    latency_budget: Duration,
+    /// Time until which the fallback model predicts
+    fallback_until: Cell<Option<Instant>>,
+    /// Work done so far
+    stats: Cell<NeuralStats>,
+}
+
+impl NeuralLanguageModel {
+    /// Total of the cached counts
+    const NORMALIZATION: u32 = 1 << 24;
+
+    /// Number of distributions cached
+    const CACHE_CAPACITY: usize = 1024;
+
+    /// Default longest a run of the network may take
+    pub const DEFAULT_LATENCY_BUDGET: Duration = Duration::from_millis(20);
+
+    /// Time the fallback model predicts for after a slow run
+    pub const RETRY_AFTER: Duration = Duration::from_secs(2);
+
+    /// Create a model predicting with `predictor`, or `fallback` when it is too slow
+    pub fn new(predictor: Box<dyn NeuralPredictor>, fallback: Box<dyn LanguageModel>) -> Self {
+        Self {
+            predictor,
+            fallback,
+            symbols: Vec::new(),
+            contexts: ContextTable::new(),
+            cache: RefCell::new(ProbabilityCache::new(Self::CACHE_CAPACITY)),
+            latency_budget: Self::DEFAULT_LATENCY_BUDGET,
+            fallback_until: Cell::new(None),
+            stats: Cell::new(NeuralStats::default()),
+        }
+    }
+
+    /// Get the longest a run of the network may take
+    pub fn latency_budget(&self) -> Duration {
+        self.latency_budget
+    }
+
+    /// Set the longest a run of the network may take
+    pub fn set_latency_budget(&mut self, budget: Duration) {
+        self.latency_budget = budget;
+    }
+
+    /// Get the work done so far
+    pub fn stats(&self) -> NeuralStats {
+        self.stats.get()
+    }
+
+    /// Check whether the fallback model is predicting after a slow run
+    pub fn is_falling_back(&self) -> bool {
+        self.fallback_until.get().is_some_and(|until| Instant::now() < until)
+    }
+
+    /// Replace the network, forgetting the predictions of the previous one
+    pub fn set_predictor(&mut self, predictor: Box<dyn NeuralPredictor>) {
+        self.predictor = predictor;
+        self.cache.borrow_mut().clear();
+        self.fallback_until.set(None);
+    }
+
+    /// Get the model predicting when the network is too slow
+    pub fn fallback_mut(&mut self) -> &mut (dyn LanguageModel + 'static) {
+        self.fallback.as_mut()
+    }
+
+    /// Run the network on `histories`, caching its predictions
+    ///
+    /// Returns the counts predicted for each history, or `None` if the
+    /// fallback model should predict instead.
+    fn predict_batch(&self, histories: &[&str]) -> Option<Vec<Vec<u32>>> {
+        if histories.is_empty() || self.is_falling_back() {
+            return None;
+        }
+
+        let start = Instant::now();
+        let result = self.predictor.predict(histories, &self.symbols);
+        let elapsed = start.elapsed();
+
+        let mut stats = self.stats.get();
+        stats.batches += 1;
+        let predictions = match result {
+            Ok(predictions) if predictions.len() == histories.len() => Some(predictions),
+            Ok(_) => {
+                dasher_log!(Warn, "Neural language model gave the wrong number of predictions");
+                None
+            }
+            Err(e) => {
+                dasher_log!(Warn, "Neural language model failed: {}", e);
+                None
+            }
+        };
+        if predictions.is_none() || elapsed > self.latency_budget {
+            dasher_log!(Debug, "Neural language model took {:?}, falling back for {:?}", elapsed, Self::RETRY_AFTER);
+            stats.slow_batches += 1;
+            self.fallback_until.set(Some(Instant::now() + Self::RETRY_AFTER));
+        }
+
+        let counts = predictions.map(|predictions| {
+            let mut cache = self.cache.borrow_mut();
+            cache.select(&self.symbols, Self::NORMALIZATION);
+            histories.iter().zip(predictions).map(|(history, probabilities)| {
+                let mut counts = Self::counts(&probabilities, self.symbols.len());
+                if counts.iter().any(|&count| count > 0) {
+                    cache.insert(history, &counts);
+                    stats.predictions += 1;
+                } else {
+                    counts.clear();
+                }
+                counts
+            }).collect()
+        });
+        self.stats.set(stats);
+        counts
+    }
+
+    /// Turn the probabilities of `len` symbols into counts summing to
+    /// [`Self::NORMALIZATION`], or all zeros if they are unusable
+    fn counts(probabilities: &[f32], len: usize) -> Vec<u32> {
+        let total: f64 = probabilities.iter().map(|&p| p.max(0.0) as f64).sum();
+        if probabilities.len() != len || !total.is_finite() || total <= 0.0 {
+            return vec![0; len];
+        }
+        let mut counts: Vec<u32> = probabilities.iter()
+            .map(|&p| (p.max(0.0) as f64 / total * Self::NORMALIZATION as f64) as u32)
+            .collect();
+        normalize_counts(&mut counts, Self::NORMALIZATION);
+        counts
+    }
+
+    /// Add `symbol` to the history of `context`, returning its fallback context
+    fn push_symbol(&mut self, context: Context, symbol: usize) -> Option<Context> {
+        let c = self.symbols.get(symbol).copied();
+        let length = self.predictor.context_length();
+        let neural_context = self.contexts.get_mut(context)?;
+        let history = &mut neural_context.history;
+        history.extend(c);
+        let excess = history.chars().count().saturating_sub(length);
+        if excess > 0 {
+            let start = history.char_indices().nth(excess).map_or(history.len(), |(i, _)| i);
+            history.drain(..start);
+        }
+        Some(neural_context.fallback)
+    }
+}
+
+impl LanguageModel for NeuralLanguageModel {
+    fn as_any(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn set_symbols(&mut self, symbols: &[char]) {
+        self.symbols = symbols.to_vec();
+        self.fallback.set_symbols(symbols);
+    }
+
+    fn create_empty_context(&mut self) -> Context {
+        let fallback = self.fallback.create_empty_context();
+        self.contexts.insert(NeuralContext { history: String::new(), fallback })
+    }
+
+    fn clone_context(&mut self, context: Context) -> Context {
+        let Some(original) = self.contexts.get(context).cloned() else {
+            return self.create_empty_context();
+        };
+        let fallback = self.fallback.clone_context(original.fallback);
+        self.contexts.insert(NeuralContext { history: original.history, fallback })
+    }
+
+    fn release_context(&mut self, context: Context) {
+        if let Some(context) = self.contexts.remove(context) {
+            self.fallback.release_context(context.fallback);
+        }
+    }
+
+    fn get_probs(&self, context: Context, norm: u32, probs: &mut Vec<u32>) {
+        probs.clear();
+        probs.resize(self.symbols.len(), 0);
+        let Some(neural_context) = self.contexts.get(context) else {
+            return;
+        };
+
+        let history = neural_context.history.as_str();
+        if !self.cache.borrow_mut().get(history, &self.symbols, Self::NORMALIZATION, probs) {
+            match self.predict_batch(&[history]).and_then(|mut counts| counts.pop()).filter(|counts| !counts.is_empty()) {
+                Some(counts) => *probs = counts,
+                None => {
+                    let mut stats = self.stats.get();
+                    stats.fallbacks += 1;
+                    self.stats.set(stats);
+                    self.fallback.get_probs(neural_context.fallback, norm, probs);
+                    return;
+                }
+            }
+        }
+        normalize_counts(probs, norm);
+    }
+
+    fn enter_symbol(&mut self, context: Context, symbol: usize) {
+        if let Some(fallback) = self.push_symbol(context, symbol) {
+            self.fallback.enter_symbol(fallback, symbol);
+        }
+    }
+
+    fn learn_symbol(&mut self, context: Context, symbol: usize) {
+        if let Some(fallback) = self.push_symbol(context, symbol) {
+            self.fallback.learn_symbol(fallback, symbol);
+        }
+    }
+
+    fn cache_stats(&self) -> Option<CacheStats> {
+        Some(self.cache.borrow().stats())
+    }
+
+    fn predicts_in_batches(&self) -> bool {
+        true
+    }
+
+    fn prefetch(&mut self, contexts: &[Context]) {
+        let mut histories: Vec<&str> = contexts.iter()
+            .filter_map(|&context| self.contexts.get(context))
+            .map(|context| context.history.as_str())
+            .filter(|history| !self.cache.borrow().contains(history, &self.symbols, Self::NORMALIZATION))
+            .collect();
+        histories.sort_unstable();
+        histories.dedup();
+        self.predict_batch(&histories);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DasherError;
+
+    /// Predictor favouring the symbol after the last character, taking
+    /// `delay` for each batch and recording the size of each batch
+    struct NextLetter {
+        delay: Duration,
+        batches: std::rc::Rc<RefCell<Vec<usize>>>,
+    }
+
+    impl NeuralPredictor for NextLetter {
+        fn context_length(&self) -> usize {
+            2
+        }
+
+        fn predict(&self, histories: &[&str], symbols: &[char]) -> Result<Vec<Vec<f32>>> {
+            std::thread::sleep(self.delay);
+            self.batches.borrow_mut().push(histories.len());
+            histories.iter().map(|history| {
+                let last = history.chars().last().ok_or_else(|| DasherError::Other("empty history".to_string()))?;
+                let next = char::from_u32(last as u32 + 1);
+                Ok(symbols.iter().map(|&c| if Some(c) == next { 0.9 } else { 0.1 }).collect())
+            }).collect()
+        }
+    }
+
+    /// Fallback predicting the first symbol
+    struct First(usize);
+
+    impl LanguageModel for First {
+        fn set_symbols(&mut self, symbols: &[char]) { self.0 = symbols.len(); }
+        fn create_empty_context(&mut self) -> Context { Context::new(0) }
+        fn clone_context(&mut self, context: Context) -> Context { context }
+        fn release_context(&mut self, _context: Context) {}
+        fn get_probs(&self, _context: Context, norm: u32, probs: &mut Vec<u32>) {
+            probs.clear();
+            probs.resize(self.0, 0);
+            probs[0] = norm;
+        }
+        fn enter_symbol(&mut self, _context: Context, _symbol: usize) {}
+        fn learn_symbol(&mut self, _context: Context, _symbol: usize) {}
+        fn as_any(&mut self) -> &mut dyn std::any::Any { self }
+    }
+
+    fn model(delay: Duration) -> (NeuralLanguageModel, std::rc::Rc<RefCell<Vec<usize>>>) {
+        let batches = std::rc::Rc::new(RefCell::new(Vec::new()));
+        let predictor = NextLetter { delay, batches: batches.clone() };
+        let mut model = NeuralLanguageModel::new(Box::new(predictor), Box::new(First(0)));
+        model.set_symbols(&['a', 'b', 'c', 'd']);
+        (model, batches)
+    }
+
+    #[test]
+    fn test_neural_model_predicts_in_batches() {
+        let (mut model, batches) = model(Duration::ZERO);
+        let contexts: Vec<Context> = (0..3).map(|symbol| {
+            let context = model.create_empty_context();
+            model.enter_symbol(context, symbol);
+            context
+        }).collect();
+
+        model.prefetch(&contexts);
+        let mut probs = Vec::new();
+        model.get_probs(contexts[1], 1000, &mut probs);
+        assert_eq!(probs.iter().sum::<u32>(), 1000);
+        assert!(probs[2] > probs[0] && probs[2] > probs[3]);
+        assert_eq!(*batches.borrow(), [3]);
+        assert_eq!(model.stats(), NeuralStats { batches: 1, predictions: 3, slow_batches: 0, fallbacks: 0 });
+
+        // Histories keep only the characters the predictor looks at
+        model.enter_symbol(contexts[0], 1);
+        model.enter_symbol(contexts[0], 2);
+        assert_eq!(model.contexts.get(contexts[0]).unwrap().history, "bc");
+        model.get_probs(contexts[0], 1000, &mut probs);
+        assert!(probs[3] > probs[0]);
+        assert_eq!(*batches.borrow(), [3, 1]);
+
+        // A failed prediction is given by the fallback model
+        let empty = model.create_empty_context();
+        model.get_probs(empty, 1000, &mut probs);
+        assert_eq!(probs, [1000, 0, 0, 0]);
+        assert!(model.is_falling_back());
+    }
+
+    #[test]
+    fn test_neural_model_falls_back_when_over_budget() {
+        let (mut model, batches) = model(Duration::from_millis(5));
+        model.set_latency_budget(Duration::from_millis(1));
+        let context = model.create_empty_context();
+        model.enter_symbol(context, 0);
+
+        // The slow prediction is still used, but later ones fall back
+        let mut probs = Vec::new();
+        model.get_probs(context, 1000, &mut probs);
+        assert!(probs[1] > probs[0]);
+        assert!(model.is_falling_back());
+
+        model.enter_symbol(context, 1);
+        model.get_probs(context, 1000, &mut probs);
+        assert_eq!(probs, [1000, 0, 0, 0]);
+        model.prefetch(&[context]);
+        assert_eq!(batches.borrow().len(), 1);
+        assert_eq!(model.stats(), NeuralStats { batches: 1, predictions: 1, slow_batches: 1, fallbacks: 1 });
+    }
+}
@@ -2,6 +2,10 @@ use std::collections::HashMap;
 use std::rc::Rc;
 use std::cell::RefCell;
 
+use serde::{Deserialize, Serialize};
+
+use crate::{DasherError, Result};
+
 /// PPM node for trie structure
 #[derive(Debug, Clone)]
 pub struct PPMNode {
@@ -48,7 +52,6 @@ impl PPMNode {
     }
 
     /// Update counts for this node
-    #[allow(dead_code)]
     fn update_counts(&mut self, increment: bool) {
         if increment {
             self.count += 1;
@@ -60,6 +63,13 @@ impl PPMNode {
     }
 }
 
+/// Counts of a PPM trie, as exported
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PPMCounts {
+    /// Each node as the symbols on the path to it and its count, parents first
+    nodes: Vec<(String, usize)>,
+}
+
 /// PPM model order
 #[derive(Debug, Clone, Copy)]
 pub enum PPMOrder {
@@ -112,6 +122,10 @@ pub struct PPMLanguageModel {
     /// Update exclusion flag
     #[allow(dead_code)]
     update_exclusion: bool,
+    /// Number of nodes in the trie, not counting the root
+    node_count: usize,
+    /// Maximum number of nodes before the trie is pruned (unlimited if `None`)
+    max_nodes: Option<usize>,
 }
 
 impl PPMLanguageModel {
@@ -122,9 +136,30 @@ impl PPMLanguageModel {
             max_order,
             exclusion: true,
             update_exclusion: true,
+            node_count: 0,
+            max_nodes: None,
         }
     }
 
+    /// Get the number of nodes in the trie
+    pub fn node_count(&self) -> usize {
+        self.node_count
+    }
+
+    /// Get the maximum number of nodes kept in the trie
+    pub fn max_nodes(&self) -> Option<usize> {
+        self.max_nodes
+    }
+
+    /// Limit the number of nodes kept in the trie (unlimited if `None`)
+    ///
+    /// Whenever learning takes the trie over the limit it is pruned, so a
+    /// long session stays within a fixed memory budget.
+    pub fn set_max_nodes(&mut self, max_nodes: Option<usize>) {
+        self.max_nodes = max_nodes;
+        self.prune_to_budget();
+    }
+
     /// Set exclusion flags
     #[allow(dead_code)]
     pub fn set_exclusion(&mut self, exclusion: bool, update_exclusion: bool) {
@@ -134,122 +169,149 @@ impl PPMLanguageModel {
 
     /// Enter symbol into model
     pub fn enter_symbol(&mut self, context: &str, symbol: char) {
-        let context_len = context.len();
         for order in (PPMOrder::None.value()..=self.max_order.value()).rev() {
-            let order_usize = order as usize;
-            let ctx_slice = if order == 0 || context_len < order_usize {
-                ""
-            } else {
-                &context[context_len - order_usize..]
+            // The last `order` characters, or none if the context is shorter
+            let ctx_slice = match order as usize {
+                0 => "",
+                order => context.char_indices().rev().nth(order - 1).map_or("", |(i, _)| &context[i..]),
             };
             // println!("[PPM][train] order: {}, ctx_slice: '{}', symbol: '{}'", order, ctx_slice, symbol);
             let mut node = self.root.clone();
-            if !ctx_slice.is_empty() {
-                for c in ctx_slice.chars() {
-                    let next = {
-                        let mut node_mut = node.borrow_mut();
-                        node_mut.children.entry(c).or_insert_with(|| Rc::new(RefCell::new(PPMNode::new(Some(c), None)))).clone()
-                    };
-                    node = next;
-                }
+            for c in ctx_slice.chars().chain(std::iter::once(symbol)) {
+                // Insert symbol at this context, creating the context's nodes on the way
+                let next = {
+                    let mut node_mut = node.borrow_mut();
+                    node_mut.children.entry(c).or_insert_with(|| {
+                        self.node_count += 1;
+                        Rc::new(RefCell::new(PPMNode::new(Some(c), None)))
+                    }).clone()
+                };
+                next.borrow_mut().update_counts(true);
+                node = next;
             }
-            // Insert symbol at this context
-            node.borrow_mut().children.entry(symbol).or_insert_with(|| Rc::new(RefCell::new(PPMNode::new(Some(symbol), None))));
+        }
+
+        if self.max_nodes.is_some_and(|max_nodes| self.node_count > max_nodes) {
+            self.prune_to_budget();
         }
     }
 
-    /// Get probability distribution for next symbol
-    pub fn get_probs(&self, context: &str) -> HashMap<char, f64> {
-        let mut probs = HashMap::new();
-        let mut seen = HashMap::new();
-        let alpha = 1.0; // Start with all probability mass
-        let mut context_orders: Vec<&str> = Vec::new();
-        // Build context slices for each order (from max down to 0), using the LAST N symbols (matching enter_symbol)
-        for order in (PPMOrder::None.value()..=self.max_order.value()).rev() {
-            let context_slice = if order < 0 {
-                ""
+    /// Prune the trie until it holds at most three quarters of the maximum number of nodes
+    ///
+    /// Each pass halves every node's count and removes the nodes (with their
+    /// descendants) whose count reaches zero, so rarely or long ago seen
+    /// contexts go first. Leaving some slack means learning can continue for
+    /// a while before the next prune.
+    fn prune_to_budget(&mut self) {
+        let Some(max_nodes) = self.max_nodes else {
+            return;
+        };
+        if self.node_count <= max_nodes {
+            return;
+        }
+
+        let target = max_nodes - max_nodes / 4;
+        while self.node_count > target {
+            let removed = Self::halve_counts(&self.root);
+            self.node_count -= removed;
+        }
+    }
+
+    /// Halve the counts below `node`, removing children whose count reaches zero
+    ///
+    /// Returns the number of nodes removed.
+    fn halve_counts(node: &Rc<RefCell<PPMNode>>) -> usize {
+        let mut removed = 0;
+        node.borrow_mut().children.retain(|_, child| {
+            let count = {
+                let mut child_mut = child.borrow_mut();
+                child_mut.count /= 2;
+                child_mut.total_count = child_mut.count;
+                child_mut.count
+            };
+            if count == 0 {
+                removed += Self::subtree_size(child);
+                false
             } else {
-                let len = context.len();
-                if order as usize > len {
-                    context
-                } else {
-                    &context[len - order as usize..]
-                }
+                removed += Self::halve_counts(child);
+                true
+            }
+        });
+        removed
+    }
+
+    /// Count the nodes in the subtree rooted at `node`, including `node` itself
+    fn subtree_size(node: &Rc<RefCell<PPMNode>>) -> usize {
+        1 + node.borrow().children.values().map(Self::subtree_size).sum::<usize>()
+    }
+
+    /// Find the node whose children predict the next symbol
+    ///
+    /// This is the node for the longest suffix of `context` present in the
+    /// trie. Returns `None` if that node has no children: as in classic PPM-C,
+    /// the model then makes no prediction rather than falling back.
+    fn predicting_node(&self, context: &str) -> Option<Rc<RefCell<PPMNode>>> {
+        for order in (0..=self.max_order.value().max(0) as usize).rev() {
+            // Use the LAST `order` symbols of the context (matching enter_symbol)
+            let start = match order {
+                0 => context.len(),
+                _ => context.char_indices().rev().nth(order - 1).map_or(0, |(i, _)| i),
             };
-            context_orders.push(context_slice);
-        }
-        context_orders.reverse();
 
-        let mut found_any_context = false;
-        for (_order, context_slice) in context_orders.iter().enumerate().rev() {
             let mut node = self.root.clone();
             let mut found = true;
-            // println!("[PPM][predict] order: {}, context_slice: '{}'", order, context_slice);
-            if !context_slice.is_empty() {
-                for c in context_slice.chars() {
-                    let next = {
-                        let node_ref = node.borrow();
-                        node_ref.children.get(&c).cloned()
-                    };
-                    if let Some(child) = next {
-                        node = child;
-                    } else {
+            for c in context[start..].chars() {
+                let next = node.borrow().children.get(&c).cloned();
+                match next {
+                    Some(child) => node = child,
+                    None => {
                         found = false;
                         break;
                     }
                 }
             }
-            // println!("[PPM][predict]  -> found: {}", found);
             if !found {
                 continue;
             }
-            found_any_context = true;
+
+            if node.borrow().children.is_empty() {
+                return None;
+            }
+            return Some(node);
+        }
+        None
+    }
+
+    /// Get probability distribution for next symbol
+    pub fn get_probs(&self, context: &str) -> HashMap<char, f64> {
+        let mut probs = HashMap::new();
+        if let Some(node) = self.predicting_node(context) {
             let node_ref = node.borrow();
             let total = node_ref.children.len() as f64;
-            if total == 0.0 {
-                // Context exists but has no children: classic PPM-C says assign nothing, do not fallback
-                break;
-            }
-            let mass = alpha * 1.0;
-            let mut assigned = 0;
             for symbol in node_ref.children.keys() {
-                if !seen.contains_key(symbol) {
-                    probs.insert(*symbol, mass / total);
-                    // println!("[PPM][predict]   assigned symbol '{}' prob {}", symbol, mass / total);
-                    seen.insert(*symbol, true);
-                    assigned += 1;
-                }
-            }
-            if assigned > 0 {
-                // Classic PPM-C: stop as soon as any symbol is assigned at any order
-                break;
+                probs.insert(*symbol, 1.0 / total);
             }
         }
+        probs
+    }
 
-        // Only fallback if no context node was found at any order
-        if !found_any_context {
-            let root_ref = self.root.borrow();
-            let unseen: Vec<char> = root_ref.children.keys()
-                .filter(|k| !seen.contains_key(k))
-                .cloned()
-                .collect();
-            let n = unseen.len();
-            if alpha > 0.0 && n > 0 {
-                // println!("[PPM][fallback] assigning fallback for unseen: {:?}", unseen);
-                let add = alpha / n as f64;
-                for symbol in unseen {
-                    probs.insert(symbol, add);
-                }
-                // Normalize only if fallback was applied
-                let sum: f64 = probs.values().sum();
-                if sum > 0.0 {
-                    for prob in probs.values_mut() {
-                        *prob /= sum;
-                    }
+    /// Fill `probs` with a count for each of `symbols`, summing to `norm`
+    ///
+    /// Unlike [`get_probs`](Self::get_probs) this does not allocate once
+    /// `probs` has grown to the size of the alphabet. The counts are all zero
+    /// if the model makes no prediction.
+    pub fn fill_probs(&self, context: &str, symbols: &[char], norm: u32, probs: &mut Vec<u32>) {
+        probs.clear();
+        probs.resize(symbols.len(), 0);
+        if let Some(node) = self.predicting_node(context) {
+            let node_ref = node.borrow();
+            for (prob, symbol) in probs.iter_mut().zip(symbols) {
+                if node_ref.children.contains_key(symbol) {
+                    *prob = 1;
                 }
             }
         }
-        probs
+        super::normalize_counts(probs, norm);
     }
 }
 
@@ -258,6 +320,58 @@ impl PPMLanguageModel {
     pub fn max_order(&self) -> PPMOrder {
         self.max_order
     }
+
+    /// Forget everything learned
+    pub fn clear(&mut self) {
+        self.root = Rc::new(RefCell::new(PPMNode::new(None, None)));
+        self.node_count = 0;
+    }
+
+    /// Serialize the counts learned to JSON
+    pub fn to_json(&self) -> Result<String> {
+        let mut counts = PPMCounts::default();
+        Self::collect_counts(&self.root, &mut String::new(), &mut counts.nodes);
+        serde_json::to_string(&counts).map_err(|e| DasherError::Other(format!("Failed to serialize PPM model: {}", e)))
+    }
+
+    /// Replace the counts learned with those serialized by [`Self::to_json`]
+    ///
+    /// The maximum order and node limit are kept; the trie is pruned if it
+    /// holds more nodes than the limit.
+    pub fn load_json(&mut self, json: &str) -> Result<()> {
+        let counts: PPMCounts = serde_json::from_str(json)
+            .map_err(|e| DasherError::InvalidParameter(format!("Invalid PPM model: {}", e)))?;
+
+        self.clear();
+        for (path, count) in counts.nodes {
+            let mut node = self.root.clone();
+            for c in path.chars() {
+                let next = node.borrow_mut().children.entry(c).or_insert_with(|| {
+                    self.node_count += 1;
+                    Rc::new(RefCell::new(PPMNode::new(Some(c), None)))
+                }).clone();
+                node = next;
+            }
+            let mut node_mut = node.borrow_mut();
+            node_mut.count = count;
+            node_mut.total_count = count;
+        }
+        self.prune_to_budget();
+        Ok(())
+    }
+
+    /// Append the path and count of each node below `node` to `nodes`, parents first
+    fn collect_counts(node: &Rc<RefCell<PPMNode>>, path: &mut String, nodes: &mut Vec<(String, usize)>) {
+        let node_ref = node.borrow();
+        let mut children: Vec<_> = node_ref.children.iter().collect();
+        children.sort_by_key(|(symbol, _)| **symbol);
+        for (symbol, child) in children {
+            path.push(*symbol);
+            nodes.push((path.clone(), child.borrow().count));
+            Self::collect_counts(child, path, nodes);
+            path.pop();
+        }
+    }
 }
 
 #[cfg(test)]
@@ -351,4 +465,69 @@ mod tests {
         assert!(probs.contains_key(&'b'));
         assert!((probs.get(&'a').unwrap() - probs.get(&'b').unwrap()).abs() < 0.1);
     }
+
+    #[test]
+    fn test_ppm_fill_probs() {
+        let mut model = PPMLanguageModel::new(PPMOrder::One);
+        model.enter_symbol("", 'a');
+        model.enter_symbol("a", 'b');
+        model.enter_symbol("b", 'a');
+        model.enter_symbol("a", 'c');
+
+        // Symbols the model has never seen get nothing, the rest share the total
+        let symbols = ['a', 'b', 'c', 'd'];
+        let mut probs = vec![7; 10];
+        model.fill_probs("a", &symbols, 65536, &mut probs);
+        assert_eq!(probs, vec![0, 32768, 32768, 0]);
+
+        // An untrained model makes no prediction
+        let empty = PPMLanguageModel::new(PPMOrder::One);
+        empty.fill_probs("a", &symbols, 65536, &mut probs);
+        assert_eq!(probs, vec![0; 4]);
+    }
+
+    #[test]
+    fn test_ppm_json_round_trip() {
+        let mut model = PPMLanguageModel::new(PPMOrder::One);
+        model.enter_symbol("", 'a');
+        model.enter_symbol("a", 'b');
+        model.enter_symbol("b", '\n');
+
+        let json = model.to_json().unwrap();
+        let mut copy = PPMLanguageModel::new(PPMOrder::One);
+        copy.load_json(&json).unwrap();
+        assert_eq!(copy.node_count(), model.node_count());
+        assert_eq!(copy.to_json().unwrap(), json);
+        assert_eq!(copy.get_probs("b"), model.get_probs("b"));
+
+        assert!(copy.load_json("not json").is_err());
+        copy.clear();
+        assert_eq!(copy.node_count(), 0);
+        assert!(copy.get_probs("").is_empty());
+    }
+
+    #[test]
+    fn test_ppm_node_limit() {
+        let mut model = PPMLanguageModel::new(PPMOrder::Two);
+        model.set_max_nodes(Some(40));
+
+        let text = "the quick brown fox jumps over the lazy dog ".repeat(5);
+        let mut ctx = String::new();
+        for c in text.chars() {
+            model.enter_symbol(&ctx, c);
+            assert!(model.node_count() <= 40);
+            ctx.push(c);
+            if ctx.len() > 2 {
+                ctx.remove(0);
+            }
+        }
+
+        // The node count matches the trie and the model still predicts
+        assert_eq!(PPMLanguageModel::subtree_size(&model.root) - 1, model.node_count());
+        assert!(!model.get_probs("").is_empty());
+
+        // Lowering the limit prunes straight away
+        model.set_max_nodes(Some(10));
+        assert!(model.node_count() <= 10);
+    }
 }
@@ -0,0 +1,36 @@
+//! # Learning Module
+//!
+//! This module decides when the language model learns from the text the
+//! user writes. Learning personalises the predictions, which is not always
+//! wanted: on a device shared by several people the model can be frozen,
+//! or taught only sentences the user has finished, so abandoned attempts
+//! and stray symbols are never learned.
+
+/// When the language model learns from the symbols written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LearningMode {
+    /// Learn each symbol as it is written
+    #[default]
+    WhileWriting,
+
+    /// Learn each sentence once it is committed, by ending it with a
+    /// sentence terminator or accepting it
+    AtSentenceEnd,
+
+    /// Never learn; written symbols only move the model's context on
+    Off,
+}
+
+impl LearningMode {
+    /// Get the mode chosen by the learning settings
+    ///
+    /// `learn` turns learning on at all, and `at_sentence_end` holds it back
+    /// until the sentence is committed.
+    pub fn from_settings(learn: bool, at_sentence_end: bool) -> Self {
+        match (learn, at_sentence_end) {
+            (false, _) => LearningMode::Off,
+            (true, false) => LearningMode::WhileWriting,
+            (true, true) => LearningMode::AtSentenceEnd,
+        }
+    }
+}
@@ -7,22 +7,44 @@ pub mod node;
 mod language;
 pub mod word_generator;
 pub mod word_prediction;
+pub mod undo;
+pub mod text_buffer;
+pub mod expansion;
+pub mod profile;
+pub mod learning;
+pub mod corpus;
+pub mod diacritics;
+pub mod hangul;
 pub use word_generator::{BaseWordGenerator, PredictiveWordGenerator};
-pub use language::{PPMLanguageModel, PPMOrder, PPMNode, LanguageModel, CombinedLanguageModel};
+pub use language::{ArpaLanguageModel, NeuralLanguageModel, NeuralPredictor, NeuralStats, PPMLanguageModel, PPMOrder, PPMNode, LanguageModel, CombinedLanguageModel, LanguageLayer, MixtureLanguageModel, PersonalLexicon, WordBigramModel, CacheStats, ProbabilityCache, Context, ContextTable, TextContexts, enter_text};
+#[cfg(feature = "candle")]
+pub use language::CandleCharModel;
 pub use word_prediction::{WordPredictionManager, create_default_manager};
+pub use undo::{EditKind, TextEdit, UndoStack};
+pub use text_buffer::{CursorMovement, TextBuffer};
+pub use expansion::{ExpansionPlan, ExpansionPolicy};
+pub use profile::LanguageProfile;
+pub use learning::LearningMode;
+pub use corpus::UserCorpus;
 use std::cell::RefCell;
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::rc::{Rc, Weak};
 use std::path::Path;
+use std::io::{BufRead, Seek};
 
-use node::{DasherNode, NodeFlags};
+use node::{DasherNode, NodeContext, NodeFlags};
 use crate::view::{DasherScreen, Color};
-use crate::alphabet::Alphabet;
+use crate::alphabet::{Alphabet, AlphabetConversion, AlphabetInfo, ConversionManager, ConversionProvider, Symbol, ToggleGroup, TrainingError, TrainingManager, TrainingStats, TrainingText};
+use crate::action::{current_sentence, SwitchProfileAction, SENTENCE_TERMINATORS};
+use crate::logging::dasher_log;
 use crate::Result;
 
 /// Event type for node creation
 pub type NodeCreationEvent = Box<dyn Fn(&Rc<RefCell<DasherNode>>)>;
 
+/// A node and the coordinates (y1, y2) it is placed at
+pub type PlacedNode = (Rc<RefCell<DasherNode>>, (i64, i64));
+
 /// The main Dasher model that implements arithmetic coding for Dasher.
 ///
 /// It contains a tree of DasherNodes and the current viewpoint, and evolves
@@ -55,7 +77,8 @@ pub struct DasherModel {
     /// Maximum allowed value for root_max
     root_max_max: i64,
 
-    /// Display offset
+    /// Distance the root is drawn from where it is, eased away over time so
+    /// that jumps in its position are animated
     display_offset: i64,
 
     /// Last node that was output
@@ -67,6 +90,10 @@ pub struct DasherModel {
     /// Whether characters entered by alphabet manager are expected to require conversion
     require_conversion: bool,
 
+    /// Conversions of the composition spelled before each node, offered
+    /// among its children once a provider is set
+    conversion: ConversionManager,
+
     /// Total information entered so far in this model (in nats)
     total_nats: f64,
 
@@ -79,8 +106,81 @@ pub struct DasherModel {
     /// The language model used by this model
     language_model: Option<Box<dyn LanguageModel>>,
 
-    /// The current output text
-    output_text: String,
+    /// Language model context of the text before the cursor, which learns the text written
+    language_model_context: Option<Context>,
+
+    /// Language model contexts of the nodes dropped since they were last released
+    released_contexts: Rc<RefCell<Vec<Context>>>,
+
+    /// Generation of the nodes' language model contexts, advanced whenever
+    /// the text before the cursor is entered afresh
+    context_generation: u64,
+
+    /// Number of nodes given children since the model was created
+    nodes_expanded: u64,
+
+    /// Number of nodes whose children were pruned since the model was created
+    nodes_collapsed: u64,
+
+    /// When the language model learns from the text written
+    learning_mode: LearningMode,
+
+    /// Text written but not yet learned, in [`LearningMode::AtSentenceEnd`]
+    unlearned: String,
+
+    /// Corpus the sentences written are recorded to, once the user opts in
+    corpus: UserCorpus,
+
+    /// Whether a sentence has been written since the last one was recorded
+    sentence_unrecorded: bool,
+
+    /// Name of the language profile in use
+    profile: String,
+
+    /// Language profiles not in use, by name
+    profiles: HashMap<String, LanguageProfile>,
+
+    /// The current output text and cursor
+    output_text: TextBuffer,
+
+    /// History of committed text operations
+    undo_stack: UndoStack,
+
+    /// Minimum probability (in 1/NORMALIZATION units) for a symbol to get its own node
+    min_child_probability: u32,
+
+    /// Share of the probability (in thousandths) spread evenly over the symbols
+    uniform: u32,
+
+    /// Share of each node's interval (in thousandths) given to control nodes
+    control_mass: u32,
+
+    /// Whether the alphabet's toggle groups are offered behind toggle nodes
+    toggle_groups: bool,
+
+    /// Whether Hangul jamo written in turn are composed into syllables
+    hangul_composition: bool,
+
+    /// Whether accents written next to their letters are composed with them
+    diacritic_composition: bool,
+
+    /// Character of each alphabet symbol, in symbol index order, for the language model
+    symbol_characters: Vec<char>,
+
+    /// Reusable buffer for the language model's symbol counts
+    probs_buffer: Vec<u32>,
+
+    /// Whether likely next words are offered after a word separator
+    next_word_prediction: bool,
+
+    /// Whether a space is written after a predicted word
+    auto_space: bool,
+
+    /// Whether punctuation written after a space is moved before it
+    smart_punctuation: bool,
+
+    /// Characters that end a word
+    word_separators: HashSet<char>,
 }
 
 impl Default for DasherModel {
@@ -90,9 +190,16 @@ impl Default for DasherModel {
 }
 
 impl DasherModel {
-    /// Stub for input coordinate application
-    pub fn apply_input_coordinates(&mut self, _coords: (i64, i64)) {
-        // Stub: No-op for now
+    /// Steer towards the pointer, scheduling one step of the classic Dasher dynamics
+    ///
+    /// `x` is the pointer's distance from the right edge of the screen and `y`
+    /// its height, both in Dasher coordinates (see [`Self::ORIGIN_X`]). The view
+    /// zooms so that the range `y - x` to `y + x` fills the screen after
+    /// `n_steps` frames: pointing right of the crosshair zooms in, pointing left
+    /// of it zooms out (reverses), and the height steers up or down.
+    pub fn apply_input_coordinates(&mut self, (x, y): (i64, i64), n_steps: i32) {
+        let x = x.max(1);
+        self.schedule_one_step(y - x, y + x, n_steps, Self::X_LIMIT as i32, false);
     }
 
     /// Stop the model (clear scheduled steps)
@@ -101,40 +208,34 @@ impl DasherModel {
     }
 
     /// Reset the model to its initial state
+    ///
+    /// The output text, its history and the tree are cleared, and a new
+    /// root filling the screen is expanded for the empty text, so writing
+    /// can start again. The alphabet, the language model and what it has
+    /// learned are kept.
     pub fn reset(&mut self) {
-        // Clear scheduled steps
-        self.clear_scheduled_steps();
-
-        // Clear the output text
+        // Clear the output text and its history
         self.output_text.clear();
+        self.undo_stack.clear();
+        self.unlearned.clear();
+        self.sentence_unrecorded = false;
+        self.total_nats = 0.0;
 
-        // Reset the root
-        if let Some(_root) = &self.root {
-            // Create a new root node
-            let new_root = Rc::new(RefCell::new(DasherNode::new(0, Some("Root".to_string()))));
+        // Return the language model to the empty text
+        self.sync_language_model_context();
 
-            // Set the new root
-            self.root = Some(new_root.clone());
-
-            // Expand the new root
-            self.expand_node(&new_root);
-
-            // Reset coordinates
-            self.root_min = 0;
-            self.root_max = 0;
-            self.display_offset = 0;
-
-            // Clear the last output
-            self.last_output = None;
-        }
-
-        // Clear old roots
+        // Replace the tree (and any scheduled steps) with a new root
+        self.clear_scheduled_steps();
+        self.rebuild_tree();
         self.old_roots.clear();
+        self.last_output = None;
 
-        // Reset the language model
-        if let Some(model) = &mut self.language_model {
-            model.reset();
+        // Reset coordinates so the root fills the screen
+        if self.root.is_some() {
+            self.root_min = 0;
+            self.root_max = Self::MAX_Y;
         }
+        self.display_offset = 0;
     }
 
     /// Set the velocity of the model
@@ -151,24 +252,51 @@ impl DasherModel {
         // Schedule a step with the calculated range
         self.schedule_one_step(y1, y2, 1, 100, false);
     }
-    /// Y origin constant for coordinate calculations
-    pub const ORIGIN_Y: i64 = 0;
-    /// X origin constant for coordinate calculations
-    pub const ORIGIN_X: i64 = 0;
+    /// Y coordinate of the crosshair (the middle of the screen)
+    pub const ORIGIN_Y: i64 = Self::MAX_Y / 2;
+    /// X coordinate of the crosshair, measured from the right edge of the screen
+    pub const ORIGIN_X: i64 = Self::MAX_X / 2;
+    /// Smallest pointer X used for steering, which limits the maximum zoom speed
+    pub const X_LIMIT: i64 = Self::MAX_X / 64;
     /// Placeholder for word predictions
     pub fn get_word_predictions(&mut self) -> Vec<String> {
         if let Some(manager) = &mut self.word_prediction {
-            manager.get_predictions(&self.output_text)
+            manager.update_context(self.output_text.text_before_cursor())
         } else {
             Vec::new()
         }
     }
+
+    /// Get the word prediction manager, to add word generators to it
+    pub fn word_prediction_mut(&mut self) -> Option<&mut WordPredictionManager> {
+        self.word_prediction.as_mut()
+    }
+
     /// Maximum Y coordinate for the model (placeholder value)
     pub const MAX_Y: i64 = 1 << 20;
     /// Maximum X coordinate for the model (placeholder value)
     pub const MAX_X: i64 = 1 << 20;
     /// Normalization constant for probability calculations
     pub const NORMALIZATION: u32 = 1 << 16;
+    /// Time (in ms) for the display offset to ease halfway to zero, like
+    /// taking a tenth off it each frame at 50 frames a second
+    pub const DISPLAY_OFFSET_HALF_LIFE_MS: f64 = 130.0;
+    /// Default share of the probability (in thousandths) spread evenly over the symbols
+    pub const DEFAULT_UNIFORM: u32 = 50;
+    /// Label of the node grouping improbable symbols
+    pub const RESIDUAL_LABEL: &'static str = "\u{2026}";
+    /// Number of characters of text a language model context is created from
+    const CONTEXT_CHARS: usize = 64;
+    /// Maximum number of next words offered after a word separator
+    pub const MAX_NEXT_WORDS: usize = 5;
+    /// Share of the probability given to next words, scaled by their predicted probability
+    pub const NEXT_WORD_WEIGHT: f64 = 0.5;
+    /// Maximum number of conversions offered for a composition
+    pub const MAX_CONVERSIONS: usize = 5;
+    /// Share of the probability given to conversions, scaled by their probability
+    pub const CONVERSION_WEIGHT: f64 = 0.5;
+    /// Punctuation that smart punctuation attaches to the preceding word
+    pub const ATTACHING_PUNCTUATION: &'static [char] = &['.', ',', '!', '?', ';', ':'];
     /// Create a new Dasher model with default settings
     pub fn new() -> Self {
         Self::with_language_model(Box::new(CombinedLanguageModel::new(PPMOrder::Three)))
@@ -178,17 +306,35 @@ impl DasherModel {
     pub fn with_language_model(language_model: Box<dyn LanguageModel>) -> Self {
 
         use crate::model::word_prediction::WordPredictionManager;
-        use crate::action::{ActionManager, BackspaceAction, SpaceAction, AcceptAction};
+        use crate::action::{ActionManager, BackspaceAction, SpaceAction, AcceptAction, ClearAction, UndoAction, RedoAction, MoveCursorAction};
         let word_prediction = WordPredictionManager::new(10, 32);
 
         let mut action_manager = ActionManager::new();
         action_manager.register_action(Box::new(BackspaceAction));
         action_manager.register_action(Box::new(SpaceAction));
         action_manager.register_action(Box::new(AcceptAction));
-        Self {
+        action_manager.register_action(Box::new(ClearAction));
+        action_manager.register_action(Box::new(UndoAction));
+        action_manager.register_action(Box::new(RedoAction));
+        for action in MoveCursorAction::all() {
+            action_manager.register_action(Box::new(action));
+        }
+        let alphabet = Alphabet::english();
+        let mut model = Self {
             action_manager,
             root: None,
-            language_model: Some(language_model),
+            language_model: None,
+            language_model_context: None,
+            released_contexts: Rc::new(RefCell::new(Vec::new())),
+            context_generation: 0,
+            nodes_expanded: 0,
+            nodes_collapsed: 0,
+            learning_mode: LearningMode::default(),
+            unlearned: String::new(),
+            corpus: UserCorpus::new(),
+            sentence_unrecorded: false,
+            profile: alphabet.name().to_string(),
+            profiles: HashMap::new(),
             word_prediction: Some(word_prediction),
             old_roots: VecDeque::new(),
             root_min: 0,
@@ -199,13 +345,87 @@ impl DasherModel {
             last_output: None,
             goto_queue: VecDeque::new(),
             require_conversion: false,
+            conversion: ConversionManager::new(),
             total_nats: 0.0,
             node_creation_handlers: Vec::new(),
-            alphabet: Some(Alphabet::english()),
-            output_text: String::new(),
+            symbol_characters: Self::symbol_characters(&alphabet),
+            probs_buffer: Vec::new(),
+            next_word_prediction: false,
+            auto_space: false,
+            smart_punctuation: false,
+            word_separators: CombinedLanguageModel::DEFAULT_WORD_SEPARATORS.iter().copied().collect(),
+            alphabet: Some(alphabet),
+            output_text: TextBuffer::new(),
+            undo_stack: UndoStack::default(),
+            min_child_probability: 0,
+            uniform: Self::DEFAULT_UNIFORM,
+            control_mass: 0,
+            toggle_groups: true,
+            hangul_composition: true,
+            diacritic_composition: true,
+        };
+        model.set_language_model(language_model);
+        model
+    }
+
+    /// Get the language model as a combined PPM and dictionary model, if it is one
+    fn combined_language_model(&mut self) -> Option<&mut CombinedLanguageModel> {
+        self.language_model.as_mut()?.as_any().downcast_mut::<CombinedLanguageModel>()
+    }
+
+    /// Get the lexicon of words the user has typed, if the language model keeps one
+    pub fn personal_lexicon_mut(&mut self) -> Option<&mut PersonalLexicon> {
+        self.combined_language_model().map(|combined| combined.lexicon_mut())
+    }
+
+    /// Load the user's lexicon from their Dasher configuration directory
+    ///
+    /// The lexicon is then saved back to the same file.
+    pub fn load_user_lexicon(&mut self) -> std::io::Result<()> {
+        let Some(path) = PersonalLexicon::user_path() else {
+            return Ok(());
+        };
+        match self.personal_lexicon_mut() {
+            Some(lexicon) => lexicon.load(path),
+            None => Ok(()),
+        }
+    }
+
+    /// Limit the number of nodes kept by the language model (unlimited if `None`)
+    pub fn set_language_model_max_nodes(&mut self, max_nodes: Option<usize>) {
+        if let Some(model) = &mut self.language_model {
+            if let Some(combined) = model.as_any().downcast_mut::<CombinedLanguageModel>() {
+                combined.layer_mut(LanguageLayer::Base).set_max_nodes(max_nodes);
+                combined.layer_mut(LanguageLayer::User).set_max_nodes(max_nodes);
+            }
         }
     }
 
+    /// Forget what the language model has learned from the user
+    ///
+    /// Only the user layer and lexicon are cleared; what was trained from
+    /// corpora is kept. Does nothing for a language model without layers.
+    pub fn reset_personalization(&mut self) {
+        if let Some(combined) = self.combined_language_model() {
+            combined.reset_personalization();
+        }
+    }
+
+    /// Serialize a layer of the language model to JSON
+    ///
+    /// Returns `None` for a language model without layers.
+    pub fn language_layer_to_json(&mut self, layer: LanguageLayer) -> Option<Result<String>> {
+        self.combined_language_model().map(|combined| combined.layer(layer).to_json())
+    }
+
+    /// Replace a layer of the language model with one serialized by
+    /// [`Self::language_layer_to_json`]
+    ///
+    /// Returns `None` for a language model without layers.
+    pub fn load_language_layer_json(&mut self, layer: LanguageLayer, json: &str) -> Option<Result<()>> {
+        self.combined_language_model().map(|combined| combined.layer_mut(layer).load_json(json))
+    }
+
     /// Load dictionary for language model
     pub fn load_dictionary<P: AsRef<Path>>(&mut self, path: P) -> std::io::Result<()> {
         if let Some(model) = &mut self.language_model {
@@ -218,33 +438,354 @@ impl DasherModel {
 
     /// Get current probability distribution
     pub fn get_probabilities(&self) -> Option<Vec<(char, f64)>> {
-        self.language_model.as_ref().map(|model| {
-            let context = self.output_text.as_str();
-            model.get_probs(context).into_iter().collect()
+        self.language_model.as_ref().zip(self.language_model_context).map(|(model, context)| {
+            Self::distribution(model.as_ref(), &self.symbol_characters, context)
         })
     }
 
+    /// Get the probability distribution of the symbol after `text`
+    ///
+    /// Unlike [`Self::get_probabilities`], the context is `text` rather than
+    /// the text before the cursor. Nothing is learned from it.
+    pub fn probabilities_after(&mut self, text: &str) -> Option<Vec<(char, f64)>> {
+        let language_model = self.language_model.as_mut()?;
+        let context = Self::create_context(language_model.as_mut(), &self.symbol_characters, text);
+        let probabilities = Self::distribution(language_model.as_ref(), &self.symbol_characters, context);
+        language_model.release_context(context);
+        Some(probabilities)
+    }
+
+    /// Get the probable next symbols, most probable first
+    ///
+    /// Symbols less probable than `min_probability` are left out, and only
+    /// the `top_k` most probable are kept (all of them if `top_k` is 0). The
+    /// symbols follow `text` if it is given, and the text before the cursor
+    /// otherwise.
+    pub fn ranked_probabilities(&mut self, top_k: usize, min_probability: f64, text: Option<&str>) -> Option<Vec<(char, f64)>> {
+        let mut probabilities = match text {
+            Some(text) => self.probabilities_after(text),
+            None => self.get_probabilities(),
+        }?;
+        probabilities.retain(|&(_, prob)| prob >= min_probability);
+        probabilities.sort_by(|a, b| b.1.total_cmp(&a.1));
+        if top_k > 0 {
+            probabilities.truncate(top_k);
+        }
+        Some(probabilities)
+    }
+
+    /// Get the probabilities of the symbols with a non-zero count in `context`
+    fn distribution(language_model: &dyn LanguageModel, symbols: &[char], context: Context) -> Vec<(char, f64)> {
+        let mut probs = Vec::new();
+        language_model.get_probs(context, Self::NORMALIZATION, &mut probs);
+        symbols.iter().zip(probs)
+            .filter(|&(_, count)| count > 0)
+            .map(|(&c, count)| (c, count as f64 / Self::NORMALIZATION as f64))
+            .collect()
+    }
+
+    /// Train the language model from a stream of text, reporting progress
+    ///
+    /// See [`TrainingManager::train_stream`] for how `progress` is called and
+    /// how training is cancelled. Contexts escaped in the text (`§...§`)
+    /// switch the model's context without being learned. The training text's
+    /// words are kept out of the user's lexicon, and afterwards the model's context is returned to
+    /// the text before the cursor.
+    pub fn train_language_model<R, P>(&mut self, reader: R, progress: P) -> std::result::Result<TrainingStats, TrainingError>
+    where
+        R: BufRead + Seek,
+        P: FnMut(u8) -> bool,
+    {
+        // Corpus text goes into the base layer, not the user's lexicon
+        let learning = self.combined_language_model().map(|combined| {
+            let learning = (combined.learn_words(), combined.learning_layer());
+            combined.set_learn_words(false);
+            combined.set_learning_layer(LanguageLayer::Base);
+            learning
+        });
+
+        let name = self.alphabet.as_ref().map_or("Default", |alphabet| alphabet.name()).to_string();
+        let mut manager = TrainingManager::new(AlphabetInfo::new(name));
+        let result = manager.train_stream(reader, |text| match text {
+            TrainingText::Learn(text) => {
+                for c in text.chars() {
+                    self.update_language_model(c);
+                }
+            }
+            TrainingText::Context(context) => self.set_language_model_context(context),
+        }, progress);
+
+        if let (Some((learn_words, layer)), Some(combined)) = (learning, self.combined_language_model()) {
+            combined.set_learn_words(learn_words);
+            combined.set_learning_layer(layer);
+        }
+        self.sync_language_model_context();
+        result
+    }
+
     /// Update language model with new symbol
+    ///
+    /// The model learns that `symbol` follows the text before it, which it
+    /// then follows. Characters that are not in the alphabet are ignored.
     pub fn update_language_model(&mut self, symbol: char) {
-        if let Some(model) = &mut self.language_model {
-            model.enter_symbol(symbol);
+        let index = self.alphabet.as_ref().and_then(|alphabet| alphabet.get_index(symbol));
+        if let (Some(model), Some(context), Some(index)) = (&mut self.language_model, self.language_model_context, index) {
+            model.learn_symbol(context, index);
+        }
+    }
+
+    /// Get when the language model learns from the text written
+    pub fn learning_mode(&self) -> LearningMode {
+        self.learning_mode
+    }
+
+    /// Set when the language model learns from the text written
+    ///
+    /// Text held back for the end of its sentence is dropped when the mode
+    /// changes, so switching learning off never teaches the model anything
+    /// more.
+    pub fn set_learning_mode(&mut self, mode: LearningMode) {
+        if mode != self.learning_mode {
+            self.unlearned.clear();
+        }
+        self.learning_mode = mode;
+    }
+
+    /// Get the corpus the sentences written are recorded to
+    pub fn corpus(&self) -> &UserCorpus {
+        &self.corpus
+    }
+
+    /// Get the corpus the sentences written are recorded to mutably
+    pub fn corpus_mut(&mut self) -> &mut UserCorpus {
+        &mut self.corpus
+    }
+
+    /// Commit the sentence just written
+    ///
+    /// The sentence is recorded to the corpus, if enabled, and learned if
+    /// it was held back by [`LearningMode::AtSentenceEnd`].
+    pub fn commit_sentence(&mut self) {
+        if std::mem::take(&mut self.sentence_unrecorded) {
+            let sentence = current_sentence(self.output_text.text_before_cursor());
+            if let Err(e) = self.corpus.record(sentence) {
+                dasher_log!(Warn, "Failed to record sentence to corpus: {}", e);
+            }
+        }
+        self.learn_held_back();
+    }
+
+    /// Learn the text held back since the last sentence was learned,
+    /// if it is still just before the cursor
+    fn learn_held_back(&mut self) {
+        let sentence = std::mem::take(&mut self.unlearned);
+        let Some(before) = self.output_text.text_before_cursor().strip_suffix(sentence.as_str()) else {
+            return;
+        };
+        if sentence.is_empty() {
+            return;
+        }
+        let before = Self::context_text(before).to_string();
+
+        if let (Some(language_model), Some(alphabet)) = (&mut self.language_model, &self.alphabet) {
+            let context = Self::create_context(language_model.as_mut(), &self.symbol_characters, &before);
+            for index in sentence.chars().filter_map(|c| alphabet.get_index(c)) {
+                language_model.learn_symbol(context, index);
+            }
+            language_model.release_context(context);
+        }
+    }
+
+    /// Move the language model's context on past a symbol written at the
+    /// cursor, learning from it as the learning mode says
+    fn write_to_language_model(&mut self, symbol: char) {
+        match self.learning_mode {
+            LearningMode::WhileWriting => self.update_language_model(symbol),
+            LearningMode::AtSentenceEnd => {
+                self.enter_language_model(symbol);
+                self.unlearned.push(symbol);
+            }
+            LearningMode::Off => self.enter_language_model(symbol),
+        }
+
+        if SENTENCE_TERMINATORS.contains(&symbol) {
+            self.commit_sentence();
+        } else if !symbol.is_whitespace() {
+            self.sentence_unrecorded = true;
+        }
+
+        // A new paragraph is predicted like the start of the text
+        if symbol == Symbol::PARAGRAPH {
+            self.set_language_model_context("");
+        }
+    }
+
+    /// Move the language model's context on past `symbol` without learning from it
+    fn enter_language_model(&mut self, symbol: char) {
+        let index = self.alphabet.as_ref().and_then(|alphabet| alphabet.get_index(symbol));
+        if let (Some(model), Some(context), Some(index)) = (&mut self.language_model, self.language_model_context, index) {
+            model.enter_symbol(context, index);
+        }
+    }
+
+    /// Forget the last symbol held back for learning, after it was erased
+    fn unlearn_last(&mut self, symbol: Option<char>) {
+        if symbol.is_some() && self.unlearned.chars().last() == symbol {
+            self.unlearned.pop();
+        }
+    }
+
+    /// Switch to another alphabet, with the language model kept for it
+    ///
+    /// Each alphabet is kept as a language profile named after it, so that
+    /// switching back to it needs no training (see [`Self::switch_profile`]).
+    /// Returns `true` if the alphabet had no profile yet, in which case a new,
+    /// untrained language model is used.
+    pub fn switch_alphabet(&mut self, alphabet: Alphabet) -> bool {
+        let name = alphabet.name().to_string();
+        if self.language_model.is_none() || name == self.profile {
+            self.set_alphabet(alphabet);
+            return false;
+        }
+
+        let untrained = match self.profiles.get_mut(&name) {
+            Some(profile) => {
+                profile.alphabet = alphabet;
+                false
+            }
+            None => {
+                let language_model = Box::new(CombinedLanguageModel::new(PPMOrder::Three));
+                self.profiles.insert(name.clone(), LanguageProfile::new(alphabet, language_model));
+                true
+            }
+        };
+        self.switch_profile(&name);
+        untrained
+    }
+
+    /// Get the name of the language profile in use
+    pub fn profile_name(&self) -> &str {
+        &self.profile
+    }
+
+    /// Get the names of all language profiles, in alphabetical order
+    pub fn profile_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+        names.push(&self.profile);
+        names.sort_unstable();
+        names
+    }
+
+    /// Add a language profile, replacing any profile with the same name
+    ///
+    /// A control action named by [`LanguageProfile::action_name`] is registered
+    /// for the new profile and for the one in use, so the user can switch
+    /// between them from the node tree.
+    pub fn add_profile(&mut self, name: &str, profile: LanguageProfile) {
+        let current = self.profile.clone();
+        self.action_manager.register_action(Box::new(SwitchProfileAction::new(&current)));
+        self.action_manager.register_action(Box::new(SwitchProfileAction::new(name)));
+
+        if name == current {
+            self.replace_language_model(profile.language_model);
+            self.set_alphabet(profile.alphabet);
+            self.rebuild_tree();
+        } else {
+            self.profiles.insert(name.to_string(), profile);
         }
     }
 
+    /// Switch to the language profile called `name`
+    ///
+    /// The profile in use is kept with everything its language model has
+    /// learned, and the personal lexicon moves over to the new profile's
+    /// model. The output text is kept and the node tree is rebuilt from the
+    /// new alphabet. Returns `false` if there is no such profile.
+    pub fn switch_profile(&mut self, name: &str) -> bool {
+        if name == self.profile {
+            return true;
+        }
+        let Some(profile) = self.profiles.remove(name) else {
+            return false;
+        };
+
+        let old_name = std::mem::replace(&mut self.profile, name.to_string());
+        let old_alphabet = self.alphabet.take();
+        let old_model = self.replace_language_model(profile.language_model);
+        if let Some(mut old_model) = old_model {
+            if let Some(old) = old_model.as_any().downcast_mut::<CombinedLanguageModel>() {
+                let lexicon = std::mem::take(old.lexicon_mut());
+                let learn_words = old.learn_words();
+                if let Some(combined) = self.combined_language_model() {
+                    *combined.lexicon_mut() = lexicon;
+                    combined.set_learn_words(learn_words);
+                }
+            }
+            if let Some(old_alphabet) = old_alphabet {
+                self.profiles.insert(old_name, LanguageProfile::new(old_alphabet, old_model));
+            }
+        }
+
+        self.set_alphabet(profile.alphabet);
+        if self.root.is_some() {
+            self.root_min = 0;
+            self.root_max = Self::MAX_Y;
+            self.rebuild_tree();
+        }
+        true
+    }
+
     /// Set the alphabet for this model
+    ///
+    /// The language model keeps what it has learned, since it predicts
+    /// characters rather than symbol indices, and its context is primed again
+    /// from the text before the cursor. Call [`Self::rebuild_tree`] afterwards
+    /// to show the new alphabet's symbols.
     pub fn set_alphabet(&mut self, alphabet: Alphabet) {
+        self.symbol_characters = Self::symbol_characters(&alphabet);
+        if let Some(language_model) = &mut self.language_model {
+            language_model.set_symbols(&self.symbol_characters);
+        }
         self.alphabet = Some(alphabet);
+        self.sync_language_model_context();
+    }
+
+    /// List the character of each symbol in `alphabet`, in symbol index order
+    fn symbol_characters(alphabet: &Alphabet) -> Vec<char> {
+        alphabet.symbols().iter().map(|symbol| symbol.character).collect()
+    }
+
+    /// Rebuild the node tree so that it reflects the current alphabet and
+    /// the text before the cursor
+    ///
+    /// The output text, language model and root coordinates are kept; only
+    /// the nodes themselves are recreated.
+    pub fn rebuild_tree(&mut self) {
+        if self.root.is_none() {
+            return;
+        }
+
+        self.clear_scheduled_steps();
+
+        // The root represents the character just before the cursor
+        let offset = self.get_offset() - 1;
+        let new_root = Rc::new(RefCell::new(DasherNode::new(offset, Some("Root".to_string()))));
+        self.root = Some(new_root.clone());
+        self.expand_node(&new_root);
+
+        self.old_roots.clear();
+        self.last_output = None;
     }
 
     /// Initialize the model
     pub fn initialize(&mut self) -> Result<()> {
         // Create an English alphabet if none exists
-        if self.alphabet.is_none() {
+        if let Some(alphabet) = self.alphabet.as_ref() {
+            dasher_log!(Debug, "Using existing alphabet with {} symbols", alphabet.size());
+        } else {
             let alphabet = crate::alphabet::Alphabet::english();
-            println!("Created default English alphabet with {} symbols", alphabet.size());
+            dasher_log!(Debug, "Created default English alphabet with {} symbols", alphabet.size());
             self.set_alphabet(alphabet);
-        } else {
-            println!("Using existing alphabet with {} symbols", self.alphabet.as_ref().unwrap().size());
         }
 
         // Create a default language model if none exists
@@ -252,29 +793,31 @@ impl DasherModel {
             let language_model = Box::new(crate::model::language::CombinedLanguageModel::new(
                 crate::model::language::PPMOrder::Three
             ));
-            println!("Created default combined language model");
+            dasher_log!(Debug, "Created default combined language model");
             self.set_language_model(language_model);
         } else {
-            println!("Using existing language model");
+            dasher_log!(Debug, "Using existing language model");
         }
 
-        // Create a root node
-        let root = Rc::new(RefCell::new(DasherNode::new(0, Some("Root".to_string()))));
-        println!("Created root node");
+        // Create a root node, representing the character just before the cursor
+        let root = Rc::new(RefCell::new(DasherNode::new(self.get_offset() - 1, Some("Root".to_string()))));
+        dasher_log!(Debug, "Created root node");
 
-        // Set the root node
+        // Set the root node, filling the screen
         self.root = Some(root.clone());
+        self.root_min = 0;
+        self.root_max = Self::MAX_Y;
 
         // Expand the root node to create its children
         self.expand_node(&root);
-        println!("Expanded root node, child count: {}", root.borrow().children().len());
+        dasher_log!(Debug, "Expanded root node, child count: {}", root.borrow().children().len());
 
         // Train the language model with some basic text if it's empty
-        if let Some(lm) = &mut self.language_model {
+        if self.language_model.is_some() {
             let training_text = "the quick brown fox jumps over the lazy dog";
-            println!("Training language model with basic text");
-            for c in training_text.chars() {
-                self.update_language_model(c);
+            dasher_log!(Debug, "Training language model with basic text");
+            if let Err(e) = self.train_language_model(std::io::Cursor::new(training_text), |_| true) {
+                dasher_log!(Warn, "Failed to train language model: {:?}", e);
             }
         }
 
@@ -288,7 +831,103 @@ impl DasherModel {
 
     /// Set the language model for this model
     pub fn set_language_model(&mut self, language_model: Box<dyn LanguageModel>) {
-        self.language_model = Some(language_model);
+        self.replace_language_model(language_model);
+    }
+
+    /// Take the language model out, leaving none
+    ///
+    /// Its context is released, so it can be given to another model, such
+    /// as the fallback of a [`NeuralLanguageModel`].
+    pub fn take_language_model(&mut self) -> Option<Box<dyn LanguageModel>> {
+        self.drop_node_contexts();
+        let mut language_model = self.language_model.take()?;
+        if let Some(context) = self.language_model_context.take() {
+            language_model.release_context(context);
+        }
+        Some(language_model)
+    }
+
+    /// Replace the language model, returning the previous one
+    ///
+    /// The previous model's context is released, and the new model is given
+    /// the alphabet, the word separators and the text before the cursor.
+    fn replace_language_model(&mut self, mut language_model: Box<dyn LanguageModel>) -> Option<Box<dyn LanguageModel>> {
+        self.drop_node_contexts();
+        language_model.set_symbols(&self.symbol_characters);
+        let mut old_model = self.language_model.replace(language_model);
+        if let (Some(old_model), Some(context)) = (&mut old_model, self.language_model_context.take()) {
+            old_model.release_context(context);
+        }
+        self.apply_word_separators();
+        self.sync_language_model_context();
+        old_model
+    }
+
+    /// Drop the nodes' language model contexts, releasing them, before the language model is replaced
+    ///
+    /// Contexts of nodes that cannot be reached from the tree are left to be
+    /// dropped with their nodes, without reaching the new model.
+    fn drop_node_contexts(&mut self) {
+        let mut pending: Vec<_> = self.old_roots.iter().chain(&self.root).cloned().collect();
+        while let Some(node) = pending.pop() {
+            let mut node = node.borrow_mut();
+            node.set_language_context(None);
+            pending.extend(node.children().iter().cloned());
+        }
+        self.release_node_contexts();
+        self.released_contexts = Rc::new(RefCell::new(Vec::new()));
+    }
+
+    /// Release the language model contexts of the nodes dropped since last time
+    fn release_node_contexts(&mut self) {
+        let released = std::mem::take(&mut *self.released_contexts.borrow_mut());
+        if let Some(language_model) = &mut self.language_model {
+            for context in released {
+                language_model.release_context(context);
+            }
+        }
+    }
+
+    /// Get the language model context following `node`
+    ///
+    /// The context is kept by the node. It is made by entering the node's
+    /// symbol into a clone of its parent's context, or from the text before
+    /// the node where the node has no parent or has been written, and is
+    /// made again once the text before the cursor has been entered afresh.
+    fn node_language_context(&mut self, node: &Rc<RefCell<DasherNode>>) -> Option<Context> {
+        self.release_node_contexts();
+        let current = |node: &DasherNode| node.language_context()
+            .filter(|context| context.is_current(self.context_generation, &self.released_contexts))
+            .map(NodeContext::context);
+        if let Some(context) = current(&node.borrow()) {
+            return Some(context);
+        }
+
+        let language_model = self.language_model.as_mut()?;
+        let (parent_context, symbol) = {
+            let node_ref = node.borrow();
+            let parent = node_ref.parent().and_then(Weak::upgrade)
+                .filter(|_| !node_ref.get_flag(NodeFlags::SEEN));
+            (parent.and_then(|parent| current(&parent.borrow())), node_ref.symbol())
+        };
+        let context = match (parent_context, symbol) {
+            // A new paragraph is predicted like the start of the text
+            (Some(_), Some(Symbol::PARAGRAPH)) => language_model.create_empty_context(),
+            (Some(parent_context), symbol) => {
+                let context = language_model.clone_context(parent_context);
+                if let Some(index) = symbol.and_then(|c| self.symbol_characters.iter().position(|&s| s == c)) {
+                    language_model.enter_symbol(context, index);
+                }
+                context
+            }
+            (None, _) => {
+                let text = Self::node_context(self.output_text.text_before_cursor(), node);
+                Self::create_context(language_model.as_mut(), &self.symbol_characters, &text)
+            }
+        };
+        let node_context = NodeContext::new(context, self.context_generation, self.released_contexts.clone());
+        node.borrow_mut().set_language_context(Some(node_context));
+        Some(context)
     }
 
     /// Get a reference to the language model
@@ -300,20 +939,208 @@ impl DasherModel {
         }
     }
 
+    /// Get a mutable reference to the language model
+    pub fn language_model_mut(&mut self) -> Option<&mut (dyn LanguageModel + 'static)> {
+        self.language_model.as_deref_mut()
+    }
+
+    /// Get the statistics of the language model's probability cache, if it has one
+    ///
+    /// A low hit rate while navigating suggests the cache is too small for
+    /// the number of nodes expanded each frame.
+    pub fn language_model_cache_stats(&self) -> Option<CacheStats> {
+        self.language_model.as_ref().and_then(|model| model.cache_stats())
+    }
+
+    /// Get the total information entered so far (in nats)
+    pub fn total_nats(&self) -> f64 {
+        self.total_nats
+    }
+
     /// Get the current output text
     pub fn output_text(&self) -> &str {
+        self.output_text.as_str()
+    }
+
+    /// Get the output buffer
+    pub fn text_buffer(&self) -> &TextBuffer {
         &self.output_text
     }
 
-    /// Append a character to the output text
+    /// Insert a character into the output text at the cursor
     pub fn append_to_output(&mut self, c: char) {
-        self.output_text.push(c);
-        self.update_language_model(c);
+        self.output_text.insert_char(c);
+        self.write_to_language_model(c);
     }
 
-    /// Set the output text
+    /// Set the output text, moving the cursor to the end
     pub fn set_output_text(&mut self, text: &str) {
-        self.output_text = text.to_string();
+        self.output_text.set_text(text);
+        self.sync_language_model_context();
+    }
+
+    /// Delete up to `count` characters before the cursor, returning the deleted text
+    pub fn delete_before_cursor(&mut self, count: usize) -> String {
+        let deleted = self.output_text.delete_before(count);
+        self.sync_language_model_context();
+        deleted
+    }
+
+    /// Delete up to `count` characters after the cursor, returning the deleted text
+    pub fn delete_after_cursor(&mut self, count: usize) -> String {
+        self.output_text.delete_after(count)
+    }
+
+    /// Delete the character before the cursor, rewinding the node tree
+    ///
+    /// If the deleted character was written by the last output node, the root is
+    /// reparented back into that node's parent (restoring old roots as needed) so
+    /// the tree and view stay in step with the text. Otherwise the tree is rebuilt
+    /// for the new context. Returns `false` if there was nothing to delete.
+    pub fn backspace(&mut self) -> bool {
+        if self.output_text.cursor() == 0 {
+            return false;
+        }
+
+        // The last jamo or accent of a composed character is erased on its own,
+        // taking the symbol of the last output node as the one written last
+        let written = self.last_output.as_ref().and_then(Weak::upgrade).and_then(|node| node.borrow().symbol());
+        let deleted = match written.and_then(|c| self.decomposition_of(Some(c))).or_else(|| self.decomposition_of(None)) {
+            Some((_, rest, last)) => {
+                self.output_text.delete_before(1);
+                self.output_text.insert_char(rest);
+                Some(last)
+            }
+            None => self.output_text.delete_before(1).chars().next(),
+        };
+        self.unlearn_last(deleted);
+        self.sync_language_model_context();
+
+        if !self.rewind_last_output(deleted) {
+            self.rebuild_tree();
+        }
+        true
+    }
+
+    /// Move the tree back into the parent of the last output node
+    ///
+    /// Returns `false` if the tree could not be rewound and must be rebuilt.
+    fn rewind_last_output(&mut self, deleted: Option<char>) -> bool {
+        let Some(node) = self.last_output.as_ref().and_then(Weak::upgrade) else {
+            return false;
+        };
+        if deleted.is_none() || node.borrow().symbol() != deleted {
+            return false;
+        }
+        let Some(parent) = node.borrow().parent().and_then(Weak::upgrade) else {
+            return false;
+        };
+
+        self.clear_scheduled_steps();
+
+        // Reparent the root until it is no longer inside the deleted node
+        while self.root_is_within(&node) {
+            if !self.reparent_root() {
+                return false;
+            }
+
+            // The new root no longer needs to be kept alive as an old root
+            if let (Some(root), Some(back)) = (&self.root, self.old_roots.back()) {
+                if Rc::ptr_eq(root, back) {
+                    self.old_roots.pop_back();
+                }
+            }
+        }
+
+        node.borrow_mut().set_flag(NodeFlags::SEEN, false);
+        self.last_output = Some(Rc::downgrade(&parent));
+        true
+    }
+
+    /// Check if the root is `node` or one of its descendants
+    fn root_is_within(&self, node: &Rc<RefCell<DasherNode>>) -> bool {
+        let mut current = self.root.clone();
+        while let Some(candidate) = current {
+            if Rc::ptr_eq(&candidate, node) {
+                return true;
+            }
+            current = candidate.borrow().parent().and_then(Weak::upgrade);
+        }
+        false
+    }
+
+    /// Rewind the language model's context to the text before the cursor
+    pub(crate) fn sync_language_model_context(&mut self) {
+        let text = Self::context_text(self.output_text.text_before_cursor()).to_string();
+        self.set_language_model_context(&text);
+    }
+
+    /// Replace the language model's context with one ending with `text`, without learning from it
+    fn set_language_model_context(&mut self, text: &str) {
+        // The nodes' contexts followed the text as it was
+        self.context_generation += 1;
+        if let Some(language_model) = &mut self.language_model {
+            if let Some(context) = self.language_model_context.take() {
+                language_model.release_context(context);
+            }
+            let context = Self::create_context(language_model.as_mut(), &self.symbol_characters, text);
+            self.language_model_context = Some(context);
+        }
+    }
+
+    /// Get the end of `text` that a language model context is created from
+    ///
+    /// The context starts after the last paragraph symbol, if there is one.
+    fn context_text(text: &str) -> &str {
+        let text = text.rfind(Symbol::PARAGRAPH).map_or(text, |i| &text[i + Symbol::PARAGRAPH.len_utf8()..]);
+        let start = text.char_indices().rev().nth(Self::CONTEXT_CHARS - 1).map_or(0, |(i, _)| i);
+        &text[start..]
+    }
+
+    /// Create a language model context from the end of `text`
+    ///
+    /// Hangul syllables and accented letters that are not symbols themselves
+    /// are entered as the symbols they were composed from.
+    fn create_context(language_model: &mut dyn LanguageModel, symbols: &[char], text: &str) -> Context {
+        let context = language_model.create_empty_context();
+        let mut entered = String::new();
+        for c in Self::context_text(text).chars() {
+            if symbols.contains(&c) {
+                entered.push(c);
+            } else if let Some(jamo) = hangul::decompose(c) {
+                entered.extend(jamo);
+            } else if let Some((first, second)) = diacritics::spelling(c, symbols) {
+                entered.extend([first, second]);
+            } else {
+                entered.push(c);
+            }
+        }
+        enter_text(language_model, context, symbols, &entered);
+        context
+    }
+
+    /// Get the cursor position in the output text (in characters)
+    pub fn cursor_position(&self) -> usize {
+        self.output_text.cursor()
+    }
+
+    /// Move the cursor in the output text, rebuilding the tree for the new context
+    pub fn set_cursor_position(&mut self, position: usize) {
+        if position != self.output_text.cursor() {
+            self.output_text.set_cursor(position);
+            self.sync_language_model_context();
+            self.rebuild_tree();
+        }
+    }
+
+    /// Move the cursor by a character, a word, or to either end of the text
+    ///
+    /// Returns `false` if the cursor was already where the movement would take it.
+    pub fn move_cursor(&mut self, movement: CursorMovement) -> bool {
+        let target = self.output_text.movement_target(movement);
+        let moved = target != self.output_text.cursor();
+        self.set_cursor_position(target);
+        moved
     }
 
     /// Set the root node
@@ -338,23 +1165,226 @@ impl DasherModel {
 
         let width = (Self::MAX_Y as f64 / (2.0 * fraction)) as i64;
 
-        self.root_min = Self::MAX_Y / 2 - width / 2;
-        self.root_max = Self::MAX_Y / 2 + width / 2;
+        self.jump_root(Self::MAX_Y / 2 - width / 2, Self::MAX_Y / 2 + width / 2);
     }
 
-    /// Get the current offset in the text buffer
-    pub fn get_offset(&self) -> i32 {
-        if let Some(last_output) = &self.last_output {
-            if let Some(node) = last_output.upgrade() {
-                return node.borrow().offset() + 1;
+    /// Get the minimum probability (in 1/NORMALIZATION units) for a symbol to get its own node
+    pub fn min_child_probability(&self) -> u32 {
+        self.min_child_probability
+    }
+
+    /// Set the minimum probability (in 1/NORMALIZATION units) for a symbol to get its own node.
+    ///
+    /// Less probable symbols are grouped under a residual node that creates
+    /// them when it is expanded. 0 disables grouping. Takes effect for nodes
+    /// expanded from now on.
+    pub fn set_min_child_probability(&mut self, probability: u32) {
+        self.min_child_probability = probability.min(Self::NORMALIZATION);
+    }
+
+    /// Get the share of the probability (in thousandths) spread evenly over the symbols
+    pub fn uniform(&self) -> u32 {
+        self.uniform
+    }
+
+    /// Set the share of the probability (in thousandths) spread evenly over the symbols
+    ///
+    /// The language model's probabilities are mixed with a uniform
+    /// distribution, so every symbol keeps at least this share divided by the
+    /// number of symbols, however unlikely the language model finds it. This
+    /// keeps rare symbols large enough to steer into. Takes effect for nodes
+    /// expanded from now on.
+    pub fn set_uniform(&mut self, uniform: u32) {
+        self.uniform = uniform.min(1000);
+    }
+
+    /// Get the share of each node's interval (in thousandths) given to control nodes
+    pub fn control_mass(&self) -> u32 {
+        self.control_mass
+    }
+
+    /// Set the share of each node's interval (in thousandths) given to control nodes
+    ///
+    /// The share is split evenly between a control node for each registered
+    /// action, placed after the symbols, and the symbols' probabilities are
+    /// scaled down to make room. 0 leaves control nodes out, as upstream
+    /// Dasher does with control mode off. Takes effect for nodes expanded
+    /// from now on.
+    pub fn set_control_mass(&mut self, control_mass: u32) {
+        self.control_mass = control_mass.min(1000);
+    }
+
+    /// Check whether the alphabet's toggle groups are offered behind toggle nodes
+    pub fn toggle_groups(&self) -> bool {
+        self.toggle_groups
+    }
+
+    /// Set whether the alphabet's toggle groups are offered behind toggle nodes
+    ///
+    /// Otherwise their symbols are offered alongside the others. Takes
+    /// effect for nodes expanded from now on.
+    pub fn set_toggle_groups(&mut self, enabled: bool) {
+        self.toggle_groups = enabled;
+    }
+
+    /// Check whether Hangul jamo written in turn are composed into syllables
+    pub fn hangul_composition(&self) -> bool {
+        self.hangul_composition
+    }
+
+    /// Set whether Hangul jamo written in turn are composed into syllables
+    ///
+    /// With composition on, a jamo written after one it composes with
+    /// replaces it with their syllable, and erasing it splits the syllable
+    /// again (see [`hangul`]). The language model still sees the jamo.
+    pub fn set_hangul_composition(&mut self, enabled: bool) {
+        self.hangul_composition = enabled;
+    }
+
+    /// Check whether accents written next to their letters are composed with them
+    pub fn diacritic_composition(&self) -> bool {
+        self.diacritic_composition
+    }
+
+    /// Set whether accents written next to their letters are composed with them
+    ///
+    /// With composition on, a combining mark written after a letter, or a
+    /// letter written after a dead key, replaces the character before it
+    /// with the accented letter, and erasing it leaves that character again
+    /// (see [`diacritics`]). The language model still sees both symbols.
+    pub fn set_diacritic_composition(&mut self, enabled: bool) {
+        self.diacritic_composition = enabled;
+    }
+
+    /// Get the character `c` composes with the character before the cursor,
+    /// and that character, if composition is on and they compose
+    fn composition_with(&self, c: char) -> Option<(char, char)> {
+        let previous = self.output_text.text_before_cursor().chars().next_back()?;
+        let composed = hangul::compose(previous, c).filter(|_| self.hangul_composition)
+            .or_else(|| diacritics::compose(previous, c).filter(|_| self.diacritic_composition))?;
+        Some((previous, composed))
+    }
+
+    /// Split the composed character before the cursor, if composition is on
+    /// and it was written ending with `c` (or anything if `c` is `None`)
+    ///
+    /// Characters that are symbols of the alphabet are not split. Returns
+    /// the composed character, what is left of it and the symbol split off.
+    fn decomposition_of(&self, c: Option<char>) -> Option<(char, char, char)> {
+        let last = self.output_text.text_before_cursor().chars().next_back()?;
+        if self.symbol_characters.contains(&last) {
+            return None;
+        }
+        if self.hangul_composition {
+            if let Some((rest, jamo)) = hangul::split_last(last) {
+                return (c.is_none() || c == Some(jamo)).then_some((last, rest, jamo));
             }
         }
+        if !self.diacritic_composition {
+            return None;
+        }
+        let (rest, written) = match c {
+            Some(c) => (diacritics::split_off(last, c)?, c),
+            None => diacritics::spelling(last, &self.symbol_characters)?,
+        };
+        Some((last, rest, written))
+    }
 
-        if let Some(root) = &self.root {
-            return root.borrow().offset() + 1;
+    /// Check whether likely next words are offered after a word separator
+    pub fn next_word_prediction(&self) -> bool {
+        self.next_word_prediction
+    }
+
+    /// Set whether likely next words are offered after a word separator
+    ///
+    /// The words appear as nodes before the individual letters, and write the
+    /// whole word as the user zooms through them. Takes effect for nodes
+    /// expanded from now on.
+    pub fn set_next_word_prediction(&mut self, enabled: bool) {
+        self.next_word_prediction = enabled;
+    }
+
+    /// Set the provider of conversions, such as a platform input method
+    ///
+    /// Each node then offers the provider's candidates for the composition
+    /// spelled since the last word separator among its children; choosing
+    /// one replaces the composition with it. `None` stops offering them.
+    pub fn set_conversion_provider(&mut self, provider: Option<Box<dyn ConversionProvider>>) {
+        self.conversion.set_provider(provider);
+    }
+
+    /// Check whether a conversion provider has been set
+    pub fn has_conversion_provider(&self) -> bool {
+        self.conversion.has_provider()
+    }
+
+    /// Get the characters that end a word
+    pub fn word_separators(&self) -> &HashSet<char> {
+        &self.word_separators
+    }
+
+    /// Replace the characters that end a word
+    ///
+    /// The separators are used by the language model to learn and predict
+    /// words, by word prediction, and to mark the nodes that write them as
+    /// word boundaries. Takes effect for nodes expanded from now on.
+    pub fn set_word_separators<I: IntoIterator<Item = char>>(&mut self, separators: I) {
+        self.word_separators = separators.into_iter().collect();
+        self.apply_word_separators();
+    }
+
+    /// Add a character that ends a word
+    pub fn add_word_separator(&mut self, separator: char) {
+        self.word_separators.insert(separator);
+        self.apply_word_separators();
+    }
+
+    /// Remove a character from those that end a word
+    pub fn remove_word_separator(&mut self, separator: char) {
+        self.word_separators.remove(&separator);
+        self.apply_word_separators();
+    }
+
+    /// Pass the word separators on to the language model and word prediction
+    fn apply_word_separators(&mut self) {
+        let separators = &self.word_separators;
+        if let Some(combined) = self.language_model.as_mut()
+            .and_then(|lm| lm.as_any().downcast_mut::<CombinedLanguageModel>()) {
+            combined.set_word_separators(separators);
+        }
+        if let Some(manager) = &mut self.word_prediction {
+            manager.set_word_separators(separators);
         }
+    }
+
+    /// Check whether a space is written after a predicted word
+    pub fn auto_space(&self) -> bool {
+        self.auto_space
+    }
+
+    /// Set whether a space is written after a predicted word, so the next
+    /// word can follow straight away. Takes effect for nodes expanded from now on.
+    pub fn set_auto_space(&mut self, enabled: bool) {
+        self.auto_space = enabled;
+    }
+
+    /// Check whether punctuation written after a space is moved before it
+    pub fn smart_punctuation(&self) -> bool {
+        self.smart_punctuation
+    }
+
+    /// Set whether punctuation written after a space is moved before it
+    ///
+    /// Writing "." after "word " then gives "word. ", so a sentence can be
+    /// finished after an automatic space without deleting it first. Only
+    /// the punctuation in [`Self::ATTACHING_PUNCTUATION`] is moved.
+    pub fn set_smart_punctuation(&mut self, enabled: bool) {
+        self.smart_punctuation = enabled;
+    }
 
-        0
+    /// Get the current offset in the text buffer (the cursor position)
+    pub fn get_offset(&self) -> i32 {
+        self.output_text.cursor() as i32
     }
 
     /// Get the node that was under the crosshair in the last frame
@@ -367,17 +1397,8 @@ impl DasherModel {
     }
 
     /// Expand a node by creating its children
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(offset = node.borrow().offset(), children)))]
     pub fn expand_node(&mut self, node: &Rc<RefCell<DasherNode>>) {
-        // Insert action nodes as children (e.g., at the end)
-        // We'll use a special label and NodeFlags for action nodes
-        for action in self.action_manager.all_actions() {
-            let mut action_node = DasherNode::new(0, Some(action.label().to_string()));
-            action_node.set_flag(NodeFlags::CONTROL, true); // Mark as control/action node
-            action_node.set_flag(NodeFlags::ALL_CHILDREN, true); // No further expansion
-            action_node.set_parent(Rc::downgrade(node));
-            node.borrow_mut().add_child(Rc::new(RefCell::new(action_node)));
-        }
-
         // Get word predictions if this is a word boundary
         let _predictions = if node.borrow().is_word_boundary() {
             self.get_word_predictions()
@@ -395,83 +1416,226 @@ impl DasherModel {
 
         // Delete existing children
         node.borrow_mut().delete_children();
+        let lm_context = self.node_language_context(node);
 
         // Get the alphabet
         if let Some(alphabet) = &self.alphabet {
             // Get the current offset
             let offset = node.borrow().offset();
 
-            // Get the probabilities for each symbol
-            let probs = if let Some(lm) = &mut self.language_model {
-                // Use the language model to get probabilities
-                // Use the current output text as context for better predictions
-                let context = self.output_text.clone();
-                lm.get_probs(&context)
+            // A node of a predicted word has the word's next symbol as its only child
+            let predicted = node.borrow().predicted_text().to_string();
+            let mut rest = predicted.chars();
+            let next = rest.next().and_then(|c| alphabet.symbols().iter().find(|symbol| symbol.character == c));
+            if let Some(symbol) = next {
+                let mut child = Self::symbol_node(alphabet, symbol, offset, &self.word_separators);
+                child.set_predicted_text(rest.as_str().to_string());
+                child.set_bounds(0, Self::NORMALIZATION);
+                child.set_parent(Rc::downgrade(node));
+                node.borrow_mut().add_child(Rc::new(RefCell::new(child)));
+                node.borrow_mut().set_flag(NodeFlags::ALL_CHILDREN, true);
+                return;
+            }
+
+            // Get the count for each symbol, indexed like the alphabet
+            let mut counts = std::mem::take(&mut self.probs_buffer);
+            if let (Some(lm), Some(lm_context)) = (&self.language_model, lm_context) {
+                lm.get_probs(lm_context, Self::NORMALIZATION, &mut counts);
             } else {
                 // Use uniform probabilities
-                let num_symbols = alphabet.size();
-                let mut map = std::collections::HashMap::new();
-                for symbol in alphabet.symbols().iter() {
-                    map.insert(symbol.character, 1.0 / num_symbols as f64);
-                }
-                map
+                counts.clear();
+                counts.resize(self.symbol_characters.len(), 1);
+            }
+
+            // A residual node creates only the improbable symbols grouped under it
+            let residual = node.borrow().residual_symbols().to_vec();
+            let (symbols, counts_used): (Vec<_>, Vec<u32>) = alphabet.symbols().iter()
+                .zip(counts.iter().copied())
+                .filter(|(symbol, _)| residual.is_empty() || residual.contains(&symbol.character))
+                .unzip();
+            self.probs_buffer = counts;
+
+            // Normalise over the symbols being created
+            let total: u64 = counts_used.iter().map(|&count| count as u64).sum();
+            let probs: Vec<f64> = if total > 0 {
+                counts_used.iter().map(|&count| count as f64 / total as f64).collect()
+            } else {
+                vec![1.0 / symbols.len().max(1) as f64; symbols.len()]
             };
 
-            // Create a child for each symbol in the alphabet
-            let mut lower_bound = 0;
-            let mut created_count = 0;
+            // Mix in a uniform distribution, so no symbol becomes too small to reach
+            let uniform = self.uniform as f64 / 1000.0;
+            let floor = uniform / probs.len().max(1) as f64;
+            let probs: Vec<f64> = probs.iter().map(|prob| prob * (1.0 - uniform) + floor).collect();
 
-            // Ensure we have at least some probability for each symbol
-            let min_prob = 1;
+            // Give symbols the alphabet pins at least their fixed share
+            let fixed: Vec<_> = symbols.iter().map(|symbol| alphabet.fixed_probability(symbol.character)).collect();
+            let probs = Self::apply_fixed_probabilities(probs, &fixed);
 
-            for symbol in alphabet.symbols().iter() {
-                // Get probability from language model or use minimum
-                let mut prob = probs.get(&symbol.character).copied().unwrap_or(0.0) as u32;
+            // Find the toggle group of each symbol, unless this node is a toggle node itself
+            let toggle_groups: &[ToggleGroup] = if self.toggle_groups && residual.is_empty() {
+                alphabet.toggle_groups()
+            } else {
+                &[]
+            };
+            let toggle_indices: Vec<_> = symbols.iter()
+                .map(|symbol| toggle_groups.iter().position(|group| group.contains(symbol.character)))
+                .collect();
+
+            // Group improbable symbols under a residual node, if more than one falls below the threshold
+            let threshold = self.min_child_probability as f64 / Self::NORMALIZATION as f64;
+            let group = residual.is_empty()
+                && probs.iter().zip(&toggle_indices).filter(|(prob, toggle)| toggle.is_none() && **prob < threshold).count() > 1;
+
+            let mut children = Vec::new();
+            let mut grouped = Vec::new();
+            let mut grouped_prob = 0.0;
+            let mut toggled = vec![(Vec::new(), 0.0); toggle_groups.len()];
+
+            for ((symbol, &prob), toggle) in symbols.iter().zip(&probs).zip(toggle_indices) {
+                if let Some(index) = toggle {
+                    toggled[index].0.push(symbol.character);
+                    toggled[index].1 += prob;
+                    continue;
+                }
 
-                // Ensure minimum probability
-                if prob < min_prob {
-                    prob = min_prob;
+                if group && prob < threshold {
+                    grouped.push(symbol.character);
+                    grouped_prob += prob;
+                    continue;
                 }
 
-                // Create node if probability is positive
-                if prob > 0 {
-                    let upper_bound = lower_bound + prob;
+                // Create a new node for this symbol
+                children.push((Self::symbol_node(alphabet, symbol, offset, &self.word_separators), prob));
+            }
+
+            for (toggle_group, (characters, prob)) in toggle_groups.iter().zip(toggled) {
+                if characters.is_empty() {
+                    continue;
+                }
+                // Like the residual node, a toggle node writes nothing itself
+                let mut toggle = DasherNode::new(offset, Some(toggle_group.label.clone()));
+                toggle.set_colors((0, 0, 0), (200, 220, 255));
+                toggle.set_residual_symbols(characters);
+                children.push((toggle, prob));
+            }
 
-                    // Create a new node for this symbol
-                    let child = Rc::new(RefCell::new(DasherNode::new(
-                        offset + 1,
-                        Some(symbol.display_text.clone()),
-                    )));
+            if !grouped.is_empty() {
+                // The residual node writes nothing itself, so it shares the node's offset
+                let mut other = DasherNode::new(offset, Some(Self::RESIDUAL_LABEL.to_string()));
+                other.set_colors((0, 0, 0), (220, 220, 220));
+                other.set_residual_symbols(grouped);
+                children.push((other, grouped_prob));
+            }
 
-                    // Set the bounds
-                    child.borrow_mut().set_bounds(lower_bound, upper_bound);
+            // Offer the likely next words before the individual letters
+            if self.next_word_prediction && residual.is_empty() {
+                let context = Self::node_context(self.output_text.text_before_cursor(), node);
+                let words: Vec<_> = self.language_model.as_mut()
+                    .and_then(|lm| lm.as_any().downcast_mut::<CombinedLanguageModel>())
+                    .map(|combined| combined.predict_next_words(&context, Self::MAX_NEXT_WORDS))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|(word, _)| word.chars().all(|c| self.symbol_characters.contains(&c)))
+                    .map(|(word, prob)| (word, prob * Self::NEXT_WORD_WEIGHT))
+                    .collect();
+
+                let word_mass: f64 = words.iter().map(|(_, prob)| prob).sum();
+                for (_, prob) in &mut children {
+                    *prob *= 1.0 - word_mass;
+                }
+                let word_nodes = words.into_iter().map(|(word, prob)| {
+                    // The word node writes nothing itself; its descendants write the word
+                    let mut word_node = DasherNode::new(offset, Some(word.clone()));
+                    word_node.set_colors((0, 0, 0), (255, 250, 205));
+                    let text = if self.auto_space && self.symbol_characters.contains(&' ') {
+                        format!("{} ", word)
+                    } else {
+                        word
+                    };
+                    word_node.set_predicted_text(text);
+                    (word_node, prob)
+                });
+                children.splice(0..0, word_nodes);
+            }
 
-                    // Set the symbol
-                    child.borrow_mut().set_symbol(symbol.character);
+            // Offer the provider's conversions of the composition spelled so far
+            if self.conversion.has_provider() && residual.is_empty() {
+                let context = Self::node_context(self.output_text.text_before_cursor(), node);
+                let start = context.char_indices().rev()
+                    .find(|(_, c)| self.word_separators.contains(c))
+                    .map_or(0, |(i, c)| i + c.len_utf8());
+                let (before, composition) = context.split_at(start);
+                if !composition.is_empty() {
+                    self.conversion.set_context(before.to_string());
+                    let candidates: Vec<_> = self.conversion.candidates(composition, AlphabetConversion::None)
+                        .into_iter()
+                        .filter(|candidate| candidate.text != composition && candidate.probability > 0.0)
+                        .take(Self::MAX_CONVERSIONS)
+                        .collect();
+                    let total: f64 = candidates.iter().map(|candidate| candidate.probability).sum();
+                    let mass = total.min(1.0) * Self::CONVERSION_WEIGHT;
+                    for (_, prob) in &mut children {
+                        *prob *= 1.0 - mass;
+                    }
+                    let conversion_nodes = candidates.into_iter().map(|candidate| {
+                        // Like a control node, a conversion node changes the text and rebuilds the tree
+                        let mut conversion = DasherNode::new(offset, Some(candidate.text.clone()));
+                        conversion.set_colors((0, 0, 0), (220, 255, 220));
+                        conversion.set_flag(NodeFlags::ALL_CHILDREN, true);
+                        conversion.set_conversion(composition.to_string(), candidate.text);
+                        (conversion, candidate.probability / total * mass)
+                    });
+                    children.splice(0..0, conversion_nodes);
+                }
+            }
 
-                    // Set the colors
-                    child.borrow_mut().set_colors(
-                        (symbol.foreground_color.r, symbol.foreground_color.g, symbol.foreground_color.b),
-                        (symbol.background_color.r, symbol.background_color.g, symbol.background_color.b)
-                    );
+            // Give the control nodes their share after the symbols
+            let actions = self.action_manager.all_actions();
+            if self.control_mass > 0 && residual.is_empty() && !actions.is_empty() {
+                let control_mass = self.control_mass as f64 / 1000.0;
+                for (_, prob) in &mut children {
+                    *prob *= 1.0 - control_mass;
+                }
+                let share = control_mass / actions.len() as f64;
+                for action in actions {
+                    // A control node writes nothing itself; running its action rebuilds the tree
+                    let mut control = DasherNode::new(offset, Some(action.label().to_string()));
+                    control.set_flag(NodeFlags::CONTROL, true);
+                    control.set_flag(NodeFlags::ALL_CHILDREN, true);
+                    control.set_action(Some(action.name().to_string()));
+                    control.set_colors((0, 0, 0), (200, 200, 200));
+                    children.push((control, share));
+                }
+            }
 
-                    // Set the parent
-                    child.borrow_mut().set_parent(Rc::downgrade(node));
+            // Give each child at least one unit, sharing the rest by probability
+            let count = children.len();
+            let free = Self::NORMALIZATION.saturating_sub(count as u32) as f64;
+            let mut lower_bound = 0;
 
-                    // Add the child to the parent
-                    node.borrow_mut().add_child(child);
+            for (index, (mut child, prob)) in children.into_iter().enumerate() {
+                let upper_bound = if index + 1 == count {
+                    Self::NORMALIZATION
+                } else {
+                    lower_bound + 1 + (prob * free) as u32
+                };
 
-                    // Update the lower bound for the next symbol
-                    lower_bound = upper_bound;
-                    created_count += 1;
-                }
+                child.set_bounds(lower_bound, upper_bound);
+                child.set_parent(Rc::downgrade(node));
+                node.borrow_mut().add_child(Rc::new(RefCell::new(child)));
+                lower_bound = upper_bound;
             }
 
-            println!("Created {} child nodes for alphabet symbols", created_count);
+            dasher_log!(Debug, "Created {} child nodes for alphabet symbols", count);
         }
 
         // Set the ALL_CHILDREN flag
         node.borrow_mut().set_flag(NodeFlags::ALL_CHILDREN, true);
+        self.nodes_expanded += 1;
+
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("children", node.borrow().children().len());
 
         // Notify event handlers
         for handler in &self.node_creation_handlers {
@@ -479,6 +1643,126 @@ impl DasherModel {
         }
     }
 
+    /// Raise each probability with a fixed share below it to that share,
+    /// scaling the others down so the probabilities still add up to one
+    ///
+    /// Scaling down can take another symbol below its fixed share, so the
+    /// pinned symbols are found again until none are left.
+    fn apply_fixed_probabilities(mut probs: Vec<f64>, fixed: &[Option<f32>]) -> Vec<f64> {
+        let mut pinned = vec![false; probs.len()];
+        loop {
+            let pinned_total: f64 = fixed.iter().zip(&pinned)
+                .filter(|(_, &pinned)| pinned)
+                .filter_map(|(fixed, _)| fixed.map(f64::from))
+                .sum();
+            let free_total: f64 = probs.iter().zip(&pinned).filter(|(_, &pinned)| !pinned).map(|(prob, _)| prob).sum();
+            let scale = if free_total > 0.0 { (1.0 - pinned_total).max(0.0) / free_total } else { 0.0 };
+
+            let mut changed = false;
+            for (index, share) in fixed.iter().enumerate() {
+                if let Some(share) = share {
+                    if !pinned[index] && probs[index] * scale < f64::from(*share) {
+                        pinned[index] = true;
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                for (index, prob) in probs.iter_mut().enumerate() {
+                    *prob = match fixed[index] {
+                        Some(share) if pinned[index] => f64::from(share),
+                        _ => *prob * scale,
+                    };
+                }
+                break;
+            }
+        }
+
+        // Fixed shares adding up to more than one are scaled down together
+        let total: f64 = probs.iter().sum();
+        if total > 0.0 {
+            probs.iter_mut().for_each(|prob| *prob /= total);
+        }
+        probs
+    }
+
+    /// Create the node writing `symbol` of `alphabet`, as a child of a node at `offset`
+    fn symbol_node(alphabet: &Alphabet, symbol: &Symbol, offset: i32, word_separators: &HashSet<char>) -> DasherNode {
+        let mut child = DasherNode::new(offset + 1, Some(symbol.display_text.clone()));
+        child.set_symbol(symbol.character);
+        child.set_word_boundary(word_separators.contains(&symbol.character));
+        let (foreground, background) = alphabet.node_colors(symbol, offset + 1);
+        child.set_colors((foreground.r, foreground.g, foreground.b), (background.r, background.g, background.b));
+        child
+    }
+
+    /// Get the text that would precede the children of a node.
+    ///
+    /// This is the text before the cursor followed by the symbols of the
+    /// node and its ancestors that have not been output yet.
+    fn node_context(text_before_cursor: &str, node: &Rc<RefCell<DasherNode>>) -> String {
+        let mut pending = Vec::new();
+        let mut seen_offset = None;
+        let mut current = Some(node.clone());
+        while let Some(n) = current {
+            let n_ref = n.borrow();
+            if n_ref.get_flag(NodeFlags::SEEN) {
+                seen_offset = Some(n_ref.offset());
+                break;
+            }
+            pending.extend(n_ref.symbol());
+            current = n_ref.parent().and_then(|parent| parent.upgrade());
+        }
+
+        // A node written further back (such as a rebuilt parent) ends the text at its offset
+        let end = seen_offset
+            .and_then(|offset| text_before_cursor.char_indices().nth(usize::try_from(offset + 1).ok()?))
+            .map_or(text_before_cursor.len(), |(i, _)| i);
+        let mut context = text_before_cursor[..end].to_string();
+        context.extend(pending.into_iter().rev());
+        context
+    }
+
+    /// Expand and collapse nodes on screen, within the limits of a policy
+    ///
+    /// Returns the number of nodes expanded.
+    pub fn expand_visible_nodes(&mut self, policy: &ExpansionPolicy) -> usize {
+        let Some(root) = self.root.clone() else {
+            return 0;
+        };
+
+        let plan = policy.plan(&root, (self.root_min, self.root_max), (0, Self::MAX_Y));
+        for node in &plan.collapse {
+            node.borrow_mut().delete_children();
+        }
+        self.nodes_collapsed += plan.collapse.len() as u64;
+        self.prefetch_predictions(&plan.expand);
+        for node in &plan.expand {
+            self.expand_node(node);
+        }
+        plan.expand.len()
+    }
+
+    /// Let a language model that predicts in batches predict for the
+    /// children of all of `nodes` at once
+    fn prefetch_predictions(&mut self, nodes: &[Rc<RefCell<DasherNode>>]) {
+        let batches = self.language_model.as_ref().is_some_and(|language_model| language_model.predicts_in_batches());
+        if nodes.len() < 2 || !batches {
+            return;
+        }
+
+        let contexts: Vec<Context> = nodes.iter()
+            .filter(|node| {
+                let node = node.borrow();
+                !node.get_flag(NodeFlags::ALL_CHILDREN) && node.predicted_text().is_empty()
+            })
+            .filter_map(|node| self.node_language_context(node))
+            .collect();
+        if let Some(language_model) = &mut self.language_model {
+            language_model.prefetch(&contexts);
+        }
+    }
+
     /// Make a child of the root into a new root
     pub fn make_root(&mut self, new_root: &Rc<RefCell<DasherNode>>) {
         // Get the current root
@@ -520,19 +1804,17 @@ impl DasherModel {
     }
 
     /// Reparent the root to its parent
+    ///
+    /// A root whose parent is no longer kept has it rebuilt from the text
+    /// before it (see [`Self::rebuild_parent`]), so zooming out can go on
+    /// erasing text back to the start.
     pub fn reparent_root(&mut self) -> bool {
         // Get the parent of the current root
-        let parent_node = {
-            if let Some(root) = &self.root {
-                if let Some(parent) = root.borrow().parent() {
-                    parent.upgrade()
-                } else {
-                    None
-                }
-            } else {
-                None
-            }
+        let Some(root) = self.root.clone() else {
+            return false;
         };
+        let parent_node = root.borrow().parent().and_then(Weak::upgrade);
+        let parent_node = parent_node.or_else(|| self.rebuild_parent(&root));
 
         if let Some(parent_node) = parent_node {
             // Calculate the new coordinates
@@ -579,95 +1861,243 @@ impl DasherModel {
         false
     }
 
+    /// Rebuild the parent of a root that has none, from the text before it
+    ///
+    /// The root takes the place of its symbol among the parent's children,
+    /// and the parent is expanded without grouping improbable symbols so
+    /// that the symbol is always a direct child. Returns `None` at the start
+    /// of the text, or if the root's character is not in the alphabet.
+    fn rebuild_parent(&mut self, root: &Rc<RefCell<DasherNode>>) -> Option<Rc<RefCell<DasherNode>>> {
+        let offset = root.borrow().offset();
+        let c = self.output_text.text_before_cursor().chars().nth(usize::try_from(offset).ok()?)?;
+        self.alphabet.as_ref()?.get_index(c)?;
+
+        // The parent's text has been written already
+        let parent = Rc::new(RefCell::new(DasherNode::new(offset - 1, Some("Root".to_string()))));
+        parent.borrow_mut().set_flag(NodeFlags::SEEN, true);
+        parent.borrow_mut().set_flag(NodeFlags::COMMITTED, true);
+        let min_child_probability = std::mem::replace(&mut self.min_child_probability, 0);
+        self.expand_node(&parent);
+        self.min_child_probability = min_child_probability;
+
+        let child = parent.borrow().children().iter()
+            .find(|child| child.borrow().symbol() == Some(c))
+            .cloned()?;
+        {
+            let child_ref = child.borrow();
+            let mut root_mut = root.borrow_mut();
+            root_mut.set_bounds(child_ref.lower_bound(), child_ref.upper_bound());
+            root_mut.set_symbol(c);
+            root_mut.set_word_boundary(child_ref.is_word_boundary());
+            root_mut.set_flag(NodeFlags::SEEN, true);
+            root_mut.set_parent(Rc::downgrade(&parent));
+        }
+        parent.borrow_mut().replace_child(&child, root.clone());
+
+        // The root's character is erased once the crosshair leaves it
+        if self.last_output.as_ref().and_then(Weak::upgrade).is_none() {
+            self.last_output = Some(Rc::downgrade(root));
+        }
+        Some(parent)
+    }
+
     /// Process the next scheduled step
     pub fn next_scheduled_step(&mut self) -> bool {
-        if self.goto_queue.is_empty() {
+        let Some(&(new_root_min, new_root_max)) = self.goto_queue.front() else {
             return false;
+        };
+
+        // Update the total information
+        self.total_nats += ((new_root_max - new_root_min) as f64 / (self.root_max - self.root_min) as f64).ln();
+
+        // When zooming out, give the root back its parent while it no longer fills the screen.
+        // Reparenting rescales the scheduled step into the parent's coordinates.
+        while let Some(&(new_root_min, new_root_max)) = self.goto_queue.front() {
+            if (new_root_min <= 0 && new_root_max >= Self::MAX_Y) || !self.reparent_root() {
+                break;
+            }
         }
 
-        if let Some((new_root_min, new_root_max)) = self.goto_queue.pop_front() {
-            // Update the total information
-            self.total_nats += ((new_root_max - new_root_min) as f64 / (self.root_max - self.root_min) as f64).ln();
-
-            // Update the display offset
-            self.display_offset = (self.display_offset * 90) / 100;
-
-            // Check if we need to make a child the new root
-            if new_root_min <= Self::ORIGIN_Y && new_root_max > Self::ORIGIN_Y {
-                // Find a child that covers the crosshair
-                let child_to_make_root = {
-                    let mut result = None;
-
-                    if let Some(root) = &self.root {
-                        let root_ref = root.borrow();
-                        let width = self.root_max - self.root_min;
-                        let is_game_path = root_ref.get_flag(NodeFlags::GAME);
-
-                        // Find the child covering the crosshair
-                        for child in root_ref.children() {
-                            let child_ref = child.borrow();
-                            let child_min = self.root_min + ((child_ref.lower_bound() as i64 * width) / (Self::NORMALIZATION as i64));
-                            let child_max = self.root_min + ((child_ref.upper_bound() as i64 * width) / (Self::NORMALIZATION as i64));
-
-                            if child_min <= Self::ORIGIN_Y && child_max > Self::ORIGIN_Y {
-                                // Check if the child is on the game path
-                                if is_game_path && !child_ref.get_flag(NodeFlags::GAME) {
-                                    return false;
-                                }
-
-                                result = Some(child.clone());
-                                break;
-                            }
-                        }
-                    }
+        let Some((mut new_root_min, mut new_root_max)) = self.goto_queue.pop_front() else {
+            return false;
+        };
 
-                    result
-                };
+        // At the top of the tree, don't let the root shrink away from the edges of the screen
+        if new_root_min > 0 || new_root_max < Self::MAX_Y {
+            let width = (new_root_max - new_root_min).max(Self::MAX_Y);
+            new_root_min = new_root_min.clamp(Self::MAX_Y - width, 0);
+            new_root_max = new_root_min + width;
+        }
 
-                if let Some(child) = child_to_make_root {
-                    // Update the target coordinates
-                    self.goto_queue.push_back((new_root_min, new_root_max));
-
-                    // Make the child the new root
-                    self.root = Some(child);
-
-                    // Get the updated coordinates
-                    if let Some((updated_min, updated_max)) = self.goto_queue.pop_back() {
-                        // Continue with the updated coordinates
-                        if updated_min <= Self::ORIGIN_Y && updated_max > Self::ORIGIN_Y &&
-                           (updated_max - updated_min) > Self::MAX_Y / 4 {
-                            self.root_min = updated_min;
-                            self.root_max = updated_max;
-                            return true;
-                        }
-                    }
+        self.root_min = new_root_min;
+        self.root_max = new_root_max;
 
-                    return false;
+        // Write (or, when reversing, erase) text up to the node under the crosshair
+        if let Some(node) = self.find_node_under_crosshair() {
+            self.output_to_crosshair_node(&node);
+        }
+
+        // When zooming in, a child that fills the screen becomes the new root
+        while let Some(child) = self.child_covering_screen() {
+            self.make_root(&child);
+        }
+
+        true
+    }
+
+    /// Get the coordinates of a child given the coordinates of its parent
+    fn child_coordinates(parent_min: i64, parent_max: i64, child: &DasherNode) -> (i64, i64) {
+        let width = parent_max - parent_min;
+        (
+            parent_min + (child.lower_bound() as i64 * width) / Self::NORMALIZATION as i64,
+            parent_min + (child.upper_bound() as i64 * width) / Self::NORMALIZATION as i64,
+        )
+    }
+
+    /// Find the child of the root that covers the whole screen, if any
+    fn child_covering_screen(&self) -> Option<Rc<RefCell<DasherNode>>> {
+        let root = self.root.as_ref()?;
+        let root_ref = root.borrow();
+        root_ref.children().iter().find(|child| {
+            let (child_min, child_max) = Self::child_coordinates(self.root_min, self.root_max, &child.borrow());
+            child_min <= 0 && child_max >= Self::MAX_Y
+        }).cloned()
+    }
+
+    /// Find the deepest node whose box contains the crosshair
+    ///
+    /// A node's box extends from the right edge of the screen as far as it is
+    /// tall, so the crosshair is inside a node once it is taller than `ORIGIN_X`.
+    pub fn find_node_under_crosshair(&self) -> Option<Rc<RefCell<DasherNode>>> {
+        self.deepest_node_at(Self::ORIGIN_X, Self::ORIGIN_Y, (self.root_min, self.root_max))
+            .map(|(node, _)| node)
+    }
+
+    /// Find the deepest node whose box, as drawn, contains the point (`x`, `y`)
+    ///
+    /// The point is in Dasher coordinates, like the pointer. Returns the node
+    /// and the coordinates (y1, y2) it is drawn at, or `None` if the point is
+    /// outside the root (or in the margin, at negative `x`).
+    pub fn node_at(&self, x: i64, y: i64) -> Option<PlacedNode> {
+        let (root_min, root_max) = self.display_bounds();
+        if x < 0 || y < root_min || y >= root_max || x >= root_max - root_min {
+            return None;
+        }
+        self.deepest_node_at(x, y, (root_min, root_max))
+    }
+
+    /// Follow the children of the root at `root_bounds` whose boxes contain
+    /// the point (`x`, `y`), returning the last and its coordinates
+    fn deepest_node_at(&self, x: i64, y: i64, root_bounds: (i64, i64)) -> Option<PlacedNode> {
+        let mut node = self.root.clone()?;
+        let (mut node_min, mut node_max) = root_bounds;
+
+        loop {
+            let next = node.borrow().children().iter().find_map(|child| {
+                let (child_min, child_max) = Self::child_coordinates(node_min, node_max, &child.borrow());
+                let contains = child_min <= y && child_max > y && child_max - child_min > x;
+                contains.then(|| (child.clone(), child_min, child_max))
+            });
+
+            match next {
+                Some((child, child_min, child_max)) => {
+                    node = child;
+                    node_min = child_min;
+                    node_max = child_max;
                 }
+                None => return Some((node, (node_min, node_max))),
             }
+        }
+    }
 
-            // Only allow the update if it won't make the root too small
-            if (new_root_max - new_root_min) > Self::MAX_Y / 4 {
-                self.root_min = new_root_min;
-                self.root_max = new_root_max;
-                return true;
+    /// Make the output match the node under the crosshair
+    ///
+    /// Nodes the crosshair has left (by reversing) are erased back to the
+    /// common ancestor before the path down to `node` is written.
+    fn output_to_crosshair_node(&mut self, node: &Rc<RefCell<DasherNode>>) {
+        while let Some(last) = self.last_output.as_ref().and_then(Weak::upgrade) {
+            if Self::is_ancestor_or_self(&last, node) {
+                break;
             }
+            self.unoutput(&last);
         }
+        self.output_to(node);
+    }
 
+    /// Check if `ancestor` is `node` or one of its ancestors
+    fn is_ancestor_or_self(ancestor: &Rc<RefCell<DasherNode>>, node: &Rc<RefCell<DasherNode>>) -> bool {
+        let mut current = Some(node.clone());
+        while let Some(candidate) = current {
+            if Rc::ptr_eq(&candidate, ancestor) {
+                return true;
+            }
+            current = candidate.borrow().parent().and_then(Weak::upgrade);
+        }
         false
     }
 
+    /// Erase the output of a node the crosshair has left, moving back to its parent
+    fn unoutput(&mut self, node: &Rc<RefCell<DasherNode>>) {
+        let symbol = node.borrow().symbol();
+        let space_moved = node.borrow().get_flag(NodeFlags::SPACE_MOVED);
+        if let Some(c) = symbol {
+            if space_moved && self.output_text.text_before_cursor().ends_with(&format!("{} ", c)) {
+                // Put the space back after the word
+                let before = self.output_text.as_str().to_string();
+                self.output_text.delete_before(2);
+                self.output_text.insert_char(' ');
+                if let Some(edit) = TextEdit::between(&before, self.output_text.as_str(), EditKind::Symbol) {
+                    self.undo_stack.push(edit);
+                }
+            } else if let Some((last, rest, _)) = self.decomposition_of(Some(c)) {
+                let offset = self.output_text.cursor() - 1;
+                self.output_text.delete_before(1);
+                self.output_text.insert_char(rest);
+                self.undo_stack.push(TextEdit { offset, removed: last.to_string(), inserted: rest.to_string(), kind: EditKind::Symbol });
+            } else {
+                let offset = self.output_text.cursor().saturating_sub(1);
+                let removed = self.output_text.delete_before(1);
+                if removed == c.to_string() {
+                    self.undo_stack.push(TextEdit { offset, removed, inserted: String::new(), kind: EditKind::Symbol });
+                }
+            }
+            self.unlearn_last(Some(c));
+            self.sync_language_model_context();
+        }
+        node.borrow_mut().set_flag(NodeFlags::SPACE_MOVED, false);
+
+        node.borrow_mut().undo_action();
+        node.borrow_mut().set_flag(NodeFlags::SEEN, false);
+        self.last_output = node.borrow().parent().cloned();
+    }
+
     /// Schedule a single step
+    ///
+    /// The step moves the view so that the target range `y1`-`y2` will fill the
+    /// screen after `n_steps` such steps. Targets narrower than `2 * lim_x` are
+    /// widened so that pointing at the very edge of the screen doesn't zoom too
+    /// fast. With `exact` dynamics each step zooms by the same factor; otherwise
+    /// a cheaper linear approximation is used.
     pub fn schedule_one_step(&mut self, y1: i64, y2: i64, n_steps: i32, lim_x: i32, exact: bool) {
         self.goto_queue.clear();
 
+        let n_steps = n_steps.max(1);
+
         // Rename for readability
         let r1 = self.root_min;
         let r2 = self.root_max;
 
+        // Apply the speed limit by widening small targets about their centre
+        let (y1, y2) = if y2 - y1 < 2 * lim_x as i64 {
+            let centre = (y1 + y2) / 2;
+            (centre - lim_x as i64, centre + lim_x as i64)
+        } else {
+            (y1, y2)
+        };
+
         // Calculate the bounds of the root node when the target range y1-y2
         // fills the viewport
-        let target_range = y2 - y1;
+        let target_range = (y2 - y1).max(1);
 
         let r1_new = Self::MAX_Y * (r1 - y1) / target_range;
         let r2_new = Self::MAX_Y * (r2 - y1) / target_range;
@@ -675,30 +2105,25 @@ impl DasherModel {
         let m1 = r1_new - r1;
         let m2 = r2_new - r2;
 
-        // Apply speed limit if necessary
-        let (m1_final, m2_final) = if target_range < 2 * lim_x as i64 {
+        let (m1_final, m2_final) = if exact {
             // Exact dynamics
-            if exact {
-                let frac = if target_range == Self::MAX_Y {
-                    1.0 / n_steps as f64
-                } else {
-                    let tr = target_range as f64;
-                    // Expansion factor for one step
-                    let e_fac = (Self::MAX_Y as f64 / tr).powf(1.0 / n_steps as f64);
-                    // Fraction of way along linear interpolation
-                    (e_fac - 1.0) / (Self::MAX_Y as f64 / tr - 1.0)
-                };
-
-                ((m1 as f64 * frac) as i64, (m2 as f64 * frac) as i64)
+            let frac = if target_range == Self::MAX_Y {
+                1.0 / n_steps as f64
             } else {
-                // Approximate dynamics
-                let ap_sq = (target_range as f64).sqrt() as i64;
-                let denom = 64 * (n_steps - 1) as i64 + ap_sq;
+                let tr = target_range as f64;
+                // Expansion factor for one step
+                let e_fac = (Self::MAX_Y as f64 / tr).powf(1.0 / n_steps as f64);
+                // Fraction of way along linear interpolation
+                (e_fac - 1.0) / (Self::MAX_Y as f64 / tr - 1.0)
+            };
 
-                ((m1 * ap_sq) / denom, (m2 * ap_sq) / denom)
-            }
+            ((m1 as f64 * frac) as i64, (m2 as f64 * frac) as i64)
         } else {
-            (m1, m2)
+            // Approximate dynamics, moving 1/n_steps of the way when the target fills the screen
+            let ap_sq = (target_range as f64).sqrt() as i64;
+            let denom = (Self::MAX_Y as f64).sqrt() as i64 * (n_steps - 1) as i64 + ap_sq;
+
+            ((m1 * ap_sq) / denom, (m2 * ap_sq) / denom)
         };
 
         // Add the step to the queue
@@ -792,17 +2217,146 @@ impl DasherModel {
 
             // If the node has a symbol, append it to the output text
             if let Some(c) = symbol {
-                self.output_text.push(c);
+                let move_space = self.smart_punctuation
+                    && Self::ATTACHING_PUNCTUATION.contains(&c)
+                    && self.output_text.text_before_cursor().ends_with(' ');
+                if move_space {
+                    // Write the punctuation before the space: "word " becomes "word. "
+                    let before = self.output_text.as_str().to_string();
+                    self.output_text.delete_before(1);
+                    self.unlearn_last(Some(' '));
+                    self.sync_language_model_context();
+                    for c in [c, ' '] {
+                        self.output_text.insert_char(c);
+                        self.write_to_language_model(c);
+                    }
+                    if let Some(edit) = TextEdit::between(&before, self.output_text.as_str(), EditKind::Symbol) {
+                        self.undo_stack.push(edit);
+                    }
+                    new_node.borrow_mut().set_flag(NodeFlags::SPACE_MOVED, true);
+                } else if let Some((previous, syllable)) = self.composition_with(c) {
+                    // The jamo or accent joins the character before it
+                    let offset = self.output_text.cursor() - 1;
+                    self.output_text.delete_before(1);
+                    self.output_text.insert_char(syllable);
+                    self.undo_stack.push(TextEdit {
+                        offset,
+                        removed: previous.to_string(),
+                        inserted: syllable.to_string(),
+                        kind: EditKind::Symbol,
+                    });
+                    self.write_to_language_model(c);
+                } else {
+                    self.undo_stack.push(TextEdit {
+                        offset: self.output_text.cursor(),
+                        removed: String::new(),
+                        inserted: c.to_string(),
+                        kind: EditKind::Symbol,
+                    });
+                    self.output_text.insert_char(c);
+                    self.write_to_language_model(c);
+                }
+            }
+
+            // A conversion node replaces the composition before it, so the
+            // tree is rebuilt around the result, filling the screen
+            let conversion = new_node.borrow().conversion().map(|(input, output)| (input.to_string(), output.to_string()));
+            if let Some((input, output)) = conversion {
+                new_node.borrow_mut().set_flag(NodeFlags::SEEN, true);
+                self.convert_composition(&input, &output);
+                self.jump_root(0, Self::MAX_Y);
+                self.rebuild_tree();
+                return;
             }
 
             // Perform the node's action
             new_node.borrow_mut().do_action();
+            let action = new_node.borrow().action().map(str::to_string);
+            if let Some(name) = action {
+                // The action may change the text, so the tree is rebuilt around the result, filling the screen
+                new_node.borrow_mut().set_flag(NodeFlags::SEEN, true);
+                self.execute_action(&name);
+                self.jump_root(0, Self::MAX_Y);
+                self.rebuild_tree();
+                return;
+            }
 
             // Mark the node as seen
             new_node.borrow_mut().set_flag(NodeFlags::SEEN, true);
         }
     }
 
+    /// Replace the composition `input` just before the cursor with its conversion `output`
+    fn convert_composition(&mut self, input: &str, output: &str) {
+        if !self.output_text.text_before_cursor().ends_with(input) {
+            return;
+        }
+        let count = input.chars().count();
+        let offset = self.output_text.cursor() - count;
+        self.output_text.delete_before(count);
+        self.output_text.insert_str(output);
+        self.undo_stack.push(TextEdit {
+            offset,
+            removed: input.to_string(),
+            inserted: output.to_string(),
+            kind: EditKind::Symbol,
+        });
+        self.conversion.learn(input, output, AlphabetConversion::None);
+        self.sync_language_model_context();
+    }
+
+    /// Execute a registered action by name
+    ///
+    /// Returns `false` if no action with that name is registered.
+    pub fn execute_action(&mut self, name: &str) -> bool {
+        let before = self.output_text.as_str().to_string();
+        let revision = self.undo_stack.revision();
+
+        // Take the manager out so the action can borrow the model mutably
+        let manager = std::mem::take(&mut self.action_manager);
+        let executed = manager.execute(name, self);
+        self.action_manager = manager;
+
+        // Record the action, unless it manipulated the history itself (undo/redo)
+        if executed && self.undo_stack.revision() == revision {
+            if let Some(edit) = TextEdit::between(&before, self.output_text.as_str(), EditKind::Action(name.to_string())) {
+                self.undo_stack.push(edit);
+            }
+        }
+        executed
+    }
+
+    /// Undo the most recent text operation
+    ///
+    /// Returns `false` if there was nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        if self.undo_stack.undo(&mut self.output_text) {
+            self.sync_language_model_context();
+            self.rebuild_tree();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Redo the most recently undone text operation
+    ///
+    /// Returns `false` if there was nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        if self.undo_stack.redo(&mut self.output_text) {
+            self.sync_language_model_context();
+            self.rebuild_tree();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get the history of text operations
+    pub fn undo_stack(&self) -> &UndoStack {
+        &self.undo_stack
+    }
+
     /// Register a handler for node creation events
     pub fn on_node_children_created<F>(&mut self, handler: F)
     where
@@ -822,222 +2376,951 @@ impl DasherModel {
         }
     }
 
+    /// Get the number of nodes expanded and collapsed since the model was created
+    ///
+    /// Both only ever grow, so the work done in a frame is the difference
+    /// between the counts before and after it.
+    pub fn node_counters(&self) -> (u64, u64) {
+        (self.nodes_expanded, self.nodes_collapsed)
+    }
+
+    /// Get the coordinates (y1, y2) of the root node
+    pub fn root_bounds(&self) -> (i64, i64) {
+        (self.root_min, self.root_max)
+    }
+
+    /// Get the coordinates (y1, y2) the root node is drawn at
+    ///
+    /// These differ from [`Self::root_bounds`] by the display offset while a
+    /// jump of the root is being animated.
+    pub fn display_bounds(&self) -> (i64, i64) {
+        (self.root_min + self.display_offset, self.root_max + self.display_offset)
+    }
+
+    /// Get the distance the root is drawn from where it is
+    pub fn display_offset(&self) -> i64 {
+        self.display_offset
+    }
+
+    /// Ease the display offset towards zero over `elapsed_ms` of frame time
+    ///
+    /// The offset halves every [`Self::DISPLAY_OFFSET_HALF_LIFE_MS`], so jumps
+    /// animate at the same speed whatever the frame rate.
+    pub fn ease_display(&mut self, elapsed_ms: u64) {
+        let remaining = 0.5f64.powf(elapsed_ms as f64 / Self::DISPLAY_OFFSET_HALF_LIFE_MS);
+        self.display_offset = (self.display_offset as f64 * remaining) as i64;
+    }
+
+    /// Move the root to new coordinates, keeping it drawn where it was so
+    /// that the display eases to its new position
+    ///
+    /// The offset is limited to the height of the screen, so even a root
+    /// far off the screen slides back into view.
+    fn jump_root(&mut self, root_min: i64, root_max: i64) {
+        let shift = (self.root_min + self.root_max) / 2 - (root_min + root_max) / 2;
+        self.display_offset = (self.display_offset + shift).clamp(-Self::MAX_Y, Self::MAX_Y);
+        self.root_min = root_min;
+        self.root_max = root_max;
+    }
+
+    /// Get the number of zoom steps scheduled but not yet taken
+    pub fn scheduled_step_count(&self) -> usize {
+        self.goto_queue.len()
+    }
+
+    /// Get the number of nodes in the tree, from the root down
+    pub fn node_count(&self) -> usize {
+        fn count(node: &Rc<RefCell<DasherNode>>) -> usize {
+            1 + node.borrow().children().iter().map(count).sum::<usize>()
+        }
+        self.root.as_ref().map_or(0, count)
+    }
+
     /// Get the root node
     pub fn get_root_node(&self) -> Option<Rc<RefCell<DasherNode>>> {
         self.root.clone()
     }
 
-    /// Render the model to a view
-    pub fn render_to_view<S: DasherScreen + ?Sized>(&mut self, view: &mut S) -> Result<()> {
-        // Get the screen dimensions
+    /// Render the model to a screen, without a view's nonlinear mapping
+    ///
+    /// Dasher coordinates are scaled linearly to fit the screen's height, with
+    /// X = 0 at its right-hand edge. Each node is drawn as deep as it is high,
+    /// and the tree is followed down until nodes are less than `min_pixels`
+    /// high.
+    pub fn render_to_view<S: DasherScreen + ?Sized>(&mut self, view: &mut S, min_pixels: i32) -> Result<()> {
         let width = view.get_width();
         let height = view.get_height();
 
-        // Debug output
-        eprintln!("Rendering to view: width={}, height={}", width, height);
-
         // Draw a background
         view.draw_rectangle(0, 0, width, height,
                            crate::view::color_palette::WHITE,
                            crate::view::color_palette::BLACK,
                            1);
 
-        // Always render the Dasher interface for now
-        eprintln!("Rendering Dasher interface");
-        self.render_dasher_interface(view, width, height);
+        // Draw the tree where the model has zoomed to
+        if let Some(root) = self.root.clone() {
+            let scale = height.max(1) as f64 / Self::MAX_Y as f64;
+            let (y1, y2) = self.display_bounds();
+            self.render_node(view, &root, y1, y2, scale, min_pixels);
+        }
 
         // Draw a crosshair
-        let cx = width / 2;
+        let cx = width - (Self::ORIGIN_X as f64 * height.max(1) as f64 / Self::MAX_Y as f64) as i32;
         let cy = height / 2;
         view.draw_line(cx - 10, cy, cx + 10, cy, crate::view::color_palette::RED, 2);
         view.draw_line(cx, cy - 10, cx, cy + 10, crate::view::color_palette::RED, 2);
 
-        // Draw a circle at the crosshair
-        view.draw_circle(cx, cy, 5,
-                        crate::view::color_palette::RED,
-                        crate::view::color_palette::BLACK,
-                        1);
-
         // Signal that the frame is complete
         view.display();
 
         Ok(())
     }
 
-    /// Render a node and its children
-    #[allow(dead_code)]
-    fn render_node<S: DasherScreen + ?Sized>(&self, view: &mut S, node: &Rc<RefCell<DasherNode>>, x1: i32, y1: i32, x2: i32, y2: i32) {
-        let node_ref = node.borrow();
-
-        // Draw the node
-        let bg_color = Color::from_tuple((node_ref.background_color().0, node_ref.background_color().1, node_ref.background_color().2, 200));
-        let fg_color = Color::from_tuple((node_ref.foreground_color().0, node_ref.foreground_color().1, node_ref.foreground_color().2, 255));
+    /// Render a node occupying `y1`-`y2` in Dasher coordinates, then its children
+    ///
+    /// `scale` is the number of pixels per Dasher unit.
+    fn render_node<S: DasherScreen + ?Sized>(&self, view: &mut S, node: &Rc<RefCell<DasherNode>>, y1: i64, y2: i64, scale: f64, min_pixels: i32) {
+        // Skip nodes off the screen or too small to see, along with their descendants
+        let (width, height) = (view.get_width(), view.get_height());
+        let top = (y1 as f64 * scale) as i32;
+        let bottom = (y2 as f64 * scale) as i32;
+        if bottom < 0 || top > height || bottom - top < min_pixels {
+            return;
+        }
 
-        // Draw the node background
-        view.draw_rectangle(x1, y1, x2, y2, bg_color, crate::view::color_palette::BLACK, 1);
+        let node_ref = node.borrow();
+        let left = width - (bottom - top).min(width);
+        let (r, g, b) = node_ref.background_color();
+        view.draw_rectangle(left, top, width, bottom, Color::from_tuple((r, g, b, 255)), crate::view::color_palette::BLACK, 1);
 
-        // Draw the node label
+        // Label the node at its left edge, if the label fits inside it
         if let Some(label) = node_ref.label() {
-            let label_obj = view.make_label(label, 0);
+            let label_obj = view.make_label(label, (width - left) as u32);
             let font_size = 24;
             let (text_width, text_height) = view.text_size(&*label_obj, font_size);
+            if text_width <= width - left && text_height <= bottom - top {
+                let (r, g, b) = node_ref.foreground_color();
+                view.draw_string(&*label_obj, left, (top + bottom - text_height) / 2, font_size, Color::from_tuple((r, g, b, 255)));
+            }
+        }
+
+        let range = y2 - y1;
+        for child in node_ref.children() {
+            let (lower, upper) = {
+                let child_ref = child.borrow();
+                (child_ref.lower_bound() as i64, child_ref.upper_bound() as i64)
+            };
+            let child_y1 = y1 + range * lower / Self::NORMALIZATION as i64;
+            let child_y2 = y1 + range * upper / Self::NORMALIZATION as i64;
+            self.render_node(view, child, child_y1, child_y2, scale, min_pixels);
+        }
+    }
+}
 
-            // Center the text in the node
-            let text_x = x1 + (x2 - x1 - text_width) / 2;
-            let text_y = y1 + (y2 - y1 - text_height) / 2;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paragraph_symbol_starts_a_new_context() {
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        let root = model.get_root_node().unwrap();
+        let paragraph = root.borrow().children().iter()
+            .find(|child| child.borrow().symbol() == Some(Symbol::PARAGRAPH))
+            .cloned()
+            .unwrap();
+        assert_eq!(paragraph.borrow().label().map(String::as_str), Some(Symbol::PARAGRAPH_DISPLAY));
+
+        model.set_output_text("Dear Sir,");
+        model.output_to(&paragraph);
+        assert_eq!(model.output_text(), "Dear Sir,\n");
+        assert_eq!(DasherModel::context_text(model.text_buffer().text_before_cursor()), "");
+        assert_eq!(DasherModel::context_text("one\ntwo\nthree"), "three");
+    }
 
-            view.draw_string(&*label_obj, text_x, text_y, font_size, fg_color);
+    #[test]
+    fn test_hangul_jamo_compose_in_the_output() {
+        let mut alphabet = Alphabet::new("Jamo");
+        for c in ['\u{1112}', '\u{1161}', '\u{11AB}', '\u{1100}'] {
+            alphabet.add_symbol(Symbol::with_default_colors(c, &c.to_string()));
         }
+        let mut model = DasherModel::new();
+        model.set_alphabet(alphabet);
+        model.initialize().unwrap();
+
+        // Follow ᄒ, ᅡ, ᆫ down the tree
+        let mut node = model.get_root_node().unwrap();
+        for c in ['\u{1112}', '\u{1161}', '\u{11AB}'] {
+            model.expand_node(&node);
+            let child = node.borrow().children().iter()
+                .find(|child| child.borrow().symbol() == Some(c))
+                .cloned()
+                .unwrap();
+            node = child;
+        }
+        model.output_to(&node);
+        assert_eq!(model.output_text(), "한");
+
+        // Reversing out of ᆫ takes it off the syllable again
+        let parent = node.borrow().parent().and_then(|parent| parent.upgrade()).unwrap();
+        model.output_to_crosshair_node(&parent);
+        assert_eq!(model.output_text(), "하");
+
+        // So does backspace, one jamo at a time
+        assert!(model.backspace());
+        assert_eq!(model.output_text(), "\u{1112}");
+        assert!(model.backspace());
+        assert_eq!(model.output_text(), "");
+
+        model.set_hangul_composition(false);
+        model.append_to_output('\u{1112}');
+        model.output_to(&parent);
+        assert!(model.output_text().chars().all(|c| hangul::split_last(c).is_none()));
+    }
 
-        // Draw the children
-        let children = node_ref.children();
-        if !children.is_empty() {
-            // Calculate the total range
-            let total_range = node_ref.range() as f32;
+    #[test]
+    fn test_accents_compose_in_the_output() {
+        let mut alphabet = Alphabet::new("Accents");
+        for c in ['e', 'a', '\u{0301}', '\u{00B4}'] {
+            alphabet.add_symbol(Symbol::with_default_colors(c, &c.to_string()));
+        }
+        let mut model = DasherModel::new();
+        model.set_alphabet(alphabet);
+        model.initialize().unwrap();
+
+        let child_of = |model: &mut DasherModel, node: &Rc<RefCell<DasherNode>>, c: char| {
+            model.expand_node(node);
+            let child = node.borrow().children().iter()
+                .find(|child| child.borrow().symbol() == Some(c))
+                .cloned()
+                .unwrap();
+            child
+        };
 
-            // In the original Dasher, the nodes are arranged vertically
-            // We'll divide the vertical space among the children
-            let height_per_child = (y2 - y1) / children.len() as i32;
+        // A combining mark joins the letter before it
+        let root = model.get_root_node().unwrap();
+        let letter = child_of(&mut model, &root, 'e');
+        let mark = child_of(&mut model, &letter, '\u{0301}');
+        model.output_to(&mark);
+        assert_eq!(model.output_text(), "é");
+
+        // Reversing out of the mark leaves the letter
+        model.output_to_crosshair_node(&letter);
+        assert_eq!(model.output_text(), "e");
+
+        // A dead key joins the letter after it, and backspace takes the letter off
+        assert!(model.backspace());
+        assert_eq!(model.output_text(), "");
+        let root = model.get_root_node().unwrap();
+        let dead_key = child_of(&mut model, &root, '\u{00B4}');
+        let letter = child_of(&mut model, &dead_key, 'a');
+        model.output_to(&letter);
+        assert_eq!(model.output_text(), "á");
+        assert!(model.backspace());
+        assert_eq!(model.output_text(), "\u{00B4}");
+
+        model.set_diacritic_composition(false);
+        let root = model.get_root_node().unwrap();
+        let letter = child_of(&mut model, &root, 'e');
+        let mark = child_of(&mut model, &letter, '\u{0301}');
+        model.output_to(&mark);
+        assert_eq!(model.output_text(), "\u{00B4}e\u{0301}");
+    }
 
-            // Draw each child
-            for (i, child) in children.iter().enumerate() {
-                let child_ref = child.borrow();
+    #[test]
+    fn test_backspace_rewinds_into_parent() {
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        let root = model.get_root_node().unwrap();
+        let child = root.borrow().children().iter()
+            .find(|child| child.borrow().symbol().is_some())
+            .cloned()
+            .unwrap();
+        let symbol = child.borrow().symbol().unwrap();
+
+        model.output_to(&child);
+        model.make_root(&child);
+        assert_eq!(model.output_text(), symbol.to_string());
+
+        assert!(model.backspace());
+        assert_eq!(model.output_text(), "");
+        assert!(Rc::ptr_eq(&model.get_root_node().unwrap(), &root));
+        assert!(Rc::ptr_eq(&model.get_node_under_crosshair().unwrap(), &root));
+        assert!(!child.borrow().get_flag(NodeFlags::SEEN));
+        assert!(!model.backspace());
+    }
 
-                // Calculate the child's position based on its probability range
-                let child_lower = child_ref.lower_bound() as f32 / total_range;
-                let child_upper = child_ref.upper_bound() as f32 / total_range;
+    #[test]
+    fn test_improbable_symbols_are_grouped_under_residual_node() {
+        let mut model = DasherModel::new();
+        model.set_min_child_probability(DasherModel::NORMALIZATION / 8);
+        model.initialize().unwrap();
+
+        let root = model.get_root_node().unwrap();
+        let alphabet_size = model.alphabet().unwrap().size();
+        let children = root.borrow().children().clone();
+        assert!(children.len() < alphabet_size);
+        assert_eq!(children.last().unwrap().borrow().upper_bound(), DasherModel::NORMALIZATION);
+
+        let residual = children.iter().find(|child| child.borrow().is_residual()).unwrap().clone();
+        let grouped = residual.borrow().residual_symbols().to_vec();
+        assert_eq!(children.len() - 1 + grouped.len(), alphabet_size);
+
+        // Expanding the residual node creates the grouped symbols, without grouping again
+        model.expand_node(&residual);
+        let symbols: Vec<char> = residual.borrow().children().iter()
+            .filter_map(|child| child.borrow().symbol())
+            .collect();
+        assert_eq!(symbols, grouped);
+        assert_eq!(residual.borrow().children()[0].borrow().offset(), root.borrow().offset() + 1);
+    }
 
-                // Map the probability range to screen coordinates
-                let child_x1 = x1 + ((x2 - x1) as f32 * child_lower) as i32;
-                let child_x2 = x1 + ((x2 - x1) as f32 * child_upper) as i32;
+    #[test]
+    fn test_fixed_probabilities_pin_symbol_sizes() {
+        // Pinned shares are kept, and the rest share what is left in proportion
+        let probs = DasherModel::apply_fixed_probabilities(vec![0.7, 0.2, 0.1], &[None, None, Some(0.5)]);
+        assert!((probs[2] - 0.5).abs() < 1e-9);
+        assert!((probs[0] - 0.7 * 0.5 / 0.9).abs() < 1e-9);
+        // A symbol already above its share keeps its prediction
+        let probs = DasherModel::apply_fixed_probabilities(vec![0.7, 0.3], &[Some(0.5), None]);
+        assert_eq!(probs, [0.7, 0.3]);
+
+        let mut alphabet = Alphabet::english();
+        alphabet.set_fixed_probability('.', 0.25);
+        let mut model = DasherModel::new();
+        model.set_alphabet(alphabet);
+        model.initialize().unwrap();
+        let root = model.get_root_node().unwrap();
+        model.expand_node(&root);
+
+        let children: Vec<_> = root.borrow().children().iter()
+            .filter(|child| child.borrow().symbol().is_some())
+            .map(|child| (child.borrow().symbol(), child.borrow().range()))
+            .collect();
+        let total: u32 = children.iter().map(|(_, range)| range).sum();
+        let stop = children.iter().find(|(symbol, _)| *symbol == Some('.')).unwrap().1;
+        assert!((stop as f64 / total as f64 - 0.25).abs() < 0.01);
+    }
 
-                // Calculate the vertical position
-                let child_y1 = y1 + (i as i32 * height_per_child);
-                let child_y2 = child_y1 + height_per_child;
+    #[test]
+    fn test_toggle_groups_are_offered_behind_toggle_nodes() {
+        let mut alphabet = Alphabet::english();
+        alphabet.add_digit_group();
+        alphabet.add_symbol_group();
+        let mut model = DasherModel::new();
+        model.set_alphabet(alphabet);
+        model.initialize().unwrap();
+
+        let root = model.get_root_node().unwrap();
+        let children = root.borrow().children().clone();
+        let labels: Vec<_> = children.iter().filter_map(|child| child.borrow().label().cloned()).collect();
+        assert!(labels.iter().any(|label| label == Alphabet::DIGITS_LABEL));
+        assert!(labels.iter().any(|label| label == Alphabet::SYMBOLS_LABEL));
+        assert!(!children.iter().any(|child| child.borrow().symbol() == Some('7')));
+
+        // Expanding a toggle node offers its symbols, without toggle nodes of its own
+        let digits = children.iter()
+            .find(|child| child.borrow().label().is_some_and(|label| label == Alphabet::DIGITS_LABEL))
+            .unwrap()
+            .clone();
+        model.expand_node(&digits);
+        let symbols: Vec<_> = digits.borrow().children().iter().filter_map(|child| child.borrow().symbol()).collect();
+        assert_eq!(symbols, ('0'..='9').collect::<Vec<_>>());
+
+        // With toggle groups off, the symbols are offered alongside the others
+        model.set_toggle_groups(false);
+        model.rebuild_tree();
+        let root = model.get_root_node().unwrap();
+        assert_eq!(root.borrow().children().len(), model.alphabet().unwrap().size());
+    }
 
-                // Recursively render the child
-                self.render_node(view, child, child_x1, child_y1, child_x2, child_y2);
-            }
+    #[test]
+    fn test_next_words_are_offered_after_a_space() {
+        let mut model = DasherModel::new();
+        model.set_next_word_prediction(true);
+        model.initialize().unwrap();
+
+        // The training text has "the quick" and "the lazy"
+        model.set_output_text("over the ");
+        model.rebuild_tree();
+        let root = model.get_root_node().unwrap();
+        let children = root.borrow().children().clone();
+        let labels: Vec<String> = children.iter().take(2)
+            .map(|child| child.borrow().label().unwrap().to_string())
+            .collect();
+        assert_eq!(labels, vec!["lazy", "quick"]);
+        assert!(children[2].borrow().symbol().is_some());
+
+        // The word node writes nothing itself; its descendants spell the word
+        let mut node = children[1].clone();
+        assert!(node.borrow().is_predicted_word());
+        assert_eq!(node.borrow().symbol(), None);
+        let mut spelled = String::new();
+        for _ in 0.."quick".len() {
+            model.expand_node(&node);
+            let only_child = {
+                let node_ref = node.borrow();
+                assert_eq!(node_ref.children().len(), 1);
+                node_ref.children()[0].clone()
+            };
+            spelled.extend(only_child.borrow().symbol());
+            node = only_child;
         }
+        assert_eq!(spelled, "quick");
+
+        // After the word, all the letters are offered again
+        model.expand_node(&node);
+        assert!(node.borrow().children().len() > 1);
+
+        // No words are offered in the middle of a word
+        model.set_output_text("over th");
+        model.rebuild_tree();
+        let root = model.get_root_node().unwrap();
+        assert!(root.borrow().children().iter().all(|child| child.borrow().symbol().is_some()));
+    }
+
+    #[test]
+    fn test_uniform_keeps_rare_symbols_reachable() {
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        let smallest = |model: &mut DasherModel| {
+            model.rebuild_tree();
+            let root = model.get_root_node().unwrap();
+            let children = root.borrow().children().clone();
+            let symbols: Vec<_> = children.iter().filter(|child| child.borrow().symbol().is_some()).cloned().collect();
+            let sizes: Vec<u32> = symbols.iter().map(|child| child.borrow().range()).collect();
+            (symbols.len() as u32, *sizes.iter().min().unwrap())
+        };
+
+        // Each symbol keeps at least its share of the uniform part
+        model.set_uniform(0);
+        let (_, without) = smallest(&mut model);
+        model.set_uniform(DasherModel::DEFAULT_UNIFORM);
+        let (count, with) = smallest(&mut model);
+        assert!(with > without);
+        assert!(with >= DasherModel::NORMALIZATION / count * DasherModel::DEFAULT_UNIFORM / 1000);
+
+        // All of the probability spread evenly gives equal nodes
+        model.set_uniform(1000);
+        let (count, smallest_size) = smallest(&mut model);
+        assert!(smallest_size + 1 >= DasherModel::NORMALIZATION / count);
+    }
+
+    #[test]
+    fn test_control_nodes_share_the_interval() {
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        model.set_output_text("ab");
+        let controls = |model: &mut DasherModel| {
+            model.rebuild_tree();
+            let root = model.get_root_node().unwrap();
+            let children = root.borrow().children().clone();
+            children.into_iter().filter(|child| child.borrow().get_flag(NodeFlags::CONTROL)).collect::<Vec<_>>()
+        };
+        assert!(controls(&mut model).is_empty());
+
+        // The control nodes come after the symbols and share their mass evenly
+        model.set_control_mass(120);
+        let nodes = controls(&mut model);
+        let actions: Vec<_> = nodes.iter().map(|node| node.borrow().action().unwrap().to_string()).collect();
+        assert_eq!(actions, [
+            "backspace", "space", "accept", "clear", "undo", "redo",
+            "cursor-left", "cursor-right", "word-left", "word-right", "cursor-start", "cursor-end",
+        ]);
+        assert_eq!(nodes.last().unwrap().borrow().upper_bound(), DasherModel::NORMALIZATION);
+        // Each child is rounded by up to a unit, and the last takes what is left
+        let mass: u32 = nodes.iter().map(|node| node.borrow().range()).sum();
+        let child_count = model.get_root_node().unwrap().borrow().children().len() as u32;
+        assert!(mass.abs_diff(DasherModel::NORMALIZATION * 120 / 1000) <= 2 * child_count);
+
+        // Entering a control node runs its action and rebuilds the tree around the result
+        model.output_to(&nodes[0]);
+        assert_eq!(model.output_text(), "a");
+        assert_eq!(model.root_bounds(), (0, DasherModel::MAX_Y));
+        assert!(!controls(&mut model).is_empty());
     }
 
-    /// Render the Dasher interface with a more realistic appearance
-    fn render_dasher_interface<S: DasherScreen + ?Sized>(&self, view: &mut S, width: i32, height: i32) {
-        eprintln!("IMPORTANT: render_dasher_interface called with width={}, height={}", width, height);
+    #[test]
+    fn test_learning_modes() {
+        let write = |mode: LearningMode, text: &str| {
+            let mut model = DasherModel::new();
+            model.initialize().unwrap();
+            model.set_learning_mode(mode);
+            text.chars().for_each(|c| model.append_to_output(c));
+            model
+        };
+        let count = |model: &mut DasherModel| model.personal_lexicon_mut().unwrap().count("zork");
+
+        assert_eq!(count(&mut write(LearningMode::WhileWriting, "zork ")), 1);
+        assert_eq!(count(&mut write(LearningMode::Off, "zork zork. ")), 0);
+
+        // Sentences are learned once ended or committed
+        let mut model = write(LearningMode::AtSentenceEnd, "zork ");
+        assert_eq!(count(&mut model), 0);
+        model.append_to_output('.');
+        assert_eq!(count(&mut model), 1);
+        "zork zork ".chars().for_each(|c| model.append_to_output(c));
+        assert_eq!(count(&mut model), 1);
+        model.commit_sentence();
+        assert_eq!(count(&mut model), 3);
+
+        // Erased text is never learned
+        "zork zo".chars().for_each(|c| model.append_to_output(c));
+        model.backspace();
+        model.backspace();
+        model.commit_sentence();
+        assert_eq!(count(&mut model), 4);
+    }
 
-        // Define colors for different node types - using the original Dasher color scheme
-        let colors = [
-            Color::from_tuple((180, 225, 180, 255)), // Light green
-            Color::from_tuple((160, 200, 240, 255)), // Light blue
-            Color::from_tuple((250, 200, 160, 255)), // Light orange
-            Color::from_tuple((230, 175, 175, 255)), // Light red
-            Color::from_tuple((190, 175, 250, 255)), // Light purple
-            Color::from_tuple((225, 225, 175, 255)), // Light yellow
-        ];
+    #[test]
+    fn test_word_separators_mark_boundaries_and_predict_words() {
+        let mut model = DasherModel::new();
+        model.set_next_word_prediction(true);
+        model.initialize().unwrap();
+
+        let boundary = |model: &DasherModel, c: char| {
+            let root = model.get_root_node().unwrap();
+            let children = root.borrow().children().clone();
+            let child = children.iter().find(|child| child.borrow().symbol() == Some(c)).unwrap().clone();
+            let is_boundary = child.borrow().is_word_boundary();
+            is_boundary
+        };
+        assert!(boundary(&model, ' '));
+        assert!(boundary(&model, '.'));
+        assert!(!boundary(&model, '"'));
+
+        // A quote ends words once it is a separator
+        model.add_word_separator('"');
+        model.remove_word_separator('.');
+        model.set_output_text("over the\"");
+        model.rebuild_tree();
+        assert!(boundary(&model, '"'));
+        assert!(!boundary(&model, '.'));
+        let root = model.get_root_node().unwrap();
+        let first = root.borrow().children()[0].clone();
+        assert!(first.borrow().is_predicted_word());
+    }
 
-        // In the traditional Dasher, the interface is divided into horizontal slices
-        // Each slice represents a character or group of characters
-        // The letters are arranged vertically along the right side
+    #[test]
+    fn test_smart_spacing_and_punctuation() {
+        let mut model = DasherModel::new();
+        model.set_next_word_prediction(true);
+        model.set_auto_space(true);
+        model.set_smart_punctuation(true);
+        model.initialize().unwrap();
+
+        // Predicted words are followed by a space
+        model.set_output_text("over the ");
+        model.rebuild_tree();
+        let root = model.get_root_node().unwrap();
+        let mut node = root.borrow().children()[0].clone();
+        while !node.borrow().predicted_text().is_empty() {
+            model.expand_node(&node);
+            let only_child = node.borrow().children()[0].clone();
+            node = only_child;
+        }
+        model.output_to(&node);
+        assert_eq!(model.output_text(), "over the lazy ");
+
+        // Punctuation after the space moves before it, and moves back when reversed
+        model.expand_node(&node);
+        let full_stop = node.borrow().children().iter()
+            .find(|child| child.borrow().symbol() == Some('.'))
+            .unwrap()
+            .clone();
+        model.output_to(&full_stop);
+        assert_eq!(model.output_text(), "over the lazy. ");
+        model.output_to_crosshair_node(&node);
+        assert_eq!(model.output_text(), "over the lazy ");
+
+        // Undo reverses the move as one edit
+        model.output_to(&full_stop);
+        assert!(model.undo());
+        assert_eq!(model.output_text(), "over the lazy ");
+    }
 
-        // Number of horizontal slices
-        let num_slices = 26; // One for each letter of the alphabet
+    #[test]
+    fn test_steering_writes_and_reversing_erases() {
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        let policy = ExpansionPolicy::default();
+
+        // Point right of the crosshair to zoom in and write a few symbols
+        for _ in 0..1000 {
+            model.apply_input_coordinates((DasherModel::X_LIMIT, DasherModel::MAX_Y / 3), 5);
+            model.next_scheduled_step();
+            model.expand_visible_nodes(&policy);
+            if model.output_text().chars().count() >= 3 {
+                break;
+            }
+        }
+        let written = model.output_text().chars().count();
+        assert_eq!(written, 3);
+        assert_eq!(model.cursor_position(), written);
+
+        // Each expansion is counted
+        let (expanded, collapsed) = model.node_counters();
+        assert!(expanded > 0);
+        let more = model.expand_visible_nodes(&policy) as u64;
+        assert_eq!(model.node_counters().0, expanded + more);
+        assert!(model.node_counters().1 >= collapsed);
+
+        // Point left of the crosshair to zoom out, erasing what was written
+        for _ in 0..1000 {
+            model.apply_input_coordinates((DasherModel::MAX_X, DasherModel::ORIGIN_Y), 5);
+            model.next_scheduled_step();
+        }
+        assert_eq!(model.output_text(), "");
+        assert!(model.root_min <= 0 && model.root_max >= DasherModel::MAX_Y);
+    }
 
-        // Height of each slice
-        let slice_height = height / num_slices;
+    #[test]
+    fn test_reset_returns_to_a_typable_initial_state() {
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        let policy = ExpansionPolicy::default();
+        let write = |model: &mut DasherModel| {
+            for _ in 0..1000 {
+                model.apply_input_coordinates((DasherModel::X_LIMIT, DasherModel::MAX_Y / 3), 5);
+                model.next_scheduled_step();
+                model.expand_visible_nodes(&policy);
+                if model.output_text().chars().count() >= 3 {
+                    break;
+                }
+            }
+            model.output_text().to_string()
+        };
+        assert_eq!(write(&mut model).chars().count(), 3);
+        model.apply_input_coordinates((DasherModel::X_LIMIT, DasherModel::ORIGIN_Y), 5);
+        assert!(!model.goto_queue.is_empty());
+
+        model.reset();
+        assert_eq!(model.output_text(), "");
+        assert_eq!(model.cursor_position(), 0);
+        assert_eq!(model.total_nats(), 0.0);
+        assert!(model.goto_queue.is_empty() && model.old_roots.is_empty() && model.last_output.is_none());
+        assert_eq!((model.root_min, model.root_max, model.display_offset), (0, DasherModel::MAX_Y, 0));
+        let root = model.get_root_node().unwrap();
+        assert_eq!(root.borrow().offset(), -1);
+        assert!(root.borrow().parent().is_none() && !root.borrow().children().is_empty());
+
+        // Steering writes text again, from the start
+        assert!(write(&mut model).chars().count() >= 3);
+    }
 
-        eprintln!("Drawing {} horizontal slices, each with height {}", num_slices, slice_height);
+    #[test]
+    fn test_root_jumps_ease_by_frame_time() {
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+
+        // A jump leaves the root drawn where it was
+        model.jump_root(DasherModel::MAX_Y, 2 * DasherModel::MAX_Y);
+        assert_eq!(model.root_bounds(), (DasherModel::MAX_Y, 2 * DasherModel::MAX_Y));
+        assert_eq!(model.display_bounds(), (0, DasherModel::MAX_Y));
+
+        // Many short frames ease it as far as one long one
+        let mut fast = DasherModel::new();
+        fast.initialize().unwrap();
+        fast.jump_root(DasherModel::MAX_Y, 2 * DasherModel::MAX_Y);
+        for _ in 0..13 {
+            fast.ease_display(10);
+        }
+        model.ease_display(130);
+        assert_eq!(model.display_offset(), -DasherModel::MAX_Y / 2);
+        assert!((fast.display_offset() - model.display_offset()).abs() <= 13);
 
-        // Draw the horizontal slices
-        for i in 0..num_slices {
-            let y1 = i * slice_height;
-            let y2 = (i + 1) * slice_height;
+        model.ease_display(10_000);
+        assert_eq!(model.display_bounds(), model.root_bounds());
+    }
 
-            // Use a different color for each slice
-            let color_index = (i as usize) % colors.len();
-            let color = colors[color_index];
+    #[test]
+    fn test_reversing_erases_text_written_before_the_tree() {
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        model.set_output_text("the end");
+        model.rebuild_tree();
+        assert!(model.get_root_node().unwrap().borrow().parent().is_none());
+
+        // The root's parents are rebuilt from the text as the view zooms out
+        let mut texts = Vec::new();
+        for _ in 0..3000 {
+            model.apply_input_coordinates((DasherModel::MAX_X, DasherModel::ORIGIN_Y), 5);
+            model.next_scheduled_step();
+            if texts.last() != Some(&model.output_text().to_string()) {
+                texts.push(model.output_text().to_string());
+            }
+        }
+        assert!(texts.contains(&"the e".to_string()) && texts.contains(&"t".to_string()));
+        assert_eq!(model.output_text(), "");
+        assert!(!model.reparent_root());
+    }
 
-            eprintln!("Drawing slice {} at y1={}, y2={} with color {:?}", i, y1, y2, color);
+    #[test]
+    fn test_language_models_are_kept_per_alphabet() {
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        let english_nodes = model.combined_language_model().unwrap().layer(LanguageLayer::Base).node_count();
+        model.personal_lexicon_mut().unwrap().record_word("zebra");
+
+        let mut tiny = Alphabet::new("Tiny");
+        tiny.add_symbol(Symbol::with_default_colors('x', "x"));
+        tiny.add_symbol(Symbol::with_default_colors('y', "y"));
+        assert!(model.switch_alphabet(tiny));
+        assert!(model.combined_language_model().unwrap().layer(LanguageLayer::Base).node_count() < english_nodes);
+        assert_eq!(model.personal_lexicon_mut().unwrap().count("zebra"), 1);
+        model.train_language_model(std::io::Cursor::new("xyxy"), |_| true).unwrap();
+
+        // Switching back restores the trained models without training again
+        assert!(!model.switch_alphabet(Alphabet::english()));
+        assert_eq!(model.combined_language_model().unwrap().layer(LanguageLayer::Base).node_count(), english_nodes);
+        assert_eq!(model.personal_lexicon_mut().unwrap().count("zebra"), 1);
+
+        let mut tiny = Alphabet::new("Tiny");
+        tiny.add_symbol(Symbol::with_default_colors('x', "x"));
+        assert!(!model.switch_alphabet(tiny));
+        assert!(model.combined_language_model().unwrap().layer(LanguageLayer::Base).node_count() > 1);
+    }
 
-            // Draw the slice
-            view.draw_rectangle(0, y1, width, y2, color, crate::view::color_palette::BLACK, 1);
+    #[test]
+    fn test_probabilities_after_explicit_context() {
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        model.train_language_model(std::io::Cursor::new("qzqzqzqzqzqzqzqzqzqz"), |_| true).unwrap();
+        let current = model.get_probabilities().unwrap();
+        assert_eq!(model.probabilities_after(""), Some(current.clone()));
 
-            // Draw a letter in the slice
-            let letter = (b'a' + i as u8) as char;
-            let label = view.make_label(&letter.to_string(), 0);
-            let font_size = 24;
-            let (text_width, text_height) = view.text_size(&*label, font_size);
+        let after_q = model.probabilities_after("q").unwrap();
+        let most_probable = after_q.iter().max_by(|a, b| a.1.total_cmp(&b.1)).unwrap();
+        assert_eq!(most_probable.0, 'z');
 
-            // Right-align the text in the slice
-            let text_x = width - text_width - 10; // 10px padding from right edge
-            let text_y = y1 + (slice_height - text_height) / 2;
+        // The model's own context is left alone
+        assert_eq!(model.get_probabilities().unwrap(), current);
+    }
 
-            eprintln!("Drawing letter '{}' at x={}, y={}", letter, text_x, text_y);
+    #[test]
+    fn test_ranked_probabilities() {
+        /// Language model making each symbol more probable than the one
+        /// before, or after the second symbol less probable
+        struct Rising {
+            symbols: usize,
+            after_second: ContextTable<bool>,
+        }
 
-            view.draw_string(&*label, text_x, text_y, font_size, crate::view::color_palette::BLACK);
+        impl LanguageModel for Rising {
+            fn as_any(&mut self) -> &mut dyn std::any::Any {
+                self
+            }
+            fn set_symbols(&mut self, symbols: &[char]) {
+                self.symbols = symbols.len();
+            }
+            fn create_empty_context(&mut self) -> Context {
+                self.after_second.insert(false)
+            }
+            fn clone_context(&mut self, context: Context) -> Context {
+                let after_second = self.after_second.get(context).copied().unwrap_or(false);
+                self.after_second.insert(after_second)
+            }
+            fn release_context(&mut self, context: Context) {
+                self.after_second.remove(context);
+            }
+            fn get_probs(&self, context: Context, norm: u32, probs: &mut Vec<u32>) {
+                let total = (self.symbols * (self.symbols + 1) / 2) as u32;
+                probs.clear();
+                probs.extend((1..=self.symbols as u32).map(|rank| rank * norm / total));
+                if self.after_second.get(context) == Some(&true) {
+                    probs.reverse();
+                }
+            }
+            fn enter_symbol(&mut self, context: Context, symbol: usize) {
+                if let Some(after_second) = self.after_second.get_mut(context) {
+                    *after_second = symbol == 1;
+                }
+            }
+            fn learn_symbol(&mut self, context: Context, symbol: usize) {
+                self.enter_symbol(context, symbol);
+            }
         }
 
-        // Draw horizontal lines between slices
-        for i in 0..=num_slices {
-            let y = i * slice_height;
-            view.draw_line(0, y, width, y, crate::view::color_palette::BLACK, 1);
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        model.set_language_model(Box::new(Rising { symbols: 0, after_second: ContextTable::new() }));
+        let symbols = model.symbol_characters.clone();
+
+        let all = model.ranked_probabilities(0, 0.0, None).unwrap();
+        assert_eq!(all.len(), symbols.len());
+        assert_eq!(all[0].0, *symbols.last().unwrap());
+        assert!(all.windows(2).all(|pair| pair[0].1 > pair[1].1));
+        assert_eq!(model.ranked_probabilities(3, 0.0, None).unwrap(), all[..3]);
+        let likely = model.ranked_probabilities(0, all[4].1, None).unwrap();
+        assert_eq!(likely, all[..5]);
+        assert!(model.ranked_probabilities(2, 1.0, None).unwrap().is_empty());
+
+        // With a text, the symbols follow it rather than the text before the cursor
+        let second = symbols[1].to_string();
+        let after_second = model.ranked_probabilities(1, 0.0, Some(&second)).unwrap();
+        assert_eq!(after_second, [(symbols[0], all[0].1)]);
+    }
+
+    #[test]
+    fn test_conversion_nodes_replace_the_composition() {
+        use crate::alphabet::{ConversionRule, ConversionTable};
+
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        let rule = ConversionRule { input: "ka".to_string(), output: "\u{304b}".to_string(), context: String::new(), trainable: true };
+        model.set_conversion_provider(Some(Box::new(ConversionTable::from(rule))));
+        model.rebuild_tree();
+
+        let child_with = |node: &Rc<RefCell<DasherNode>>, c: char| {
+            node.borrow().children().iter().find(|child| child.borrow().symbol() == Some(c)).unwrap().clone()
+        };
+        let conversion_of = |node: &Rc<RefCell<DasherNode>>| {
+            node.borrow().children().iter().find(|child| child.borrow().conversion().is_some()).cloned()
+        };
+        let root = model.get_root_node().unwrap();
+        let k = child_with(&root, 'k');
+        model.expand_node(&k);
+        let a = child_with(&k, 'a');
+        model.expand_node(&a);
+
+        // Only a composition the provider converts is offered a conversion
+        assert!(conversion_of(&k).is_none());
+        let conversion = conversion_of(&a).unwrap();
+        assert_eq!(conversion.borrow().conversion(), Some(("ka", "\u{304b}")));
+        assert_eq!(conversion.borrow().label().map(String::as_str), Some("\u{304b}"));
+
+        // Choosing it replaces the composition, as one edit
+        model.output_to(&conversion);
+        assert_eq!(model.output_text(), "\u{304b}");
+        assert!(model.undo());
+        assert_eq!(model.output_text(), "ka");
+
+        model.set_conversion_provider(None);
+        model.rebuild_tree();
+        assert!(conversion_of(&model.get_root_node().unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_nodes_keep_their_language_model_context() {
+        /// Language model predicting every symbol alike, sharing its contexts
+        /// and the number of contexts created empty
+        struct Counting(Rc<RefCell<(ContextTable<()>, usize)>>);
+
+        impl LanguageModel for Counting {
+            fn as_any(&mut self) -> &mut dyn std::any::Any {
+                self
+            }
+            fn set_symbols(&mut self, _symbols: &[char]) {}
+            fn create_empty_context(&mut self) -> Context {
+                let mut state = self.0.borrow_mut();
+                state.1 += 1;
+                state.0.insert(())
+            }
+            fn clone_context(&mut self, _context: Context) -> Context {
+                self.0.borrow_mut().0.insert(())
+            }
+            fn release_context(&mut self, context: Context) {
+                assert!(self.0.borrow_mut().0.remove(context).is_some(), "context released twice");
+            }
+            fn get_probs(&self, _context: Context, _norm: u32, probs: &mut Vec<u32>) {
+                probs.iter_mut().for_each(|prob| *prob = 1);
+            }
+            fn enter_symbol(&mut self, _context: Context, _symbol: usize) {}
+            fn learn_symbol(&mut self, _context: Context, _symbol: usize) {}
         }
 
-        eprintln!("render_dasher_interface completed");
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        let state = Rc::new(RefCell::new((ContextTable::new(), 0)));
+        model.set_language_model(Box::new(Counting(state.clone())));
+        model.rebuild_tree();
+        let alive = || state.borrow().0.len();
+        let created = state.borrow().1;
+
+        // Only the root's context comes from the text, its descendants clone their parent's
+        let root = model.get_root_node().unwrap();
+        let child_with = |node: &Rc<RefCell<DasherNode>>, c: char| {
+            node.borrow().children().iter().find(|child| child.borrow().symbol() == Some(c)).unwrap().clone()
+        };
+        let child = child_with(&root, 'a');
+        model.expand_node(&child);
+        let grandchild = child_with(&child, 'b');
+        model.expand_node(&grandchild);
+        assert_eq!(state.borrow().1, created);
+        assert_eq!(alive(), 4);
+
+        // Contexts are released with their nodes
+        child.borrow_mut().delete_children();
+        drop(grandchild);
+        model.expand_node(&child);
+        assert_eq!(alive(), 3);
+        drop((root, child));
+        model.rebuild_tree();
+        assert_eq!(alive(), 2);
+        assert_eq!(state.borrow().1, created + 1);
+
+        // The contexts of the old model are released before it is replaced
+        model.set_language_model(Box::new(CombinedLanguageModel::new(PPMOrder::Two)));
+        assert_eq!(alive(), 0);
     }
 
-    /// Draw nested boxes to simulate the Dasher zooming effect
-    #[allow(dead_code)]
-    fn draw_nested_boxes<S: DasherScreen + ?Sized>(
-        &self,
-        view: &mut S,
-        x1: i32,
-        y1: i32,
-        x2: i32,
-        y2: i32,
-        depth: i32,
-        color: (u8, u8, u8, u8)
-    ) {
-        if depth <= 0 {
-            return;
+    #[test]
+    fn test_nodes_expanded_together_are_predicted_in_one_batch() {
+        /// Predictor giving every symbol the same probability, recording the size of each batch
+        struct Uniform(Rc<RefCell<Vec<usize>>>);
+
+        impl NeuralPredictor for Uniform {
+            fn context_length(&self) -> usize {
+                4
+            }
+
+            fn predict(&self, histories: &[&str], symbols: &[char]) -> Result<Vec<Vec<f32>>> {
+                self.0.borrow_mut().push(histories.len());
+                Ok(vec![vec![1.0; symbols.len()]; histories.len()])
+            }
         }
 
-        // Calculate the size of the inner box
-        let inner_width = (x2 - x1) / 3;
-        let inner_height = (y2 - y1) / 3;
-
-        // Calculate the position of the inner box
-        let inner_x1 = x1 + inner_width;
-        let inner_y1 = y1 + inner_height;
-        let inner_x2 = x2 - inner_width;
-        let inner_y2 = y2 - inner_height;
-
-        // Draw the inner box
-        let darker_color = (
-            (color.0 as f32 * 0.8) as u8,
-            (color.1 as f32 * 0.8) as u8,
-            (color.2 as f32 * 0.8) as u8,
-            color.3
-        );
-
-        view.draw_rectangle(
-            inner_x1,
-            inner_y1,
-            inner_x2,
-            inner_y2,
-            Color::from_tuple(darker_color),
-            crate::view::color_palette::BLACK,
-            1
-        );
-
-        // Recursively draw nested boxes
-        self.draw_nested_boxes(
-            view,
-            inner_x1,
-            inner_y1,
-            inner_x2,
-            inner_y2,
-            depth - 1,
-            darker_color
-        );
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        let batches = Rc::new(RefCell::new(Vec::new()));
+        let fallback = model.take_language_model().unwrap();
+        let mut neural = NeuralLanguageModel::new(Box::new(Uniform(batches.clone())), fallback);
+        neural.set_latency_budget(std::time::Duration::from_secs(60));
+        model.set_language_model(Box::new(neural));
+        model.rebuild_tree();
+        batches.borrow_mut().clear();
+
+        // Contexts already predicted are left out of the batch
+        assert_eq!(model.expand_visible_nodes(&ExpansionPolicy::new(4, 0, 0)), 4);
+        assert_eq!(batches.borrow().len(), 1);
+        assert!(batches.borrow()[0] > 1);
+    }
+
+    #[test]
+    fn test_profile_control_node_switches_language() {
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        let english_nodes = model.combined_language_model().unwrap().layer(LanguageLayer::Base).node_count();
+
+        let mut tiny = Alphabet::new("Tiny");
+        tiny.add_symbol(Symbol::with_default_colors('x', "x"));
+        tiny.add_symbol(Symbol::with_default_colors('y', "y"));
+        let language_model = Box::new(CombinedLanguageModel::new(PPMOrder::Two));
+        model.add_profile("Tiny", LanguageProfile::new(tiny, language_model));
+        assert_eq!(model.profile_names(), vec!["English", "Tiny"]);
+        assert_eq!(model.profile_name(), "English");
+
+        // Entering the profile's control node switches to it
+        assert!(model.execute_action(&LanguageProfile::action_name("Tiny")));
+        assert_eq!(model.profile_name(), "Tiny");
+        assert_eq!(model.alphabet().unwrap().size(), 2);
+        assert_eq!(model.combined_language_model().unwrap().layer(LanguageLayer::Base).node_count(), 0);
+        let root = model.get_root_node().unwrap();
+        assert!(root.borrow().children().iter().any(|child| child.borrow().symbol() == Some('x')));
+
+        assert!(model.execute_action(&LanguageProfile::action_name("English")));
+        assert_eq!(model.combined_language_model().unwrap().layer(LanguageLayer::Base).node_count(), english_nodes);
+        assert!(!model.switch_profile("Unknown"));
     }
 }
@@ -6,6 +6,8 @@
 use std::cell::RefCell;
 use std::rc::{Rc, Weak};
 
+use super::language::Context;
+
 /// Node flags representing the state of the node
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct NodeFlags(pub u32);
@@ -41,6 +43,9 @@ impl NodeFlags {
     /// Node is a super node (fills the screen)
     pub const SUPER: u32 = 0x0040;
 
+    /// Node wrote punctuation before the space preceding it (smart punctuation)
+    pub const SPACE_MOVED: u32 = 0x0080;
+
     /// Check if a flag is set
     pub fn is_set(&self, flag: u32) -> bool {
         (self.0 & flag) != 0
@@ -56,6 +61,42 @@ impl NodeFlags {
     }
 }
 
+/// Language model context following a node
+///
+/// The context is handed back when the node is dropped, for the model to
+/// release it (the node cannot reach the language model itself).
+pub struct NodeContext {
+    /// Handle of the context
+    context: Context,
+    /// Generation of contexts the context belongs to
+    generation: u64,
+    /// Contexts of dropped nodes, waiting to be released
+    released: Rc<RefCell<Vec<Context>>>,
+}
+
+impl NodeContext {
+    /// Wrap `context`, to be pushed onto `released` when dropped
+    pub fn new(context: Context, generation: u64, released: Rc<RefCell<Vec<Context>>>) -> Self {
+        Self { context, generation, released }
+    }
+
+    /// Get the handle of the context
+    pub fn context(&self) -> Context {
+        self.context
+    }
+
+    /// Check whether the context belongs to `generation` of the contexts released to `released`
+    pub fn is_current(&self, generation: u64, released: &Rc<RefCell<Vec<Context>>>) -> bool {
+        self.generation == generation && Rc::ptr_eq(&self.released, released)
+    }
+}
+
+impl Drop for NodeContext {
+    fn drop(&mut self) {
+        self.released.borrow_mut().push(self.context);
+    }
+}
+
 /// A node in the Dasher tree
 pub struct DasherNode {
     /// Lower bound probability relative to parent
@@ -93,6 +134,21 @@ pub struct DasherNode {
 
     /// Speed multiplier for this node (affects how quickly the user can navigate through it)
     speed_mul: f64,
+
+    /// Name of the action performed when this node is output
+    action: Option<String>,
+
+    /// Improbable symbols grouped under this node, created when it is expanded
+    residual_symbols: Vec<char>,
+
+    /// Rest of a predicted word, written one symbol per generation of children
+    predicted_text: String,
+
+    /// Composition (input, output) this node converts when output
+    conversion: Option<(String, String)>,
+
+    /// Language model context following this node, once it has been needed
+    language_context: Option<NodeContext>,
 }
 
 impl DasherNode {
@@ -133,6 +189,11 @@ impl DasherNode {
             foreground_color: (0, 0, 0),
             background_color: (255, 255, 255),
             speed_mul: 1.0,
+            action: None,
+            residual_symbols: Vec::new(),
+            predicted_text: String::new(),
+            conversion: None,
+            language_context: None,
         }
     }
 
@@ -146,6 +207,54 @@ impl DasherNode {
         self.speed_mul = speed_mul;
     }
 
+    /// Get the name of the action performed when this node is output
+    pub fn action(&self) -> Option<&str> {
+        self.action.as_deref()
+    }
+
+    /// Set the name of the action performed when this node is output
+    pub fn set_action(&mut self, action: Option<String>) {
+        self.action = action;
+    }
+
+    /// Get the improbable symbols grouped under this node
+    pub fn residual_symbols(&self) -> &[char] {
+        &self.residual_symbols
+    }
+
+    /// Group improbable symbols under this node, to be created on expansion
+    pub fn set_residual_symbols(&mut self, symbols: Vec<char>) {
+        self.residual_symbols = symbols;
+    }
+
+    /// Check if this node groups improbable symbols
+    pub fn is_residual(&self) -> bool {
+        !self.residual_symbols.is_empty()
+    }
+
+    /// Get the rest of the predicted word this node is part of
+    pub fn predicted_text(&self) -> &str {
+        &self.predicted_text
+    }
+
+    /// Set the rest of a predicted word, whose symbols become this node's
+    /// only child, grandchild and so on when expanded
+    pub fn set_predicted_text(&mut self, text: String) {
+        self.set_predicted_word(!text.is_empty());
+        self.predicted_text = text;
+    }
+
+    /// Get the composition (input, output) this node converts, if it is a conversion node
+    pub fn conversion(&self) -> Option<(&str, &str)> {
+        self.conversion.as_ref().map(|(input, output)| (input.as_str(), output.as_str()))
+    }
+
+    /// Make this node replace the composition `input` before it with `output` when output
+    pub fn set_conversion(&mut self, input: String, output: String) {
+        self.set_flag(NodeFlags::CONVERTED, true);
+        self.conversion = Some((input, output));
+    }
+
     /// Set the bounds of this node
     pub fn set_bounds(&mut self, lower_bound: u32, upper_bound: u32) {
         self.lower_bound = lower_bound;
@@ -183,6 +292,16 @@ impl DasherNode {
         self.background_color
     }
 
+    /// Get the language model context following this node
+    pub fn language_context(&self) -> Option<&NodeContext> {
+        self.language_context.as_ref()
+    }
+
+    /// Set the language model context following this node, dropping any it had
+    pub fn set_language_context(&mut self, context: Option<NodeContext>) {
+        self.language_context = context;
+    }
+
     /// Set the parent of this node
     pub fn set_parent(&mut self, parent: Weak<RefCell<DasherNode>>) {
         self.parent = Some(parent);
@@ -332,6 +451,20 @@ impl DasherNode {
         self.set_flag(NodeFlags::ALL_CHILDREN, false);
     }
 
+    /// Put `child` in the place of the child `old` among the children
+    ///
+    /// The new child's parent and bounds are left for the caller to set.
+    /// Returns `false` if `old` is not a child of this node.
+    pub fn replace_child(&mut self, old: &Rc<RefCell<DasherNode>>, child: Rc<RefCell<DasherNode>>) -> bool {
+        match self.children.iter_mut().find(|node| Rc::ptr_eq(node, old)) {
+            Some(slot) => {
+                *slot = child;
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Find the most probable child
     pub fn most_probable_child(&self) -> u32 {
         let mut max = 0;
@@ -380,6 +513,11 @@ pub fn do_action(&mut self) {
             foreground_color: self.foreground_color,
             background_color: self.background_color,
             speed_mul: self.speed_mul,
+            action: self.action.clone(),
+            residual_symbols: self.residual_symbols.clone(),
+            predicted_text: self.predicted_text.clone(),
+            conversion: self.conversion.clone(),
+            language_context: None,
         }
     }
 }
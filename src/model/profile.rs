@@ -0,0 +1,28 @@
+//! # Profile Module
+//!
+//! A language profile pairs an alphabet with the language model used with
+//! it, including the model's dictionary. The model keeps the profiles not in
+//! use, so that switching language does not lose what each has learned.
+
+use crate::alphabet::Alphabet;
+use super::language::LanguageModel;
+
+/// An alphabet and the language model used with it
+pub struct LanguageProfile {
+    /// The profile's alphabet
+    pub alphabet: Alphabet,
+    /// The profile's language model
+    pub language_model: Box<dyn LanguageModel>,
+}
+
+impl LanguageProfile {
+    /// Create a profile from an alphabet and a language model
+    pub fn new(alphabet: Alphabet, language_model: Box<dyn LanguageModel>) -> Self {
+        Self { alphabet, language_model }
+    }
+
+    /// Get the name of the action that switches to the profile called `name`
+    pub fn action_name(name: &str) -> String {
+        format!("profile:{}", name)
+    }
+}
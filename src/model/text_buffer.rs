@@ -0,0 +1,214 @@
+//! # Text Buffer Module
+//!
+//! This module contains the editable output buffer. Text is inserted and
+//! deleted at a cursor, which is measured in characters (not bytes).
+
+/// A movement of the cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorMovement {
+    /// One character left
+    CharLeft,
+    /// One character right
+    CharRight,
+    /// To the start of the word before the cursor
+    WordLeft,
+    /// To the end of the word after the cursor
+    WordRight,
+    /// To the start of the text
+    Start,
+    /// To the end of the text
+    End,
+}
+
+/// Editable text with a cursor position
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextBuffer {
+    /// The text
+    text: String,
+
+    /// Cursor position (in characters, from 0 to the length of the text)
+    cursor: usize,
+}
+
+impl TextBuffer {
+    /// Create an empty buffer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the text
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Get the length of the text (in characters)
+    pub fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    /// Check if the buffer is empty
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Get the cursor position (in characters)
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Move the cursor, clamping it to the text
+    pub fn set_cursor(&mut self, cursor: usize) {
+        self.cursor = cursor.min(self.len());
+    }
+
+    /// Move the cursor by a number of characters, clamping it to the text
+    pub fn move_cursor(&mut self, delta: isize) {
+        self.set_cursor(self.cursor.saturating_add_signed(delta));
+    }
+
+    /// Get where a movement would take the cursor (in characters)
+    ///
+    /// Words are separated by whitespace; moving by a word first skips any
+    /// whitespace next to the cursor.
+    pub fn movement_target(&self, movement: CursorMovement) -> usize {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut position = self.cursor;
+        match movement {
+            CursorMovement::CharLeft => position.saturating_sub(1),
+            CursorMovement::CharRight => (position + 1).min(chars.len()),
+            CursorMovement::WordLeft => {
+                while position > 0 && chars[position - 1].is_whitespace() {
+                    position -= 1;
+                }
+                while position > 0 && !chars[position - 1].is_whitespace() {
+                    position -= 1;
+                }
+                position
+            }
+            CursorMovement::WordRight => {
+                while position < chars.len() && chars[position].is_whitespace() {
+                    position += 1;
+                }
+                while position < chars.len() && !chars[position].is_whitespace() {
+                    position += 1;
+                }
+                position
+            }
+            CursorMovement::Start => 0,
+            CursorMovement::End => chars.len(),
+        }
+    }
+
+    /// Get the text before the cursor (the context for prediction)
+    pub fn text_before_cursor(&self) -> &str {
+        &self.text[..self.byte_index(self.cursor)]
+    }
+
+    /// Replace the whole text, leaving the cursor at the end
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.to_string();
+        self.cursor = self.len();
+    }
+
+    /// Remove all text
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+    }
+
+    /// Insert a character at the cursor, moving the cursor past it
+    pub fn insert_char(&mut self, c: char) {
+        let index = self.byte_index(self.cursor);
+        self.text.insert(index, c);
+        self.cursor += 1;
+    }
+
+    /// Insert a string at the cursor, moving the cursor past it
+    pub fn insert_str(&mut self, s: &str) {
+        let index = self.byte_index(self.cursor);
+        self.text.insert_str(index, s);
+        self.cursor += s.chars().count();
+    }
+
+    /// Delete up to `count` characters before the cursor, returning the deleted text
+    pub fn delete_before(&mut self, count: usize) -> String {
+        let start = self.cursor.saturating_sub(count);
+        let deleted = self.replace_range(start, self.cursor - start, "");
+        self.cursor = start;
+        deleted
+    }
+
+    /// Delete up to `count` characters after the cursor, returning the deleted text
+    pub fn delete_after(&mut self, count: usize) -> String {
+        self.replace_range(self.cursor, count, "")
+    }
+
+    /// Replace `count` characters starting at character `start` with `replacement`.
+    ///
+    /// The cursor is left just after the replacement. Returns the replaced text.
+    pub fn replace_range(&mut self, start: usize, count: usize, replacement: &str) -> String {
+        let start = start.min(self.len());
+        let end = (start + count).min(self.len());
+        let (start_byte, end_byte) = (self.byte_index(start), self.byte_index(end));
+
+        let removed = self.text[start_byte..end_byte].to_string();
+        self.text.replace_range(start_byte..end_byte, replacement);
+        self.cursor = start + replacement.chars().count();
+        removed
+    }
+
+    /// Convert a character position to a byte index into the text
+    fn byte_index(&self, position: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(position)
+            .map_or(self.text.len(), |(index, _)| index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_delete_at_cursor() {
+        let mut buffer = TextBuffer::new();
+        buffer.insert_str("héllo");
+        assert_eq!(buffer.cursor(), 5);
+
+        buffer.set_cursor(1);
+        buffer.insert_char('x');
+        assert_eq!(buffer.as_str(), "hxéllo");
+        assert_eq!(buffer.text_before_cursor(), "hx");
+
+        assert_eq!(buffer.delete_after(1), "é");
+        assert_eq!(buffer.delete_before(5), "hx");
+        assert_eq!(buffer.as_str(), "llo");
+        assert_eq!(buffer.cursor(), 0);
+
+        buffer.move_cursor(-3);
+        assert_eq!(buffer.cursor(), 0);
+        buffer.move_cursor(10);
+        assert_eq!(buffer.cursor(), 3);
+    }
+
+    #[test]
+    fn test_movement_targets() {
+        let mut buffer = TextBuffer::new();
+        buffer.set_text("one  twö three");
+        buffer.set_cursor(7);
+        assert_eq!(buffer.movement_target(CursorMovement::CharLeft), 6);
+        assert_eq!(buffer.movement_target(CursorMovement::CharRight), 8);
+        assert_eq!(buffer.movement_target(CursorMovement::WordLeft), 5);
+        assert_eq!(buffer.movement_target(CursorMovement::WordRight), 8);
+        assert_eq!(buffer.movement_target(CursorMovement::Start), 0);
+        assert_eq!(buffer.movement_target(CursorMovement::End), 14);
+
+        buffer.set_cursor(5);
+        assert_eq!(buffer.movement_target(CursorMovement::WordLeft), 0);
+        buffer.set_cursor(3);
+        assert_eq!(buffer.movement_target(CursorMovement::WordRight), 8);
+        buffer.set_cursor(0);
+        assert_eq!(buffer.movement_target(CursorMovement::CharLeft), 0);
+    }
+}
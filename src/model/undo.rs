@@ -0,0 +1,206 @@
+//! # Undo Module
+//!
+//! This module records committed text operations so they can be undone and
+//! redone. Each operation is stored as the minimal edit between the text
+//! before and after it, rather than a full copy of the text.
+
+use std::collections::VecDeque;
+
+use super::text_buffer::TextBuffer;
+
+/// What caused a text edit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditKind {
+    /// A symbol was written by navigating into its node
+    Symbol,
+    /// A named action was executed
+    Action(String),
+    /// The text was changed directly (e.g. by the host)
+    Edit,
+}
+
+/// A single edit: `removed` was replaced by `inserted` at character `offset`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    /// Character offset at which the edit starts
+    pub offset: usize,
+    /// Text removed by the edit
+    pub removed: String,
+    /// Text inserted by the edit
+    pub inserted: String,
+    /// What caused the edit
+    pub kind: EditKind,
+}
+
+impl TextEdit {
+    /// Compute the edit that turns `before` into `after`, or `None` if they are equal
+    pub fn between(before: &str, after: &str, kind: EditKind) -> Option<Self> {
+        if before == after {
+            return None;
+        }
+
+        let before: Vec<char> = before.chars().collect();
+        let after: Vec<char> = after.chars().collect();
+
+        let prefix = before.iter().zip(&after).take_while(|(a, b)| a == b).count();
+        let max_suffix = before.len().min(after.len()) - prefix;
+        let suffix = before.iter().rev().zip(after.iter().rev())
+            .take(max_suffix)
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        Some(Self {
+            offset: prefix,
+            removed: before[prefix..before.len() - suffix].iter().collect(),
+            inserted: after[prefix..after.len() - suffix].iter().collect(),
+            kind,
+        })
+    }
+
+    /// Apply the edit to a buffer, leaving the cursor after the inserted text
+    pub fn apply(&self, buffer: &mut TextBuffer) {
+        buffer.replace_range(self.offset, self.removed.chars().count(), &self.inserted);
+    }
+
+    /// Reverse the edit on a buffer, leaving the cursor after the restored text
+    pub fn revert(&self, buffer: &mut TextBuffer) {
+        buffer.replace_range(self.offset, self.inserted.chars().count(), &self.removed);
+    }
+}
+
+/// Multi-level undo/redo history
+#[derive(Debug, Clone)]
+pub struct UndoStack {
+    /// Edits that can be undone, oldest first
+    undo: VecDeque<TextEdit>,
+    /// Edits that can be redone, most recently undone last
+    redo: Vec<TextEdit>,
+    /// Maximum number of edits kept
+    max_depth: usize,
+    /// Incremented whenever the history changes
+    revision: u64,
+}
+
+impl Default for UndoStack {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MAX_DEPTH)
+    }
+}
+
+impl UndoStack {
+    /// Default maximum number of edits kept
+    pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+    /// Create an empty history keeping at most `max_depth` edits
+    pub fn new(max_depth: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            max_depth,
+            revision: 0,
+        }
+    }
+
+    /// Record a new edit, discarding anything that could be redone
+    pub fn push(&mut self, edit: TextEdit) {
+        self.revision += 1;
+        self.redo.clear();
+        self.undo.push_back(edit);
+        while self.undo.len() > self.max_depth {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Undo the most recent edit on a buffer
+    ///
+    /// Returns `false` if there was nothing to undo.
+    pub fn undo(&mut self, buffer: &mut TextBuffer) -> bool {
+        let Some(edit) = self.undo.pop_back() else {
+            return false;
+        };
+        self.revision += 1;
+        edit.revert(buffer);
+        self.redo.push(edit);
+        true
+    }
+
+    /// Redo the most recently undone edit on a buffer
+    ///
+    /// Returns `false` if there was nothing to redo.
+    pub fn redo(&mut self, buffer: &mut TextBuffer) -> bool {
+        let Some(edit) = self.redo.pop() else {
+            return false;
+        };
+        self.revision += 1;
+        edit.apply(buffer);
+        self.undo.push_back(edit);
+        true
+    }
+
+    /// Check if there is anything to undo
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Check if there is anything to redo
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Get a counter that changes whenever the history changes
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Forget all recorded edits
+    pub fn clear(&mut self) {
+        self.revision += 1;
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_between_and_revert() {
+        let edit = TextEdit::between("hello world", "hello brave world", EditKind::Edit).unwrap();
+        assert_eq!(edit.offset, 6);
+        assert_eq!(edit.removed, "");
+        assert_eq!(edit.inserted, "brave ");
+
+        let mut buffer = TextBuffer::new();
+        buffer.set_text("hello brave world");
+        edit.revert(&mut buffer);
+        assert_eq!(buffer.as_str(), "hello world");
+        edit.apply(&mut buffer);
+        assert_eq!(buffer.as_str(), "hello brave world");
+        assert_eq!(buffer.cursor(), 12);
+        assert!(TextEdit::between("same", "same", EditKind::Edit).is_none());
+    }
+
+    #[test]
+    fn test_undo_redo_multiple_levels() {
+        let mut stack = UndoStack::new(2);
+        let mut buffer = TextBuffer::new();
+        for next in ["a", "ab", "abc"] {
+            stack.push(TextEdit::between(buffer.as_str(), next, EditKind::Symbol).unwrap());
+            buffer.set_text(next);
+        }
+
+        // Only the two most recent edits are kept
+        assert!(stack.undo(&mut buffer));
+        assert!(stack.undo(&mut buffer));
+        assert_eq!(buffer.as_str(), "a");
+        assert!(!stack.undo(&mut buffer));
+
+        assert!(stack.redo(&mut buffer));
+        assert_eq!(buffer.as_str(), "ab");
+
+        // A new edit discards the redo history
+        stack.push(TextEdit::between(buffer.as_str(), "abx", EditKind::Symbol).unwrap());
+        assert!(!stack.can_redo());
+    }
+}
@@ -57,7 +57,6 @@ impl From<io::Error> for WordGeneratorError {
 /// Base implementation for word generators that use an alphabet
 pub struct BaseWordGenerator {
     /// The alphabet information
-    #[allow(dead_code)]
     alphabet_info: AlphabetInfo,
     /// The alphabet map for converting between symbols and indices
     alphabet_map: AlphabetMap,
@@ -83,6 +82,15 @@ impl BaseWordGenerator {
         }
         symbols
     }
+
+    /// Get the first character of each symbol's text, in symbol index order
+    pub fn symbol_characters(&self) -> Vec<char> {
+        self.alphabet_info
+            .characters
+            .iter()
+            .map(|character| character.text.chars().next().unwrap_or('\0'))
+            .collect()
+    }
 }
 
 /// A word generator that reads words from a file
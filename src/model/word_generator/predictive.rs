@@ -1,5 +1,5 @@
 use super::WordGenerator;
-use crate::model::language::LanguageModel;
+use crate::model::language::{enter_text, LanguageModel};
 
 /// A word generator that uses a language model to predict words
 pub struct PredictiveWordGenerator {
@@ -15,11 +15,20 @@ pub struct PredictiveWordGenerator {
     prediction_buffer: Vec<String>,
     /// Index into the prediction buffer
     buffer_index: usize,
+    /// Character of each alphabet symbol, in symbol index order
+    symbols: Vec<char>,
+    /// Reusable buffer for the language model's counts
+    probs: Vec<u32>,
 }
 
 impl PredictiveWordGenerator {
+    /// Total of the counts requested from the language model
+    const NORMALIZATION: u32 = 1 << 16;
+
     /// Create a new predictive word generator
-    pub fn new(language_model: Box<dyn LanguageModel>, max_predictions: usize, base: crate::model::word_generator::BaseWordGenerator) -> Self {
+    pub fn new(mut language_model: Box<dyn LanguageModel>, max_predictions: usize, base: crate::model::word_generator::BaseWordGenerator) -> Self {
+        let symbols = base.symbol_characters();
+        language_model.set_symbols(&symbols);
         Self {
             base,
             language_model,
@@ -27,6 +36,8 @@ impl PredictiveWordGenerator {
             context: String::new(),
             prediction_buffer: Vec::new(),
             buffer_index: 0,
+            symbols,
+            probs: Vec::new(),
         }
     }
 
@@ -38,12 +49,15 @@ impl PredictiveWordGenerator {
 
     /// Generate and store predictions in the buffer
     fn refill_predictions(&mut self) {
-        use std::collections::HashMap;
         // Get probability distribution from the language model
-        let probs: HashMap<char, f64> = self.language_model.get_probs(&self.context);
+        let context = self.context.clone();
+        self.fill_probs(&context);
         // Sort by probability descending
-        let mut sorted: Vec<(char, f64)> = probs.into_iter().collect();
-        sorted.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        let mut sorted: Vec<(char, u32)> = self.symbols.iter().copied()
+            .zip(self.probs.iter().copied())
+            .filter(|&(_, count)| count > 0)
+            .collect();
+        sorted.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
         // Take top-N characters and turn them into strings (single-char predictions)
         self.prediction_buffer = sorted.iter().take(self.max_predictions).map(|(c, _)| c.to_string()).collect();
         self.buffer_index = 0;
@@ -57,21 +71,38 @@ impl PredictiveWordGenerator {
 
     /// Get the probability of a specific word given the current context
     pub fn get_probability(&mut self, word: &str) -> f64 {
-        let probs = self.language_model.get_probs(&self.context);
-        if word.len() == 1 {
-            probs.get(&word.chars().next().unwrap()).cloned().unwrap_or(0.0)
+        if word.chars().count() == 1 {
+            let context = self.context.clone();
+            self.symbol_probability(&context, word.chars().next().unwrap()).unwrap_or(0.0)
         } else {
             // For multi-char words, multiply probabilities (naive approach)
             let mut ctx = self.context.clone();
             let mut prob = 1.0;
             for c in word.chars() {
-                let probs = self.language_model.get_probs(&ctx);
-                prob *= probs.get(&c).cloned().unwrap_or(1e-9);
+                prob *= self.symbol_probability(&ctx, c).unwrap_or(1e-9);
                 ctx.push(c);
             }
             prob
         }
     }
+
+    /// Get the language model's counts for the symbol following `text`
+    fn fill_probs(&mut self, text: &str) {
+        let context = self.language_model.create_empty_context();
+        enter_text(self.language_model.as_mut(), context, &self.symbols, text);
+        self.language_model.get_probs(context, Self::NORMALIZATION, &mut self.probs);
+        self.language_model.release_context(context);
+    }
+
+    /// Get the probability of `symbol` following `context`, if the model predicts it
+    fn symbol_probability(&mut self, context: &str, symbol: char) -> Option<f64> {
+        self.fill_probs(context);
+        let index = self.symbols.iter().position(|&c| c == symbol)?;
+        match self.probs[index] {
+            0 => None,
+            count => Some(count as f64 / Self::NORMALIZATION as f64),
+        }
+    }
 }
 
 impl WordGenerator for PredictiveWordGenerator {
@@ -1,7 +1,7 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use crate::model::word_generator::WordGenerator;
 
-use crate::model::language::LanguageModel;
+use crate::model::language::{CombinedLanguageModel, LanguageModel};
 
 /// Manages word prediction and generation for the Dasher model
 pub struct WordPredictionManager {
@@ -11,8 +11,10 @@ pub struct WordPredictionManager {
     prediction_cache: HashMap<String, Vec<String>>,
     /// Maximum number of predictions to cache
     max_predictions: usize,
-    /// Maximum size of context to consider
+    /// Maximum number of words of context to consider
     max_context_size: usize,
+    /// Characters that end a word
+    word_separators: HashSet<char>,
 }
 
 impl WordPredictionManager {
@@ -23,9 +25,16 @@ impl WordPredictionManager {
             prediction_cache: HashMap::new(),
             max_predictions,
             max_context_size,
+            word_separators: CombinedLanguageModel::DEFAULT_WORD_SEPARATORS.iter().copied().collect(),
         }
     }
 
+    /// Set the characters that end a word
+    pub fn set_word_separators(&mut self, separators: &HashSet<char>) {
+        self.word_separators = separators.clone();
+        self.prediction_cache.clear();
+    }
+
     /// Add a word generator
     pub fn add_generator(&mut self, generator: Box<dyn WordGenerator>) {
         self.generators.push(generator);
@@ -71,13 +80,19 @@ impl WordPredictionManager {
     /// Update the context and get new predictions
     pub fn update_context(&mut self, context: &str) -> Vec<String> {
         // Take only the last max_context_size words
-        let context = if context.len() > self.max_context_size {
-            &context[context.len() - self.max_context_size..]
-        } else {
-            context
+        let separators = &self.word_separators;
+        let start = match self.max_context_size {
+            0 => context.len(),
+            max => context.char_indices()
+                .filter(|&(i, c)| {
+                    !separators.contains(&c)
+                        && context[..i].chars().next_back().is_none_or(|previous| separators.contains(&previous))
+                })
+                .nth_back(max - 1)
+                .map_or(0, |(i, _)| i),
         };
 
-        self.get_predictions(context)
+        self.get_predictions(&context[start..])
     }
 }
 
@@ -95,29 +110,43 @@ pub fn create_default_manager<M: LanguageModel + 'static>(
 mod tests {
     use super::*;
     use crate::model::word_generator::{PredictiveWordGenerator, BaseWordGenerator};
-    use crate::alphabet::{AlphabetInfo, AlphabetMap};
-    use crate::model::language::LanguageModel;
-    use std::collections::HashMap;
+    use crate::alphabet::{AlphabetInfo, AlphabetMap, Character};
+    use crate::model::language::{Context, LanguageModel};
 
-    struct MockLanguageModel;
+    struct MockLanguageModel(Vec<char>);
     use std::any::Any;
     impl LanguageModel for MockLanguageModel {
-        fn get_probs(&self, _context: &str) -> HashMap<char, f64> {
-            let mut map = HashMap::new();
-            map.insert('t', 0.7);
-            map.insert('w', 0.3);
-            map
+        fn set_symbols(&mut self, symbols: &[char]) {
+            self.0 = symbols.to_vec();
+        }
+        fn create_empty_context(&mut self) -> Context { Context::new(0) }
+        fn clone_context(&mut self, context: Context) -> Context { context }
+        fn release_context(&mut self, _context: Context) {}
+        fn get_probs(&self, _context: Context, norm: u32, probs: &mut Vec<u32>) {
+            probs.clear();
+            probs.extend(self.0.iter().map(|&c| match c {
+                't' => norm * 7 / 10,
+                'w' => norm - norm * 7 / 10,
+                _ => 0,
+            }));
         }
-        fn enter_symbol(&mut self, _symbol: char) {}
-        fn reset(&mut self) {}
+        fn enter_symbol(&mut self, _context: Context, _symbol: usize) {}
+        fn learn_symbol(&mut self, _context: Context, _symbol: usize) {}
         fn as_any(&mut self) -> &mut dyn Any { self }
     }
 
     #[test]
     fn test_word_prediction_manager_with_predictive() {
         let mut manager = WordPredictionManager::new(2, 2);
-        let model = Box::new(MockLanguageModel);
-        let alphabet_info = AlphabetInfo::default();
+        let model = Box::new(MockLanguageModel(Vec::new()));
+        let mut alphabet_info = AlphabetInfo::default();
+        for text in ["a", "t", "w"] {
+            alphabet_info.characters.push(Character {
+                display: text.to_string(),
+                text: text.to_string(),
+                ..Default::default()
+            });
+        }
         let alphabet_map = AlphabetMap::default();
         let base = BaseWordGenerator::new(alphabet_info, alphabet_map);
         let mut predictor = PredictiveWordGenerator::new(model, 2, base);
@@ -129,4 +158,33 @@ mod tests {
         assert!(predictions.contains(&"t".to_string()));
         assert!(predictions.contains(&"w".to_string()));
     }
+
+    #[test]
+    fn test_context_is_limited_to_whole_words() {
+        struct Echo;
+        impl WordGenerator for Echo {
+            fn next_word(&mut self) -> Option<String> {
+                None
+            }
+            fn get_symbols(&self, _word: &str) -> Vec<u32> {
+                Vec::new()
+            }
+            fn generate_words(&mut self, context: &str) -> Vec<String> {
+                vec![context.to_string()]
+            }
+        }
+
+        let mut manager = WordPredictionManager::new(1, 2);
+        manager.add_generator(Box::new(Echo));
+        assert_eq!(manager.update_context("¿qué tal estás"), ["tal estás"]);
+        assert_eq!(manager.update_context("uno, dos. "), ["uno, dos. "]);
+
+        let separators: HashSet<char> = [' ', '¿'].into_iter().collect();
+        manager.set_word_separators(&separators);
+        assert_eq!(manager.update_context("uno, dos. "), ["uno, dos. "]);
+        assert_eq!(manager.update_context("y dijo ¿qué"), ["dijo ¿qué"]);
+        assert_eq!(manager.update_context("y dijo¿qué"), ["dijo¿qué"]);
+        manager.set_word_separators(&[' '].into_iter().collect());
+        assert_eq!(manager.update_context("a b c"), ["b c"]);
+    }
 }
@@ -5,29 +5,144 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::model::{CombinedLanguageModel, DasherModel};
+use crate::input::InputFilterRegistry;
+use crate::view::FontDescriptor;
+
 /// Parameter types
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Parameter {
     // Boolean parameters
     ButtonMode,
     DrawMouse,
+    /// Draw a line from the crosshair to the pointer
     DrawMouseLine,
+    /// Adapt the speed to how smoothly the user steers
+    AutoSpeedControl,
+    /// Offer likely next words after a word separator
+    NextWordPrediction,
+    /// Write a space after a predicted word
+    AutoSpace,
+    /// Move punctuation written after a space to before it
+    SmartPunctuation,
+    /// Use the head pointer profile: low-pass filtering, vertical gain and dwelling to start
+    HeadPointer,
+    /// Start by holding the pointer in one box and then another, instead of clicking
+    TwoBoxStart,
+    /// Let written symbols teach the language model; off freezes it
+    LearnWhileWriting,
+    /// Hold learning back until each sentence is committed
+    LearnAtSentenceEnd,
+    /// Record committed sentences to the user's corpus file
+    RecordCorpus,
+    /// Draw frame rate, tree and context figures over each frame
+    DebugOverlay,
+    /// Offer the alphabet's digit and symbol groups behind toggle nodes
+    ToggleGroups,
     
     // Long parameters
+    /// Maximum speed, in hundredths of a bit per second
     MaxBitRate,
     ViewID,
     Language,
+    /// Direction text is written in, or the alphabet's if -1
     Orientation,
+    /// Minimum probability (in 1/65536ths) for a child node to be created on
+    /// its own; less probable symbols are grouped together. 0 disables grouping.
+    MinChildProbability,
+    /// X nonlinearity of the view, in tenths; 0 disables it
+    XNonlinearity,
+    /// Y nonlinearity of the view (compression factor at the edges); 1 or less disables it
+    YNonlinearity,
+    /// Width of the view's margin, in Dasher X units
+    MarginWidth,
+    /// How much fonts shrink for larger nodes, in hundredths
+    FontScaling,
+    /// Maximum number of nodes in the language model's trie; 0 means no limit
+    LanguageModelMaxNodes,
+    /// How the pointer is smoothed: 0 none, 1 exponential, 2 moving average, 3 Kalman
+    PointerSmoothing,
+    /// Strength of smoothing of the pointer's X axis, in hundredths
+    PointerSmoothingX,
+    /// Strength of smoothing of the pointer's Y axis, in hundredths
+    PointerSmoothingY,
+    /// Strength of the head pointer's low-pass filter, in hundredths
+    HeadPointerSmoothing,
+    /// Gain of the head pointer's vertical movement, in hundredths
+    HeadPointerVerticalGain,
+    /// How long the head pointer must dwell to start, in milliseconds
+    HeadPointerDwellTime,
+    /// How long the pointer must hover in a button region to press it, in
+    /// milliseconds; 0 disables dwell selection
+    ButtonDwellTime,
+    /// Speed multiplier while the boost (turbo) key is held, in percent
+    BoostFactor,
+    /// Share of the probability spread evenly over the symbols, in
+    /// thousandths, so rare symbols stay large enough to reach
+    Uniform,
+    /// Share of each node given to control nodes, in thousandths; 0 leaves
+    /// them out
+    ControlNodeMass,
+    /// Weight of node labels, from 100 to 900 as in CSS
+    FontWeight,
     
     // String parameters
     AlphabetID,
     ColourID,
+    /// Calibration of each input device, as a JSON object mapping device
+    /// names to their offsets and gains
+    InputCalibration,
+    /// Characters that end a word, such as space and full stop
+    WordSeparators,
+    /// Name of the input device the pointer is taken from, or empty for
+    /// the first device added
+    PrimaryInputDevice,
+    /// Name of the input filter, as registered in the input filter registry
+    InputFilterID,
+    /// Font family of node labels, or empty for the screen's own font
+    FontFamily,
     
     // TODO: Add more parameters as needed
 }
 
+impl Parameter {
+    /// Get the type of value stored for this parameter
+    pub fn parameter_type(&self) -> ParameterType {
+        match self {
+            Parameter::ButtonMode | Parameter::DrawMouse | Parameter::DrawMouseLine
+            | Parameter::AutoSpeedControl | Parameter::NextWordPrediction
+            | Parameter::AutoSpace | Parameter::SmartPunctuation
+            | Parameter::HeadPointer | Parameter::TwoBoxStart
+            | Parameter::LearnWhileWriting | Parameter::LearnAtSentenceEnd
+            | Parameter::RecordCorpus | Parameter::DebugOverlay
+            | Parameter::ToggleGroups => ParameterType::Bool,
+            Parameter::MaxBitRate | Parameter::ViewID | Parameter::Language | Parameter::Orientation
+            | Parameter::MinChildProbability | Parameter::XNonlinearity | Parameter::YNonlinearity
+            | Parameter::MarginWidth | Parameter::FontScaling
+            | Parameter::LanguageModelMaxNodes | Parameter::PointerSmoothing
+            | Parameter::PointerSmoothingX | Parameter::PointerSmoothingY
+            | Parameter::HeadPointerSmoothing | Parameter::HeadPointerVerticalGain
+            | Parameter::HeadPointerDwellTime | Parameter::ButtonDwellTime
+            | Parameter::BoostFactor | Parameter::Uniform
+            | Parameter::ControlNodeMass | Parameter::FontWeight => ParameterType::Long,
+            Parameter::AlphabetID | Parameter::ColourID
+            | Parameter::InputCalibration | Parameter::WordSeparators
+            | Parameter::PrimaryInputDevice | Parameter::InputFilterID
+            | Parameter::FontFamily => ParameterType::String,
+        }
+    }
+}
+
+/// The kinds of value a parameter can hold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ParameterType {
+    Bool,
+    Long,
+    String,
+}
+
 /// Parameter value types
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ParameterValue {
     Bool(bool),
     Long(i64),
@@ -55,16 +170,59 @@ impl Settings {
         values.insert(Parameter::ButtonMode, ParameterValue::Bool(false));
         values.insert(Parameter::DrawMouse, ParameterValue::Bool(true));
         values.insert(Parameter::DrawMouseLine, ParameterValue::Bool(false));
+        values.insert(Parameter::AutoSpeedControl, ParameterValue::Bool(true));
+        values.insert(Parameter::NextWordPrediction, ParameterValue::Bool(false));
+        values.insert(Parameter::AutoSpace, ParameterValue::Bool(false));
+        values.insert(Parameter::SmartPunctuation, ParameterValue::Bool(false));
+        values.insert(Parameter::HeadPointer, ParameterValue::Bool(false));
+        values.insert(Parameter::TwoBoxStart, ParameterValue::Bool(false));
+        values.insert(Parameter::LearnWhileWriting, ParameterValue::Bool(true));
+        values.insert(Parameter::LearnAtSentenceEnd, ParameterValue::Bool(false));
+        values.insert(Parameter::RecordCorpus, ParameterValue::Bool(false));
+        values.insert(Parameter::DebugOverlay, ParameterValue::Bool(false));
+        values.insert(Parameter::ToggleGroups, ParameterValue::Bool(true));
         values.insert(Parameter::MaxBitRate, ParameterValue::Long(100));
         values.insert(Parameter::ViewID, ParameterValue::Long(1));
         values.insert(Parameter::Language, ParameterValue::Long(0));
-        values.insert(Parameter::Orientation, ParameterValue::Long(0)); // Default: LeftToRight
+        values.insert(Parameter::Orientation, ParameterValue::Long(-1)); // Default: the alphabet's
+        values.insert(Parameter::MinChildProbability, ParameterValue::Long(0));
+        values.insert(Parameter::XNonlinearity, ParameterValue::Long(48));
+        values.insert(Parameter::YNonlinearity, ParameterValue::Long(4));
+        values.insert(Parameter::MarginWidth, ParameterValue::Long(DasherModel::MAX_X / 10));
+        values.insert(Parameter::FontScaling, ParameterValue::Long(50));
+        values.insert(Parameter::LanguageModelMaxNodes, ParameterValue::Long(0));
+        values.insert(Parameter::PointerSmoothing, ParameterValue::Long(0));
+        values.insert(Parameter::PointerSmoothingX, ParameterValue::Long(50));
+        values.insert(Parameter::PointerSmoothingY, ParameterValue::Long(50));
+        values.insert(Parameter::HeadPointerSmoothing, ParameterValue::Long(70));
+        values.insert(Parameter::HeadPointerVerticalGain, ParameterValue::Long(150));
+        values.insert(Parameter::HeadPointerDwellTime, ParameterValue::Long(1000));
+        values.insert(Parameter::ButtonDwellTime, ParameterValue::Long(0));
+        values.insert(Parameter::BoostFactor, ParameterValue::Long(175));
+        values.insert(Parameter::Uniform, ParameterValue::Long(DasherModel::DEFAULT_UNIFORM as i64));
+        values.insert(Parameter::ControlNodeMass, ParameterValue::Long(0));
+        values.insert(Parameter::FontWeight, ParameterValue::Long(FontDescriptor::NORMAL_WEIGHT as i64));
         values.insert(Parameter::AlphabetID, ParameterValue::String("Default".to_string()));
         values.insert(Parameter::ColourID, ParameterValue::String("Default".to_string()));
+        values.insert(Parameter::InputCalibration, ParameterValue::String("{}".to_string()));
+        values.insert(Parameter::WordSeparators, ParameterValue::String(Self::default_word_separators()));
+        values.insert(Parameter::PrimaryInputDevice, ParameterValue::String(String::new()));
+        values.insert(Parameter::InputFilterID, ParameterValue::String(InputFilterRegistry::DEFAULT.to_string()));
+        values.insert(Parameter::FontFamily, ParameterValue::String(String::new()));
         
         Self { values }
     }
     
+    /// Get the value of a parameter, whatever its type
+    pub fn get(&self, param: Parameter) -> Option<&ParameterValue> {
+        self.values.get(&param)
+    }
+
+    /// Set the value of a parameter, whatever its type
+    pub fn set(&mut self, param: Parameter, value: ParameterValue) {
+        self.values.insert(param, value);
+    }
+
     /// Get a boolean parameter value
     pub fn get_bool(&self, param: Parameter) -> Option<bool> {
         match self.values.get(&param) {
@@ -104,18 +262,56 @@ impl Settings {
         self.values.insert(param, ParameterValue::String(value));
     }
     
+    /// Get the default word separators, as stored in [`Parameter::WordSeparators`]
+    fn default_word_separators() -> String {
+        CombinedLanguageModel::DEFAULT_WORD_SEPARATORS.iter().collect()
+    }
+
     /// Reset a parameter to its default value
     pub fn reset_parameter(&mut self, param: Parameter) {
         match param {
             Parameter::ButtonMode => self.set_bool(param, false),
             Parameter::DrawMouse => self.set_bool(param, true),
             Parameter::DrawMouseLine => self.set_bool(param, false),
+            Parameter::AutoSpeedControl => self.set_bool(param, true),
+            Parameter::NextWordPrediction => self.set_bool(param, false),
+            Parameter::AutoSpace => self.set_bool(param, false),
+            Parameter::SmartPunctuation => self.set_bool(param, false),
+            Parameter::HeadPointer => self.set_bool(param, false),
+            Parameter::TwoBoxStart => self.set_bool(param, false),
+            Parameter::LearnWhileWriting => self.set_bool(param, true),
+            Parameter::LearnAtSentenceEnd => self.set_bool(param, false),
+            Parameter::RecordCorpus => self.set_bool(param, false),
+            Parameter::DebugOverlay => self.set_bool(param, false),
+            Parameter::ToggleGroups => self.set_bool(param, true),
             Parameter::MaxBitRate => self.set_long(param, 100),
             Parameter::ViewID => self.set_long(param, 1),
             Parameter::Language => self.set_long(param, 0),
-            Parameter::Orientation => self.set_long(param, 0),
+            Parameter::Orientation => self.set_long(param, -1),
+            Parameter::MinChildProbability => self.set_long(param, 0),
+            Parameter::XNonlinearity => self.set_long(param, 48),
+            Parameter::YNonlinearity => self.set_long(param, 4),
+            Parameter::MarginWidth => self.set_long(param, DasherModel::MAX_X / 10),
+            Parameter::FontScaling => self.set_long(param, 50),
+            Parameter::LanguageModelMaxNodes => self.set_long(param, 0),
+            Parameter::PointerSmoothing => self.set_long(param, 0),
+            Parameter::PointerSmoothingX => self.set_long(param, 50),
+            Parameter::PointerSmoothingY => self.set_long(param, 50),
+            Parameter::HeadPointerSmoothing => self.set_long(param, 70),
+            Parameter::HeadPointerVerticalGain => self.set_long(param, 150),
+            Parameter::HeadPointerDwellTime => self.set_long(param, 1000),
+            Parameter::ButtonDwellTime => self.set_long(param, 0),
+            Parameter::BoostFactor => self.set_long(param, 175),
+            Parameter::Uniform => self.set_long(param, DasherModel::DEFAULT_UNIFORM as i64),
+            Parameter::ControlNodeMass => self.set_long(param, 0),
+            Parameter::FontWeight => self.set_long(param, FontDescriptor::NORMAL_WEIGHT as i64),
             Parameter::AlphabetID => self.set_string(param, "Default".to_string()),
             Parameter::ColourID => self.set_string(param, "Default".to_string()),
+            Parameter::InputCalibration => self.set_string(param, "{}".to_string()),
+            Parameter::WordSeparators => self.set_string(param, Self::default_word_separators()),
+            Parameter::PrimaryInputDevice => self.set_string(param, String::new()),
+            Parameter::InputFilterID => self.set_string(param, InputFilterRegistry::DEFAULT.to_string()),
+            Parameter::FontFamily => self.set_string(param, String::new()),
         }
     }
 }
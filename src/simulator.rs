@@ -0,0 +1,347 @@
+//! # Simulator Module
+//!
+//! This module drives a [`DasherModel`] without a screen, view or input
+//! device, so the dynamics and language model can be exercised in tests and
+//! tuned offline. Time is simulated, one frame at a time, so runs are
+//! deterministic.
+//!
+//! A simulated user either steers a pointer, giving its position in Dasher
+//! coordinates each frame, or selects symbols directly from the node tree.
+//! Either way the run produces the text written and how long and how much
+//! information it took.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::input::FrameRate;
+use crate::model::{DasherModel, ExpansionPolicy, node::DasherNode};
+use crate::{DasherError, Result};
+
+/// Settings for a simulation
+#[derive(Debug, Clone)]
+pub struct SimulationConfig {
+    /// Simulated time between frames (in milliseconds)
+    pub frame_ms: u64,
+    /// Speed of zooming at full deflection (in bits per second)
+    pub bit_rate: f64,
+    /// Number of frames after which a run gives up
+    pub max_frames: u64,
+    /// Limits on how many nodes are expanded and collapsed each frame
+    pub expansion_policy: ExpansionPolicy,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            frame_ms: 16,
+            bit_rate: 10.0,
+            max_frames: 10_000,
+            expansion_policy: ExpansionPolicy::default(),
+        }
+    }
+}
+
+/// The outcome of a simulated run
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationResult {
+    /// Output text at the end of the run
+    pub text: String,
+    /// Number of frames simulated
+    pub frames: u64,
+    /// Simulated time taken (in milliseconds)
+    pub time_ms: u64,
+    /// Information entered during the run (in nats)
+    pub nats: f64,
+    /// Whether the run reached its goal before giving up
+    pub completed: bool,
+}
+
+impl SimulationResult {
+    /// Get the information entered during the run in bits
+    pub fn bits(&self) -> f64 {
+        self.nats / std::f64::consts::LN_2
+    }
+
+    /// Get the average rate of entering information (in bits per second)
+    pub fn bits_per_second(&self) -> f64 {
+        if self.time_ms == 0 {
+            0.0
+        } else {
+            self.bits() * 1000.0 / self.time_ms as f64
+        }
+    }
+
+    /// Get the average writing speed (in characters of output per minute)
+    pub fn chars_per_minute(&self) -> f64 {
+        if self.time_ms == 0 {
+            0.0
+        } else {
+            self.text.chars().count() as f64 * 60_000.0 / self.time_ms as f64
+        }
+    }
+}
+
+/// Drives a model with a simulated user
+pub struct Simulator {
+    /// The model being driven
+    model: DasherModel,
+    /// Simulation settings
+    config: SimulationConfig,
+    /// Simulated time of the next frame (in milliseconds)
+    time_ms: u64,
+}
+
+impl Simulator {
+    /// Create a simulator driving `model`, which should already be initialized
+    pub fn new(model: DasherModel, config: SimulationConfig) -> Self {
+        Self { model, config, time_ms: 0 }
+    }
+
+    /// Get the model being driven
+    pub fn model(&self) -> &DasherModel {
+        &self.model
+    }
+
+    /// Get the model being driven mutably
+    pub fn model_mut(&mut self) -> &mut DasherModel {
+        &mut self.model
+    }
+
+    /// Stop simulating, returning the model
+    pub fn into_model(self) -> DasherModel {
+        self.model
+    }
+
+    /// Get the simulation settings
+    pub fn config(&self) -> &SimulationConfig {
+        &self.config
+    }
+
+    /// Run frames with the pointer where `pointer` puts it
+    ///
+    /// `pointer` is given the model and the frame number and returns the
+    /// pointer position in Dasher coordinates (see
+    /// [`DasherModel::apply_input_coordinates`]), or `None` to end the run.
+    /// The run is complete if `pointer` ends it before the frame limit.
+    pub fn run_pointer<F>(&mut self, mut pointer: F) -> SimulationResult
+    where
+        F: FnMut(&DasherModel, u64) -> Option<(i64, i64)>,
+    {
+        let nats_before = self.model.total_nats();
+        let mut frame_rate = FrameRate::new(self.config.bit_rate, 1);
+        frame_rate.reset_ms(self.time_ms);
+
+        // At full deflection each zoom gains this many bits
+        let bits_at_limit = (DasherModel::ORIGIN_X as f64 / DasherModel::X_LIMIT as f64).log2();
+
+        let mut frames = 0;
+        let mut completed = false;
+        while frames < self.config.max_frames {
+            let Some(position) = pointer(&self.model, frames) else {
+                completed = true;
+                break;
+            };
+
+            self.time_ms += self.config.frame_ms;
+            frame_rate.record_frame_ms(self.time_ms);
+            let steps = ((frame_rate.steps() as f64 * bits_at_limit).round() as i32).max(1);
+            self.model.apply_input_coordinates(position, steps);
+            self.model.next_scheduled_step();
+            self.model.expand_visible_nodes(&self.config.expansion_policy);
+            frames += 1;
+        }
+
+        SimulationResult {
+            text: self.model.output_text().to_string(),
+            frames,
+            time_ms: frames * self.config.frame_ms,
+            nats: self.model.total_nats() - nats_before,
+            completed,
+        }
+    }
+
+    /// Steer towards the nodes that write `target`, as an ideal user would
+    ///
+    /// `target` is the whole text wanted, including any already written. The
+    /// pointer is aimed at the node that continues the target, and at the
+    /// left of the screen to reverse out of any text that does not. The run
+    /// is complete once the output is `target`.
+    pub fn run_to_text(&mut self, target: &str) -> SimulationResult {
+        let target: Vec<char> = target.chars().collect();
+        self.run_pointer(|model, _| Self::steer_to_text(model, &target))
+    }
+
+    /// Get the pointer position an ideal user would steer to when writing `target`
+    ///
+    /// Returns `None` once the output is `target`.
+    pub(crate) fn steer_to_text(model: &DasherModel, target: &[char]) -> Option<(i64, i64)> {
+        let output: Vec<char> = model.output_text().chars().collect();
+        if output == target {
+            return None;
+        }
+        let reverse = (DasherModel::MAX_X, DasherModel::ORIGIN_Y);
+        if !target.starts_with(&output) {
+            return Some(reverse);
+        }
+        Some(Self::target_position(model, target).unwrap_or(reverse))
+    }
+
+    /// Write `text` by selecting its symbols directly from the node tree
+    ///
+    /// Each symbol is selected from the children of the last one, as with a
+    /// switch that picks a node outright, starting below the root. No time
+    /// passes; the information is that of each symbol's share of its
+    /// parent. Fails if a symbol is not offered where it is needed.
+    pub fn select_symbols(&mut self, text: &str) -> Result<SimulationResult> {
+        let root = self.model.get_root_node()
+            .ok_or_else(|| DasherError::Other("Model is not initialized".to_string()))?;
+
+        let mut nats = 0.0;
+        let mut node = root;
+        for c in text.chars() {
+            let (child, share) = self.find_symbol_child(&node, c)
+                .ok_or_else(|| DasherError::InvalidParameter(format!("Symbol '{}' is not offered", c)))?;
+            nats -= share.ln();
+            self.model.output_to(&child);
+            node = child;
+        }
+
+        Ok(SimulationResult {
+            text: self.model.output_text().to_string(),
+            frames: 0,
+            time_ms: 0,
+            nats,
+            completed: true,
+        })
+    }
+
+    /// Find the child of `node` writing `c`, looking inside residual groups
+    ///
+    /// Returns the child with its share of `node`'s probability.
+    fn find_symbol_child(&mut self, node: &Rc<RefCell<DasherNode>>, c: char) -> Option<(Rc<RefCell<DasherNode>>, f64)> {
+        if node.borrow().children().is_empty() {
+            self.model.expand_node(node);
+        }
+
+        let children = node.borrow().children().clone();
+        for child in children {
+            let (symbol, share, grouped) = {
+                let child_ref = child.borrow();
+                let share = (child_ref.upper_bound() - child_ref.lower_bound()) as f64
+                    / DasherModel::NORMALIZATION as f64;
+                (child_ref.symbol(), share, child_ref.residual_symbols().contains(&c))
+            };
+            if symbol == Some(c) {
+                return Some((child, share));
+            }
+            if grouped {
+                let (grandchild, inner) = self.find_symbol_child(&child, c)?;
+                return Some((grandchild, share * inner));
+            }
+        }
+        None
+    }
+
+    /// Get the pointer position that steers into the node continuing `target`
+    ///
+    /// Returns `None` if the root itself strays from the target, so the only
+    /// way back is to reverse.
+    fn target_position(model: &DasherModel, target: &[char]) -> Option<(i64, i64)> {
+        let mut node = model.get_root_node()?;
+        if node.borrow().symbol().is_some() && !Self::continues_target(&node.borrow(), target) {
+            return None;
+        }
+        let (mut node_min, mut node_max) = model.root_bounds();
+
+        // Follow the children that agree with the target as far as they go
+        loop {
+            let next = node.borrow().children().iter().find_map(|child| {
+                let child_ref = child.borrow();
+                Self::continues_target(&child_ref, target).then(|| {
+                    let width = node_max - node_min;
+                    (
+                        child.clone(),
+                        node_min + child_ref.lower_bound() as i64 * width / DasherModel::NORMALIZATION as i64,
+                        node_min + child_ref.upper_bound() as i64 * width / DasherModel::NORMALIZATION as i64,
+                    )
+                })
+            });
+            match next {
+                Some((child, child_min, child_max)) => {
+                    node = child;
+                    node_min = child_min;
+                    node_max = child_max;
+                }
+                None => break,
+            }
+        }
+
+        // Zoom so the node fills the screen, but keep moving while its
+        // children are expanded
+        let y = (node_min + node_max) / 2;
+        let x = ((node_max - node_min) / 2).clamp(DasherModel::X_LIMIT, DasherModel::ORIGIN_X / 2);
+        Some((x, y))
+    }
+
+    /// Check whether a node writes (or groups) the next part of `target`
+    fn continues_target(node: &DasherNode, target: &[char]) -> bool {
+        let offset = node.offset();
+        let next = |offset: i32| usize::try_from(offset).ok().and_then(|i| target.get(i)).copied();
+        match node.symbol() {
+            Some(symbol) => next(offset) == Some(symbol),
+            None if !node.residual_symbols().is_empty() => {
+                next(offset + 1).is_some_and(|c| node.residual_symbols().contains(&c))
+            }
+            None => !node.predicted_text().is_empty()
+                && usize::try_from(offset + 1).is_ok_and(|start| {
+                    target.get(start..).is_some_and(|rest| rest.iter().copied().take(node.predicted_text().chars().count()).eq(node.predicted_text().chars()))
+                }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simulator() -> Simulator {
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        Simulator::new(model, SimulationConfig::default())
+    }
+
+    #[test]
+    fn test_select_symbols() {
+        let mut simulator = simulator();
+        let result = simulator.select_symbols("the dog").unwrap();
+        assert_eq!(result.text, "the dog");
+        assert!(result.nats > 0.0);
+        assert!(result.bits() > result.nats);
+
+        assert!(simulator.select_symbols("\u{1F600}").is_err());
+    }
+
+    #[test]
+    fn test_steering_to_text_is_deterministic() {
+        let mut first = simulator();
+        let result = first.run_to_text("the ");
+        assert!(result.completed, "gave up with {:?}", result.text);
+        assert_eq!(result.text, "the ");
+        assert!(result.frames > 0);
+        assert!(result.bits_per_second() > 0.0);
+        assert!(result.chars_per_minute() > 0.0);
+
+        let mut second = simulator();
+        assert_eq!(second.run_to_text("the "), result);
+    }
+
+    #[test]
+    fn test_run_pointer_stops_at_frame_limit() {
+        let mut simulator = simulator();
+        simulator.config.max_frames = 5;
+        let result = simulator.run_pointer(|_, _| Some((DasherModel::ORIGIN_X, DasherModel::ORIGIN_Y)));
+        assert!(!result.completed);
+        assert_eq!(result.frames, 5);
+        assert_eq!(result.time_ms, 5 * simulator.config().frame_ms);
+    }
+}
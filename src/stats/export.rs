@@ -0,0 +1,206 @@
+//! Session reports
+//!
+//! A summary of a writing session, minute by minute, exported as JSON or
+//! CSV for clinicians and researchers following a user's performance.
+
+use serde::{Deserialize, Serialize};
+
+use super::{SpeedChange, UserStats, CHARS_PER_WORD, MINUTE_MS};
+use crate::{DasherError, Result};
+
+/// Format of an exported session report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsFormat {
+    /// The whole report as a JSON object
+    Json,
+    /// One row per minute, with a header row
+    Csv,
+}
+
+/// Performance during one minute of writing time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MinuteReport {
+    /// Index of the minute, from 0
+    pub minute: usize,
+
+    /// Writing time in the minute (in milliseconds), less than a minute for the last
+    pub duration_ms: u64,
+
+    /// Writing speed, based on net symbols
+    pub words_per_minute: f64,
+
+    /// Number of symbols committed to the output
+    pub symbols_entered: usize,
+
+    /// Number of symbols removed from the output
+    pub symbols_deleted: usize,
+
+    /// Number of corrections started
+    pub corrections: usize,
+
+    /// Average information entered per symbol (in bits)
+    pub bits_per_symbol: f64,
+
+    /// Speed setting at the end of the minute (in bits per second), if known
+    pub max_bit_rate: Option<f64>,
+}
+
+/// Performance over a writing session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionReport {
+    /// Time spent actively writing (in milliseconds)
+    pub duration_ms: u64,
+
+    /// Writing speed, based on net symbols
+    pub words_per_minute: f64,
+
+    /// Number of symbols committed to the output
+    pub symbols_entered: usize,
+
+    /// Number of symbols removed from the output
+    pub symbols_deleted: usize,
+
+    /// Number of corrections, each a run of deletions after writing
+    pub corrections: usize,
+
+    /// Average information entered per symbol (in bits)
+    pub bits_per_symbol: f64,
+
+    /// Information rate (in bits per second)
+    pub bits_per_second: f64,
+
+    /// Performance in each minute of writing time
+    pub minutes: Vec<MinuteReport>,
+
+    /// Speed settings in the order they were used
+    pub speed_history: Vec<SpeedChange>,
+}
+
+impl SessionReport {
+    /// Header row of the CSV export
+    pub const CSV_HEADER: &'static str =
+        "minute,duration_ms,words_per_minute,symbols_entered,symbols_deleted,corrections,bits_per_symbol,max_bit_rate";
+
+    /// Summarise the statistics of a session
+    pub fn from_stats(stats: &UserStats) -> Self {
+        let minutes = stats.minutes().iter().enumerate().map(|(minute, counts)| {
+            let start_ms = minute as u64 * MINUTE_MS;
+            let duration_ms = stats.session_duration_ms().saturating_sub(start_ms).min(MINUTE_MS);
+            let net_symbols = counts.symbols_entered.saturating_sub(counts.symbols_deleted);
+            let words_per_minute = if duration_ms == 0 {
+                0.0
+            } else {
+                (net_symbols as f64 / CHARS_PER_WORD) / (duration_ms as f64 / MINUTE_MS as f64)
+            };
+            let bits_per_symbol = if counts.symbols_entered == 0 {
+                0.0
+            } else {
+                counts.nats / std::f64::consts::LN_2 / counts.symbols_entered as f64
+            };
+            let max_bit_rate = stats.speed_history().iter()
+                .take_while(|change| change.time_ms < start_ms + MINUTE_MS)
+                .last()
+                .map(|change| change.max_bit_rate);
+            MinuteReport {
+                minute,
+                duration_ms,
+                words_per_minute,
+                symbols_entered: counts.symbols_entered,
+                symbols_deleted: counts.symbols_deleted,
+                corrections: counts.corrections,
+                bits_per_symbol,
+                max_bit_rate,
+            }
+        }).collect();
+
+        Self {
+            duration_ms: stats.session_duration_ms(),
+            words_per_minute: stats.words_per_minute(),
+            symbols_entered: stats.symbols_entered(),
+            symbols_deleted: stats.symbols_deleted(),
+            corrections: stats.corrections(),
+            bits_per_symbol: stats.bits_per_symbol(),
+            bits_per_second: stats.bits_per_second(),
+            minutes,
+            speed_history: stats.speed_history().to_vec(),
+        }
+    }
+
+    /// Export the report as JSON
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| DasherError::Other(format!("Failed to serialize session report: {}", e)))
+    }
+
+    /// Export the minutes of the report as CSV
+    ///
+    /// The speed is left empty in minutes before any speed was recorded.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(Self::CSV_HEADER);
+        csv.push('\n');
+        for minute in &self.minutes {
+            csv.push_str(&format!(
+                "{},{},{:.2},{},{},{},{:.3},{}\n",
+                minute.minute,
+                minute.duration_ms,
+                minute.words_per_minute,
+                minute.symbols_entered,
+                minute.symbols_deleted,
+                minute.corrections,
+                minute.bits_per_symbol,
+                minute.max_bit_rate.map_or(String::new(), |rate| rate.to_string()),
+            ));
+        }
+        csv
+    }
+
+    /// Export the report in the given format
+    pub fn export(&self, format: StatsFormat) -> Result<String> {
+        match format {
+            StatsFormat::Json => self.to_json(),
+            StatsFormat::Csv => Ok(self.to_csv()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_breaks_the_session_into_minutes() {
+        let mut stats = UserStats::new();
+        stats.record_speed(1.5);
+        stats.record_frame(0);
+        stats.record_entered(50);
+        stats.record_nats(std::f64::consts::LN_2 * 100.0);
+        stats.record_frame(60_000);
+        stats.record_speed(2.0);
+        stats.record_entered(12);
+        stats.record_deleted(2);
+        stats.record_frame(90_000);
+
+        let report = SessionReport::from_stats(&stats);
+        assert_eq!(report.corrections, 1);
+        assert!((report.bits_per_symbol - 100.0 / 62.0).abs() < 1e-9);
+        assert_eq!(report.minutes.len(), 2);
+        assert!((report.minutes[0].words_per_minute - 10.0).abs() < 1e-9);
+        assert!((report.minutes[0].bits_per_symbol - 2.0).abs() < 1e-9);
+        assert_eq!(report.minutes[0].max_bit_rate, Some(1.5));
+        assert_eq!(report.minutes[1].duration_ms, 30_000);
+        assert!((report.minutes[1].words_per_minute - 4.0).abs() < 1e-9);
+        assert_eq!(report.minutes[1].max_bit_rate, Some(2.0));
+
+        let csv = report.to_csv();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines, [
+            SessionReport::CSV_HEADER,
+            "0,60000,10.00,50,0,0,2.000,1.5",
+            "1,30000,4.00,12,2,1,0.000,2",
+        ]);
+
+        let json = report.export(StatsFormat::Json).unwrap();
+        let parsed: SessionReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, report);
+    }
+}
@@ -0,0 +1,296 @@
+//! # Stats Module
+//!
+//! This module tracks user statistics for a Dasher session, in the spirit of
+//! the UserLog in upstream Dasher: information entered, symbols written and
+//! erased, time spent writing and the resulting words per minute.
+
+mod export;
+
+pub use export::{MinuteReport, SessionReport, StatsFormat};
+
+use serde::{Deserialize, Serialize};
+
+use crate::logging::Logger;
+
+/// Number of characters counted as one word when computing words per minute
+pub const CHARS_PER_WORD: f64 = 5.0;
+
+/// Length of each period the session is broken into (in milliseconds)
+pub const MINUTE_MS: u64 = 60_000;
+
+/// Statistics for one minute of writing time
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MinuteStats {
+    /// Information entered (in nats)
+    pub nats: f64,
+
+    /// Number of symbols committed to the output
+    pub symbols_entered: usize,
+
+    /// Number of symbols removed from the output
+    pub symbols_deleted: usize,
+
+    /// Number of corrections started
+    pub corrections: usize,
+}
+
+/// A change of the speed setting
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SpeedChange {
+    /// Writing time at which the speed was set (in milliseconds)
+    pub time_ms: u64,
+
+    /// Maximum speed (in bits per second)
+    pub max_bit_rate: f64,
+}
+
+/// Statistics for the current writing session
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserStats {
+    /// Total information entered (in nats)
+    total_nats: f64,
+
+    /// Number of symbols committed to the output
+    symbols_entered: usize,
+
+    /// Number of symbols removed from the output
+    symbols_deleted: usize,
+
+    /// Number of corrections, each a run of deletions after writing
+    #[serde(default)]
+    corrections: usize,
+
+    /// Time spent actively writing (in milliseconds)
+    active_time_ms: u64,
+
+    /// Statistics for each minute of writing time
+    #[serde(default)]
+    minutes: Vec<MinuteStats>,
+
+    /// Speed settings in the order they were used
+    #[serde(default)]
+    speed_history: Vec<SpeedChange>,
+
+    /// Time of the last frame while writing, if the session is not suspended
+    #[serde(skip)]
+    last_frame_ms: Option<u64>,
+
+    /// Whether the last change to the output was a deletion
+    #[serde(skip)]
+    deleting: bool,
+}
+
+impl UserStats {
+    /// Create a new, empty set of statistics
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reset all statistics to zero
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Record a frame in which the user was writing.
+    ///
+    /// Time between consecutive frames is added to the session duration.
+    pub fn record_frame(&mut self, time_ms: u64) {
+        if let Some(last_frame_ms) = self.last_frame_ms {
+            self.active_time_ms += time_ms.saturating_sub(last_frame_ms);
+        }
+        self.last_frame_ms = Some(time_ms);
+    }
+
+    /// Stop counting time until the next frame (e.g. when paused or stopped)
+    pub fn suspend(&mut self) {
+        self.last_frame_ms = None;
+    }
+
+    /// Get the statistics for the current minute of writing time
+    fn current_minute(&mut self) -> &mut MinuteStats {
+        let minute = (self.active_time_ms / MINUTE_MS) as usize;
+        if self.minutes.len() <= minute {
+            self.minutes.resize(minute + 1, MinuteStats::default());
+        }
+        &mut self.minutes[minute]
+    }
+
+    /// Record information entered (in nats)
+    pub fn record_nats(&mut self, nats: f64) {
+        if nats.is_finite() {
+            self.total_nats += nats;
+            self.current_minute().nats += nats;
+        }
+    }
+
+    /// Record symbols committed to the output
+    pub fn record_entered(&mut self, count: usize) {
+        if count == 0 {
+            return;
+        }
+        self.symbols_entered += count;
+        self.current_minute().symbols_entered += count;
+        self.deleting = false;
+    }
+
+    /// Record symbols removed from the output
+    ///
+    /// Deletions following one another count as a single correction.
+    pub fn record_deleted(&mut self, count: usize) {
+        if count == 0 {
+            return;
+        }
+        self.symbols_deleted += count;
+        let starts_correction = !self.deleting;
+        let minute = self.current_minute();
+        minute.symbols_deleted += count;
+        if starts_correction {
+            minute.corrections += 1;
+            self.corrections += 1;
+        }
+        self.deleting = true;
+    }
+
+    /// Record the speed setting in use (in bits per second)
+    ///
+    /// Only changes of speed are kept.
+    pub fn record_speed(&mut self, max_bit_rate: f64) {
+        if self.speed_history.last().map(|change| change.max_bit_rate) != Some(max_bit_rate) {
+            self.speed_history.push(SpeedChange { time_ms: self.active_time_ms, max_bit_rate });
+        }
+    }
+
+    /// Get the total information entered (in nats)
+    pub fn total_nats(&self) -> f64 {
+        self.total_nats
+    }
+
+    /// Get the total information entered (in bits)
+    pub fn total_bits(&self) -> f64 {
+        self.total_nats / std::f64::consts::LN_2
+    }
+
+    /// Get the number of symbols committed to the output
+    pub fn symbols_entered(&self) -> usize {
+        self.symbols_entered
+    }
+
+    /// Get the number of symbols removed from the output
+    pub fn symbols_deleted(&self) -> usize {
+        self.symbols_deleted
+    }
+
+    /// Get the number of corrections, each a run of deletions after writing
+    pub fn corrections(&self) -> usize {
+        self.corrections
+    }
+
+    /// Get the statistics for each minute of writing time
+    pub fn minutes(&self) -> &[MinuteStats] {
+        &self.minutes
+    }
+
+    /// Get the speed settings in the order they were used
+    pub fn speed_history(&self) -> &[SpeedChange] {
+        &self.speed_history
+    }
+
+    /// Get the number of symbols entered and not subsequently deleted
+    pub fn net_symbols(&self) -> usize {
+        self.symbols_entered.saturating_sub(self.symbols_deleted)
+    }
+
+    /// Get the time spent actively writing (in milliseconds)
+    pub fn session_duration_ms(&self) -> u64 {
+        self.active_time_ms
+    }
+
+    /// Get the writing speed in words per minute, based on net symbols
+    pub fn words_per_minute(&self) -> f64 {
+        if self.active_time_ms == 0 {
+            return 0.0;
+        }
+        let minutes = self.active_time_ms as f64 / 60_000.0;
+        (self.net_symbols() as f64 / CHARS_PER_WORD) / minutes
+    }
+
+    /// Get the information rate in bits per second
+    pub fn bits_per_second(&self) -> f64 {
+        if self.active_time_ms == 0 {
+            return 0.0;
+        }
+        self.total_bits() / (self.active_time_ms as f64 / 1000.0)
+    }
+
+    /// Get the average information entered per symbol (in bits)
+    pub fn bits_per_symbol(&self) -> f64 {
+        if self.symbols_entered == 0 {
+            return 0.0;
+        }
+        self.total_bits() / self.symbols_entered as f64
+    }
+
+    /// Write the statistics to a logger
+    pub fn report(&self, logger: &mut dyn Logger) {
+        logger.log_stat("bits_entered", self.total_bits().round() as usize);
+        logger.log_stat("symbols_entered", self.symbols_entered);
+        logger.log_stat("symbols_deleted", self.symbols_deleted);
+        logger.log_stat("corrections", self.corrections);
+        logger.log_stat("session_duration_ms", self.active_time_ms as usize);
+        logger.log_stat("words_per_minute", self.words_per_minute().round() as usize);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_words_per_minute() {
+        let mut stats = UserStats::new();
+        stats.record_frame(0);
+        stats.record_frame(30_000);
+        stats.suspend();
+        // Time while suspended is not counted
+        stats.record_frame(90_000);
+        stats.record_frame(120_000);
+
+        stats.record_entered(55);
+        stats.record_deleted(5);
+
+        assert_eq!(stats.session_duration_ms(), 60_000);
+        assert_eq!(stats.net_symbols(), 50);
+        assert!((stats.words_per_minute() - 10.0).abs() < 1e-9);
+
+        stats.record_nats(std::f64::consts::LN_2 * 8.0);
+        assert!((stats.total_bits() - 8.0).abs() < 1e-9);
+
+        stats.reset();
+        assert_eq!(stats, UserStats::new());
+    }
+
+    #[test]
+    fn test_minutes_corrections_and_speed() {
+        let mut stats = UserStats::new();
+        stats.record_speed(1.5);
+        stats.record_frame(0);
+        stats.record_entered(10);
+        stats.record_deleted(1);
+        stats.record_deleted(2);
+        stats.record_entered(4);
+        stats.record_speed(1.5);
+        stats.record_frame(70_000);
+        stats.record_speed(2.0);
+        stats.record_deleted(1);
+        stats.record_deleted(0);
+
+        assert_eq!(stats.corrections(), 2);
+        assert_eq!(stats.minutes().len(), 2);
+        assert_eq!(stats.minutes()[0], MinuteStats { nats: 0.0, symbols_entered: 14, symbols_deleted: 3, corrections: 1 });
+        assert_eq!(stats.minutes()[1], MinuteStats { nats: 0.0, symbols_entered: 0, symbols_deleted: 1, corrections: 1 });
+        assert_eq!(stats.speed_history(), [
+            SpeedChange { time_ms: 0, max_bit_rate: 1.5 },
+            SpeedChange { time_ms: 70_000, max_bit_rate: 2.0 },
+        ]);
+    }
+}
@@ -0,0 +1,304 @@
+//! Command-list screen
+//!
+//! A screen that records what is drawn instead of drawing it. Each frame
+//! becomes a list of primitives that can be serialized and handed to a host
+//! (a web page, a test, or a remote user interface), which then only needs
+//! to know how to draw five kinds of primitive.
+
+use serde::{Deserialize, Serialize};
+
+use super::{Color, DasherScreen, FontDescriptor, Label};
+use crate::{DasherError, Result};
+
+/// A drawing primitive
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DrawCommand {
+    /// A rectangle with corners (`x1`, `y1`) and (`x2`, `y2`)
+    Rectangle { x1: i32, y1: i32, x2: i32, y2: i32, fill: Color, outline: Color, line_width: i32 },
+
+    /// A circle centred on (`cx`, `cy`)
+    Circle { cx: i32, cy: i32, r: i32, fill: Color, outline: Color, line_width: i32 },
+
+    /// A straight line
+    Line { x1: i32, y1: i32, x2: i32, y2: i32, color: Color, line_width: i32 },
+
+    /// A closed polygon
+    Polygon { points: Vec<(i32, i32)>, fill: Color, outline: Color, line_width: i32 },
+
+    /// Text with its top-left corner at (`x`, `y`), in `font` if its label has one
+    Text {
+        text: String,
+        x: i32,
+        y: i32,
+        font_size: u32,
+        color: Color,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        font: Option<FontDescriptor>,
+    },
+}
+
+/// Everything drawn in one frame
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Frame {
+    /// Width of the screen the frame was drawn for
+    pub width: i32,
+
+    /// Height of the screen the frame was drawn for
+    pub height: i32,
+
+    /// Primitives in the order they were drawn
+    pub commands: Vec<DrawCommand>,
+}
+
+impl Frame {
+    /// Serialize the frame to JSON
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(self).map_err(|e| DasherError::Other(format!("Failed to serialize frame: {}", e)))
+    }
+
+    /// Deserialize a frame from JSON
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| DasherError::InvalidParameter(format!("Invalid frame: {}", e)))
+    }
+}
+
+/// Label of a command-list screen
+pub(super) struct CommandLabel {
+    pub(super) text: String,
+    pub(super) wrap_size: u32,
+    pub(super) font: Option<FontDescriptor>,
+}
+
+impl Label for CommandLabel {
+    fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    fn get_wrap_size(&self) -> u32 {
+        self.wrap_size
+    }
+
+    fn font(&self) -> Option<&FontDescriptor> {
+        self.font.as_ref()
+    }
+}
+
+/// Estimate the size of a label from an average character width and line height
+///
+/// Both are fractions of the font size. Text wider than the label's wrap
+/// size goes onto further lines.
+pub(super) fn estimate_text_size(label: &dyn Label, font_size: u32, char_width: f64, line_height: f64) -> (i32, i32) {
+    let chars = label.get_text().chars().count() as f64;
+    let width = chars * char_width * font_size as f64;
+    let line_height = line_height * font_size as f64;
+
+    let wrap_size = label.get_wrap_size() as f64;
+    if wrap_size > 0.0 && width > wrap_size {
+        let lines = (width / wrap_size).ceil();
+        (wrap_size.round() as i32, (lines * line_height).round() as i32)
+    } else {
+        (width.round() as i32, line_height.round() as i32)
+    }
+}
+
+/// Screen recording draw calls into a list of primitives per frame
+#[derive(Debug, Clone)]
+pub struct CommandListScreen {
+    /// Width of the screen (in pixels)
+    width: i32,
+
+    /// Height of the screen (in pixels)
+    height: i32,
+
+    /// Average width of a character, as a fraction of the font size
+    char_width: f64,
+
+    /// Height of a line of text, as a fraction of the font size
+    line_height: f64,
+
+    /// Primitives drawn since the last frame was completed
+    pending: Vec<DrawCommand>,
+
+    /// Last completed frame
+    frame: Option<Frame>,
+}
+
+impl CommandListScreen {
+    /// Create a screen of the given size
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            char_width: 0.6,
+            line_height: 1.2,
+            pending: Vec::new(),
+            frame: None,
+        }
+    }
+
+    /// Change the size of the screen
+    pub fn set_size(&mut self, width: i32, height: i32) {
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Set the metrics used to measure text, as fractions of the font size
+    ///
+    /// The host draws the text, so these should approximate its font.
+    pub fn set_text_metrics(&mut self, char_width: f64, line_height: f64) {
+        self.char_width = char_width.max(0.0);
+        self.line_height = line_height.max(0.0);
+    }
+
+    /// Get the primitives drawn since the last frame was completed
+    pub fn pending_commands(&self) -> &[DrawCommand] {
+        &self.pending
+    }
+
+    /// Get the last completed frame
+    pub fn last_frame(&self) -> Option<&Frame> {
+        self.frame.as_ref()
+    }
+
+    /// Take the last completed frame, so it is only handed to the host once
+    pub fn take_frame(&mut self) -> Option<Frame> {
+        self.frame.take()
+    }
+}
+
+impl DasherScreen for CommandListScreen {
+    fn get_width(&self) -> i32 {
+        self.width
+    }
+
+    fn get_height(&self) -> i32 {
+        self.height
+    }
+
+    fn make_label(&self, text: &str, wrap_size: u32) -> Box<dyn Label> {
+        Box::new(CommandLabel { text: text.to_string(), wrap_size, font: None })
+    }
+
+    fn make_font_label(&self, text: &str, wrap_size: u32, font: &FontDescriptor) -> Box<dyn Label> {
+        Box::new(CommandLabel { text: text.to_string(), wrap_size, font: Some(font.clone()) })
+    }
+
+    fn text_size(&self, label: &dyn Label, font_size: u32) -> (i32, i32) {
+        estimate_text_size(label, font_size, self.char_width, self.line_height)
+    }
+
+    fn draw_string(&mut self, label: &dyn Label, x: i32, y: i32, font_size: u32, color: Color) {
+        self.pending.push(DrawCommand::Text {
+            text: label.get_text().to_string(),
+            x,
+            y,
+            font_size,
+            color,
+            font: label.font().cloned(),
+        });
+    }
+
+    fn draw_rectangle(&mut self, x1: i32, y1: i32, x2: i32, y2: i32,
+                     fill_color: Color, outline_color: Color, line_width: i32) {
+        self.pending.push(DrawCommand::Rectangle { x1, y1, x2, y2, fill: fill_color, outline: outline_color, line_width });
+    }
+
+    fn draw_circle(&mut self, cx: i32, cy: i32, r: i32,
+                  fill_color: Color, line_color: Color, line_width: i32) {
+        self.pending.push(DrawCommand::Circle { cx, cy, r, fill: fill_color, outline: line_color, line_width });
+    }
+
+    fn draw_line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: Color, line_width: i32) {
+        self.pending.push(DrawCommand::Line { x1, y1, x2, y2, color, line_width });
+    }
+
+    fn draw_polygon(&mut self, points: &[(i32, i32)], fill_color: Color, outline_color: Color, line_width: i32) {
+        if points.len() < 3 {
+            return;
+        }
+        self.pending.push(DrawCommand::Polygon { points: points.to_vec(), fill: fill_color, outline: outline_color, line_width });
+    }
+
+    fn display(&mut self) {
+        self.frame = Some(Frame {
+            width: self.width,
+            height: self.height,
+            commands: std::mem::take(&mut self.pending),
+        });
+    }
+
+    fn is_point_visible(&self, _x: i32, _y: i32) -> bool {
+        true
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::DasherModel;
+    use crate::view::{color_palette, DasherView, DasherViewSquare, SquareViewConfig};
+
+    #[test]
+    fn test_records_frames_of_primitives() {
+        let mut screen = CommandListScreen::new(400, 300);
+        screen.draw_line(0, 0, 10, 10, color_palette::RED, 2);
+        screen.draw_polygon(&[(0, 0), (5, 5)], color_palette::RED, color_palette::BLACK, 1);
+        assert_eq!(screen.pending_commands().len(), 1);
+        assert!(screen.last_frame().is_none());
+
+        screen.display();
+        assert!(screen.pending_commands().is_empty());
+        let frame = screen.take_frame().unwrap();
+        assert_eq!((frame.width, frame.height), (400, 300));
+        assert_eq!(frame.commands, vec![DrawCommand::Line { x1: 0, y1: 0, x2: 10, y2: 10, color: color_palette::RED, line_width: 2 }]);
+        assert_eq!(Frame::from_json(&frame.to_json().unwrap()).unwrap(), frame);
+        assert!(screen.take_frame().is_none());
+
+        // Text is measured with the configured metrics, wrapping at the wrap size
+        screen.set_text_metrics(0.5, 1.0);
+        let label = screen.make_label("abcdefgh", 0);
+        assert_eq!(screen.text_size(&*label, 20), (80, 20));
+        let label = screen.make_label("abcdefgh", 30);
+        assert_eq!(screen.text_size(&*label, 20), (30, 60));
+    }
+
+    #[test]
+    fn test_model_renders_to_command_list() {
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        let mut screen = CommandListScreen::new(800, 600);
+        model.render_to_view(&mut screen, SquareViewConfig::default().min_node_pixels).unwrap();
+
+        let frame = screen.take_frame().unwrap();
+        assert!(frame.commands.iter().filter(|command| matches!(command, DrawCommand::Rectangle { .. })).count() > 1);
+        assert!(frame.commands.iter().any(|command| matches!(command, DrawCommand::Text { .. })));
+    }
+
+    #[test]
+    fn test_labels_are_drawn_in_the_chosen_font() {
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        let font = FontDescriptor::new(Some("OpenDyslexic"), FontDescriptor::BOLD_WEIGHT);
+        let mut view = DasherViewSquare::new(Box::new(CommandListScreen::new(800, 600)));
+        view.set_font(font.clone());
+        view.render(&mut model).unwrap();
+
+        let frame = view.screen_mut().as_any_mut()
+            .and_then(|screen| screen.downcast_mut::<CommandListScreen>())
+            .and_then(CommandListScreen::take_frame)
+            .unwrap();
+        let fonts: Vec<_> = frame.commands.iter()
+            .filter_map(|command| match command {
+                DrawCommand::Text { font, .. } => Some(font),
+                _ => None,
+            })
+            .collect();
+        assert!(!fonts.is_empty());
+        assert!(fonts.iter().all(|label_font| label_font.as_ref() == Some(&font)));
+        assert_eq!(Frame::from_json(&frame.to_json().unwrap()).unwrap(), frame);
+    }
+}
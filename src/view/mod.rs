@@ -4,19 +4,36 @@
 //! responsible for rendering the Dasher interface.
 
 pub mod square;
+pub mod command_list;
+pub mod overlay;
+pub mod scene;
+#[cfg(feature = "svg")]
+pub mod svg;
+#[cfg(feature = "raster")]
+pub mod raster;
 #[cfg(test)]
 mod square_tests;
 
 pub use square::DasherViewSquare;
 pub use square::NodeShape;
 pub use square::SquareViewConfig;
+pub use square::VisibleNode;
+pub use command_list::{CommandListScreen, DrawCommand, Frame};
+pub use overlay::DebugInfo;
+pub use scene::Scene;
+#[cfg(feature = "svg")]
+pub use svg::render_to_svg;
+#[cfg(feature = "raster")]
+pub use raster::RasterScreen;
+
+use serde::{Deserialize, Serialize};
 
 use crate::DasherInput;
 use crate::model::DasherModel;
 use crate::Result;
 
 /// Color representation
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -64,6 +81,46 @@ pub mod color_palette {
     pub const TRANSPARENT: Color = Color { r: 0, g: 0, b: 0, a: 0 };
 }
 
+/// Font a label is drawn in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FontDescriptor {
+    /// Font family, or `None` for the screen's own font
+    pub family: Option<String>,
+
+    /// Weight, from 100 (thin) to 900 (black) as in CSS
+    pub weight: u16,
+}
+
+impl FontDescriptor {
+    /// Weight of regular text
+    pub const NORMAL_WEIGHT: u16 = 400;
+
+    /// Weight of bold text
+    pub const BOLD_WEIGHT: u16 = 700;
+
+    /// Create a font descriptor
+    pub fn new(family: Option<&str>, weight: u16) -> Self {
+        Self {
+            family: family.map(str::to_string),
+            weight,
+        }
+    }
+
+    /// Get the family as a CSS font family, falling back to sans-serif
+    pub fn css_family(&self) -> String {
+        match &self.family {
+            Some(family) => format!("'{}', sans-serif", family),
+            None => "sans-serif".to_string(),
+        }
+    }
+}
+
+impl Default for FontDescriptor {
+    fn default() -> Self {
+        Self::new(None, Self::NORMAL_WEIGHT)
+    }
+}
+
 /// Interface for text labels
 pub trait Label {
     /// Get the text of the label
@@ -71,6 +128,11 @@ pub trait Label {
 
     /// Get the wrap size of the label
     fn get_wrap_size(&self) -> u32;
+
+    /// Get the font the label is drawn in, if it was created with one
+    fn font(&self) -> Option<&FontDescriptor> {
+        None
+    }
 }
 
 /// Abstract interface for drawing operations, implemented by platform-specific canvases.
@@ -84,6 +146,14 @@ pub trait DasherScreen {
     /// Create a label for text rendering
     fn make_label(&self, text: &str, wrap_size: u32) -> Box<dyn Label>;
 
+    /// Create a label drawn in `font`
+    ///
+    /// Screens that cannot choose fonts can leave this to create the label
+    /// with [`DasherScreen::make_label`], ignoring the font.
+    fn make_font_label(&self, text: &str, wrap_size: u32, _font: &FontDescriptor) -> Box<dyn Label> {
+        self.make_label(text, wrap_size)
+    }
+
     /// Get the size of a label
     fn text_size(&self, label: &dyn Label, font_size: u32) -> (i32, i32);
 
@@ -127,6 +197,11 @@ pub trait DasherScreen {
 
     /// Returns true if point on screen is not obscured by another window
     fn is_point_visible(&self, x: i32, y: i32) -> bool;
+
+    /// Get the screen as `Any`, for screens whose output is read back (such as a command list)
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        None
+    }
 }
 
 /// Orientation of the Dasher view
@@ -145,6 +220,44 @@ pub enum Orientation {
     BottomToTop,
 }
 
+impl Orientation {
+    /// Code of the `Orientation` setting for following the alphabet's orientation
+    pub const ALPHABET_DEFAULT: i64 = -1;
+
+    /// Get the orientation with a code of the `Orientation` setting, from 0 to 3
+    pub fn from_code(code: i64) -> Option<Self> {
+        match code {
+            0 => Some(Orientation::LeftToRight),
+            1 => Some(Orientation::RightToLeft),
+            2 => Some(Orientation::TopToBottom),
+            3 => Some(Orientation::BottomToTop),
+            _ => None,
+        }
+    }
+
+    /// Get the code of the orientation in the `Orientation` setting
+    pub fn code(self) -> i64 {
+        match self {
+            Orientation::LeftToRight => 0,
+            Orientation::RightToLeft => 1,
+            Orientation::TopToBottom => 2,
+            Orientation::BottomToTop => 3,
+        }
+    }
+}
+
+impl From<crate::alphabet::ScreenOrientation> for Orientation {
+    fn from(orientation: crate::alphabet::ScreenOrientation) -> Self {
+        use crate::alphabet::ScreenOrientation;
+        match orientation {
+            ScreenOrientation::LeftToRight => Orientation::LeftToRight,
+            ScreenOrientation::RightToLeft => Orientation::RightToLeft,
+            ScreenOrientation::TopToBottom => Orientation::TopToBottom,
+            ScreenOrientation::BottomToTop => Orientation::BottomToTop,
+        }
+    }
+}
+
 /// The main Dasher view interface
 pub trait DasherView {
     /// Get the dimensions of the view
@@ -173,6 +286,25 @@ pub trait DasherView {
     /// Render the model
     fn render(&mut self, model: &mut DasherModel) -> Result<()>;
 
+    /// Render the model, letting `decorate` draw over it before the frame is displayed
+    ///
+    /// Views that cannot draw over a rendered frame render it undecorated.
+    fn render_decorated(&mut self, model: &mut DasherModel, _decorate: &mut dyn FnMut(&mut dyn DasherView)) -> Result<()> {
+        self.render(model)
+    }
+
+    /// Draw the debug overlay over the frame being rendered
+    ///
+    /// Views that cannot draw text ignore it.
+    fn draw_overlay(&mut self, _info: &DebugInfo) {}
+
+    /// Get the number of drawing calls made on the screen for the last frame rendered
+    ///
+    /// Views that do not count them return 0.
+    fn draw_calls(&self) -> u64 {
+        0
+    }
+
     /// Render a node and its children
     fn render_node(&mut self, node: std::rc::Rc<std::cell::RefCell<crate::model::node::DasherNode>>);
 
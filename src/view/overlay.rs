@@ -0,0 +1,116 @@
+//! Debug overlay
+//!
+//! Figures describing the model's dynamics and predictions, drawn over the
+//! top-left corner of each frame while diagnosing a problem.
+
+use super::{color_palette, Color, DasherScreen};
+use crate::model::DasherModel;
+
+/// What the debug overlay shows for one frame
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DebugInfo {
+    /// Measured frame rate, in frames per second
+    pub fps: f64,
+
+    /// Zoom steps scheduled but not yet taken
+    pub scheduled_steps: usize,
+
+    /// Nodes in the tree
+    pub node_count: usize,
+
+    /// Dasher coordinates (y1, y2) of the root node
+    pub root_bounds: (i64, i64),
+
+    /// Information entered so far, in nats
+    pub total_nats: f64,
+
+    /// End of the text the language model predicts from
+    pub context: String,
+}
+
+impl DebugInfo {
+    /// Number of characters of context shown
+    pub const CONTEXT_CHARS: usize = 24;
+
+    /// Font size of the overlay text
+    const FONT_SIZE: u32 = 12;
+
+    /// Gap around the overlay text, in pixels
+    const PADDING: i32 = 4;
+
+    /// Gather the figures for a frame of `model`, drawn at `fps` frames per second
+    pub fn from_model(model: &DasherModel, fps: f64) -> Self {
+        let text = model.text_buffer().text_before_cursor();
+        let start = text.char_indices().rev().nth(Self::CONTEXT_CHARS - 1).map_or(0, |(i, _)| i);
+        Self {
+            fps,
+            scheduled_steps: model.scheduled_step_count(),
+            node_count: model.node_count(),
+            root_bounds: model.root_bounds(),
+            total_nats: model.total_nats(),
+            context: text[start..].to_string(),
+        }
+    }
+
+    /// Get the lines of text shown
+    pub fn lines(&self) -> Vec<String> {
+        vec![
+            format!("FPS: {:.1}", self.fps),
+            format!("Scheduled steps: {}", self.scheduled_steps),
+            format!("Nodes: {}", self.node_count),
+            format!("Root: [{}, {}]", self.root_bounds.0, self.root_bounds.1),
+            format!("Total nats: {:.2}", self.total_nats),
+            format!("Context: {:?}", self.context),
+        ]
+    }
+
+    /// Draw the overlay over the top-left corner of `screen`
+    pub fn draw(&self, screen: &mut dyn DasherScreen) {
+        let labels: Vec<_> = self.lines().iter().map(|line| screen.make_label(line, 0)).collect();
+        let sizes: Vec<_> = labels.iter().map(|label| screen.text_size(&**label, Self::FONT_SIZE)).collect();
+        let width = sizes.iter().map(|(width, _)| *width).max().unwrap_or(0);
+        let height: i32 = sizes.iter().map(|(_, height)| *height).sum();
+
+        let background = Color::new(255, 255, 255, 220);
+        screen.draw_rectangle(0, 0, width + 2 * Self::PADDING, height + 2 * Self::PADDING, background, color_palette::BLACK, 1);
+        let mut y = Self::PADDING;
+        for (label, (_, line_height)) in labels.iter().zip(&sizes) {
+            screen.draw_string(&**label, Self::PADDING, y, Self::FONT_SIZE, color_palette::BLACK);
+            y += line_height;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::view::{CommandListScreen, DrawCommand};
+
+    #[test]
+    fn test_overlay_shows_model_figures() {
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        model.set_output_text("a long enough context\nto be cut");
+        let info = DebugInfo::from_model(&model, 59.94);
+        assert_eq!(info.context, "enough context\nto be cut");
+        assert_eq!(info.node_count, model.node_count());
+        assert!(info.node_count > 1);
+        assert_eq!(info.root_bounds, (0, DasherModel::MAX_Y));
+
+        let lines = info.lines();
+        assert_eq!(lines[0], "FPS: 59.9");
+        assert_eq!(lines[5], "Context: \"enough context\\nto be cut\"");
+
+        // Each line is drawn over a background in the top-left corner
+        let mut screen = CommandListScreen::new(800, 600);
+        info.draw(&mut screen);
+        screen.display();
+        let frame = screen.take_frame().unwrap();
+        assert!(matches!(frame.commands[0], DrawCommand::Rectangle { x1: 0, y1: 0, .. }));
+        let texts: Vec<_> = frame.commands.iter().filter_map(|command| match command {
+            DrawCommand::Text { text, .. } => Some(text.clone()),
+            _ => None,
+        }).collect();
+        assert_eq!(texts, lines);
+    }
+}
@@ -0,0 +1,288 @@
+//! Raster screen
+//!
+//! A screen that draws into an RGBA buffer, so frames can be saved as PNG
+//! and compared pixel by pixel against golden images in tests. No font is
+//! bundled: text is drawn as a solid block per character, which is enough
+//! to check where labels are laid out but not what they say.
+
+use std::io::Cursor;
+use std::path::Path;
+
+use image::{ImageFormat, Rgba, RgbaImage};
+
+use super::command_list::{estimate_text_size, CommandLabel};
+use super::{Color, DasherScreen, Label};
+use crate::{DasherError, Result};
+
+/// Average width of a character, as a fraction of the font size
+const CHAR_WIDTH: f64 = 0.6;
+
+/// Height of a line of text, as a fraction of the font size
+const LINE_HEIGHT: f64 = 1.2;
+
+/// Screen drawing frames into an RGBA buffer
+#[derive(Debug, Clone)]
+pub struct RasterScreen {
+    /// Buffer being drawn into
+    canvas: RgbaImage,
+
+    /// Last completed frame
+    frame: Option<RgbaImage>,
+}
+
+impl RasterScreen {
+    /// Create a transparent screen of the given size
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            canvas: RgbaImage::new(width, height),
+            frame: None,
+        }
+    }
+
+    /// Get the last completed frame
+    pub fn frame(&self) -> Option<&RgbaImage> {
+        self.frame.as_ref()
+    }
+
+    /// Take the last completed frame
+    pub fn take_frame(&mut self) -> Option<RgbaImage> {
+        self.frame.take()
+    }
+
+    /// Get the color of a pixel of the last completed frame
+    pub fn pixel(&self, x: u32, y: u32) -> Option<Color> {
+        let frame = self.frame.as_ref()?;
+        if x >= frame.width() || y >= frame.height() {
+            return None;
+        }
+        let Rgba([r, g, b, a]) = *frame.get_pixel(x, y);
+        Some(Color::new(r, g, b, a))
+    }
+
+    /// Encode the last completed frame as PNG
+    pub fn png_bytes(&self) -> Result<Vec<u8>> {
+        let frame = self.frame.as_ref().ok_or_else(|| DasherError::RenderingError("No frame has been completed".to_string()))?;
+        let mut bytes = Cursor::new(Vec::new());
+        frame.write_to(&mut bytes, ImageFormat::Png)
+            .map_err(|e| DasherError::RenderingError(format!("Failed to encode frame: {}", e)))?;
+        Ok(bytes.into_inner())
+    }
+
+    /// Save the last completed frame as a PNG file
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path, self.png_bytes()?).map_err(|e| DasherError::Other(format!("Failed to save frame: {}", e)))
+    }
+
+    /// Blend a color over one pixel, ignoring pixels off the screen
+    fn blend(&mut self, x: i32, y: i32, color: Color) {
+        if color.a == 0 || x < 0 || y < 0 || x as u32 >= self.canvas.width() || y as u32 >= self.canvas.height() {
+            return;
+        }
+        let pixel = self.canvas.get_pixel_mut(x as u32, y as u32);
+        let alpha = color.a as u32;
+        let mix = |src: u8, dst: u8| ((src as u32 * alpha + dst as u32 * (255 - alpha)) / 255) as u8;
+        let Rgba([r, g, b, a]) = *pixel;
+        *pixel = Rgba([mix(color.r, r), mix(color.g, g), mix(color.b, b), (alpha + a as u32 * (255 - alpha) / 255) as u8]);
+    }
+
+    /// Fill the pixels from (`x1`, `y1`) up to but not including (`x2`, `y2`)
+    fn fill_rect(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: Color) {
+        let (width, height) = (self.canvas.width() as i32, self.canvas.height() as i32);
+        for y in y1.max(0)..y2.min(height) {
+            for x in x1.max(0)..x2.min(width) {
+                self.blend(x, y, color);
+            }
+        }
+    }
+
+    /// Fill the pixels whose centres are within `half_width` of a line segment
+    fn stroke_segment(&mut self, (x1, y1): (i32, i32), (x2, y2): (i32, i32), half_width: f64, color: Color) {
+        let pad = half_width.ceil() as i32 + 1;
+        let (dx, dy) = ((x2 - x1) as f64, (y2 - y1) as f64);
+        let length_squared = dx * dx + dy * dy;
+        for y in y1.min(y2) - pad..=y1.max(y2) + pad {
+            for x in x1.min(x2) - pad..=x1.max(x2) + pad {
+                let (px, py) = (x as f64 + 0.5 - x1 as f64, y as f64 + 0.5 - y1 as f64);
+                let t = if length_squared > 0.0 { ((px * dx + py * dy) / length_squared).clamp(0.0, 1.0) } else { 0.0 };
+                if (px - t * dx).hypot(py - t * dy) <= half_width {
+                    self.blend(x, y, color);
+                }
+            }
+        }
+    }
+}
+
+/// Count the pixels of two images whose channels differ by more than `tolerance`
+///
+/// Returns `None` if the images are different sizes.
+pub fn count_differing_pixels(expected: &RgbaImage, actual: &RgbaImage, tolerance: u8) -> Option<usize> {
+    if expected.dimensions() != actual.dimensions() {
+        return None;
+    }
+    Some(expected.pixels().zip(actual.pixels())
+        .filter(|(a, b)| a.0.iter().zip(b.0.iter()).any(|(a, b)| a.abs_diff(*b) > tolerance))
+        .count())
+}
+
+impl DasherScreen for RasterScreen {
+    fn get_width(&self) -> i32 {
+        self.canvas.width() as i32
+    }
+
+    fn get_height(&self) -> i32 {
+        self.canvas.height() as i32
+    }
+
+    fn make_label(&self, text: &str, wrap_size: u32) -> Box<dyn Label> {
+        Box::new(CommandLabel { text: text.to_string(), wrap_size, font: None })
+    }
+
+    fn text_size(&self, label: &dyn Label, font_size: u32) -> (i32, i32) {
+        estimate_text_size(label, font_size, CHAR_WIDTH, LINE_HEIGHT)
+    }
+
+    fn draw_string(&mut self, label: &dyn Label, x: i32, y: i32, font_size: u32, color: Color) {
+        // A block for each character, leaving gaps between characters and for spaces
+        let cell = (CHAR_WIDTH * font_size as f64).round().max(1.0) as i32;
+        let height = font_size as i32;
+        for (index, c) in label.get_text().chars().enumerate() {
+            if !c.is_whitespace() {
+                let left = x + index as i32 * cell;
+                self.fill_rect(left, y, left + cell - (cell / 4).max(1), y + height, color);
+            }
+        }
+    }
+
+    fn draw_rectangle(&mut self, x1: i32, y1: i32, x2: i32, y2: i32,
+                     fill_color: Color, outline_color: Color, line_width: i32) {
+        let (x1, x2) = (x1.min(x2), x1.max(x2));
+        let (y1, y2) = (y1.min(y2), y1.max(y2));
+        self.fill_rect(x1, y1, x2, y2, fill_color);
+
+        // The outline lies just inside the rectangle
+        if line_width > 0 && outline_color.a > 0 {
+            let width = line_width.min((x2 - x1 + 1) / 2).min((y2 - y1 + 1) / 2);
+            self.fill_rect(x1, y1, x2, y1 + width, outline_color);
+            self.fill_rect(x1, y2 - width, x2, y2, outline_color);
+            self.fill_rect(x1, y1 + width, x1 + width, y2 - width, outline_color);
+            self.fill_rect(x2 - width, y1 + width, x2, y2 - width, outline_color);
+        }
+    }
+
+    fn draw_circle(&mut self, cx: i32, cy: i32, r: i32,
+                  fill_color: Color, line_color: Color, line_width: i32) {
+        let radius = r.abs() as f64;
+        let inner = radius - line_width.max(0) as f64;
+        for y in cy - r.abs() - 1..=cy + r.abs() + 1 {
+            for x in cx - r.abs() - 1..=cx + r.abs() + 1 {
+                let distance = (x as f64 + 0.5 - cx as f64).hypot(y as f64 + 0.5 - cy as f64);
+                if distance > radius {
+                    continue;
+                }
+                if distance > inner && line_color.a > 0 {
+                    self.blend(x, y, line_color);
+                } else {
+                    self.blend(x, y, fill_color);
+                }
+            }
+        }
+    }
+
+    fn draw_line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: Color, line_width: i32) {
+        self.stroke_segment((x1, y1), (x2, y2), line_width.max(1) as f64 / 2.0, color);
+    }
+
+    fn draw_polygon(&mut self, points: &[(i32, i32)], fill_color: Color, outline_color: Color, line_width: i32) {
+        if points.len() < 3 {
+            return;
+        }
+
+        // Fill each row between pairs of edge crossings at the pixel centres
+        if fill_color.a > 0 {
+            let top = points.iter().map(|&(_, y)| y).min().unwrap_or(0).max(0);
+            let bottom = points.iter().map(|&(_, y)| y).max().unwrap_or(0).min(self.get_height());
+            for y in top..bottom {
+                let centre = y as f64 + 0.5;
+                let mut crossings: Vec<f64> = (0..points.len())
+                    .filter_map(|i| {
+                        let ((x1, y1), (x2, y2)) = (points[i], points[(i + 1) % points.len()]);
+                        let (y1, y2, x1, x2) = (y1 as f64, y2 as f64, x1 as f64, x2 as f64);
+                        ((y1 <= centre) != (y2 <= centre)).then(|| x1 + (centre - y1) * (x2 - x1) / (y2 - y1))
+                    })
+                    .collect();
+                crossings.sort_by(f64::total_cmp);
+                for pair in crossings.chunks_exact(2) {
+                    self.fill_rect((pair[0] - 0.5).ceil() as i32, y, (pair[1] - 0.5).ceil() as i32, y + 1, fill_color);
+                }
+            }
+        }
+
+        if line_width > 0 && outline_color.a > 0 {
+            for i in 0..points.len() {
+                self.stroke_segment(points[i], points[(i + 1) % points.len()], line_width as f64 / 2.0, outline_color);
+            }
+        }
+    }
+
+    fn display(&mut self) {
+        self.frame = Some(self.canvas.clone());
+    }
+
+    fn is_point_visible(&self, _x: i32, _y: i32) -> bool {
+        true
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::DasherModel;
+    use crate::view::{color_palette, DasherView, DasherViewSquare, Orientation, SquareViewConfig};
+
+    #[test]
+    fn test_draws_primitives() {
+        let mut screen = RasterScreen::new(40, 30);
+        screen.draw_rectangle(0, 0, 40, 30, color_palette::WHITE, color_palette::BLACK, 1);
+        screen.draw_circle(20, 15, 5, Color::new(255, 0, 0, 128), color_palette::TRANSPARENT, 1);
+        screen.draw_polygon(&[(30, 2), (38, 2), (38, 10)], color_palette::BLUE, color_palette::TRANSPARENT, 0);
+        assert!(screen.pixel(0, 0).is_none());
+        screen.display();
+
+        assert_eq!(screen.pixel(0, 0), Some(color_palette::BLACK));
+        assert_eq!(screen.pixel(1, 1), Some(color_palette::WHITE));
+        assert_eq!(screen.pixel(20, 15), Some(Color::new(255, 127, 127, 255)));
+        assert_eq!(screen.pixel(37, 3), Some(color_palette::BLUE));
+        assert_eq!(screen.pixel(31, 9), Some(color_palette::WHITE));
+        assert_eq!(screen.pixel(40, 0), None);
+
+        let png = screen.png_bytes().unwrap();
+        let decoded = image::load_from_memory(&png).unwrap().to_rgba8();
+        assert_eq!(count_differing_pixels(screen.frame().unwrap(), &decoded, 0), Some(0));
+    }
+
+    /// Render the model's initial frame without labels or crosshair
+    fn render_layout(orientation: Orientation) -> RgbaImage {
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        let config = SquareViewConfig { draw_crosshair: false, min_label_pixels: i32::MAX, ..SquareViewConfig::default() };
+        let mut view = DasherViewSquare::with_config(Box::new(RasterScreen::new(160, 120)), config);
+        view.set_orientation(orientation);
+        view.render(&mut model).unwrap();
+        view.screen_mut().as_any_mut().unwrap().downcast_mut::<RasterScreen>().unwrap().take_frame().unwrap()
+    }
+
+    #[test]
+    fn test_opposite_orientations_mirror_each_other() {
+        let left_to_right = render_layout(Orientation::LeftToRight);
+        let right_to_left = render_layout(Orientation::RightToLeft);
+        assert!(count_differing_pixels(&left_to_right, &right_to_left, 0).unwrap() > 500);
+
+        // One is exactly the other flipped
+        let flipped = image::imageops::flip_horizontal(&right_to_left);
+        assert_eq!(count_differing_pixels(&left_to_right, &flipped, 0), Some(0));
+    }
+}
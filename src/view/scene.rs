@@ -0,0 +1,42 @@
+//! Frame scenes
+//!
+//! A structured description of a frame: the nodes with their rectangles,
+//! labels and colors, and where the crosshair and cursor are. Unlike a
+//! command list it says what is on the screen rather than how to draw it,
+//! so a web or remote user interface can draw it with its own widgets.
+
+use serde::{Deserialize, Serialize};
+
+use super::VisibleNode;
+use crate::{DasherError, Result};
+
+/// What is on the screen in one frame
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Scene {
+    /// Width of the screen the scene was laid out for
+    pub width: i32,
+
+    /// Height of the screen the scene was laid out for
+    pub height: i32,
+
+    /// Nodes, parents before their children
+    pub nodes: Vec<VisibleNode>,
+
+    /// Screen position of the crosshair, if it is drawn
+    pub crosshair: Option<(i32, i32)>,
+
+    /// Screen position of the pointer's cursor, if it is drawn
+    pub cursor: Option<(i32, i32)>,
+}
+
+impl Scene {
+    /// Serialize the scene to JSON
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(self).map_err(|e| DasherError::Other(format!("Failed to serialize scene: {}", e)))
+    }
+
+    /// Deserialize a scene from JSON
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| DasherError::InvalidParameter(format!("Invalid scene: {}", e)))
+    }
+}
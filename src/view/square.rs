@@ -1,18 +1,24 @@
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc::Rc;
 
 use crate::model::DasherModel;
-use crate::model::node::DasherNode;
+use crate::model::node::{DasherNode, NodeFlags};
+use crate::alphabet::GroupAppearance;
 use crate::DasherInput;
 use crate::Result;
-use crate::view::{DasherView, DasherScreen, Orientation, Color, Label};
+use crate::view::{DasherView, DasherScreen, DebugInfo, Orientation, Color, FontDescriptor, Label, Scene};
+use serde::{Deserialize, Serialize};
 use crate::view::color_palette;
 use crate::ffi::context;
 
 /// Text string for delayed rendering
 struct TextString {
     /// The label to render
-    label: Box<dyn Label>,
+    label: Rc<dyn Label>,
+
+    /// Width and height of the label on the screen
+    extent: (i32, i32),
 
     /// X coordinate
     x: i32,
@@ -32,9 +38,10 @@ struct TextString {
 
 impl TextString {
     /// Create a new text string
-    fn new(label: Box<dyn Label>, x: i32, y: i32, size: u32, color: Color) -> Self {
+    fn new((label, extent): MeasuredLabel, x: i32, y: i32, size: u32, color: Color) -> Self {
         Self {
             label,
+            extent,
             x,
             y,
             size,
@@ -44,8 +51,98 @@ impl TextString {
     }
 }
 
-/// Constants for the Square View
-const SCALE_FACTOR: i64 = 1 << 26; // Large power of 2 for efficient division
+/// A label with its width and height on the screen
+type MeasuredLabel = (Rc<dyn Label>, (i32, i32));
+
+/// Labels made and measured in earlier frames, kept while the screen keeps its size
+#[derive(Default)]
+struct LabelCache {
+    /// Width and height of the screen the labels were measured on
+    screen_size: (i32, i32),
+
+    /// Labels and their sizes, by font size and wrap size and then text
+    labels: HashMap<(u32, u32), HashMap<String, MeasuredLabel>>,
+
+    /// Number of labels held
+    count: usize,
+}
+
+impl LabelCache {
+    /// Most labels held before the cache starts again, as zooming brings in new text
+    const MAX_LABELS: usize = 4096;
+
+    /// Forget every label
+    fn clear(&mut self) {
+        self.labels.clear();
+        self.count = 0;
+    }
+}
+
+/// Marks a label cut short to fit its node
+const ELLIPSIS: &str = "\u{2026}";
+
+/// Break a label's text into lines no wider than its wrap size, at most `max_lines` of them
+///
+/// Lines break between words where possible, and within a word too long
+/// for a line. If the text needs more lines, the last one is cut short with
+/// an ellipsis. Returns no lines if not even an ellipsis fits. A wrap size
+/// of 0 leaves the text on one line.
+pub(crate) fn fit_label_lines(label: &dyn Label, max_lines: usize, measure: impl Fn(&str) -> i32) -> Vec<String> {
+    let text = label.get_text();
+    if max_lines == 0 {
+        return Vec::new();
+    }
+    let max_width = label.get_wrap_size() as i32;
+    if max_width == 0 || measure(text) <= max_width {
+        return vec![text.to_string()];
+    }
+
+    // Fill each line with as many words, or characters of a long word, as fit
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let joined = if line.is_empty() { word.to_string() } else { format!("{} {}", line, word) };
+        if measure(&joined) <= max_width {
+            line = joined;
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        for c in word.chars() {
+            line.push(c);
+            if measure(&line) > max_width && line.chars().count() > 1 {
+                line.pop();
+                lines.push(std::mem::replace(&mut line, c.to_string()));
+            }
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    // Cut the last line that fits short if there is more text after it
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        if let Some(last) = lines.last_mut() {
+            last.push_str(ELLIPSIS);
+        }
+    }
+    for line in &mut lines {
+        while measure(line) > max_width && line.chars().count() > 1 {
+            let ellipsis = line.ends_with(ELLIPSIS);
+            if ellipsis {
+                line.truncate(line.len() - ELLIPSIS.len());
+            }
+            line.pop();
+            line.push_str(ELLIPSIS);
+        }
+    }
+    if lines.iter().any(|line| measure(line) > max_width) {
+        return Vec::new();
+    }
+    lines
+}
 
 /// Configuration for the Square View
 #[derive(Debug, Clone)]
@@ -62,13 +159,13 @@ pub struct SquareViewConfig {
     /// Enable Y nonlinearity
     pub y_nonlinear: bool,
 
-    /// Y1 parameter for Y nonlinearity (lower bound of first region)
+    /// Y1 parameter for Y nonlinearity (compression factor beyond Y2 and Y3)
     pub y1: i64,
 
-    /// Y2 parameter for Y nonlinearity (upper bound of second region)
+    /// Y2 parameter for Y nonlinearity (Y above which coordinates are compressed)
     pub y2: i64,
 
-    /// Y3 parameter for Y nonlinearity (boundary between first and second regions)
+    /// Y3 parameter for Y nonlinearity (Y below which coordinates are compressed)
     pub y3: i64,
 
     /// Enable 3D text rendering
@@ -83,7 +180,10 @@ pub struct SquareViewConfig {
     /// Font size scaling factor
     pub font_size_scaling: f64,
 
-    /// Margin width in abstract screen coordinates
+    /// Font family and weight of node labels
+    pub font: FontDescriptor,
+
+    /// Width of the blank margin at the edge the nodes are anchored to, in Dasher X units
     pub margin_width: i64,
 
     /// Whether to draw the crosshair
@@ -92,9 +192,28 @@ pub struct SquareViewConfig {
     /// Whether to draw the cursor
     pub draw_cursor: bool,
 
+    /// Whether to draw a line from the crosshair to the pointer
+    pub draw_mouse_line: bool,
+
+    /// Colour of the line to the pointer
+    pub mouse_line_color: Color,
+
+    /// Width of the line to the pointer, in pixels
+    pub mouse_line_width: i32,
+
     /// Whether to draw node outlines
     pub draw_outlines: bool,
 
+    /// Nodes smaller than this (in pixels, in either direction) are not labelled
+    pub min_label_pixels: i32,
+
+    /// Nodes less high than this (in pixels across the direction of flow)
+    /// are not drawn, nor are their descendants
+    pub min_node_pixels: i32,
+
+    /// Shape and colors overriding the defaults for nodes of each group
+    pub group_styles: HashMap<NodeGroup, GroupStyle>,
+
     /// Whether to use the flowing interface (right to left movement)
     pub flowing_interface: bool,
 
@@ -112,17 +231,24 @@ impl Default for SquareViewConfig {
             x_nonlinear: true,
             x_nonlinear_factor: 4.8, // Default from C++ implementation
             y_nonlinear: true,
-            y1: 4, // Lower bound of first region
-            y2: (0.95 * DasherModel::MAX_Y as f64) as i64, // Upper bound of second region
-            y3: (0.05 * DasherModel::MAX_Y as f64) as i64, // Boundary between first and second regions
+            y1: 4, // Compression factor
+            y2: (0.95 * DasherModel::MAX_Y as f64) as i64, // Upper bound of the linear region
+            y3: (0.05 * DasherModel::MAX_Y as f64) as i64, // Lower bound of the linear region
             text_3d: true,
             text_3d_depth: 2,
             base_font_size: 24,
             font_size_scaling: 0.5,
-            margin_width: SCALE_FACTOR / 10, // 10% of screen width
+            font: FontDescriptor::default(),
+            margin_width: DasherModel::MAX_X / 10, // 10% of the Dasher width
             draw_crosshair: true,
             draw_cursor: true,
+            draw_mouse_line: false,
+            mouse_line_color: color_palette::BLUE,
+            mouse_line_width: 2,
             draw_outlines: true,
+            min_label_pixels: 8,
+            min_node_pixels: 2,
+            group_styles: HashMap::new(),
             flowing_interface: true, // Enable flowing interface by default
             flowing_speed: 2.0, // Default speed
             use_ppm: true, // Enable PPM by default
@@ -130,8 +256,77 @@ impl Default for SquareViewConfig {
     }
 }
 
+/// Categories of nodes that can be drawn differently from each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeGroup {
+    /// Letters of any script
+    Letter,
+    /// Digits
+    Digit,
+    /// Punctuation and other symbols
+    Punctuation,
+    /// Control nodes, which perform actions instead of writing text
+    Control,
+}
+
+impl NodeGroup {
+    /// Get the group of a node, if it belongs to one
+    ///
+    /// Whitespace and nodes without a symbol, such as the root, belong to no group.
+    pub fn of(node: &DasherNode) -> Option<Self> {
+        if node.get_flag(NodeFlags::CONTROL) || node.action().is_some() {
+            return Some(Self::Control);
+        }
+        match node.symbol()? {
+            c if c.is_alphabetic() => Some(Self::Letter),
+            c if c.is_numeric() => Some(Self::Digit),
+            c if c.is_whitespace() || c.is_control() => None,
+            _ => Some(Self::Punctuation),
+        }
+    }
+
+    /// Get a group from its name in a color scheme file
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "letter" | "letters" => Some(Self::Letter),
+            "digit" | "digits" | "numbers" => Some(Self::Digit),
+            "punctuation" => Some(Self::Punctuation),
+            "control" => Some(Self::Control),
+            _ => None,
+        }
+    }
+}
+
+/// How nodes of a group are drawn; unset fields keep the view's defaults
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct GroupStyle {
+    /// Shape of the nodes
+    pub shape: Option<NodeShape>,
+    /// Color of the nodes
+    pub background: Option<Color>,
+    /// Color of the labels
+    pub foreground: Option<Color>,
+}
+
+/// A node as the square view draws it, for frontends that draw the scene themselves
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VisibleNode {
+    /// Screen rectangle (x1, y1, x2, y2) the node's box covers
+    pub rect: (i32, i32, i32, i32),
+    /// Text the node is labelled with, if it is large enough to be labelled
+    pub label: Option<String>,
+    /// Color of the label
+    pub foreground: Color,
+    /// Color of the node
+    pub background: Color,
+    /// Shape of the node
+    pub shape: NodeShape,
+    /// Number of generations below the root, which is at depth 0
+    pub depth: usize,
+}
+
 /// Node shape types
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum NodeShape {
     /// Rectangle shape
     Rectangle,
@@ -145,6 +340,20 @@ pub enum NodeShape {
     Quadric,
 }
 
+impl NodeShape {
+    /// Get a shape from its name, such as `truncated-triangle`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "rectangle" => Some(Self::Rectangle),
+            "triangle" => Some(Self::Triangle),
+            "truncated-triangle" | "truncatedtriangle" => Some(Self::TruncatedTriangle),
+            "circle" => Some(Self::Circle),
+            "quadric" => Some(Self::Quadric),
+            _ => None,
+        }
+    }
+}
+
 /// Square Dasher view implementation
 pub struct DasherViewSquare {
     /// Screen for rendering
@@ -156,32 +365,30 @@ pub struct DasherViewSquare {
     /// Input device
     input_device: Option<Box<dyn DasherInput>>,
 
-    /// Scale factor for X coordinate
-    scale_factor_x: i64,
-
-    /// Scale factor for Y coordinate
-    scale_factor_y: i64,
-
-    /// Margin width in abstract screen coordinates
+    /// Margin width in Dasher X units
     margin_width: i64,
 
-    /// Coefficient for X logarithmic mapping
+    /// Coefficient for X logarithmic mapping (0 for a linear mapping)
     x_log_coeff: f64,
 
     /// Threshold for X logarithmic mapping
     x_log_threshold: i64,
 
-    /// Cached visible region
-    visible_region: Option<(i64, i64, i64, i64)>,
-
     /// Delayed text objects for rendering
     delayed_texts: Vec<TextString>,
 
-    /// Y3 screen parameter (screen coordinate corresponding to Y3)
-    y3_screen: i64,
+    /// Coordinates (y1, y2) at which the root node is drawn
+    root_bounds: (i64, i64),
 
     /// Configuration for the view
     config: SquareViewConfig,
+
+    /// Labels reused from frame to frame, as making and measuring them can
+    /// be slow on some screens
+    label_cache: RefCell<LabelCache>,
+
+    /// Drawing calls made on the screen in the frame being rendered
+    draw_calls: u64,
 }
 
 impl DasherViewSquare {
@@ -196,6 +403,11 @@ impl DasherViewSquare {
         &self.screen
     }
 
+    /// Get the screen being drawn on
+    pub fn screen_mut(&mut self) -> &mut dyn DasherScreen {
+        &mut *self.screen
+    }
+
     /// Access the x_map method for testing
     #[cfg(test)]
     pub(crate) fn x_map(&self, dasher_x: i64) -> i64 {
@@ -216,15 +428,14 @@ impl DasherViewSquare {
             screen,
             orientation: Orientation::LeftToRight,
             input_device: None,
-            scale_factor_x: 0,
-            scale_factor_y: 0,
             margin_width: 0,
             x_log_coeff: 0.0,
             x_log_threshold: 0,
-            visible_region: None,
             delayed_texts: Vec::new(),
-            y3_screen: 0, // Will be calculated in set_scale_factor
+            root_bounds: (0, DasherModel::MAX_Y),
             config,
+            label_cache: RefCell::new(LabelCache::default()),
+            draw_calls: 0,
         };
 
         // Initialize scale factors
@@ -248,6 +459,28 @@ impl DasherViewSquare {
         self.config.node_shape = shape;
     }
 
+    /// Set how nodes of a group are drawn
+    pub fn set_group_style(&mut self, group: NodeGroup, style: GroupStyle) {
+        self.config.group_styles.insert(group, style);
+    }
+
+    /// Take the group styles from a color scheme, replacing any set before
+    ///
+    /// Groups and shapes with names the view doesn't know are ignored.
+    pub fn apply_group_appearances(&mut self, groups: &[GroupAppearance]) {
+        let to_color = |color: crate::alphabet::Color| Color::from_tuple((color.r, color.g, color.b, color.a));
+        self.config.group_styles = groups.iter()
+            .filter_map(|appearance| {
+                let group = NodeGroup::from_name(&appearance.name)?;
+                Some((group, GroupStyle {
+                    shape: appearance.shape.as_deref().and_then(NodeShape::from_name),
+                    background: appearance.background.map(to_color),
+                    foreground: appearance.foreground.map(to_color),
+                }))
+            })
+            .collect();
+    }
+
     /// Enable or disable X nonlinearity
     pub fn set_x_nonlinear(&mut self, enable: bool) {
         self.config.x_nonlinear = enable;
@@ -266,6 +499,52 @@ impl DasherViewSquare {
         self._set_scale_factor(); // Recalculate scale factors
     }
 
+    /// Set the Y nonlinearity factor (how much Y is compressed at the edges)
+    pub fn set_y_nonlinear_factor(&mut self, factor: i64) {
+        self.config.y1 = factor;
+    }
+
+    /// Set how much fonts shrink for larger nodes
+    pub fn set_font_size_scaling(&mut self, scaling: f64) {
+        self.config.font_size_scaling = scaling;
+    }
+
+    /// Set the font family and weight of node labels
+    pub fn set_font(&mut self, font: FontDescriptor) {
+        if font != self.config.font {
+            self.config.font = font;
+            self.clear_label_cache();
+        }
+    }
+
+    /// Forget the labels kept from earlier frames
+    ///
+    /// The cache is cleared when the screen changes size or the font
+    /// changes; a host changing how its screen draws text in some other way
+    /// should clear it too.
+    pub fn clear_label_cache(&mut self) {
+        self.label_cache.get_mut().clear();
+    }
+
+    /// Get a label for `text` in `font_size`, wrapped at `wrap_size` pixels, with its width and height
+    ///
+    /// Each label is made and measured once, then reused until the cache is cleared.
+    fn cached_label(&self, text: &str, font_size: u32, wrap_size: u32) -> MeasuredLabel {
+        let mut cache = self.label_cache.borrow_mut();
+        if let Some((label, extent)) = cache.labels.get(&(font_size, wrap_size)).and_then(|labels| labels.get(text)) {
+            return (label.clone(), *extent);
+        }
+
+        let label: Rc<dyn Label> = Rc::from(self.screen.make_font_label(text, wrap_size, &self.config.font));
+        let extent = self.screen.text_size(&*label, font_size);
+        if cache.count >= LabelCache::MAX_LABELS {
+            cache.clear();
+        }
+        cache.labels.entry((font_size, wrap_size)).or_default().insert(text.to_string(), (label.clone(), extent));
+        cache.count += 1;
+        (label, extent)
+    }
+
     /// Enable or disable 3D text rendering
     pub fn set_text_3d(&mut self, enable: bool) {
         self.config.text_3d = enable;
@@ -301,25 +580,50 @@ impl DasherViewSquare {
         self.config.draw_cursor = enable;
     }
 
+    /// Enable or disable drawing a line from the crosshair to the pointer
+    pub fn set_draw_mouse_line(&mut self, enable: bool) {
+        self.config.draw_mouse_line = enable;
+    }
+
+    /// Set the colour and width (in pixels) of the line to the pointer
+    pub fn set_mouse_line_style(&mut self, color: Color, width: i32) {
+        self.config.mouse_line_color = color;
+        self.config.mouse_line_width = width;
+    }
+
     /// Enable or disable drawing node outlines
     pub fn set_draw_outlines(&mut self, enable: bool) {
         self.config.draw_outlines = enable;
     }
 
+    /// Set the height in pixels below which nodes and their descendants are not drawn
+    pub fn set_min_node_pixels(&mut self, pixels: i32) {
+        self.config.min_node_pixels = pixels;
+    }
+
     /// Set the margin width
     pub fn set_margin_width(&mut self, width: i64) {
         self.config.margin_width = width;
         self._set_scale_factor(); // Recalculate scale factors
     }
 
+    /// Get the screen to draw on, counting the drawing call
+    fn counted_screen(&mut self) -> &mut dyn DasherScreen {
+        self.draw_calls += 1;
+        &mut *self.screen
+    }
+
     /// Process delayed text rendering
     fn do_delayed_text(&mut self, text: &mut TextString) {
-        // Get text dimensions
-        let (_text_width, text_height) = self.screen.text_size(&*text.label, text.size);
-
-        // Calculate text position
-        let text_x = text.x;
-        let text_y = text.y - text_height / 2;
+        let (text_width, text_height) = text.extent;
+
+        // The text is anchored at the far edge of its node; place it inside the node
+        let (text_x, text_y) = match self.orientation {
+            Orientation::LeftToRight => (text.x, text.y - text_height / 2),
+            Orientation::RightToLeft => (text.x - text_width, text.y - text_height / 2),
+            Orientation::TopToBottom => (text.x - text_width / 2, text.y),
+            Orientation::BottomToTop => (text.x - text_width / 2, text.y - text_height),
+        };
 
         // Check if 3D text rendering is enabled
         if self.config.text_3d {
@@ -336,14 +640,14 @@ impl DasherViewSquare {
 
             // Draw shadow layers
             for i in 1..=depth {
-                self.screen.draw_string(&*text.label, text_x + i, text_y + i, text.size, shadow_color);
+                self.counted_screen().draw_string(&*text.label, text_x + i, text_y + i, text.size, shadow_color);
             }
 
             // Draw the main text on top
-            self.screen.draw_string(&*text.label, text_x, text_y, text.size, text.color);
+            self.counted_screen().draw_string(&*text.label, text_x, text_y, text.size, text.color);
         } else {
             // Draw normal text
-            self.screen.draw_string(&*text.label, text_x, text_y, text.size, text.color);
+            self.counted_screen().draw_string(&*text.label, text_x, text_y, text.size, text.color);
         }
 
         // Process children
@@ -354,63 +658,38 @@ impl DasherViewSquare {
 
     /// Set the scale factor based on screen dimensions
     fn _set_scale_factor(&mut self) {
-        let (width, height) = self.get_dimensions();
-
-        // Set scale factors based on orientation
-        match self.orientation {
-            Orientation::LeftToRight | Orientation::RightToLeft => {
-                self.scale_factor_x = SCALE_FACTOR / width as i64;
-                self.scale_factor_y = SCALE_FACTOR / height as i64;
-            }
-            Orientation::TopToBottom | Orientation::BottomToTop => {
-                self.scale_factor_x = SCALE_FACTOR / height as i64;
-                self.scale_factor_y = SCALE_FACTOR / width as i64;
-            }
-        }
-
-        // Set margin width (10% of screen width)
-        self.margin_width = SCALE_FACTOR / 10;
+        self.margin_width = self.config.margin_width.max(0);
 
-        // Set X logarithmic mapping parameters
+        // Set X logarithmic mapping parameters; beyond the crosshair, X is
+        // compressed so that more of the zoomed-out tree fits on screen
         if self.config.x_nonlinear {
             self.x_log_coeff = f64::exp(self.config.x_nonlinear_factor / 3.0);
-            self.x_log_threshold = DasherModel::MAX_Y / 2;
+            self.x_log_threshold = DasherModel::ORIGIN_X;
         } else {
-            // Disable X nonlinearity
-            self.x_log_coeff = 1.0;
-            self.x_log_threshold = DasherModel::MAX_Y;
-        }
-
-        // Calculate Y3 screen parameter for Y nonlinearity
-        // This is the screen coordinate corresponding to Y3
-        // In the C++ implementation, this is calculated based on the screen height
-        if self.config.y_nonlinear {
-            // Calculate Y3 screen as a fraction of the distance between Y1 and Y2
-            let y_range = self.config.y2 - self.config.y1;
-            let y3_fraction = (self.config.y3 - self.config.y1) as f64 / y_range as f64;
-
-            // Apply a nonlinear transformation to make the first region steeper
-            // and the second region shallower
-            let transformed_fraction = y3_fraction * 0.7; // Adjust this factor to control nonlinearity
-
-            // Calculate the screen coordinate
-            self.y3_screen = self.config.y1 + (y_range as f64 * transformed_fraction) as i64;
-        } else {
-            // Disable Y nonlinearity
-            self.y3_screen = self.config.y3;
+            self.x_log_coeff = 0.0;
+            self.x_log_threshold = DasherModel::MAX_X;
         }
+    }
 
-        // Invalidate cached visible region
-        self.visible_region = None;
+    /// Get the lengths of the screen along and across the direction of flow
+    fn axis_lengths(&self) -> (i64, i64) {
+        let (width, height) = self.get_dimensions();
+        let (along, across) = match self.orientation {
+            Orientation::LeftToRight | Orientation::RightToLeft => (width, height),
+            Orientation::TopToBottom | Orientation::BottomToTop => (height, width),
+        };
+        (along.max(1) as i64, across.max(1) as i64)
     }
 
-    /// Draw text with the specified parameters
-    fn dasher_draw_text(&mut self, max_x: i64, mid_y: i64, label: &str, color: Color) -> TextString {
+    /// Draw text with the specified parameters, fitting it into a box of `box_size` pixels
+    ///
+    /// A label too wide for the box is wrapped onto as many lines as the box
+    /// has room for, and cut short with an ellipsis beyond that. Returns
+    /// `None` if not even an ellipsis fits.
+    fn dasher_draw_text(&mut self, max_x: i64, mid_y: i64, label: &str, color: Color, box_size: (i32, i32)) -> Option<TextString> {
         // Convert Dasher coordinates to screen coordinates
         let (screen_x, screen_y) = self.dasher_to_screen(max_x, mid_y);
-
-        // Create label object
-        let label_obj = self.screen.make_label(label, 0);
+        let (box_width, box_height) = box_size;
 
         // Calculate font size based on position
         // In C++, font size is scaled based on the distance from the origin
@@ -420,8 +699,40 @@ impl DasherViewSquare {
         let scaling_factor = self.config.font_size_scaling;
         let font_size = (base_font_size * (1.0 - distance_factor * scaling_factor).max(0.5)) as u32;
 
-        // Create text string
-        TextString::new(label_obj, screen_x, screen_y, font_size, color)
+        // Draw the label as it is if it fits
+        let whole = self.cached_label(label, font_size, 0);
+        let (text_width, text_height) = whole.1;
+        if text_width <= box_width && text_height <= box_height {
+            return Some(TextString::new(whole, screen_x, screen_y, font_size, color));
+        }
+        if box_width <= 0 {
+            return None;
+        }
+
+        // Otherwise wrap it at the box width, onto as many lines as fit across the box
+        let (wrapped, _) = self.cached_label(label, font_size, box_width as u32);
+        let (_, (_, line_height)) = self.cached_label("M", font_size, 0);
+        let line_height = line_height.max(1);
+        let max_lines = (box_height / line_height).max(0) as usize;
+        let lines = fit_label_lines(&*wrapped, max_lines, |text| {
+            let (_, (width, _)) = self.cached_label(text, font_size, 0);
+            width
+        });
+
+        // Stack the lines about the anchor as the orientation places a single line
+        let count = lines.len() as i32;
+        let mut texts = lines.into_iter().enumerate().map(|(index, line)| {
+            let index = index as i32;
+            let y = match self.orientation {
+                Orientation::LeftToRight | Orientation::RightToLeft => screen_y + index * line_height - (count - 1) * line_height / 2,
+                Orientation::TopToBottom => screen_y + index * line_height,
+                Orientation::BottomToTop => screen_y - (count - 1 - index) * line_height,
+            };
+            TextString::new(self.cached_label(&line, font_size, 0), screen_x, y, font_size, color)
+        });
+        let mut first = texts.next()?;
+        first.children = texts.collect();
+        Some(first)
     }
 
     /// Add text to be rendered later
@@ -431,98 +742,54 @@ impl DasherViewSquare {
 
     /// Map Dasher Y coordinate to screen Y coordinate
     fn y_map(&self, dasher_y: i64) -> i64 {
-        // Check if Y nonlinearity is enabled
+        // Outside the Y3-Y2 range, coordinates are compressed by a factor of Y1
         if self.config.y_nonlinear {
-            // Apply nonlinear mapping based on the C++ implementation
-            // The C++ implementation uses three regions:
-            // 1. Y1 to Y3: Linear mapping with a steeper gradient
-            // 2. Y3 to Y2: Linear mapping with a shallower gradient
-            // 3. Outside these regions: Linear mapping with the original gradient
-
-            let y1 = self.config.y1;
-            let y2 = self.config.y2;
-            let y3 = self.config.y3;
-
-            if dasher_y > y1 && dasher_y < y3 {
-                // Region 1: Steeper gradient
-                let gradient = (y3 - y1) as f64 / (self.y3_screen - y1) as f64;
-                let mapped = y1 + ((dasher_y - y1) as f64 / gradient) as i64;
-                return mapped;
-            } else if dasher_y >= y3 && dasher_y < y2 {
-                // Region 2: Shallower gradient
-                let gradient = (y2 - y3) as f64 / (y2 - self.y3_screen) as f64;
-                let mapped = self.y3_screen + ((dasher_y - y3) as f64 / gradient) as i64;
-                return mapped;
+            let factor = self.config.y1.max(1);
+            if dasher_y > self.config.y2 {
+                return self.config.y2 + (dasher_y - self.config.y2) / factor;
+            } else if dasher_y < self.config.y3 {
+                return self.config.y3 + (dasher_y - self.config.y3) / factor;
             }
         }
 
-        // Linear mapping for regions outside the nonlinear range or if nonlinearity is disabled
         dasher_y
     }
 
     /// Map Dasher X coordinate to screen X coordinate
     fn _x_map(&self, dasher_x: i64) -> i64 {
-        // Apply margin
-        let x = dasher_x - self.margin_width;
-
-        // Apply logarithmic mapping if enabled
-        if self.x_log_coeff > 1.0 && x >= self.x_log_threshold {
-            // Calculate the logarithmic part
-            let dx = (x - self.x_log_threshold) as f64 / DasherModel::MAX_Y as f64;
-            let dx = ((dx * self.x_log_coeff).exp() - 1.0) / self.x_log_coeff;
-
-            // Combine linear and logarithmic parts
-            let result = (dx * DasherModel::MAX_Y as f64) as i64 + self.x_log_threshold;
-            return result;
+        // Beyond the threshold, coordinates are compressed logarithmically
+        if self.x_log_coeff > 0.0 && dasher_x > self.x_log_threshold {
+            let dx = (dasher_x - self.x_log_threshold) as f64 / DasherModel::MAX_X as f64;
+            let dx = (dx * self.x_log_coeff).ln_1p() / self.x_log_coeff;
+            return self.x_log_threshold + (dx * DasherModel::MAX_X as f64) as i64;
         }
 
-        // Linear mapping for values below threshold
-        x
+        dasher_x
     }
 
     /// Inverse Y mapping
     fn iy_map(&self, screen_y: i64) -> i64 {
-        // Check if Y nonlinearity is enabled
         if self.config.y_nonlinear {
-            // Apply inverse nonlinear mapping
-            let y1 = self.config.y1;
-            let y2 = self.config.y2;
-            let y3 = self.config.y3;
-            let y3_screen = self.y3_screen;
-
-            if screen_y > y1 && screen_y < y3_screen {
-                // Region 1: Steeper gradient
-                let gradient = (y3 - y1) as f64 / (y3_screen - y1) as f64;
-                let mapped = y1 + ((screen_y - y1) as f64 * gradient) as i64;
-                return mapped;
-            } else if screen_y >= y3_screen && screen_y < y2 {
-                // Region 2: Shallower gradient
-                let gradient = (y2 - y3) as f64 / (y2 - y3_screen) as f64;
-                let mapped = y3 + ((screen_y - y3_screen) as f64 * gradient) as i64;
-                return mapped;
+            let factor = self.config.y1.max(1);
+            if screen_y > self.config.y2 {
+                return self.config.y2 + (screen_y - self.config.y2) * factor;
+            } else if screen_y < self.config.y3 {
+                return self.config.y3 + (screen_y - self.config.y3) * factor;
             }
         }
 
-        // Linear mapping for regions outside the nonlinear range or if nonlinearity is disabled
         screen_y
     }
 
     /// Inverse X mapping
     fn ix_map(&self, screen_x: i64) -> i64 {
-        // Apply logarithmic mapping if enabled
-        if self.x_log_coeff > 1.0 && screen_x >= self.x_log_threshold {
-            // Calculate the logarithmic part
-            let dx = ((screen_x - self.x_log_threshold) as f64 * self.x_log_coeff / DasherModel::MAX_Y as f64 + 1.0).ln() / self.x_log_coeff;
-
-            // Combine linear and logarithmic parts
-            let result = (dx * DasherModel::MAX_Y as f64) as i64 + self.x_log_threshold;
-
-            // Apply margin
-            return result + self.margin_width;
+        if self.x_log_coeff > 0.0 && screen_x > self.x_log_threshold {
+            let dx = (screen_x - self.x_log_threshold) as f64 / DasherModel::MAX_X as f64;
+            let dx = (dx * self.x_log_coeff).exp_m1() / self.x_log_coeff;
+            return self.x_log_threshold + (dx * DasherModel::MAX_X as f64) as i64;
         }
 
-        // Linear mapping for values below threshold
-        screen_x + self.margin_width
+        screen_x
     }
 
     /// Draw a triangle node
@@ -543,7 +810,7 @@ impl DasherViewSquare {
         ];
 
         // Draw filled triangle
-        self.screen.draw_polygon(&points, fill_color, outline_color, line_width);
+        self.counted_screen().draw_polygon(&points, fill_color, outline_color, line_width);
     }
 
     /// Draw a truncated triangle node
@@ -567,7 +834,7 @@ impl DasherViewSquare {
         ];
 
         // Draw filled polygon
-        self.screen.draw_polygon(&points, fill_color, outline_color, line_width);
+        self.counted_screen().draw_polygon(&points, fill_color, outline_color, line_width);
     }
 
     /// Draw a quadric node (curved shape)
@@ -635,7 +902,7 @@ impl DasherViewSquare {
         }
 
         // Draw the polygon
-        self.screen.draw_polygon(&points, fill_color, outline_color, line_width);
+        self.counted_screen().draw_polygon(&points, fill_color, outline_color, line_width);
     }
 
     /// Draw a circle node
@@ -647,22 +914,24 @@ impl DasherViewSquare {
         // Convert to screen coordinates
         let (cx, cy) = self.dasher_to_screen(radius, center_y);
 
-        // Calculate screen radius
-        let (width, _) = self.get_dimensions();
-        let screen_radius = (radius as f64 / DasherModel::MAX_Y as f64 * width as f64) as i32;
+        // Calculate screen radius from the distance to the anchored edge
+        let (ex, ey) = self.dasher_to_screen(0, center_y);
+        let screen_radius = (cx - ex).abs().max((cy - ey).abs());
 
         // Draw the circle
-        self.screen.draw_circle(cx, cy, screen_radius, fill_color, outline_color, line_width);
+        self.counted_screen().draw_circle(cx, cy, screen_radius, fill_color, outline_color, line_width);
     }
 
-    /// Draw a node with the current shape
-    fn draw_node_shape(&mut self, range: i64, y1: i64, y2: i64, fill_color: Color, outline_color: Color, line_width: i32) {
-        match self.config.node_shape {
+    /// Draw a node with the given shape and a one-pixel outline
+    fn draw_node_shape(&mut self, shape: NodeShape, range: i64, y1: i64, y2: i64, fill_color: Color, outline_color: Color) {
+        let line_width = 1;
+        match shape {
             NodeShape::Rectangle => {
                 // Draw a rectangle
                 let (sx1, sy1) = self.dasher_to_screen(0, y1);
                 let (sx2, sy2) = self.dasher_to_screen(range, y2);
-                self.screen.draw_rectangle(sx1, sy1, sx2, sy2, fill_color, outline_color, line_width);
+                self.counted_screen().draw_rectangle(sx1.min(sx2), sy1.min(sy2), sx1.max(sx2), sy1.max(sy2),
+                                          fill_color, outline_color, line_width);
             }
             NodeShape::Triangle => {
                 // Draw a triangle
@@ -683,20 +952,146 @@ impl DasherViewSquare {
         }
     }
 
-    /// Draw a crosshair at the center of the screen
+    /// Draw a crosshair at the origin
     fn crosshair(&mut self) {
-        let (width, height) = self.get_dimensions();
-        let cx = width / 2;
-        let cy = height / 2;
+        let (cx, cy) = self.dasher_to_screen(DasherModel::ORIGIN_X, DasherModel::ORIGIN_Y);
 
         // Draw horizontal line
-        self.screen.draw_line(cx - 10, cy, cx + 10, cy, color_palette::RED, 2);
+        self.counted_screen().draw_line(cx - 10, cy, cx + 10, cy, color_palette::RED, 2);
 
         // Draw vertical line
-        self.screen.draw_line(cx, cy - 10, cx, cy + 10, color_palette::RED, 2);
+        self.counted_screen().draw_line(cx, cy - 10, cx, cy + 10, color_palette::RED, 2);
 
         // Draw circle at intersection
-        self.screen.draw_circle(cx, cy, 5, color_palette::RED, color_palette::BLACK, 1);
+        self.counted_screen().draw_circle(cx, cy, 5, color_palette::RED, color_palette::BLACK, 1);
+    }
+
+    /// Draw a node occupying `y1`-`y2`, then its children
+    fn render_subtree(&mut self, node: &Rc<RefCell<DasherNode>>, y1: i64, y2: i64) {
+        // Skip nodes off the screen or too small to see, along with their descendants
+        let Some(depth) = self.drawn_depth(y1, y2) else {
+            return;
+        };
+
+        let node_ref = node.borrow();
+        let (shape, fill, foreground) = self.node_appearance(&node_ref);
+        let outline = if self.config.draw_outlines { color_palette::BLACK } else { color_palette::TRANSPARENT };
+        self.draw_node_shape(shape, depth, y1, y2, fill, outline);
+
+        // Label the node at its far edge, fitting the label inside it
+        if let Some(label) = node_ref.label() {
+            if let Some(box_size) = self.label_box(depth, y1, y2) {
+                if let Some(text) = self.dasher_draw_text(depth, (y1 + y2) / 2, label, foreground, box_size) {
+                    self.add_delayed_text(text);
+                }
+            }
+        }
+
+        for child in node_ref.children() {
+            let (child_y1, child_y2) = Self::child_bounds(y1, y2, &child.borrow());
+            self.render_subtree(child, child_y1, child_y2);
+        }
+    }
+
+    /// Get how deep a node at `y1`-`y2` is drawn, or `None` if it is off the
+    /// screen or too small to see, and so are its descendants
+    ///
+    /// Nodes are square: as deep as they are high, but no deeper than the screen.
+    fn drawn_depth(&self, y1: i64, y2: i64) -> Option<i64> {
+        let (_, min_y, max_x, max_y) = self.get_visible_region();
+        if y2 < min_y || y1 > max_y {
+            return None;
+        }
+        let (sx1, sy1) = self.dasher_to_screen(0, y1);
+        let (sx2, sy2) = self.dasher_to_screen(0, y2);
+        if (sx2 - sx1).abs().max((sy2 - sy1).abs()) < self.config.min_node_pixels {
+            return None;
+        }
+        Some((y2 - y1).min(max_x))
+    }
+
+    /// Get the size in pixels of the box of a node drawn `depth` deep at
+    /// `y1`-`y2`, or `None` if it is too small to be labelled
+    fn label_box(&self, depth: i64, y1: i64, y2: i64) -> Option<(i32, i32)> {
+        let (near_x, near_y) = self.dasher_to_screen(0, y1);
+        let (far_x, far_y) = self.dasher_to_screen(depth, y2);
+        let box_size = ((far_x - near_x).abs(), (far_y - near_y).abs());
+        (box_size.0.min(box_size.1) >= self.config.min_label_pixels).then_some(box_size)
+    }
+
+    /// Get the shape, color and label color of a node, styled by its group
+    fn node_appearance(&self, node: &DasherNode) -> (NodeShape, Color, Color) {
+        let style = NodeGroup::of(node)
+            .and_then(|group| self.config.group_styles.get(&group).copied())
+            .unwrap_or_default();
+        let (r, g, b) = node.background_color();
+        let background = style.background.unwrap_or(Color::from_tuple((r, g, b, 255)));
+        let (r, g, b) = node.foreground_color();
+        let foreground = style.foreground.unwrap_or(Color::from_tuple((r, g, b, 255)));
+        (style.shape.unwrap_or(self.config.node_shape), background, foreground)
+    }
+
+    /// Get the coordinates of `child` inside its parent at `y1`-`y2`
+    fn child_bounds(y1: i64, y2: i64, child: &DasherNode) -> (i64, i64) {
+        let range = y2 - y1;
+        (
+            y1 + (range * child.lower_bound() as i64) / DasherNode::NORMALIZATION as i64,
+            y1 + (range * child.upper_bound() as i64) / DasherNode::NORMALIZATION as i64,
+        )
+    }
+
+    /// Call `visit` with each node the view would draw for `model`, parents before their children
+    ///
+    /// This walks the tree as [`DasherView::render`] does, without drawing, so
+    /// frontends can draw the nodes themselves (for example, instanced on a GPU).
+    pub fn visit_visible_nodes(&self, model: &DasherModel, visit: &mut dyn FnMut(&VisibleNode)) {
+        if let Some(root) = model.get_root_node() {
+            let (y1, y2) = model.display_bounds();
+            self.visit_subtree(&root, y1, y2, 0, visit);
+        }
+    }
+
+    /// Get the nodes the view would draw for `model`, parents before their children
+    pub fn visible_nodes(&self, model: &DasherModel) -> Vec<VisibleNode> {
+        let mut nodes = Vec::new();
+        self.visit_visible_nodes(model, &mut |node| nodes.push(node.clone()));
+        nodes
+    }
+
+    /// Describe the frame the view would draw for `model`, for hosts that draw it natively
+    pub fn scene(&self, model: &DasherModel) -> Scene {
+        let (width, height) = self.get_dimensions();
+        let crosshair = self.config.draw_crosshair
+            .then(|| self.dasher_to_screen(DasherModel::ORIGIN_X, DasherModel::ORIGIN_Y));
+        let cursor = self.get_input_device()
+            .filter(|_| self.config.draw_cursor)
+            .and_then(|input| input.get_screen_coordinates(self));
+        Scene { width, height, nodes: self.visible_nodes(model), crosshair, cursor }
+    }
+
+    /// Visit a node and its descendants, as [`Self::render_subtree`] draws them
+    fn visit_subtree(&self, node: &Rc<RefCell<DasherNode>>, y1: i64, y2: i64, generation: usize, visit: &mut dyn FnMut(&VisibleNode)) {
+        let Some(depth) = self.drawn_depth(y1, y2) else {
+            return;
+        };
+
+        let node_ref = node.borrow();
+        let (shape, background, foreground) = self.node_appearance(&node_ref);
+        let (sx1, sy1) = self.dasher_to_screen(0, y1);
+        let (sx2, sy2) = self.dasher_to_screen(depth, y2);
+        visit(&VisibleNode {
+            rect: (sx1.min(sx2), sy1.min(sy2), sx1.max(sx2), sy1.max(sy2)),
+            label: node_ref.label().filter(|_| self.label_box(depth, y1, y2).is_some()).cloned(),
+            foreground,
+            background,
+            shape,
+            depth: generation,
+        });
+
+        for child in node_ref.children() {
+            let (child_y1, child_y2) = Self::child_bounds(y1, y2, &child.borrow());
+            self.visit_subtree(child, child_y1, child_y2, generation + 1, visit);
+        }
     }
 
     /// Draw the cursor at the specified position
@@ -707,13 +1102,13 @@ impl DasherViewSquare {
         let cursor_width = 2;
 
         // Draw horizontal line
-        self.screen.draw_line(x - cursor_size, y, x + cursor_size, y, cursor_color, cursor_width);
+        self.counted_screen().draw_line(x - cursor_size, y, x + cursor_size, y, cursor_color, cursor_width);
 
         // Draw vertical line
-        self.screen.draw_line(x, y - cursor_size, x, y + cursor_size, cursor_color, cursor_width);
+        self.counted_screen().draw_line(x, y - cursor_size, x, y + cursor_size, cursor_color, cursor_width);
 
         // Draw small circle at intersection
-        self.screen.draw_circle(x, y, 3, cursor_color, color_palette::BLACK, 1);
+        self.counted_screen().draw_circle(x, y, 3, cursor_color, color_palette::BLACK, 1);
     }
 }
 
@@ -732,110 +1127,62 @@ impl DasherView for DasherViewSquare {
 
     fn get_visible_region(&self) -> (i64, i64, i64, i64) {
         // Return the visible region in Dasher coordinates
-        // (min_x, min_y, max_x, max_y)
-
-        // If we have a cached visible region, return it
-        if let Some(region) = self.visible_region {
-            return region;
-        }
-
-        // Calculate the visible region based on the screen dimensions
+        // (min_x, min_y, max_x, max_y), whichever corners of the screen they come from
         let (width, height) = self.get_dimensions();
+        let corners = [(0, 0), (width, 0), (0, height), (width, height)]
+            .map(|(x, y)| self.screen_to_dasher(x, y));
 
-        // Convert screen corners to Dasher coordinates
-        let (_min_x, min_y) = self.screen_to_dasher(0, 0);
-        let (max_x, max_y) = match self.orientation {
-            Orientation::LeftToRight => self.screen_to_dasher(width, height),
-            Orientation::RightToLeft => self.screen_to_dasher(0, height),
-            Orientation::TopToBottom => self.screen_to_dasher(width, height),
-            Orientation::BottomToTop => self.screen_to_dasher(width, 0),
-        };
-
-        // Ensure min_x is always 0 (origin)
-        let min_x = 0;
-
-        // Ensure coordinates are in the correct order
-        let min_y = min_y.min(max_y);
-        let max_y = min_y.max(max_y);
-        let max_x = max_x.max(min_x);
-
-        // We can't cache the result here because self is immutable
-        // The caching is done in the _set_scale_factor method
+        let min_x = corners.iter().map(|&(x, _)| x).min().unwrap_or(0);
+        let max_x = corners.iter().map(|&(x, _)| x).max().unwrap_or(0);
+        let min_y = corners.iter().map(|&(_, y)| y).min().unwrap_or(0);
+        let max_y = corners.iter().map(|&(_, y)| y).max().unwrap_or(0);
 
         (min_x, min_y, max_x, max_y)
     }
 
     fn screen_to_dasher(&self, x: i32, y: i32) -> (i64, i64) {
-        let (width, height) = self.get_dimensions();
-
-        // Convert screen coordinates to normalized Dasher coordinates
-        let (mapped_x, mapped_y) = match self.orientation {
-            Orientation::LeftToRight => {
-                let mapped_x = x as i64 * self.scale_factor_x;
-                let mapped_y = y as i64 * self.scale_factor_y;
-                (mapped_x, mapped_y)
-            }
-            Orientation::RightToLeft => {
-                let mapped_x = (width - x) as i64 * self.scale_factor_x;
-                let mapped_y = y as i64 * self.scale_factor_y;
-                (mapped_x, mapped_y)
-            }
-            Orientation::TopToBottom => {
-                let mapped_x = y as i64 * self.scale_factor_x;
-                let mapped_y = x as i64 * self.scale_factor_y;
-                (mapped_x, mapped_y)
-            }
-            Orientation::BottomToTop => {
-                let mapped_x = (height - y) as i64 * self.scale_factor_x;
-                let mapped_y = x as i64 * self.scale_factor_y;
-                (mapped_x, mapped_y)
-            }
+        let (along_length, across_length) = self.axis_lengths();
+        let (x, y) = (x as i64, y as i64);
+
+        // Distance from the edge the nodes are anchored to, and position across the flow
+        let (along, across) = match self.orientation {
+            Orientation::LeftToRight => (along_length - x, y),
+            Orientation::RightToLeft => (x, y),
+            Orientation::TopToBottom => (along_length - y, x),
+            Orientation::BottomToTop => (y, x),
         };
 
-        // Apply inverse coordinate mapping
-        let dasher_x = self.ix_map(mapped_x);
-        let dasher_y = self.iy_map(mapped_y);
+        let mapped_x = along * (DasherModel::MAX_X + self.margin_width) / along_length - self.margin_width;
+        let mapped_y = DasherModel::ORIGIN_Y + (across - across_length / 2) * DasherModel::MAX_Y / across_length;
 
-        (dasher_x, dasher_y)
+        // Apply inverse coordinate mapping
+        (self.ix_map(mapped_x), self.iy_map(mapped_y))
     }
 
     fn dasher_to_screen(&self, x: i64, y: i64) -> (i32, i32) {
-        // Apply the nonlinearities
-        let mapped_x = self._x_map(x);
-        let mapped_y = self.y_map(y);
-
-        let (width, height) = self.get_dimensions();
+        let (along_length, across_length) = self.axis_lengths();
+
+        // Apply the nonlinearities, then scale so that the screen spans the
+        // margin plus MAX_X along the flow and MAX_Y across it
+        let along = (self._x_map(x) + self.margin_width) * along_length / (DasherModel::MAX_X + self.margin_width);
+        let across = across_length / 2 + (self.y_map(y) - DasherModel::ORIGIN_Y) * across_length / DasherModel::MAX_Y;
+
+        let (screen_x, screen_y) = match self.orientation {
+            Orientation::LeftToRight => (along_length - along, across),
+            Orientation::RightToLeft => (along, across),
+            Orientation::TopToBottom => (across, along_length - along),
+            Orientation::BottomToTop => (across, along),
+        };
 
-        // Convert to screen coordinates based on orientation
-        match self.orientation {
-            Orientation::LeftToRight => {
-                let screen_x = (mapped_x / self.scale_factor_x) as i32;
-                let screen_y = (mapped_y / self.scale_factor_y) as i32;
-                (screen_x, screen_y)
-            }
-            Orientation::RightToLeft => {
-                let screen_x = width - (mapped_x / self.scale_factor_x) as i32;
-                let screen_y = (mapped_y / self.scale_factor_y) as i32;
-                (screen_x, screen_y)
-            }
-            Orientation::TopToBottom => {
-                let screen_x = (mapped_y / self.scale_factor_y) as i32;
-                let screen_y = (mapped_x / self.scale_factor_x) as i32;
-                (screen_x, screen_y)
-            }
-            Orientation::BottomToTop => {
-                let screen_x = (mapped_y / self.scale_factor_y) as i32;
-                let screen_y = height - (mapped_x / self.scale_factor_x) as i32;
-                (screen_x, screen_y)
-            }
-        }
+        let clamp = |value: i64| value.clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+        (clamp(screen_x), clamp(screen_y))
     }
 
     fn draw_line(&mut self, x1: i64, y1: i64, x2: i64, y2: i64, color: (u8, u8, u8, u8), line_width: i32) {
         let (sx1, sy1) = self.dasher_to_screen(x1, y1);
         let (sx2, sy2) = self.dasher_to_screen(x2, y2);
 
-        self.screen.draw_line(sx1, sy1, sx2, sy2, Color::from_tuple(color), line_width);
+        self.counted_screen().draw_line(sx1, sy1, sx2, sy2, Color::from_tuple(color), line_width);
     }
 
     fn draw_rectangle(&mut self, x1: i64, y1: i64, x2: i64, y2: i64,
@@ -843,7 +1190,7 @@ impl DasherView for DasherViewSquare {
         let (sx1, sy1) = self.dasher_to_screen(x1, y1);
         let (sx2, sy2) = self.dasher_to_screen(x2, y2);
 
-        self.screen.draw_rectangle(sx1, sy1, sx2, sy2,
+        self.counted_screen().draw_rectangle(sx1.min(sx2), sy1.min(sy2), sx1.max(sx2), sy1.max(sy2),
                                   Color::from_tuple(fill_color),
                                   Color::from_tuple(outline_color),
                                   line_width);
@@ -853,34 +1200,47 @@ impl DasherView for DasherViewSquare {
                   fill_color: (u8, u8, u8, u8), line_color: (u8, u8, u8, u8), line_width: i32) {
         let (sx, sy) = self.dasher_to_screen(cx, cy);
 
-        // Convert radius from Dasher to screen coordinates
-        let (width, height) = self.get_dimensions();
-        let sr = match self.orientation {
-            Orientation::LeftToRight | Orientation::RightToLeft => {
-                (r as f64 / DasherModel::MAX_Y as f64 * width as f64) as i32
-            }
-            Orientation::TopToBottom | Orientation::BottomToTop => {
-                (r as f64 / DasherModel::MAX_Y as f64 * height as f64) as i32
-            }
-        };
+        // Convert radius from Dasher to screen coordinates, using the scale across the flow
+        let (_, across_length) = self.axis_lengths();
+        let sr = (r as f64 / DasherModel::MAX_Y as f64 * across_length as f64) as i32;
 
-        self.screen.draw_circle(sx, sy, sr,
+        self.counted_screen().draw_circle(sx, sy, sr,
                                Color::from_tuple(fill_color),
                                Color::from_tuple(line_color),
                                line_width);
     }
 
     fn render(&mut self, model: &mut DasherModel) -> Result<()> {
-        // Get screen dimensions
+        self.render_decorated(model, &mut |_| {})
+    }
+
+    fn draw_overlay(&mut self, info: &DebugInfo) {
+        // The overlay draws its background, then each of its lines
+        self.draw_calls += 1 + info.lines().len() as u64;
+        info.draw(&mut *self.screen);
+    }
+
+    fn draw_calls(&self) -> u64 {
+        self.draw_calls
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(nodes = model.node_count())))]
+    fn render_decorated(&mut self, model: &mut DasherModel, decorate: &mut dyn FnMut(&mut dyn DasherView)) -> Result<()> {
+        // Get screen dimensions, measuring labels again if they have changed
         let (width, height) = self.get_dimensions();
+        let cache = self.label_cache.get_mut();
+        if cache.screen_size != (width, height) {
+            cache.clear();
+            cache.screen_size = (width, height);
+        }
 
         // Clear the screen
-        self.screen.draw_rectangle(0, 0, width, height, color_palette::WHITE, color_palette::BLACK, 1);
+        self.draw_calls = 0;
+        self.counted_screen().draw_rectangle(0, 0, width, height, color_palette::WHITE, color_palette::BLACK, 1);
 
-        // Draw the root node and its children
+        // Draw the root node and its children where the model has zoomed to
         if let Some(root) = model.get_root_node() {
-            // Always use the standard render_node method
-            // The flowing interface is handled internally
+            self.root_bounds = model.display_bounds();
             self.render_node(root);
         }
 
@@ -889,12 +1249,19 @@ impl DasherView for DasherViewSquare {
             self.crosshair();
         }
 
-        // Draw the cursor if enabled and an input device is available
-        if self.config.draw_cursor {
-            if let Some(input) = self.get_input_device() {
-                // Clone the input device to avoid borrowing issues
-                let input_clone = input.box_clone();
-                if let Some((x, y)) = input_clone.get_screen_coordinates(self) {
+        // Draw the line to the pointer and the cursor if enabled and an input device is available
+        if self.config.draw_mouse_line || self.config.draw_cursor {
+            // Clone the input device to avoid borrowing issues
+            let pointer = self.get_input_device()
+                .map(|input| input.box_clone())
+                .and_then(|input| input.get_screen_coordinates(self));
+            if let Some((x, y)) = pointer {
+                if self.config.draw_mouse_line {
+                    let (cx, cy) = self.dasher_to_screen(DasherModel::ORIGIN_X, DasherModel::ORIGIN_Y);
+                    let (color, width) = (self.config.mouse_line_color, self.config.mouse_line_width);
+                    self.counted_screen().draw_line(cx, cy, x, y, color, width);
+                }
+                if self.config.draw_cursor {
                     self.draw_cursor(x, y);
                 }
             }
@@ -906,6 +1273,9 @@ impl DasherView for DasherViewSquare {
             self.do_delayed_text(text);
         }
 
+        // Draw the input decorations over the frame
+        decorate(self);
+
         // Display the frame
         self.screen.display();
 
@@ -928,333 +1298,9 @@ impl DasherView for DasherViewSquare {
             global_context.add_debug(&format!("render_node: Starting to render node {}", drawing_context.node_id));
         }
 
-        let node_ref = node.borrow();
-
-        // Calculate node boundaries in Dasher coordinates
-        let lower = node_ref.lower_bound() as i64;
-        let upper = node_ref.upper_bound() as i64;
-        let _range = upper - lower; // Used later in the method
-
-        // Get the visible region
-        let (_min_x, min_y, _max_x, max_y) = self.get_visible_region();
-
-        // Skip nodes that are completely outside the visible region
-        if upper < min_y || lower > max_y {
-            if global_context.get_debug_mode() {
-                global_context.add_debug(&format!("render_node: Node {} is outside visible region, skipping", drawing_context.node_id));
-            }
-
-            // Clear the current drawing context
-            crate::ffi::context::clear_current_drawing_context();
-
-            return;
-        }
-
-        // Log node information
-        if global_context.get_debug_mode() {
-            global_context.add_debug(&format!(
-                "render_node: Node {} boundaries: lower={}, upper={}, visible_region=({}, {})",
-                drawing_context.node_id, lower, upper, min_y, max_y
-            ));
-        }
-
-        // Get screen dimensions
-        let (width, height) = self.get_dimensions();
-
-        // Calculate the base node depth (distance from origin)
-        let base_depth = DasherModel::MAX_Y / 4;
-
-        // Calculate the node depth based on the flowing interface settings
-        let node_depth = if self.config.flowing_interface {
-            // In flowing interface mode, nodes start from the right side of the screen
-            // and move left as they get closer to being selected
-
-            // Calculate a depth that places the node on the right side of the screen
-            // and adjusts based on the flowing speed
-            let flow_factor = self.config.flowing_speed;
-            let depth_factor = 1.0 - (node_ref.offset() as f64 * 0.1).min(0.9); // Adjust based on node depth in tree
-
-            // Calculate the flowing depth - higher values = further to the right
-            let flowing_depth = (width as f64 * depth_factor * flow_factor) as i64;
-
-            // Ensure the depth is within reasonable bounds
-            flowing_depth.max(base_depth).min(width as i64 * 2)
-        } else {
-            // In standard mode, use a fixed depth
-            base_depth
-        };
-
-        // Create colors with appropriate alpha for depth perception
-        let alpha = if self.config.flowing_interface {
-            // Make nodes more transparent when they're further away
-            let distance_factor = (node_depth as f64 / (width as f64 * 2.0)).min(1.0);
-            let alpha_value = (255.0 * (1.0 - distance_factor * 0.5)) as u8;
-            alpha_value
-        } else {
-            200 // Default alpha
-        };
-
-        let bg_color = Color::from_tuple((
-            node_ref.background_color().0,
-            node_ref.background_color().1,
-            node_ref.background_color().2,
-            alpha
-        ));
-
-        let fg_color = Color::from_tuple((
-            node_ref.foreground_color().0,
-            node_ref.foreground_color().1,
-            node_ref.foreground_color().2,
-            255
-        ));
-
-        // Draw the node with the current shape
-        self.draw_node_shape(
-            node_depth,
-            lower,
-            upper,
-            bg_color,
-            if self.config.draw_outlines { color_palette::BLACK } else { color_palette::TRANSPARENT },
-            1
-        );
-
-        // Draw the node label
-        if let Some(label) = node_ref.label() {
-            // Calculate text position based on flowing interface
-            let text_x = if self.config.flowing_interface {
-                // Position text closer to the left edge of the node
-                node_depth / 4
-            } else {
-                // Standard position
-                node_depth / 2
-            };
-
-            // Create a delayed text object
-            let text = self.dasher_draw_text(text_x, (lower + upper) / 2, label, fg_color);
-
-            // Add it to the delayed texts
-            self.add_delayed_text(text);
-        }
-
-        // Render children recursively
-        // We'll do this directly here instead of calling a separate method
-        let node_ref = node.borrow();
-        let range = upper - lower;
-
-        // Calculate the child depth based on the flowing interface settings
-        let child_depth = if self.config.flowing_interface {
-            // In flowing interface mode, children are positioned to the right of their parent
-            // The deeper in the tree, the further to the right
-            let flow_factor = self.config.flowing_speed;
-
-            // Make sure children are visible by using a larger offset
-            let depth_offset = (width as f64 * 0.5 * flow_factor) as i64; // Increased space between parent and child
-
-            // Position children to the right of their parent
-            node_depth + depth_offset
-        } else {
-            // In standard mode, children are twice as far from the origin as their parent
-            node_depth * 2
-        };
-
-        // Debug output to help diagnose rendering issues
-        println!("Rendering child nodes at depth: {}", child_depth);
-
-        // Render each child
-        for child in node_ref.children() {
-            let child_ref = child.borrow();
-
-            // Calculate child boundaries in Dasher coordinates
-            let child_lower = lower + (range * child_ref.lower_bound() as i64) / DasherNode::NORMALIZATION as i64;
-            let child_upper = lower + (range * child_ref.upper_bound() as i64) / DasherNode::NORMALIZATION as i64;
-
-            // Skip nodes that are completely outside the visible region
-            if child_upper < min_y || child_lower > max_y {
-                continue;
-            }
-
-            // If using PPM, adjust the child height based on probability
-            let mut adjusted_lower = child_lower;
-            let mut adjusted_upper = child_upper;
-
-            if self.config.use_ppm {
-                // Calculate the probability
-                let probability = child_ref.cumulative_probability().unwrap_or(0.01) as f64;
-
-                // Adjust the height based on probability
-                let height_factor = probability.sqrt() * 2.0;
-                let child_height = child_upper - child_lower;
-                let new_height = (child_height as f64 * height_factor) as i64;
-
-                // Center the adjusted height
-                let center = (child_lower + child_upper) / 2;
-                adjusted_lower = center - new_height / 2;
-                adjusted_upper = center + new_height / 2;
-            }
-
-            // Create colors with appropriate alpha for depth perception
-            // Always use full opacity for better visibility during debugging
-            let child_alpha = 255; // Full opacity
-
-            // Make child nodes more visible with brighter colors
-            let child_bg_color = Color::from_tuple((
-                child_ref.background_color().0,
-                child_ref.background_color().1,
-                child_ref.background_color().2,
-                child_alpha
-            ));
-
-            let child_fg_color = Color::from_tuple((
-                child_ref.foreground_color().0,
-                child_ref.foreground_color().1,
-                child_ref.foreground_color().2,
-                255
-            ));
-
-            // Debug output to help diagnose rendering issues
-            if let Some(label) = child_ref.label() {
-                println!("Rendering child node with label: {}", label);
-            }
-
-            // Draw the child node with the current shape
-            self.draw_node_shape(
-                child_depth,
-                adjusted_lower,
-                adjusted_upper,
-                child_bg_color,
-                if self.config.draw_outlines { color_palette::BLACK } else { color_palette::TRANSPARENT },
-                1
-            );
-
-            // Draw the child node label
-            if let Some(label) = child_ref.label() {
-                // Calculate text position based on flowing interface
-                let text_x = if self.config.flowing_interface {
-                    // Position text at a fixed position for better visibility during debugging
-                    child_depth + 20 // Fixed offset from the node
-                } else {
-                    // Standard position
-                    child_depth / 2
-                };
-
-                // Create a delayed text object
-                let text = self.dasher_draw_text(text_x, (child_lower + child_upper) / 2, label, child_fg_color);
-
-                // Add it to the delayed texts
-                self.add_delayed_text(text);
-
-                // Debug output
-                println!("Added text '{}' at position ({}, {})",
-                    label, text_x, (child_lower + child_upper) / 2);
-            }
-
-            // Recursively render grandchildren if any
-            if !child_ref.children().is_empty() {
-                // Create a new Rc to avoid borrowing issues
-                let child_node = Rc::clone(&child);
-
-                // Render the child's children
-                let child_node_ref = child_node.borrow();
-                for grandchild in child_node_ref.children() {
-                    let grandchild_ref = grandchild.borrow();
-
-                    // Calculate grandchild boundaries
-                    let grandchild_range = child_upper - child_lower;
-                    let grandchild_lower = child_lower + (grandchild_range * grandchild_ref.lower_bound() as i64) / DasherNode::NORMALIZATION as i64;
-                    let grandchild_upper = child_lower + (grandchild_range * grandchild_ref.upper_bound() as i64) / DasherNode::NORMALIZATION as i64;
-
-                    // Skip nodes that are completely outside the visible region
-                    if grandchild_upper < min_y || grandchild_lower > max_y {
-                        continue;
-                    }
-
-                    // Calculate the grandchild depth based on the flowing interface settings
-                    let grandchild_depth = if self.config.flowing_interface {
-                        // In flowing interface mode, grandchildren are positioned to the right of their parent
-                        // The deeper in the tree, the further to the right
-                        let flow_factor = self.config.flowing_speed;
-                        let depth_offset = (width as f64 * 0.2 * flow_factor) as i64; // Space between parent and child
-
-                        // Position grandchildren to the right of their parent
-                        child_depth + depth_offset
-                    } else {
-                        // In standard mode, grandchildren are twice as far from the origin as their parent
-                        child_depth * 2
-                    };
-
-                    // If using PPM, adjust the grandchild height based on probability
-                    let mut adjusted_lower = grandchild_lower;
-                    let mut adjusted_upper = grandchild_upper;
-
-                    if self.config.use_ppm {
-                        // Calculate the probability
-                        let probability = grandchild_ref.cumulative_probability().unwrap_or(0.01) as f64;
-
-                        // Adjust the height based on probability
-                        let height_factor = probability.sqrt() * 2.0;
-                        let grandchild_height = grandchild_upper - grandchild_lower;
-                        let new_height = (grandchild_height as f64 * height_factor) as i64;
-
-                        // Center the adjusted height
-                        let center = (grandchild_lower + grandchild_upper) / 2;
-                        adjusted_lower = center - new_height / 2;
-                        adjusted_upper = center + new_height / 2;
-                    }
-
-                    // Create colors with appropriate alpha for depth perception
-                    let grandchild_alpha = if self.config.flowing_interface {
-                        // Make nodes more transparent when they're further away
-                        let distance_factor = (grandchild_depth as f64 / (width as f64 * 2.0)).min(1.0);
-                        let alpha_value = (255.0 * (1.0 - distance_factor * 0.5)) as u8;
-                        alpha_value
-                    } else {
-                        200 // Default alpha
-                    };
-
-                    let grandchild_bg_color = Color::from_tuple((
-                        grandchild_ref.background_color().0,
-                        grandchild_ref.background_color().1,
-                        grandchild_ref.background_color().2,
-                        grandchild_alpha
-                    ));
-
-                    let grandchild_fg_color = Color::from_tuple((
-                        grandchild_ref.foreground_color().0,
-                        grandchild_ref.foreground_color().1,
-                        grandchild_ref.foreground_color().2,
-                        255
-                    ));
-
-                    // Draw the grandchild node with the current shape
-                    self.draw_node_shape(
-                        grandchild_depth,
-                        adjusted_lower,
-                        adjusted_upper,
-                        grandchild_bg_color,
-                        if self.config.draw_outlines { color_palette::BLACK } else { color_palette::TRANSPARENT },
-                        1
-                    );
-
-                    // Draw the grandchild node label
-                    if let Some(label) = grandchild_ref.label() {
-                        // Calculate text position based on flowing interface
-                        let text_x = if self.config.flowing_interface {
-                            // Position text closer to the left edge of the node
-                            grandchild_depth / 4
-                        } else {
-                            // Standard position
-                            grandchild_depth / 2
-                        };
-
-                        // Create a delayed text object
-                        let text = self.dasher_draw_text(text_x, (grandchild_lower + grandchild_upper) / 2, label, grandchild_fg_color);
-
-                        // Add it to the delayed texts
-                        self.add_delayed_text(text);
-                    }
-                }
-            }
-        }
+        // Draw the node where the root is, then its descendants
+        let (y1, y2) = self.root_bounds;
+        self.render_subtree(&node, y1, y2);
 
         // Log completion of rendering this node
         if global_context.get_debug_mode() {
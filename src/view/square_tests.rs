@@ -1,9 +1,10 @@
 #[cfg(test)]
 mod tests {
-    use std::cell::RefCell;
+    use std::cell::{Cell, RefCell};
     use std::rc::Rc;
     use crate::model::node::DasherNode;
-    use crate::view::{Color, DasherScreen, DasherView, Label, Orientation};
+    use crate::model::DasherModel;
+    use crate::view::{Color, DasherScreen, DasherView, FontDescriptor, Label, Orientation};
     use crate::view::square::{DasherViewSquare, NodeShape, SquareViewConfig};
 
     // Mock implementation of DasherScreen for testing
@@ -11,6 +12,7 @@ mod tests {
         width: i32,
         height: i32,
         draw_calls: RefCell<Vec<DrawCall>>,
+        labels_made: Cell<usize>,
     }
 
     // Enum to track different drawing operations
@@ -79,6 +81,7 @@ mod tests {
                 width,
                 height,
                 draw_calls: RefCell::new(Vec::new()),
+                labels_made: Cell::new(0),
             }
         }
 
@@ -101,15 +104,16 @@ mod tests {
         }
 
         fn make_label(&self, text: &str, wrap_size: u32) -> Box<dyn Label> {
+            self.labels_made.set(self.labels_made.get() + 1);
             Box::new(MockLabel {
                 text: text.to_string(),
                 wrap_size,
             })
         }
 
-        fn text_size(&self, _label: &dyn Label, font_size: u32) -> (i32, i32) {
+        fn text_size(&self, label: &dyn Label, font_size: u32) -> (i32, i32) {
             // Simple approximation: each character is 10x20 pixels
-            let width = 10 * font_size as i32 / 24;
+            let width = 10 * label.get_text().chars().count().max(1) as i32 * font_size as i32 / 24;
             let height = 20 * font_size as i32 / 24;
             (width, height)
         }
@@ -174,6 +178,10 @@ mod tests {
         fn is_point_visible(&self, _x: i32, _y: i32) -> bool {
             true
         }
+
+        fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+            Some(self)
+        }
     }
 
     #[test]
@@ -203,10 +211,17 @@ mod tests {
             text_3d_depth: 1,
             base_font_size: 16,
             font_size_scaling: 0.3,
+            font: FontDescriptor::new(Some("Atkinson Hyperlegible"), FontDescriptor::BOLD_WEIGHT),
             margin_width: 1000,
             draw_crosshair: true,
             draw_cursor: true,
+            draw_mouse_line: false,
+            mouse_line_color: crate::view::color_palette::BLUE,
+            mouse_line_width: 2,
             draw_outlines: true,
+            min_label_pixels: 8,
+            min_node_pixels: 4,
+            group_styles: Default::default(),
             flowing_interface: true,
             flowing_speed: 2.0,
             use_ppm: true,
@@ -219,6 +234,7 @@ mod tests {
         assert!(!view.config().y_nonlinear);
         assert!(!view.config().text_3d);
         assert_eq!(view.config().base_font_size, 16);
+        assert_eq!(view.config().font.weight, FontDescriptor::BOLD_WEIGHT);
     }
 
     #[test]
@@ -376,6 +392,393 @@ mod tests {
         view.config_mut().x_nonlinear = false;
         assert!(!view.config().x_nonlinear);
     }
+
+    const ORIENTATIONS: [Orientation; 4] = [
+        Orientation::LeftToRight,
+        Orientation::RightToLeft,
+        Orientation::TopToBottom,
+        Orientation::BottomToTop,
+    ];
+
+    #[test]
+    fn test_square_view_mapping_in_all_orientations() {
+        for orientation in ORIENTATIONS {
+            let mut view = DasherViewSquare::new(Box::new(MockScreen::new(800, 600)));
+            view.set_orientation(orientation);
+
+            // The crosshair is centred across the direction of flow
+            let (cx, cy) = view.dasher_to_screen(DasherModel::ORIGIN_X, DasherModel::ORIGIN_Y);
+            let (ax, ay) = view.dasher_to_screen(0, DasherModel::ORIGIN_Y);
+            let (_, low_y) = view.dasher_to_screen(DasherModel::ORIGIN_X, 0);
+            match orientation {
+                Orientation::LeftToRight => {
+                    assert_eq!(cy, 300);
+                    assert!(ax > cx && ax < 800);
+                    assert!(low_y < cy);
+                }
+                Orientation::RightToLeft => {
+                    assert_eq!(cy, 300);
+                    assert!(ax < cx && ax > 0);
+                }
+                Orientation::TopToBottom => {
+                    assert_eq!(cx, 400);
+                    assert!(ay > cy && ay < 600);
+                }
+                Orientation::BottomToTop => {
+                    assert_eq!(cx, 400);
+                    assert!(ay < cy && ay > 0);
+                }
+            }
+
+            // Screen to Dasher coordinates and back is (nearly) the identity
+            for &(x, y) in &[(0, 0), (400, 300), (799, 10), (20, 590), (650, 450)] {
+                let (dasher_x, dasher_y) = view.screen_to_dasher(x, y);
+                let (sx, sy) = view.dasher_to_screen(dasher_x, dasher_y);
+                assert!((sx - x).abs() <= 1 && (sy - y).abs() <= 1, "{:?}: ({}, {}) -> ({}, {})", orientation, x, y, sx, sy);
+            }
+
+            // The whole of the Dasher range is visible
+            let (min_x, min_y, max_x, max_y) = view.get_visible_region();
+            assert!(min_x < 0 && max_x > DasherModel::MAX_X, "{:?}", orientation);
+            assert!(min_y < 0 && max_y > DasherModel::MAX_Y, "{:?}", orientation);
+        }
+    }
+
+    #[test]
+    fn test_square_view_renders_nodes_in_all_orientations() {
+        use crate::view::square_tests::DasherViewSquareExt;
+
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+
+        for orientation in ORIENTATIONS {
+            let config = SquareViewConfig { text_3d: false, draw_crosshair: false, ..SquareViewConfig::default() };
+            let mut view = DasherViewSquare::with_config(Box::new(MockScreen::new(800, 600)), config);
+            view.set_orientation(orientation);
+            view.render(&mut model).unwrap();
+
+            let draw_calls = view.get_screen_for_testing().get_draw_calls();
+            // Skip the rectangle clearing the screen
+            let rectangles: Vec<(i32, i32, i32, i32)> = draw_calls.iter().filter_map(|call| match call {
+                DrawCall::Rectangle { x1, y1, x2, y2, .. } => Some((*x1, *y1, *x2, *y2)),
+                _ => None,
+            }).skip(1).collect();
+            assert!(rectangles.len() > 1);
+            assert!(rectangles.iter().all(|&(x1, y1, x2, y2)| x1 <= x2 && y1 <= y2));
+
+            // The root spans the screen symmetrically across the flow, anchored at the margin
+            let (x1, y1, x2, y2) = rectangles[0];
+            let (anchor_x, anchor_y) = view.dasher_to_screen(0, DasherModel::ORIGIN_Y);
+            match orientation {
+                Orientation::LeftToRight | Orientation::RightToLeft => {
+                    assert!(y1 < 100 && (y1 + y2 - 600).abs() <= 1, "{:?}", orientation);
+                    assert!(x1 == anchor_x || x2 == anchor_x, "{:?}", orientation);
+                }
+                Orientation::TopToBottom | Orientation::BottomToTop => {
+                    assert!(x1 < 100 && (x1 + x2 - 800).abs() <= 1, "{:?}", orientation);
+                    assert!(y1 == anchor_y || y2 == anchor_y, "{:?}", orientation);
+                }
+            }
+
+            // Every label is drawn inside a node
+            let mut labels = 0;
+            for call in &draw_calls {
+                if let DrawCall::String { x, y, .. } = call {
+                    labels += 1;
+                    assert!(rectangles.iter().any(|&(x1, y1, x2, y2)| {
+                        (x1..=x2).contains(x) && (y1..=y2).contains(y)
+                    }), "{:?}: label at ({}, {})", orientation, x, y);
+                }
+            }
+            assert!(labels > 0);
+        }
+    }
+
+    #[test]
+    fn test_square_view_counts_draw_calls_per_frame() {
+        use crate::view::square_tests::DasherViewSquareExt;
+
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        let mut view = DasherViewSquare::new(Box::new(MockScreen::new(800, 600)));
+        assert_eq!(view.draw_calls(), 0);
+
+        // Every call but the one displaying the frame is counted
+        for _ in 0..2 {
+            view.get_screen_for_testing().clear_draw_calls();
+            view.render(&mut model).unwrap();
+            let draw_calls = view.get_screen_for_testing().get_draw_calls();
+            let drawn = draw_calls.iter().filter(|call| !matches!(call, DrawCall::Display)).count();
+            assert!(drawn > 0);
+            assert_eq!(view.draw_calls(), drawn as u64);
+        }
+    }
+
+    #[test]
+    fn test_square_view_draws_input_decorations_before_display() {
+        use crate::input::{ButtonRegion, InputManager, VirtualKey};
+        use crate::view::square_tests::DasherViewSquareExt;
+
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        let config = SquareViewConfig {
+            x_nonlinear: false,
+            y_nonlinear: false,
+            draw_crosshair: false,
+            ..SquareViewConfig::default()
+        };
+        let mut view = DasherViewSquare::with_config(Box::new(MockScreen::new(800, 600)), config);
+
+        let mut input_manager = InputManager::new();
+        input_manager.set_button_regions(vec![ButtonRegion { x: 10, y: 20, width: 100, height: 50, key: VirtualKey::Backspace }]);
+        view.render_decorated(&mut model, &mut |view| {
+            assert!(input_manager.decorate_view(view));
+        }).unwrap();
+
+        let draw_calls = view.get_screen_for_testing().get_draw_calls();
+        assert_eq!(draw_calls.last(), Some(&DrawCall::Display));
+
+        // The start circle is on the crosshair and the button box where it was placed
+        let (cx, cy) = view.dasher_to_screen(DasherModel::ORIGIN_X, DasherModel::ORIGIN_Y);
+        assert!(draw_calls.iter().any(|call| matches!(call,
+            DrawCall::Circle { cx: x, cy: y, r, .. } if (x - cx).abs() <= 1 && (y - cy).abs() <= 1 && (r - 50).abs() <= 1)));
+        assert!(draw_calls.iter().any(|call| matches!(call,
+            DrawCall::Rectangle { x1, y1, x2, y2, .. } if (x1 - 10).abs() <= 1 && (y1 - 20).abs() <= 1
+                && (x2 - 110).abs() <= 1 && (y2 - 70).abs() <= 1)));
+    }
+
+    #[test]
+    fn test_fit_label_lines() {
+        use crate::view::square::fit_label_lines;
+        let measure = |text: &str| 10 * text.chars().count() as i32;
+        let label = |text: &str, wrap_size: u32| MockLabel { text: text.to_string(), wrap_size };
+
+        assert_eq!(fit_label_lines(&label("short", 100), 1, measure), vec!["short"]);
+        assert_eq!(fit_label_lines(&label("the quick brown fox", 100), 3, measure), vec!["the quick", "brown fox"]);
+        assert_eq!(fit_label_lines(&label("the quick brown fox", 100), 1, measure), vec!["the quick\u{2026}"]);
+        assert_eq!(fit_label_lines(&label("the quick brown fox", 90), 1, measure), vec!["the quic\u{2026}"]);
+        assert_eq!(fit_label_lines(&label("extraordinarily", 50), 3, measure), vec!["extra", "ordin", "arily"]);
+        assert_eq!(fit_label_lines(&label("extraordinarily", 50), 2, measure), vec!["extra", "ordi\u{2026}"]);
+        assert_eq!(fit_label_lines(&label("the quick brown fox", 0), 2, measure), vec!["the quick brown fox"]);
+        assert!(fit_label_lines(&label("anything", 5), 2, measure).is_empty());
+        assert!(fit_label_lines(&label("anything", 100), 0, measure).is_empty());
+    }
+
+    #[test]
+    fn test_square_view_wraps_long_labels() {
+        use crate::view::square_tests::DasherViewSquareExt;
+
+        let label = "a label much too long to fit on a single line across the node";
+        let mut model = DasherModel::new();
+        model.set_node(Rc::new(RefCell::new(DasherNode::new(0, Some(label.to_string())))));
+        let config = SquareViewConfig { text_3d: false, ..SquareViewConfig::default() };
+        let mut view = DasherViewSquare::with_config(Box::new(MockScreen::new(300, 600)), config);
+        view.render(&mut model).unwrap();
+
+        // The label is drawn over several lines, one above the other, each inside the screen
+        let lines: Vec<(String, i32, i32)> = view.get_screen_for_testing().get_draw_calls().into_iter()
+            .filter_map(|call| match call {
+                DrawCall::String { text, x, y, .. } => Some((text, x, y)),
+                _ => None,
+            })
+            .collect();
+        assert!(lines.len() > 1, "{:?}", lines);
+        assert_eq!(lines.iter().map(|(text, _, _)| text.as_str()).collect::<Vec<_>>().join(" "), label);
+        assert!(lines.windows(2).all(|pair| pair[0].2 < pair[1].2));
+        assert!(lines.iter().all(|(text, x, _)| *x >= 0 && x + 5 * text.chars().count() as i32 <= 300), "{:?}", lines);
+    }
+
+    #[test]
+    fn test_square_view_reuses_labels_until_the_screen_changes() {
+        use crate::view::square_tests::DasherViewSquareExt;
+
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        let mut view = DasherViewSquare::new(Box::new(MockScreen::new(800, 600)));
+        view.render(&mut model).unwrap();
+        let made = view.get_screen_for_testing().labels_made.get();
+        assert!(made > 0);
+
+        // The same frame again makes no new labels
+        view.render(&mut model).unwrap();
+        assert_eq!(view.get_screen_for_testing().labels_made.get(), made);
+
+        // A resized screen measures its labels again
+        let screen = view.screen_mut().as_any_mut().and_then(|screen| screen.downcast_mut::<MockScreen>()).unwrap();
+        screen.width = 640;
+        view.render(&mut model).unwrap();
+        let remade = view.get_screen_for_testing().labels_made.get();
+        assert!(remade > made);
+
+        // So does a new font
+        view.set_font(FontDescriptor::new(Some("Lexend"), FontDescriptor::NORMAL_WEIGHT));
+        view.render(&mut model).unwrap();
+        assert!(view.get_screen_for_testing().labels_made.get() > remade);
+    }
+
+    #[test]
+    fn test_square_view_skips_nodes_below_min_size() {
+        use crate::view::square_tests::DasherViewSquareExt;
+
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        let mut view = DasherViewSquare::new(Box::new(MockScreen::new(800, 600)));
+        let count_rectangles = |view: &DasherViewSquare| view.get_screen_for_testing().get_draw_calls().iter()
+            .filter(|call| matches!(call, DrawCall::Rectangle { .. }))
+            .count();
+
+        view.render(&mut model).unwrap();
+        let all = count_rectangles(&view);
+
+        // Small nodes are skipped along with everything inside them
+        view.set_min_node_pixels(60);
+        view.get_screen_for_testing().clear_draw_calls();
+        view.render(&mut model).unwrap();
+        let large = count_rectangles(&view);
+        assert!(large > 1 && large < all);
+    }
+
+    #[test]
+    fn test_square_view_lists_the_nodes_it_draws() {
+        use crate::view::square_tests::DasherViewSquareExt;
+
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        let mut view = DasherViewSquare::new(Box::new(MockScreen::new(800, 600)));
+        let nodes = view.visible_nodes(&model);
+
+        // The root comes first, each node before its children
+        assert_eq!(nodes[0].depth, 0);
+        assert!(nodes.windows(2).all(|pair| pair[1].depth <= pair[0].depth + 1));
+        assert!(nodes[1..].iter().all(|node| node.depth > 0));
+        assert!(nodes.iter().any(|node| node.label.as_deref() == Some("e")));
+
+        // Each node is one rectangle drawn, after the background
+        view.render(&mut model).unwrap();
+        let rectangles: Vec<_> = view.get_screen_for_testing().get_draw_calls().into_iter()
+            .filter_map(|call| match call {
+                DrawCall::Rectangle { x1, y1, x2, y2, fill_color, .. } => Some(((x1, y1, x2, y2), fill_color)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(rectangles.len(), nodes.len() + 1);
+        for (node, &((x1, y1, x2, y2), fill)) in nodes.iter().zip(&rectangles[1..]) {
+            assert_eq!(node.rect, (x1.min(x2), y1.min(y2), x1.max(x2), y1.max(y2)));
+            assert_eq!(node.background, fill);
+        }
+    }
+
+    #[test]
+    fn test_square_view_describes_its_scene() {
+        use crate::input::{DasherInput, MouseInput};
+        use crate::view::Scene;
+
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        let mut view = DasherViewSquare::new(Box::new(MockScreen::new(800, 600)));
+        let scene = view.scene(&model);
+        assert_eq!((scene.width, scene.height), (800, 600));
+        assert_eq!(scene.nodes, view.visible_nodes(&model));
+        assert_eq!(scene.crosshair, Some(view.dasher_to_screen(DasherModel::ORIGIN_X, DasherModel::ORIGIN_Y)));
+        assert_eq!(scene.cursor, None);
+
+        let mut mouse = MouseInput::new();
+        mouse.activate();
+        mouse.set_screen_position(600, 150);
+        view.set_input_device(Box::new(mouse));
+        view.set_draw_crosshair(false);
+        let scene = view.scene(&model);
+        assert_eq!((scene.crosshair, scene.cursor), (None, Some((600, 150))));
+        assert_eq!(Scene::from_json(&scene.to_json().unwrap()).unwrap(), scene);
+    }
+
+    #[test]
+    fn test_square_view_draws_mouse_line() {
+        use crate::input::{DasherInput, MouseInput};
+        use crate::view::color_palette;
+        use crate::view::square_tests::DasherViewSquareExt;
+
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        let mut view = DasherViewSquare::new(Box::new(MockScreen::new(800, 600)));
+        let mut mouse = MouseInput::new();
+        mouse.activate();
+        mouse.set_screen_position(600, 150);
+        view.set_input_device(Box::new(mouse));
+
+        // Off by default
+        view.render(&mut model).unwrap();
+        assert!(!view.get_screen_for_testing().get_draw_calls().iter()
+            .any(|call| matches!(call, DrawCall::Line { x2: 600, y2: 150, .. })));
+
+        view.set_draw_mouse_line(true);
+        view.set_mouse_line_style(color_palette::GREEN, 3);
+        view.render(&mut model).unwrap();
+        let (cx, cy) = view.dasher_to_screen(DasherModel::ORIGIN_X, DasherModel::ORIGIN_Y);
+        assert!(view.get_screen_for_testing().get_draw_calls().contains(&DrawCall::Line {
+            x1: cx, y1: cy, x2: 600, y2: 150, color: color_palette::GREEN, line_width: 3,
+        }));
+    }
+
+    #[test]
+    fn test_square_view_styles_nodes_by_group() {
+        use crate::alphabet::{Color as SchemeColor, GroupAppearance};
+        use crate::view::square::NodeGroup;
+        use crate::view::square_tests::DasherViewSquareExt;
+
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        let config = SquareViewConfig { draw_crosshair: false, ..SquareViewConfig::default() };
+        let mut view = DasherViewSquare::with_config(Box::new(MockScreen::new(800, 600)), config);
+        view.apply_group_appearances(&[
+            GroupAppearance {
+                name: "punctuation".to_string(),
+                shape: Some("circle".to_string()),
+                foreground: None,
+                background: Some(SchemeColor::new(0, 200, 0)),
+            },
+            GroupAppearance { name: "emoji".to_string(), shape: None, foreground: None, background: None },
+        ]);
+        assert_eq!(view.config().group_styles.len(), 1);
+        assert_eq!(view.config().group_styles[&NodeGroup::Punctuation].shape, Some(NodeShape::Circle));
+        view.render(&mut model).unwrap();
+
+        // Only punctuation is drawn as circles, in its own color; letters keep the default shape
+        let draw_calls = view.get_screen_for_testing().get_draw_calls();
+        let circles: Vec<Color> = draw_calls.iter().filter_map(|call| match call {
+            DrawCall::Circle { fill_color, .. } => Some(*fill_color),
+            _ => None,
+        }).collect();
+        assert!(!circles.is_empty());
+        assert!(circles.iter().all(|&color| color == Color::from_tuple((0, 200, 0, 255))));
+        assert!(draw_calls.iter().filter(|call| matches!(call, DrawCall::Rectangle { .. })).count() > 2);
+
+        let mut node = DasherNode::new(0, Some("7".to_string()));
+        node.set_symbol('7');
+        assert_eq!(NodeGroup::of(&node), Some(NodeGroup::Digit));
+        node.set_symbol(' ');
+        assert_eq!(NodeGroup::of(&node), None);
+    }
+
+    #[test]
+    fn test_model_renders_node_tree_to_size_cutoff() {
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        let mut screen = MockScreen::new(800, 600);
+        model.render_to_view(&mut screen, 6).unwrap();
+
+        // The background, then the root and its descendants down to the cutoff
+        let rectangles: Vec<_> = screen.get_draw_calls().into_iter()
+            .filter_map(|call| match call {
+                DrawCall::Rectangle { x1, y1, x2, y2, .. } => Some((x1, y1, x2, y2)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(rectangles[0], (0, 0, 800, 600));
+        assert!(rectangles.len() > 2);
+        assert!(rectangles[1..].iter().all(|&(_, y1, x2, y2)| x2 == 800 && y2 - y1 >= 6));
+        assert_eq!(screen.get_draw_calls().last(), Some(&DrawCall::Display));
+    }
 }
 
 // Add this extension trait to access the screen for testing
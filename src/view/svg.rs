@@ -0,0 +1,120 @@
+//! SVG frame export
+//!
+//! Turns a frame recorded by a [`CommandListScreen`] into an SVG document, so
+//! a frame can be kept as vector graphics for documentation and bug reports,
+//! or compared between builds to catch visual regressions.
+
+use std::fmt::Write;
+
+use super::{Color, CommandListScreen, DasherView, DasherViewSquare, DrawCommand, Frame};
+use crate::model::DasherModel;
+use crate::{DasherError, Result};
+
+/// Render a frame of the model with the square view and export it as SVG
+pub fn render_to_svg(model: &mut DasherModel, width: i32, height: i32) -> Result<String> {
+    let mut view = DasherViewSquare::new(Box::new(CommandListScreen::new(width, height)));
+    view.render(model)?;
+
+    let frame = view.screen_mut().as_any_mut()
+        .and_then(|screen| screen.downcast_mut::<CommandListScreen>())
+        .and_then(CommandListScreen::take_frame)
+        .ok_or_else(|| DasherError::RenderingError("The view did not complete a frame".to_string()))?;
+    Ok(frame.to_svg())
+}
+
+/// Get the SVG paint and opacity attributes for a color
+fn paint(attribute: &str, color: Color) -> String {
+    if color.a == 0 {
+        return format!(r#"{}="none""#, attribute);
+    }
+    let mut paint = format!(r##"{}="#{:02x}{:02x}{:02x}""##, attribute, color.r, color.g, color.b);
+    if color.a < 255 {
+        let _ = write!(paint, r#" {}-opacity="{:.3}""#, attribute, color.a as f64 / 255.0);
+    }
+    paint
+}
+
+/// Escape text for use in an SVG document
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl Frame {
+    /// Export the frame as an SVG document
+    pub fn to_svg(&self) -> String {
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+            self.width, self.height
+        );
+        svg.push('\n');
+
+        // Writing to a String can't fail
+        for command in &self.commands {
+            let _ = match command {
+                DrawCommand::Rectangle { x1, y1, x2, y2, fill, outline, line_width } => writeln!(svg,
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" {} {} stroke-width="{}"/>"#,
+                    x1.min(x2), y1.min(y2), (x2 - x1).abs(), (y2 - y1).abs(),
+                    paint("fill", *fill), paint("stroke", *outline), line_width),
+                DrawCommand::Circle { cx, cy, r, fill, outline, line_width } => writeln!(svg,
+                    r#"<circle cx="{}" cy="{}" r="{}" {} {} stroke-width="{}"/>"#,
+                    cx, cy, r, paint("fill", *fill), paint("stroke", *outline), line_width),
+                DrawCommand::Line { x1, y1, x2, y2, color, line_width } => writeln!(svg,
+                    r#"<line x1="{}" y1="{}" x2="{}" y2="{}" {} stroke-width="{}"/>"#,
+                    x1, y1, x2, y2, paint("stroke", *color), line_width),
+                DrawCommand::Polygon { points, fill, outline, line_width } => {
+                    let points: Vec<String> = points.iter().map(|(x, y)| format!("{},{}", x, y)).collect();
+                    writeln!(svg, r#"<polygon points="{}" {} {} stroke-width="{}"/>"#,
+                        points.join(" "), paint("fill", *fill), paint("stroke", *outline), line_width)
+                }
+                DrawCommand::Text { text, x, y, font_size, color, font } => {
+                    let font = font.clone().unwrap_or_default();
+                    writeln!(svg,
+                        r#"<text x="{}" y="{}" font-family="{}" font-weight="{}" font-size="{}" {}>{}</text>"#,
+                        x, y, escape(&font.css_family()), font.weight, font_size, paint("fill", *color), escape(text))
+                }
+            };
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::view::color_palette;
+
+    #[test]
+    fn test_frame_to_svg() {
+        let frame = Frame {
+            width: 200,
+            height: 100,
+            commands: vec![
+                DrawCommand::Rectangle { x1: 50, y1: 40, x2: 10, y2: 20, fill: color_palette::WHITE, outline: color_palette::TRANSPARENT, line_width: 1 },
+                DrawCommand::Line { x1: 0, y1: 0, x2: 5, y2: 5, color: Color::new(255, 0, 0, 128), line_width: 2 },
+                DrawCommand::Text { text: "a<b".to_string(), x: 3, y: 4, font_size: 12, color: color_palette::BLACK, font: None },
+            ],
+        };
+        assert_eq!(frame.to_svg(), concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100" viewBox="0 0 200 100">"#, "\n",
+            r##"<rect x="10" y="20" width="40" height="20" fill="#ffffff" stroke="none" stroke-width="1"/>"##, "\n",
+            r##"<line x1="0" y1="0" x2="5" y2="5" stroke="#ff0000" stroke-opacity="0.502" stroke-width="2"/>"##, "\n",
+            r##"<text x="3" y="4" font-family="sans-serif" font-weight="400" font-size="12" fill="#000000">a&lt;b</text>"##, "\n",
+            "</svg>\n",
+        ));
+    }
+
+    #[test]
+    fn test_render_model_to_svg() {
+        let mut model = DasherModel::new();
+        model.initialize().unwrap();
+        let svg = render_to_svg(&mut model, 800, 600).unwrap();
+        assert!(svg.starts_with("<svg ") && svg.ends_with("</svg>\n"));
+        assert!(svg.matches("<rect ").count() > 1);
+        assert!(svg.contains("<text "));
+    }
+}
@@ -10,7 +10,7 @@ use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
 use std::collections::HashMap;
 
 use crate::api::DasherInterface;
-use crate::view::{Color, DasherScreen, Label};
+use crate::view::{Color, DasherScreen, FontDescriptor, Label};
 use crate::settings::Settings;
 
 /// WebAssembly bindings for the Dasher interface
@@ -67,14 +67,8 @@ impl DasherInterfaceWasm {
     pub fn set_node_shape(&mut self, shape: &str) -> Result<(), JsValue> {
         use crate::view::NodeShape;
 
-        let node_shape = match shape {
-            "rectangle" => NodeShape::Rectangle,
-            "triangle" => NodeShape::Triangle,
-            "truncated-triangle" => NodeShape::TruncatedTriangle,
-            "circle" => NodeShape::Circle,
-            "quadric" => NodeShape::Quadric,
-            _ => return Err(JsValue::from_str(&format!("Invalid node shape: {}", shape))),
-        };
+        let node_shape = NodeShape::from_name(shape)
+            .ok_or_else(|| JsValue::from_str(&format!("Invalid node shape: {}", shape)))?;
 
         self.interface.set_node_shape(node_shape)
             .map_err(|e| JsValue::from_str(&format!("Failed to set node shape: {}", e)))
@@ -172,6 +166,12 @@ impl DasherScreen for WebDasherScreen {
         Box::new(WebLabel::new(text, wrap_size))
     }
 
+    fn make_font_label(&self, text: &str, wrap_size: u32, font: &FontDescriptor) -> Box<dyn Label> {
+        let mut label = WebLabel::new(text, wrap_size);
+        label.font = Some(font.clone());
+        Box::new(label)
+    }
+
     fn text_size(&self, label: &dyn Label, _font_size: u32) -> (i32, i32) {
         // TODO: Implement proper text measurement
         let text = label.get_text();
@@ -182,7 +182,8 @@ impl DasherScreen for WebDasherScreen {
         // TODO: Implement proper text rendering
         let text = label.get_text();
         self.context.set_fill_style(&JsValue::from_str(&color.to_css_string()));
-        self.context.set_font(&format!("{}px sans-serif", font_size));
+        let font = label.font().cloned().unwrap_or_default();
+        self.context.set_font(&format!("{} {}px {}", font.weight, font_size, font.css_family()));
         let _ = self.context.fill_text(text, x as f64, y as f64);
     }
 
@@ -244,6 +245,7 @@ impl DasherScreen for WebDasherScreen {
 pub struct WebLabel {
     text: String,
     wrap_size: u32,
+    font: Option<FontDescriptor>,
 }
 
 impl WebLabel {
@@ -252,6 +254,7 @@ impl WebLabel {
         Self {
             text: text.to_string(),
             wrap_size,
+            font: None,
         }
     }
 }
@@ -264,4 +267,8 @@ impl Label for WebLabel {
     fn get_wrap_size(&self) -> u32 {
         self.wrap_size
     }
+
+    fn font(&self) -> Option<&FontDescriptor> {
+        self.font.as_ref()
+    }
 }
@@ -6,16 +6,63 @@ use wasm_bindgen::JsValue;
 use web_sys::console;
 
 use crate::view::NodeShape;
-use crate::settings::Settings;
+use crate::settings::{Parameter, Settings};
 use crate::api::DasherInterface;
+use crate::action::{ClipboardProvider, SpeechProvider};
+use crate::alphabet::{ConversionCandidate, ConversionProvider};
+use crate::logging::{self, dasher_log, LogLevel};
 
 // Create a global static to hold the Dasher interface
 static mut DASHER_INTERFACE: Option<DasherInterface> = None;
 
+/// Run a closure against the global interface, failing if it is not initialized
+fn with_interface<T>(f: impl FnOnce(&mut DasherInterface) -> T) -> Result<T, JsValue> {
+    // WASM is single-threaded and the interface is only touched from these bindings
+    let interface = unsafe { (*std::ptr::addr_of_mut!(DASHER_INTERFACE)).as_mut() };
+    interface.map(f).ok_or_else(|| JsValue::from_str("Dasher has not been initialized"))
+}
+
+/// Speech provider that forwards text to a JavaScript callback
+struct JsSpeechProvider {
+    callback: js_sys::Function,
+}
+
+impl SpeechProvider for JsSpeechProvider {
+    fn speak(&self, text: &str) {
+        if let Err(e) = self.callback.call1(&JsValue::NULL, &JsValue::from_str(text)) {
+            dasher_log!(Warn, "Speech callback failed: {:?}", e);
+        }
+    }
+}
+
+/// Show core diagnostics in the browser console
+///
+/// Messages at `level` or more severe are shown (0 = error, 1 = warning,
+/// 2 = info, 3 = debug); a negative level shows none.
+#[wasm_bindgen]
+pub fn set_console_log_level(level: i32) -> Result<(), JsValue> {
+    if level < 0 {
+        logging::clear_log_sink();
+        return Ok(());
+    }
+    let max_level = LogLevel::from_i32(level)
+        .ok_or_else(|| JsValue::from_str(&format!("Invalid log level: {}", level)))?;
+    logging::set_log_sink(max_level, |level, target, message| {
+        let text = JsValue::from_str(&format!("[{}] {}", target, message));
+        match level {
+            LogLevel::Error => console::error_1(&text),
+            LogLevel::Warn => console::warn_1(&text),
+            LogLevel::Info => console::info_1(&text),
+            LogLevel::Debug => console::debug_1(&text),
+        }
+    });
+    Ok(())
+}
+
 /// Initialize the Dasher interface with default settings
 #[wasm_bindgen]
 pub fn init_dasher() -> Result<(), JsValue> {
-    console::log_1(&JsValue::from_str("[WASM] Initializing Dasher"));
+    dasher_log!(Debug, "Initializing Dasher");
 
     // Create default settings
     let settings = Settings::new();
@@ -34,11 +81,11 @@ pub fn init_dasher() -> Result<(), JsValue> {
 /// Set the canvas for rendering
 #[wasm_bindgen]
 pub fn set_canvas(canvas_id: &str) -> Result<(), JsValue> {
-    console::log_1(&JsValue::from_str(&format!("[WASM] Setting canvas: {}", canvas_id)));
+    dasher_log!(Debug, "Setting canvas: {}", canvas_id);
 
     // For now, just log that we received the canvas ID
     // In a real implementation, we would create a WebScreen and set it in the interface
-    console::log_1(&JsValue::from_str(&format!("[WASM] Canvas ID: {}", canvas_id)));
+    dasher_log!(Debug, "Canvas ID: {}", canvas_id);
 
     Ok(())
 }
@@ -48,7 +95,7 @@ pub fn set_canvas(canvas_id: &str) -> Result<(), JsValue> {
 pub fn new_frame(timestamp: f64) -> Result<(), JsValue> {
     // For now, just log that we received a frame
     // In a real implementation, we would call the interface's new_frame method
-    console::log_1(&JsValue::from_str(&format!("[WASM] New frame: {}", timestamp)));
+    dasher_log!(Debug, "New frame: {}", timestamp);
 
     Ok(())
 }
@@ -56,7 +103,7 @@ pub fn new_frame(timestamp: f64) -> Result<(), JsValue> {
 /// Start the interface
 #[wasm_bindgen]
 pub fn start() -> Result<(), JsValue> {
-    console::log_1(&JsValue::from_str("[WASM] Starting Dasher"));
+    dasher_log!(Debug, "Starting Dasher");
 
     // For now, just log that we're starting
     // In a real implementation, we would call the interface's start method
@@ -67,7 +114,7 @@ pub fn start() -> Result<(), JsValue> {
 /// Stop the interface
 #[wasm_bindgen]
 pub fn stop() -> Result<(), JsValue> {
-    console::log_1(&JsValue::from_str("[WASM] Stopping Dasher"));
+    dasher_log!(Debug, "Stopping Dasher");
 
     // For now, just log that we're stopping
     // In a real implementation, we would call the interface's stop method
@@ -78,7 +125,7 @@ pub fn stop() -> Result<(), JsValue> {
 /// Pause the interface
 #[wasm_bindgen]
 pub fn pause() -> Result<(), JsValue> {
-    console::log_1(&JsValue::from_str("[WASM] Pausing Dasher"));
+    dasher_log!(Debug, "Pausing Dasher");
 
     // For now, just log that we're pausing
     // In a real implementation, we would call the interface's pause method
@@ -89,7 +136,7 @@ pub fn pause() -> Result<(), JsValue> {
 /// Resume the interface
 #[wasm_bindgen]
 pub fn resume() -> Result<(), JsValue> {
-    console::log_1(&JsValue::from_str("[WASM] Resuming Dasher"));
+    dasher_log!(Debug, "Resuming Dasher");
 
     // For now, just log that we're resuming
     // In a real implementation, we would call the interface's resume method
@@ -100,7 +147,7 @@ pub fn resume() -> Result<(), JsValue> {
 /// Reset the interface
 #[wasm_bindgen]
 pub fn reset() -> Result<(), JsValue> {
-    console::log_1(&JsValue::from_str("[WASM] Resetting Dasher"));
+    dasher_log!(Debug, "Resetting Dasher");
 
     // For now, just log that we're resetting
     // In a real implementation, we would call the interface's reset method
@@ -111,7 +158,7 @@ pub fn reset() -> Result<(), JsValue> {
 /// Handle backspace
 #[wasm_bindgen]
 pub fn backspace() -> Result<(), JsValue> {
-    console::log_1(&JsValue::from_str("[WASM] Backspace"));
+    dasher_log!(Debug, "Backspace");
 
     // For now, just log that we're handling backspace
     // In a real implementation, we would call the interface's backspace method
@@ -130,7 +177,7 @@ pub fn get_output_text() -> String {
 /// Set the node shape
 #[wasm_bindgen]
 pub fn set_node_shape(shape: &str) -> Result<(), JsValue> {
-    console::log_1(&JsValue::from_str(&format!("[WASM] Setting node shape: {}", shape)));
+    dasher_log!(Debug, "Setting node shape: {}", shape);
 
     // For now, just log that we're setting the node shape
     // In a real implementation, we would call the interface's set_node_shape method
@@ -141,7 +188,7 @@ pub fn set_node_shape(shape: &str) -> Result<(), JsValue> {
 /// Enable or disable X nonlinearity
 #[wasm_bindgen]
 pub fn set_x_nonlinear(enable: bool) -> Result<(), JsValue> {
-    console::log_1(&JsValue::from_str(&format!("[WASM] Setting X nonlinearity: {}", enable)));
+    dasher_log!(Debug, "Setting X nonlinearity: {}", enable);
 
     // For now, just log that we're setting X nonlinearity
     // In a real implementation, we would call the interface's set_x_nonlinear method
@@ -152,7 +199,7 @@ pub fn set_x_nonlinear(enable: bool) -> Result<(), JsValue> {
 /// Enable or disable Y nonlinearity
 #[wasm_bindgen]
 pub fn set_y_nonlinear(enable: bool) -> Result<(), JsValue> {
-    console::log_1(&JsValue::from_str(&format!("[WASM] Setting Y nonlinearity: {}", enable)));
+    dasher_log!(Debug, "Setting Y nonlinearity: {}", enable);
 
     // For now, just log that we're setting Y nonlinearity
     // In a real implementation, we would call the interface's set_y_nonlinear method
@@ -163,7 +210,7 @@ pub fn set_y_nonlinear(enable: bool) -> Result<(), JsValue> {
 /// Enable or disable 3D text
 #[wasm_bindgen]
 pub fn set_text_3d(enable: bool) -> Result<(), JsValue> {
-    console::log_1(&JsValue::from_str(&format!("[WASM] Setting 3D text: {}", enable)));
+    dasher_log!(Debug, "Setting 3D text: {}", enable);
 
     // For now, just log that we're setting 3D text
     // In a real implementation, we would call the interface's set_text_3d method
@@ -174,7 +221,7 @@ pub fn set_text_3d(enable: bool) -> Result<(), JsValue> {
 /// Enable or disable the flowing interface
 #[wasm_bindgen]
 pub fn set_flowing_interface(enable: bool) -> Result<(), JsValue> {
-    console::log_1(&JsValue::from_str(&format!("[WASM] Setting flowing interface: {}", enable)));
+    dasher_log!(Debug, "Setting flowing interface: {}", enable);
 
     // For now, just log that we're setting the flowing interface
     // In a real implementation, we would call the interface's set_flowing_interface method
@@ -185,7 +232,7 @@ pub fn set_flowing_interface(enable: bool) -> Result<(), JsValue> {
 /// Set the flowing interface speed
 #[wasm_bindgen]
 pub fn set_flowing_speed(speed: f64) -> Result<(), JsValue> {
-    console::log_1(&JsValue::from_str(&format!("[WASM] Setting flowing speed: {}", speed)));
+    dasher_log!(Debug, "Setting flowing speed: {}", speed);
 
     // For now, just log that we're setting the flowing speed
     // In a real implementation, we would call the interface's set_flowing_speed method
@@ -196,7 +243,7 @@ pub fn set_flowing_speed(speed: f64) -> Result<(), JsValue> {
 /// Enable or disable PPM (Prediction by Partial Match)
 #[wasm_bindgen]
 pub fn set_ppm(enable: bool) -> Result<(), JsValue> {
-    console::log_1(&JsValue::from_str(&format!("[WASM] Setting PPM: {}", enable)));
+    dasher_log!(Debug, "Setting PPM: {}", enable);
 
     // For now, just log that we're setting PPM
     // In a real implementation, we would call the interface's set_ppm method
@@ -207,7 +254,7 @@ pub fn set_ppm(enable: bool) -> Result<(), JsValue> {
 /// Enable or disable drawing the crosshair
 #[wasm_bindgen]
 pub fn set_draw_crosshair(enable: bool) -> Result<(), JsValue> {
-    console::log_1(&JsValue::from_str(&format!("[WASM] Setting draw crosshair: {}", enable)));
+    dasher_log!(Debug, "Setting draw crosshair: {}", enable);
 
     // For now, just log that we're setting draw crosshair
     // In a real implementation, we would call the interface's set_draw_crosshair method
@@ -218,7 +265,7 @@ pub fn set_draw_crosshair(enable: bool) -> Result<(), JsValue> {
 /// Enable or disable drawing the cursor
 #[wasm_bindgen]
 pub fn set_draw_cursor(enable: bool) -> Result<(), JsValue> {
-    console::log_1(&JsValue::from_str(&format!("[WASM] Setting draw cursor: {}", enable)));
+    dasher_log!(Debug, "Setting draw cursor: {}", enable);
 
     // For now, just log that we're setting draw cursor
     // In a real implementation, we would call the interface's set_draw_cursor method
@@ -229,10 +276,193 @@ pub fn set_draw_cursor(enable: bool) -> Result<(), JsValue> {
 /// Enable or disable drawing node outlines
 #[wasm_bindgen]
 pub fn set_draw_outlines(enable: bool) -> Result<(), JsValue> {
-    console::log_1(&JsValue::from_str(&format!("[WASM] Setting draw outlines: {}", enable)));
+    dasher_log!(Debug, "Setting draw outlines: {}", enable);
 
     // For now, just log that we're setting draw outlines
     // In a real implementation, we would call the interface's set_draw_outlines method
 
     Ok(())
 }
+
+/// Clipboard provider that forwards text to a JavaScript callback
+struct JsClipboardProvider {
+    callback: js_sys::Function,
+}
+
+impl ClipboardProvider for JsClipboardProvider {
+    fn set_text(&self, text: &str) {
+        if let Err(e) = self.callback.call1(&JsValue::NULL, &JsValue::from_str(text)) {
+            dasher_log!(Warn, "Clipboard callback failed: {:?}", e);
+        }
+    }
+}
+
+/// Set the JavaScript function called with text when the speak action is selected
+///
+/// Passing `null` or `undefined` disables the speak action.
+#[wasm_bindgen]
+pub fn set_speech_callback(callback: Option<js_sys::Function>) -> Result<(), JsValue> {
+    with_interface(|interface| match callback {
+        Some(callback) => interface.set_speech_provider(Box::new(JsSpeechProvider { callback })),
+        None => interface.clear_speech_provider(),
+    })
+}
+
+/// Speak the current sentence through the speech callback
+#[wasm_bindgen]
+pub fn speak() -> Result<(), JsValue> {
+    if with_interface(|interface| interface.speak())? {
+        Ok(())
+    } else {
+        Err(JsValue::from_str("No speech callback has been set"))
+    }
+}
+
+/// Handle a finger touching the canvas (the interface's input must be a touch screen)
+#[wasm_bindgen]
+pub fn touch_down(timestamp: f64, id: u32, x: i32, y: i32) -> Result<(), JsValue> {
+    with_interface(|interface| interface.touch_down(timestamp as u64, id as u64, x, y))?
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Handle a finger moving on the canvas
+#[wasm_bindgen]
+pub fn touch_move(timestamp: f64, id: u32, x: i32, y: i32) -> Result<(), JsValue> {
+    with_interface(|interface| interface.touch_move(timestamp as u64, id as u64, x, y))?
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Handle a finger leaving the canvas
+#[wasm_bindgen]
+pub fn touch_up(timestamp: f64, id: u32) -> Result<(), JsValue> {
+    with_interface(|interface| interface.touch_up(timestamp as u64, id as u64))?
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Conversion provider that asks a JavaScript callback for candidates
+struct JsConversionProvider {
+    callback: js_sys::Function,
+}
+
+impl ConversionProvider for JsConversionProvider {
+    fn candidates(&self, input: &str, context: &str) -> Vec<ConversionCandidate> {
+        let result = match self.callback.call2(&JsValue::NULL, &JsValue::from_str(input), &JsValue::from_str(context)) {
+            Ok(result) => result,
+            Err(e) => {
+                dasher_log!(Warn, "Conversion callback failed: {:?}", e);
+                return Vec::new();
+            }
+        };
+        let texts: Vec<String> = js_sys::Array::from(&result).iter().filter_map(|text| text.as_string()).collect();
+        let probability = 1.0 / texts.len().max(1) as f64;
+        texts.into_iter().map(|text| ConversionCandidate { text, probability }).collect()
+    }
+}
+
+/// Set the JavaScript function that converts what the user spelled, such as
+/// kana or pinyin, into candidates offered as nodes
+///
+/// The function is called with the composition spelled since the last word
+/// separator and the text before it, and returns an array of candidate
+/// strings, most likely first. Passing `null` or `undefined` stops offering
+/// conversions.
+#[wasm_bindgen]
+pub fn set_conversion_callback(callback: Option<js_sys::Function>) -> Result<(), JsValue> {
+    with_interface(|interface| match callback {
+        Some(callback) => interface.set_conversion_provider(Box::new(JsConversionProvider { callback })),
+        None => interface.clear_conversion_provider(),
+    })
+}
+
+/// Set the JavaScript function that receives text from the copy and cut actions
+///
+/// Passing `null` or `undefined` disables the copy and cut actions.
+#[wasm_bindgen]
+pub fn set_clipboard_callback(callback: Option<js_sys::Function>) -> Result<(), JsValue> {
+    with_interface(|interface| match callback {
+        Some(callback) => interface.set_clipboard_provider(Box::new(JsClipboardProvider { callback })),
+        None => interface.clear_clipboard_provider(),
+    })
+}
+
+/// Set the JavaScript function called with a description of each change to the text,
+/// such as "typed letter h", for a screen reader to speak
+///
+/// Passing `null` or `undefined` removes the callback.
+#[wasm_bindgen]
+pub fn set_announcement_callback(callback: Option<js_sys::Function>) -> Result<(), JsValue> {
+    with_interface(|interface| match callback {
+        Some(callback) => interface.set_announcement_callback(move |announcement| {
+            if let Err(e) = callback.call1(&JsValue::NULL, &JsValue::from_str(&announcement.to_string())) {
+                dasher_log!(Warn, "Announcement callback failed: {:?}", e);
+            }
+        }),
+        None => interface.clear_announcement_callback(),
+    })
+}
+
+/// Set the JavaScript function called with the cursor position (in characters)
+/// whenever Dasher moves it, such as with the "cursor-left" or "word-right" actions
+///
+/// Passing `null` or `undefined` removes the callback.
+#[wasm_bindgen]
+pub fn set_cursor_callback(callback: Option<js_sys::Function>) -> Result<(), JsValue> {
+    with_interface(|interface| match callback {
+        Some(callback) => interface.set_cursor_callback(move |position| {
+            if let Err(e) = callback.call1(&JsValue::NULL, &JsValue::from_f64(position as f64)) {
+                dasher_log!(Warn, "Cursor callback failed: {:?}", e);
+            }
+        }),
+        None => interface.clear_cursor_callback(),
+    })
+}
+
+/// Execute a registered action (e.g. "copy", "cut", "clear") by name
+#[wasm_bindgen]
+pub fn execute_action(name: &str) -> Result<(), JsValue> {
+    with_interface(|interface| interface.execute_action(name))?
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Zoom so that `y - x` to `y + x`, in Dasher coordinates, fills the canvas over `steps` frames
+///
+/// The zoom is taken while Dasher is running, in place of the input's steering,
+/// for tap-to-zoom or buttons that zoom to a prediction.
+#[wasm_bindgen]
+pub fn zoom_to(x: f64, y: f64, steps: i32) -> Result<(), JsValue> {
+    with_interface(|interface| interface.zoom_to(x as i64, y as i64, steps))?
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Describe the current frame as JSON (its nodes, crosshair and cursor), for
+/// pages that draw it with their own elements instead of the canvas screen
+#[wasm_bindgen]
+pub fn scene_json() -> Result<String, JsValue> {
+    with_interface(|interface| interface.scene_json())?
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Turn recording of committed sentences to the corpus on or off
+///
+/// With no file system the sentences are kept until taken with
+/// `take_corpus_text`.
+#[wasm_bindgen]
+pub fn set_record_corpus(enable: bool) -> Result<(), JsValue> {
+    with_interface(|interface| {
+        interface.settings_mut().set_bool(Parameter::RecordCorpus, enable);
+        interface.handle_parameter_change(Parameter::RecordCorpus);
+    })
+}
+
+/// Record a sentence committed by the page to the corpus
+#[wasm_bindgen]
+pub fn record_sentence(sentence: &str) -> Result<(), JsValue> {
+    with_interface(|interface| interface.record_sentence(sentence))?
+        .map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Take the sentences recorded to the corpus, as training text
+#[wasm_bindgen]
+pub fn take_corpus_text() -> Result<String, JsValue> {
+    with_interface(|interface| interface.take_corpus_text())
+}